@@ -0,0 +1,10 @@
+//! SSH config, keys, known_hosts, connections and the other services behind SSH Buddy,
+//! without any Tauri dependency
+//! The desktop app's commands are thin wrappers around these; a CLI, an IPC server or
+//! integration tests can link them directly
+
+pub mod models;
+pub mod services;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+pub mod utils;
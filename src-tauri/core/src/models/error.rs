@@ -0,0 +1,222 @@
+use super::LocalizedMessage;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug, Deserialize, Clone)]
+pub enum SshBuddyError {
+    // Key errors
+    #[error("Key not found: {path}")]
+    KeyNotFound { path: String },
+
+    #[error("Invalid key format: {message}")]
+    InvalidKeyFormat { message: String },
+
+    #[error("Key already exists: {name}")]
+    KeyAlreadyExists { name: String },
+
+    #[error("Key permissions too open: {path}")]
+    KeyPermissionsTooOpen { path: String },
+
+    // Security errors
+    #[error("Invalid path: {message}")]
+    InvalidPath { message: String },
+
+    #[error("Path traversal detected: {path}")]
+    PathTraversalDetected { path: String },
+
+    #[error("Invalid key name: {message}")]
+    InvalidKeyName { message: String },
+
+    // Connection errors
+    #[error("Host key changed: {hostname}")]
+    HostKeyChanged { hostname: String },
+
+    #[error("Host key unknown: {hostname}")]
+    HostKeyUnknown { hostname: String },
+
+    #[error("Connection refused: {message}")]
+    ConnectionRefused { message: String },
+
+    #[error("Connection timeout")]
+    ConnectionTimeout,
+
+    #[error("DNS resolution failed: {hostname}")]
+    DnsResolutionFailed { hostname: String },
+
+    #[error("Proxy error: {message}")]
+    ProxyError { message: String },
+
+    // Config errors
+    #[error("Host not found in SSH config: {alias}")]
+    HostNotFound { alias: String },
+
+    #[error("Host already exists in SSH config: {alias}")]
+    HostAlreadyExists { alias: String },
+
+    #[error("Invalid host alias: {alias}")]
+    InvalidHostAlias { alias: String },
+
+    #[error("Invalid config option: {message}")]
+    InvalidOption { message: String },
+
+    #[error("Invalid host template: {message}")]
+    InvalidTemplate { message: String },
+
+    // Export errors
+    #[error("Secrets detected in export ({count} found)")]
+    SecretsDetected { count: usize },
+
+    // Vault errors
+    #[error("Vault is locked")]
+    VaultLocked,
+
+    #[error("Vault not found")]
+    VaultNotFound,
+
+    #[error("Incorrect master password")]
+    InvalidMasterPassword,
+
+    // Authentication errors
+    #[error("Permission denied: {reason}")]
+    PermissionDenied { reason: String },
+
+    #[error("Passphrase required for key: {path}")]
+    PassphraseRequired { path: String },
+
+    #[error("Key not in agent: {path}")]
+    KeyNotInAgent { path: String },
+
+    // Mode errors
+    #[error("Read-only mode is on, cannot {operation}")]
+    ReadOnlyMode { operation: String },
+
+    // System errors
+    #[error("IO error: {message}")]
+    IoError { message: String },
+
+    #[error("Agent not running")]
+    AgentNotRunning,
+
+    #[error("Home directory not found")]
+    HomeDirNotFound,
+
+    #[error("Unknown error: {message}")]
+    Unknown { message: String },
+}
+
+impl From<std::io::Error> for SshBuddyError {
+    fn from(e: std::io::Error) -> Self {
+        SshBuddyError::IoError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<ssh_key::Error> for SshBuddyError {
+    fn from(e: ssh_key::Error) -> Self {
+        SshBuddyError::InvalidKeyFormat {
+            message: e.to_string(),
+        }
+    }
+}
+
+// Result type for Tauri command returns
+pub type SshResult<T> = Result<T, SshBuddyError>;
+
+// Implement Serialize for Tauri IPC
+impl serde::Serialize for SshBuddyError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Serialize as a struct containing type, message and the localizable form
+        use serde::ser::SerializeStruct;
+        let localized = self.localized();
+        let mut state = serializer.serialize_struct("SshBuddyError", 4)?;
+        state.serialize_field("type", &self.error_type())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("messageKey", &localized.key)?;
+        state.serialize_field("messageParams", &localized.params)?;
+        state.end()
+    }
+}
+
+impl SshBuddyError {
+    /// Catalog key ("error." and the camelCase type) with the variant's fields as parameters
+    pub fn localized(&self) -> LocalizedMessage {
+        let error_type = self.error_type();
+        let key = format!(
+            "error.{}{}",
+            error_type[..1].to_lowercase(),
+            &error_type[1..]
+        );
+        let message = LocalizedMessage::new(&key);
+        match self {
+            SshBuddyError::KeyNotFound { path }
+            | SshBuddyError::KeyPermissionsTooOpen { path }
+            | SshBuddyError::PathTraversalDetected { path }
+            | SshBuddyError::PassphraseRequired { path }
+            | SshBuddyError::KeyNotInAgent { path } => message.with("path", path),
+            SshBuddyError::InvalidKeyFormat { message: text }
+            | SshBuddyError::InvalidPath { message: text }
+            | SshBuddyError::InvalidKeyName { message: text }
+            | SshBuddyError::ConnectionRefused { message: text }
+            | SshBuddyError::ProxyError { message: text }
+            | SshBuddyError::InvalidOption { message: text }
+            | SshBuddyError::InvalidTemplate { message: text }
+            | SshBuddyError::IoError { message: text }
+            | SshBuddyError::Unknown { message: text } => message.with("message", text),
+            SshBuddyError::KeyAlreadyExists { name } => message.with("name", name),
+            SshBuddyError::HostKeyChanged { hostname }
+            | SshBuddyError::HostKeyUnknown { hostname }
+            | SshBuddyError::DnsResolutionFailed { hostname } => message.with("hostname", hostname),
+            SshBuddyError::HostNotFound { alias }
+            | SshBuddyError::HostAlreadyExists { alias }
+            | SshBuddyError::InvalidHostAlias { alias } => message.with("alias", alias),
+            SshBuddyError::SecretsDetected { count } => message.with("count", count),
+            SshBuddyError::PermissionDenied { reason } => message.with("reason", reason),
+            SshBuddyError::ReadOnlyMode { operation } => message.with("operation", operation),
+            SshBuddyError::ConnectionTimeout
+            | SshBuddyError::VaultLocked
+            | SshBuddyError::VaultNotFound
+            | SshBuddyError::InvalidMasterPassword
+            | SshBuddyError::AgentNotRunning
+            | SshBuddyError::HomeDirNotFound => message,
+        }
+    }
+
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            SshBuddyError::KeyNotFound { .. } => "KeyNotFound",
+            SshBuddyError::InvalidKeyFormat { .. } => "InvalidKeyFormat",
+            SshBuddyError::KeyAlreadyExists { .. } => "KeyAlreadyExists",
+            SshBuddyError::KeyPermissionsTooOpen { .. } => "KeyPermissionsTooOpen",
+            SshBuddyError::InvalidPath { .. } => "InvalidPath",
+            SshBuddyError::PathTraversalDetected { .. } => "PathTraversalDetected",
+            SshBuddyError::InvalidKeyName { .. } => "InvalidKeyName",
+            SshBuddyError::HostKeyChanged { .. } => "HostKeyChanged",
+            SshBuddyError::HostKeyUnknown { .. } => "HostKeyUnknown",
+            SshBuddyError::ConnectionRefused { .. } => "ConnectionRefused",
+            SshBuddyError::ConnectionTimeout => "ConnectionTimeout",
+            SshBuddyError::DnsResolutionFailed { .. } => "DnsResolutionFailed",
+            SshBuddyError::ProxyError { .. } => "ProxyError",
+            SshBuddyError::HostNotFound { .. } => "HostNotFound",
+            SshBuddyError::HostAlreadyExists { .. } => "HostAlreadyExists",
+            SshBuddyError::InvalidHostAlias { .. } => "InvalidHostAlias",
+            SshBuddyError::InvalidOption { .. } => "InvalidOption",
+            SshBuddyError::InvalidTemplate { .. } => "InvalidTemplate",
+            SshBuddyError::SecretsDetected { .. } => "SecretsDetected",
+            SshBuddyError::VaultLocked => "VaultLocked",
+            SshBuddyError::VaultNotFound => "VaultNotFound",
+            SshBuddyError::InvalidMasterPassword => "InvalidMasterPassword",
+            SshBuddyError::PermissionDenied { .. } => "PermissionDenied",
+            SshBuddyError::PassphraseRequired { .. } => "PassphraseRequired",
+            SshBuddyError::KeyNotInAgent { .. } => "KeyNotInAgent",
+            SshBuddyError::ReadOnlyMode { .. } => "ReadOnlyMode",
+            SshBuddyError::IoError { .. } => "IoError",
+            SshBuddyError::AgentNotRunning => "AgentNotRunning",
+            SshBuddyError::HomeDirNotFound => "HomeDirNotFound",
+            SshBuddyError::Unknown { .. } => "Unknown",
+        }
+    }
+}
@@ -48,6 +48,8 @@ pub struct SSHKeyInfo {
     pub fingerprint: Option<String>,
     pub comment: Option<String>,
     pub bit_size: Option<u32>,
+    /// OpenSSH-style randomart of the fingerprint
+    pub randomart: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,4 +60,5 @@ pub struct KeyDetails {
     pub comment: String,
     #[serde(rename = "type")]
     pub key_type: KeyType,
+    pub randomart: String,
 }
@@ -0,0 +1,46 @@
+use crate::utils::{render_message, DEFAULT_LOCALE};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// User-facing result message: a catalog key with its parameters, so the frontend
+/// can render it in the user's language, plus the English text
+///
+/// Flattened into result structs it serializes as `message`, `messageKey` and
+/// `messageParams`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LocalizedMessage {
+    /// English rendering, for logs and callers that don't localize
+    #[serde(rename = "message")]
+    pub text: String,
+    #[serde(rename = "messageKey")]
+    pub key: String,
+    #[serde(rename = "messageParams", default)]
+    pub params: BTreeMap<String, String>,
+}
+
+impl LocalizedMessage {
+    pub fn new(key: &str) -> Self {
+        Self {
+            text: render_message(DEFAULT_LOCALE, key, &BTreeMap::new()),
+            key: key.to_string(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Add a parameter (re-renders the English text)
+    pub fn with(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self.text = render_message(DEFAULT_LOCALE, &self.key, &self.params);
+        self
+    }
+
+    pub fn render(&self, locale: &str) -> String {
+        render_message(locale, &self.key, &self.params)
+    }
+}
+
+impl std::fmt::Display for LocalizedMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
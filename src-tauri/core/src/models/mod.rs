@@ -1,5 +1,7 @@
 pub mod error;
 pub mod key_info;
+pub mod message;
 
 pub use error::*;
 pub use key_info::*;
+pub use message::*;
@@ -1,4 +1,6 @@
-use crate::models::{SshBuddyError, SshResult};
+use crate::models::{LocalizedMessage, SshBuddyError, SshResult};
+use crate::services::onboarding_service::{OnboardingService, OnboardingStep};
+use crate::services::read_only::ReadOnlyMode;
 use base64::Engine;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use serde::{Deserialize, Serialize};
@@ -6,6 +8,7 @@ use ssh_key::{PrivateKey, PublicKey};
 use std::io::{Cursor, Read};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use tokio::fs;
 
@@ -23,6 +26,9 @@ use std::os::windows::fs::OpenOptionsExt;
 const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
 const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
 const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENT_SUCCESS: u8 = 6;
+const SSH_AGENTC_LOCK: u8 = 22;
+const SSH_AGENTC_UNLOCK: u8 = 23;
 
 /// Key information in Agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -396,7 +402,7 @@ impl AgentService {
         let content = match std::fs::read_to_string(key_path) {
             Ok(c) => c,
             Err(e) => {
-                log::warn!("[agent_service] Failed to read key file: {}", e);
+                tracing::warn!("[agent_service] Failed to read key file: {}", e);
                 return false;
             }
         };
@@ -404,7 +410,7 @@ impl AgentService {
         // Method 1: Legacy PEM format keys (RSA PRIVATE KEY etc.)
         // Encrypted legacy format contains "ENCRYPTED" or "Proc-Type: 4,ENCRYPTED"
         if content.contains("ENCRYPTED") || content.contains("Proc-Type: 4,ENCRYPTED") {
-            log::info!("[agent_service] Key is encrypted (PEM format with ENCRYPTED header)");
+            tracing::info!("[agent_service] Key is encrypted (PEM format with ENCRYPTED header)");
             return true;
         }
 
@@ -439,15 +445,18 @@ impl AgentService {
                         if decoded.len() >= 19 + cipher_len {
                             let cipher_name = &decoded[19..19 + cipher_len];
                             if let Ok(cipher_str) = std::str::from_utf8(cipher_name) {
-                                log::info!("[agent_service] OpenSSH key cipher: '{}'", cipher_str);
+                                tracing::info!(
+                                    "[agent_service] OpenSSH key cipher: '{}'",
+                                    cipher_str
+                                );
 
                                 if cipher_str == "none" {
-                                    log::info!(
+                                    tracing::info!(
                                         "[agent_service] Key is NOT encrypted (cipher=none)"
                                     );
                                     return false;
                                 } else {
-                                    log::info!(
+                                    tracing::info!(
                                         "[agent_service] Key IS encrypted (cipher={})",
                                         cipher_str
                                     );
@@ -460,32 +469,34 @@ impl AgentService {
             }
 
             // If unable to parse, try using ssh_key crate
-            log::info!("[agent_service] Could not parse cipher from binary, trying ssh_key crate");
+            tracing::info!(
+                "[agent_service] Could not parse cipher from binary, trying ssh_key crate"
+            );
         }
 
         // Method 3: Try parsing without password using ssh_key crate
         // If successful, not encrypted; if failed, possibly encrypted
         match PrivateKey::from_openssh(&content) {
             Ok(_) => {
-                log::info!("[agent_service] Key parsed without passphrase - not encrypted");
+                tracing::info!("[agent_service] Key parsed without passphrase - not encrypted");
                 false
             }
             Err(e) => {
                 let err_str = e.to_string().to_lowercase();
-                log::info!("[agent_service] Key parse error: {}", err_str);
+                tracing::info!("[agent_service] Key parse error: {}", err_str);
                 // Check if it explicitly mentions encryption
                 if err_str.contains("decrypt")
                     || err_str.contains("passphrase")
                     || err_str.contains("cipher")
                     || err_str.contains("encrypted")
                 {
-                    log::info!(
+                    tracing::info!(
                         "[agent_service] Key is encrypted (parse error indicates encryption)"
                     );
                     true
                 } else {
                     // Other errors - possibly format issue rather than encryption
-                    log::warn!("[agent_service] Key parse failed for unknown reason, assuming encrypted for safety");
+                    tracing::warn!("[agent_service] Key parse failed for unknown reason, assuming encrypted for safety");
                     true
                 }
             }
@@ -495,6 +506,15 @@ impl AgentService {
     /// Add key to Agent (using ssh-add command, as it handles passphrase)
     /// If passphrase is Some, it will be passed via stdin
     pub async fn add_key(key_path: &str, passphrase: Option<&str>) -> SshResult<AddKeyResult> {
+        let result = Self::load_key(key_path, passphrase).await?;
+        if result.success {
+            OnboardingService::complete(OnboardingStep::AddKeyToAgent).await;
+        }
+        Ok(result)
+    }
+
+    async fn load_key(key_path: &str, passphrase: Option<&str>) -> SshResult<AddKeyResult> {
+        ReadOnlyMode::ensure_writable("add key to agent")?;
         // Validate key path
         let path = PathBuf::from(key_path);
         if !path.exists() {
@@ -507,7 +527,7 @@ impl AgentService {
         if Self::is_key_in_agent(key_path).await.unwrap_or(false) {
             return Ok(AddKeyResult {
                 success: true,
-                message: "Key is already loaded in the agent".to_string(),
+                message: LocalizedMessage::new("agent.alreadyLoaded"),
                 needs_passphrase: false,
             });
         }
@@ -517,20 +537,20 @@ impl AgentService {
 
         // If key is encrypted but no passphrase provided, request input
         if is_encrypted && passphrase.is_none() {
-            log::info!(
+            tracing::info!(
                 "[agent_service] Key is encrypted, needs passphrase: {}",
                 key_path
             );
             return Ok(AddKeyResult {
                 success: false,
-                message: "This key requires a passphrase.".to_string(),
+                message: LocalizedMessage::new("agent.passphraseRequired"),
                 needs_passphrase: true,
             });
         }
 
         // If passphrase provided, use SSH_ASKPASS environment variable approach
         if let Some(pass) = passphrase {
-            log::info!(
+            tracing::info!(
                 "[agent_service] Adding encrypted key with passphrase: {}",
                 key_path
             );
@@ -539,7 +559,7 @@ impl AgentService {
 
         // Key has no passphrase, add using ssh-add command
         // Use tokio with timeout for async command to avoid any blocking
-        log::info!(
+        tracing::info!(
             "[agent_service] Running ssh-add for unencrypted key: {}",
             key_path
         );
@@ -561,15 +581,15 @@ impl AgentService {
         match result {
             Ok(Ok(Ok(output))) => {
                 if output.status.success() {
-                    log::info!("[agent_service] Key added to agent: {}", key_path);
+                    tracing::info!("[agent_service] Key added to agent: {}", key_path);
                     Ok(AddKeyResult {
                         success: true,
-                        message: "Key added to SSH agent successfully".to_string(),
+                        message: LocalizedMessage::new("agent.keyAdded"),
                         needs_passphrase: false,
                     })
                 } else {
                     let stderr = String::from_utf8_lossy(&output.stderr);
-                    log::warn!(
+                    tracing::warn!(
                         "[agent_service] Failed to add key: {}, stderr: {}",
                         key_path,
                         stderr
@@ -579,45 +599,44 @@ impl AgentService {
                     if stderr_lower.contains("passphrase") || stderr_lower.contains("password") {
                         Ok(AddKeyResult {
                             success: false,
-                            message: "This key requires a passphrase. Please run 'ssh-add' manually in terminal.".to_string(),
+                            message: LocalizedMessage::new("agent.passphraseManual"),
                             needs_passphrase: true,
                         })
                     } else {
                         Ok(AddKeyResult {
                             success: false,
-                            message: stderr.to_string(),
+                            message: LocalizedMessage::new("agent.sshAddOutput")
+                                .with("output", stderr),
                             needs_passphrase: false,
                         })
                     }
                 }
             }
             Ok(Ok(Err(e))) => {
-                log::error!("[agent_service] ssh-add command error: {}", e);
+                tracing::error!("[agent_service] ssh-add command error: {}", e);
                 Ok(AddKeyResult {
                     success: false,
-                    message: format!("Failed to run ssh-add: {}", e),
+                    message: LocalizedMessage::new("agent.sshAddFailed").with("error", e),
                     needs_passphrase: false,
                 })
             }
             Ok(Err(e)) => {
-                log::error!("[agent_service] spawn_blocking error: {}", e);
+                tracing::error!("[agent_service] spawn_blocking error: {}", e);
                 Ok(AddKeyResult {
                     success: false,
-                    message: format!("Internal error: {}", e),
+                    message: LocalizedMessage::new("agent.internalError").with("error", e),
                     needs_passphrase: false,
                 })
             }
             Err(_) => {
                 // Timeout - possibly waiting for passphrase input
-                log::warn!(
+                tracing::warn!(
                     "[agent_service] ssh-add timed out, key likely needs passphrase: {}",
                     key_path
                 );
                 Ok(AddKeyResult {
                     success: false,
-                    message:
-                        "This key requires a passphrase. Please run 'ssh-add' manually in terminal."
-                            .to_string(),
+                    message: LocalizedMessage::new("agent.passphraseManual"),
                     needs_passphrase: true,
                 })
             }
@@ -688,18 +707,18 @@ impl AgentService {
         match result {
             Ok(Ok(Ok(output))) => {
                 if output.status.success() {
-                    log::info!(
+                    tracing::info!(
                         "[agent_service] Key added to agent with passphrase: {}",
                         key_path
                     );
                     Ok(AddKeyResult {
                         success: true,
-                        message: "Key added to SSH agent successfully".to_string(),
+                        message: LocalizedMessage::new("agent.keyAdded"),
                         needs_passphrase: false,
                     })
                 } else {
                     let stderr = String::from_utf8_lossy(&output.stderr);
-                    log::warn!(
+                    tracing::warn!(
                         "[agent_service] Failed to add key with passphrase: {}",
                         stderr
                     );
@@ -712,39 +731,40 @@ impl AgentService {
                     {
                         Ok(AddKeyResult {
                             success: false,
-                            message: "Incorrect passphrase. Please try again.".to_string(),
+                            message: LocalizedMessage::new("agent.incorrectPassphrase"),
                             needs_passphrase: true,
                         })
                     } else {
                         Ok(AddKeyResult {
                             success: false,
-                            message: stderr.to_string(),
+                            message: LocalizedMessage::new("agent.sshAddOutput")
+                                .with("output", stderr),
                             needs_passphrase: false,
                         })
                     }
                 }
             }
             Ok(Ok(Err(e))) => {
-                log::error!("[agent_service] ssh-add command error: {}", e);
+                tracing::error!("[agent_service] ssh-add command error: {}", e);
                 Ok(AddKeyResult {
                     success: false,
-                    message: format!("Failed to run ssh-add: {}", e),
+                    message: LocalizedMessage::new("agent.sshAddFailed").with("error", e),
                     needs_passphrase: false,
                 })
             }
             Ok(Err(e)) => {
-                log::error!("[agent_service] spawn_blocking error: {}", e);
+                tracing::error!("[agent_service] spawn_blocking error: {}", e);
                 Ok(AddKeyResult {
                     success: false,
-                    message: format!("Internal error: {}", e),
+                    message: LocalizedMessage::new("agent.internalError").with("error", e),
                     needs_passphrase: false,
                 })
             }
             Err(_) => {
-                log::warn!("[agent_service] ssh-add with passphrase timed out");
+                tracing::warn!("[agent_service] ssh-add with passphrase timed out");
                 Ok(AddKeyResult {
                     success: false,
-                    message: "Operation timed out. Please try again.".to_string(),
+                    message: LocalizedMessage::new("agent.timedOut"),
                     needs_passphrase: true,
                 })
             }
@@ -753,6 +773,7 @@ impl AgentService {
 
     /// Remove key from Agent
     pub async fn remove_key(key_path: &str) -> SshResult<RemoveKeyResult> {
+        ReadOnlyMode::ensure_writable("remove key from agent")?;
         let path = PathBuf::from(key_path);
         if !path.exists() {
             return Err(SshBuddyError::KeyNotFound {
@@ -773,24 +794,78 @@ impl AgentService {
             })?;
 
         if output.status.success() {
-            log::info!("[agent_service] Key removed from agent: {}", key_path);
+            tracing::info!("[agent_service] Key removed from agent: {}", key_path);
             return Ok(RemoveKeyResult {
                 success: true,
-                message: "Key removed from SSH agent".to_string(),
+                message: LocalizedMessage::new("agent.keyRemoved"),
             });
         }
 
         let stderr = String::from_utf8_lossy(&output.stderr);
-        log::warn!(
+        tracing::warn!(
             "[agent_service] Failed to remove key: {}, stderr: {}",
             key_path,
             stderr
         );
         Ok(RemoveKeyResult {
             success: false,
-            message: stderr.to_string(),
+            message: LocalizedMessage::new("agent.sshAddOutput").with("output", stderr),
         })
     }
+
+    fn locked_state() -> &'static AtomicBool {
+        static LOCKED: AtomicBool = AtomicBool::new(false);
+        &LOCKED
+    }
+
+    /// Whether the agent was locked from this app (the protocol has no way to ask)
+    pub fn is_locked() -> bool {
+        Self::locked_state().load(Ordering::SeqCst)
+    }
+
+    /// Lock or unlock the agent with a password (like `ssh-add -x` / `ssh-add -X`)
+    /// A locked agent keeps its keys but refuses to list or use them
+    pub async fn set_locked(locked: bool, password: &str) -> SshResult<()> {
+        let request = lock_request(locked, password);
+        #[cfg(unix)]
+        let response = {
+            let mut stream = Self::connect().await?;
+            Self::send_request(&mut stream, &request).await?
+        };
+        #[cfg(windows)]
+        let response = {
+            let mut pipe = Self::connect_windows_pipe()?;
+            Self::send_request_windows(&mut pipe, &request)?
+        };
+
+        if response.first() != Some(&SSH_AGENT_SUCCESS) {
+            return Err(SshBuddyError::Unknown {
+                message: if locked {
+                    "The agent refused to lock (it may already be locked)".to_string()
+                } else {
+                    "The agent refused to unlock (wrong password?)".to_string()
+                },
+            });
+        }
+        Self::locked_state().store(locked, Ordering::SeqCst);
+        tracing::info!(
+            "[agent_service] Agent {}",
+            if locked { "locked" } else { "unlocked" }
+        );
+        Ok(())
+    }
+}
+
+/// SSH_AGENTC_LOCK / SSH_AGENTC_UNLOCK message: type byte and the password as an SSH string
+fn lock_request(locked: bool, password: &str) -> Vec<u8> {
+    let mut request = vec![if locked {
+        SSH_AGENTC_LOCK
+    } else {
+        SSH_AGENTC_UNLOCK
+    }];
+    request.extend_from_slice(&(password.len() as u32).to_be_bytes());
+    request.extend_from_slice(password.as_bytes());
+    request
 }
 
 /// Result of adding key
@@ -798,7 +873,8 @@ impl AgentService {
 #[serde(rename_all = "camelCase")]
 pub struct AddKeyResult {
     pub success: bool,
-    pub message: String,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
     pub needs_passphrase: bool,
 }
 
@@ -807,7 +883,8 @@ pub struct AddKeyResult {
 #[serde(rename_all = "camelCase")]
 pub struct RemoveKeyResult {
     pub success: bool,
-    pub message: String,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
 }
 
 #[cfg(test)]
@@ -917,4 +994,10 @@ EncryptedKeyDataHere...
         let pub_key = PublicKey::from_openssh(pub_key_content).unwrap();
         assert_eq!(AgentService::get_key_bit_size(&pub_key), 256);
     }
+
+    #[test]
+    fn test_lock_request() {
+        assert_eq!(lock_request(true, "pw"), vec![22, 0, 0, 0, 2, b'p', b'w']);
+        assert_eq!(lock_request(false, ""), vec![23, 0, 0, 0, 0]);
+    }
 }
@@ -0,0 +1,488 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::utils::{
+    connect_happy_eyeballs, is_aead_cipher, parse_server_hello, resolve_addresses,
+    ssh_client_options, AddressFamily, KexAlgorithms, CONNECTION_ATTEMPT_DELAY,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::timeout;
+
+/// Timeout for the server probe and for each ssh call
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Stop reading the server hello after this many bytes
+const MAX_HELLO_BYTES: usize = 64 * 1024;
+
+/// Identification string sent by the probe (it disconnects after the server's KEXINIT)
+const PROBE_BANNER: &[u8] = b"SSH-2.0-SSHBuddy_probe\r\n";
+
+/// Negotiated algorithm category
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AlgorithmCategory {
+    Kex,
+    HostKey,
+    Cipher,
+    Mac,
+}
+
+impl AlgorithmCategory {
+    const ALL: [AlgorithmCategory; 4] = [
+        AlgorithmCategory::Kex,
+        AlgorithmCategory::HostKey,
+        AlgorithmCategory::Cipher,
+        AlgorithmCategory::Mac,
+    ];
+
+    /// ssh_config option that controls the category
+    pub fn option_name(self) -> &'static str {
+        match self {
+            AlgorithmCategory::Kex => "KexAlgorithms",
+            AlgorithmCategory::HostKey => "HostKeyAlgorithms",
+            AlgorithmCategory::Cipher => "Ciphers",
+            AlgorithmCategory::Mac => "MACs",
+        }
+    }
+
+    /// `ssh -Q` query listing what the client supports
+    fn query(self) -> &'static str {
+        match self {
+            AlgorithmCategory::Kex => "kex",
+            AlgorithmCategory::HostKey => "HostKeyAlgorithms",
+            AlgorithmCategory::Cipher => "cipher",
+            AlgorithmCategory::Mac => "mac",
+        }
+    }
+
+    /// Server list for the client-to-server direction
+    fn server_offer(self, offer: &KexAlgorithms) -> &[String] {
+        match self {
+            AlgorithmCategory::Kex => &offer.kex,
+            AlgorithmCategory::HostKey => &offer.host_key,
+            AlgorithmCategory::Cipher => &offer.ciphers_client_to_server,
+            AlgorithmCategory::Mac => &offer.macs_client_to_server,
+        }
+    }
+}
+
+/// Outcome of one category
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CompatStatus {
+    /// An enabled algorithm matches
+    Ok,
+    /// Only algorithms the client supports but has disabled match
+    NeedsOverride,
+    /// The client supports nothing the server offers
+    Unsupported,
+}
+
+/// Host-scoped ssh_config override, e.g. KexAlgorithms +diffie-hellman-group14-sha1
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgorithmOverride {
+    pub option: String,
+    pub value: String,
+}
+
+/// Result for one algorithm category
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryCheck {
+    pub category: AlgorithmCategory,
+    pub status: CompatStatus,
+    /// Algorithm that will be used (None for MACs with an AEAD cipher)
+    pub negotiated: Option<String>,
+    pub server_offered: Vec<String>,
+    pub client_enabled: Vec<String>,
+    pub suggestion: Option<AlgorithmOverride>,
+}
+
+/// Algorithm compatibility of the local OpenSSH client with a host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgorithmCompatReport {
+    pub host_alias: String,
+    pub server_banner: String,
+    /// True when every category has an enabled match
+    pub compatible: bool,
+    pub checks: Vec<CategoryCheck>,
+    /// Minimal overrides that would make the connection work
+    pub overrides: Vec<AlgorithmOverride>,
+}
+
+/// Finds "no matching key exchange/host key/cipher/MAC" failures before connecting
+pub struct AlgorithmCheckService;
+
+impl AlgorithmCheckService {
+    /// Run the OpenSSH client
+    async fn run_ssh(args: Vec<String>) -> SshResult<Output> {
+        let result = timeout(
+            PROBE_TIMEOUT,
+            tokio::task::spawn_blocking(move || {
+                Command::new("ssh")
+                    .args(ssh_client_options())
+                    .args(&args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .output()
+            }),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(Ok(output))) => Ok(output),
+            Ok(Ok(Err(e))) => Err(SshBuddyError::IoError {
+                message: format!("Failed to run the OpenSSH client: {}", e),
+            }),
+            Ok(Err(e)) => Err(SshBuddyError::Unknown {
+                message: format!("Internal error: {}", e),
+            }),
+            Err(_) => Err(SshBuddyError::ConnectionTimeout),
+        }
+    }
+
+    /// Run the OpenSSH client and return its stdout
+    async fn ssh(args: Vec<String>) -> SshResult<String> {
+        let output = Self::run_ssh(args).await?;
+        if !output.status.success() {
+            return Err(SshBuddyError::Unknown {
+                message: format!(
+                    "ssh failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Local client version, e.g. "OpenSSH_9.9p1, OpenSSL 3.4.0 22 Oct 2024"
+    pub(crate) async fn client_version() -> SshResult<String> {
+        // `ssh -V` prints to stderr
+        let output = Self::run_ssh(vec!["-V".into()]).await?;
+        Ok(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+
+    /// Effective client configuration of a host (`ssh -G`)
+    pub(crate) async fn effective_config(host_alias: &str) -> SshResult<HashMap<String, String>> {
+        Ok(parse_effective_config(
+            &Self::ssh(vec!["-G".into(), host_alias.into()]).await?,
+        ))
+    }
+
+    /// Algorithms the client supports for a category
+    pub(crate) async fn supported(category: AlgorithmCategory) -> SshResult<Vec<String>> {
+        let output = match Self::ssh(vec!["-Q".into(), category.query().into()]).await {
+            Ok(output) => output,
+            // Clients before OpenSSH 8.x only know the key type query
+            Err(_) if category == AlgorithmCategory::HostKey => {
+                Self::ssh(vec!["-Q".into(), "key".into()]).await?
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Connect, exchange identification strings and read until the server's KEXINIT
+    async fn read_server_hello(
+        hostname: &str,
+        port: u16,
+    ) -> SshResult<Option<(String, Option<KexAlgorithms>)>> {
+        let addrs = resolve_addresses(hostname, port, AddressFamily::Any).await?;
+        let (mut stream, _) = connect_happy_eyeballs(&addrs, CONNECTION_ATTEMPT_DELAY).await?;
+        stream.write_all(PROBE_BANNER).await?;
+
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 {
+                return Ok(parse_server_hello(&data));
+            }
+            data.extend_from_slice(&buf[..read]);
+            let hello = parse_server_hello(&data);
+            if matches!(hello, Some((_, Some(_)))) || data.len() >= MAX_HELLO_BYTES {
+                return Ok(hello);
+            }
+        }
+    }
+
+    /// Banner and algorithms offered by the server
+    /// (direct connections only; hosts behind ProxyJump/ProxyCommand aren't probed)
+    pub(crate) async fn probe_server(
+        hostname: &str,
+        port: u16,
+    ) -> SshResult<(String, KexAlgorithms)> {
+        let hello = timeout(PROBE_TIMEOUT, Self::read_server_hello(hostname, port))
+            .await
+            .map_err(|_| SshBuddyError::ConnectionTimeout)??;
+
+        match hello {
+            Some((banner, Some(offer))) => Ok((banner, offer)),
+            Some((banner, None)) => Err(SshBuddyError::Unknown {
+                message: format!("{} closed the connection before key exchange", banner),
+            }),
+            None => Err(SshBuddyError::Unknown {
+                message: format!("{}:{} is not an SSH server", hostname, port),
+            }),
+        }
+    }
+
+    /// Compare the server's offer with what the local client has enabled for the host
+    pub async fn check(host_alias: &str) -> SshResult<AlgorithmCompatReport> {
+        let effective = Self::effective_config(host_alias).await?;
+        let hostname = effective
+            .get("hostname")
+            .cloned()
+            .unwrap_or_else(|| host_alias.to_string());
+        let port = effective
+            .get("port")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(22);
+        let (server_banner, offer) = Self::probe_server(&hostname, port).await?;
+
+        let mut checks: Vec<CategoryCheck> = Vec::new();
+        for category in AlgorithmCategory::ALL {
+            let enabled = split_list(
+                effective
+                    .get(&category.option_name().to_lowercase())
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+            );
+            let supported = Self::supported(category).await?;
+            // No MAC is needed when the cipher is AEAD
+            let aead = category == AlgorithmCategory::Mac
+                && checks
+                    .iter()
+                    .find(|c| c.category == AlgorithmCategory::Cipher)
+                    .and_then(|c| c.negotiated.as_deref())
+                    .is_some_and(is_aead_cipher);
+            checks.push(evaluate(
+                category,
+                &enabled,
+                &supported,
+                category.server_offer(&offer),
+                aead,
+            ));
+        }
+
+        let overrides: Vec<AlgorithmOverride> =
+            checks.iter().filter_map(|c| c.suggestion.clone()).collect();
+        let compatible = checks.iter().all(|c| c.status == CompatStatus::Ok);
+        tracing::info!(
+            "[algorithm_check] {} ({}): compatible={}, {} override(s) suggested",
+            host_alias,
+            server_banner,
+            compatible,
+            overrides.len()
+        );
+        Ok(AlgorithmCompatReport {
+            host_alias: host_alias.to_string(),
+            server_banner,
+            compatible,
+            checks,
+            overrides,
+        })
+    }
+
+    /// Write suggested overrides into the host's own block
+    pub async fn apply_overrides(
+        host_alias: &str,
+        overrides: &[AlgorithmOverride],
+    ) -> SshResult<()> {
+        ConfigService::update(|editor| {
+            if !editor.has_host(host_alias) {
+                return Err(SshBuddyError::HostNotFound {
+                    alias: host_alias.to_string(),
+                });
+            }
+            for entry in overrides {
+                if !AlgorithmCategory::ALL
+                    .iter()
+                    .any(|c| c.option_name().eq_ignore_ascii_case(&entry.option))
+                {
+                    return Err(SshBuddyError::InvalidOption {
+                        message: format!("Not an algorithm option: {}", entry.option),
+                    });
+                }
+                // Extend a list the host already sets instead of replacing it
+                let value = match (
+                    editor.get_option(host_alias, &entry.option),
+                    entry.value.strip_prefix('+'),
+                ) {
+                    (Some(existing), Some(added)) => format!("{},{}", existing, added),
+                    _ => entry.value.clone(),
+                };
+                editor.set_option(host_alias, &entry.option, &value);
+            }
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[algorithm_check] Applied {} override(s) to {}",
+            overrides.len(),
+            host_alias
+        );
+        Ok(())
+    }
+}
+
+/// Parse `ssh -G` output ("key value" lines, keys lowercase)
+fn parse_effective_config(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once(' ')?;
+            Some((key.to_lowercase(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+pub(crate) fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Markers of deprecated algorithms (SHA-1, MD5, CBC mode, DSA, truncated MACs)
+const WEAK_MARKERS: &[&str] = &[
+    "sha1", "group1-", "md5", "cbc", "3des", "arcfour", "ssh-dss", "-96",
+];
+
+/// Number of weak markers in an algorithm name (lower is stronger)
+fn weakness(name: &str) -> usize {
+    WEAK_MARKERS.iter().filter(|m| name.contains(*m)).count()
+}
+
+/// Check one category: enabled match first, then the strongest algorithm the server
+/// offers that the client supports but has disabled (server order breaks ties)
+fn evaluate(
+    category: AlgorithmCategory,
+    enabled: &[String],
+    supported: &[String],
+    server: &[String],
+    aead_cipher: bool,
+) -> CategoryCheck {
+    let mut check = CategoryCheck {
+        category,
+        status: CompatStatus::Ok,
+        negotiated: None,
+        server_offered: server.to_vec(),
+        client_enabled: enabled.to_vec(),
+        suggestion: None,
+    };
+    if aead_cipher {
+        return check;
+    }
+
+    check.negotiated = enabled.iter().find(|name| server.contains(name)).cloned();
+    if check.negotiated.is_some() {
+        return check;
+    }
+    let candidate = server
+        .iter()
+        .filter(|name| supported.contains(name))
+        .min_by_key(|name| weakness(name));
+    match candidate {
+        Some(name) => {
+            check.status = CompatStatus::NeedsOverride;
+            check.suggestion = Some(AlgorithmOverride {
+                option: category.option_name().to_string(),
+                // "+" appends to the client defaults instead of replacing them
+                value: format!("+{}", name),
+            });
+        }
+        None => check.status = CompatStatus::Unsupported,
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &str) -> Vec<String> {
+        split_list(list)
+    }
+
+    #[test]
+    fn test_parse_effective_config() {
+        let config = parse_effective_config(
+            "hostname 10.0.0.5\nport 22\nkexalgorithms curve25519-sha256,ecdh-sha2-nistp256\n",
+        );
+        assert_eq!(config.get("hostname").map(String::as_str), Some("10.0.0.5"));
+        assert_eq!(
+            split_list(&config["kexalgorithms"]),
+            vec!["curve25519-sha256", "ecdh-sha2-nistp256"]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_match() {
+        let check = evaluate(
+            AlgorithmCategory::Kex,
+            &names("curve25519-sha256,diffie-hellman-group16-sha512"),
+            &names("curve25519-sha256,diffie-hellman-group16-sha512,diffie-hellman-group1-sha1"),
+            &names("diffie-hellman-group16-sha512,curve25519-sha256"),
+            false,
+        );
+        assert_eq!(check.status, CompatStatus::Ok);
+        assert_eq!(check.negotiated.as_deref(), Some("curve25519-sha256"));
+        assert!(check.suggestion.is_none());
+    }
+
+    #[test]
+    fn test_evaluate_legacy_switch() {
+        // Old switch offering only SHA-1 key exchange
+        let check = evaluate(
+            AlgorithmCategory::Kex,
+            &names("curve25519-sha256,diffie-hellman-group16-sha512"),
+            &names("curve25519-sha256,diffie-hellman-group14-sha1,diffie-hellman-group1-sha1"),
+            &names("diffie-hellman-group1-sha1,diffie-hellman-group14-sha1"),
+            false,
+        );
+        assert_eq!(check.status, CompatStatus::NeedsOverride);
+        assert_eq!(
+            check.suggestion,
+            Some(AlgorithmOverride {
+                option: "KexAlgorithms".to_string(),
+                value: "+diffie-hellman-group14-sha1".to_string(),
+            })
+        );
+
+        let unsupported = evaluate(
+            AlgorithmCategory::Cipher,
+            &names("aes128-ctr"),
+            &names("aes128-ctr"),
+            &names("3des-cbc"),
+            false,
+        );
+        assert_eq!(unsupported.status, CompatStatus::Unsupported);
+        assert!(unsupported.suggestion.is_none());
+    }
+
+    #[test]
+    fn test_evaluate_mac_with_aead_cipher() {
+        let check = evaluate(
+            AlgorithmCategory::Mac,
+            &names("hmac-sha2-256"),
+            &names("hmac-sha2-256"),
+            &names("hmac-md5"),
+            true,
+        );
+        assert_eq!(check.status, CompatStatus::Ok);
+        assert_eq!(check.negotiated, None);
+    }
+}
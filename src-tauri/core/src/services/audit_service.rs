@@ -0,0 +1,522 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::export_signing::ExportSigningService;
+use crate::services::fleet_service::csv_field;
+use crate::services::history_service::{HistoryService, SessionRecord};
+use crate::services::privacy_service::{redact_host, PrivacyService};
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use crate::utils::{app_data_path, append_json_line, read_json_lines, retain_json_lines};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+
+/// Timeout for delivering one event to the SIEM
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Syslog facility "security/authorization" (4), see RFC 5424 section 6.2.1
+const SYSLOG_FACILITY: u8 = 4;
+
+/// What an audit event is about
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    /// ~/.ssh/config or known_hosts written
+    FileChange,
+    SessionClosed,
+    ConnectionFailed,
+    /// Command run on hosts (the command itself only when recording commands is allowed)
+    CommandRun,
+    /// Sent by "test forwarder"
+    Test,
+}
+
+/// One entry of audit.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub kind: AuditKind,
+    /// File or host alias
+    pub subject: String,
+    pub message: String,
+}
+
+/// Where audit events are forwarded
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SiemTarget {
+    /// RFC 5424 syslog over UDP, or TCP with octet-counting framing (RFC 6587)
+    Syslog { host: String, port: u16, tcp: bool },
+    /// JSON POST per event
+    Http {
+        url: String,
+        /// Authorization header value, e.g. "Bearer ..." or "Splunk ..."
+        authorization: Option<String>,
+    },
+}
+
+/// siem.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SiemSettings {
+    pub enabled: bool,
+    pub target: Option<SiemTarget>,
+}
+
+impl SiemSettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        if let Some(target) = &self.target {
+            validate_target(target)?;
+        }
+        if self.enabled && self.target.is_none() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Choose where to forward audit events first".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Which log to export
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LogSource {
+    History,
+    Audit,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogExportFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogExportRequest {
+    pub source: LogSource,
+    pub format: LogExportFormat,
+    /// Unix timestamp in milliseconds (inclusive)
+    pub from: Option<i64>,
+    /// Unix timestamp in milliseconds (exclusive)
+    pub to: Option<i64>,
+    /// Absolute path of the file to write
+    pub destination: String,
+    /// Write a `.sig` made with the app signing key next to the file
+    #[serde(default)]
+    pub sign: bool,
+}
+
+/// Audit log (audit.jsonl), its exporters and the optional SIEM forwarder
+pub struct AuditService;
+
+impl AuditService {
+    fn get_data_path(file_name: &str) -> SshResult<PathBuf> {
+        app_data_path(file_name)
+    }
+
+    /// Append an event and forward it when a SIEM is configured; failures are logged
+    pub async fn record(kind: AuditKind, subject: &str, message: &str) {
+        let event = AuditEvent {
+            timestamp: now_millis(),
+            kind,
+            subject: subject.to_string(),
+            message: message.to_string(),
+        };
+        if let Err(e) = Self::append(&event).await {
+            tracing::warn!("[audit_service] Failed to record event: {}", e);
+        }
+        match Self::get_settings().await {
+            Ok(SiemSettings {
+                enabled: true,
+                target: Some(target),
+            }) => {
+                tokio::spawn(async move {
+                    if let Err(e) = forward(&target, &event).await {
+                        tracing::warn!("[audit_service] Failed to forward event: {}", e);
+                    }
+                });
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("[audit_service] Failed to load SIEM settings: {}", e),
+        }
+    }
+
+    /// Audit event for a session history record
+    pub(crate) async fn record_session(record: &SessionRecord) {
+        match &record.failure {
+            Some(cause) => {
+                Self::record(AuditKind::ConnectionFailed, &record.host_alias, cause).await
+            }
+            None => {
+                let message = format!(
+                    "{} ms, {} bytes transferred",
+                    record.ended_at - record.started_at,
+                    record.bytes_transferred
+                );
+                Self::record(AuditKind::SessionClosed, &record.host_alias, &message).await
+            }
+        }
+    }
+
+    async fn append(event: &AuditEvent) -> SshResult<()> {
+        append_json_line(&Self::get_data_path("audit.jsonl")?, event).await
+    }
+
+    /// All audit events, oldest first (unreadable lines are skipped)
+    pub async fn load() -> SshResult<Vec<AuditEvent>> {
+        read_json_lines(&Self::get_data_path("audit.jsonl")?).await
+    }
+
+    /// Remove events older than `cutoff` (Unix milliseconds)
+    pub async fn purge_before(cutoff: i64) -> SshResult<usize> {
+        retain_json_lines(&Self::get_data_path("audit.jsonl")?, |e: &AuditEvent| {
+            e.timestamp >= cutoff
+        })
+        .await
+    }
+
+    /// Write the history or audit log to a file; returns the number of entries
+    /// Host aliases are pseudonymized when the privacy settings ask for it
+    pub async fn export(request: &LogExportRequest) -> SshResult<usize> {
+        let path = PathBuf::from(&request.destination);
+        if !path.is_absolute() {
+            return Err(SshBuddyError::InvalidPath {
+                message: format!("Export path must be absolute: {}", request.destination),
+            });
+        }
+        let redact = PrivacyService::get_settings().await.redact_hostnames;
+        let in_range = |timestamp: i64| {
+            request.from.map_or(true, |from| timestamp >= from)
+                && request.to.map_or(true, |to| timestamp < to)
+        };
+
+        let (content, count) = match request.source {
+            LogSource::History => {
+                let records: Vec<SessionRecord> = HistoryService::load()
+                    .await?
+                    .into_iter()
+                    .filter(|r| in_range(r.started_at))
+                    .map(|mut r| {
+                        if redact {
+                            r.host_alias = redact_host(&r.host_alias);
+                        }
+                        r
+                    })
+                    .collect();
+                let content = match request.format {
+                    LogExportFormat::Csv => history_to_csv(&records),
+                    LogExportFormat::Jsonl => to_json_lines(&records)?,
+                };
+                (content, records.len())
+            }
+            LogSource::Audit => {
+                let events: Vec<AuditEvent> = Self::load()
+                    .await?
+                    .into_iter()
+                    .filter(|e| in_range(e.timestamp))
+                    .map(|e| if redact { redact_event(e) } else { e })
+                    .collect();
+                let content = match request.format {
+                    LogExportFormat::Csv => audit_to_csv(&events),
+                    LogExportFormat::Jsonl => to_json_lines(&events)?,
+                };
+                (content, events.len())
+            }
+        };
+        fs::write(&path, content)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to write export: {}", e),
+            })?;
+        ExportSigningService::finish_export(&path, request.sign).await?;
+        tracing::info!(
+            "[audit_service] Exported {} {:?} entries to {:?}",
+            count,
+            request.source,
+            path
+        );
+        Ok(count)
+    }
+
+    pub async fn get_settings() -> SshResult<SiemSettings> {
+        Ok(SettingsService::get().siem)
+    }
+
+    pub async fn set_settings(settings: &SiemSettings) -> SshResult<()> {
+        settings.validate()?;
+        SettingsService::update(|current| {
+            current.siem = settings.clone();
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Send a test event to the target and report delivery errors
+    pub async fn test_forwarder(target: &SiemTarget) -> SshResult<()> {
+        validate_target(target)?;
+        let event = AuditEvent {
+            timestamp: now_millis(),
+            kind: AuditKind::Test,
+            subject: "ssh-buddy".to_string(),
+            message: "SIEM forwarding test".to_string(),
+        };
+        forward(target, &event).await
+    }
+}
+
+fn validate_target(target: &SiemTarget) -> SshResult<()> {
+    let invalid = |message: &str| {
+        Err(SshBuddyError::InvalidOption {
+            message: message.to_string(),
+        })
+    };
+    match target {
+        SiemTarget::Syslog { host, port, .. } => {
+            if host.trim().is_empty() || *port == 0 {
+                return invalid("Syslog host and port are required");
+            }
+        }
+        SiemTarget::Http { url, .. } => {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return invalid("Collector URL must start with https:// or http://");
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn forward(target: &SiemTarget, event: &AuditEvent) -> SshResult<()> {
+    let network_error = |e: String| SshBuddyError::ConnectionRefused { message: e };
+    match target {
+        SiemTarget::Syslog { host, port, tcp } => {
+            let message = syslog_message(event, &local_hostname());
+            let send = async {
+                if *tcp {
+                    let mut stream = TcpStream::connect((host.as_str(), *port)).await?;
+                    let framed = format!("{} {}", message.len(), message);
+                    stream.write_all(framed.as_bytes()).await
+                } else {
+                    let socket = UdpSocket::bind(("0.0.0.0", 0)).await?;
+                    socket
+                        .send_to(message.as_bytes(), (host.as_str(), *port))
+                        .await
+                        .map(|_| ())
+                }
+            };
+            tokio::time::timeout(FORWARD_TIMEOUT, send)
+                .await
+                .map_err(|_| SshBuddyError::ConnectionTimeout)?
+                .map_err(|e| network_error(e.to_string()))
+        }
+        SiemTarget::Http { url, authorization } => {
+            let client = reqwest::Client::builder()
+                .timeout(FORWARD_TIMEOUT)
+                .build()
+                .map_err(|e| network_error(e.to_string()))?;
+            let body = serde_json::to_string(event).map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?;
+            let mut request = client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .body(body);
+            if let Some(authorization) = authorization {
+                request = request.header("Authorization", authorization);
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| network_error(e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(network_error(format!(
+                    "Collector answered {}",
+                    response.status()
+                )));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn local_hostname() -> String {
+    whoami::fallible::hostname().unwrap_or_else(|_| "-".to_string())
+}
+
+fn rfc3339(timestamp_ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+/// RFC 5424 message with the event as JSON in the MSG part
+fn syslog_message(event: &AuditEvent, hostname: &str) -> String {
+    // Warning (4) for failed connections, notice (5) otherwise
+    let severity = if event.kind == AuditKind::ConnectionFailed {
+        4
+    } else {
+        5
+    };
+    let msg_id = serde_json::to_value(event.kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| "-".to_string());
+    format!(
+        "<{}>1 {} {} ssh-buddy - {} - {}",
+        SYSLOG_FACILITY * 8 + severity,
+        rfc3339(event.timestamp),
+        hostname,
+        msg_id,
+        serde_json::to_string(event).unwrap_or_default()
+    )
+}
+
+/// Event with host aliases replaced; file change messages name hosts, so they're dropped
+fn redact_event(mut event: AuditEvent) -> AuditEvent {
+    match event.kind {
+        AuditKind::SessionClosed | AuditKind::ConnectionFailed => {
+            event.subject = redact_host(&event.subject);
+        }
+        AuditKind::FileChange | AuditKind::CommandRun => {
+            event.message = "(redacted)".to_string();
+        }
+        AuditKind::Test => {}
+    }
+    event
+}
+
+fn to_json_lines<T: Serialize>(items: &[T]) -> SshResult<String> {
+    let mut out = String::new();
+    for item in items {
+        out.push_str(
+            &serde_json::to_string(item).map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?,
+        );
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn history_to_csv(records: &[SessionRecord]) -> String {
+    let mut csv = String::from("host,started_at,ended_at,duration_ms,bytes_transferred,failure\n");
+    for record in records {
+        let row = [
+            csv_field(&record.host_alias),
+            rfc3339(record.started_at),
+            rfc3339(record.ended_at),
+            (record.ended_at - record.started_at).to_string(),
+            record.bytes_transferred.to_string(),
+            csv_field(record.failure.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn audit_to_csv(events: &[AuditEvent]) -> String {
+    let mut csv = String::from("timestamp,kind,subject,message\n");
+    for event in events {
+        let kind = serde_json::to_value(event.kind)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let row = [
+            rfc3339(event.timestamp),
+            kind,
+            csv_field(&event.subject),
+            csv_field(&event.message),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: AuditKind, message: &str) -> AuditEvent {
+        AuditEvent {
+            timestamp: 1_709_251_200_123,
+            kind,
+            subject: "web-1".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_syslog_message() {
+        let message = syslog_message(&event(AuditKind::ConnectionFailed, "timeout"), "laptop");
+        assert!(message.starts_with(
+            "<36>1 2024-03-01T00:00:00.123Z laptop ssh-buddy - connection_failed - {"
+        ));
+        let message = syslog_message(&event(AuditKind::FileChange, "x"), "laptop");
+        assert!(message.starts_with("<37>1 "));
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let csv = audit_to_csv(&[event(AuditKind::FileChange, "Remove web, db")]);
+        assert_eq!(
+            csv,
+            "timestamp,kind,subject,message\n2024-03-01T00:00:00.123Z,file_change,web-1,\"Remove web, db\"\n"
+        );
+        let csv = history_to_csv(&[SessionRecord {
+            host_alias: "db".to_string(),
+            started_at: 1_709_251_200_000,
+            ended_at: 1_709_251_260_000,
+            bytes_transferred: 42,
+            failure: None,
+        }]);
+        assert!(csv.ends_with("db,2024-03-01T00:00:00.000Z,2024-03-01T00:01:00.000Z,60000,42,\n"));
+    }
+
+    #[test]
+    fn test_redact_event() {
+        let redacted = redact_event(event(AuditKind::ConnectionFailed, "ConnectionTimeout"));
+        assert_eq!(redacted.subject, redact_host("web-1"));
+        assert_eq!(redacted.message, "ConnectionTimeout");
+        let redacted = redact_event(event(AuditKind::FileChange, "Remove web-1"));
+        assert_eq!(redacted.message, "(redacted)");
+    }
+
+    #[test]
+    fn test_validate_target() {
+        assert!(validate_target(&SiemTarget::Http {
+            url: "ftp://siem".to_string(),
+            authorization: None
+        })
+        .is_err());
+        assert!(validate_target(&SiemTarget::Syslog {
+            host: "siem.local".to_string(),
+            port: 514,
+            tcp: false
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_settings_format() {
+        let settings: SiemSettings = serde_json::from_str(
+            r#"{"enabled":true,"target":{"type":"syslog","host":"siem","port":6514,"tcp":true}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.target,
+            Some(SiemTarget::Syslog {
+                host: "siem".to_string(),
+                port: 6514,
+                tcp: true
+            })
+        );
+    }
+}
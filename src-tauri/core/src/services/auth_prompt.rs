@@ -0,0 +1,164 @@
+use crate::models::{SshBuddyError, SshResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::{timeout, Duration};
+
+/// How long to wait for the user to answer a prompt before giving up
+const PROMPT_TIMEOUT_SECS: u64 = 120;
+
+/// Kind of interactive authentication prompt
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthPromptKind {
+    Password,
+    KeyboardInteractive,
+    /// sudo password for a remote command
+    Sudo,
+}
+
+/// Single question inside a prompt request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptField {
+    pub prompt: String,
+    /// Whether the answer may be shown while typing (false for passwords/OTP)
+    pub echo: bool,
+}
+
+/// Prompt request forwarded to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptRequest {
+    pub request_id: String,
+    pub host_alias: String,
+    pub kind: AuthPromptKind,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<AuthPromptField>,
+}
+
+impl AuthPromptRequest {
+    /// Create a request with a fresh random id
+    pub fn new(host_alias: &str, kind: AuthPromptKind) -> Self {
+        Self {
+            request_id: format!("{:016x}", rand::random::<u64>()),
+            host_alias: host_alias.to_string(),
+            kind,
+            name: String::new(),
+            instructions: String::new(),
+            prompts: Vec::new(),
+        }
+    }
+}
+
+/// Answers the prompts of an interactive authentication round
+#[async_trait]
+pub trait AuthPrompter: Send + Sync {
+    /// Returns one answer per prompt, or None if the user cancelled
+    async fn prompt(&self, request: AuthPromptRequest) -> Option<Vec<String>>;
+}
+
+/// Pending prompt replies, keyed by request id
+pub struct AuthPromptBroker {
+    pending: Mutex<HashMap<String, oneshot::Sender<Option<Vec<String>>>>>,
+}
+
+impl AuthPromptBroker {
+    fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the process-wide broker
+    pub fn global() -> &'static AuthPromptBroker {
+        static BROKER: OnceLock<AuthPromptBroker> = OnceLock::new();
+        BROKER.get_or_init(AuthPromptBroker::new)
+    }
+
+    /// Register a request and get the receiver for its answers
+    pub async fn register(&self, request_id: &str) -> oneshot::Receiver<Option<Vec<String>>> {
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(request_id.to_string(), tx);
+        rx
+    }
+
+    /// Wait for the answers of a registered request (None on cancel or timeout)
+    pub async fn wait(
+        &self,
+        request_id: &str,
+        rx: oneshot::Receiver<Option<Vec<String>>>,
+    ) -> Option<Vec<String>> {
+        match timeout(Duration::from_secs(PROMPT_TIMEOUT_SECS), rx).await {
+            Ok(Ok(answers)) => answers,
+            Ok(Err(_)) => None,
+            Err(_) => {
+                tracing::warn!("[auth_prompt] Prompt {} timed out", request_id);
+                self.pending.lock().await.remove(request_id);
+                None
+            }
+        }
+    }
+
+    /// Deliver answers (or a cancellation) for a pending request
+    pub async fn respond(&self, request_id: &str, answers: Option<Vec<String>>) -> SshResult<()> {
+        let sender = self
+            .pending
+            .lock()
+            .await
+            .remove(request_id)
+            .ok_or_else(|| SshBuddyError::Unknown {
+                message: format!("No pending auth prompt: {}", request_id),
+            })?;
+
+        // The waiting side may already have timed out; nothing to do then
+        let _ = sender.send(answers);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_ids_are_unique() {
+        let a = AuthPromptRequest::new("host", AuthPromptKind::Password);
+        let b = AuthPromptRequest::new("host", AuthPromptKind::Password);
+        assert_eq!(a.request_id.len(), 16);
+        assert_ne!(a.request_id, b.request_id);
+    }
+
+    #[tokio::test]
+    async fn test_broker_delivers_answers() {
+        let broker = AuthPromptBroker::new();
+        let rx = broker.register("req1").await;
+
+        broker
+            .respond("req1", Some(vec!["123456".to_string()]))
+            .await
+            .unwrap();
+
+        let answers = broker.wait("req1", rx).await;
+        assert_eq!(answers, Some(vec!["123456".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_broker_cancel() {
+        let broker = AuthPromptBroker::new();
+        let rx = broker.register("req2").await;
+
+        broker.respond("req2", None).await.unwrap();
+
+        assert_eq!(broker.wait("req2", rx).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_broker_unknown_request() {
+        let broker = AuthPromptBroker::new();
+        assert!(broker.respond("missing", None).await.is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use crate::models::SshResult;
+use crate::services::fleet_service::{FleetRequest, FleetService};
+use crate::services::key_exposure::KeyExposureService;
+use crate::services::key_manager::KeyManager;
+use crate::services::registry_service::now_millis;
+use crate::services::team_catalog::TeamCatalogService;
+use crate::utils::parse_authorized_keys;
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
+use std::collections::{BTreeMap, HashMap};
+
+/// Prints authorized_keys; a missing file is an empty list, not a failure
+const READ_AUTHORIZED_KEYS: &str = "cat ~/.ssh/authorized_keys 2>/dev/null || true";
+
+/// Per-host timeout of the audit
+const AUDIT_TIMEOUT_SECS: u64 = 30;
+
+/// Who an authorized key belongs to (most alarming first)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyOwnership {
+    /// Marked revoked in a team catalog (e.g. a former member)
+    Revoked,
+    /// Neither a local key nor in a team catalog
+    Unknown,
+    /// Listed in a team catalog
+    Team,
+    /// Key in the SSH directory
+    Local,
+}
+
+/// A key found in the authorized_keys of one or more hosts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditedKey {
+    pub fingerprint: String,
+    pub key_type: String,
+    /// Comments the key carries on the hosts
+    pub comments: Vec<String>,
+    pub ownership: KeyOwnership,
+    /// Local key name or team member
+    pub owner: Option<String>,
+    pub hosts: Vec<String>,
+}
+
+/// authorized_keys of one host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditedHost {
+    pub host_alias: String,
+    /// Why the host couldn't be read
+    pub error: Option<String>,
+    pub key_count: usize,
+    pub revoked_keys: usize,
+    pub unknown_keys: usize,
+}
+
+/// Hosts to audit (a tag group and/or explicit aliases)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizedKeysAuditRequest {
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub host_aliases: Vec<String>,
+    pub concurrency: Option<usize>,
+}
+
+/// Result of a fleet authorized_keys audit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizedKeysAudit {
+    /// Unix timestamp in milliseconds
+    pub ran_at: i64,
+    pub hosts: Vec<AuditedHost>,
+    /// Revoked keys first, then unknown, team and local keys; widest spread first within each
+    pub keys: Vec<AuditedKey>,
+    /// Hosts where revoked or unknown keys have access
+    pub flagged_hosts: Vec<String>,
+}
+
+/// Known owner of a key
+#[derive(Debug, Clone, PartialEq)]
+struct KnownKey {
+    ownership: KeyOwnership,
+    owner: String,
+}
+
+/// Reads authorized_keys across a group of hosts and classifies every key
+pub struct AuthorizedKeysAuditService;
+
+impl AuthorizedKeysAuditService {
+    /// Audit the reachable hosts of the selection; unreachable hosts are listed with their error
+    pub async fn run(request: AuthorizedKeysAuditRequest) -> SshResult<AuthorizedKeysAudit> {
+        let known = Self::known_keys().await?;
+        let summary = FleetService::run(
+            FleetRequest {
+                command: READ_AUTHORIZED_KEYS.to_string(),
+                tag: request.tag,
+                host_aliases: request.host_aliases,
+                concurrency: request.concurrency,
+                timeout_secs: Some(AUDIT_TIMEOUT_SECS),
+            },
+            |_| {},
+        )
+        .await?;
+
+        let outputs: Vec<(String, Result<String, String>)> = summary
+            .results
+            .into_iter()
+            .map(|result| {
+                let output = match result.error {
+                    Some(error) => Err(error),
+                    None if !result.success => Err(result.stderr.trim().to_string()),
+                    None => Ok(result.stdout),
+                };
+                (result.host_alias, output)
+            })
+            .collect();
+
+        // Keep the key exposure report in step with what the hosts really authorize
+        for (alias, output) in &outputs {
+            let Ok(content) = output else {
+                continue;
+            };
+            let fingerprints: Vec<String> = parse_authorized_keys(content)
+                .iter()
+                .map(|line| line.key.fingerprint(HashAlg::Sha256).to_string())
+                .collect();
+            if let Err(e) = KeyExposureService::record_scan(alias, &fingerprints).await {
+                tracing::warn!("[authorized_keys_audit] Failed to record {}: {}", alias, e);
+            }
+        }
+
+        let audit = audit_outputs(&outputs, &known, now_millis());
+        tracing::info!(
+            "[authorized_keys_audit] {} host(s), {} distinct key(s), {} flagged host(s)",
+            audit.hosts.len(),
+            audit.keys.len(),
+            audit.flagged_hosts.len()
+        );
+        Ok(audit)
+    }
+
+    /// Local keys and team catalog keys by fingerprint; revoked wins over listed
+    async fn known_keys() -> SshResult<HashMap<String, KnownKey>> {
+        let mut known = HashMap::new();
+        for subscription in TeamCatalogService::list().await? {
+            for key in subscription.keys {
+                let Ok(public_key) = PublicKey::from_openssh(key.public_key.trim()) else {
+                    continue;
+                };
+                let ownership = if key.revoked {
+                    KeyOwnership::Revoked
+                } else {
+                    KeyOwnership::Team
+                };
+                let entry = known
+                    .entry(public_key.fingerprint(HashAlg::Sha256).to_string())
+                    .or_insert(KnownKey {
+                        ownership,
+                        owner: key.owner.clone(),
+                    });
+                if ownership < entry.ownership {
+                    *entry = KnownKey {
+                        ownership,
+                        owner: key.owner,
+                    };
+                }
+            }
+        }
+        for key in KeyManager::new()?.list_keys().await? {
+            if let Some(fingerprint) = key.fingerprint {
+                known.entry(fingerprint).or_insert(KnownKey {
+                    ownership: KeyOwnership::Local,
+                    owner: key.name,
+                });
+            }
+        }
+        Ok(known)
+    }
+}
+
+/// Classify the keys of every host's authorized_keys output
+fn audit_outputs(
+    outputs: &[(String, Result<String, String>)],
+    known: &HashMap<String, KnownKey>,
+    ran_at: i64,
+) -> AuthorizedKeysAudit {
+    let mut hosts = Vec::new();
+    let mut keys: BTreeMap<String, AuditedKey> = BTreeMap::new();
+    let mut flagged_hosts = Vec::new();
+
+    for (alias, output) in outputs {
+        let content = match output {
+            Ok(content) => content,
+            Err(error) => {
+                hosts.push(AuditedHost {
+                    host_alias: alias.clone(),
+                    error: Some(error.clone()),
+                    key_count: 0,
+                    revoked_keys: 0,
+                    unknown_keys: 0,
+                });
+                continue;
+            }
+        };
+        let mut host = AuditedHost {
+            host_alias: alias.clone(),
+            error: None,
+            key_count: 0,
+            revoked_keys: 0,
+            unknown_keys: 0,
+        };
+        for line in parse_authorized_keys(content) {
+            let fingerprint = line.key.fingerprint(HashAlg::Sha256).to_string();
+            let known_key = known.get(&fingerprint);
+            let key = keys
+                .entry(fingerprint.clone())
+                .or_insert_with(|| AuditedKey {
+                    fingerprint,
+                    key_type: line.key.algorithm().as_str().to_string(),
+                    comments: Vec::new(),
+                    ownership: known_key.map_or(KeyOwnership::Unknown, |k| k.ownership),
+                    owner: known_key.map(|k| k.owner.clone()),
+                    hosts: Vec::new(),
+                });
+            let comment = line.key.comment().trim();
+            if !comment.is_empty() && !key.comments.iter().any(|c| c == comment) {
+                key.comments.push(comment.to_string());
+            }
+            if key.hosts.contains(alias) {
+                continue;
+            }
+            key.hosts.push(alias.clone());
+            host.key_count += 1;
+            match key.ownership {
+                KeyOwnership::Revoked => host.revoked_keys += 1,
+                KeyOwnership::Unknown => host.unknown_keys += 1,
+                KeyOwnership::Team | KeyOwnership::Local => {}
+            }
+        }
+        if host.revoked_keys + host.unknown_keys > 0 {
+            flagged_hosts.push(alias.clone());
+        }
+        hosts.push(host);
+    }
+
+    let mut keys: Vec<AuditedKey> = keys.into_values().collect();
+    keys.sort_by(|a, b| {
+        a.ownership
+            .cmp(&b.ownership)
+            .then_with(|| b.hosts.len().cmp(&a.hosts.len()))
+    });
+    AuthorizedKeysAudit {
+        ran_at,
+        hosts,
+        keys,
+        flagged_hosts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl alice";
+    const BOB: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBG5Qh1pHZPhCFH5bz6hXNXwMLC4uI2PyzeRHQjVSlpc bob";
+
+    fn fingerprint(line: &str) -> String {
+        PublicKey::from_openssh(line)
+            .unwrap()
+            .fingerprint(HashAlg::Sha256)
+            .to_string()
+    }
+
+    #[test]
+    fn test_audit_outputs() {
+        let known = HashMap::from([(
+            fingerprint(ALICE),
+            KnownKey {
+                ownership: KeyOwnership::Revoked,
+                owner: "alice".to_string(),
+            },
+        )]);
+        let outputs = vec![
+            ("web".to_string(), Ok(format!("{}\n{}\n", ALICE, BOB))),
+            ("db".to_string(), Ok(format!("restrict {}\n", BOB))),
+            ("ci".to_string(), Ok(String::new())),
+            ("old".to_string(), Err("Connection refused".to_string())),
+        ];
+        let audit = audit_outputs(&outputs, &known, 1);
+
+        assert_eq!(audit.flagged_hosts, vec!["web", "db"]);
+        assert_eq!(audit.keys.len(), 2);
+        assert_eq!(audit.keys[0].ownership, KeyOwnership::Revoked);
+        assert_eq!(audit.keys[0].owner.as_deref(), Some("alice"));
+        assert_eq!(audit.keys[1].ownership, KeyOwnership::Unknown);
+        assert_eq!(audit.keys[1].hosts, vec!["web", "db"]);
+        assert_eq!(audit.keys[1].comments, vec!["bob"]);
+
+        let web = &audit.hosts[0];
+        assert_eq!(
+            (web.key_count, web.revoked_keys, web.unknown_keys),
+            (2, 1, 1)
+        );
+        assert_eq!(audit.hosts[3].error.as_deref(), Some("Connection refused"));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::host_notes::normalize_notes;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::{now_millis, HostMetadata, RegistryService};
+use crate::utils::{append_json_line, read_json_lines, retain_json_lines, workspace_data_path};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Output kept per capture; the rest is cut off (and marked as such)
+const MAX_CAPTURE_BYTES: usize = 64 * 1024;
+
+/// Captures kept per host; older ones are dropped when new ones come in
+const MAX_CAPTURES_PER_HOST: usize = 50;
+
+/// Where a capture goes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureTarget {
+    /// Appended to the host's Markdown notes
+    Notes,
+    /// Kept in the host's captures log
+    #[default]
+    Captures,
+}
+
+/// Output of a remote command run (terminal, script, fleet or snippet) to keep
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureRequest {
+    pub host_alias: String,
+    pub command: String,
+    pub output: String,
+    #[serde(default)]
+    pub exit_code: Option<u32>,
+    /// When the command ran, Unix milliseconds (default: now)
+    #[serde(default)]
+    pub captured_at: Option<i64>,
+    #[serde(default)]
+    pub target: CaptureTarget,
+}
+
+/// Diagnostic snapshot in the captures log
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureRecord {
+    pub id: String,
+    pub host_alias: String,
+    pub command: String,
+    pub output: String,
+    pub exit_code: Option<u32>,
+    /// Unix timestamp in milliseconds
+    pub captured_at: i64,
+    /// Output was longer than the capture limit
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// Keeps command output next to the host: in its notes or in captures.jsonl
+pub struct CaptureService;
+
+impl CaptureService {
+    fn get_log_path() -> SshResult<PathBuf> {
+        workspace_data_path("captures.jsonl")
+    }
+
+    /// Store a command's output with its command and time
+    pub async fn capture(request: &CaptureRequest) -> SshResult<CaptureRecord> {
+        ReadOnlyMode::ensure_writable("capture command output")?;
+        if request.command.trim().is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "The captured command is required".to_string(),
+            });
+        }
+        let (output, truncated) = truncate_output(&request.output, MAX_CAPTURE_BYTES);
+        let record = CaptureRecord {
+            id: format!("{:08x}", rand::random::<u32>()),
+            host_alias: request.host_alias.clone(),
+            command: request.command.trim().to_string(),
+            output,
+            exit_code: request.exit_code,
+            captured_at: request.captured_at.unwrap_or_else(now_millis),
+            truncated,
+        };
+
+        match request.target {
+            CaptureTarget::Notes => {
+                RegistryService::update(|store| {
+                    let metadata = store
+                        .hosts
+                        .entry(record.host_alias.clone())
+                        .or_insert_with(HostMetadata::new);
+                    let notes = append_to_notes(metadata.notes.as_deref(), &record);
+                    metadata.notes = normalize_notes(Some(&notes))?;
+                    Ok(())
+                })
+                .await?
+            }
+            CaptureTarget::Captures => Self::store(&Self::get_log_path()?, &record).await?,
+        }
+        tracing::info!(
+            "[captures] Captured {} bytes of {:?} on {} into {:?}",
+            record.output.len(),
+            record.command,
+            record.host_alias,
+            request.target
+        );
+        Ok(record)
+    }
+
+    /// Append a capture and drop the host's oldest beyond the limit
+    async fn store(path: &Path, record: &CaptureRecord) -> SshResult<()> {
+        append_json_line(path, record).await?;
+        let records: Vec<CaptureRecord> = read_json_lines(path).await?;
+        let dropped = oldest_captures(&records, &record.host_alias);
+        if !dropped.is_empty() {
+            retain_json_lines(path, |r: &CaptureRecord| {
+                r.host_alias != record.host_alias || !dropped.contains(r.id.as_str())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Captures of a host, newest first
+    pub async fn list(alias: &str, limit: Option<usize>) -> SshResult<Vec<CaptureRecord>> {
+        let records: Vec<CaptureRecord> = read_json_lines(&Self::get_log_path()?).await?;
+        Ok(records
+            .into_iter()
+            .rev()
+            .filter(|r| r.host_alias == alias)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Delete one capture (`id`) or all captures of a host
+    pub async fn delete(alias: &str, id: Option<&str>) -> SshResult<usize> {
+        ReadOnlyMode::ensure_writable("delete captures")?;
+        retain_json_lines(&Self::get_log_path()?, |r: &CaptureRecord| {
+            r.host_alias != alias || id.is_some_and(|id| r.id != id)
+        })
+        .await
+    }
+}
+
+/// Ids of the host's captures beyond the newest `MAX_CAPTURES_PER_HOST`
+fn oldest_captures<'a>(records: &'a [CaptureRecord], alias: &str) -> HashSet<&'a str> {
+    let ids: Vec<&str> = records
+        .iter()
+        .filter(|r| r.host_alias == alias)
+        .map(|r| r.id.as_str())
+        .collect();
+    let excess = ids.len().saturating_sub(MAX_CAPTURES_PER_HOST);
+    ids[..excess].iter().copied().collect()
+}
+
+/// Output cut at `max_bytes` (on a char boundary), and whether anything was cut
+pub(crate) fn truncate_output(output: &str, max_bytes: usize) -> (String, bool) {
+    if output.len() <= max_bytes {
+        return (output.to_string(), false);
+    }
+    let mut end = max_bytes;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    (output[..end].to_string(), true)
+}
+
+/// Notes with the capture added as a Markdown section
+fn append_to_notes(notes: Option<&str>, record: &CaptureRecord) -> String {
+    let time = chrono::DateTime::from_timestamp_millis(record.captured_at)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    // A fence longer than any backtick run in the output
+    let longest_run = record
+        .output
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    let mut section = format!("### Capture {}\n\n`{}`", time, record.command);
+    if let Some(code) = record.exit_code.filter(|c| *c != 0) {
+        section.push_str(&format!(" (exit {})", code));
+    }
+    section.push_str(&format!("\n\n{}text\n{}", fence, record.output));
+    if !record.output.ends_with('\n') {
+        section.push('\n');
+    }
+    if record.truncated {
+        section.push_str("[output truncated]\n");
+    }
+    section.push_str(&fence);
+    section.push('\n');
+
+    match notes.map(str::trim_end).filter(|n| !n.is_empty()) {
+        Some(notes) => format!("{}\n\n{}", notes, section),
+        None => section,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-01T12:30:00Z
+    const CAPTURED_AT: i64 = 1_709_296_200_000;
+
+    fn record(command: &str, output: &str, exit_code: Option<u32>) -> CaptureRecord {
+        CaptureRecord {
+            id: "1".to_string(),
+            host_alias: "web".to_string(),
+            command: command.to_string(),
+            output: output.to_string(),
+            exit_code,
+            captured_at: CAPTURED_AT,
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn test_append_to_notes() {
+        let notes = append_to_notes(Some("# Web\n\n"), &record("uptime", "up 3 days", None));
+        assert_eq!(
+            notes,
+            "# Web\n\n### Capture 2024-03-01 12:30 UTC\n\n`uptime`\n\n```text\nup 3 days\n```\n"
+        );
+
+        let failed = append_to_notes(None, &record("df -h", "see ````\n", Some(1)));
+        assert!(failed.starts_with("### Capture 2024-03-01 12:30 UTC\n\n`df -h` (exit 1)"));
+        assert!(failed.contains("\n`````text\nsee ````\n`````\n"));
+    }
+
+    #[test]
+    fn test_oldest_captures() {
+        let records: Vec<CaptureRecord> = (0..MAX_CAPTURES_PER_HOST + 2)
+            .flat_map(|i| {
+                let mut web = record("uptime", "", None);
+                web.id = format!("web-{}", i);
+                let mut db = record("uptime", "", None);
+                db.host_alias = "db".to_string();
+                db.id = format!("db-{}", i);
+                [web, db]
+            })
+            .collect();
+        let dropped = oldest_captures(&records, "web");
+        assert_eq!(dropped, HashSet::from(["web-0", "web-1"]));
+        assert!(oldest_captures(&records[..10], "web").is_empty());
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let (output, truncated) = truncate_output("short", MAX_CAPTURE_BYTES);
+        assert_eq!((output.as_str(), truncated), ("short", false));
+
+        let long = "é".repeat(MAX_CAPTURE_BYTES);
+        let (output, truncated) = truncate_output(&long, MAX_CAPTURE_BYTES);
+        assert!(truncated);
+        assert!(output.len() <= MAX_CAPTURE_BYTES);
+        assert!(output.chars().all(|c| c == 'é'));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::host_key_rotation::known_host_names;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::revision_service::{ManagedFile, RevisionService};
+use crate::services::ssh_connection::SshConnectionService;
+use crate::utils::{glob_match, ssh_dir, write_atomic};
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
+use std::path::PathBuf;
+use tokio::fs;
+
+const MARKER: &str = "@cert-authority";
+
+/// A CA trusted to sign host certificates for the hosts matching its patterns
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CertAuthority {
+    /// known_hosts patterns, e.g. `*.example.com` or `!bastion.example.com`
+    pub patterns: Vec<String>,
+    pub key_type: String,
+    /// SHA256 fingerprint of the CA key, identifies the entry
+    pub fingerprint: String,
+    pub comment: String,
+}
+
+/// How the key of a configured host is trusted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostTrust {
+    pub alias: String,
+    pub hostname: String,
+    pub port: u16,
+    /// Fingerprints of the CAs whose patterns cover the host
+    pub authorities: Vec<String>,
+    /// The host has its own key pinned in known_hosts
+    pub pinned: bool,
+}
+
+/// Manages `@cert-authority` lines in ~/.ssh/known_hosts
+pub struct CertAuthorityService;
+
+impl CertAuthorityService {
+    fn known_hosts_path() -> SshResult<PathBuf> {
+        Ok(ssh_dir()?.join("known_hosts"))
+    }
+
+    async fn read() -> SshResult<Option<String>> {
+        match fs::read_to_string(Self::known_hosts_path()?).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SshBuddyError::IoError {
+                message: format!("Failed to read known_hosts: {}", e),
+            }),
+        }
+    }
+
+    async fn write(previous: Option<&str>, content: &str, description: &str) -> SshResult<()> {
+        let path = Self::known_hosts_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        write_atomic(&path, content.as_bytes()).await?;
+        RevisionService::record_change(ManagedFile::KnownHosts, previous, content, description)
+            .await;
+        Ok(())
+    }
+
+    /// Trusted CAs in known_hosts
+    pub async fn list() -> SshResult<Vec<CertAuthority>> {
+        Ok(parse_cert_authorities(
+            Self::read().await?.as_deref().unwrap_or_default(),
+        ))
+    }
+
+    /// Trust a CA public key (OpenSSH format) for hosts matching `patterns`
+    pub async fn add(public_key: &str, patterns: &[String]) -> SshResult<CertAuthority> {
+        ReadOnlyMode::ensure_writable("add a certificate authority to known_hosts")?;
+        validate_patterns(patterns)?;
+        let key = PublicKey::from_openssh(public_key.trim()).map_err(|e| {
+            SshBuddyError::InvalidOption {
+                message: format!("Invalid CA public key: {}", e),
+            }
+        })?;
+        let authority = authority_of(patterns.to_vec(), &key);
+
+        let previous = Self::read().await?;
+        let mut content = previous.clone().unwrap_or_default();
+        if parse_cert_authorities(&content)
+            .iter()
+            .any(|a| a.fingerprint == authority.fingerprint)
+        {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "{} is already a trusted CA, edit its patterns instead",
+                    authority.fingerprint
+                ),
+            });
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&authority_line(&authority.patterns, &key)?);
+        content.push('\n');
+
+        Self::write(
+            previous.as_deref(),
+            &content,
+            &format!(
+                "Trust CA {} for {}",
+                authority.fingerprint,
+                patterns.join(",")
+            ),
+        )
+        .await?;
+        tracing::info!(
+            "[cert_authority] Trusted CA {} for {}",
+            authority.fingerprint,
+            authority.patterns.join(",")
+        );
+        Ok(authority)
+    }
+
+    /// Change the patterns a CA is trusted for
+    pub async fn set_patterns(fingerprint: &str, patterns: &[String]) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("edit a certificate authority in known_hosts")?;
+        validate_patterns(patterns)?;
+        let previous = Self::read().await?.unwrap_or_default();
+        let content = rewrite_authority(&previous, fingerprint, Some(patterns))?;
+        Self::write(
+            Some(&previous),
+            &content,
+            &format!("Scope CA {} to {}", fingerprint, patterns.join(",")),
+        )
+        .await
+    }
+
+    /// Stop trusting a CA
+    pub async fn remove(fingerprint: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("remove a certificate authority from known_hosts")?;
+        let previous = Self::read().await?.unwrap_or_default();
+        let content = rewrite_authority(&previous, fingerprint, None)?;
+        Self::write(
+            Some(&previous),
+            &content,
+            &format!("Remove CA {}", fingerprint),
+        )
+        .await?;
+        tracing::info!("[cert_authority] Removed CA {}", fingerprint);
+        Ok(())
+    }
+
+    /// For every configured host: the CAs covering it and whether its key is pinned
+    pub async fn coverage() -> SshResult<Vec<HostTrust>> {
+        let authorities = Self::list().await?;
+        let pinned = SshConnectionService::load_known_hosts().await;
+        let mut hosts = Vec::new();
+        for alias in ConfigService::load_editor().await?.host_aliases() {
+            let host = SshConnectionService::resolve_host(&alias).await?;
+            let names = known_host_names(host.get_hostname(), host.get_port());
+            hosts.push(HostTrust {
+                authorities: authorities
+                    .iter()
+                    .filter(|a| patterns_match(&a.patterns, &names[0]))
+                    .map(|a| a.fingerprint.clone())
+                    .collect(),
+                pinned: names.iter().any(|n| pinned.contains_key(n)),
+                alias,
+                hostname: host.get_hostname().to_string(),
+                port: host.get_port(),
+            });
+        }
+        Ok(hosts)
+    }
+}
+
+fn authority_of(patterns: Vec<String>, key: &PublicKey) -> CertAuthority {
+    CertAuthority {
+        patterns,
+        key_type: key.algorithm().as_str().to_string(),
+        fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+        comment: key.comment().to_string(),
+    }
+}
+
+fn authority_line(patterns: &[String], key: &PublicKey) -> SshResult<String> {
+    let key = key.to_openssh().map_err(|e| SshBuddyError::Unknown {
+        message: format!("Failed to encode CA key: {}", e),
+    })?;
+    Ok(format!("{} {} {}", MARKER, patterns.join(","), key))
+}
+
+fn validate_patterns(patterns: &[String]) -> SshResult<()> {
+    let invalid = |message: String| Err(SshBuddyError::InvalidOption { message });
+    if patterns.iter().all(|p| p.starts_with('!')) {
+        return invalid("At least one pattern that is not negated is required".to_string());
+    }
+    for pattern in patterns {
+        let name = pattern.trim_start_matches('!');
+        if name.is_empty()
+            || name.starts_with("|1|")
+            || pattern
+                .chars()
+                .any(|c| c == ',' || c.is_whitespace() || c.is_control())
+        {
+            return invalid(format!("Invalid host pattern \"{}\"", pattern));
+        }
+    }
+    Ok(())
+}
+
+/// `@cert-authority` line split into patterns and key
+fn parse_authority_line(line: &str) -> Option<(Vec<String>, PublicKey)> {
+    let mut fields = line.split_whitespace();
+    if !fields.next()?.eq_ignore_ascii_case(MARKER) {
+        return None;
+    }
+    let patterns = fields.next()?.split(',').map(str::to_string).collect();
+    let key = PublicKey::from_openssh(&fields.collect::<Vec<_>>().join(" ")).ok()?;
+    Some((patterns, key))
+}
+
+fn parse_cert_authorities(content: &str) -> Vec<CertAuthority> {
+    content
+        .lines()
+        .filter_map(parse_authority_line)
+        .map(|(patterns, key)| authority_of(patterns, &key))
+        .collect()
+}
+
+/// known_hosts with the CA's lines given new patterns, or removed when `patterns` is None
+fn rewrite_authority(
+    content: &str,
+    fingerprint: &str,
+    patterns: Option<&[String]>,
+) -> SshResult<String> {
+    let mut found = false;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        let key = parse_authority_line(line)
+            .map(|(_, key)| key)
+            .filter(|key| key.fingerprint(HashAlg::Sha256).to_string() == fingerprint);
+        match (key, patterns) {
+            (None, _) => lines.push(line.to_string()),
+            (Some(key), Some(patterns)) => {
+                found = true;
+                lines.push(authority_line(patterns, &key)?);
+            }
+            (Some(_), None) => found = true,
+        }
+    }
+    if !found {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!("No trusted CA with fingerprint {}", fingerprint),
+        });
+    }
+    let mut rewritten = lines.join("\n");
+    if !rewritten.is_empty() {
+        rewritten.push('\n');
+    }
+    Ok(rewritten)
+}
+
+/// known_hosts pattern list semantics: a negated match excludes the host outright
+fn patterns_match(patterns: &[String], name: &str) -> bool {
+    let name = name.to_lowercase();
+    let mut matched = false;
+    for pattern in patterns {
+        let pattern = pattern.to_lowercase();
+        match pattern.strip_prefix('!') {
+            Some(negated) if glob_match(negated, &name) => return false,
+            Some(_) => {}
+            None => matched |= glob_match(&pattern, &name),
+        }
+    }
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CA_KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl ca@example";
+
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_patterns_match() {
+        let scope = patterns(&["*.example.com", "!bastion.example.com"]);
+        assert!(patterns_match(&scope, "web.example.com"));
+        assert!(patterns_match(&scope, "DB.Example.com"));
+        assert!(!patterns_match(&scope, "bastion.example.com"));
+        assert!(!patterns_match(&scope, "example.org"));
+        assert!(patterns_match(
+            &patterns(&["[*.example.com]:2222"]),
+            "[web.example.com]:2222"
+        ));
+    }
+
+    #[test]
+    fn test_parse_and_rewrite() {
+        let content = format!(
+            "github.com ssh-ed25519 AAAA\n@cert-authority *.example.com {}\n@revoked * ssh-rsa AAAA\n",
+            CA_KEY
+        );
+        let authorities = parse_cert_authorities(&content);
+        assert_eq!(authorities.len(), 1);
+        assert_eq!(authorities[0].patterns, patterns(&["*.example.com"]));
+        assert_eq!(authorities[0].key_type, "ssh-ed25519");
+        assert_eq!(authorities[0].comment, "ca@example");
+
+        let fingerprint = authorities[0].fingerprint.clone();
+        let scoped = patterns(&["*.corp", "!old.corp"]);
+        let rewritten = rewrite_authority(&content, &fingerprint, Some(&scoped)).unwrap();
+        assert_eq!(parse_cert_authorities(&rewritten)[0].patterns, scoped);
+        assert!(rewritten.starts_with("github.com ssh-ed25519 AAAA\n"));
+
+        let removed = rewrite_authority(&content, &fingerprint, None).unwrap();
+        assert!(parse_cert_authorities(&removed).is_empty());
+        assert_eq!(removed.lines().count(), 2);
+        assert!(rewrite_authority(&removed, &fingerprint, None).is_err());
+    }
+
+    #[test]
+    fn test_validate_patterns() {
+        assert!(validate_patterns(&patterns(&["*.example.com", "!db.example.com"])).is_ok());
+        assert!(validate_patterns(&patterns(&["!db.example.com"])).is_err());
+        assert!(validate_patterns(&patterns(&["a b"])).is_err());
+        assert!(validate_patterns(&patterns(&[])).is_err());
+    }
+}
@@ -0,0 +1,1408 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::onboarding_service::{OnboardingService, OnboardingStep};
+use crate::services::proxy_service::{ProxyService, ProxySettings};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::{HostMetadata, MetadataStore, RegistryService};
+use crate::services::revision_service::{ManagedFile, RevisionService};
+use crate::services::team_catalog::TeamCatalogService;
+use crate::services::trash_service::{DeletedKind, TrashService};
+use crate::utils::{
+    app_data_path, for_each_file_line, glob_match, ssh_config_path, unified_diff,
+    unquote_config_arg, write_atomic, IndexCache, Page, PageQuery, SshConfigEditor,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::{ControlFlow, Range};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tokio::fs;
+
+/// GSSAPI (Kerberos) options of a host
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GssapiOptions {
+    /// GSSAPIAuthentication (None = not set in the Host block)
+    pub authentication: Option<bool>,
+    /// GSSAPIDelegateCredentials (None = not set in the Host block)
+    pub delegate_credentials: Option<bool>,
+}
+
+/// Parameter of a host template
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateField {
+    /// Placeholder name used as `{{name}}` in directive values
+    pub name: String,
+    pub label: String,
+    pub default_value: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Directive written by a host template
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateDirective {
+    pub key: String,
+    /// May contain `{{field}}` placeholders; the directive is skipped if it renders empty
+    pub value: String,
+}
+
+/// Reusable host template
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Built-in templates can't be modified or deleted
+    #[serde(default)]
+    pub builtin: bool,
+    pub fields: Vec<TemplateField>,
+    pub directives: Vec<TemplateDirective>,
+    /// Tags added to the registry for hosts created from this template
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Host created from a template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedHost {
+    pub alias: String,
+    pub directives: Vec<TemplateDirective>,
+    pub tags: Vec<String>,
+}
+
+/// Which hosts a bulk update applies to (all given criteria must match)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostFilter {
+    /// Alias glob pattern, e.g. "web-*"
+    pub pattern: Option<String>,
+    /// Host must have at least one of these registry tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Explicit aliases
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// Kind of option change
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    /// Replace the option (or add it if missing)
+    Set,
+    /// Add another line for multi-value options (IdentityFile, LocalForward, ...)
+    Add,
+    /// Remove all lines of the option
+    Remove,
+}
+
+/// Single option change of a bulk update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionChange {
+    pub action: ChangeAction,
+    pub key: String,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+/// Bulk update result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUpdateResult {
+    pub matched_hosts: Vec<String>,
+    pub changed_hosts: Vec<String>,
+    /// Unified diff of the config file (empty if nothing changes)
+    pub diff: String,
+    /// False for a preview (dry run)
+    pub applied: bool,
+}
+
+/// One Host (or Match) block of the SSH config, as listed page by page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHostSummary {
+    /// 1-based line number of the Host line
+    pub line: u32,
+    /// Host patterns, or the criteria of a Match block
+    pub patterns: Vec<String>,
+    pub is_match: bool,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    /// Directives in the block
+    pub option_count: usize,
+}
+
+/// SSH config file service
+pub struct ConfigService;
+
+impl ConfigService {
+    /// Get SSH config file path
+    fn get_config_path() -> SshResult<PathBuf> {
+        ssh_config_path()
+    }
+
+    fn index_cache() -> &'static IndexCache {
+        static CACHE: OnceLock<IndexCache> = OnceLock::new();
+        CACHE.get_or_init(IndexCache::default)
+    }
+
+    /// A page of the config's Host/Match blocks, read by offset from an index of the
+    /// file; with a search, the file is streamed one block at a time
+    pub async fn list_hosts(query: &PageQuery) -> SshResult<Page<ConfigHostSummary>> {
+        let path = Self::get_config_path()?;
+        let mut page = Page {
+            items: Vec::new(),
+            offset: query.offset,
+            total: 0,
+            matched: 0,
+        };
+        if !path.exists() {
+            return Ok(page);
+        }
+        let index = Self::index_cache().get(&path, starts_block).await?;
+        page.total = index.len();
+        let range = query.offset..query.offset.saturating_add(query.limit());
+
+        match query.needle() {
+            None => {
+                page.matched = index.len();
+                for (record, text) in index.read_records(&path, range.start, range.len()).await? {
+                    page.items.push(summarize_block(record.line, &text));
+                }
+            }
+            Some(needle) => {
+                // Lines before the first Host line are global options, not a block
+                let mut block: Option<(u32, String)> = None;
+                for_each_file_line(&path, |line, _, text| {
+                    if starts_block(text) {
+                        if let Some((start, content)) = block.take() {
+                            collect_match(&mut page, &range, &needle, start, &content);
+                        }
+                        block = Some((line, String::new()));
+                    }
+                    if let Some((_, content)) = block.as_mut() {
+                        content.push_str(text);
+                        content.push('\n');
+                    }
+                    ControlFlow::Continue(())
+                })
+                .await?;
+                if let Some((start, content)) = block {
+                    collect_match(&mut page, &range, &needle, start, &content);
+                }
+            }
+        }
+        Ok(page)
+    }
+
+    /// Summaries of every Host/Match block, in file order, from one pass over the file
+    pub async fn host_summaries() -> SshResult<Vec<ConfigHostSummary>> {
+        let path = Self::get_config_path()?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut summaries = Vec::new();
+        let mut block: Option<(u32, String)> = None;
+        for_each_file_line(&path, |line, _, text| {
+            if starts_block(text) {
+                if let Some((start, content)) = block.take() {
+                    summaries.push(summarize_block(start, &content));
+                }
+                block = Some((line, String::new()));
+            }
+            if let Some((_, content)) = block.as_mut() {
+                content.push_str(text);
+                content.push('\n');
+            }
+            ControlFlow::Continue(())
+        })
+        .await?;
+        if let Some((start, content)) = block {
+            summaries.push(summarize_block(start, &content));
+        }
+        Ok(summaries)
+    }
+
+    /// Load the config into an editor (empty if the file doesn't exist)
+    pub async fn load_editor() -> SshResult<SshConfigEditor> {
+        let config_path = Self::get_config_path()?;
+        if !config_path.exists() {
+            return Ok(SshConfigEditor::parse(""));
+        }
+
+        let content =
+            fs::read_to_string(&config_path)
+                .await
+                .map_err(|e| SshBuddyError::IoError {
+                    message: format!("Failed to read SSH config: {}", e),
+                })?;
+        Ok(SshConfigEditor::parse(&content))
+    }
+
+    /// Write the editor back to disk; callers go through `update` or
+    /// `update_with_registry`, which hold the write lock
+    /// The previous file is kept as config.bak (same as the frontend writer)
+    async fn save_editor(editor: &SshConfigEditor) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("modify SSH config")?;
+        let config_path = Self::get_config_path()?;
+
+        let previous = if config_path.exists() {
+            let backup_path = config_path.with_file_name("config.bak");
+            fs::copy(&config_path, &backup_path)
+                .await
+                .map_err(|e| SshBuddyError::IoError {
+                    message: format!("Failed to back up SSH config: {}", e),
+                })?;
+            fs::read_to_string(&config_path).await.ok()
+        } else {
+            if let Some(parent) = config_path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            None
+        };
+
+        let content = editor.render();
+        write_atomic(&config_path, content.as_bytes()).await?;
+        RevisionService::record_change(
+            ManagedFile::SshConfig,
+            previous.as_deref(),
+            &content,
+            "Update SSH config",
+        )
+        .await;
+        tracing::info!("[config_service] Wrote SSH config: {:?}", config_path);
+        if !editor.host_aliases().is_empty() {
+            OnboardingService::complete(OnboardingStep::AddHost).await;
+        }
+        Ok(())
+    }
+
+    /// Serializes read-modify-write cycles of the SSH config
+    fn write_lock() -> &'static tokio::sync::Mutex<()> {
+        static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+    }
+
+    /// Load the config, apply `change` and write it back while other writers wait, so
+    /// nothing they write in between is lost; the file isn't touched when `change`
+    /// fails or leaves the config as it was
+    pub async fn update<T, F>(change: F) -> SshResult<T>
+    where
+        F: FnOnce(&mut SshConfigEditor) -> SshResult<T>,
+    {
+        let _guard = Self::write_lock().lock().await;
+        let original = Self::load_editor().await?;
+        let mut editor = original.clone();
+        let value = change(&mut editor)?;
+        if editor.render() != original.render() {
+            Self::save_editor(&editor).await?;
+        }
+        Ok(value)
+    }
+
+    /// `update`, followed by the matching registry change (given what `change`
+    /// returned); the config is rolled back if the registry update fails
+    pub async fn update_with_registry<T, F, R>(change: F, register: R) -> SshResult<T>
+    where
+        F: FnOnce(&mut SshConfigEditor) -> SshResult<T>,
+        R: FnOnce(&mut MetadataStore, &T) -> SshResult<()>,
+    {
+        let _guard = Self::write_lock().lock().await;
+        let original = Self::load_editor().await?;
+        let mut editor = original.clone();
+        let value = change(&mut editor)?;
+        Self::save_editor_with_registry(&editor, &original, |store| register(store, &value))
+            .await?;
+        Ok(value)
+    }
+
+    /// Write the editor, then apply the matching registry change; the config is
+    /// rolled back to `original` if the registry update fails so both stay in sync
+    /// (callers hold the write lock, so the rollback can't undo another change)
+    async fn save_editor_with_registry<F>(
+        editor: &SshConfigEditor,
+        original: &SshConfigEditor,
+        change: F,
+    ) -> SshResult<()>
+    where
+        F: FnOnce(&mut MetadataStore) -> SshResult<()>,
+    {
+        let changed = editor.render() != original.render();
+        if changed {
+            Self::save_editor(editor).await?;
+        }
+
+        if let Err(e) = RegistryService::update(change).await {
+            if changed {
+                tracing::error!(
+                    "[config_service] Registry update failed, reverting config: {}",
+                    e
+                );
+                Self::save_editor(original).await?;
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Append hosts to the config and tag each created host in the registry (used by
+    /// the importers); `append` returns the created aliases and the skipped entries
+    pub async fn create_hosts_with_tags<F>(
+        tag: &str,
+        append: F,
+    ) -> SshResult<(Vec<String>, Vec<String>)>
+    where
+        F: FnOnce(&mut SshConfigEditor) -> (Vec<String>, Vec<String>),
+    {
+        Self::update_with_registry(
+            |editor| Ok(append(editor)),
+            |store, (created, _)| {
+                for alias in created {
+                    let metadata = store
+                        .hosts
+                        .entry(alias.clone())
+                        .or_insert_with(HostMetadata::new);
+                    if !metadata.tags.iter().any(|t| t == tag) {
+                        metadata.tags.push(tag.to_string());
+                    }
+                }
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Remove a host from the config; its block is kept in the recently deleted list
+    /// when the trash is enabled
+    pub async fn delete_host(alias: &str) -> SshResult<()> {
+        let block = Self::update(|editor| {
+            let block = editor
+                .host_block(alias)
+                .ok_or_else(|| SshBuddyError::HostNotFound {
+                    alias: alias.to_string(),
+                })?;
+            editor.remove_host(alias);
+            Ok(block)
+        })
+        .await?;
+        TrashService::record(DeletedKind::Host, alias, Vec::new(), Some(block)).await;
+        tracing::info!("[config_service] Deleted host {}", alias);
+        Ok(())
+    }
+
+    /// Check a Host line and options from the host form: everything has to stay on
+    /// its own line, and Host/Match can't appear among the options
+    fn validate_host_form(host: &str, options: &[(String, String)]) -> SshResult<()> {
+        let invalid = |message: String| Err(SshBuddyError::InvalidOption { message });
+
+        if host.trim().is_empty() || host.chars().any(|c| c.is_control() || c == '#') {
+            return Err(SshBuddyError::InvalidHostAlias {
+                alias: host.to_string(),
+            });
+        }
+        for (key, value) in options {
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return invalid(format!("Invalid option name: {}", key));
+            }
+            if key.eq_ignore_ascii_case("host") || key.eq_ignore_ascii_case("match") {
+                return invalid(format!("{} can't be set as a host option", key));
+            }
+            if value.contains(['\n', '\r']) {
+                return invalid(format!("A single-line value is required for {}", key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Append a Host block from the host form
+    pub async fn add_host(host: &str, options: &[(String, String)]) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("create host")?;
+        Self::validate_host_form(host, options)?;
+        Self::update(|editor| {
+            if editor
+                .host_aliases()
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(host))
+            {
+                return Err(SshBuddyError::HostAlreadyExists {
+                    alias: host.to_string(),
+                });
+            }
+            editor.append_host(host, options);
+            Ok(())
+        })
+        .await?;
+        tracing::info!("[config_service] Added host {}", host);
+        Ok(())
+    }
+
+    /// Replace the Host line and options of a block with the host form's
+    /// (`original` is the Host line being edited)
+    pub async fn update_host(
+        original: &str,
+        host: &str,
+        options: &[(String, String)],
+    ) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("modify host")?;
+        Self::validate_host_form(host, options)?;
+        Self::update(|editor| {
+            if host != original && editor.has_host(host) {
+                return Err(SshBuddyError::HostAlreadyExists {
+                    alias: host.to_string(),
+                });
+            }
+            if !editor.replace_host(original, host, options) {
+                return Err(SshBuddyError::HostNotFound {
+                    alias: original.to_string(),
+                });
+            }
+            Ok(())
+        })
+        .await?;
+        tracing::info!("[config_service] Updated host {}", original);
+        Ok(())
+    }
+
+    /// Parse a yes/no config value
+    fn parse_yes_no(value: Option<String>) -> Option<bool> {
+        match value?.to_lowercase().as_str() {
+            "yes" | "true" => Some(true),
+            "no" | "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Apply a yes/no option to a host (None removes the option)
+    fn apply_yes_no(editor: &mut SshConfigEditor, alias: &str, key: &str, value: Option<bool>) {
+        match value {
+            Some(enabled) => {
+                editor.set_option(alias, key, if enabled { "yes" } else { "no" });
+            }
+            None => {
+                editor.remove_option(alias, key);
+            }
+        }
+    }
+
+    /// Read GSSAPI options from a host's block
+    fn read_gssapi_options(editor: &SshConfigEditor, alias: &str) -> GssapiOptions {
+        GssapiOptions {
+            authentication: Self::parse_yes_no(editor.get_option(alias, "GSSAPIAuthentication")),
+            delegate_credentials: Self::parse_yes_no(
+                editor.get_option(alias, "GSSAPIDelegateCredentials"),
+            ),
+        }
+    }
+
+    /// Get GSSAPI options of a host
+    pub async fn get_gssapi_options(alias: &str) -> SshResult<GssapiOptions> {
+        let editor = Self::load_editor().await?;
+        if !editor.has_host(alias) {
+            return Err(SshBuddyError::HostNotFound {
+                alias: alias.to_string(),
+            });
+        }
+        Ok(Self::read_gssapi_options(&editor, alias))
+    }
+
+    /// Set GSSAPI options of a host
+    pub async fn set_gssapi_options(
+        alias: &str,
+        options: &GssapiOptions,
+    ) -> SshResult<GssapiOptions> {
+        TeamCatalogService::ensure_editable(alias).await?;
+        Self::update(|editor| {
+            if !editor.has_host(alias) {
+                return Err(SshBuddyError::HostNotFound {
+                    alias: alias.to_string(),
+                });
+            }
+
+            Self::apply_yes_no(
+                editor,
+                alias,
+                "GSSAPIAuthentication",
+                options.authentication,
+            );
+            Self::apply_yes_no(
+                editor,
+                alias,
+                "GSSAPIDelegateCredentials",
+                options.delegate_credentials,
+            );
+            Ok(Self::read_gssapi_options(editor, alias))
+        })
+        .await
+    }
+
+    /// Check that a string can be used as a Host alias
+    pub fn validate_alias(alias: &str) -> SshResult<()> {
+        let invalid = alias.is_empty()
+            || alias.chars().any(|c| {
+                c.is_whitespace() || c.is_control() || matches!(c, '*' | '?' | '!' | '#' | '"')
+            });
+        if invalid {
+            return Err(SshBuddyError::InvalidHostAlias {
+                alias: alias.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Get user templates file path
+    fn get_templates_path() -> SshResult<PathBuf> {
+        app_data_path("host_templates.json")
+    }
+
+    /// Built-in host templates
+    fn builtin_templates() -> Vec<HostTemplate> {
+        let field =
+            |name: &str, label: &str, default_value: Option<&str>, required: bool| TemplateField {
+                name: name.to_string(),
+                label: label.to_string(),
+                default_value: default_value.map(|v| v.to_string()),
+                required,
+            };
+        let directive = |key: &str, value: &str| TemplateDirective {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+
+        vec![
+            HostTemplate {
+                id: "generic".to_string(),
+                name: "Generic server".to_string(),
+                description: "Plain SSH server".to_string(),
+                builtin: true,
+                fields: vec![
+                    field("host", "Hostname or IP", None, true),
+                    field("user", "User", None, false),
+                    field("port", "Port", None, false),
+                    field("identity_file", "Identity file", None, false),
+                ],
+                directives: vec![
+                    directive("HostName", "{{host}}"),
+                    directive("User", "{{user}}"),
+                    directive("Port", "{{port}}"),
+                    directive("IdentityFile", "{{identity_file}}"),
+                ],
+                tags: Vec::new(),
+            },
+            HostTemplate {
+                id: "kubernetes-node".to_string(),
+                name: "Kubernetes node".to_string(),
+                description: "Cluster node, optionally reached through a bastion".to_string(),
+                builtin: true,
+                fields: vec![
+                    field("host", "Node address", None, true),
+                    field("user", "User", Some("ubuntu"), true),
+                    field("identity_file", "Identity file", None, false),
+                    field("bastion", "Bastion host (ProxyJump)", None, false),
+                ],
+                directives: vec![
+                    directive("HostName", "{{host}}"),
+                    directive("User", "{{user}}"),
+                    directive("IdentityFile", "{{identity_file}}"),
+                    directive("ProxyJump", "{{bastion}}"),
+                    directive("ServerAliveInterval", "30"),
+                ],
+                tags: vec!["kubernetes".to_string()],
+            },
+            HostTemplate {
+                id: "raspberry-pi".to_string(),
+                name: "Raspberry Pi".to_string(),
+                description: "Raspberry Pi on the local network".to_string(),
+                builtin: true,
+                fields: vec![
+                    field("host", "Hostname or IP", Some("raspberrypi.local"), true),
+                    field("user", "User", Some("pi"), true),
+                    field("identity_file", "Identity file", None, false),
+                ],
+                directives: vec![
+                    directive("HostName", "{{host}}"),
+                    directive("User", "{{user}}"),
+                    directive("IdentityFile", "{{identity_file}}"),
+                    // Pis get re-imaged often, which changes the host key
+                    directive("StrictHostKeyChecking", "accept-new"),
+                ],
+                tags: vec!["raspberry-pi".to_string()],
+            },
+        ]
+    }
+
+    /// Load user-defined templates
+    async fn load_user_templates() -> SshResult<Vec<HostTemplate>> {
+        let path = Self::get_templates_path()?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to read host templates: {}", e),
+            })?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse host templates: {}", e),
+        })
+    }
+
+    /// Save user-defined templates
+    async fn save_user_templates(templates: &[HostTemplate]) -> SshResult<()> {
+        let path = Self::get_templates_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content =
+            serde_json::to_string_pretty(templates).map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// List built-in and user-defined templates
+    pub async fn list_templates() -> SshResult<Vec<HostTemplate>> {
+        let mut templates = Self::builtin_templates();
+        templates.extend(Self::load_user_templates().await?);
+        Ok(templates)
+    }
+
+    /// Create or replace a user-defined template
+    pub async fn save_template(mut template: HostTemplate) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("save host template")?;
+        if Self::builtin_templates()
+            .iter()
+            .any(|t| t.id == template.id)
+        {
+            return Err(SshBuddyError::InvalidTemplate {
+                message: format!("'{}' is a built-in template", template.id),
+            });
+        }
+        Self::validate_template(&template)?;
+        template.builtin = false;
+
+        let mut templates = Self::load_user_templates().await?;
+        match templates.iter_mut().find(|t| t.id == template.id) {
+            Some(existing) => *existing = template,
+            None => templates.push(template),
+        }
+        Self::save_user_templates(&templates).await
+    }
+
+    /// Delete a user-defined template
+    pub async fn delete_template(template_id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete host template")?;
+        let mut templates = Self::load_user_templates().await?;
+        let before = templates.len();
+        templates.retain(|t| t.id != template_id);
+        if templates.len() == before {
+            return Err(SshBuddyError::InvalidTemplate {
+                message: format!("Template not found: {}", template_id),
+            });
+        }
+        Self::save_user_templates(&templates).await
+    }
+
+    /// Check that a template is well-formed
+    fn validate_template(template: &HostTemplate) -> SshResult<()> {
+        let invalid = |message: String| Err(SshBuddyError::InvalidTemplate { message });
+
+        if template.id.trim().is_empty() || template.name.trim().is_empty() {
+            return invalid("Template id and name are required".to_string());
+        }
+        for directive in &template.directives {
+            if directive.key.is_empty() || !directive.key.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return invalid(format!("Invalid directive: {}", directive.key));
+            }
+            for placeholder in placeholders(&directive.value) {
+                if !template.fields.iter().any(|f| f.name == placeholder) {
+                    return invalid(format!("Unknown placeholder: {{{{{}}}}}", placeholder));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill a template's placeholders; directives that render empty are dropped
+    fn render_template(
+        template: &HostTemplate,
+        params: &HashMap<String, String>,
+    ) -> SshResult<Vec<TemplateDirective>> {
+        Self::validate_template(template)?;
+
+        let mut values = HashMap::new();
+        for field in &template.fields {
+            let value = params
+                .get(&field.name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .or_else(|| field.default_value.clone())
+                .unwrap_or_default();
+
+            if value.contains(['\n', '\r']) {
+                return Err(SshBuddyError::InvalidTemplate {
+                    message: format!("{} must be a single line", field.label),
+                });
+            }
+            if field.required && value.is_empty() {
+                return Err(SshBuddyError::InvalidTemplate {
+                    message: format!("{} is required", field.label),
+                });
+            }
+            values.insert(field.name.as_str(), value);
+        }
+
+        let mut directives = Vec::new();
+        for directive in &template.directives {
+            let mut value = directive.value.clone();
+            for (name, field_value) in &values {
+                value = value.replace(&format!("{{{{{}}}}}", name), field_value);
+            }
+            let value = value.trim();
+            if !value.is_empty() {
+                directives.push(TemplateDirective {
+                    key: directive.key.clone(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        Ok(directives)
+    }
+
+    /// Create a Host block from a template and register its metadata
+    pub async fn create_host_from_template(
+        template_id: &str,
+        alias: &str,
+        params: &HashMap<String, String>,
+        extra_tags: &[String],
+    ) -> SshResult<CreatedHost> {
+        ReadOnlyMode::ensure_writable("create host")?;
+        Self::validate_alias(alias)?;
+
+        let template = Self::list_templates()
+            .await?
+            .into_iter()
+            .find(|t| t.id == template_id)
+            .ok_or_else(|| SshBuddyError::InvalidTemplate {
+                message: format!("Template not found: {}", template_id),
+            })?;
+        let directives = Self::render_template(&template, params)?;
+        let options: Vec<(String, String)> = directives
+            .iter()
+            .map(|d| (d.key.clone(), d.value.clone()))
+            .collect();
+
+        let mut tags = template.tags.clone();
+        for tag in extra_tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+
+        Self::update_with_registry(
+            |editor| {
+                if editor.has_host(alias) {
+                    return Err(SshBuddyError::HostAlreadyExists {
+                        alias: alias.to_string(),
+                    });
+                }
+                editor.append_host(alias, &options);
+                Ok(())
+            },
+            |store, _| {
+                let mut metadata = HostMetadata::new();
+                metadata.tags = tags.clone();
+                store.hosts.insert(alias.to_string(), metadata);
+                Ok(())
+            },
+        )
+        .await?;
+
+        tracing::info!(
+            "[config_service] Created host {} from template {}",
+            alias,
+            template_id
+        );
+        Ok(CreatedHost {
+            alias: alias.to_string(),
+            directives,
+            tags,
+        })
+    }
+
+    /// Check that an option change is well-formed
+    fn validate_change(change: &OptionChange) -> SshResult<()> {
+        let invalid = |message: String| Err(SshBuddyError::InvalidOption { message });
+
+        if change.key.is_empty() || !change.key.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return invalid(format!("Invalid option name: {}", change.key));
+        }
+        if change.key.eq_ignore_ascii_case("host") || change.key.eq_ignore_ascii_case("match") {
+            return invalid(format!("{} can't be changed in bulk", change.key));
+        }
+        match (&change.action, change.value.as_deref().map(str::trim)) {
+            (ChangeAction::Remove, _) => Ok(()),
+            (_, Some(value)) if !value.is_empty() && !value.contains(['\n', '\r']) => Ok(()),
+            _ => invalid(format!(
+                "A single-line value is required for {}",
+                change.key
+            )),
+        }
+    }
+
+    /// Apply option changes to the matching hosts of an editor (locked hosts never match)
+    /// Returns (matched, changed) aliases
+    fn apply_bulk_changes(
+        editor: &mut SshConfigEditor,
+        filter: &HostFilter,
+        host_tags: &HashMap<String, Vec<String>>,
+        locked: &HashSet<String>,
+        changes: &[OptionChange],
+    ) -> (Vec<String>, Vec<String>) {
+        let matched: Vec<String> = editor
+            .host_aliases()
+            .into_iter()
+            .filter(|alias| !locked.contains(alias))
+            .filter(|alias| {
+                let pattern_ok = filter
+                    .pattern
+                    .as_deref()
+                    .map_or(true, |pattern| glob_match(pattern, alias));
+                let tags_ok = filter.tags.is_empty()
+                    || host_tags
+                        .get(alias)
+                        .is_some_and(|tags| tags.iter().any(|t| filter.tags.contains(t)));
+                let alias_ok = filter.aliases.is_empty() || filter.aliases.contains(alias);
+                pattern_ok && tags_ok && alias_ok
+            })
+            .collect();
+
+        let mut changed = Vec::new();
+        for alias in &matched {
+            let before = editor.render();
+            for change in changes {
+                let value = change.value.as_deref().unwrap_or_default().trim();
+                match change.action {
+                    ChangeAction::Set => {
+                        editor.set_option(alias, &change.key, value);
+                    }
+                    ChangeAction::Add => {
+                        editor.add_option(alias, &change.key, value);
+                    }
+                    ChangeAction::Remove => {
+                        editor.remove_option(alias, &change.key);
+                    }
+                }
+            }
+            if editor.render() != before {
+                changed.push(alias.clone());
+            }
+        }
+
+        (matched, changed)
+    }
+
+    /// Apply option changes to all hosts matching the filter
+    /// With `dry_run` only the diff is computed; otherwise the config is written (with backup)
+    pub async fn bulk_update_hosts(
+        filter: &HostFilter,
+        changes: &[OptionChange],
+        dry_run: bool,
+    ) -> SshResult<BulkUpdateResult> {
+        if !dry_run {
+            ReadOnlyMode::ensure_writable("bulk update hosts")?;
+        }
+        for change in changes {
+            Self::validate_change(change)?;
+        }
+
+        let host_tags: HashMap<String, Vec<String>> = if filter.tags.is_empty() {
+            HashMap::new()
+        } else {
+            RegistryService::load()
+                .await?
+                .hosts
+                .into_iter()
+                .map(|(alias, metadata)| (alias, metadata.tags))
+                .collect()
+        };
+
+        // Catalog hosts are read-only
+        let locked = TeamCatalogService::locked_aliases().await?;
+
+        let apply = |editor: &mut SshConfigEditor| {
+            let original = editor.render();
+            let (matched_hosts, changed_hosts) =
+                Self::apply_bulk_changes(editor, filter, &host_tags, &locked, changes);
+            let diff = unified_diff(
+                &original,
+                &editor.render(),
+                "~/.ssh/config",
+                "~/.ssh/config (updated)",
+                3,
+            );
+            (matched_hosts, changed_hosts, diff)
+        };
+        let (matched_hosts, changed_hosts, diff) = if dry_run {
+            apply(&mut Self::load_editor().await?)
+        } else {
+            Self::update(|editor| Ok(apply(editor))).await?
+        };
+
+        let applied = !dry_run && !changed_hosts.is_empty();
+        if applied {
+            tracing::info!(
+                "[config_service] Bulk update changed {} host(s)",
+                changed_hosts.len()
+            );
+        }
+
+        Ok(BulkUpdateResult {
+            matched_hosts,
+            changed_hosts,
+            diff,
+            applied,
+        })
+    }
+
+    /// Get the proxy of a host (from a netcat-style ProxyCommand)
+    pub async fn get_host_proxy(alias: &str) -> SshResult<Option<ProxySettings>> {
+        let editor = Self::load_editor().await?;
+        if !editor.has_host(alias) {
+            return Err(SshBuddyError::HostNotFound {
+                alias: alias.to_string(),
+            });
+        }
+        Ok(editor
+            .get_option(alias, "ProxyCommand")
+            .and_then(|command| ProxyService::from_proxy_command(&command)))
+    }
+
+    /// Set the proxy of a host by writing an equivalent ProxyCommand (None removes it)
+    /// Other ProxyCommand values (e.g. `ssh -W`) are only replaced when a proxy is set
+    pub async fn set_host_proxy(alias: &str, proxy: Option<&ProxySettings>) -> SshResult<()> {
+        TeamCatalogService::ensure_editable(alias).await?;
+        Self::update(|editor| {
+            if !editor.has_host(alias) {
+                return Err(SshBuddyError::HostNotFound {
+                    alias: alias.to_string(),
+                });
+            }
+            Self::apply_proxy(editor, alias, proxy);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Apply a proxy to a host's ProxyCommand
+    fn apply_proxy(editor: &mut SshConfigEditor, alias: &str, proxy: Option<&ProxySettings>) {
+        match proxy {
+            Some(proxy) => {
+                editor.set_option(
+                    alias,
+                    "ProxyCommand",
+                    &ProxyService::to_proxy_command(proxy),
+                );
+            }
+            None => {
+                let is_proxy_command = editor
+                    .get_option(alias, "ProxyCommand")
+                    .is_some_and(|command| ProxyService::from_proxy_command(&command).is_some());
+                if is_proxy_command {
+                    editor.remove_option(alias, "ProxyCommand");
+                }
+            }
+        }
+    }
+}
+
+/// Names of the `{{name}}` placeholders in a value
+/// Split a config line into its lowercased keyword and value
+fn directive(line: &str) -> Option<(String, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let split = line.find(|c: char| c == '=' || c.is_whitespace())?;
+    let value = line[split..].trim_start_matches(|c: char| c == '=' || c.is_whitespace());
+    Some((line[..split].to_lowercase(), value.trim_end()))
+}
+
+/// Host and Match lines start a block
+fn starts_block(line: &str) -> bool {
+    matches!(directive(line), Some((keyword, _)) if keyword == "host" || keyword == "match")
+}
+
+fn summarize_block(line: u32, text: &str) -> ConfigHostSummary {
+    let mut summary = ConfigHostSummary {
+        line,
+        patterns: Vec::new(),
+        is_match: false,
+        hostname: None,
+        user: None,
+        port: None,
+        identity_file: None,
+        option_count: 0,
+    };
+    let mut directives = text.lines().filter_map(directive);
+    if let Some((keyword, value)) = directives.next() {
+        summary.is_match = keyword == "match";
+        summary.patterns = value.split_whitespace().map(str::to_string).collect();
+    }
+    for (keyword, value) in directives {
+        summary.option_count += 1;
+        // The first value of a directive is the one ssh uses
+        match keyword.as_str() {
+            "hostname" if summary.hostname.is_none() => summary.hostname = Some(value.to_string()),
+            "user" if summary.user.is_none() => summary.user = Some(value.to_string()),
+            "port" if summary.port.is_none() => summary.port = value.parse().ok(),
+            "identityfile" if summary.identity_file.is_none() => {
+                summary.identity_file = Some(unquote_config_arg(value))
+            }
+            _ => {}
+        }
+    }
+    summary
+}
+
+/// Count a block matching the search and keep it when it falls in the page
+fn collect_match(
+    page: &mut Page<ConfigHostSummary>,
+    range: &Range<usize>,
+    needle: &str,
+    line: u32,
+    text: &str,
+) {
+    if !text.to_lowercase().contains(needle) {
+        return;
+    }
+    if range.contains(&page.matched) {
+        page.items.push(summarize_block(line, text));
+    }
+    page.matched += 1;
+}
+
+fn placeholders(value: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                names.push(after[..end].trim());
+                rest = &after[end + 2..];
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_block() {
+        assert!(starts_block("Host web"));
+        assert!(starts_block("  host=db"));
+        assert!(starts_block("Match host *.corp exec true"));
+        assert!(!starts_block("HostName web.example.com"));
+        assert!(!starts_block("# Host old"));
+
+        let summary = summarize_block(
+            7,
+            "Host web web-alias\n  HostName=web.example.com\n  Port 2222\n  # note\n  User deploy\n  User ignored\n\n",
+        );
+        assert_eq!(summary.line, 7);
+        assert_eq!(summary.patterns, vec!["web", "web-alias"]);
+        assert!(!summary.is_match);
+        assert_eq!(summary.hostname.as_deref(), Some("web.example.com"));
+        assert_eq!(summary.port, Some(2222));
+        assert_eq!(summary.user.as_deref(), Some("deploy"));
+        assert_eq!(summary.option_count, 4);
+
+        assert!(summarize_block(1, "Match host *.corp\n").is_match);
+
+        let summary = summarize_block(
+            1,
+            "Host 開發機\n  IdentityFile \"C:\\Users\\王 小明\\.ssh\\id 🔑\"\n",
+        );
+        assert_eq!(summary.patterns, vec!["開發機"]);
+        assert_eq!(
+            summary.identity_file.as_deref(),
+            Some(r"C:\Users\王 小明\.ssh\id 🔑")
+        );
+    }
+
+    #[test]
+    fn test_collect_match_pages() {
+        let mut page = Page {
+            items: Vec::new(),
+            offset: 1,
+            total: 3,
+            matched: 0,
+        };
+        for (line, text) in [
+            (1, "Host web1\n"),
+            (3, "Host db\n"),
+            (5, "Host web2\n"),
+            (7, "Host web3\n"),
+        ] {
+            collect_match(&mut page, &(1..2), "web", line, text);
+        }
+        assert_eq!(page.matched, 3);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].patterns, vec!["web2"]);
+    }
+
+    #[test]
+    fn test_parse_yes_no() {
+        assert_eq!(
+            ConfigService::parse_yes_no(Some("yes".to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            ConfigService::parse_yes_no(Some("No".to_string())),
+            Some(false)
+        );
+        assert_eq!(ConfigService::parse_yes_no(Some("maybe".to_string())), None);
+        assert_eq!(ConfigService::parse_yes_no(None), None);
+    }
+
+    #[test]
+    fn test_apply_gssapi_options() {
+        let mut editor = SshConfigEditor::parse(
+            "Host corp\n    HostName corp.example.com\n    GSSAPIDelegateCredentials yes\n",
+        );
+
+        ConfigService::apply_yes_no(&mut editor, "corp", "GSSAPIAuthentication", Some(true));
+        ConfigService::apply_yes_no(&mut editor, "corp", "GSSAPIDelegateCredentials", None);
+
+        assert_eq!(
+            ConfigService::read_gssapi_options(&editor, "corp"),
+            GssapiOptions {
+                authentication: Some(true),
+                delegate_credentials: None,
+            }
+        );
+        assert_eq!(
+            editor.render(),
+            "Host corp\n    HostName corp.example.com\n    GSSAPIAuthentication yes\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_proxy() {
+        use crate::services::proxy_service::ProxyType;
+
+        let mut editor = SshConfigEditor::parse(
+            "Host corp\n    HostName corp.example.com\n\nHost jumped\n    ProxyCommand ssh -W %h:%p bastion\n",
+        );
+        let proxy = ProxySettings {
+            proxy_type: ProxyType::Socks5,
+            host: "127.0.0.1".to_string(),
+            port: 1080,
+            username: None,
+            password: None,
+        };
+
+        ConfigService::apply_proxy(&mut editor, "corp", Some(&proxy));
+        assert_eq!(
+            editor.get_option("corp", "ProxyCommand").as_deref(),
+            Some("nc -X 5 -x 127.0.0.1:1080 %h %p")
+        );
+
+        ConfigService::apply_proxy(&mut editor, "corp", None);
+        assert_eq!(editor.get_option("corp", "ProxyCommand"), None);
+
+        // Unrelated ProxyCommand is kept when clearing
+        ConfigService::apply_proxy(&mut editor, "jumped", None);
+        assert_eq!(
+            editor.get_option("jumped", "ProxyCommand").as_deref(),
+            Some("ssh -W %h:%p bastion")
+        );
+    }
+
+    #[test]
+    fn test_validate_alias() {
+        assert!(ConfigService::validate_alias("web-01").is_ok());
+        assert!(ConfigService::validate_alias("").is_err());
+        assert!(ConfigService::validate_alias("two words").is_err());
+        assert!(ConfigService::validate_alias("web-*").is_err());
+    }
+
+    #[test]
+    fn test_render_builtin_template() {
+        let template = ConfigService::builtin_templates()
+            .into_iter()
+            .find(|t| t.id == "kubernetes-node")
+            .unwrap();
+        let params = HashMap::from([("host".to_string(), "10.0.0.12".to_string())]);
+
+        let directives = ConfigService::render_template(&template, &params).unwrap();
+        let rendered: Vec<(&str, &str)> = directives
+            .iter()
+            .map(|d| (d.key.as_str(), d.value.as_str()))
+            .collect();
+        // Empty optional fields drop their directives, defaults fill the rest
+        assert_eq!(
+            rendered,
+            vec![
+                ("HostName", "10.0.0.12"),
+                ("User", "ubuntu"),
+                ("ServerAliveInterval", "30"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_template_errors() {
+        let template = ConfigService::builtin_templates()
+            .into_iter()
+            .find(|t| t.id == "generic")
+            .unwrap();
+
+        // Missing required field
+        assert!(ConfigService::render_template(&template, &HashMap::new()).is_err());
+
+        // Newlines would inject extra directives
+        let params = HashMap::from([("host".to_string(), "a\n    ProxyCommand evil".to_string())]);
+        assert!(ConfigService::render_template(&template, &params).is_err());
+    }
+
+    #[test]
+    fn test_validate_template_unknown_placeholder() {
+        let mut template = ConfigService::builtin_templates().remove(0);
+        template.directives.push(TemplateDirective {
+            key: "User".to_string(),
+            value: "{{username}}".to_string(),
+        });
+        assert!(ConfigService::validate_template(&template).is_err());
+        assert_eq!(placeholders("{{a}}-{{ b }}"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_bulk_changes() {
+        let mut editor = SshConfigEditor::parse(
+            "Host web-1\n    User root\n\nHost web-2\n    User deploy\n\nHost db-1\n    User root\n",
+        );
+        let filter = HostFilter {
+            pattern: Some("web-*".to_string()),
+            ..Default::default()
+        };
+        let changes = vec![
+            OptionChange {
+                action: ChangeAction::Set,
+                key: "User".to_string(),
+                value: Some("deploy".to_string()),
+            },
+            OptionChange {
+                action: ChangeAction::Add,
+                key: "IdentityFile".to_string(),
+                value: Some("~/.ssh/web".to_string()),
+            },
+        ];
+
+        let (matched, changed) = ConfigService::apply_bulk_changes(
+            &mut editor,
+            &filter,
+            &HashMap::new(),
+            &HashSet::new(),
+            &changes,
+        );
+        assert_eq!(matched, vec!["web-1", "web-2"]);
+        assert_eq!(changed, vec!["web-1", "web-2"]);
+        assert_eq!(
+            editor.get_option("web-1", "User").as_deref(),
+            Some("deploy")
+        );
+        assert_eq!(
+            editor.get_option("web-2", "IdentityFile").as_deref(),
+            Some("~/.ssh/web")
+        );
+        assert_eq!(editor.get_option("db-1", "User").as_deref(), Some("root"));
+    }
+
+    #[test]
+    fn test_bulk_changes_tag_filter() {
+        let mut editor = SshConfigEditor::parse("Host a\n    User x\n\nHost b\n    User x\n");
+        let filter = HostFilter {
+            tags: vec!["prod".to_string()],
+            ..Default::default()
+        };
+        let host_tags = HashMap::from([("b".to_string(), vec!["prod".to_string()])]);
+        let changes = vec![OptionChange {
+            action: ChangeAction::Remove,
+            key: "User".to_string(),
+            value: None,
+        }];
+
+        let (matched, changed) = ConfigService::apply_bulk_changes(
+            &mut editor,
+            &filter,
+            &host_tags,
+            &HashSet::new(),
+            &changes,
+        );
+        assert_eq!(matched, vec!["b"]);
+        assert_eq!(changed, vec!["b"]);
+        assert_eq!(editor.get_option("a", "User").as_deref(), Some("x"));
+        assert_eq!(editor.get_option("b", "User"), None);
+    }
+
+    #[test]
+    fn test_bulk_changes_skip_locked() {
+        let mut editor = SshConfigEditor::parse("Host a\n    User x\n\nHost team\n    User x\n");
+        let locked = HashSet::from(["team".to_string()]);
+        let changes = vec![OptionChange {
+            action: ChangeAction::Set,
+            key: "User".to_string(),
+            value: Some("y".to_string()),
+        }];
+
+        let (matched, _) = ConfigService::apply_bulk_changes(
+            &mut editor,
+            &HostFilter::default(),
+            &HashMap::new(),
+            &locked,
+            &changes,
+        );
+        assert_eq!(matched, vec!["a"]);
+        assert_eq!(editor.get_option("team", "User").as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_validate_change() {
+        let change = |action, key: &str, value: Option<&str>| OptionChange {
+            action,
+            key: key.to_string(),
+            value: value.map(|v| v.to_string()),
+        };
+        assert!(
+            ConfigService::validate_change(&change(ChangeAction::Set, "User", Some("git"))).is_ok()
+        );
+        assert!(
+            ConfigService::validate_change(&change(ChangeAction::Remove, "User", None)).is_ok()
+        );
+        assert!(ConfigService::validate_change(&change(ChangeAction::Set, "User", None)).is_err());
+        assert!(
+            ConfigService::validate_change(&change(ChangeAction::Set, "Host", Some("x"))).is_err()
+        );
+        assert!(ConfigService::validate_change(&change(
+            ChangeAction::Add,
+            "User",
+            Some("a\nHost evil")
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::models::{LocalizedMessage, SshResult};
+use crate::services::config_service::{ChangeAction, ConfigService, HostFilter, OptionChange};
+use crate::services::port_scan::PortScanService;
+use crate::services::ssh_connection::{ConnectionTestResult, SshConnectionService, SshErrorType};
+use crate::services::team_catalog::TeamCatalogService;
+use serde::{Deserialize, Serialize};
+
+/// Cloud images and the login user they are built with, matched against the server banner
+const CLOUD_IMAGE_USERS: &[(&str, &str, &str)] = &[
+    ("ubuntu", "Ubuntu", "ubuntu"),
+    ("debian", "Debian", "admin"),
+    ("freebsd", "FreeBSD", "freebsd"),
+];
+
+/// What a suggestion changes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SuggestionKind {
+    User,
+    Port,
+    HostKeyAlias,
+}
+
+/// Config patch that would likely fix a failed connection test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSuggestion {
+    pub alias: String,
+    pub kind: SuggestionKind,
+    /// Catalog key `suggestion.<kind>` explaining why
+    #[serde(flatten)]
+    pub reason: LocalizedMessage,
+    pub changes: Vec<OptionChange>,
+    /// Unified diff of the config file
+    pub diff: String,
+}
+
+/// Turns recognizable connection failures into config patches
+pub struct ConfigSuggestionService;
+
+impl ConfigSuggestionService {
+    /// Suggestions for a failed connection test (empty if nothing is recognized)
+    pub async fn suggest(
+        alias: &str,
+        result: &ConnectionTestResult,
+    ) -> SshResult<Vec<ConfigSuggestion>> {
+        let Some(error_type) = result.error_type.as_ref() else {
+            return Ok(Vec::new());
+        };
+        if result.success || TeamCatalogService::ensure_editable(alias).await.is_err() {
+            return Ok(Vec::new());
+        }
+        let host = SshConnectionService::resolve_host(alias).await?;
+
+        let mut candidates = Vec::new();
+        match error_type {
+            SshErrorType::PermissionDenied
+            | SshErrorType::PermissionDeniedWrongKey
+            | SshErrorType::PermissionDeniedAuthMethod => {
+                let banner = result.handshake.as_ref().map(|h| h.server_banner.as_str());
+                let user = host
+                    .get_user()
+                    .map(str::to_string)
+                    .unwrap_or_else(whoami::username);
+                if let Some((suggested, reason)) = suggest_user(
+                    result.platform.as_deref(),
+                    banner,
+                    &user,
+                    &whoami::username(),
+                ) {
+                    candidates.push((SuggestionKind::User, set_change("User", &suggested), reason));
+                }
+            }
+            SshErrorType::ConnectionRefused | SshErrorType::Timeout => {
+                let port = host.get_port();
+                match PortScanService::scan_ssh_ports(host.get_hostname(), &[]).await {
+                    Ok(scan) => {
+                        if let Some(found) = scan.ssh_port.filter(|p| *p != port) {
+                            candidates.push((
+                                SuggestionKind::Port,
+                                set_change("Port", &found.to_string()),
+                                LocalizedMessage::new("suggestion.port")
+                                    .with("port", port)
+                                    .with("found", found),
+                            ));
+                        }
+                    }
+                    Err(e) => tracing::warn!("[suggestions] Port scan failed for {}: {}", alias, e),
+                }
+            }
+            SshErrorType::HostKeyUnknown | SshErrorType::HostKeyChanged => {
+                let known_as = result
+                    .error_details
+                    .as_ref()
+                    .and_then(|d| d.fix_params.as_ref())
+                    .and_then(|p| p.get("knownAs"));
+                if let (Some(known_as), false) =
+                    (known_as, host.options.contains_key("hostkeyalias"))
+                {
+                    let names: Vec<&str> = known_as.split(',').collect();
+                    if let Some(name) = host_key_alias_for(&names, host.get_port()) {
+                        candidates.push((
+                            SuggestionKind::HostKeyAlias,
+                            set_change("HostKeyAlias", &name),
+                            LocalizedMessage::new("suggestion.hostKeyAlias").with("name", &name),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let filter = Self::filter(alias);
+        let mut suggestions = Vec::new();
+        for (kind, changes, reason) in candidates {
+            let preview = ConfigService::bulk_update_hosts(&filter, &changes, true).await?;
+            if preview.diff.is_empty() {
+                continue;
+            }
+            suggestions.push(ConfigSuggestion {
+                alias: alias.to_string(),
+                kind,
+                reason,
+                changes,
+                diff: preview.diff,
+            });
+        }
+        if !suggestions.is_empty() {
+            tracing::info!(
+                "[suggestions] {} config fix(es) for {}",
+                suggestions.len(),
+                alias
+            );
+        }
+        Ok(suggestions)
+    }
+
+    /// Write a suggested patch to the config
+    pub async fn apply(suggestion: &ConfigSuggestion) -> SshResult<()> {
+        TeamCatalogService::ensure_editable(&suggestion.alias).await?;
+        let result = ConfigService::bulk_update_hosts(
+            &Self::filter(&suggestion.alias),
+            &suggestion.changes,
+            false,
+        )
+        .await?;
+        tracing::info!(
+            "[suggestions] Applied {:?} fix to {} (changed: {})",
+            suggestion.kind,
+            suggestion.alias,
+            !result.changed_hosts.is_empty()
+        );
+        Ok(())
+    }
+
+    fn filter(alias: &str) -> HostFilter {
+        HostFilter {
+            aliases: vec![alias.to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+fn set_change(key: &str, value: &str) -> Vec<OptionChange> {
+    vec![OptionChange {
+        action: ChangeAction::Set,
+        key: key.to_string(),
+        value: Some(value.to_string()),
+    }]
+}
+
+/// Login user the server most likely expects instead of `user`
+/// Cloud image users are only suggested when `user` looks like a default
+/// (root or the local account), never over a deliberately configured one
+fn suggest_user(
+    platform: Option<&str>,
+    banner: Option<&str>,
+    user: &str,
+    local_user: &str,
+) -> Option<(String, LocalizedMessage)> {
+    if let Some(platform) = platform {
+        if user == "git" {
+            return None;
+        }
+        let reason = LocalizedMessage::new("suggestion.userGit")
+            .with("platform", platform)
+            .with("user", user);
+        return Some(("git".to_string(), reason));
+    }
+
+    if user != "root" && user != local_user {
+        return None;
+    }
+    let banner = banner?.to_lowercase();
+    let (_, os, suggested) = CLOUD_IMAGE_USERS
+        .iter()
+        .find(|(needle, _, _)| banner.contains(needle))?;
+    if *suggested == user {
+        return None;
+    }
+    let reason = LocalizedMessage::new("suggestion.userCloud")
+        .with("os", os)
+        .with("suggested", suggested)
+        .with("user", user);
+    Some((suggested.to_string(), reason))
+}
+
+/// HostKeyAlias under which one of the `known_as` known_hosts names is looked up
+/// Plain names only match port 22; `[name]:port` needs the host's own port
+fn host_key_alias_for(known_as: &[&str], port: u16) -> Option<String> {
+    known_as
+        .iter()
+        .filter(|name| !name.is_empty() && !name.starts_with('|'))
+        .find_map(|name| match name.strip_prefix('[') {
+            Some(rest) => {
+                let (host, name_port) = rest.rsplit_once("]:")?;
+                (name_port.parse::<u16>().ok()? == port).then(|| host.to_string())
+            }
+            None => (port == 22).then(|| name.to_string()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_git_user() {
+        let (user, reason) = suggest_user(Some("github"), None, "alice", "alice").unwrap();
+        assert_eq!(user, "git");
+        assert_eq!(reason.key, "suggestion.userGit");
+        assert!(suggest_user(Some("github"), None, "git", "alice").is_none());
+    }
+
+    #[test]
+    fn test_suggest_cloud_user() {
+        let banner = Some("SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13");
+        let (user, _) = suggest_user(None, banner, "root", "alice").unwrap();
+        assert_eq!(user, "ubuntu");
+        let (user, _) = suggest_user(
+            None,
+            Some("SSH-2.0-OpenSSH_9.2p1 Debian-2"),
+            "alice",
+            "alice",
+        )
+        .unwrap();
+        assert_eq!(user, "admin");
+
+        // A deliberately configured user is left alone
+        assert!(suggest_user(None, banner, "deploy", "alice").is_none());
+        assert!(suggest_user(None, banner, "ubuntu", "ubuntu").is_none());
+        assert!(suggest_user(None, Some("SSH-2.0-OpenSSH_9.6"), "root", "alice").is_none());
+        assert!(suggest_user(None, None, "root", "alice").is_none());
+    }
+
+    #[test]
+    fn test_host_key_alias() {
+        assert_eq!(
+            host_key_alias_for(&["web", "10.0.0.5"], 22),
+            Some("web".to_string())
+        );
+        assert_eq!(host_key_alias_for(&["web"], 2222), None);
+        assert_eq!(
+            host_key_alias_for(&["[web]:2222", "10.0.0.5"], 2222),
+            Some("web".to_string())
+        );
+        assert_eq!(host_key_alias_for(&["[web]:2200"], 2222), None);
+        assert_eq!(host_key_alias_for(&["|1|abc=|def="], 22), None);
+    }
+}
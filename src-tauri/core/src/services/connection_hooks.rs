@@ -0,0 +1,333 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::registry_service::{now_millis, HostMetadata, RegistryService};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// Hook timeout unless the hook sets one
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 30;
+
+/// Upper bound for a hook's timeout
+const MAX_HOOK_TIMEOUT_SECS: u64 = 600;
+
+/// Captured output is cut at this many bytes per stream
+const MAX_CAPTURED_OUTPUT: usize = 64 * 1024;
+
+/// Recent runs kept per host
+const MAX_RECENT_RUNS: usize = 10;
+
+/// Local command run around an SSH session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookCommand {
+    /// Shell command line (sh -c on Unix, cmd /C on Windows)
+    pub command: String,
+    pub timeout_secs: Option<u64>,
+}
+
+/// Per-host connection hooks, stored in the host registry
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostHooks {
+    /// Must succeed before the session is opened (e.g. bring up a VPN)
+    pub pre_connect: Option<HookCommand>,
+    /// Run after the session closed; failures are only recorded
+    pub post_disconnect: Option<HookCommand>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HookKind {
+    PreConnect,
+    PostDisconnect,
+}
+
+impl HookKind {
+    fn name(self) -> &'static str {
+        match self {
+            HookKind::PreConnect => "pre-connect",
+            HookKind::PostDisconnect => "post-disconnect",
+        }
+    }
+}
+
+/// Captured result of one hook run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRun {
+    pub host_alias: String,
+    pub kind: HookKind,
+    pub command: String,
+    /// Unix timestamp in milliseconds
+    pub started_at: i64,
+    pub duration_ms: u64,
+    /// None when the hook was killed or couldn't be started
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub error: Option<String>,
+}
+
+impl HookRun {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Runs per-host local hooks as managed subprocesses (killed on timeout)
+pub struct ConnectionHookService;
+
+impl ConnectionHookService {
+    fn recent_runs() -> &'static Mutex<HashMap<String, VecDeque<HookRun>>> {
+        static RUNS: OnceLock<Mutex<HashMap<String, VecDeque<HookRun>>>> = OnceLock::new();
+        RUNS.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    /// Hooks configured for a host
+    pub async fn get_hooks(host_alias: &str) -> SshResult<HostHooks> {
+        let store = RegistryService::load().await?;
+        Ok(store
+            .hosts
+            .get(host_alias)
+            .and_then(|m| m.hooks.clone())
+            .unwrap_or_default())
+    }
+
+    /// Replace a host's hooks (empty commands remove a hook)
+    pub async fn set_hooks(host_alias: &str, hooks: HostHooks) -> SshResult<()> {
+        let normalize = |hook: Option<HookCommand>| {
+            hook.map(|h| HookCommand {
+                command: h.command.trim().to_string(),
+                timeout_secs: h.timeout_secs,
+            })
+            .filter(|h| !h.command.is_empty())
+        };
+        let hooks = HostHooks {
+            pre_connect: normalize(hooks.pre_connect),
+            post_disconnect: normalize(hooks.post_disconnect),
+        };
+
+        RegistryService::update(|store| {
+            store
+                .hosts
+                .entry(host_alias.to_string())
+                .or_insert_with(HostMetadata::new)
+                .hooks = (hooks != HostHooks::default()).then_some(hooks);
+            Ok(())
+        })
+        .await?;
+        tracing::info!("[connection_hooks] Updated hooks of {}", host_alias);
+        Ok(())
+    }
+
+    /// Recent hook runs of a host, newest first
+    pub fn recent(host_alias: &str) -> Vec<HookRun> {
+        let runs = Self::recent_runs()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        runs.get(host_alias)
+            .map(|r| r.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Run the pre-connect hook; a failing hook aborts the connection
+    pub async fn run_pre_connect(host_alias: &str) -> SshResult<()> {
+        let Some(run) = Self::run(host_alias, HookKind::PreConnect).await? else {
+            return Ok(());
+        };
+        if run.success() {
+            return Ok(());
+        }
+        let reason = match (&run.error, run.timed_out) {
+            (Some(error), _) => error.clone(),
+            (None, true) => "timed out".to_string(),
+            (None, false) => format!(
+                "exited with {:?}: {}",
+                run.exit_code,
+                last_line(&run.stderr)
+            ),
+        };
+        Err(SshBuddyError::Unknown {
+            message: format!("Pre-connect hook of {} failed: {}", host_alias, reason),
+        })
+    }
+
+    /// Run the post-disconnect hook (failures are logged, not returned)
+    pub async fn run_post_disconnect(host_alias: &str) {
+        match Self::run(host_alias, HookKind::PostDisconnect).await {
+            Ok(Some(run)) if !run.success() => tracing::warn!(
+                "[connection_hooks] Post-disconnect hook of {} failed ({:?})",
+                host_alias,
+                run.exit_code
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::warn!(
+                "[connection_hooks] Post-disconnect hook of {} not run: {}",
+                host_alias,
+                e
+            ),
+        }
+    }
+
+    /// Run a host's hook of the given kind, if it has one
+    pub async fn run(host_alias: &str, kind: HookKind) -> SshResult<Option<HookRun>> {
+        let hooks = Self::get_hooks(host_alias).await?;
+        let hook = match kind {
+            HookKind::PreConnect => hooks.pre_connect,
+            HookKind::PostDisconnect => hooks.post_disconnect,
+        };
+        let Some(hook) = hook else {
+            return Ok(None);
+        };
+
+        let run = Self::execute(host_alias, kind, &hook).await;
+        tracing::info!(
+            "[connection_hooks] {} hook of {} finished in {}ms ({:?}, timed_out={})",
+            kind.name(),
+            host_alias,
+            run.duration_ms,
+            run.exit_code,
+            run.timed_out
+        );
+        let mut runs = Self::recent_runs()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let host_runs = runs.entry(host_alias.to_string()).or_default();
+        if host_runs.len() >= MAX_RECENT_RUNS {
+            host_runs.pop_front();
+        }
+        host_runs.push_back(run.clone());
+        Ok(Some(run))
+    }
+
+    /// Run a hook command without recording it
+    pub(crate) async fn execute(host_alias: &str, kind: HookKind, hook: &HookCommand) -> HookRun {
+        let limit = Duration::from_secs(
+            hook.timeout_secs
+                .unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS)
+                .clamp(1, MAX_HOOK_TIMEOUT_SECS),
+        );
+        let mut run = HookRun {
+            host_alias: host_alias.to_string(),
+            kind,
+            command: hook.command.clone(),
+            started_at: now_millis(),
+            duration_ms: 0,
+            exit_code: None,
+            timed_out: false,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: None,
+        };
+
+        let mut command = shell_command(&hook.command);
+        command
+            .env("SSH_BUDDY_HOST", host_alias)
+            .env("SSH_BUDDY_HOOK", kind.name())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Dropping the future on timeout kills the process
+            .kill_on_drop(true);
+
+        let started = Instant::now();
+        match command.spawn() {
+            Ok(child) => match timeout(limit, child.wait_with_output()).await {
+                Ok(Ok(output)) => {
+                    run.exit_code = output.status.code();
+                    run.stdout = capture(&output.stdout);
+                    run.stderr = capture(&output.stderr);
+                }
+                Ok(Err(e)) => run.error = Some(format!("Failed to wait for hook: {}", e)),
+                Err(_) => run.timed_out = true,
+            },
+            Err(e) => run.error = Some(format!("Failed to start hook: {}", e)),
+        }
+        run.duration_ms = started.elapsed().as_millis() as u64;
+        run
+    }
+}
+
+#[cfg(windows)]
+fn shell_command(command_line: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.args(["/C", command_line]);
+    command
+}
+
+#[cfg(not(windows))]
+fn shell_command(command_line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.args(["-c", command_line]);
+    command
+}
+
+/// Output as text, cut at `MAX_CAPTURED_OUTPUT` bytes
+fn capture(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_CAPTURED_OUTPUT)]);
+    text.to_string()
+}
+
+fn last_line(text: &str) -> &str {
+    text.lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str, timeout_secs: Option<u64>) -> HookCommand {
+        HookCommand {
+            command: command.to_string(),
+            timeout_secs,
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_execute_captures_output() {
+        let run = ConnectionHookService::execute(
+            "web",
+            HookKind::PreConnect,
+            &hook(
+                "echo \"$SSH_BUDDY_HOST $SSH_BUDDY_HOOK\"; echo oops >&2; exit 3",
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(run.exit_code, Some(3));
+        assert_eq!(run.stdout, "web pre-connect\n");
+        assert_eq!(run.stderr, "oops\n");
+        assert!(!run.success());
+        assert!(!run.timed_out);
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_execute_times_out() {
+        let run = ConnectionHookService::execute(
+            "web",
+            HookKind::PostDisconnect,
+            &hook("sleep 5", Some(1)),
+        )
+        .await;
+        assert!(run.timed_out);
+        assert_eq!(run.exit_code, None);
+        assert!(run.duration_ms < 5000);
+    }
+
+    #[test]
+    fn test_last_line() {
+        assert_eq!(last_line("a\nvpn: no route\n\n"), "vpn: no route");
+        assert_eq!(last_line(""), "");
+    }
+}
@@ -0,0 +1,268 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::registry_service::{now_millis, HostMetadata, RegistryService};
+use crate::utils::app_data_path;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Break sequence of Opengear/portmanager consoles
+const DEFAULT_BREAK_SEQUENCE: &str = "~b";
+
+/// Folder (in the app data dir) console output is logged to
+const CONSOLE_LOG_DIR: &str = "console_logs";
+
+/// What the Enter key sends down the serial line
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsoleLineEnding {
+    #[default]
+    Cr,
+    Lf,
+    CrLf,
+}
+
+/// What the Backspace key sends
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsoleBackspace {
+    /// DEL (0x7f), what terminals send by default
+    #[default]
+    Delete,
+    /// BS (0x08), expected by many network devices
+    Backspace,
+}
+
+/// Per-host console server options, stored in the host registry
+/// A console server host's "shell" is a serial console (Cisco/Opengear style)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HostConsole {
+    pub enabled: bool,
+    pub line_ending: ConsoleLineEnding,
+    pub backspace: ConsoleBackspace,
+    /// Sent by "send break"; `\r`, `\n`, `\xHH` and `^X` (control character) are
+    /// understood
+    pub break_sequence: String,
+    /// Append console output to `console_logs/<host>-<time>.log`
+    pub log_output: bool,
+}
+
+impl Default for HostConsole {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            line_ending: ConsoleLineEnding::Cr,
+            backspace: ConsoleBackspace::Delete,
+            break_sequence: DEFAULT_BREAK_SEQUENCE.to_string(),
+            log_output: false,
+        }
+    }
+}
+
+/// Console options of a running shell session
+pub struct ConsoleSession {
+    pub settings: HostConsole,
+    /// Parsed break sequence
+    break_bytes: Vec<u8>,
+    log: Option<(PathBuf, Mutex<std::fs::File>)>,
+}
+
+impl ConsoleSession {
+    /// Start a session, opening its log file if output is logged
+    pub fn start(host_alias: &str, settings: HostConsole) -> SshResult<Self> {
+        let break_bytes = parse_key_sequence(&settings.break_sequence)?;
+        let log = if settings.log_output {
+            let dir = app_data_path(CONSOLE_LOG_DIR)?;
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join(log_file_name(host_alias, now_millis()));
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            tracing::info!("[console_server] Logging {} to {:?}", host_alias, path);
+            Some((path, Mutex::new(file)))
+        } else {
+            None
+        };
+        Ok(Self {
+            settings,
+            break_bytes,
+            log,
+        })
+    }
+
+    /// Keyboard input translated by the line discipline
+    pub fn translate_input(&self, data: &[u8]) -> Vec<u8> {
+        translate_input(&self.settings, data)
+    }
+
+    pub fn break_bytes(&self) -> &[u8] {
+        &self.break_bytes
+    }
+
+    pub fn log_path(&self) -> Option<&PathBuf> {
+        self.log.as_ref().map(|(path, _)| path)
+    }
+
+    /// Append output to the log; failures are logged, they must not break the session
+    pub fn log_output(&self, text: &str) {
+        if let Some((path, file)) = &self.log {
+            if let Ok(mut file) = file.lock() {
+                if let Err(e) = file.write_all(text.as_bytes()) {
+                    tracing::warn!("[console_server] Failed to write {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+}
+
+/// Stores console server options per host
+pub struct ConsoleServerService;
+
+impl ConsoleServerService {
+    /// Console options of a host (disabled when none are set)
+    pub async fn get_host_settings(host_alias: &str) -> SshResult<HostConsole> {
+        let store = RegistryService::load().await?;
+        Ok(store
+            .hosts
+            .get(host_alias)
+            .and_then(|m| m.console.clone())
+            .unwrap_or_default())
+    }
+
+    /// Set a host's console options
+    pub async fn set_host_settings(host_alias: &str, settings: HostConsole) -> SshResult<()> {
+        parse_key_sequence(&settings.break_sequence)?;
+        RegistryService::update(|store| {
+            store
+                .hosts
+                .entry(host_alias.to_string())
+                .or_insert_with(HostMetadata::new)
+                .console = (settings != HostConsole::default()).then_some(settings);
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[console_server] Updated console settings of {}",
+            host_alias
+        );
+        Ok(())
+    }
+
+    /// Console options for a new session (None unless the host is a console server)
+    pub async fn session_for(host_alias: &str) -> SshResult<Option<ConsoleSession>> {
+        let settings = Self::get_host_settings(host_alias).await?;
+        if !settings.enabled {
+            return Ok(None);
+        }
+        ConsoleSession::start(host_alias, settings).map(Some)
+    }
+}
+
+/// Map Enter (CR) and Backspace (DEL) to what the console expects
+fn translate_input(settings: &HostConsole, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for &byte in data {
+        match byte {
+            b'\r' => match settings.line_ending {
+                ConsoleLineEnding::Cr => out.push(b'\r'),
+                ConsoleLineEnding::Lf => out.push(b'\n'),
+                ConsoleLineEnding::CrLf => out.extend_from_slice(b"\r\n"),
+            },
+            0x7f if settings.backspace == ConsoleBackspace::Backspace => out.push(0x08),
+            _ => out.push(byte),
+        }
+    }
+    out
+}
+
+/// Bytes of a key sequence with `\r`, `\n`, `\t`, `\\`, `\xHH` and `^X` escapes
+fn parse_key_sequence(sequence: &str) -> SshResult<Vec<u8>> {
+    let invalid = || SshBuddyError::InvalidOption {
+        message: format!("Invalid key sequence: {}", sequence),
+    };
+    let mut bytes = Vec::new();
+    let mut chars = sequence.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next().ok_or_else(invalid)? {
+                'r' => bytes.push(b'\r'),
+                'n' => bytes.push(b'\n'),
+                't' => bytes.push(b'\t'),
+                '\\' => bytes.push(b'\\'),
+                '^' => bytes.push(b'^'),
+                'x' => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    bytes.push(u8::from_str_radix(&hex, 16).map_err(|_| invalid())?);
+                }
+                _ => return Err(invalid()),
+            },
+            '^' => {
+                let control = chars.next().ok_or_else(invalid)?.to_ascii_uppercase();
+                if !('@'..='_').contains(&control) {
+                    return Err(invalid());
+                }
+                bytes.push(control as u8 - b'@');
+            }
+            c => {
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            }
+        }
+    }
+    if bytes.is_empty() {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+/// `<host>-<millis>.log` with characters unsafe in file names replaced
+fn log_file_name(host_alias: &str, millis: i64) -> String {
+    let host: String = host_alias
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}-{}.log", host, millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_input() {
+        let mut settings = HostConsole::default();
+        assert_eq!(translate_input(&settings, b"show ver\r"), b"show ver\r");
+
+        settings.line_ending = ConsoleLineEnding::CrLf;
+        settings.backspace = ConsoleBackspace::Backspace;
+        assert_eq!(translate_input(&settings, b"ab\x7f\r"), b"ab\x08\r\n");
+
+        settings.line_ending = ConsoleLineEnding::Lf;
+        assert_eq!(translate_input(&settings, b"\r"), b"\n");
+    }
+
+    #[test]
+    fn test_parse_key_sequence() {
+        assert_eq!(parse_key_sequence("~b").unwrap(), b"~b");
+        assert_eq!(parse_key_sequence("^^x").unwrap(), vec![0x1e, b'x']);
+        assert_eq!(parse_key_sequence("\\x00\\r").unwrap(), vec![0x00, b'\r']);
+        assert_eq!(parse_key_sequence("^c").unwrap(), vec![0x03]);
+        assert!(parse_key_sequence("").is_err());
+        assert!(parse_key_sequence("\\xZZ").is_err());
+        assert!(parse_key_sequence("^").is_err());
+        assert!(parse_key_sequence("\\q").is_err());
+    }
+
+    #[test]
+    fn test_log_file_name() {
+        assert_eq!(log_file_name("rack1/con 3", 42), "rack1_con_3-42.log");
+    }
+}
@@ -0,0 +1,207 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::short_codes::ShortCodeService;
+use crate::utils::{parse_deep_link, DeepLink, SshConfigEditor, SshTarget};
+use serde::Serialize;
+
+/// A parsed link matched against the SSH config
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkRequest {
+    pub url: String,
+    pub link: DeepLink,
+    /// Configured host the link points at
+    pub alias: Option<String>,
+    /// The link points at a host that isn't configured; ask before connecting
+    pub requires_confirmation: bool,
+    /// Alias to use when the user adds the host
+    pub suggested_alias: Option<String>,
+}
+
+/// Host block fields used for matching
+struct ConfiguredHost {
+    alias: String,
+    hostname: String,
+    user: Option<String>,
+    port: u16,
+}
+
+/// Turns `ssh://` and `sshbuddy://` links into app actions
+pub struct DeepLinkService;
+
+impl DeepLinkService {
+    /// Parse a link and find the configured host it refers to
+    pub async fn resolve(url: &str) -> SshResult<DeepLinkRequest> {
+        let link = parse_deep_link(url)?;
+        let editor = ConfigService::load_editor().await?;
+
+        let (alias, suggested_alias) = match &link {
+            // The alias part may also be a short code (`sshbuddy://connect/p1`)
+            DeepLink::ConnectAlias { alias } | DeepLink::ShowHost { alias } => {
+                match ShortCodeService::resolve(alias).await? {
+                    Some(resolved) => (Some(resolved), None),
+                    None => {
+                        return Err(SshBuddyError::HostNotFound {
+                            alias: alias.clone(),
+                        })
+                    }
+                }
+            }
+            DeepLink::Connect { target } => {
+                let hosts = configured_hosts(&editor);
+                match match_target(target, &hosts) {
+                    Some(alias) => (Some(alias), None),
+                    None => (None, Some(suggest_alias(target, &hosts))),
+                }
+            }
+            // Not a host; watching a shared session is always confirmed first
+            DeepLink::ObserveSession { .. } => (None, None),
+        };
+        tracing::info!(
+            "[deep_link] Resolved {} to {:?} (known={})",
+            url,
+            link,
+            alias.is_some()
+        );
+        Ok(DeepLinkRequest {
+            url: url.to_string(),
+            requires_confirmation: alias.is_none(),
+            link,
+            alias,
+            suggested_alias,
+        })
+    }
+}
+
+fn configured_hosts(editor: &SshConfigEditor) -> Vec<ConfiguredHost> {
+    editor
+        .host_aliases()
+        .into_iter()
+        .map(|alias| ConfiguredHost {
+            hostname: editor
+                .get_option(&alias, "HostName")
+                .unwrap_or_else(|| alias.clone()),
+            user: editor.get_option(&alias, "User"),
+            port: editor
+                .get_option(&alias, "Port")
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(22),
+            alias,
+        })
+        .collect()
+}
+
+/// Configured host with the same address, port and (when both set) user
+fn match_target(target: &SshTarget, hosts: &[ConfiguredHost]) -> Option<String> {
+    let port = target.port.unwrap_or(22);
+    let same_user = |host: &ConfiguredHost| match (&target.user, &host.user) {
+        (Some(wanted), Some(configured)) => wanted == configured,
+        _ => true,
+    };
+    let candidates = || hosts.iter().filter(|h| h.port == port && same_user(h));
+    // A link to an alias wins over one to the address behind it
+    candidates()
+        .find(|h| h.alias.eq_ignore_ascii_case(&target.host))
+        .or_else(|| candidates().find(|h| h.hostname.eq_ignore_ascii_case(&target.host)))
+        .map(|h| h.alias.clone())
+}
+
+/// First label of the host name (or the address), made unique
+fn suggest_alias(target: &SshTarget, hosts: &[ConfiguredHost]) -> String {
+    let is_ip = target.host.contains(':') || target.host.parse::<std::net::Ipv4Addr>().is_ok();
+    let base = if is_ip {
+        target.host.replace(':', "-")
+    } else {
+        target
+            .host
+            .split('.')
+            .next()
+            .unwrap_or(&target.host)
+            .to_string()
+    };
+    let taken = |alias: &str| hosts.iter().any(|h| h.alias == alias);
+    if !taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|alias| !taken(alias))
+        .unwrap_or(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts() -> Vec<ConfiguredHost> {
+        vec![
+            ConfiguredHost {
+                alias: "web".to_string(),
+                hostname: "web.example.com".to_string(),
+                user: Some("deploy".to_string()),
+                port: 22,
+            },
+            ConfiguredHost {
+                alias: "git".to_string(),
+                hostname: "git.example.com".to_string(),
+                user: None,
+                port: 2222,
+            },
+        ]
+    }
+
+    fn target(host: &str, user: Option<&str>, port: Option<u16>) -> SshTarget {
+        SshTarget {
+            host: host.to_string(),
+            user: user.map(str::to_string),
+            port,
+            fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn test_match_target() {
+        let hosts = hosts();
+        assert_eq!(
+            match_target(&target("web.example.com", Some("deploy"), None), &hosts).as_deref(),
+            Some("web")
+        );
+        assert_eq!(
+            match_target(&target("WEB", None, Some(22)), &hosts).as_deref(),
+            Some("web")
+        );
+        assert_eq!(
+            match_target(&target("web.example.com", Some("root"), None), &hosts),
+            None
+        );
+        assert_eq!(
+            match_target(&target("git.example.com", Some("git"), Some(2222)), &hosts).as_deref(),
+            Some("git")
+        );
+        assert_eq!(
+            match_target(&target("git.example.com", None, None), &hosts),
+            None
+        );
+    }
+
+    #[test]
+    fn test_suggest_alias() {
+        let hosts = hosts();
+        assert_eq!(
+            suggest_alias(&target("db.example.com", None, None), &hosts),
+            "db"
+        );
+        assert_eq!(
+            suggest_alias(&target("web.other.org", None, None), &hosts),
+            "web-2"
+        );
+        assert_eq!(
+            suggest_alias(&target("10.0.0.5", None, None), &hosts),
+            "10.0.0.5"
+        );
+        assert_eq!(
+            suggest_alias(&target("2001:db8::1", None, None), &hosts),
+            "2001-db8--1"
+        );
+    }
+}
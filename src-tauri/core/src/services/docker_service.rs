@@ -0,0 +1,279 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::script_service::shell_quote;
+use crate::services::ssh_connection::SshConnectionService;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::fs;
+
+/// Timeout for docker CLI calls on the remote host
+const DOCKER_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Local docker context with an ssh:// endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerContext {
+    pub name: String,
+    /// e.g. "ssh://deploy@build.example.com"
+    pub endpoint: String,
+    /// SSH config host the endpoint points at
+    pub host_alias: Option<String>,
+}
+
+/// Docker availability on a remote host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerStatus {
+    /// docker CLI found
+    pub installed: bool,
+    /// Daemon answered (the user may talk to it)
+    pub reachable: bool,
+    pub server_version: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Container as listed by `docker ps -a`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerContainer {
+    pub id: String,
+    pub names: String,
+    pub image: String,
+    /// e.g. "running", "exited"
+    pub state: String,
+    /// e.g. "Up 2 hours"
+    pub status: String,
+    pub ports: String,
+}
+
+/// Docker daemons reached over SSH
+pub struct DockerService;
+
+impl DockerService {
+    /// List local docker contexts that use an ssh:// endpoint, matched to SSH config hosts
+    pub async fn list_contexts() -> SshResult<Vec<DockerContext>> {
+        let meta_dir = dirs::home_dir()
+            .ok_or(SshBuddyError::HomeDirNotFound)?
+            .join(".docker")
+            .join("contexts")
+            .join("meta");
+        if !meta_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let editor = ConfigService::load_editor().await?;
+        let hosts: Vec<(String, String)> = editor
+            .host_aliases()
+            .into_iter()
+            .map(|alias| {
+                let hostname = editor
+                    .get_option(&alias, "HostName")
+                    .unwrap_or_else(|| alias.clone());
+                (alias, hostname)
+            })
+            .collect();
+
+        let mut contexts = Vec::new();
+        let mut entries = fs::read_dir(&meta_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(content) = fs::read_to_string(entry.path().join("meta.json")).await else {
+                continue;
+            };
+            if let Some(mut context) = parse_context_meta(&content) {
+                let target = parse_ssh_endpoint(&context.endpoint);
+                context.host_alias = target.and_then(|(host, _)| {
+                    hosts
+                        .iter()
+                        .find(|(alias, hostname)| *alias == host || *hostname == host)
+                        .map(|(alias, _)| alias.clone())
+                });
+                contexts.push(context);
+            }
+        }
+        contexts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(contexts)
+    }
+
+    /// Check whether the host has docker and the user can reach the daemon
+    pub async fn probe(host_alias: &str) -> SshResult<DockerStatus> {
+        let session = SshConnectionService::open_session(host_alias, None).await?;
+        let output = session
+            .exec(
+                "docker version --format '{{.Server.Version}}'",
+                None,
+                DOCKER_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+
+        let output = output?;
+        let status = classify_probe(output.exit_code, &output.stdout, &output.stderr);
+        tracing::info!(
+            "[docker_service] {}: installed={} reachable={}",
+            host_alias,
+            status.installed,
+            status.reachable
+        );
+        Ok(status)
+    }
+
+    /// List all containers on the host
+    pub async fn list_containers(host_alias: &str) -> SshResult<Vec<DockerContainer>> {
+        let session = SshConnectionService::open_session(host_alias, None).await?;
+        let output = session
+            .exec(
+                "docker ps -a --no-trunc --format '{{json .}}'",
+                None,
+                DOCKER_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+
+        let output = output?;
+        if !output.success() {
+            return Err(SshBuddyError::Unknown {
+                message: format!("docker ps failed: {}", output.stderr.trim()),
+            });
+        }
+        Ok(parse_containers(&output.stdout))
+    }
+
+    /// Command that starts an interactive shell inside a container
+    pub fn exec_shell_command(container: &str, shell: &str) -> String {
+        format!(
+            "docker exec -it {} {}",
+            shell_quote(container),
+            shell_quote(shell)
+        )
+    }
+}
+
+/// Read a context's meta.json; None for contexts without an ssh:// endpoint
+fn parse_context_meta(content: &str) -> Option<DockerContext> {
+    let meta: Value = serde_json::from_str(content).ok()?;
+    let endpoint = meta["Endpoints"]["docker"]["Host"].as_str()?;
+    if !endpoint.starts_with("ssh://") {
+        return None;
+    }
+    Some(DockerContext {
+        name: meta["Name"].as_str()?.to_string(),
+        endpoint: endpoint.to_string(),
+        host_alias: None,
+    })
+}
+
+/// Split "ssh://[user@]host[:port]" into host and user
+fn parse_ssh_endpoint(endpoint: &str) -> Option<(String, Option<String>)> {
+    let rest = endpoint.strip_prefix("ssh://")?.trim_end_matches('/');
+    let (user, host_port) = match rest.rsplit_once('@') {
+        Some((user, host_port)) => (Some(user.to_string()), host_port),
+        None => (None, rest),
+    };
+    let host = match host_port.strip_prefix('[') {
+        // [IPv6]:port
+        Some(bracketed) => bracketed.split(']').next()?,
+        None => host_port.split(':').next()?,
+    };
+    (!host.is_empty()).then(|| (host.to_string(), user))
+}
+
+fn classify_probe(exit_code: Option<u32>, stdout: &str, stderr: &str) -> DockerStatus {
+    let version = stdout.trim();
+    if exit_code == Some(0) && !version.is_empty() {
+        return DockerStatus {
+            installed: true,
+            reachable: true,
+            server_version: Some(version.to_string()),
+            error: None,
+        };
+    }
+    let installed = exit_code != Some(127) && !stderr.contains("command not found");
+    DockerStatus {
+        installed,
+        reachable: false,
+        server_version: None,
+        error: Some(if installed {
+            stderr.trim().to_string()
+        } else {
+            "docker is not installed".to_string()
+        }),
+    }
+}
+
+/// Parse `docker ps --format '{{json .}}'` output (one JSON object per line)
+fn parse_containers(output: &str) -> Vec<DockerContainer> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .map(|row| {
+            let field = |key: &str| row[key].as_str().unwrap_or_default().to_string();
+            DockerContainer {
+                id: field("ID"),
+                names: field("Names"),
+                image: field("Image"),
+                state: field("State"),
+                status: field("Status"),
+                ports: field("Ports"),
+            }
+        })
+        .filter(|container| !container.id.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_context_meta() {
+        let meta = r#"{"Name":"build","Metadata":{},"Endpoints":{"docker":{"Host":"ssh://deploy@build.example.com","SkipTLSVerify":false}}}"#;
+        let context = parse_context_meta(meta).unwrap();
+        assert_eq!(context.name, "build");
+        assert_eq!(context.endpoint, "ssh://deploy@build.example.com");
+
+        let local =
+            r#"{"Name":"desktop","Endpoints":{"docker":{"Host":"unix:///var/run/docker.sock"}}}"#;
+        assert!(parse_context_meta(local).is_none());
+    }
+
+    #[test]
+    fn test_parse_ssh_endpoint() {
+        assert_eq!(
+            parse_ssh_endpoint("ssh://deploy@build.example.com:2222"),
+            Some(("build.example.com".to_string(), Some("deploy".to_string())))
+        );
+        assert_eq!(
+            parse_ssh_endpoint("ssh://[2001:db8::1]:22"),
+            Some(("2001:db8::1".to_string(), None))
+        );
+        assert_eq!(parse_ssh_endpoint("ssh://"), None);
+    }
+
+    #[test]
+    fn test_classify_probe() {
+        assert!(classify_probe(Some(0), "24.0.7\n", "").reachable);
+        let denied = classify_probe(
+            Some(1),
+            "",
+            "permission denied while trying to connect to the Docker daemon socket",
+        );
+        assert!(denied.installed && !denied.reachable);
+        assert!(!classify_probe(Some(127), "", "sh: docker: not found").installed);
+    }
+
+    #[test]
+    fn test_parse_containers() {
+        let output = concat!(
+            r#"{"ID":"abc123","Image":"nginx:1.25","Names":"web","Ports":"0.0.0.0:80->80/tcp","State":"running","Status":"Up 2 hours"}"#,
+            "\n",
+            "not json\n",
+        );
+        let containers = parse_containers(output);
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].names, "web");
+        assert_eq!(containers[0].state, "running");
+    }
+}
@@ -0,0 +1,224 @@
+use crate::models::{LocalizedMessage, SshBuddyError, SshResult};
+use crate::services::keypair_audit::KeypairAuditService;
+use crate::services::registry_service::now_millis;
+use crate::services::ssh_installations::SshInstallationsService;
+use crate::services::tamper_detection::TamperDetectionService;
+use crate::utils::{append_json_line, read_json_lines, retain_json_lines, workspace_data_path};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Doctor runs kept for comparisons
+const MAX_STORED_RUNS: usize = 50;
+
+/// How serious a finding is (same levels as the frontend security checks)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Check a finding comes from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DoctorCheck {
+    /// ssh/ssh-agent/ssh-add binaries, SSH_AUTH_SOCK and GIT_SSH
+    SshInstallations,
+    /// Public/private key pairs in the SSH directory
+    Keypairs,
+    /// Unexpected files in the SSH directory and outside changes to the config
+    SshDirectory,
+    /// Modes of the SSH directory and private keys against the permission policy
+    Permissions,
+    /// Certificates that expired or expire soon
+    KeyExpiry,
+}
+
+/// Problem found by the doctor
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorFinding {
+    /// Stable id (`<check>.<problem>`), the same problem gets the same id on every run
+    pub id: String,
+    pub check: DoctorCheck,
+    pub severity: FindingSeverity,
+    /// What the finding is about (e.g. a key name) when a problem can occur several times
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// Catalog key `doctor.<problem>` with the details
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
+}
+
+/// Result of a doctor run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorReport {
+    /// Unix milliseconds
+    pub ran_at: i64,
+    /// Most severe first
+    pub findings: Vec<DoctorFinding>,
+}
+
+/// What changed between two doctor runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorComparison {
+    /// Run compared against (None when there is no earlier run)
+    pub from: Option<i64>,
+    pub to: i64,
+    /// In the later run only
+    pub new: Vec<DoctorFinding>,
+    /// In the earlier run only
+    pub resolved: Vec<DoctorFinding>,
+    /// In both runs (as reported by the later one)
+    pub persisting: Vec<DoctorFinding>,
+}
+
+/// Diagnoses the local SSH setup
+pub struct DoctorService;
+
+impl DoctorService {
+    fn get_runs_path() -> SshResult<PathBuf> {
+        workspace_data_path("doctor_runs.jsonl")
+    }
+
+    /// Run all checks and store the report for comparisons
+    pub async fn run() -> DoctorReport {
+        let mut findings = SshInstallationsService::check().await;
+        findings.extend(KeypairAuditService::check().await);
+        findings.extend(TamperDetectionService::check().await);
+        findings.sort_by_key(|f| f.severity);
+        tracing::info!("[doctor] {} finding(s)", findings.len());
+        let report = DoctorReport {
+            ran_at: now_millis(),
+            findings,
+        };
+        if let Err(e) = Self::store(&report).await {
+            tracing::warn!("[doctor] Failed to store the report: {}", e);
+        }
+        report
+    }
+
+    async fn store(report: &DoctorReport) -> SshResult<()> {
+        let path = Self::get_runs_path()?;
+        append_json_line(&path, report).await?;
+        let runs: Vec<DoctorReport> = read_json_lines(&path).await?;
+        if runs.len() > MAX_STORED_RUNS {
+            let cutoff = runs[runs.len() - MAX_STORED_RUNS].ran_at;
+            retain_json_lines(&path, |r: &DoctorReport| r.ran_at >= cutoff).await?;
+        }
+        Ok(())
+    }
+
+    /// Stored runs, newest first
+    pub async fn list_runs() -> SshResult<Vec<DoctorReport>> {
+        let mut runs: Vec<DoctorReport> = read_json_lines(&Self::get_runs_path()?).await?;
+        runs.reverse();
+        Ok(runs)
+    }
+
+    /// Compare two stored runs (by `ran_at`); `to` defaults to the latest run and
+    /// `from` to the run before `to`
+    pub async fn compare(from: Option<i64>, to: Option<i64>) -> SshResult<DoctorComparison> {
+        let runs = Self::list_runs().await?;
+        let find = |ran_at: i64| {
+            runs.iter().position(|r| r.ran_at == ran_at).ok_or_else(|| {
+                SshBuddyError::InvalidOption {
+                    message: format!("No doctor run at {}", ran_at),
+                }
+            })
+        };
+        let to_index = match to {
+            Some(ran_at) => find(ran_at)?,
+            None if runs.is_empty() => {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "The doctor hasn't run yet".to_string(),
+                })
+            }
+            None => 0,
+        };
+        let from = match from {
+            Some(ran_at) => Some(&runs[find(ran_at)?]),
+            None => runs.get(to_index + 1),
+        };
+        let to = &runs[to_index];
+        Ok(compare_findings(
+            from.map(|r| r.ran_at),
+            from.map(|r| r.findings.as_slice()).unwrap_or_default(),
+            to,
+        ))
+    }
+}
+
+/// Split findings into new, resolved and persisting (the same id and subject is the
+/// same finding)
+pub fn compare_findings(
+    from: Option<i64>,
+    previous: &[DoctorFinding],
+    current: &DoctorReport,
+) -> DoctorComparison {
+    let key = |f: &DoctorFinding| (f.id.clone(), f.subject.clone());
+    let before: HashSet<_> = previous.iter().map(key).collect();
+    let after: HashSet<_> = current.findings.iter().map(key).collect();
+    let (persisting, new) = current
+        .findings
+        .iter()
+        .cloned()
+        .partition(|f| before.contains(&key(f)));
+    DoctorComparison {
+        from,
+        to: current.ran_at,
+        new,
+        resolved: previous
+            .iter()
+            .filter(|f| !after.contains(&key(f)))
+            .cloned()
+            .collect(),
+        persisting,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(id: &str, subject: Option<&str>) -> DoctorFinding {
+        DoctorFinding {
+            id: id.to_string(),
+            check: DoctorCheck::Keypairs,
+            severity: FindingSeverity::Warning,
+            subject: subject.map(str::to_string),
+            message: LocalizedMessage::new("doctor.orphanedPublicKey"),
+        }
+    }
+
+    #[test]
+    fn test_compare_findings() {
+        let previous = vec![
+            finding("keypairs.orphanedPublicKey", Some("id_old")),
+            finding("keypairs.orphanedPublicKey", Some("id_rsa")),
+            finding("installations.noSshClient", None),
+        ];
+        let current = DoctorReport {
+            ran_at: 2,
+            findings: vec![
+                finding("keypairs.orphanedPublicKey", Some("id_rsa")),
+                finding("keypairs.orphanedPublicKey", Some("id_ed25519")),
+                finding("installations.noSshClient", None),
+            ],
+        };
+        let comparison = compare_findings(Some(1), &previous, &current);
+        assert_eq!(comparison.from, Some(1));
+        assert_eq!(comparison.to, 2);
+        assert_eq!(comparison.new, vec![current.findings[1].clone()]);
+        assert_eq!(comparison.resolved, vec![previous[0].clone()]);
+        assert_eq!(comparison.persisting.len(), 2);
+
+        let first = compare_findings(None, &[], &current);
+        assert_eq!(first.new.len(), 3);
+        assert!(first.resolved.is_empty());
+    }
+}
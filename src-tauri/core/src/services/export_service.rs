@@ -0,0 +1,346 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::export_signing::{ExportSigningService, SignatureCheck};
+use crate::services::registry_service::{now_millis, RegistryService};
+use crate::services::revision_service::ManagedFile;
+use crate::services::vault_service::VaultService;
+use crate::utils::{
+    decrypt_with_password, encrypt_with_password, redact_secrets, scan_secrets, EncryptedPayload,
+    SecretFinding,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// Bundle format version
+const BUNDLE_VERSION: u32 = 1;
+
+/// What to do when secrets are found in an export
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SecretHandling {
+    /// Refuse to export
+    #[default]
+    Block,
+    /// Replace secrets with placeholders
+    Redact,
+    /// Keep secrets (only allowed for encrypted exports)
+    Include,
+}
+
+/// Export options
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub include_known_hosts: bool,
+    #[serde(default)]
+    pub include_metadata: bool,
+    #[serde(default)]
+    pub secret_handling: SecretHandling,
+    /// Encrypt the bundle with this password
+    pub password: Option<String>,
+    /// Write a `.sig` made with the app signing key next to the bundle
+    #[serde(default)]
+    pub sign: bool,
+}
+
+/// File inside a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleFile {
+    pub name: String,
+    pub content: String,
+}
+
+/// Plain bundle contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportBundle {
+    pub version: u32,
+    /// Unix timestamp in milliseconds
+    pub created_at: i64,
+    pub files: Vec<BundleFile>,
+}
+
+/// Bundle file written to disk (either plain or encrypted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "camelCase")]
+pub enum BundleEnvelope {
+    Plain(ExportBundle),
+    Encrypted {
+        version: u32,
+        #[serde(flatten)]
+        payload: EncryptedPayload,
+    },
+}
+
+/// Export result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportResult {
+    pub path: String,
+    pub files: Vec<String>,
+    pub findings: Vec<SecretFinding>,
+    pub redacted: bool,
+    pub encrypted: bool,
+    /// Path of the detached signature, when signed
+    pub signature_path: Option<String>,
+}
+
+/// Bundle read back for a restore, after its signature was checked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedBundle {
+    pub signature: SignatureCheck,
+    pub encrypted: bool,
+    pub bundle: ExportBundle,
+}
+
+/// Export service (bundles of the SSH config files and app metadata)
+pub struct ExportService;
+
+impl ExportService {
+    /// Collect the files selected for export
+    async fn collect_files(options: &ExportOptions) -> SshResult<Vec<BundleFile>> {
+        let mut managed = vec![ManagedFile::SshConfig];
+        if options.include_known_hosts {
+            managed.push(ManagedFile::KnownHosts);
+        }
+
+        let mut files = Vec::new();
+        for file in managed {
+            let path = file.live_path()?;
+            if path.exists() {
+                files.push(BundleFile {
+                    name: file.id().to_string(),
+                    content: fs::read_to_string(&path).await?,
+                });
+            }
+        }
+
+        if options.include_metadata {
+            let store = RegistryService::load().await?;
+            files.push(BundleFile {
+                name: "metadata.json".to_string(),
+                content: serde_json::to_string_pretty(&store).map_err(|e| {
+                    SshBuddyError::Unknown {
+                        message: e.to_string(),
+                    }
+                })?,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Secrets gate every export payload must pass before it is written or sent
+    /// Blocks, redacts in place, or (for encrypted payloads only) lets secrets through
+    pub fn guard_payload(
+        files: &mut [BundleFile],
+        handling: SecretHandling,
+        encrypted: bool,
+    ) -> SshResult<Vec<SecretFinding>> {
+        let findings: Vec<SecretFinding> = files
+            .iter()
+            .flat_map(|f| scan_secrets(&f.name, &f.content))
+            .collect();
+        if findings.is_empty() {
+            return Ok(findings);
+        }
+
+        match handling {
+            SecretHandling::Block => {
+                return Err(SshBuddyError::SecretsDetected {
+                    count: findings.len(),
+                })
+            }
+            SecretHandling::Include if !encrypted => {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "Secrets can only be included in encrypted exports".to_string(),
+                })
+            }
+            SecretHandling::Redact => {
+                for file in files.iter_mut() {
+                    file.content = redact_secrets(&file.content);
+                }
+            }
+            SecretHandling::Include => {}
+        }
+        Ok(findings)
+    }
+
+    /// Scan the files an export would contain (preview before exporting)
+    pub async fn scan(options: &ExportOptions) -> SshResult<Vec<SecretFinding>> {
+        let files = Self::collect_files(options).await?;
+        Ok(files
+            .iter()
+            .flat_map(|f| scan_secrets(&f.name, &f.content))
+            .collect())
+    }
+
+    /// Write an export bundle
+    pub async fn export_bundle(
+        destination: &str,
+        options: &ExportOptions,
+    ) -> SshResult<ExportResult> {
+        let path = PathBuf::from(destination);
+        if !path.is_absolute() {
+            return Err(SshBuddyError::InvalidPath {
+                message: format!("Export path must be absolute: {}", destination),
+            });
+        }
+
+        let mut files = Self::collect_files(options).await?;
+        let password = options.password.as_deref().filter(|p| !p.is_empty());
+        let findings =
+            Self::guard_payload(&mut files, options.secret_handling, password.is_some())?;
+
+        let file_names = files.iter().map(|f| f.name.clone()).collect();
+        let bundle = ExportBundle {
+            version: BUNDLE_VERSION,
+            created_at: now_millis(),
+            files,
+        };
+        let envelope = match password {
+            Some(password) => {
+                let plaintext =
+                    serde_json::to_vec(&bundle).map_err(|e| SshBuddyError::Unknown {
+                        message: e.to_string(),
+                    })?;
+                let kdf = VaultService::load_settings().await?.kdf;
+                BundleEnvelope::Encrypted {
+                    version: BUNDLE_VERSION,
+                    payload: encrypt_with_password(&plaintext, password, &kdf)?,
+                }
+            }
+            None => BundleEnvelope::Plain(bundle),
+        };
+
+        let content =
+            serde_json::to_string_pretty(&envelope).map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?;
+        fs::write(&path, content)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to write export: {}", e),
+            })?;
+
+        #[cfg(unix)]
+        {
+            let perms = std::fs::Permissions::from_mode(0o600);
+            fs::set_permissions(&path, perms).await?;
+        }
+        let signature_path = ExportSigningService::finish_export(&path, options.sign).await?;
+
+        tracing::info!(
+            "[export_service] Exported bundle to {:?} ({} secret findings)",
+            path,
+            findings.len()
+        );
+        Ok(ExportResult {
+            path: path.to_string_lossy().to_string(),
+            files: file_names,
+            redacted: options.secret_handling == SecretHandling::Redact && !findings.is_empty(),
+            encrypted: password.is_some(),
+            findings,
+            signature_path,
+        })
+    }
+
+    /// Read a bundle for a restore; the signature is checked before anything is decrypted
+    /// or written, so a tampered or corrupted bundle never reaches the SSH directory
+    pub async fn open_bundle(source: &str, password: Option<&str>) -> SshResult<OpenedBundle> {
+        let path = PathBuf::from(source);
+        let signature = ExportSigningService::ensure_importable(&path).await?;
+        let content = fs::read_to_string(&path).await?;
+        let envelope: BundleEnvelope =
+            serde_json::from_str(&content).map_err(|e| SshBuddyError::InvalidOption {
+                message: format!("Not an SSH Buddy bundle: {}", e),
+            })?;
+        let (bundle, encrypted) = match envelope {
+            BundleEnvelope::Plain(bundle) => (bundle, false),
+            BundleEnvelope::Encrypted { payload, .. } => {
+                let password = password.filter(|p| !p.is_empty()).ok_or_else(|| {
+                    SshBuddyError::InvalidOption {
+                        message: "The bundle is encrypted; a password is required".to_string(),
+                    }
+                })?;
+                let plaintext = decrypt_with_password(&payload, password)?;
+                let bundle = serde_json::from_slice(&plaintext).map_err(|e| {
+                    SshBuddyError::InvalidOption {
+                        message: format!("Invalid bundle contents: {}", e),
+                    }
+                })?;
+                (bundle, true)
+            }
+        };
+        if bundle.version > BUNDLE_VERSION {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Bundle version {} is newer than supported", bundle.version),
+            });
+        }
+        tracing::info!(
+            "[export_service] Opened bundle {:?} ({:?} signature)",
+            path,
+            signature.status
+        );
+        Ok(OpenedBundle {
+            signature,
+            encrypted,
+            bundle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<BundleFile> {
+        vec![BundleFile {
+            name: "config".to_string(),
+            content: "Host a\n  ProxyCommand sshpass -p hunter2 ssh -W %h:%p jump\n".to_string(),
+        }]
+    }
+
+    #[test]
+    fn test_guard_blocks_by_default() {
+        let mut files = files();
+        let err =
+            ExportService::guard_payload(&mut files, SecretHandling::default(), false).unwrap_err();
+        assert_eq!(err.error_type(), "SecretsDetected");
+    }
+
+    #[test]
+    fn test_guard_redacts() {
+        let mut files = files();
+        let findings =
+            ExportService::guard_payload(&mut files, SecretHandling::Redact, false).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert!(!files[0].content.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_guard_include_requires_encryption() {
+        let mut files = files();
+        assert!(ExportService::guard_payload(&mut files, SecretHandling::Include, false).is_err());
+        assert!(ExportService::guard_payload(&mut files, SecretHandling::Include, true).is_ok());
+        assert!(files[0].content.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_clean_payload_passes() {
+        let mut files = vec![BundleFile {
+            name: "config".to_string(),
+            content: "Host a\n  HostName a.example\n".to_string(),
+        }];
+        assert!(
+            ExportService::guard_payload(&mut files, SecretHandling::Block, false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::utils::{app_data_path, write_atomic, write_atomic_private};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use ssh_key::{Algorithm, HashAlg, LineEnding, PrivateKey, PublicKey, SshSig};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::fs;
+
+/// SSHSIG namespace of export signatures (`ssh-keygen -Y verify -n ssh-buddy-export`)
+const SIGNATURE_NAMESPACE: &str = "ssh-buddy-export";
+
+/// App signing key (OpenSSH private key format)
+const SIGNING_KEY_FILE: &str = "export_signing_key";
+
+const TRUSTED_SIGNERS_FILE: &str = "trusted_export_signers.json";
+
+/// Public half of the app signing key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningKeyInfo {
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+/// Key of another installation whose exports are accepted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedSigner {
+    pub fingerprint: String,
+    pub public_key: String,
+    pub label: Option<String>,
+    /// Unix timestamp in milliseconds
+    pub added_at: i64,
+}
+
+/// Outcome of checking an export's `.sig`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    /// No `.sig` next to the file
+    Unsigned,
+    /// Signed by this installation or a trusted signer
+    Trusted,
+    /// Valid signature by a key that isn't trusted yet
+    Untrusted,
+    /// The file or the signature was changed or corrupted
+    Invalid,
+}
+
+/// Signature check of an export file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureCheck {
+    pub status: SignatureStatus,
+    /// Signer key (None when unsigned or unreadable)
+    pub public_key: Option<String>,
+    pub fingerprint: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Signs export artifacts with an app-managed ed25519 key (detached `<file>.sig`
+/// in SSHSIG format) and verifies them before imports
+pub struct ExportSigningService;
+
+impl ExportSigningService {
+    /// Creating the key happens once
+    fn key_lock() -> &'static tokio::sync::Mutex<()> {
+        static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+    }
+
+    /// The app signing key, created on first use
+    async fn signing_key() -> SshResult<PrivateKey> {
+        let _guard = Self::key_lock().lock().await;
+        let path = app_data_path(SIGNING_KEY_FILE)?;
+        if path.exists() {
+            let content = fs::read_to_string(&path).await?;
+            return Ok(PrivateKey::from_openssh(&content)?);
+        }
+
+        let mut key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).map_err(|e| {
+            SshBuddyError::Unknown {
+                message: format!("Failed to generate the signing key: {}", e),
+            }
+        })?;
+        key.set_comment("ssh-buddy export signing");
+        let content = key.to_openssh(LineEnding::LF)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        write_atomic_private(&path, content.as_bytes()).await?;
+        tracing::info!("[export_signing] Created the export signing key");
+        Ok(key)
+    }
+
+    /// Public key to hand to other installations that should trust this one
+    pub async fn key_info() -> SshResult<SigningKeyInfo> {
+        let key = Self::signing_key().await?;
+        Ok(SigningKeyInfo {
+            public_key: key.public_key().to_openssh()?,
+            fingerprint: key.public_key().fingerprint(HashAlg::Sha256).to_string(),
+        })
+    }
+
+    /// Write `<file>.sig` for an export, or remove a stale one when not signing
+    /// Returns the signature path
+    pub async fn finish_export(path: &Path, sign: bool) -> SshResult<Option<String>> {
+        let signature_path = signature_path(path);
+        if !sign {
+            if signature_path.exists() {
+                fs::remove_file(&signature_path).await?;
+            }
+            return Ok(None);
+        }
+        let key = Self::signing_key().await?;
+        let content = fs::read(path).await?;
+        let signature = key.sign(SIGNATURE_NAMESPACE, HashAlg::Sha512, &content)?;
+        write_atomic(
+            &signature_path,
+            signature.to_pem(LineEnding::LF)?.as_bytes(),
+        )
+        .await?;
+        tracing::info!("[export_signing] Signed {:?}", path);
+        Ok(Some(signature_path.to_string_lossy().to_string()))
+    }
+
+    /// Check the `.sig` next to an export file
+    pub async fn verify(path: &Path) -> SshResult<SignatureCheck> {
+        let content = fs::read(path).await?;
+        let signature_path = signature_path(path);
+        let signature = if signature_path.exists() {
+            Some(fs::read_to_string(&signature_path).await?)
+        } else {
+            None
+        };
+        let mut trusted: Vec<String> = Self::list_trusted_signers()
+            .await?
+            .into_iter()
+            .map(|s| s.fingerprint)
+            .collect();
+        trusted.push(Self::key_info().await?.fingerprint);
+        Ok(check_signature(&content, signature.as_deref(), &trusted))
+    }
+
+    /// Refuse to import a file whose signature is broken or by an untrusted key
+    /// (unsigned files are accepted; signing is optional)
+    pub async fn ensure_importable(path: &Path) -> SshResult<SignatureCheck> {
+        let check = Self::verify(path).await?;
+        match check.status {
+            SignatureStatus::Unsigned | SignatureStatus::Trusted => Ok(check),
+            SignatureStatus::Untrusted => Err(SshBuddyError::PermissionDenied {
+                reason: format!(
+                    "{} is signed by an untrusted key ({})",
+                    path.display(),
+                    check.fingerprint.as_deref().unwrap_or_default()
+                ),
+            }),
+            SignatureStatus::Invalid => Err(SshBuddyError::PermissionDenied {
+                reason: format!(
+                    "{} was modified or is corrupted: {}",
+                    path.display(),
+                    check.error.as_deref().unwrap_or_default()
+                ),
+            }),
+        }
+    }
+
+    /// Keys of other installations whose exports are accepted
+    pub async fn list_trusted_signers() -> SshResult<Vec<TrustedSigner>> {
+        let path = app_data_path(TRUSTED_SIGNERS_FILE)?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse trusted export signers: {}", e),
+        })
+    }
+
+    async fn save_trusted(signers: &[TrustedSigner]) -> SshResult<()> {
+        let path = app_data_path(TRUSTED_SIGNERS_FILE)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content =
+            serde_json::to_string_pretty(signers).map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Accept exports signed by this key from now on
+    pub async fn trust_signer(public_key: &str, label: Option<String>) -> SshResult<TrustedSigner> {
+        ReadOnlyMode::ensure_writable("trust export signer")?;
+        let key = PublicKey::from_openssh(public_key.trim())?;
+        let signer = TrustedSigner {
+            fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+            public_key: key.to_openssh()?,
+            label: label.filter(|l| !l.trim().is_empty()),
+            added_at: now_millis(),
+        };
+        let mut signers = Self::list_trusted_signers().await?;
+        signers.retain(|s| s.fingerprint != signer.fingerprint);
+        signers.push(signer.clone());
+        Self::save_trusted(&signers).await?;
+        tracing::info!("[export_signing] Trusting signer {}", signer.fingerprint);
+        Ok(signer)
+    }
+
+    pub async fn remove_trusted_signer(fingerprint: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("remove trusted export signer")?;
+        let mut signers = Self::list_trusted_signers().await?;
+        signers.retain(|s| s.fingerprint != fingerprint);
+        Self::save_trusted(&signers).await
+    }
+}
+
+/// `<file>.sig`, as written by `ssh-keygen -Y sign`
+fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Verify an SSHSIG signature over `content`; `trusted` holds SHA-256 fingerprints
+fn check_signature(content: &[u8], signature: Option<&str>, trusted: &[String]) -> SignatureCheck {
+    let Some(signature) = signature else {
+        return SignatureCheck {
+            status: SignatureStatus::Unsigned,
+            public_key: None,
+            fingerprint: None,
+            error: None,
+        };
+    };
+    let invalid = |public_key: Option<&PublicKey>, error: String| SignatureCheck {
+        status: SignatureStatus::Invalid,
+        public_key: public_key.and_then(|k| k.to_openssh().ok()),
+        fingerprint: public_key.map(|k| k.fingerprint(HashAlg::Sha256).to_string()),
+        error: Some(error),
+    };
+    let signature = match SshSig::from_pem(signature.trim()) {
+        Ok(signature) => signature,
+        Err(e) => return invalid(None, format!("Unreadable signature: {}", e)),
+    };
+    let public_key = PublicKey::from(signature.public_key().clone());
+    if let Err(e) = public_key.verify(SIGNATURE_NAMESPACE, content, &signature) {
+        return invalid(Some(&public_key), format!("Signature mismatch: {}", e));
+    }
+    let fingerprint = public_key.fingerprint(HashAlg::Sha256).to_string();
+    SignatureCheck {
+        status: if trusted.contains(&fingerprint) {
+            SignatureStatus::Trusted
+        } else {
+            SignatureStatus::Untrusted
+        },
+        public_key: public_key.to_openssh().ok(),
+        fingerprint: Some(fingerprint),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_path() {
+        assert_eq!(
+            signature_path(Path::new("/tmp/backup.json")),
+            PathBuf::from("/tmp/backup.json.sig")
+        );
+    }
+
+    #[test]
+    fn test_check_signature() {
+        let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).unwrap();
+        let fingerprint = key.public_key().fingerprint(HashAlg::Sha256).to_string();
+        let content = b"{\"version\":1}";
+        let signature = key
+            .sign(SIGNATURE_NAMESPACE, HashAlg::Sha512, content)
+            .unwrap()
+            .to_pem(LineEnding::LF)
+            .unwrap();
+
+        let unsigned = check_signature(content, None, &[]);
+        assert_eq!(unsigned.status, SignatureStatus::Unsigned);
+
+        let trusted = check_signature(content, Some(&signature), &[fingerprint.clone()]);
+        assert_eq!(trusted.status, SignatureStatus::Trusted);
+        assert_eq!(trusted.fingerprint.as_deref(), Some(fingerprint.as_str()));
+
+        let untrusted = check_signature(content, Some(&signature), &[]);
+        assert_eq!(untrusted.status, SignatureStatus::Untrusted);
+
+        let tampered = check_signature(b"{\"version\":2}", Some(&signature), &[fingerprint]);
+        assert_eq!(tampered.status, SignatureStatus::Invalid);
+        assert!(tampered.error.is_some());
+
+        let garbage = check_signature(content, Some("not a signature"), &[]);
+        assert_eq!(garbage.status, SignatureStatus::Invalid);
+    }
+}
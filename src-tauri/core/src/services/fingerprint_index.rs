@@ -0,0 +1,472 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::agent_service::AgentService;
+use crate::services::key_exposure::{KeyAccessRecord, KeyExposureService};
+use crate::services::registry_service::now_millis;
+use crate::services::watcher_service::{WatchedFile, WatcherService};
+use crate::services::workspace_service::WorkspaceService;
+use crate::utils::{ssh_dir, workspace_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::fs;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Agent identities have no file to watch; they're re-read this often
+const AGENT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Modification time (Unix milliseconds) and size of a file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct FileStamp {
+    modified: i64,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as i64);
+        Self {
+            modified,
+            len: metadata.len(),
+        }
+    }
+}
+
+/// Public key file as last hashed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct IndexedFile {
+    name: String,
+    stamp: FileStamp,
+    /// None when the file isn't a valid public key
+    fingerprint: Option<String>,
+}
+
+/// Identity loaded in the agent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct AgentIdentity {
+    fingerprint: String,
+    comment: String,
+}
+
+/// fingerprint_index.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexStore {
+    /// By public key path
+    #[serde(default)]
+    key_files: BTreeMap<String, IndexedFile>,
+    #[serde(default)]
+    agent: Vec<AgentIdentity>,
+    /// key_access.jsonl when `hosts` was built from it
+    #[serde(default)]
+    key_access: Option<FileStamp>,
+    /// Host aliases by fingerprint
+    #[serde(default)]
+    hosts: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    updated_at: i64,
+}
+
+/// Local key file with a given fingerprint
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedKeyFile {
+    pub name: String,
+    pub public_key_path: String,
+}
+
+/// Everything known about a fingerprint
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintIndexEntry {
+    pub fingerprint: String,
+    pub key_files: Vec<IndexedKeyFile>,
+    /// Comment of the agent identity (None when not loaded in the agent)
+    pub agent_comment: Option<String>,
+    /// Hosts the key was deployed to or found on
+    pub hosts: Vec<String>,
+}
+
+/// Outcome of an index refresh
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexRefresh {
+    pub key_files: usize,
+    /// Key files read again because they are new or changed
+    pub rehashed: usize,
+    pub fingerprints: usize,
+    pub updated_at: i64,
+}
+
+/// Serializes refreshes (watcher events and the agent interval can overlap)
+static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Persistent index from key fingerprints to local key files, agent identities and
+/// hosts; refreshed incrementally so lookups never re-hash keys
+pub struct FingerprintIndexService;
+
+impl FingerprintIndexService {
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("fingerprint_index.json")
+    }
+
+    async fn load_store() -> SshResult<IndexStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(IndexStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse fingerprint index: {}", e),
+        })
+    }
+
+    async fn save_store(store: &IndexStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Bring the index up to date: only new or changed key files are hashed, and the
+    /// host records are re-read only when they changed
+    pub async fn refresh() -> SshResult<IndexRefresh> {
+        let _guard = STORE_LOCK.lock().await;
+        let mut store = Self::load_store().await?;
+
+        let (key_files, rehashed) = index_key_files(
+            &ssh_dir()?,
+            &store.key_files,
+            WorkspaceService::is_key_visible,
+        )
+        .await?;
+        store.key_files = key_files;
+
+        store.agent = match AgentService::list_keys().await {
+            Ok(keys) => keys
+                .into_iter()
+                .map(|key| AgentIdentity {
+                    fingerprint: key.fingerprint,
+                    comment: key.comment,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::debug!("[fingerprint_index] Agent not available: {}", e);
+                Vec::new()
+            }
+        };
+
+        let access_path = workspace_data_path("key_access.jsonl")?;
+        let access_stamp = fs::metadata(&access_path)
+            .await
+            .ok()
+            .map(|m| FileStamp::of(&m));
+        if access_stamp.is_none() || access_stamp != store.key_access {
+            store.hosts = hosts_by_fingerprint(&KeyExposureService::records().await?);
+            store.key_access = access_stamp;
+        }
+
+        store.updated_at = now_millis();
+        Self::save_store(&store).await?;
+        let refresh = IndexRefresh {
+            key_files: store.key_files.len(),
+            rehashed,
+            fingerprints: assemble(&store).len(),
+            updated_at: store.updated_at,
+        };
+        if rehashed > 0 {
+            tracing::info!(
+                "[fingerprint_index] Hashed {} key file(s), {} fingerprint(s) indexed",
+                rehashed,
+                refresh.fingerprints
+            );
+        }
+        Ok(refresh)
+    }
+
+    /// All indexed fingerprints, as of the last refresh
+    pub async fn entries() -> SshResult<Vec<FingerprintIndexEntry>> {
+        Ok(assemble(&Self::load_store().await?))
+    }
+
+    /// What is known about one fingerprint
+    pub async fn lookup(fingerprint: &str) -> SshResult<Option<FingerprintIndexEntry>> {
+        Ok(Self::entries()
+            .await?
+            .into_iter()
+            .find(|entry| entry.fingerprint == fingerprint))
+    }
+
+    /// Local key names by fingerprint (the first file when several hold the same key)
+    /// Key files are checked first, so only keys changed since the last refresh are hashed
+    pub async fn local_key_names() -> SshResult<BTreeMap<String, String>> {
+        let _guard = STORE_LOCK.lock().await;
+        let mut store = Self::load_store().await?;
+        let (key_files, _) = index_key_files(
+            &ssh_dir()?,
+            &store.key_files,
+            WorkspaceService::is_key_visible,
+        )
+        .await?;
+        if key_files != store.key_files {
+            store.key_files = key_files;
+            Self::save_store(&store).await?;
+        }
+
+        let mut names = BTreeMap::new();
+        for file in store.key_files.values() {
+            if let Some(fingerprint) = &file.fingerprint {
+                names
+                    .entry(fingerprint.clone())
+                    .or_insert_with(|| file.name.clone());
+            }
+        }
+        Ok(names)
+    }
+
+    /// Refresh on key directory and key access changes, and periodically for the agent
+    /// (spawned once at app setup)
+    pub async fn run() {
+        let mut events = WatcherService::global().subscribe();
+        loop {
+            if let Err(e) = Self::refresh().await {
+                tracing::warn!("[fingerprint_index] Refresh failed: {}", e);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(AGENT_REFRESH_INTERVAL) => {}
+                alive = wait_for_key_event(&mut events) => {
+                    if !alive {
+                        // Watcher gone; fall back to the interval
+                        tokio::time::sleep(AGENT_REFRESH_INTERVAL).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Wait for a change of the key files or key access records; false if the watcher is gone
+async fn wait_for_key_event(events: &mut tokio::sync::broadcast::Receiver<WatchedFile>) -> bool {
+    loop {
+        match events.recv().await {
+            Ok(WatchedFile::SshDir | WatchedFile::KeyAccess) => return true,
+            Ok(_) => continue,
+            Err(RecvError::Lagged(_)) => return true,
+            Err(RecvError::Closed) => return false,
+        }
+    }
+}
+
+/// Stat the `.pub` files of a directory, reusing the previous fingerprint of each file
+/// whose size and modification time didn't change; returns the files and how many
+/// were hashed
+async fn index_key_files(
+    dir: &Path,
+    previous: &BTreeMap<String, IndexedFile>,
+    visible: fn(&str) -> bool,
+) -> SshResult<(BTreeMap<String, IndexedFile>, usize)> {
+    let mut files = BTreeMap::new();
+    let mut rehashed = 0;
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return Ok((files, rehashed));
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !path.extension().is_some_and(|ext| ext == "pub") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !visible(name) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        let stamp = FileStamp::of(&metadata);
+        let key = path.to_string_lossy().to_string();
+        let fingerprint = match previous.get(&key).filter(|file| file.stamp == stamp) {
+            Some(file) => file.fingerprint.clone(),
+            None => {
+                rehashed += 1;
+                fs::read_to_string(&path)
+                    .await
+                    .ok()
+                    .and_then(|content| PublicKey::from_openssh(content.trim()).ok())
+                    .map(|key| key.fingerprint(HashAlg::Sha256).to_string())
+            }
+        };
+        files.insert(
+            key,
+            IndexedFile {
+                name: name.to_string(),
+                stamp,
+                fingerprint,
+            },
+        );
+    }
+    Ok((files, rehashed))
+}
+
+/// Host aliases by fingerprint, sorted and without duplicates
+fn hosts_by_fingerprint(records: &[KeyAccessRecord]) -> BTreeMap<String, Vec<String>> {
+    let mut hosts: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for record in records {
+        hosts
+            .entry(record.fingerprint.clone())
+            .or_default()
+            .insert(record.host_alias.clone());
+    }
+    hosts
+        .into_iter()
+        .map(|(fingerprint, aliases)| (fingerprint, aliases.into_iter().collect()))
+        .collect()
+}
+
+/// One entry per fingerprint seen in any source, sorted by fingerprint
+fn assemble(store: &IndexStore) -> Vec<FingerprintIndexEntry> {
+    fn entry<'a>(
+        entries: &'a mut BTreeMap<String, FingerprintIndexEntry>,
+        fingerprint: &str,
+    ) -> &'a mut FingerprintIndexEntry {
+        entries
+            .entry(fingerprint.to_string())
+            .or_insert_with(|| FingerprintIndexEntry {
+                fingerprint: fingerprint.to_string(),
+                key_files: Vec::new(),
+                agent_comment: None,
+                hosts: Vec::new(),
+            })
+    }
+
+    let mut entries: BTreeMap<String, FingerprintIndexEntry> = BTreeMap::new();
+    for (path, file) in &store.key_files {
+        if let Some(fingerprint) = &file.fingerprint {
+            entry(&mut entries, fingerprint)
+                .key_files
+                .push(IndexedKeyFile {
+                    name: file.name.clone(),
+                    public_key_path: path.clone(),
+                });
+        }
+    }
+    for identity in &store.agent {
+        entry(&mut entries, &identity.fingerprint).agent_comment = Some(identity.comment.clone());
+    }
+    for (fingerprint, hosts) in &store.hosts {
+        entry(&mut entries, fingerprint).hosts = hosts.clone();
+    }
+    entries.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::key_exposure::KeyAccessSource;
+    use tempfile::TempDir;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl a@b";
+
+    fn all_visible(_: &str) -> bool {
+        true
+    }
+
+    fn record(fingerprint: &str, host_alias: &str) -> KeyAccessRecord {
+        KeyAccessRecord {
+            fingerprint: fingerprint.to_string(),
+            host_alias: host_alias.to_string(),
+            source: KeyAccessSource::Deploy,
+            recorded_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_key_files_reuses_unchanged() {
+        let dir = TempDir::new().unwrap();
+        tokio::fs::write(dir.path().join("id_ed25519.pub"), KEY)
+            .await
+            .unwrap();
+        tokio::fs::write(dir.path().join("broken.pub"), "not a key")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.path().join("id_ed25519"), "private")
+            .await
+            .unwrap();
+
+        let (files, rehashed) = index_key_files(dir.path(), &BTreeMap::new(), all_visible)
+            .await
+            .unwrap();
+        assert_eq!((files.len(), rehashed), (2, 2));
+        let key = files.values().find(|f| f.name == "id_ed25519").unwrap();
+        assert!(key.fingerprint.as_deref().unwrap().starts_with("SHA256:"));
+
+        let (again, rehashed) = index_key_files(dir.path(), &files, all_visible)
+            .await
+            .unwrap();
+        assert_eq!(again, files);
+        assert_eq!(rehashed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_index_key_files_skips_hidden() {
+        let dir = TempDir::new().unwrap();
+        tokio::fs::write(dir.path().join("id_ed25519.pub"), KEY)
+            .await
+            .unwrap();
+        let (files, _) = index_key_files(dir.path(), &BTreeMap::new(), |_| false)
+            .await
+            .unwrap();
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_assemble_merges_sources() {
+        let mut store = IndexStore::default();
+        store.key_files.insert(
+            "/k/a.pub".to_string(),
+            IndexedFile {
+                name: "a".to_string(),
+                stamp: FileStamp {
+                    modified: 1,
+                    len: 1,
+                },
+                fingerprint: Some("SHA256:a".to_string()),
+            },
+        );
+        store.agent.push(AgentIdentity {
+            fingerprint: "SHA256:a".to_string(),
+            comment: "laptop".to_string(),
+        });
+        store.agent.push(AgentIdentity {
+            fingerprint: "SHA256:b".to_string(),
+            comment: "forwarded".to_string(),
+        });
+        store.hosts = hosts_by_fingerprint(&[
+            record("SHA256:a", "web"),
+            record("SHA256:a", "db"),
+            record("SHA256:a", "web"),
+        ]);
+
+        let entries = assemble(&store);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key_files[0].name, "a");
+        assert_eq!(entries[0].agent_comment.as_deref(), Some("laptop"));
+        assert_eq!(entries[0].hosts, vec!["db", "web"]);
+        assert!(entries[1].key_files.is_empty());
+        assert!(entries[1].hosts.is_empty());
+    }
+}
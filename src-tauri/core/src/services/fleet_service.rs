@@ -0,0 +1,447 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::audit_service::{AuditKind, AuditService};
+use crate::services::config_service::ConfigService;
+use crate::services::privacy_service::PrivacyService;
+use crate::services::registry_service::{now_millis, RegistryService};
+use crate::services::ssh_connection::OutputStream;
+use crate::services::ssh_engine::SshEngineService;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::sync::Semaphore;
+
+/// Hosts run at the same time unless the request says otherwise
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Upper bound for the requested concurrency
+const MAX_CONCURRENCY: usize = 32;
+
+/// Per-host command timeout unless the request says otherwise
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Fleet run request (hosts from a tag group and/or an explicit alias list)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetRequest {
+    pub command: String,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub host_aliases: Vec<String>,
+    pub concurrency: Option<usize>,
+    pub timeout_secs: Option<u64>,
+}
+
+/// Progress event of a fleet run
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetEvent {
+    pub run_id: String,
+    pub host_alias: String,
+    #[serde(flatten)]
+    pub kind: FleetEventKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FleetEventKind {
+    Started,
+    Output {
+        stream: OutputStream,
+        data: String,
+    },
+    Finished {
+        success: bool,
+        exit_code: Option<u32>,
+    },
+}
+
+/// Result of the command on one host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetHostResult {
+    pub host_alias: String,
+    pub success: bool,
+    pub exit_code: Option<u32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Connection/authentication error (the command never ran)
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Hosts that produced the same output
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputGroup {
+    pub output: String,
+    pub success: bool,
+    pub hosts: Vec<String>,
+}
+
+/// Aggregated result of a fleet run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetSummary {
+    pub run_id: String,
+    pub command: String,
+    /// Unix timestamp in milliseconds
+    pub started_at: i64,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<FleetHostResult>,
+    /// Largest group first
+    pub groups: Vec<OutputGroup>,
+}
+
+/// Summary export format
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FleetExportFormat {
+    Json,
+    Csv,
+}
+
+/// Runs one command across many hosts
+pub struct FleetService;
+
+impl FleetService {
+    /// Resolve the target hosts: tag members plus explicit aliases, limited to
+    /// hosts that still exist in ~/.ssh/config
+    pub(crate) async fn resolve_targets(request: &FleetRequest) -> SshResult<Vec<String>> {
+        let mut targets = request.host_aliases.clone();
+        if let Some(tag) = request.tag.as_deref().filter(|t| !t.is_empty()) {
+            let store = RegistryService::load().await?;
+            targets.extend(
+                store
+                    .hosts
+                    .into_iter()
+                    .filter(|(_, metadata)| metadata.tags.iter().any(|t| t == tag))
+                    .map(|(alias, _)| alias),
+            );
+        }
+
+        let configured = ConfigService::load_editor().await?.host_aliases();
+        targets.retain(|alias| configured.contains(alias));
+        targets.sort();
+        targets.dedup();
+        Ok(targets)
+    }
+
+    /// Run the command on every target host, reporting progress through `on_event`
+    /// Hosts that need interactive authentication fail instead of prompting
+    pub async fn run<F>(request: FleetRequest, on_event: F) -> SshResult<FleetSummary>
+    where
+        F: Fn(FleetEvent) + Send + Sync + 'static,
+    {
+        let command = request.command.trim().to_string();
+        if command.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Command must not be empty".to_string(),
+            });
+        }
+        let targets = Self::resolve_targets(&request).await?;
+        if targets.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "No hosts match the fleet selection".to_string(),
+            });
+        }
+
+        let run_id = format!("{:016x}", rand::random::<u64>());
+        let started_at = now_millis();
+        let concurrency = request
+            .concurrency
+            .unwrap_or(DEFAULT_CONCURRENCY)
+            .clamp(1, MAX_CONCURRENCY);
+        let limit = Duration::from_secs(request.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        tracing::info!(
+            "[fleet_service] Run {} on {} host(s), concurrency {}",
+            run_id,
+            targets.len(),
+            concurrency
+        );
+        let recorded = if PrivacyService::get_settings().await.record_commands {
+            format!("`{}`", command)
+        } else {
+            "A command".to_string()
+        };
+        AuditService::record(
+            AuditKind::CommandRun,
+            &format!("fleet {}", run_id),
+            &format!("{} on {}", recorded, targets.join(", ")),
+        )
+        .await;
+
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let on_event = Arc::new(on_event);
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|alias| {
+                let semaphore = semaphore.clone();
+                let on_event = on_event.clone();
+                let run_id = run_id.clone();
+                let alias = alias.clone();
+                let command = command.clone();
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    let emit = |kind| {
+                        on_event(FleetEvent {
+                            run_id: run_id.clone(),
+                            host_alias: alias.clone(),
+                            kind,
+                        })
+                    };
+                    emit(FleetEventKind::Started);
+                    let result = Self::run_on_host(&alias, &command, limit, |stream, data| {
+                        emit(FleetEventKind::Output {
+                            stream,
+                            data: data.to_string(),
+                        })
+                    })
+                    .await;
+                    emit(FleetEventKind::Finished {
+                        success: result.success,
+                        exit_code: result.exit_code,
+                    });
+                    result
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(handles.len());
+        for (alias, handle) in targets.iter().zip(handles) {
+            results.push(handle.await.unwrap_or_else(|e| FleetHostResult {
+                host_alias: alias.clone(),
+                success: false,
+                exit_code: None,
+                stdout: String::new(),
+                stderr: String::new(),
+                error: Some(format!("Task failed: {}", e)),
+                duration_ms: 0,
+            }));
+        }
+
+        let summary = summarize(run_id, command, started_at, results);
+        tracing::info!(
+            "[fleet_service] Run {} finished: {} succeeded, {} failed",
+            summary.run_id,
+            summary.succeeded,
+            summary.failed
+        );
+        Ok(summary)
+    }
+
+    /// Connect, run and disconnect; errors end up in the host result
+    async fn run_on_host<F>(
+        alias: &str,
+        command: &str,
+        limit: Duration,
+        on_output: F,
+    ) -> FleetHostResult
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let started = Instant::now();
+        let outcome = SshEngineService::exec(alias, command, limit, on_output).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        match outcome {
+            Ok(output) => FleetHostResult {
+                host_alias: alias.to_string(),
+                success: output.success(),
+                exit_code: output.exit_code,
+                stdout: output.stdout,
+                stderr: output.stderr,
+                error: None,
+                duration_ms,
+            },
+            Err(e) => FleetHostResult {
+                host_alias: alias.to_string(),
+                success: false,
+                exit_code: None,
+                stdout: String::new(),
+                stderr: String::new(),
+                error: Some(e.to_string()),
+                duration_ms,
+            },
+        }
+    }
+
+    /// Write a summary to a file as JSON or CSV
+    pub async fn export_summary(
+        summary: &FleetSummary,
+        format: FleetExportFormat,
+        destination: &str,
+    ) -> SshResult<String> {
+        let path = PathBuf::from(destination);
+        if !path.is_absolute() {
+            return Err(SshBuddyError::InvalidPath {
+                message: format!("Export path must be absolute: {}", destination),
+            });
+        }
+
+        let content = match format {
+            FleetExportFormat::Json => {
+                serde_json::to_string_pretty(summary).map_err(|e| SshBuddyError::Unknown {
+                    message: e.to_string(),
+                })?
+            }
+            FleetExportFormat::Csv => summary_to_csv(summary),
+        };
+        fs::write(&path, content)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to write fleet summary: {}", e),
+            })?;
+
+        tracing::info!(
+            "[fleet_service] Exported run {} to {:?}",
+            summary.run_id,
+            path
+        );
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+/// Count successes and group hosts with identical output
+/// Failed connections are grouped by their error message
+fn summarize(
+    run_id: String,
+    command: String,
+    started_at: i64,
+    results: Vec<FleetHostResult>,
+) -> FleetSummary {
+    let mut groups: Vec<OutputGroup> = Vec::new();
+    for result in &results {
+        let output = match &result.error {
+            Some(error) => error.clone(),
+            None if result.success || !result.stdout.trim().is_empty() => {
+                result.stdout.trim().to_string()
+            }
+            None => result.stderr.trim().to_string(),
+        };
+        match groups
+            .iter_mut()
+            .find(|g| g.output == output && g.success == result.success)
+        {
+            Some(group) => group.hosts.push(result.host_alias.clone()),
+            None => groups.push(OutputGroup {
+                output,
+                success: result.success,
+                hosts: vec![result.host_alias.clone()],
+            }),
+        }
+    }
+    // Stable sort keeps first-seen order between groups of the same size
+    groups.sort_by(|a, b| b.hosts.len().cmp(&a.hosts.len()));
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    FleetSummary {
+        run_id,
+        command,
+        started_at,
+        total: results.len(),
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+        groups,
+    }
+}
+
+/// Quote a CSV field when needed (RFC 4180)
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row per host
+fn summary_to_csv(summary: &FleetSummary) -> String {
+    let mut csv = String::from("host,success,exit_code,duration_ms,error,stdout,stderr\n");
+    for result in &summary.results {
+        let row = [
+            csv_field(&result.host_alias),
+            result.success.to_string(),
+            result.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            result.duration_ms.to_string(),
+            csv_field(result.error.as_deref().unwrap_or_default()),
+            csv_field(&result.stdout),
+            csv_field(&result.stderr),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(
+        alias: &str,
+        exit_code: Option<u32>,
+        stdout: &str,
+        error: Option<&str>,
+    ) -> FleetHostResult {
+        FleetHostResult {
+            host_alias: alias.to_string(),
+            success: exit_code == Some(0),
+            exit_code,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+            error: error.map(str::to_string),
+            duration_ms: 5,
+        }
+    }
+
+    #[test]
+    fn test_summarize_groups_identical_output() {
+        let summary = summarize(
+            "run".to_string(),
+            "uname -r".to_string(),
+            0,
+            vec![
+                result("a", Some(0), "6.1.0\n", None),
+                result("b", Some(0), "6.8.0\n", None),
+                result("c", Some(0), "6.8.0", None),
+                result("d", None, "", Some("Connection timeout")),
+            ],
+        );
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.succeeded, 3);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.groups.len(), 3);
+        assert_eq!(summary.groups[0].output, "6.8.0");
+        assert_eq!(summary.groups[0].hosts, vec!["b", "c"]);
+        assert!(!summary.groups[2].success);
+    }
+
+    #[test]
+    fn test_csv_escaping() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\"\n"), "\"say \"\"hi\"\"\n\"");
+    }
+
+    #[test]
+    fn test_summary_to_csv() {
+        let summary = summarize(
+            "run".to_string(),
+            "id".to_string(),
+            0,
+            vec![result("web-1", Some(0), "uid=0(root)", None)],
+        );
+        let csv = summary_to_csv(&summary);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "host,success,exit_code,duration_ms,error,stdout,stderr"
+        );
+        assert_eq!(lines[1], "web-1,true,0,5,,uid=0(root),");
+    }
+}
@@ -0,0 +1,295 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::script_service::shell_quote;
+use crate::services::shell_history::split_words;
+use crate::services::ssh_installations::resolve_program;
+use crate::utils::resolve_ssh_root;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Client shipped with Windows 10/11
+const WINDOWS_OPENSSH: &str = r"C:\Windows\System32\OpenSSH\ssh.exe";
+
+/// Where an sshCommand setting comes from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitSshSource {
+    /// GIT_SSH_COMMAND environment variable (overrides the config)
+    Environment,
+    /// GIT_SSH environment variable (a program path, no arguments)
+    EnvironmentProgram,
+    /// core.sshCommand in ~/.gitconfig
+    Global,
+    /// core.sshCommand in the system gitconfig
+    System,
+}
+
+/// Parts of an sshCommand the assistant understands
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSshCommandSpec {
+    /// ssh client to run; None runs `ssh` from PATH
+    pub program: Option<String>,
+    pub identity_file: Option<String>,
+    /// Offer only the given identity (-o IdentitiesOnly=yes)
+    #[serde(default)]
+    pub identities_only: bool,
+}
+
+/// git's ssh settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSshStatus {
+    pub git_available: bool,
+    pub environment: Option<String>,
+    pub environment_program: Option<String>,
+    pub global: Option<String>,
+    pub system: Option<String>,
+    /// Setting git actually uses
+    pub effective_source: Option<GitSshSource>,
+    /// Effective command parsed (None if it uses options the assistant doesn't edit)
+    pub effective_spec: Option<GitSshCommandSpec>,
+    /// Windows OpenSSH client, if installed
+    pub windows_open_ssh: Option<String>,
+}
+
+/// Reads and updates git's core.sshCommand
+pub struct GitSshCommandService;
+
+impl GitSshCommandService {
+    fn git_config(args: &[&str]) -> SshResult<Option<String>> {
+        let output = Command::new("git")
+            .arg("config")
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to run git: {}", e),
+            })?;
+        match output.status.code() {
+            Some(0) => Ok(Some(
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            )),
+            // 1 = key not set (--get), 5 = nothing to unset (--unset)
+            Some(1) | Some(5) => Ok(None),
+            _ => Err(SshBuddyError::IoError {
+                message: format!(
+                    "git config failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            }),
+        }
+    }
+
+    /// Current settings and which one git uses
+    pub async fn status() -> SshResult<GitSshStatus> {
+        tokio::task::spawn_blocking(|| {
+            let git_available = Command::new("git")
+                .arg("--version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success());
+            let env = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+            let (global, system) = if git_available {
+                (
+                    Self::git_config(&["--global", "--get", "core.sshCommand"])?,
+                    Self::git_config(&["--system", "--get", "core.sshCommand"])?,
+                )
+            } else {
+                (None, None)
+            };
+            let mut status = GitSshStatus {
+                git_available,
+                environment: env("GIT_SSH_COMMAND"),
+                environment_program: env("GIT_SSH"),
+                global,
+                system,
+                effective_source: None,
+                effective_spec: None,
+                windows_open_ssh: Path::new(WINDOWS_OPENSSH)
+                    .is_file()
+                    .then(|| WINDOWS_OPENSSH.to_string()),
+            };
+            let effective = [
+                (GitSshSource::Environment, &status.environment),
+                (
+                    GitSshSource::EnvironmentProgram,
+                    &status.environment_program,
+                ),
+                (GitSshSource::Global, &status.global),
+                (GitSshSource::System, &status.system),
+            ]
+            .into_iter()
+            .find_map(|(source, value)| Some((source, value.clone()?)));
+            if let Some((source, value)) = effective {
+                status.effective_source = Some(source);
+                status.effective_spec = if source == GitSshSource::EnvironmentProgram {
+                    Some(GitSshCommandSpec {
+                        program: Some(value),
+                        ..Default::default()
+                    })
+                } else {
+                    parse_command(&value)
+                };
+            }
+            Ok(status)
+        })
+        .await
+        .map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?
+    }
+
+    /// Validate a spec and build the sshCommand for it
+    pub fn build(spec: &GitSshCommandSpec) -> SshResult<String> {
+        if let Some(program) = spec.program.as_deref().map(str::trim) {
+            if resolve_program(&resolve_ssh_root(program, None).to_string_lossy()).is_none() {
+                return Err(SshBuddyError::InvalidPath {
+                    message: format!("ssh client not found: {}", program),
+                });
+            }
+        }
+        if let Some(identity) = spec.identity_file.as_deref().map(str::trim) {
+            if identity.ends_with(".pub") {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "Use the private key, not the .pub file".to_string(),
+                });
+            }
+            if !resolve_ssh_root(identity, None).is_file() {
+                return Err(SshBuddyError::KeyNotFound {
+                    path: identity.to_string(),
+                });
+            }
+        }
+        Ok(build_command(spec))
+    }
+
+    /// Set (or with None, remove) the global core.sshCommand
+    pub async fn set_global(spec: Option<GitSshCommandSpec>) -> SshResult<GitSshStatus> {
+        ReadOnlyMode::ensure_writable("change git's sshCommand")?;
+        let command = spec.as_ref().map(Self::build).transpose()?;
+        let args = command.clone();
+        tokio::task::spawn_blocking(move || match &args {
+            Some(command) => {
+                Self::git_config(&["--global", "core.sshCommand", command.as_str()]).map(drop)
+            }
+            None => Self::git_config(&["--global", "--unset", "core.sshCommand"]).map(drop),
+        })
+        .await
+        .map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })??;
+        tracing::info!(
+            "[git_ssh_command] Global core.sshCommand {}",
+            command.as_deref().unwrap_or("removed")
+        );
+        Self::status().await
+    }
+}
+
+/// sshCommand for a spec; paths use forward slashes since git runs it through sh,
+/// also on Windows
+fn build_command(spec: &GitSshCommandSpec) -> String {
+    let path = |p: &str| shell_quote(&p.trim().replace('\\', "/"));
+    let mut words = vec![spec
+        .program
+        .as_deref()
+        .map(path)
+        .unwrap_or_else(|| "ssh".to_string())];
+    if let Some(identity) = &spec.identity_file {
+        words.push("-i".to_string());
+        words.push(path(identity));
+    }
+    if spec.identities_only {
+        words.push("-o".to_string());
+        words.push("IdentitiesOnly=yes".to_string());
+    }
+    words.join(" ")
+}
+
+/// Spec of a command built like `build_command` (None for anything else)
+fn parse_command(command: &str) -> Option<GitSshCommandSpec> {
+    let words = split_words(command);
+    let (program, args) = words.split_first()?;
+    let mut spec = GitSshCommandSpec {
+        program: (program != "ssh").then(|| program.clone()),
+        ..Default::default()
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-i" => spec.identity_file = Some(args.next()?.clone()),
+            "-o" if args.next()?.eq_ignore_ascii_case("IdentitiesOnly=yes") => {
+                spec.identities_only = true
+            }
+            _ => return None,
+        }
+    }
+    Some(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_command() {
+        let spec = GitSshCommandSpec {
+            program: Some(r"C:\Windows\System32\OpenSSH\ssh.exe".to_string()),
+            identity_file: Some(r"C:\Users\Jo Doe\.ssh\id_work".to_string()),
+            identities_only: true,
+        };
+        let command = build_command(&spec);
+        assert_eq!(
+            command,
+            "C:/Windows/System32/OpenSSH/ssh.exe -i 'C:/Users/Jo Doe/.ssh/id_work' -o IdentitiesOnly=yes"
+        );
+        assert_eq!(
+            parse_command(&command),
+            Some(GitSshCommandSpec {
+                program: Some("C:/Windows/System32/OpenSSH/ssh.exe".to_string()),
+                identity_file: Some("C:/Users/Jo Doe/.ssh/id_work".to_string()),
+                identities_only: true,
+            })
+        );
+        assert_eq!(build_command(&GitSshCommandSpec::default()), "ssh");
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("ssh -i ~/.ssh/id_github"),
+            Some(GitSshCommandSpec {
+                program: None,
+                identity_file: Some("~/.ssh/id_github".to_string()),
+                identities_only: false,
+            })
+        );
+        assert_eq!(parse_command("ssh -F /dev/null"), None);
+        assert_eq!(parse_command("ssh -i"), None);
+    }
+
+    #[test]
+    fn test_build_validates() {
+        let missing_key = GitSshCommandSpec {
+            identity_file: Some("/nonexistent/id_ed25519".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            GitSshCommandService::build(&missing_key),
+            Err(SshBuddyError::KeyNotFound { .. })
+        ));
+        let public_key = GitSshCommandSpec {
+            identity_file: Some("~/.ssh/id_ed25519.pub".to_string()),
+            ..Default::default()
+        };
+        assert!(GitSshCommandService::build(&public_key).is_err());
+        let missing_program = GitSshCommandSpec {
+            program: Some("/nonexistent/bin/ssh".to_string()),
+            ..Default::default()
+        };
+        assert!(GitSshCommandService::build(&missing_program).is_err());
+    }
+}
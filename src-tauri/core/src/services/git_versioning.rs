@@ -0,0 +1,323 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::revision_service::{ManagedFile, RevisionService};
+use crate::utils::{workspace_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tokio::fs;
+
+/// Only these files are ever copied into the history repository (never private keys)
+const GITIGNORE: &str = "*\n!.gitignore\n!config\n!known_hosts\n";
+
+/// Git versioning status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitVersioningStatus {
+    pub git_available: bool,
+    pub enabled: bool,
+    pub repo_path: String,
+    pub commit_count: usize,
+}
+
+/// Commit in the history repository
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitInfo {
+    pub id: String,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    pub message: String,
+}
+
+/// Keeps a local git repository with copies of the managed files
+/// The repository lives in the app data directory; ~/.ssh itself is never turned into a repo
+pub struct GitVersioningService;
+
+impl GitVersioningService {
+    /// Get the history repository path (one per workspace)
+    fn get_repo_path() -> SshResult<PathBuf> {
+        workspace_data_path("config-history")
+    }
+
+    /// Run git in the repository and return stdout
+    async fn git(repo: &Path, args: &[&str]) -> SshResult<String> {
+        let repo = repo.to_path_buf();
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+
+        let output = tokio::task::spawn_blocking(move || {
+            Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args([
+                    "-c",
+                    "user.name=SSH Buddy",
+                    "-c",
+                    "user.email=ssh-buddy@localhost",
+                ])
+                .args(["-c", "commit.gpgsign=false"])
+                .args(&args)
+                .stdin(Stdio::null())
+                .output()
+        })
+        .await
+        .map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?
+        .map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to run git: {}", e),
+        })?;
+
+        if !output.status.success() {
+            return Err(SshBuddyError::IoError {
+                message: format!(
+                    "git failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Check whether versioning is enabled (the repository exists)
+    fn is_enabled(repo: &Path) -> bool {
+        repo.join(".git").exists()
+    }
+
+    /// Reject anything that isn't a commit hash (keeps user input out of git options)
+    fn validate_commit_id(commit_id: &str) -> SshResult<()> {
+        let valid =
+            (4..=40).contains(&commit_id.len()) && commit_id.chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(SshBuddyError::InvalidPath {
+                message: format!("Invalid commit id: {}", commit_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// Get versioning status
+    pub async fn status() -> SshResult<GitVersioningStatus> {
+        let repo = Self::get_repo_path()?;
+        let git_available = Command::new("git")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        let enabled = Self::is_enabled(&repo);
+
+        let commit_count = if enabled && git_available {
+            Self::git(&repo, &["rev-list", "--count", "HEAD"])
+                .await
+                .ok()
+                .and_then(|out| out.trim().parse().ok())
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        Ok(GitVersioningStatus {
+            git_available,
+            enabled,
+            repo_path: repo.to_string_lossy().to_string(),
+            commit_count,
+        })
+    }
+
+    /// Enable versioning: create the repository and commit the current files
+    pub async fn enable() -> SshResult<GitVersioningStatus> {
+        ReadOnlyMode::ensure_writable("enable git versioning")?;
+        let repo = Self::get_repo_path()?;
+        let disabled_git_dir = repo.join(".git-disabled");
+        if !Self::is_enabled(&repo) && disabled_git_dir.exists() {
+            // Resume the previous history and catch up with changes made meanwhile
+            fs::rename(&disabled_git_dir, repo.join(".git")).await?;
+            for file in [ManagedFile::SshConfig, ManagedFile::KnownHosts] {
+                let live_path = file.live_path()?;
+                if live_path.exists() {
+                    let content = fs::read_to_string(&live_path).await?;
+                    Self::commit_file(file, &content, "Changes made while versioning was off")
+                        .await;
+                }
+            }
+        } else if !Self::is_enabled(&repo) {
+            fs::create_dir_all(&repo).await?;
+            Self::git(&repo, &["init", "--quiet"]).await?;
+            fs::write(repo.join(".gitignore"), GITIGNORE).await?;
+
+            for file in [ManagedFile::SshConfig, ManagedFile::KnownHosts] {
+                let live_path = file.live_path()?;
+                if live_path.exists() {
+                    fs::copy(&live_path, repo.join(file.id())).await?;
+                }
+            }
+            Self::git(&repo, &["add", "--all"]).await?;
+            Self::git(
+                &repo,
+                &[
+                    "commit",
+                    "--quiet",
+                    "--allow-empty",
+                    "-m",
+                    "Initial snapshot",
+                ],
+            )
+            .await?;
+            tracing::info!("[git_versioning] Enabled at {:?}", repo);
+        }
+        Self::status().await
+    }
+
+    /// Disable versioning; the history is deleted only if asked to
+    pub async fn disable(delete_history: bool) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("disable git versioning")?;
+        let repo = Self::get_repo_path()?;
+        if !Self::is_enabled(&repo) {
+            return Ok(());
+        }
+
+        if delete_history {
+            fs::remove_dir_all(&repo).await?;
+        } else {
+            // Keep the history but stop committing to it
+            fs::rename(repo.join(".git"), repo.join(".git-disabled")).await?;
+        }
+        tracing::info!(
+            "[git_versioning] Disabled (history deleted: {})",
+            delete_history
+        );
+        Ok(())
+    }
+
+    /// Commit a new version of a managed file if versioning is enabled
+    /// Failures are logged but never fail the write itself
+    pub async fn commit_file(file: ManagedFile, content: &str, message: &str) {
+        let Ok(repo) = Self::get_repo_path() else {
+            return;
+        };
+        if !Self::is_enabled(&repo) {
+            return;
+        }
+
+        let result = async {
+            fs::write(repo.join(file.id()), content).await?;
+            Self::git(&repo, &["add", "--", file.id()]).await?;
+            // Nothing staged means the content didn't change
+            if Self::git(&repo, &["diff", "--cached", "--quiet"])
+                .await
+                .is_ok()
+            {
+                return Ok(());
+            }
+            Self::git(&repo, &["commit", "--quiet", "-m", message])
+                .await
+                .map(|_| ())
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("[git_versioning] Failed to commit {}: {}", file.id(), e);
+        }
+    }
+
+    /// Get the commit log, newest first
+    pub async fn log(limit: usize) -> SshResult<Vec<GitCommitInfo>> {
+        let repo = Self::get_repo_path()?;
+        if !Self::is_enabled(&repo) {
+            return Ok(Vec::new());
+        }
+
+        let limit = limit.to_string();
+        let output = Self::git(&repo, &["log", "--format=%H%x1f%ct%x1f%s", "-n", &limit]).await?;
+        Ok(parse_log(&output))
+    }
+
+    /// Get the patch of a commit
+    pub async fn show(commit_id: &str) -> SshResult<String> {
+        Self::validate_commit_id(commit_id)?;
+        let repo = Self::get_repo_path()?;
+        Self::git(&repo, &["show", "--format=", commit_id]).await
+    }
+
+    /// Read a managed file's content at a commit
+    pub async fn read_at(commit_id: &str, file: ManagedFile) -> SshResult<String> {
+        Self::validate_commit_id(commit_id)?;
+        let repo = Self::get_repo_path()?;
+        if !Self::is_enabled(&repo) {
+            return Err(SshBuddyError::IoError {
+                message: "Git versioning is not enabled".to_string(),
+            });
+        }
+        Self::git(&repo, &["show", &format!("{}:{}", commit_id, file.id())]).await
+    }
+
+    /// Restore a managed file to its content at a commit (recorded as a new change)
+    pub async fn revert_file(commit_id: &str, file: ManagedFile) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("revert config file")?;
+        let content = Self::read_at(commit_id, file).await?;
+        let live_path = file.live_path()?;
+        let previous = fs::read_to_string(&live_path).await.ok();
+
+        write_atomic(&live_path, content.as_bytes()).await?;
+        let short_id = &commit_id[..commit_id.len().min(7)];
+        RevisionService::record_change(
+            file,
+            previous.as_deref(),
+            &content,
+            &format!("Revert {} to {}", file.id(), short_id),
+        )
+        .await;
+        tracing::info!("[git_versioning] Reverted {} to {}", file.id(), short_id);
+        Ok(())
+    }
+}
+
+/// Parse `git log --format=%H%x1f%ct%x1f%s` output
+fn parse_log(output: &str) -> Vec<GitCommitInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\x1f');
+            Some(GitCommitInfo {
+                id: parts.next()?.to_string(),
+                timestamp: parts.next()?.parse().ok()?,
+                message: parts.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let output = "abc123\x1f1700000000\x1fUpdate SSH config\n\
+                      def456\x1f1690000000\x1fInitial snapshot\n\
+                      garbage line\n";
+        assert_eq!(
+            parse_log(output),
+            vec![
+                GitCommitInfo {
+                    id: "abc123".to_string(),
+                    timestamp: 1700000000,
+                    message: "Update SSH config".to_string(),
+                },
+                GitCommitInfo {
+                    id: "def456".to_string(),
+                    timestamp: 1690000000,
+                    message: "Initial snapshot".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_commit_id() {
+        assert!(GitVersioningService::validate_commit_id("abc1234").is_ok());
+        assert!(GitVersioningService::validate_commit_id("--output=/tmp/x").is_err());
+        assert!(GitVersioningService::validate_commit_id("abc").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::doctor_service::{
+    compare_findings, DoctorFinding, DoctorReport, DoctorService,
+};
+use crate::services::key_expiry::KeyExpiryService;
+use crate::services::permission_service::PermissionService;
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use crate::utils::{workspace_data_path, write_atomic, CronSchedule};
+use chrono::{Local, NaiveDateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::fs;
+
+/// Check a scheduled run can include
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthCheckKind {
+    /// Everything `run_doctor` checks
+    Doctor,
+    /// SSH directory and private key modes against the permission policy
+    Permissions,
+    /// Certificates that expired or expire soon
+    KeyExpiry,
+}
+
+/// Health check section of the settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HealthCheckSettings {
+    pub enabled: bool,
+    /// Cron expressions in local time (e.g. "0 9 * * 1-5")
+    pub schedules: Vec<String>,
+    pub checks: Vec<HealthCheckKind>,
+}
+
+impl Default for HealthCheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedules: vec!["0 9 * * *".to_string()],
+            checks: vec![
+                HealthCheckKind::Doctor,
+                HealthCheckKind::Permissions,
+                HealthCheckKind::KeyExpiry,
+            ],
+        }
+    }
+}
+
+impl HealthCheckSettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        self.parsed_schedules()?;
+        if self.enabled && (self.schedules.is_empty() || self.checks.is_empty()) {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Scheduled health checks need a schedule and at least one check"
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn parsed_schedules(&self) -> SshResult<Vec<CronSchedule>> {
+        self.schedules
+            .iter()
+            .map(|s| CronSchedule::parse(s))
+            .collect()
+    }
+}
+
+/// Result of a health check run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckRun {
+    /// Unix milliseconds
+    pub ran_at: i64,
+    /// Started by a schedule rather than by the user
+    pub scheduled: bool,
+    pub checks: Vec<HealthCheckKind>,
+    /// Most severe first
+    pub findings: Vec<DoctorFinding>,
+    /// Findings the previous run didn't have
+    pub new_findings: Vec<DoctorFinding>,
+}
+
+/// Last run and when the next one is due
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckStatus {
+    pub settings: HealthCheckSettings,
+    pub last_run: Option<HealthCheckRun>,
+    /// Unix milliseconds; None when disabled
+    pub next_run_at: Option<i64>,
+}
+
+/// Runs the doctor, permission audit and key expiry checks on cron schedules
+pub struct HealthCheckService;
+
+impl HealthCheckService {
+    fn get_last_run_path() -> SshResult<PathBuf> {
+        workspace_data_path("health_check.json")
+    }
+
+    /// Runs don't overlap, so each compares with the one before
+    fn lock() -> &'static tokio::sync::Mutex<()> {
+        static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+    }
+
+    pub fn get_settings() -> HealthCheckSettings {
+        SettingsService::get().health_checks
+    }
+
+    pub async fn set_settings(settings: &HealthCheckSettings) -> SshResult<()> {
+        settings.validate()?;
+        SettingsService::update(|current| {
+            current.health_checks = settings.clone();
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[health_check] Scheduled checks {} ({})",
+            if settings.enabled { "on" } else { "off" },
+            settings.schedules.join(", ")
+        );
+        Ok(())
+    }
+
+    pub async fn last_run() -> SshResult<Option<HealthCheckRun>> {
+        let path = Self::get_last_run_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to parse the last health check: {}", e),
+            })
+    }
+
+    async fn save_last_run(run: &HealthCheckRun) -> SshResult<()> {
+        let path = Self::get_last_run_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(run).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    pub async fn status() -> SshResult<HealthCheckStatus> {
+        let settings = Self::get_settings();
+        let next_run_at = if settings.enabled {
+            next_run(&settings.parsed_schedules()?, &Local::now().naive_local())
+                .and_then(|next| Local.from_local_datetime(&next).earliest())
+                .map(|next| next.timestamp_millis())
+        } else {
+            None
+        };
+        Ok(HealthCheckStatus {
+            settings,
+            last_run: Self::last_run().await?,
+            next_run_at,
+        })
+    }
+
+    /// Run the configured checks and compare with the previous run
+    pub async fn run(scheduled: bool) -> SshResult<HealthCheckRun> {
+        let _guard = Self::lock().lock().await;
+        let checks = Self::get_settings().checks;
+        let mut findings = Vec::new();
+        for check in &checks {
+            match check {
+                HealthCheckKind::Doctor => findings.extend(DoctorService::run().await.findings),
+                HealthCheckKind::Permissions => match PermissionService::audit().await {
+                    Ok(audit) => findings.extend(audit),
+                    Err(e) => tracing::warn!("[health_check] Permission audit failed: {}", e),
+                },
+                HealthCheckKind::KeyExpiry => findings.extend(KeyExpiryService::check().await),
+            }
+        }
+        findings.sort_by_key(|f| f.severity);
+
+        let previous = Self::last_run().await.unwrap_or_else(|e| {
+            tracing::warn!("[health_check] Ignoring the last run: {}", e);
+            None
+        });
+        let report = DoctorReport {
+            ran_at: now_millis(),
+            findings,
+        };
+        // The first run has nothing to compare with, so everything is new
+        let comparison = compare_findings(
+            previous.as_ref().map(|p| p.ran_at),
+            previous
+                .as_ref()
+                .map(|p| p.findings.as_slice())
+                .unwrap_or_default(),
+            &report,
+        );
+        let run = HealthCheckRun {
+            ran_at: report.ran_at,
+            scheduled,
+            checks,
+            findings: report.findings,
+            new_findings: comparison.new,
+        };
+        Self::save_last_run(&run).await?;
+        tracing::info!(
+            "[health_check] {} finding(s), {} new",
+            run.findings.len(),
+            run.new_findings.len()
+        );
+        Ok(run)
+    }
+
+    /// Run the checks whenever a schedule is due; `on_new` gets runs with new findings
+    /// (called once at app setup)
+    pub async fn run_schedule_loop<F>(on_new: F)
+    where
+        F: Fn(HealthCheckRun) + Send + Sync + 'static,
+    {
+        loop {
+            // Wake at the start of the next minute and evaluate that minute, even if
+            // the timer fires a little early
+            let now = Local::now().naive_local();
+            let Some(minute) = now
+                .with_second(0)
+                .and_then(|t| t.with_nanosecond(0))
+                .map(|t| t + chrono::Duration::minutes(1))
+            else {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                continue;
+            };
+            let wait = (minute - now).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            let settings = Self::get_settings();
+            if !settings.enabled {
+                continue;
+            }
+            let due = match settings.parsed_schedules() {
+                Ok(schedules) => schedules.iter().any(|s| s.matches(&minute)),
+                Err(e) => {
+                    tracing::warn!("[health_check] {}", e);
+                    false
+                }
+            };
+            if !due {
+                continue;
+            }
+            match Self::run(true).await {
+                Ok(run) if !run.new_findings.is_empty() => on_new(run),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("[health_check] Scheduled run failed: {}", e),
+            }
+        }
+    }
+}
+
+/// Earliest time any schedule fires after `now`
+fn next_run(schedules: &[CronSchedule], now: &NaiveDateTime) -> Option<NaiveDateTime> {
+    schedules.iter().filter_map(|s| s.next_after(now)).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(HealthCheckSettings::default().validate().is_ok());
+        let enabled = HealthCheckSettings {
+            enabled: true,
+            ..HealthCheckSettings::default()
+        };
+        assert!(enabled.validate().is_ok());
+        assert!(HealthCheckSettings {
+            schedules: vec!["every day".to_string()],
+            ..enabled.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(HealthCheckSettings {
+            checks: Vec::new(),
+            ..enabled
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_next_run() {
+        let schedules = vec![
+            CronSchedule::parse("0 9 * * *").unwrap(),
+            CronSchedule::parse("0 17 * * *").unwrap(),
+        ];
+        let now = chrono::NaiveDate::from_ymd_opt(2026, 10, 16)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(next_run(&schedules, &now), now.with_hour(17));
+    }
+}
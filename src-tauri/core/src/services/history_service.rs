@@ -0,0 +1,305 @@
+use crate::models::SshResult;
+use crate::services::audit_service::AuditService;
+use crate::services::privacy_service::PrivacyService;
+use crate::utils::{append_json_line, read_json_lines, retain_json_lines, workspace_data_path};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Top hosts returned by the activity statistics
+const TOP_HOSTS: usize = 10;
+
+/// One SSH session, or a failed attempt to open one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecord {
+    pub host_alias: String,
+    /// Unix timestamp in milliseconds
+    pub started_at: i64,
+    /// Unix timestamp in milliseconds; equal to `started_at` for failed attempts
+    pub ended_at: i64,
+    /// File transfer bytes sent or received over the session
+    #[serde(default)]
+    pub bytes_transferred: u64,
+    /// Error type (see `SshBuddyError::error_type`) when the session couldn't be opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+}
+
+/// Filter of the activity statistics; day buckets use the caller's UTC offset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityQuery {
+    /// Unix timestamp in milliseconds (inclusive)
+    pub from: Option<i64>,
+    /// Unix timestamp in milliseconds (exclusive)
+    pub to: Option<i64>,
+    pub host_alias: Option<String>,
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyActivity {
+    /// YYYY-MM-DD in the query's UTC offset
+    pub day: String,
+    pub connections: u64,
+    pub failures: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostActivity {
+    pub host_alias: String,
+    pub connections: u64,
+    pub bytes_transferred: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureCount {
+    pub cause: String,
+    pub count: u64,
+}
+
+/// Aggregated session history for the statistics dashboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityStats {
+    /// Sessions and failed attempts
+    pub connections: u64,
+    pub failures: u64,
+    pub bytes_transferred: u64,
+    /// Over successful sessions; None without any
+    pub average_session_ms: Option<u64>,
+    /// Days without activity are left out
+    pub per_day: Vec<DailyActivity>,
+    /// Most connected hosts first
+    pub top_hosts: Vec<HostActivity>,
+    /// Most frequent cause first
+    pub failures_by_cause: Vec<FailureCount>,
+}
+
+/// Session history, kept as JSON lines in history.jsonl
+pub struct HistoryService;
+
+impl HistoryService {
+    fn get_history_path() -> SshResult<PathBuf> {
+        workspace_data_path("history.jsonl")
+    }
+
+    /// Append a record (also to the audit log) unless history recording is turned off;
+    /// failures are logged, history must never break a connection
+    pub async fn record(record: &SessionRecord) {
+        if !PrivacyService::get_settings().await.record_history {
+            return;
+        }
+        if let Err(e) = Self::append(record).await {
+            tracing::warn!("[history_service] Failed to record session: {}", e);
+        }
+        AuditService::record_session(record).await;
+    }
+
+    async fn append(record: &SessionRecord) -> SshResult<()> {
+        append_json_line(&Self::get_history_path()?, record).await
+    }
+
+    /// All records, oldest first (unreadable lines are skipped)
+    pub async fn load() -> SshResult<Vec<SessionRecord>> {
+        read_json_lines(&Self::get_history_path()?).await
+    }
+
+    /// Remove records of sessions started before `cutoff` (Unix milliseconds)
+    pub async fn purge_before(cutoff: i64) -> SshResult<usize> {
+        retain_json_lines(&Self::get_history_path()?, |r: &SessionRecord| {
+            r.started_at >= cutoff
+        })
+        .await
+    }
+
+    /// Statistics over the records matching the query
+    pub async fn activity(query: &ActivityQuery) -> SshResult<ActivityStats> {
+        Ok(aggregate(&Self::load().await?, query))
+    }
+}
+
+fn matches(record: &SessionRecord, query: &ActivityQuery) -> bool {
+    query.from.map_or(true, |from| record.started_at >= from)
+        && query.to.map_or(true, |to| record.started_at < to)
+        && query
+            .host_alias
+            .as_deref()
+            .map_or(true, |alias| record.host_alias == alias)
+}
+
+/// Calendar day of a timestamp, as days since 1970-01-01 in the given offset
+fn day_index(timestamp_ms: i64, utc_offset_minutes: i32) -> i64 {
+    (timestamp_ms + i64::from(utc_offset_minutes) * 60_000).div_euclid(DAY_MS)
+}
+
+fn format_day(day_index: i64) -> String {
+    // 719_163 days from 0001-01-01 (CE day 1) to 1970-01-01
+    chrono::NaiveDate::from_num_days_from_ce_opt((day_index + 719_163) as i32)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn aggregate(records: &[SessionRecord], query: &ActivityQuery) -> ActivityStats {
+    let mut stats = ActivityStats::default();
+    let mut days: HashMap<i64, (u64, u64)> = HashMap::new();
+    let mut hosts: HashMap<&str, (u64, u64)> = HashMap::new();
+    let mut causes: HashMap<&str, u64> = HashMap::new();
+    let mut session_ms = 0u64;
+    let mut sessions = 0u64;
+
+    for record in records.iter().filter(|r| matches(r, query)) {
+        stats.connections += 1;
+        stats.bytes_transferred += record.bytes_transferred;
+        let day = days
+            .entry(day_index(record.started_at, query.utc_offset_minutes))
+            .or_default();
+        day.0 += 1;
+        let host = hosts.entry(&record.host_alias).or_default();
+        host.0 += 1;
+        host.1 += record.bytes_transferred;
+        match &record.failure {
+            Some(cause) => {
+                stats.failures += 1;
+                day.1 += 1;
+                *causes.entry(cause).or_default() += 1;
+            }
+            None => {
+                sessions += 1;
+                session_ms += (record.ended_at - record.started_at).max(0) as u64;
+            }
+        }
+    }
+
+    stats.average_session_ms = (sessions > 0).then(|| session_ms / sessions);
+
+    let mut per_day: Vec<_> = days.into_iter().collect();
+    per_day.sort_by_key(|(day, _)| *day);
+    stats.per_day = per_day
+        .into_iter()
+        .map(|(day, (connections, failures))| DailyActivity {
+            day: format_day(day),
+            connections,
+            failures,
+        })
+        .collect();
+
+    let mut top_hosts: Vec<_> = hosts
+        .into_iter()
+        .map(|(alias, (connections, bytes))| HostActivity {
+            host_alias: alias.to_string(),
+            connections,
+            bytes_transferred: bytes,
+        })
+        .collect();
+    top_hosts.sort_by(|a, b| {
+        b.connections
+            .cmp(&a.connections)
+            .then_with(|| a.host_alias.cmp(&b.host_alias))
+    });
+    top_hosts.truncate(TOP_HOSTS);
+    stats.top_hosts = top_hosts;
+
+    let mut failures_by_cause: Vec<_> = causes
+        .into_iter()
+        .map(|(cause, count)| FailureCount {
+            cause: cause.to_string(),
+            count,
+        })
+        .collect();
+    failures_by_cause.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.cause.cmp(&b.cause)));
+    stats.failures_by_cause = failures_by_cause;
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-01T00:00:00Z
+    const MARCH_1: i64 = 1_709_251_200_000;
+
+    fn record(alias: &str, started_at: i64, minutes: i64, failure: Option<&str>) -> SessionRecord {
+        SessionRecord {
+            host_alias: alias.to_string(),
+            started_at,
+            ended_at: started_at + minutes * 60_000,
+            bytes_transferred: if failure.is_none() { 1000 } else { 0 },
+            failure: failure.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_day_index() {
+        assert_eq!(format_day(day_index(MARCH_1, 0)), "2024-03-01");
+        assert_eq!(format_day(day_index(MARCH_1 - 1, 0)), "2024-02-29");
+        // 23:30 UTC is already the next day at UTC+1
+        assert_eq!(
+            format_day(day_index(MARCH_1 + DAY_MS - 30 * 60_000, 60)),
+            "2024-03-02"
+        );
+        assert_eq!(format_day(day_index(MARCH_1, -60)), "2024-02-29");
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let records = vec![
+            record("web", MARCH_1 + 1000, 10, None),
+            record("web", MARCH_1 + DAY_MS, 20, None),
+            record("db", MARCH_1 + DAY_MS, 0, Some("ConnectionTimeout")),
+            record("db", MARCH_1 + DAY_MS + 5, 0, Some("ConnectionTimeout")),
+            record("db", MARCH_1 + DAY_MS + 9, 0, Some("PermissionDenied")),
+        ];
+        let stats = aggregate(&records, &ActivityQuery::default());
+        assert_eq!(stats.connections, 5);
+        assert_eq!(stats.failures, 3);
+        assert_eq!(stats.bytes_transferred, 2000);
+        assert_eq!(stats.average_session_ms, Some(15 * 60_000));
+        assert_eq!(
+            stats.per_day,
+            vec![
+                DailyActivity {
+                    day: "2024-03-01".to_string(),
+                    connections: 1,
+                    failures: 0
+                },
+                DailyActivity {
+                    day: "2024-03-02".to_string(),
+                    connections: 4,
+                    failures: 3
+                },
+            ]
+        );
+        assert_eq!(stats.top_hosts[0].host_alias, "db");
+        assert_eq!(stats.top_hosts[1].bytes_transferred, 2000);
+        assert_eq!(stats.failures_by_cause[0].cause, "ConnectionTimeout");
+        assert_eq!(stats.failures_by_cause[0].count, 2);
+
+        let web_first_day = aggregate(
+            &records,
+            &ActivityQuery {
+                to: Some(MARCH_1 + DAY_MS),
+                host_alias: Some("web".to_string()),
+                ..ActivityQuery::default()
+            },
+        );
+        assert_eq!(web_first_day.connections, 1);
+        assert!(web_first_day.failures_by_cause.is_empty());
+
+        let none = aggregate(
+            &records,
+            &ActivityQuery {
+                from: Some(MARCH_1 + 2 * DAY_MS),
+                ..ActivityQuery::default()
+            },
+        );
+        assert_eq!(none, ActivityStats::default());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::models::SshResult;
+use crate::services::registry_service::{now_millis, HostMetadata, RegistryService};
+use crate::services::ssh_connection::SshConnectionService;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Timeout for the fact gathering script
+const COLLECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// POSIX sh script run with `sh -s`, prints one key=value line per fact
+/// (works on Linux; on other systems the /proc based facts stay empty)
+const FACTS_SCRIPT: &str = r#"
+echo "os=$(uname -s)"
+echo "kernel=$(uname -r)"
+echo "arch=$(uname -m)"
+if [ -r /etc/os-release ]; then
+  echo "distro=$(sed -n 's/^PRETTY_NAME=//p' /etc/os-release | tr -d '"')"
+elif command -v sw_vers >/dev/null 2>&1; then
+  echo "distro=$(sw_vers -productName) $(sw_vers -productVersion)"
+fi
+[ -r /proc/uptime ] && echo "uptime=$(cut -d' ' -f1 /proc/uptime)"
+echo "cpus=$(nproc 2>/dev/null || getconf _NPROCESSORS_ONLN 2>/dev/null)"
+[ -r /proc/cpuinfo ] && echo "cpu_model=$(grep -m1 'model name' /proc/cpuinfo | cut -d: -f2)"
+if [ -r /proc/meminfo ]; then
+  echo "mem_total=$(awk '/^MemTotal:/{print $2}' /proc/meminfo)"
+  echo "mem_available=$(awk '/^MemAvailable:/{print $2}' /proc/meminfo)"
+fi
+echo "disk_root=$(df -Pk / 2>/dev/null | awk 'NR==2{print $2, $3, $4}')"
+echo "sshd=$( { sshd -V || /usr/sbin/sshd -V; } 2>&1 | grep -m1 -o 'OpenSSH_[^ ,]*')"
+"#;
+
+/// System facts of a remote host (cached in the host registry)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostFacts {
+    /// `uname -s`, e.g. "Linux"
+    pub os: Option<String>,
+    /// e.g. "Debian GNU/Linux 12 (bookworm)"
+    pub distro: Option<String>,
+    pub kernel: Option<String>,
+    pub arch: Option<String>,
+    pub uptime_seconds: Option<u64>,
+    pub cpu_count: Option<u32>,
+    pub cpu_model: Option<String>,
+    pub memory_total_kb: Option<u64>,
+    pub memory_available_kb: Option<u64>,
+    /// Root filesystem usage
+    pub disk_total_kb: Option<u64>,
+    pub disk_used_kb: Option<u64>,
+    pub disk_available_kb: Option<u64>,
+    /// e.g. "OpenSSH_9.6p1"
+    pub sshd_version: Option<String>,
+    /// Unix timestamp in milliseconds
+    pub collected_at: i64,
+}
+
+/// Collects system facts of remote hosts
+pub struct HostFactsService;
+
+impl HostFactsService {
+    /// Gather facts over a single SSH session and cache them in the registry
+    pub async fn collect(host_alias: &str) -> SshResult<HostFacts> {
+        let session = SshConnectionService::open_session(host_alias, None).await?;
+        let output = session
+            .exec(
+                "sh -s",
+                Some(FACTS_SCRIPT.as_bytes()),
+                COLLECT_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+
+        let mut facts = parse_facts(&output?.stdout);
+        facts.collected_at = now_millis();
+
+        // Caching is best effort (e.g. not possible in read-only mode); facts collected
+        // across a fleet are written to the registry together
+        let alias = host_alias.to_string();
+        let cached = facts.clone();
+        RegistryService::defer(move |store| {
+            store
+                .hosts
+                .entry(alias)
+                .or_insert_with(HostMetadata::new)
+                .facts = Some(cached);
+        });
+
+        tracing::info!(
+            "[host_facts] Collected facts for {}: {:?} {:?}",
+            host_alias,
+            facts.distro,
+            facts.kernel
+        );
+        Ok(facts)
+    }
+}
+
+/// Parse the key=value output of the facts script (empty values are skipped)
+fn parse_facts(output: &str) -> HostFacts {
+    let mut facts = HostFacts::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        let text = Some(value.to_string());
+        match key {
+            "os" => facts.os = text,
+            "distro" => facts.distro = text,
+            "kernel" => facts.kernel = text,
+            "arch" => facts.arch = text,
+            // /proc/uptime has fractional seconds
+            "uptime" => {
+                facts.uptime_seconds = value.split('.').next().and_then(|secs| secs.parse().ok())
+            }
+            "cpus" => facts.cpu_count = value.parse().ok(),
+            "cpu_model" => facts.cpu_model = text,
+            "mem_total" => facts.memory_total_kb = value.parse().ok(),
+            "mem_available" => facts.memory_available_kb = value.parse().ok(),
+            "disk_root" => {
+                let mut fields = value.split_whitespace().map(|v| v.parse().ok());
+                facts.disk_total_kb = fields.next().flatten();
+                facts.disk_used_kb = fields.next().flatten();
+                facts.disk_available_kb = fields.next().flatten();
+            }
+            "sshd" => facts.sshd_version = text,
+            _ => {}
+        }
+    }
+    facts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_linux_facts() {
+        let output = "os=Linux\nkernel=6.1.0-18-amd64\narch=x86_64\n\
+            distro=Debian GNU/Linux 12 (bookworm)\nuptime=123456.78\ncpus=4\n\
+            cpu_model= Intel(R) Xeon(R) CPU\nmem_total=8000000\nmem_available=6000000\n\
+            disk_root=41152736 10000000 29000000\nsshd=OpenSSH_9.2p1\n";
+        let facts = parse_facts(output);
+        assert_eq!(facts.os.as_deref(), Some("Linux"));
+        assert_eq!(
+            facts.distro.as_deref(),
+            Some("Debian GNU/Linux 12 (bookworm)")
+        );
+        assert_eq!(facts.uptime_seconds, Some(123456));
+        assert_eq!(facts.cpu_count, Some(4));
+        assert_eq!(facts.cpu_model.as_deref(), Some("Intel(R) Xeon(R) CPU"));
+        assert_eq!(facts.memory_available_kb, Some(6000000));
+        assert_eq!(facts.disk_used_kb, Some(10000000));
+        assert_eq!(facts.sshd_version.as_deref(), Some("OpenSSH_9.2p1"));
+    }
+
+    #[test]
+    fn test_parse_skips_missing_facts() {
+        let facts = parse_facts("os=Darwin\nsshd=\ndisk_root=\nnoise line\n");
+        assert_eq!(facts.os.as_deref(), Some("Darwin"));
+        assert_eq!(facts.sshd_version, None);
+        assert_eq!(facts.disk_total_kb, None);
+    }
+}
@@ -0,0 +1,657 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::revision_service::{ManagedFile, RevisionService};
+use crate::services::script_service::shell_quote;
+use crate::services::ssh_connection::{ExecOutput, RemoteSession, SshConnectionService};
+use crate::services::sudo_service::SudoService;
+use crate::utils::{ssh_dir, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::fs;
+
+/// Server configuration file whose HostKey lines are rewritten
+const SSHD_CONFIG: &str = "/etc/ssh/sshd_config";
+
+/// Timeout for each remote step
+const STEP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Wait after reloading sshd before the verification connection
+const RELOAD_SETTLE: Duration = Duration::from_secs(2);
+
+/// Reload sshd without dropping established sessions (SIGHUP only restarts the listener)
+const RELOAD_COMMAND: &str =
+    "systemctl reload ssh 2>/dev/null || systemctl reload sshd 2>/dev/null \
+     || service ssh reload 2>/dev/null || service sshd reload 2>/dev/null \
+     || kill -HUP \"$(cat /run/sshd.pid /var/run/sshd.pid 2>/dev/null | head -n 1)\"";
+
+/// Server host key type
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum HostKeyType {
+    Ed25519,
+    Ecdsa,
+    Rsa,
+}
+
+impl HostKeyType {
+    /// Order of OpenSSH's default HostKey list
+    const DEFAULTS: [HostKeyType; 3] = [HostKeyType::Rsa, HostKeyType::Ecdsa, HostKeyType::Ed25519];
+
+    /// Name used in host key file names (ssh_host_<name>_key)
+    fn file_name(self) -> &'static str {
+        match self {
+            HostKeyType::Ed25519 => "ed25519",
+            HostKeyType::Ecdsa => "ecdsa",
+            HostKeyType::Rsa => "rsa",
+        }
+    }
+
+    fn keygen_args(self) -> &'static str {
+        match self {
+            HostKeyType::Ed25519 => "-t ed25519",
+            HostKeyType::Ecdsa => "-t ecdsa -b 256",
+            HostKeyType::Rsa => "-t rsa -b 4096",
+        }
+    }
+
+    /// Type of a known_hosts key ("ecdsa-sha2-nistp384" counts as ECDSA)
+    fn from_known_hosts_type(name: &str) -> Option<Self> {
+        match name {
+            "ssh-ed25519" => Some(HostKeyType::Ed25519),
+            "ssh-rsa" => Some(HostKeyType::Rsa),
+            n if n.starts_with("ecdsa-sha2-") => Some(HostKeyType::Ecdsa),
+            _ => None,
+        }
+    }
+
+    /// Type of a HostKey path, guessed from the file name
+    fn from_path(path: &str) -> Option<Self> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        [HostKeyType::Ed25519, HostKeyType::Ecdsa, HostKeyType::Rsa]
+            .into_iter()
+            .find(|t| name.contains(t.file_name()))
+    }
+
+    fn default_path(self) -> String {
+        format!("/etc/ssh/ssh_host_{}_key", self.file_name())
+    }
+}
+
+/// Host key rotation request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyRotationRequest {
+    pub host_alias: String,
+    /// Key types to replace (defaults to Ed25519)
+    #[serde(default)]
+    pub key_types: Vec<HostKeyType>,
+}
+
+/// Progress of a rotation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RotationStep {
+    Connected,
+    KeysGenerated,
+    ConfigUpdated,
+    SshdReloaded,
+    /// A new connection accepted the new keys
+    Verified,
+    KnownHostsUpdated,
+    /// The old configuration was restored after a failure
+    RolledBack,
+}
+
+/// New server host key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatedHostKey {
+    pub key_type: HostKeyType,
+    /// Private key path on the server
+    pub path: String,
+    /// "ssh-ed25519 AAAA..."
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+/// Result of a completed rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyRotationResult {
+    pub host_alias: String,
+    pub new_keys: Vec<RotatedHostKey>,
+    /// Backup of sshd_config on the server
+    pub config_backup: String,
+    /// known_hosts lines replaced by the new keys
+    pub replaced_entries: usize,
+}
+
+/// Replaces the host keys of a server the user administers
+///
+/// The session used for the change stays open until a second connection has
+/// authenticated with the new keys; if that fails the old sshd_config is restored
+/// through it. Local known_hosts is only touched once the new keys are verified.
+pub struct HostKeyRotationService;
+
+impl HostKeyRotationService {
+    /// Generate new host keys on the server, switch sshd over to them and trust them
+    /// locally, reporting progress through `on_step`
+    pub async fn rotate<F>(
+        request: &HostKeyRotationRequest,
+        prompter: Option<&dyn AuthPrompter>,
+        on_step: F,
+    ) -> SshResult<HostKeyRotationResult>
+    where
+        F: Fn(RotationStep),
+    {
+        ReadOnlyMode::ensure_writable("rotate host keys")?;
+        let mut key_types: Vec<HostKeyType> = Vec::new();
+        for key_type in &request.key_types {
+            if !key_types.contains(key_type) {
+                key_types.push(*key_type);
+            }
+        }
+        if key_types.is_empty() {
+            key_types.push(HostKeyType::Ed25519);
+        }
+
+        let host_config = SshConnectionService::resolve_host(&request.host_alias).await?;
+        let names = known_host_names(host_config.get_hostname(), host_config.get_port());
+
+        let session = SshConnectionService::open_session(&request.host_alias, prompter).await?;
+        on_step(RotationStep::Connected);
+        let result = Self::rotate_in_session(
+            &session,
+            &request.host_alias,
+            &key_types,
+            &names,
+            prompter,
+            &on_step,
+        )
+        .await;
+        session.close().await;
+        let (new_keys, config_backup) = result?;
+
+        let replaced_entries = Self::update_known_hosts(&names, &key_types, &new_keys).await?;
+        on_step(RotationStep::KnownHostsUpdated);
+        tracing::info!(
+            "[host_key_rotation] Rotated {} host key(s) of {} ({} known_hosts line(s) replaced)",
+            new_keys.len(),
+            request.host_alias,
+            replaced_entries
+        );
+        Ok(HostKeyRotationResult {
+            host_alias: request.host_alias.clone(),
+            new_keys,
+            config_backup,
+            replaced_entries,
+        })
+    }
+
+    /// Run a shell command under sudo
+    async fn sudo(
+        session: &RemoteSession,
+        command: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<ExecOutput> {
+        let marker = SudoService::new_marker();
+        let command =
+            SudoService::wrap_command(&format!("sh -c {}", shell_quote(command)), &marker);
+        SudoService::exec(
+            session,
+            &command,
+            &marker,
+            prompter,
+            STEP_TIMEOUT,
+            |_, _| {},
+        )
+        .await
+    }
+
+    fn step_error(step: &str, output: &ExecOutput) -> SshBuddyError {
+        SshBuddyError::Unknown {
+            message: format!(
+                "{} failed (exit {:?}): {}",
+                step,
+                output.exit_code,
+                output.stderr.trim()
+            ),
+        }
+    }
+
+    async fn rotate_in_session<F>(
+        session: &RemoteSession,
+        host_alias: &str,
+        key_types: &[HostKeyType],
+        names: &[String],
+        prompter: Option<&dyn AuthPrompter>,
+        on_step: &F,
+    ) -> SshResult<(Vec<RotatedHostKey>, String)>
+    where
+        F: Fn(RotationStep),
+    {
+        let stamp = now_millis();
+        let new_keys = Self::generate_keys(session, key_types, stamp, prompter).await?;
+        on_step(RotationStep::KeysGenerated);
+
+        let current = Self::sudo(session, &format!("cat {}", SSHD_CONFIG), prompter).await?;
+        if !current.success() {
+            return Err(Self::step_error("Reading sshd_config", &current));
+        }
+        let paths: Vec<(HostKeyType, String)> = new_keys
+            .iter()
+            .map(|k| (k.key_type, k.path.clone()))
+            .collect();
+        let config = rewrite_host_keys(&current.stdout, &paths);
+
+        let temp = session
+            .exec(
+                "mktemp /tmp/ssh-buddy-sshd.XXXXXXXX",
+                None,
+                STEP_TIMEOUT,
+                |_, _| {},
+            )
+            .await?;
+        let temp_path = temp.stdout.trim().to_string();
+        if !temp.success() || temp_path.is_empty() {
+            return Err(Self::step_error("Creating a temp file", &temp));
+        }
+        session
+            .upload_file(&temp_path, config.as_bytes(), 0o600)
+            .await?;
+
+        // Copy over the live file (keeps its owner and mode) and validate with sshd -t
+        let backup = format!("{}.ssh-buddy-{}", SSHD_CONFIG, stamp);
+        let install = format!(
+            "cp -p {live} {backup} && cat {temp} > {live} && rm -f {temp} \
+             && {{ $(command -v sshd || echo /usr/sbin/sshd) -t \
+             || {{ cp -p {backup} {live}; exit 1; }}; }}",
+            live = SSHD_CONFIG,
+            backup = shell_quote(&backup),
+            temp = shell_quote(&temp_path),
+        );
+        let installed = Self::sudo(session, &install, prompter).await?;
+        if !installed.success() {
+            return Err(Self::step_error("Updating sshd_config", &installed));
+        }
+        on_step(RotationStep::ConfigUpdated);
+
+        let verified = async {
+            let reloaded = Self::sudo(session, RELOAD_COMMAND, prompter).await?;
+            if !reloaded.success() {
+                return Err(Self::step_error("Reloading sshd", &reloaded));
+            }
+            on_step(RotationStep::SshdReloaded);
+            tokio::time::sleep(RELOAD_SETTLE).await;
+
+            let known = verification_keys(
+                SshConnectionService::load_known_hosts().await,
+                names,
+                key_types,
+                &new_keys,
+            );
+            let check =
+                SshConnectionService::open_session_with_known_hosts(host_alias, prompter, known)
+                    .await?;
+            check.close().await;
+            Ok::<(), SshBuddyError>(())
+        }
+        .await;
+
+        if let Err(e) = verified {
+            tracing::error!(
+                "[host_key_rotation] Verification of {} failed, restoring sshd_config: {}",
+                host_alias,
+                e
+            );
+            let restore = format!(
+                "cp -p {backup} {live} && ({reload})",
+                backup = shell_quote(&backup),
+                live = SSHD_CONFIG,
+                reload = RELOAD_COMMAND,
+            );
+            match Self::sudo(session, &restore, prompter).await {
+                Ok(output) if output.success() => on_step(RotationStep::RolledBack),
+                Ok(output) => tracing::error!(
+                    "[host_key_rotation] Restoring sshd_config on {} failed: {}",
+                    host_alias,
+                    output.stderr.trim()
+                ),
+                Err(restore_error) => tracing::error!(
+                    "[host_key_rotation] Restoring sshd_config on {} failed: {}",
+                    host_alias,
+                    restore_error
+                ),
+            }
+            return Err(e);
+        }
+        on_step(RotationStep::Verified);
+        Ok((new_keys, backup))
+    }
+
+    /// Generate the new key files next to the old ones and read their public halves
+    async fn generate_keys(
+        session: &RemoteSession,
+        key_types: &[HostKeyType],
+        stamp: i64,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<Vec<RotatedHostKey>> {
+        let paths: Vec<(HostKeyType, String)> = key_types
+            .iter()
+            .map(|t| (*t, format!("{}.{}", t.default_path(), stamp)))
+            .collect();
+        let command = paths
+            .iter()
+            .map(|(key_type, path)| {
+                format!(
+                    "ssh-keygen -q -N '' {} -C '' -f {path} && cat {path}.pub",
+                    key_type.keygen_args(),
+                    path = shell_quote(path)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" && ");
+        let output = Self::sudo(session, &command, prompter).await?;
+        if !output.success() {
+            return Err(Self::step_error("Generating host keys", &output));
+        }
+
+        let public_keys: Vec<&str> = output
+            .stdout
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        if public_keys.len() != paths.len() {
+            return Err(SshBuddyError::Unknown {
+                message: "ssh-keygen did not print the expected public keys".to_string(),
+            });
+        }
+        paths
+            .into_iter()
+            .zip(public_keys)
+            .map(|((key_type, path), line)| {
+                let key =
+                    ssh_key::PublicKey::from_openssh(line).map_err(|e| SshBuddyError::Unknown {
+                        message: format!("Invalid public key from ssh-keygen: {}", e),
+                    })?;
+                let public_key = line
+                    .split_whitespace()
+                    .take(2)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Ok(RotatedHostKey {
+                    key_type,
+                    path,
+                    public_key,
+                    fingerprint: key.fingerprint(ssh_key::HashAlg::Sha256).to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Swap the rotated keys in ~/.ssh/known_hosts (written atomically)
+    async fn update_known_hosts(
+        names: &[String],
+        key_types: &[HostKeyType],
+        new_keys: &[RotatedHostKey],
+    ) -> SshResult<usize> {
+        let path = ssh_dir()?.join("known_hosts");
+        let previous = fs::read_to_string(&path).await.ok();
+        let (content, replaced) = replace_known_host_keys(
+            previous.as_deref().unwrap_or_default(),
+            names,
+            key_types,
+            new_keys,
+        );
+        write_atomic(&path, content.as_bytes()).await?;
+        RevisionService::record_change(
+            ManagedFile::KnownHosts,
+            previous.as_deref(),
+            &content,
+            &format!("Rotate host keys of {}", names[0]),
+        )
+        .await;
+        Ok(replaced)
+    }
+}
+
+/// known_hosts names of a server, primary first (same variants the connection checks)
+pub(crate) fn known_host_names(hostname: &str, port: u16) -> Vec<String> {
+    if port == 22 {
+        vec![hostname.to_string()]
+    } else {
+        vec![format!("[{}]:{}", hostname, port), hostname.to_string()]
+    }
+}
+
+/// Replace the HostKey lines of the rotated types with the new paths
+///
+/// Without any HostKey line sshd uses its default key list, so the defaults are
+/// written out explicitly (the new paths replacing the rotated types). Lines are
+/// inserted after the last HostKey line before the first Match block, where HostKey
+/// is still allowed. HostKey lines in Include'd files are not looked at.
+fn rewrite_host_keys(config: &str, new_paths: &[(HostKeyType, String)]) -> String {
+    let is_option = |line: &str, name: &str| {
+        line.split_whitespace()
+            .next()
+            .is_some_and(|k| k.eq_ignore_ascii_case(name))
+    };
+    let host_key_path = |line: &str| {
+        is_option(line.trim(), "HostKey").then(|| {
+            line.split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_string()
+        })
+    };
+    let new_path = |key_type: Option<HostKeyType>| {
+        new_paths
+            .iter()
+            .find(|(t, _)| Some(*t) == key_type)
+            .map(|(_, p)| p.as_str())
+    };
+
+    let lines: Vec<&str> = config.lines().collect();
+    let has_host_keys = lines.iter().any(|l| host_key_path(l).is_some());
+    let mut output: Vec<String> = Vec::with_capacity(lines.len() + new_paths.len());
+    let mut written: Vec<HostKeyType> = Vec::new();
+    let mut insert_at = None;
+    let mut in_match = false;
+
+    for line in &lines {
+        if is_option(line.trim(), "Match") && !in_match {
+            in_match = true;
+            insert_at.get_or_insert(output.len());
+        }
+        match host_key_path(line) {
+            Some(path) => {
+                let key_type = HostKeyType::from_path(&path);
+                match new_path(key_type) {
+                    Some(_) if key_type.is_some_and(|t| written.contains(&t)) => {}
+                    Some(replacement) => {
+                        output.push(format!("HostKey {}", replacement));
+                        written.extend(key_type);
+                    }
+                    None => output.push(line.to_string()),
+                }
+                if !in_match {
+                    insert_at = Some(output.len());
+                }
+            }
+            None => output.push(line.to_string()),
+        }
+    }
+
+    let mut added: Vec<String> = Vec::new();
+    if !has_host_keys {
+        for key_type in HostKeyType::DEFAULTS {
+            let path = new_path(Some(key_type))
+                .map(str::to_string)
+                .unwrap_or_else(|| key_type.default_path());
+            added.push(format!("HostKey {}", path));
+        }
+    } else {
+        for (key_type, path) in new_paths {
+            if !written.contains(key_type) {
+                added.push(format!("HostKey {}", path));
+            }
+        }
+    }
+    let at = insert_at.unwrap_or(output.len());
+    output.splice(at..at, added);
+
+    let mut result = output.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Does a known_hosts line belong to one of `names` with a rotated key type?
+fn is_rotated_entry(line: &str, names: &[String], key_types: &[HostKeyType]) -> bool {
+    let mut fields = line.split_whitespace();
+    let (Some(hosts), Some(key_type)) = (fields.next(), fields.next()) else {
+        return false;
+    };
+    hosts.split(',').any(|h| names.iter().any(|n| n == h))
+        && HostKeyType::from_known_hosts_type(key_type).is_some_and(|t| key_types.contains(&t))
+}
+
+/// known_hosts with the rotated keys of `names` replaced; returns the number removed
+/// (hashed and @marker lines are kept as they are)
+fn replace_known_host_keys(
+    content: &str,
+    names: &[String],
+    key_types: &[HostKeyType],
+    new_keys: &[RotatedHostKey],
+) -> (String, usize) {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut removed = 0;
+    for line in content.lines() {
+        if is_rotated_entry(line.trim(), names, key_types) {
+            removed += 1;
+        } else {
+            lines.push(line);
+        }
+    }
+    let mut result = lines.join("\n");
+    if !result.is_empty() {
+        result.push('\n');
+    }
+    for key in new_keys {
+        result.push_str(&format!("{} {}\n", names[0], key.public_key));
+    }
+    (result, removed)
+}
+
+/// Trusted keys for the verification connection: known_hosts with the rotated
+/// types of this server swapped for the new keys
+fn verification_keys(
+    mut known: HashMap<String, Vec<String>>,
+    names: &[String],
+    key_types: &[HostKeyType],
+    new_keys: &[RotatedHostKey],
+) -> HashMap<String, Vec<String>> {
+    for name in names {
+        let keys = known.entry(name.clone()).or_default();
+        keys.retain(|key| {
+            let key_type = key.split_whitespace().next().unwrap_or_default();
+            !HostKeyType::from_known_hosts_type(key_type).is_some_and(|t| key_types.contains(&t))
+        });
+        keys.extend(new_keys.iter().map(|k| k.public_key.clone()));
+    }
+    known
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_key(key_type: HostKeyType, public_key: &str) -> RotatedHostKey {
+        RotatedHostKey {
+            key_type,
+            path: format!("{}.1", key_type.default_path()),
+            public_key: public_key.to_string(),
+            fingerprint: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_rewrite_existing_host_keys() {
+        let config = "Port 22\nHostKey /etc/ssh/ssh_host_rsa_key\nHostKey /etc/ssh/ssh_host_ed25519_key\n\nMatch User backup\n    ForceCommand internal-sftp\n";
+        let rewritten = rewrite_host_keys(
+            config,
+            &[(
+                HostKeyType::Ed25519,
+                "/etc/ssh/ssh_host_ed25519_key.1".to_string(),
+            )],
+        );
+        assert_eq!(
+            rewritten,
+            "Port 22\nHostKey /etc/ssh/ssh_host_rsa_key\nHostKey /etc/ssh/ssh_host_ed25519_key.1\n\nMatch User backup\n    ForceCommand internal-sftp\n"
+        );
+
+        // A type without a HostKey line is added after the existing ones
+        let added = rewrite_host_keys(
+            config,
+            &[(
+                HostKeyType::Ecdsa,
+                "/etc/ssh/ssh_host_ecdsa_key.1".to_string(),
+            )],
+        );
+        assert!(added.starts_with(
+            "Port 22\nHostKey /etc/ssh/ssh_host_rsa_key\nHostKey /etc/ssh/ssh_host_ed25519_key\nHostKey /etc/ssh/ssh_host_ecdsa_key.1\n\nMatch"
+        ));
+    }
+
+    #[test]
+    fn test_rewrite_default_host_keys() {
+        let config = "Port 22\nMatch Address 10.0.0.0/8\n    PasswordAuthentication yes\n";
+        let rewritten = rewrite_host_keys(
+            config,
+            &[(
+                HostKeyType::Ed25519,
+                "/etc/ssh/ssh_host_ed25519_key.1".to_string(),
+            )],
+        );
+        assert_eq!(
+            rewritten,
+            "Port 22\nHostKey /etc/ssh/ssh_host_rsa_key\nHostKey /etc/ssh/ssh_host_ecdsa_key\nHostKey /etc/ssh/ssh_host_ed25519_key.1\nMatch Address 10.0.0.0/8\n    PasswordAuthentication yes\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_known_host_keys() {
+        let names = known_host_names("db.example.com", 2222);
+        let content = "[db.example.com]:2222 ssh-ed25519 OLD\n[db.example.com]:2222 ssh-rsa KEEP\nweb.example.com ssh-ed25519 OTHER\n|1|abc|def ssh-ed25519 HASHED\n";
+        let (updated, removed) = replace_known_host_keys(
+            content,
+            &names,
+            &[HostKeyType::Ed25519],
+            &[new_key(HostKeyType::Ed25519, "ssh-ed25519 NEW")],
+        );
+        assert_eq!(removed, 1);
+        assert_eq!(
+            updated,
+            "[db.example.com]:2222 ssh-rsa KEEP\nweb.example.com ssh-ed25519 OTHER\n|1|abc|def ssh-ed25519 HASHED\n[db.example.com]:2222 ssh-ed25519 NEW\n"
+        );
+    }
+
+    #[test]
+    fn test_verification_keys() {
+        let mut known = HashMap::new();
+        known.insert(
+            "db".to_string(),
+            vec!["ssh-ed25519 OLD".to_string(), "ssh-rsa KEEP".to_string()],
+        );
+        let keys = verification_keys(
+            known,
+            &known_host_names("db", 22),
+            &[HostKeyType::Ed25519],
+            &[new_key(HostKeyType::Ed25519, "ssh-ed25519 NEW")],
+        );
+        assert_eq!(keys["db"], vec!["ssh-rsa KEEP", "ssh-ed25519 NEW"]);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::{now_millis, HostMetadata, MetadataStore, RegistryService};
+use crate::utils::workspace_data_path;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Largest Markdown note kept in the registry
+pub const MAX_NOTES_BYTES: usize = 256 * 1024;
+
+/// Largest single attachment
+const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Attachment space of one host
+const MAX_HOST_ATTACHMENT_BYTES: u64 = 50 * 1024 * 1024;
+
+const MAX_HOST_ATTACHMENTS: usize = 32;
+
+/// File attached to a host (runbook, network diagram); the content lives in the
+/// attachments directory under `id`, so renaming the host doesn't move it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostAttachment {
+    pub id: String,
+    /// Original file name, shown and searched
+    pub name: String,
+    pub size: u64,
+    /// Unix timestamp in milliseconds
+    pub added_at: i64,
+}
+
+/// Per-host Markdown notes (stored in the registry) and attachments
+pub struct HostNotesService;
+
+impl HostNotesService {
+    fn attachments_dir() -> SshResult<PathBuf> {
+        workspace_data_path("attachments")
+    }
+
+    /// Copy a local file into the host's attachments
+    pub async fn add_attachment(alias: &str, source: &str) -> SshResult<HostAttachment> {
+        ReadOnlyMode::ensure_writable("add host attachment")?;
+        if !ConfigService::load_editor().await?.has_host(alias) {
+            return Err(SshBuddyError::HostNotFound {
+                alias: alias.to_string(),
+            });
+        }
+        let source = Path::new(source);
+        let size = match fs::metadata(source).await {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => {
+                return Err(SshBuddyError::InvalidPath {
+                    message: format!("{} is not a file", source.display()),
+                })
+            }
+        };
+        check_size(size)?;
+        let name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "attachment".to_string());
+
+        let attachment = HostAttachment {
+            id: format!("{:016x}", rand::random::<u64>()),
+            name,
+            size,
+            added_at: now_millis(),
+        };
+        // Registered before the file is copied, so pruning (after hosts were
+        // removed) never sees the new file without its reference
+        RegistryService::update(|store| {
+            let metadata = store
+                .hosts
+                .entry(alias.to_string())
+                .or_insert_with(HostMetadata::new);
+            check_host_space(metadata, size)?;
+            metadata.attachments.push(attachment.clone());
+            Ok(())
+        })
+        .await?;
+        if let Err(e) = Self::copy_attachment(source, &attachment.id).await {
+            let removed = RegistryService::update(|store| {
+                if let Some(metadata) = store.hosts.get_mut(alias) {
+                    metadata.attachments.retain(|a| a.id != attachment.id);
+                }
+                Ok(())
+            })
+            .await;
+            if let Err(e) = removed {
+                tracing::warn!(
+                    "[host_notes] Failed to unregister attachment {}: {}",
+                    attachment.id,
+                    e
+                );
+            }
+            return Err(e);
+        }
+        tracing::info!(
+            "[host_notes] Attached {} ({} bytes) to {}",
+            attachment.name,
+            size,
+            alias
+        );
+        Ok(attachment)
+    }
+
+    async fn copy_attachment(source: &Path, id: &str) -> SshResult<()> {
+        let dir = Self::attachments_dir()?;
+        fs::create_dir_all(&dir).await?;
+        let target = dir.join(id);
+        if let Err(e) = fs::copy(source, &target).await {
+            let _ = fs::remove_file(&target).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Delete an attachment and its file
+    pub async fn remove_attachment(alias: &str, id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("remove host attachment")?;
+        let removed = RegistryService::update(|store| {
+            let Some(metadata) = store.hosts.get_mut(alias) else {
+                return Ok(false);
+            };
+            let before = metadata.attachments.len();
+            metadata.attachments.retain(|a| a.id != id);
+            Ok(metadata.attachments.len() != before)
+        })
+        .await?;
+        if !removed {
+            return Err(attachment_not_found(alias, id));
+        }
+        remove_attachment_file(&Self::attachments_dir()?.join(id)).await;
+        tracing::info!("[host_notes] Removed attachment {} of {}", id, alias);
+        Ok(())
+    }
+
+    /// Local path of an attachment, to open or save it elsewhere
+    pub async fn attachment_path(alias: &str, id: &str) -> SshResult<PathBuf> {
+        let store = RegistryService::load().await?;
+        let known = store
+            .hosts
+            .get(alias)
+            .is_some_and(|m| m.attachments.iter().any(|a| a.id == id));
+        let path = Self::attachments_dir()?.join(id);
+        if !known || !path.is_file() {
+            return Err(attachment_not_found(alias, id));
+        }
+        Ok(path)
+    }
+
+    /// Delete attachment files no host refers to anymore (after hosts were removed)
+    pub async fn prune_attachments(store: &MetadataStore) -> SshResult<usize> {
+        let dir = Self::attachments_dir()?;
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let referenced: HashSet<&str> = store
+            .hosts
+            .values()
+            .flat_map(|m| m.attachments.iter().map(|a| a.id.as_str()))
+            .collect();
+        let mut removed = 0;
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !referenced.contains(name.as_str()) {
+                remove_attachment_file(&entry.path()).await;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            tracing::info!("[host_notes] Pruned {} unused attachment(s)", removed);
+        }
+        Ok(removed)
+    }
+}
+
+fn attachment_not_found(alias: &str, id: &str) -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: format!("Attachment {} of {} not found", id, alias),
+    }
+}
+
+async fn remove_attachment_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path).await {
+        tracing::warn!(
+            "[host_notes] Failed to remove attachment {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+fn check_size(size: u64) -> SshResult<()> {
+    if size > MAX_ATTACHMENT_BYTES {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "Attachments are limited to {} MB",
+                MAX_ATTACHMENT_BYTES / (1024 * 1024)
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Room for another attachment of `size` bytes on the host
+fn check_host_space(metadata: &HostMetadata, size: u64) -> SshResult<()> {
+    if metadata.attachments.len() >= MAX_HOST_ATTACHMENTS {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "A host can have at most {} attachments",
+                MAX_HOST_ATTACHMENTS
+            ),
+        });
+    }
+    let used: u64 = metadata.attachments.iter().map(|a| a.size).sum();
+    if used + size > MAX_HOST_ATTACHMENT_BYTES {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "Attachments of a host are limited to {} MB in total",
+                MAX_HOST_ATTACHMENT_BYTES / (1024 * 1024)
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Notes as stored: None when blank, an error past the size limit
+pub fn normalize_notes(notes: Option<&str>) -> SshResult<Option<String>> {
+    let Some(notes) = notes.filter(|n| !n.trim().is_empty()) else {
+        return Ok(None);
+    };
+    if notes.len() > MAX_NOTES_BYTES {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!("Host notes are limited to {} KB", MAX_NOTES_BYTES / 1024),
+        });
+    }
+    Ok(Some(notes.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(size: u64) -> HostAttachment {
+        HostAttachment {
+            id: "0".to_string(),
+            name: "runbook.md".to_string(),
+            size,
+            added_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_attachment_limits() {
+        assert!(check_size(MAX_ATTACHMENT_BYTES).is_ok());
+        assert!(check_size(MAX_ATTACHMENT_BYTES + 1).is_err());
+
+        let mut metadata = HostMetadata::new();
+        metadata.attachments = vec![attachment(MAX_ATTACHMENT_BYTES); 4];
+        assert!(check_host_space(&metadata, MAX_ATTACHMENT_BYTES).is_ok());
+        assert!(check_host_space(&metadata, MAX_ATTACHMENT_BYTES + 1).is_err());
+
+        metadata.attachments = vec![attachment(1); MAX_HOST_ATTACHMENTS];
+        assert!(check_host_space(&metadata, 1).is_err());
+    }
+
+    #[test]
+    fn test_normalize_notes() {
+        assert_eq!(normalize_notes(None).unwrap(), None);
+        assert_eq!(normalize_notes(Some(" \n")).unwrap(), None);
+        assert_eq!(
+            normalize_notes(Some("# Runbook")).unwrap().as_deref(),
+            Some("# Runbook")
+        );
+        assert!(normalize_notes(Some(&"x".repeat(MAX_NOTES_BYTES + 1))).is_err());
+    }
+}
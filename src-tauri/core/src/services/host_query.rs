@@ -0,0 +1,457 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::registry_service::{pinned_aliases, RegistryService};
+use crate::utils::MAX_PAGE_SIZE;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// Column a host list is sorted by (ties are broken by alias)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HostSort {
+    #[default]
+    Alias,
+    Hostname,
+    User,
+    LastUsed,
+    UseCount,
+    CreatedAt,
+    /// Pinned hosts first, in pin order
+    Pinned,
+}
+
+/// Query over the host list; pages are fetched by passing back `next_cursor`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostQuery {
+    /// `next_cursor` of the previous page (None for the first page)
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub sort: HostSort,
+    #[serde(default)]
+    pub descending: bool,
+    /// Case-insensitive substring of the alias, hostname, user, a tag, the notes
+    /// or an attachment name
+    #[serde(default)]
+    pub search: Option<String>,
+    /// Host must have at least one of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorites_only: bool,
+}
+
+/// Host row of a virtualized list: config summary plus registry metadata
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostListItem {
+    pub alias: String,
+    /// 1-based line number of the Host line
+    pub line: u32,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub tags: Vec<String>,
+    pub is_favorite: bool,
+    /// Position in the pin order (favorites only)
+    pub pin_position: Option<u32>,
+    pub last_used: Option<i64>,
+    pub use_count: u32,
+    pub created_at: Option<i64>,
+    /// Merged from a team catalog
+    pub read_only: bool,
+    /// Searched, but not sent with the rows
+    #[serde(skip)]
+    pub notes: Option<String>,
+    /// Attachment file names
+    pub attachments: Vec<String>,
+}
+
+/// One page of hosts
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostPage {
+    pub items: Vec<HostListItem>,
+    /// Cursor of the next page (None on the last page)
+    pub next_cursor: Option<String>,
+    /// Hosts in the config
+    pub total: usize,
+    /// Hosts matching the search and filters
+    pub matched: usize,
+}
+
+/// Value a host is sorted by
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SortKey {
+    Text(String),
+    Number(i64),
+}
+
+/// Position after the last host of a page; the sort key is kept so the next page starts
+/// at the right place even when that host was renamed or deleted in between
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cursor {
+    sort: HostSort,
+    descending: bool,
+    key: SortKey,
+    alias: String,
+}
+
+const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// Sorted, filtered and paginated queries over the hosts, so the frontend doesn't
+/// need the whole registry to render a list
+pub struct HostQueryService;
+
+impl HostQueryService {
+    pub async fn query(query: &HostQuery) -> SshResult<HostPage> {
+        let hosts = Self::list_items().await?;
+        run_query(hosts, query)
+    }
+
+    /// Every literal host alias of the config with its registry metadata
+    async fn list_items() -> SshResult<Vec<HostListItem>> {
+        let store = RegistryService::load().await?;
+        let pinned = pinned_aliases(&store);
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+        for summary in ConfigService::host_summaries().await? {
+            if summary.is_match {
+                continue;
+            }
+            for pattern in &summary.patterns {
+                // ssh uses the first block of an alias; later ones only add options
+                if pattern.contains(['*', '?', '!']) || !seen.insert(pattern.clone()) {
+                    continue;
+                }
+                let metadata = store.hosts.get(pattern);
+                items.push(HostListItem {
+                    alias: pattern.clone(),
+                    line: summary.line,
+                    hostname: summary.hostname.clone(),
+                    user: summary.user.clone(),
+                    port: summary.port,
+                    tags: metadata.map(|m| m.tags.clone()).unwrap_or_default(),
+                    is_favorite: metadata.is_some_and(|m| m.is_favorite),
+                    pin_position: pinned.iter().position(|p| p == pattern).map(|p| p as u32),
+                    last_used: metadata.and_then(|m| m.last_used),
+                    use_count: metadata.map_or(0, |m| m.use_count),
+                    created_at: metadata.map(|m| m.created_at),
+                    read_only: metadata.is_some_and(|m| m.catalog_source.is_some()),
+                    notes: metadata.and_then(|m| m.notes.clone()),
+                    attachments: metadata
+                        .map(|m| m.attachments.iter().map(|a| a.name.clone()).collect())
+                        .unwrap_or_default(),
+                });
+            }
+        }
+        Ok(items)
+    }
+}
+
+fn sort_key(item: &HostListItem, sort: HostSort) -> SortKey {
+    let text =
+        |value: &Option<String>| SortKey::Text(value.as_deref().unwrap_or("").to_lowercase());
+    match sort {
+        HostSort::Alias => SortKey::Text(item.alias.to_lowercase()),
+        HostSort::Hostname => text(&item.hostname),
+        HostSort::User => text(&item.user),
+        HostSort::LastUsed => SortKey::Number(item.last_used.unwrap_or(0)),
+        HostSort::UseCount => SortKey::Number(item.use_count as i64),
+        HostSort::CreatedAt => SortKey::Number(item.created_at.unwrap_or(0)),
+        HostSort::Pinned => SortKey::Number(item.pin_position.map_or(i64::MAX, i64::from)),
+    }
+}
+
+fn compare(a: (&SortKey, &str), b: (&SortKey, &str), descending: bool) -> Ordering {
+    let by_key = if descending {
+        b.0.cmp(a.0)
+    } else {
+        a.0.cmp(b.0)
+    };
+    by_key.then_with(|| a.1.cmp(b.1))
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    let json = serde_json::to_vec(cursor).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+fn decode_cursor(cursor: &str, query: &HostQuery) -> SshResult<Cursor> {
+    let invalid = || SshBuddyError::InvalidOption {
+        message: "Invalid host list cursor".to_string(),
+    };
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let cursor: Cursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if cursor.sort != query.sort || cursor.descending != query.descending {
+        return Err(SshBuddyError::InvalidOption {
+            message: "Host list cursor belongs to a different sort order".to_string(),
+        });
+    }
+    Ok(cursor)
+}
+
+fn matches(item: &HostListItem, query: &HostQuery, needle: Option<&str>) -> bool {
+    if query.favorites_only && !item.is_favorite {
+        return false;
+    }
+    if !query.tags.is_empty() && !item.tags.iter().any(|t| query.tags.contains(t)) {
+        return false;
+    }
+    let Some(needle) = needle else {
+        return true;
+    };
+    let contains = |value: &str| value.to_lowercase().contains(needle);
+    contains(&item.alias)
+        || item.hostname.as_deref().is_some_and(contains)
+        || item.user.as_deref().is_some_and(contains)
+        || item.tags.iter().any(|t| contains(t))
+        || item.notes.as_deref().is_some_and(contains)
+        || item.attachments.iter().any(|a| contains(a))
+}
+
+fn run_query(hosts: Vec<HostListItem>, query: &HostQuery) -> SshResult<HostPage> {
+    let after = query
+        .cursor
+        .as_deref()
+        .filter(|c| !c.is_empty())
+        .map(|c| decode_cursor(c, query))
+        .transpose()?;
+    let needle = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let total = hosts.len();
+    let mut keyed: Vec<(SortKey, HostListItem)> = hosts
+        .into_iter()
+        .filter(|item| matches(item, query, needle.as_deref()))
+        .map(|item| (sort_key(&item, query.sort), item))
+        .collect();
+    keyed.sort_by(|a, b| compare((&a.0, &a.1.alias), (&b.0, &b.1.alias), query.descending));
+    let matched = keyed.len();
+
+    let start = after.map_or(0, |cursor| {
+        keyed.partition_point(|(key, item)| {
+            compare(
+                (key, &item.alias),
+                (&cursor.key, &cursor.alias),
+                query.descending,
+            ) != Ordering::Greater
+        })
+    });
+    let end = start.saturating_add(limit).min(matched);
+    let next_cursor = keyed[..end]
+        .last()
+        .filter(|_| end < matched)
+        .map(|(key, item)| {
+            encode_cursor(&Cursor {
+                sort: query.sort,
+                descending: query.descending,
+                key: key.clone(),
+                alias: item.alias.clone(),
+            })
+        });
+    let items = keyed.drain(start..end).map(|(_, item)| item).collect();
+
+    Ok(HostPage {
+        items,
+        next_cursor,
+        total,
+        matched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(alias: &str, hostname: &str, use_count: u32, tags: &[&str]) -> HostListItem {
+        HostListItem {
+            alias: alias.to_string(),
+            line: 1,
+            hostname: Some(hostname.to_string()),
+            user: None,
+            port: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            is_favorite: false,
+            pin_position: None,
+            last_used: None,
+            use_count,
+            created_at: None,
+            read_only: false,
+            notes: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    fn aliases(page: &HostPage) -> Vec<&str> {
+        page.items.iter().map(|i| i.alias.as_str()).collect()
+    }
+
+    fn sample() -> Vec<HostListItem> {
+        vec![
+            host("web-2", "10.0.0.2", 5, &["prod"]),
+            host("db", "10.0.1.1", 9, &["prod", "db"]),
+            host("Web-1", "10.0.0.1", 5, &[]),
+            host("staging", "stage.example.com", 0, &["staging"]),
+        ]
+    }
+
+    #[test]
+    fn test_pages_follow_cursor() {
+        let mut query = HostQuery {
+            limit: Some(3),
+            ..Default::default()
+        };
+        let first = run_query(sample(), &query).unwrap();
+        assert_eq!(aliases(&first), vec!["db", "staging", "Web-1"]);
+        assert_eq!((first.total, first.matched), (4, 4));
+
+        query.cursor = first.next_cursor.clone();
+        let second = run_query(sample(), &query).unwrap();
+        assert_eq!(aliases(&second), vec!["web-2"]);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[test]
+    fn test_cursor_survives_removed_host() {
+        let query = HostQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let first = run_query(sample(), &query).unwrap();
+        assert_eq!(aliases(&first), vec!["db", "staging"]);
+
+        let hosts: Vec<_> = sample()
+            .into_iter()
+            .filter(|h| h.alias != "staging")
+            .collect();
+        let next = HostQuery {
+            cursor: first.next_cursor,
+            ..query
+        };
+        assert_eq!(
+            aliases(&run_query(hosts, &next).unwrap()),
+            vec!["Web-1", "web-2"]
+        );
+    }
+
+    #[test]
+    fn test_sort_descending_breaks_ties_by_alias() {
+        let mut query = HostQuery {
+            sort: HostSort::UseCount,
+            descending: true,
+            limit: Some(2),
+            ..Default::default()
+        };
+        let first = run_query(sample(), &query).unwrap();
+        assert_eq!(aliases(&first), vec!["db", "Web-1"]);
+
+        query.cursor = first.next_cursor;
+        assert_eq!(
+            aliases(&run_query(sample(), &query).unwrap()),
+            vec!["web-2", "staging"]
+        );
+    }
+
+    #[test]
+    fn test_search_and_filters() {
+        let query = HostQuery {
+            search: Some(" 10.0.0 ".to_string()),
+            ..Default::default()
+        };
+        let page = run_query(sample(), &query).unwrap();
+        assert_eq!(aliases(&page), vec!["Web-1", "web-2"]);
+        assert_eq!(page.matched, 2);
+
+        let query = HostQuery {
+            tags: vec!["prod".to_string()],
+            sort: HostSort::Hostname,
+            ..Default::default()
+        };
+        assert_eq!(
+            aliases(&run_query(sample(), &query).unwrap()),
+            vec!["web-2", "db"]
+        );
+
+        let query = HostQuery {
+            favorites_only: true,
+            ..Default::default()
+        };
+        assert!(run_query(sample(), &query).unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn test_search_notes_and_attachments() {
+        let mut hosts = sample();
+        hosts[1].notes = Some("## Failover\nPromote the replica first".to_string());
+        hosts[3].attachments = vec!["network-diagram.png".to_string()];
+
+        let search = |text: &str| {
+            let query = HostQuery {
+                search: Some(text.to_string()),
+                ..Default::default()
+            };
+            aliases(&run_query(hosts.clone(), &query).unwrap())
+                .into_iter()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search("replica"), vec!["db"]);
+        assert_eq!(search("DIAGRAM"), vec!["staging"]);
+    }
+
+    #[test]
+    fn test_sort_by_pin_order() {
+        let mut hosts = sample();
+        hosts[0].pin_position = Some(1);
+        hosts[3].pin_position = Some(0);
+        let query = HostQuery {
+            sort: HostSort::Pinned,
+            ..Default::default()
+        };
+        assert_eq!(
+            aliases(&run_query(hosts, &query).unwrap()),
+            vec!["staging", "web-2", "db", "Web-1"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_foreign_cursor() {
+        let first = run_query(
+            sample(),
+            &HostQuery {
+                limit: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let query = HostQuery {
+            cursor: first.next_cursor,
+            sort: HostSort::LastUsed,
+            ..Default::default()
+        };
+        assert!(run_query(sample(), &query).is_err());
+
+        let query = HostQuery {
+            cursor: Some("not a cursor".to_string()),
+            ..Default::default()
+        };
+        assert!(run_query(sample(), &query).is_err());
+    }
+}
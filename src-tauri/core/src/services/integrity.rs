@@ -0,0 +1,540 @@
+use crate::models::{LocalizedMessage, SshBuddyError, SshResult};
+use crate::services::registry_service::now_millis;
+use crate::services::watcher_service::{WatchedFile, WatcherService};
+use crate::utils::{ssh_config_path, ssh_dir, unified_diff, workspace_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::fs;
+
+/// How often the files are re-verified without a watcher event
+const VERIFY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Wait after a watcher event so the app can record its own write first
+const SETTLE_DELAY: Duration = Duration::from_secs(3);
+
+/// Larger files are hashed but their content isn't kept for diffs
+const MAX_DIFF_SIZE: usize = 256 * 1024;
+
+/// Lines of context around each change in the diff
+const DIFF_CONTEXT: usize = 3;
+
+/// Kind of file covered by the integrity baseline
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityFileKind {
+    SshConfig,
+    KnownHosts,
+    AuthorizedKeys,
+    PublicKey,
+}
+
+/// Hash (and content, for diffs) of a file when it was last trusted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct BaselineEntry {
+    kind: IntegrityFileKind,
+    sha256: String,
+    size: u64,
+    /// None for binary or very large files
+    content: Option<String>,
+    /// Unix timestamp in milliseconds
+    recorded_at: i64,
+}
+
+/// integrity.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntegrityBaseline {
+    /// Unix timestamp in milliseconds of the first recording
+    created_at: i64,
+    /// By path
+    #[serde(default)]
+    files: BTreeMap<String, BaselineEntry>,
+}
+
+/// How a file differs from the baseline
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityChangeKind {
+    Modified,
+    Added,
+    Removed,
+}
+
+/// Change that SSH Buddy didn't make
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityChange {
+    pub path: String,
+    pub file_kind: IntegrityFileKind,
+    pub change: IntegrityChangeKind,
+    pub expected_sha256: Option<String>,
+    pub actual_sha256: Option<String>,
+    /// Unified diff from the baseline (empty when the content isn't text)
+    pub diff: String,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
+}
+
+/// Result of a verification
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    /// Unix timestamp in milliseconds
+    pub baseline_created_at: i64,
+    /// Unix timestamp in milliseconds
+    pub checked_at: i64,
+    pub tracked_files: usize,
+    pub changes: Vec<IntegrityChange>,
+}
+
+/// A file as it is on disk now
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedFile {
+    kind: IntegrityFileKind,
+    content: Vec<u8>,
+}
+
+/// Records hashes of the SSH config, known_hosts, authorized_keys and public keys,
+/// and reports changes made behind SSH Buddy's back
+pub struct IntegrityService;
+
+impl IntegrityService {
+    fn get_baseline_path() -> SshResult<std::path::PathBuf> {
+        workspace_data_path("integrity.json")
+    }
+
+    /// Baseline updates run one at a time
+    fn lock() -> &'static tokio::sync::Mutex<()> {
+        static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+    }
+
+    async fn load_baseline() -> SshResult<Option<IntegrityBaseline>> {
+        let path = Self::get_baseline_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to parse the integrity baseline: {}", e),
+            })
+    }
+
+    async fn save_baseline(baseline: &IntegrityBaseline) -> SshResult<()> {
+        let path = Self::get_baseline_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content =
+            serde_json::to_string_pretty(baseline).map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Files the baseline covers, by path
+    async fn current_files() -> SshResult<BTreeMap<String, TrackedFile>> {
+        let dir = ssh_dir()?;
+        let mut candidates = vec![
+            (ssh_config_path()?, IntegrityFileKind::SshConfig),
+            (dir.join("known_hosts"), IntegrityFileKind::KnownHosts),
+            (
+                dir.join("authorized_keys"),
+                IntegrityFileKind::AuthorizedKeys,
+            ),
+        ];
+        if dir.is_dir() {
+            let mut entries = fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "pub") {
+                    candidates.push((path, IntegrityFileKind::PublicKey));
+                }
+            }
+        }
+
+        let mut files = BTreeMap::new();
+        for (path, kind) in candidates {
+            if !path.is_file() {
+                continue;
+            }
+            let content = fs::read(&path).await?;
+            files.insert(
+                path.to_string_lossy().to_string(),
+                TrackedFile { kind, content },
+            );
+        }
+        Ok(files)
+    }
+
+    /// Compare the files with the baseline; the first call records the baseline
+    pub async fn verify() -> SshResult<IntegrityReport> {
+        let _guard = Self::lock().lock().await;
+        let current = Self::current_files().await?;
+        let now = now_millis();
+        let baseline = match Self::load_baseline().await? {
+            Some(baseline) => baseline,
+            None => {
+                let baseline = IntegrityBaseline {
+                    created_at: now,
+                    files: current
+                        .iter()
+                        .map(|(path, file)| (path.clone(), baseline_entry(file, now)))
+                        .collect(),
+                };
+                Self::save_baseline(&baseline).await?;
+                tracing::info!(
+                    "[integrity] Recorded baseline of {} file(s)",
+                    baseline.files.len()
+                );
+                baseline
+            }
+        };
+        let changes = compare(&baseline.files, &current);
+        if !changes.is_empty() {
+            tracing::warn!("[integrity] {} unexpected change(s)", changes.len());
+        }
+        Ok(IntegrityReport {
+            baseline_created_at: baseline.created_at,
+            checked_at: now,
+            tracked_files: current.len(),
+            changes,
+        })
+    }
+
+    /// Trust the current content of the given files (all reported changes when empty)
+    pub async fn accept(paths: &[String]) -> SshResult<IntegrityReport> {
+        {
+            let _guard = Self::lock().lock().await;
+            let current = Self::current_files().await?;
+            let now = now_millis();
+            let mut baseline = Self::load_baseline().await?.unwrap_or(IntegrityBaseline {
+                created_at: now,
+                files: BTreeMap::new(),
+            });
+            let changed: Vec<String> = compare(&baseline.files, &current)
+                .into_iter()
+                .map(|c| c.path)
+                .filter(|path| paths.is_empty() || paths.contains(path))
+                .collect();
+            for path in &changed {
+                match current.get(path) {
+                    Some(file) => {
+                        baseline
+                            .files
+                            .insert(path.clone(), baseline_entry(file, now));
+                    }
+                    None => {
+                        baseline.files.remove(path);
+                    }
+                }
+            }
+            Self::save_baseline(&baseline).await?;
+            tracing::info!("[integrity] Accepted {} change(s)", changed.len());
+        }
+        Self::verify().await
+    }
+
+    /// Take a file SSH Buddy just wrote (or deleted) into the baseline
+    /// Failures are logged but never fail the write itself
+    pub async fn record_app_write(path: &Path) {
+        if let Err(e) = Self::try_record_app_write(path).await {
+            tracing::warn!("[integrity] Failed to record {:?}: {}", path, e);
+        }
+    }
+
+    /// Take `<key>.pub` into the baseline after SSH Buddy wrote or deleted it
+    pub async fn record_key_write(key_name: &str) {
+        match ssh_dir() {
+            Ok(dir) => Self::record_app_write(&dir.join(format!("{}.pub", key_name))).await,
+            Err(e) => tracing::warn!("[integrity] Failed to record {}.pub: {}", key_name, e),
+        }
+    }
+
+    async fn try_record_app_write(path: &Path) -> SshResult<()> {
+        let _guard = Self::lock().lock().await;
+        // Without a baseline the next verification records everything anyway
+        let Some(mut baseline) = Self::load_baseline().await? else {
+            return Ok(());
+        };
+        let key = path.to_string_lossy().to_string();
+        let current = Self::current_files().await?;
+        match current.get(&key) {
+            Some(file) => {
+                baseline
+                    .files
+                    .insert(key, baseline_entry(file, now_millis()));
+            }
+            None => {
+                baseline.files.remove(&key);
+            }
+        }
+        Self::save_baseline(&baseline).await
+    }
+
+    /// Re-verify on watcher events and periodically, reporting each unexpected
+    /// content once (spawned once at app setup)
+    pub async fn run_verify_loop<F>(on_changes: F)
+    where
+        F: Fn(Vec<IntegrityChange>) + Send + Sync + 'static,
+    {
+        let mut events = WatcherService::global().subscribe();
+        let mut reported: HashSet<(String, Option<String>)> = HashSet::new();
+        loop {
+            match Self::verify().await {
+                Ok(report) => {
+                    let current: HashSet<(String, Option<String>)> = report
+                        .changes
+                        .iter()
+                        .map(|c| (c.path.clone(), c.actual_sha256.clone()))
+                        .collect();
+                    let new: Vec<IntegrityChange> = report
+                        .changes
+                        .into_iter()
+                        .filter(|c| !reported.contains(&(c.path.clone(), c.actual_sha256.clone())))
+                        .collect();
+                    if !new.is_empty() {
+                        on_changes(new);
+                    }
+                    // Accepted or reverted changes may be reported again if they come back
+                    reported = current;
+                }
+                Err(e) => tracing::warn!("[integrity] Verification failed: {}", e),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(VERIFY_INTERVAL) => {}
+                event = wait_for_ssh_file_event(&mut events) => {
+                    if event {
+                        tokio::time::sleep(SETTLE_DELAY).await;
+                    } else {
+                        // Watcher gone; fall back to the interval
+                        tokio::time::sleep(VERIFY_INTERVAL).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Wait for a change of the SSH config or known_hosts; false if the watcher is gone
+async fn wait_for_ssh_file_event(
+    events: &mut tokio::sync::broadcast::Receiver<WatchedFile>,
+) -> bool {
+    loop {
+        match events.recv().await {
+            Ok(WatchedFile::SshConfig | WatchedFile::KnownHosts) => return true,
+            Ok(_) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => return true,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return false,
+        }
+    }
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn text_content(content: &[u8]) -> Option<String> {
+    if content.len() > MAX_DIFF_SIZE {
+        return None;
+    }
+    String::from_utf8(content.to_vec()).ok()
+}
+
+fn baseline_entry(file: &TrackedFile, recorded_at: i64) -> BaselineEntry {
+    BaselineEntry {
+        kind: file.kind,
+        sha256: sha256_hex(&file.content),
+        size: file.content.len() as u64,
+        content: text_content(&file.content),
+        recorded_at,
+    }
+}
+
+/// Differences between the baseline and the files on disk
+fn compare(
+    baseline: &BTreeMap<String, BaselineEntry>,
+    current: &BTreeMap<String, TrackedFile>,
+) -> Vec<IntegrityChange> {
+    let mut changes = Vec::new();
+    for (path, entry) in baseline {
+        match current.get(path) {
+            Some(file) => {
+                let actual = sha256_hex(&file.content);
+                if actual == entry.sha256 {
+                    continue;
+                }
+                let diff = match (&entry.content, text_content(&file.content)) {
+                    (Some(old), Some(new)) => unified_diff(old, &new, path, path, DIFF_CONTEXT),
+                    _ => String::new(),
+                };
+                changes.push(change(
+                    path,
+                    entry.kind,
+                    IntegrityChangeKind::Modified,
+                    Some(entry.sha256.clone()),
+                    Some(actual),
+                    diff,
+                ));
+            }
+            None => {
+                let diff = entry
+                    .content
+                    .as_deref()
+                    .map(|old| unified_diff(old, "", path, "/dev/null", DIFF_CONTEXT))
+                    .unwrap_or_default();
+                changes.push(change(
+                    path,
+                    entry.kind,
+                    IntegrityChangeKind::Removed,
+                    Some(entry.sha256.clone()),
+                    None,
+                    diff,
+                ));
+            }
+        }
+    }
+    for (path, file) in current {
+        if baseline.contains_key(path) {
+            continue;
+        }
+        let diff = text_content(&file.content)
+            .map(|new| unified_diff("", &new, "/dev/null", path, DIFF_CONTEXT))
+            .unwrap_or_default();
+        changes.push(change(
+            path,
+            file.kind,
+            IntegrityChangeKind::Added,
+            None,
+            Some(sha256_hex(&file.content)),
+            diff,
+        ));
+    }
+    changes
+}
+
+fn change(
+    path: &str,
+    file_kind: IntegrityFileKind,
+    change: IntegrityChangeKind,
+    expected_sha256: Option<String>,
+    actual_sha256: Option<String>,
+    diff: String,
+) -> IntegrityChange {
+    let key = match change {
+        IntegrityChangeKind::Modified => "integrity.modified",
+        IntegrityChangeKind::Added => "integrity.added",
+        IntegrityChangeKind::Removed => "integrity.removed",
+    };
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    IntegrityChange {
+        path: path.to_string(),
+        file_kind,
+        change,
+        expected_sha256,
+        actual_sha256,
+        diff,
+        message: LocalizedMessage::new(key).with("name", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(kind: IntegrityFileKind, content: &str) -> TrackedFile {
+        TrackedFile {
+            kind,
+            content: content.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let before = BTreeMap::from([
+            (
+                "/ssh/config".to_string(),
+                tracked(IntegrityFileKind::SshConfig, "Host a\n  User x\n"),
+            ),
+            (
+                "/ssh/known_hosts".to_string(),
+                tracked(IntegrityFileKind::KnownHosts, "a ssh-ed25519 AAAA\n"),
+            ),
+            (
+                "/ssh/id_old.pub".to_string(),
+                tracked(IntegrityFileKind::PublicKey, "ssh-ed25519 BBBB old\n"),
+            ),
+        ]);
+        let baseline: BTreeMap<String, BaselineEntry> = before
+            .iter()
+            .map(|(path, file)| (path.clone(), baseline_entry(file, 1)))
+            .collect();
+        assert!(compare(&baseline, &before).is_empty());
+
+        let after = BTreeMap::from([
+            (
+                "/ssh/config".to_string(),
+                tracked(IntegrityFileKind::SshConfig, "Host a\n  User root\n"),
+            ),
+            (
+                "/ssh/known_hosts".to_string(),
+                tracked(IntegrityFileKind::KnownHosts, "a ssh-ed25519 AAAA\n"),
+            ),
+            (
+                "/ssh/authorized_keys".to_string(),
+                tracked(IntegrityFileKind::AuthorizedKeys, "ssh-rsa CCCC\n"),
+            ),
+        ]);
+        let changes = compare(&baseline, &after);
+        let summary: Vec<(&str, IntegrityChangeKind)> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.change))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/ssh/config", IntegrityChangeKind::Modified),
+                ("/ssh/id_old.pub", IntegrityChangeKind::Removed),
+                ("/ssh/authorized_keys", IntegrityChangeKind::Added),
+            ]
+        );
+        assert!(changes[0].diff.contains("-  User x\n+  User root\n"));
+        assert_eq!(changes[1].actual_sha256, None);
+        assert_eq!(changes[2].file_kind, IntegrityFileKind::AuthorizedKeys);
+        assert_eq!(changes[0].message.key, "integrity.modified");
+    }
+
+    #[test]
+    fn test_binary_content_has_no_diff() {
+        let file = TrackedFile {
+            kind: IntegrityFileKind::PublicKey,
+            content: vec![0xff, 0xfe, 0x00],
+        };
+        let entry = baseline_entry(&file, 1);
+        assert_eq!(entry.content, None);
+        let changed = BTreeMap::from([(
+            "/ssh/x.pub".to_string(),
+            TrackedFile {
+                kind: IntegrityFileKind::PublicKey,
+                content: vec![0xff],
+            },
+        )]);
+        let changes = compare(
+            &BTreeMap::from([("/ssh/x.pub".to_string(), entry)]),
+            &changed,
+        );
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].diff.is_empty());
+    }
+}
@@ -0,0 +1,576 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::captures::truncate_output;
+use crate::services::fleet_service::{FleetHostResult, FleetRequest, FleetService};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::snippet_service::SnippetService;
+use crate::utils::{
+    append_json_line, read_json_lines, retain_json_lines, workspace_data_path, write_atomic,
+    CronSchedule,
+};
+use chrono::{Local, NaiveDateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::fs;
+
+/// Output kept per host and run; recurring checks only need the gist
+const MAX_JOB_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Runs older than this are dropped from the run log
+const RUN_RETENTION_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// Runs kept per job within the retention window (a job running every minute
+/// would otherwise keep 43,200)
+const MAX_RUNS_PER_JOB: usize = 100;
+
+/// Saved snippet run against hosts on a cron schedule while the app is running
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJob {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub snippet_id: String,
+    /// Hosts to run on, in addition to the members of `tag`
+    #[serde(default)]
+    pub host_aliases: Vec<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Cron expression in local time (e.g. "*/15 * * * *")
+    pub schedule: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Per-host command timeout (default: the fleet timeout)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ScheduledJob {
+    fn validate(&self) -> SshResult<()> {
+        if self.name.is_empty() || self.snippet_id.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Job name and snippet are required".to_string(),
+            });
+        }
+        CronSchedule::parse(&self.schedule)?;
+        let has_tag = self.tag.as_deref().is_some_and(|t| !t.is_empty());
+        if self.host_aliases.is_empty() && !has_tag {
+            return Err(SshBuddyError::InvalidOption {
+                message: "A job needs hosts or a tag to run on".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Job with when it runs next and how it went last time
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJobStatus {
+    #[serde(flatten)]
+    pub job: ScheduledJob,
+    /// Unix milliseconds; None when disabled
+    pub next_run_at: Option<i64>,
+    pub last_run: Option<JobRun>,
+}
+
+/// Outcome of a job run on one host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobHostResult {
+    pub host_alias: String,
+    pub success: bool,
+    pub exit_code: Option<u32>,
+    /// stdout followed by stderr
+    pub output: String,
+    /// Output was longer than the per-host limit
+    #[serde(default)]
+    pub truncated: bool,
+    /// Connection/authentication error (the command never ran)
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// One run of a job, kept in job_runs.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRun {
+    pub id: String,
+    pub job_id: String,
+    pub job_name: String,
+    /// Unix timestamp in milliseconds
+    pub started_at: i64,
+    /// Started by the schedule rather than by the user
+    pub scheduled: bool,
+    pub results: Vec<JobHostResult>,
+    /// Why the job couldn't run at all (snippet deleted, no hosts left)
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl JobRun {
+    pub fn failed(&self) -> bool {
+        self.error.is_some() || self.results.iter().any(|r| !r.success)
+    }
+
+    /// Hosts the job failed on
+    pub fn failed_hosts(&self) -> Vec<&str> {
+        self.results
+            .iter()
+            .filter(|r| !r.success)
+            .map(|r| r.host_alias.as_str())
+            .collect()
+    }
+}
+
+/// scheduled_jobs.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JobStore {
+    #[serde(default)]
+    jobs: Vec<ScheduledJob>,
+}
+
+/// Jobs currently running; a job that is still busy when due again is skipped
+static RUNNING: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Runs saved snippets on hosts on cron schedules, keeping each run's output and
+/// exit status
+pub struct ScheduledJobService;
+
+impl ScheduledJobService {
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("scheduled_jobs.json")
+    }
+
+    fn get_runs_path() -> SshResult<PathBuf> {
+        workspace_data_path("job_runs.jsonl")
+    }
+
+    async fn load_store() -> SshResult<JobStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(JobStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse scheduled jobs: {}", e),
+        })
+    }
+
+    async fn save_store(store: &JobStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// All jobs with their next and last run
+    pub async fn list() -> SshResult<Vec<ScheduledJobStatus>> {
+        let jobs = Self::load_store().await?.jobs;
+        let runs = Self::load_runs().await?;
+        let now = Local::now().naive_local();
+        Ok(jobs
+            .into_iter()
+            .map(|job| ScheduledJobStatus {
+                next_run_at: next_run_at(&job, &now),
+                last_run: runs.iter().rev().find(|r| r.job_id == job.id).cloned(),
+                job,
+            })
+            .collect())
+    }
+
+    /// Add or replace a job (matched by id); the snippet must exist and the
+    /// schedule must parse
+    pub async fn save(mut job: ScheduledJob) -> SshResult<ScheduledJob> {
+        ReadOnlyMode::ensure_writable("save scheduled job")?;
+        job.name = job.name.trim().to_string();
+        job.schedule = job.schedule.trim().to_string();
+        job.tag = job
+            .tag
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+        job.host_aliases.retain(|a| !a.trim().is_empty());
+        job.validate()?;
+        if !SnippetService::list()
+            .await?
+            .iter()
+            .any(|s| s.id == job.snippet_id)
+        {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Snippet {} not found", job.snippet_id),
+            });
+        }
+        if job.id.trim().is_empty() {
+            job.id = format!("{:08x}", rand::random::<u32>());
+        }
+
+        let mut store = Self::load_store().await?;
+        match store.jobs.iter_mut().find(|j| j.id == job.id) {
+            Some(existing) => *existing = job.clone(),
+            None => store.jobs.push(job.clone()),
+        }
+        Self::save_store(&store).await?;
+        tracing::info!("[job_scheduler] Saved job {} ({})", job.id, job.schedule);
+        Ok(job)
+    }
+
+    /// Delete a job and its runs
+    pub async fn delete(id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete scheduled job")?;
+        let mut store = Self::load_store().await?;
+        store.jobs.retain(|j| j.id != id);
+        Self::save_store(&store).await?;
+        retain_json_lines(&Self::get_runs_path()?, |r: &JobRun| r.job_id != id).await?;
+        tracing::info!("[job_scheduler] Deleted job {}", id);
+        Ok(())
+    }
+
+    async fn load_runs() -> SshResult<Vec<JobRun>> {
+        read_json_lines(&Self::get_runs_path()?).await
+    }
+
+    /// Runs of a job (or of all jobs), newest first
+    pub async fn list_runs(job_id: Option<&str>, limit: Option<usize>) -> SshResult<Vec<JobRun>> {
+        Ok(Self::load_runs()
+            .await?
+            .into_iter()
+            .rev()
+            .filter(|r| job_id.map_or(true, |id| r.job_id == id))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Run a job now, outside its schedule
+    pub async fn run_now(id: &str) -> SshResult<JobRun> {
+        let job = Self::load_store()
+            .await?
+            .jobs
+            .into_iter()
+            .find(|j| j.id == id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Scheduled job {} not found", id),
+            })?;
+        Self::run(&job, false)
+            .await
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Job {} is already running", job.name),
+            })
+    }
+
+    /// Run the job's snippet on its hosts and record the run
+    /// None when the job is still running from before
+    async fn run(job: &ScheduledJob, scheduled: bool) -> Option<JobRun> {
+        {
+            let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+            if !running
+                .get_or_insert_with(HashSet::new)
+                .insert(job.id.clone())
+            {
+                return None;
+            }
+        }
+        let started_at = now_millis();
+        let outcome = Self::execute(job).await;
+        if let Some(running) = RUNNING.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            running.remove(&job.id);
+        }
+
+        let (id, results, error) = match outcome {
+            Ok((id, results)) => (id, results, None),
+            Err(e) => (
+                format!("{:016x}", rand::random::<u64>()),
+                Vec::new(),
+                Some(e.to_string()),
+            ),
+        };
+        let run = JobRun {
+            id,
+            job_id: job.id.clone(),
+            job_name: job.name.clone(),
+            started_at,
+            scheduled,
+            results,
+            error,
+        };
+        if let Err(e) = Self::record(&run).await {
+            tracing::warn!("[job_scheduler] Failed to record run of {}: {}", job.id, e);
+        }
+        tracing::info!(
+            "[job_scheduler] Job {} ran on {} host(s), {} failed",
+            job.id,
+            run.results.len(),
+            run.failed_hosts().len()
+        );
+        Some(run)
+    }
+
+    async fn execute(job: &ScheduledJob) -> SshResult<(String, Vec<JobHostResult>)> {
+        let snippet = SnippetService::list()
+            .await?
+            .into_iter()
+            .find(|s| s.id == job.snippet_id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Snippet {} no longer exists", job.snippet_id),
+            })?;
+        let request = FleetRequest {
+            command: snippet.command,
+            tag: job.tag.clone(),
+            host_aliases: job.host_aliases.clone(),
+            timeout_secs: job.timeout_secs,
+            ..Default::default()
+        };
+        let summary = FleetService::run(request, |_| {}).await?;
+        Ok((
+            summary.run_id,
+            summary.results.into_iter().map(host_result).collect(),
+        ))
+    }
+
+    /// Append a run and drop runs past the retention window or the job's run limit
+    async fn record(run: &JobRun) -> SshResult<()> {
+        let path = Self::get_runs_path()?;
+        append_json_line(&path, run).await?;
+        let runs: Vec<JobRun> = read_json_lines(&path).await?;
+        let cutoff = run.started_at - RUN_RETENTION_MS;
+        let dropped = oldest_runs(&runs, &run.job_id);
+        if !dropped.is_empty() || runs.first().is_some_and(|r| r.started_at < cutoff) {
+            retain_json_lines(&path, |r: &JobRun| {
+                r.started_at >= cutoff && !dropped.contains(r.id.as_str())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Start due jobs every minute; `on_failure` gets runs that failed on any host
+    /// (called once at app setup)
+    pub async fn run_schedule_loop<F>(on_failure: F)
+    where
+        F: Fn(JobRun) + Send + Sync + 'static,
+    {
+        let on_failure = Arc::new(on_failure);
+        loop {
+            // Wake at the start of the next minute and evaluate that minute, even if
+            // the timer fires a little early
+            let now = Local::now().naive_local();
+            let Some(minute) = now
+                .with_second(0)
+                .and_then(|t| t.with_nanosecond(0))
+                .map(|t| t + chrono::Duration::minutes(1))
+            else {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                continue;
+            };
+            let wait = (minute - now).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            let jobs = match Self::load_store().await {
+                Ok(store) => store.jobs,
+                Err(e) => {
+                    tracing::warn!("[job_scheduler] {}", e);
+                    continue;
+                }
+            };
+            // Jobs run side by side, so a slow one doesn't hold up the others
+            for job in jobs.into_iter().filter(|j| is_due(j, &minute)) {
+                let on_failure = on_failure.clone();
+                tokio::spawn(async move {
+                    match Self::run(&job, true).await {
+                        Some(run) if run.failed() => on_failure(run),
+                        Some(_) => {}
+                        None => {
+                            tracing::warn!("[job_scheduler] Skipped job {}: still running", job.id)
+                        }
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Whether an enabled job's schedule fires at `minute`
+fn is_due(job: &ScheduledJob, minute: &NaiveDateTime) -> bool {
+    if !job.enabled {
+        return false;
+    }
+    match CronSchedule::parse(&job.schedule) {
+        Ok(schedule) => schedule.matches(minute),
+        Err(e) => {
+            tracing::warn!("[job_scheduler] Job {}: {}", job.id, e);
+            false
+        }
+    }
+}
+
+/// Ids of the job's runs beyond the newest `MAX_RUNS_PER_JOB`
+fn oldest_runs<'a>(runs: &'a [JobRun], job_id: &str) -> HashSet<&'a str> {
+    let ids: Vec<&str> = runs
+        .iter()
+        .filter(|r| r.job_id == job_id)
+        .map(|r| r.id.as_str())
+        .collect();
+    let excess = ids.len().saturating_sub(MAX_RUNS_PER_JOB);
+    ids[..excess].iter().copied().collect()
+}
+
+fn next_run_at(job: &ScheduledJob, now: &NaiveDateTime) -> Option<i64> {
+    if !job.enabled {
+        return None;
+    }
+    CronSchedule::parse(&job.schedule)
+        .ok()?
+        .next_after(now)
+        .and_then(|next| Local.from_local_datetime(&next).earliest())
+        .map(|next| next.timestamp_millis())
+}
+
+/// Fleet result as kept in the run log: both streams in one, cut at the limit
+fn host_result(result: FleetHostResult) -> JobHostResult {
+    let mut output = result.stdout;
+    if !result.stderr.is_empty() {
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&result.stderr);
+    }
+    let (output, truncated) = truncate_output(&output, MAX_JOB_OUTPUT_BYTES);
+    JobHostResult {
+        host_alias: result.host_alias,
+        success: result.success,
+        exit_code: result.exit_code,
+        output,
+        truncated,
+        error: result.error,
+        duration_ms: result.duration_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job() -> ScheduledJob {
+        ScheduledJob {
+            id: "1".to_string(),
+            name: "Disk space".to_string(),
+            snippet_id: "df".to_string(),
+            host_aliases: vec!["web".to_string()],
+            tag: None,
+            schedule: "*/15 * * * *".to_string(),
+            enabled: true,
+            timeout_secs: None,
+        }
+    }
+
+    fn fleet_result(stdout: &str, stderr: &str, success: bool) -> FleetHostResult {
+        FleetHostResult {
+            host_alias: "web".to_string(),
+            success,
+            exit_code: Some(if success { 0 } else { 1 }),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            error: None,
+            duration_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_validate_job() {
+        assert!(job().validate().is_ok());
+        assert!(ScheduledJob {
+            schedule: "hourly".to_string(),
+            ..job()
+        }
+        .validate()
+        .is_err());
+        assert!(ScheduledJob {
+            host_aliases: Vec::new(),
+            ..job()
+        }
+        .validate()
+        .is_err());
+        assert!(ScheduledJob {
+            host_aliases: Vec::new(),
+            tag: Some("prod".to_string()),
+            ..job()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_schedule() {
+        let at = |hour, minute| {
+            chrono::NaiveDate::from_ymd_opt(2026, 10, 16)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        };
+        assert!(is_due(&job(), &at(9, 45)));
+        assert!(!is_due(&job(), &at(9, 50)));
+        let disabled = ScheduledJob {
+            enabled: false,
+            ..job()
+        };
+        assert!(!is_due(&disabled, &at(9, 45)));
+        assert_eq!(next_run_at(&disabled, &at(9, 45)), None);
+    }
+
+    #[test]
+    fn test_oldest_runs() {
+        let run = |job_id: &str, i: usize| JobRun {
+            id: format!("{}-{}", job_id, i),
+            job_id: job_id.to_string(),
+            job_name: job_id.to_string(),
+            started_at: i as i64,
+            scheduled: true,
+            results: Vec::new(),
+            error: None,
+        };
+        let runs: Vec<JobRun> = (0..MAX_RUNS_PER_JOB + 1)
+            .flat_map(|i| [run("disk", i), run("load", i)])
+            .collect();
+        assert_eq!(oldest_runs(&runs, "disk"), HashSet::from(["disk-0"]));
+        assert!(oldest_runs(&runs[..10], "disk").is_empty());
+    }
+
+    #[test]
+    fn test_host_result() {
+        let result = host_result(fleet_result("used 91%", "warning: low space\n", false));
+        assert_eq!(result.output, "used 91%\nwarning: low space\n");
+        assert!(!result.truncated);
+
+        let long = host_result(fleet_result(
+            &"x".repeat(MAX_JOB_OUTPUT_BYTES + 1),
+            "",
+            true,
+        ));
+        assert!(long.truncated);
+        assert_eq!(long.output.len(), MAX_JOB_OUTPUT_BYTES);
+
+        let run = JobRun {
+            id: "r".to_string(),
+            job_id: "1".to_string(),
+            job_name: "Disk space".to_string(),
+            started_at: 0,
+            scheduled: true,
+            results: vec![result, long],
+            error: None,
+        };
+        assert!(run.failed());
+        assert_eq!(run.failed_hosts(), vec!["web"]);
+    }
+}
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Kerberos ticket cache status (from klist)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KerberosTicketStatus {
+    /// Whether the klist command could be run at all
+    pub klist_available: bool,
+    pub has_ticket: bool,
+    pub principal: Option<String>,
+    pub message: String,
+}
+
+/// Kerberos diagnostics service
+pub struct KerberosService;
+
+impl KerberosService {
+    /// Check whether a Kerberos ticket is available by running klist
+    pub async fn check_ticket() -> KerberosTicketStatus {
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            Command::new("klist")
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                // Dropping the future on timeout kills the process
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(output)) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let status = Self::parse_klist_output(&stdout, output.status.success());
+                tracing::info!(
+                    "[kerberos_service] Ticket available: {}, principal: {:?}",
+                    status.has_ticket,
+                    status.principal
+                );
+                status
+            }
+            Ok(Err(e)) => {
+                tracing::info!("[kerberos_service] klist not available: {}", e);
+                KerberosTicketStatus {
+                    klist_available: false,
+                    has_ticket: false,
+                    principal: None,
+                    message: "klist was not found. Kerberos tools may not be installed."
+                        .to_string(),
+                }
+            }
+            Err(_) => KerberosTicketStatus {
+                klist_available: true,
+                has_ticket: false,
+                principal: None,
+                message: "klist timed out".to_string(),
+            },
+        }
+    }
+
+    /// Parse klist output (MIT, Heimdal and Windows formats)
+    fn parse_klist_output(stdout: &str, exit_success: bool) -> KerberosTicketStatus {
+        let mut principal = None;
+        let mut windows_ticket_count = None;
+
+        for line in stdout.lines() {
+            let line = line.trim();
+
+            // MIT: "Default principal: user@REALM", Heimdal: "Principal: user@REALM"
+            if let Some(value) = line
+                .strip_prefix("Default principal:")
+                .or_else(|| line.strip_prefix("Principal:"))
+            {
+                principal = Some(value.trim().to_string());
+            }
+
+            // Windows: "Cached Tickets: (2)" and "#0>  Client: user @ REALM"
+            if let Some(value) = line.strip_prefix("Cached Tickets:") {
+                windows_ticket_count = value
+                    .trim()
+                    .trim_start_matches('(')
+                    .trim_end_matches(')')
+                    .parse::<u32>()
+                    .ok();
+            }
+            if principal.is_none() {
+                if let Some((_, value)) = line.split_once("Client:") {
+                    principal = Some(value.trim().replace(" @ ", "@"));
+                }
+            }
+        }
+
+        let has_ticket =
+            exit_success && principal.is_some() && !matches!(windows_ticket_count, Some(0));
+
+        let message = match (&principal, has_ticket) {
+            (Some(p), true) => format!("Kerberos ticket available for {}", p),
+            _ => "No Kerberos ticket found. Run 'kinit' to obtain one.".to_string(),
+        };
+
+        KerberosTicketStatus {
+            klist_available: true,
+            has_ticket,
+            principal: if has_ticket { principal } else { None },
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_klist_mit() {
+        let output = "Ticket cache: FILE:/tmp/krb5cc_1000
+Default principal: alice@CORP.EXAMPLE.COM
+
+Valid starting       Expires              Service principal
+01/02/2025 09:00:00  01/02/2025 19:00:00  krbtgt/CORP.EXAMPLE.COM@CORP.EXAMPLE.COM
+";
+        let status = KerberosService::parse_klist_output(output, true);
+        assert!(status.has_ticket);
+        assert_eq!(status.principal.as_deref(), Some("alice@CORP.EXAMPLE.COM"));
+    }
+
+    #[test]
+    fn test_parse_klist_mit_no_cache() {
+        let status = KerberosService::parse_klist_output("", false);
+        assert!(status.klist_available);
+        assert!(!status.has_ticket);
+        assert!(status.message.contains("kinit"));
+    }
+
+    #[test]
+    fn test_parse_klist_heimdal() {
+        let output = "Credentials cache: API:1234
+        Principal: bob@EXAMPLE.ORG
+
+  Issued                Expires               Principal
+Jan  2 09:00:00 2025  Jan  2 19:00:00 2025  krbtgt/EXAMPLE.ORG@EXAMPLE.ORG
+";
+        let status = KerberosService::parse_klist_output(output, true);
+        assert!(status.has_ticket);
+        assert_eq!(status.principal.as_deref(), Some("bob@EXAMPLE.ORG"));
+    }
+
+    #[test]
+    fn test_parse_klist_windows() {
+        let output = "
+Current LogonId is 0:0x3e7b1
+
+Cached Tickets: (2)
+
+#0>     Client: carol @ CORP.EXAMPLE.COM
+        Server: krbtgt/CORP.EXAMPLE.COM @ CORP.EXAMPLE.COM
+";
+        let status = KerberosService::parse_klist_output(output, true);
+        assert!(status.has_ticket);
+        assert_eq!(status.principal.as_deref(), Some("carol@CORP.EXAMPLE.COM"));
+    }
+
+    #[test]
+    fn test_parse_klist_windows_empty() {
+        let output = "
+Current LogonId is 0:0x3e7b1
+
+Cached Tickets: (0)
+";
+        let status = KerberosService::parse_klist_output(output, true);
+        assert!(!status.has_ticket);
+        assert_eq!(status.principal, None);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::key_exposure::KeyExposureService;
+use crate::services::key_manager::KeyManager;
+use crate::services::script_service::shell_quote;
+use crate::services::ssh_connection::SshConnectionService;
+use crate::utils::{build_authorized_keys_line, split_public_key, AuthorizedKeyOptions};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Timeout for updating authorized_keys
+const DEPLOY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Key deployment request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyDeployRequest {
+    pub host_alias: String,
+    /// Key in ~/.ssh (its .pub file is deployed)
+    pub key_name: String,
+    #[serde(default)]
+    pub options: AuthorizedKeyOptions,
+}
+
+/// Key deployment result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyDeployResult {
+    pub host_alias: String,
+    /// Line written to ~/.ssh/authorized_keys
+    pub line: String,
+    /// True when an existing line for the same key was replaced
+    pub replaced: bool,
+}
+
+/// Installs public keys in a host's ~/.ssh/authorized_keys
+pub struct KeyDeployService;
+
+impl KeyDeployService {
+    /// Preview the authorized_keys line without connecting
+    pub async fn preview(key_name: &str, options: &AuthorizedKeyOptions) -> SshResult<String> {
+        let public_key = KeyManager::new()?.read_public_key(key_name).await?;
+        build_authorized_keys_line(&public_key, options)
+    }
+
+    /// Add the key (with its options) to the remote authorized_keys
+    /// An existing line for the same key is replaced, so options can be changed later
+    pub async fn deploy(
+        request: &KeyDeployRequest,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<KeyDeployResult> {
+        let public_key = KeyManager::new()?
+            .read_public_key(&request.key_name)
+            .await?;
+        let result =
+            Self::deploy_public_key(&request.host_alias, &public_key, &request.options, prompter)
+                .await?;
+        tracing::info!(
+            "[key_deploy] Deployed {} to {} (replaced={})",
+            request.key_name,
+            request.host_alias,
+            result.replaced
+        );
+        Ok(result)
+    }
+
+    /// Add a public key given as text, e.g. one generated on another host
+    pub async fn deploy_public_key(
+        host_alias: &str,
+        public_key: &str,
+        options: &AuthorizedKeyOptions,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<KeyDeployResult> {
+        let line = build_authorized_keys_line(public_key, options)?;
+        let (_, blob, _) = split_public_key(public_key)?;
+
+        let session = SshConnectionService::open_session(host_alias, prompter).await?;
+        let input = format!("{}\n", line);
+        let output = session
+            .exec(
+                &install_command(blob),
+                Some(input.as_bytes()),
+                DEPLOY_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+
+        let output = output?;
+        if !output.success() {
+            return Err(SshBuddyError::Unknown {
+                message: format!("Failed to update authorized_keys: {}", output.stderr.trim()),
+            });
+        }
+        KeyExposureService::record_deploy(public_key, host_alias).await;
+        Ok(KeyDeployResult {
+            host_alias: host_alias.to_string(),
+            line,
+            replaced: output.stdout.trim() == "replaced",
+        })
+    }
+
+    /// Remove a key's line from the remote authorized_keys; false when it wasn't there
+    pub async fn revoke_public_key(
+        host_alias: &str,
+        public_key: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<bool> {
+        let (_, blob, _) = split_public_key(public_key)?;
+        let session = SshConnectionService::open_session(host_alias, prompter).await?;
+        // Nothing on stdin: the key's line is dropped and nothing replaces it
+        let output = session
+            .exec(
+                &install_command(blob),
+                Some(b"".as_slice()),
+                DEPLOY_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+
+        let output = output?;
+        if !output.success() {
+            return Err(SshBuddyError::Unknown {
+                message: format!("Failed to update authorized_keys: {}", output.stderr.trim()),
+            });
+        }
+        let removed = output.stdout.trim() == "replaced";
+        tracing::info!(
+            "[key_deploy] Revoked key on {} (removed={})",
+            host_alias,
+            removed
+        );
+        Ok(removed)
+    }
+}
+
+/// Shell command that swaps any line containing `blob` for the line read from stdin
+/// (prints "replaced" when one existed; the file is swapped in with mv, mode 600)
+fn install_command(blob: &str) -> String {
+    let blob = shell_quote(blob);
+    format!(
+        "umask 077 && mkdir -p ~/.ssh && touch ~/.ssh/authorized_keys \
+         && if grep -qF {blob} ~/.ssh/authorized_keys; then echo replaced; fi \
+         && {{ grep -vF {blob} ~/.ssh/authorized_keys; cat; }} > ~/.ssh/authorized_keys.ssh-buddy \
+         && chmod 600 ~/.ssh/authorized_keys.ssh-buddy \
+         && mv ~/.ssh/authorized_keys.ssh-buddy ~/.ssh/authorized_keys",
+        blob = blob
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_command_quotes_blob() {
+        let command = install_command("AAAAC3Nz+/=");
+        assert!(command.contains("grep -qF AAAAC3Nz+/= ~/.ssh/authorized_keys"));
+        assert!(command.contains("grep -vF AAAAC3Nz+/= ~/.ssh/authorized_keys; cat;"));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::models::{LocalizedMessage, SshResult};
+use crate::services::doctor_service::{DoctorCheck, DoctorFinding, FindingSeverity};
+use crate::services::registry_service::now_millis;
+use crate::services::workspace_service::WorkspaceService;
+use crate::utils::ssh_dir;
+use ssh_key::Certificate;
+use tokio::fs;
+
+/// Certificates expiring within this many days are reported
+const EXPIRY_WARNING_DAYS: i64 = 14;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Finds SSH certificates (`<key>-cert.pub`) that expired or expire soon
+pub struct KeyExpiryService;
+
+impl KeyExpiryService {
+    /// Certificate expiry findings for the keys the active workspace may see
+    pub async fn scan() -> SshResult<Vec<DoctorFinding>> {
+        let dir = ssh_dir()?;
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let now = now_millis() / 1000;
+        let mut findings = Vec::new();
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(key_name) = file_name.strip_suffix("-cert.pub") else {
+                continue;
+            };
+            if !WorkspaceService::is_key_visible(key_name) {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path).await else {
+                continue;
+            };
+            match Certificate::from_openssh(content.trim()) {
+                Ok(cert) => {
+                    if let Some(finding) = expiry_finding(file_name, cert.valid_before(), now) {
+                        findings.push(finding);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("[key_expiry] Unreadable certificate {}: {}", file_name, e)
+                }
+            }
+        }
+        findings.sort_by(|a, b| a.subject.cmp(&b.subject));
+        tracing::info!("[key_expiry] {} expiring certificate(s)", findings.len());
+        Ok(findings)
+    }
+
+    /// Expiry findings for the doctor and scheduled checks
+    pub async fn check() -> Vec<DoctorFinding> {
+        match Self::scan().await {
+            Ok(findings) => findings,
+            Err(e) => {
+                tracing::warn!("[key_expiry] Scan failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Finding for a certificate valid before `valid_before` (Unix seconds; u64::MAX is forever)
+fn expiry_finding(name: &str, valid_before: u64, now: i64) -> Option<DoctorFinding> {
+    if valid_before == u64::MAX {
+        return None;
+    }
+    let valid_before = i64::try_from(valid_before).unwrap_or(i64::MAX);
+    let (problem, severity, message) = if valid_before <= now {
+        (
+            "certificateExpired",
+            FindingSeverity::Error,
+            LocalizedMessage::new("doctor.certificateExpired").with("name", name),
+        )
+    } else if valid_before - now <= EXPIRY_WARNING_DAYS * SECONDS_PER_DAY {
+        let days = (valid_before - now + SECONDS_PER_DAY - 1) / SECONDS_PER_DAY;
+        (
+            "certificateExpiring",
+            FindingSeverity::Warning,
+            LocalizedMessage::new("doctor.certificateExpiring")
+                .with("name", name)
+                .with("days", days),
+        )
+    } else {
+        return None;
+    };
+    Some(DoctorFinding {
+        id: format!("keyExpiry.{}", problem),
+        check: DoctorCheck::KeyExpiry,
+        severity,
+        subject: Some(name.to_string()),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_finding() {
+        let now = 1_800_000_000;
+        assert!(expiry_finding("id_ed25519-cert.pub", u64::MAX, now).is_none());
+        assert!(expiry_finding(
+            "id_ed25519-cert.pub",
+            (now + 30 * SECONDS_PER_DAY) as u64,
+            now
+        )
+        .is_none());
+
+        let expired = expiry_finding("id_ed25519-cert.pub", (now - 1) as u64, now).unwrap();
+        assert_eq!(expired.id, "keyExpiry.certificateExpired");
+        assert_eq!(expired.severity, FindingSeverity::Error);
+        assert_eq!(expired.subject.as_deref(), Some("id_ed25519-cert.pub"));
+
+        let expiring = expiry_finding(
+            "id_ed25519-cert.pub",
+            (now + 2 * SECONDS_PER_DAY - 60) as u64,
+            now,
+        )
+        .unwrap();
+        assert_eq!(expiring.id, "keyExpiry.certificateExpiring");
+        assert_eq!(expiring.severity, FindingSeverity::Warning);
+        assert_eq!(
+            expiring.message.params.get("days").map(String::as_str),
+            Some("2")
+        );
+    }
+}
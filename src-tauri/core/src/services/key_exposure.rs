@@ -0,0 +1,403 @@
+use crate::models::{LocalizedMessage, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::fingerprint_index::FingerprintIndexService;
+use crate::services::registry_service::{now_millis, RegistryService};
+use crate::services::ssh_connection::SshConnectionService;
+use crate::utils::{
+    append_json_line, parse_authorized_keys, read_json_lines, retain_json_lines,
+    workspace_data_path,
+};
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Timeout for reading a host's authorized_keys
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Keys opening at least this many hosts are flagged by default
+pub const DEFAULT_WIDE_KEY_HOSTS: usize = 5;
+
+/// Host tags recognized as environments, and the environment they stand for
+const ENVIRONMENT_TAGS: &[(&str, &str)] = &[
+    ("prod", "production"),
+    ("production", "production"),
+    ("live", "production"),
+    ("staging", "staging"),
+    ("stage", "staging"),
+    ("uat", "staging"),
+    ("dev", "development"),
+    ("development", "development"),
+    ("test", "test"),
+    ("qa", "test"),
+];
+
+/// How it became known that a key opens a host
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyAccessSource {
+    /// Deployed from this app
+    Deploy,
+    /// Found in the host's authorized_keys
+    Scan,
+}
+
+/// A key known to be authorized on a host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyAccessRecord {
+    pub fingerprint: String,
+    pub host_alias: String,
+    pub source: KeyAccessSource,
+    /// Unix timestamp in milliseconds
+    pub recorded_at: i64,
+}
+
+/// Host a key opens
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposedHost {
+    pub host_alias: String,
+    pub source: KeyAccessSource,
+    pub recorded_at: i64,
+    /// From the host's tags
+    pub environment: Option<String>,
+}
+
+/// Hosts a local key grants access to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyExposure {
+    pub fingerprint: String,
+    pub key_name: String,
+    pub hosts: Vec<ExposedHost>,
+    /// Environments among the hosts, sorted
+    pub environments: Vec<String>,
+    /// Opens at least the threshold number of hosts
+    pub wide: bool,
+    /// Advice for wide or cross-environment keys (catalog key `exposure.<advice>`)
+    pub suggestion: Option<LocalizedMessage>,
+}
+
+/// Which keys open which hosts, most exposed key first
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureReport {
+    pub generated_at: i64,
+    pub wide_threshold: usize,
+    pub keys: Vec<KeyExposure>,
+}
+
+/// Local keys found in a host's authorized_keys
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyScan {
+    pub host_alias: String,
+    /// Names of the local keys authorized on the host
+    pub local_keys: Vec<String>,
+    /// Keys on the host that aren't in the SSH directory
+    pub other_keys: usize,
+}
+
+/// Tracks which keys open which hosts, from deployments and authorized_keys scans
+pub struct KeyExposureService;
+
+impl KeyExposureService {
+    fn get_records_path() -> SshResult<PathBuf> {
+        workspace_data_path("key_access.jsonl")
+    }
+
+    /// Every key access record
+    pub(crate) async fn records() -> SshResult<Vec<KeyAccessRecord>> {
+        read_json_lines(&Self::get_records_path()?).await
+    }
+
+    /// Remember a deployment; failures are logged, they must not fail the deployment
+    pub async fn record_deploy(public_key: &str, host_alias: &str) {
+        let Ok(key) = PublicKey::from_openssh(public_key.trim()) else {
+            return;
+        };
+        let record = KeyAccessRecord {
+            fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+            host_alias: host_alias.to_string(),
+            source: KeyAccessSource::Deploy,
+            recorded_at: now_millis(),
+        };
+        let appended = async { append_json_line(&Self::get_records_path()?, &record).await };
+        if let Err(e) = appended.await {
+            tracing::warn!("[key_exposure] Failed to record deployment: {}", e);
+        }
+    }
+
+    /// Read a host's authorized_keys; the result replaces everything known about the host
+    pub async fn scan_host(
+        host_alias: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<HostKeyScan> {
+        let session = SshConnectionService::open_session(host_alias, prompter).await?;
+        let output = session
+            .exec(
+                "cat ~/.ssh/authorized_keys 2>/dev/null",
+                None,
+                SCAN_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+        let authorized = authorized_fingerprints(&output?.stdout);
+        let local_keys = Self::record_scan(host_alias, &authorized).await?;
+        Ok(HostKeyScan {
+            host_alias: host_alias.to_string(),
+            other_keys: authorized.len() - local_keys.len(),
+            local_keys,
+        })
+    }
+
+    /// Replace what is known about a host with the keys found in its authorized_keys;
+    /// returns the names of the local keys among them
+    pub(crate) async fn record_scan(
+        host_alias: &str,
+        fingerprints: &[String],
+    ) -> SshResult<Vec<String>> {
+        let names = local_key_names().await?;
+        let path = Self::get_records_path()?;
+        retain_json_lines(&path, |r: &KeyAccessRecord| r.host_alias != host_alias).await?;
+        let now = now_millis();
+        let mut local_keys = Vec::new();
+        for fingerprint in fingerprints {
+            let Some(name) = names.get(fingerprint) else {
+                continue;
+            };
+            let record = KeyAccessRecord {
+                fingerprint: fingerprint.clone(),
+                host_alias: host_alias.to_string(),
+                source: KeyAccessSource::Scan,
+                recorded_at: now,
+            };
+            append_json_line(&path, &record).await?;
+            local_keys.push(name.clone());
+        }
+        local_keys.sort();
+        tracing::info!(
+            "[key_exposure] {} authorizes {} local key(s) of {}",
+            host_alias,
+            local_keys.len(),
+            fingerprints.len()
+        );
+        Ok(local_keys)
+    }
+
+    /// Hosts a key (by SHA256 fingerprint) is known to be authorized on
+    pub async fn hosts_with(fingerprint: &str) -> SshResult<Vec<String>> {
+        let records: Vec<KeyAccessRecord> = read_json_lines(&Self::get_records_path()?).await?;
+        let mut hosts: Vec<String> = records
+            .into_iter()
+            .filter(|r| r.fingerprint == fingerprint)
+            .map(|r| r.host_alias)
+            .collect();
+        hosts.sort();
+        hosts.dedup();
+        Ok(hosts)
+    }
+
+    /// Exposure of the local keys; keys opening `wide_threshold` or more hosts are flagged
+    pub async fn report(wide_threshold: Option<usize>) -> SshResult<ExposureReport> {
+        let wide_threshold = wide_threshold.unwrap_or(DEFAULT_WIDE_KEY_HOSTS).max(2);
+        let records: Vec<KeyAccessRecord> = read_json_lines(&Self::get_records_path()?).await?;
+        let names = local_key_names().await?;
+        let store = RegistryService::load().await?;
+        let environments: HashMap<String, String> = store
+            .hosts
+            .iter()
+            .filter_map(|(alias, metadata)| Some((alias.clone(), environment_of(&metadata.tags)?)))
+            .collect();
+        Ok(ExposureReport {
+            generated_at: now_millis(),
+            wide_threshold,
+            keys: build_exposures(&records, &names, &environments, wide_threshold),
+        })
+    }
+}
+
+/// Local key names by SHA256 fingerprint, from the fingerprint index
+async fn local_key_names() -> SshResult<HashMap<String, String>> {
+    Ok(FingerprintIndexService::local_key_names()
+        .await?
+        .into_iter()
+        .collect())
+}
+
+/// Fingerprints of the keys in authorized_keys content (options and comments allowed)
+fn authorized_fingerprints(content: &str) -> Vec<String> {
+    let mut fingerprints = Vec::new();
+    for line in parse_authorized_keys(content) {
+        let fingerprint = line.key.fingerprint(HashAlg::Sha256).to_string();
+        if !fingerprints.contains(&fingerprint) {
+            fingerprints.push(fingerprint);
+        }
+    }
+    fingerprints
+}
+
+/// Environment named by a host's tags (the first recognized tag wins)
+fn environment_of(tags: &[String]) -> Option<String> {
+    tags.iter().find_map(|tag| {
+        let tag = tag.to_lowercase();
+        ENVIRONMENT_TAGS
+            .iter()
+            .find(|(name, _)| *name == tag)
+            .map(|(_, environment)| environment.to_string())
+    })
+}
+
+fn build_exposures(
+    records: &[KeyAccessRecord],
+    names: &HashMap<String, String>,
+    environments: &HashMap<String, String>,
+    wide_threshold: usize,
+) -> Vec<KeyExposure> {
+    // Latest record per key and host
+    let mut latest: BTreeMap<(&str, &str), &KeyAccessRecord> = BTreeMap::new();
+    for record in records {
+        let entry = latest
+            .entry((record.fingerprint.as_str(), record.host_alias.as_str()))
+            .or_insert(record);
+        if record.recorded_at >= entry.recorded_at {
+            *entry = record;
+        }
+    }
+
+    let mut by_key: BTreeMap<&str, Vec<ExposedHost>> = BTreeMap::new();
+    for ((fingerprint, host_alias), record) in latest {
+        if !names.contains_key(fingerprint) {
+            continue;
+        }
+        by_key.entry(fingerprint).or_default().push(ExposedHost {
+            host_alias: host_alias.to_string(),
+            source: record.source,
+            recorded_at: record.recorded_at,
+            environment: environments.get(host_alias).cloned(),
+        });
+    }
+
+    let mut exposures: Vec<KeyExposure> = by_key
+        .into_iter()
+        .map(|(fingerprint, hosts)| {
+            let key_name = names[fingerprint].clone();
+            let environments: Vec<String> = hosts
+                .iter()
+                .filter_map(|h| h.environment.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            let wide = hosts.len() >= wide_threshold;
+            let suggestion = if environments.len() > 1 {
+                Some(
+                    LocalizedMessage::new("exposure.splitByEnvironment")
+                        .with("key", &key_name)
+                        .with("environments", environments.join(", ")),
+                )
+            } else if wide {
+                Some(
+                    LocalizedMessage::new("exposure.wide")
+                        .with("key", &key_name)
+                        .with("count", hosts.len()),
+                )
+            } else {
+                None
+            };
+            KeyExposure {
+                fingerprint: fingerprint.to_string(),
+                key_name,
+                hosts,
+                environments,
+                wide,
+                suggestion,
+            }
+        })
+        .collect();
+    exposures.sort_by(|a, b| {
+        b.hosts
+            .len()
+            .cmp(&a.hosts.len())
+            .then_with(|| a.key_name.cmp(&b.key_name))
+    });
+    exposures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl a@b";
+
+    fn record(fingerprint: &str, host: &str, source: KeyAccessSource, at: i64) -> KeyAccessRecord {
+        KeyAccessRecord {
+            fingerprint: fingerprint.to_string(),
+            host_alias: host.to_string(),
+            source,
+            recorded_at: at,
+        }
+    }
+
+    #[test]
+    fn test_authorized_fingerprints() {
+        let content = format!(
+            "# keys\n{}\nrestrict,command=\"uptime\" {}\nnot a key\n",
+            KEY, KEY
+        );
+        let fingerprints = authorized_fingerprints(&content);
+        assert_eq!(fingerprints.len(), 1);
+        assert!(fingerprints[0].starts_with("SHA256:"));
+    }
+
+    #[test]
+    fn test_environment_of() {
+        assert_eq!(
+            environment_of(&["db".to_string(), "Prod".to_string()]),
+            Some("production".to_string())
+        );
+        assert_eq!(environment_of(&["db".to_string()]), None);
+    }
+
+    #[test]
+    fn test_build_exposures() {
+        let records = vec![
+            record("SHA256:a", "web", KeyAccessSource::Deploy, 1),
+            record("SHA256:a", "web", KeyAccessSource::Scan, 2),
+            record("SHA256:a", "dev-box", KeyAccessSource::Deploy, 1),
+            record("SHA256:a", "db", KeyAccessSource::Scan, 1),
+            record("SHA256:b", "ci", KeyAccessSource::Deploy, 1),
+            record("SHA256:gone", "web", KeyAccessSource::Deploy, 1),
+        ];
+        let names = HashMap::from([
+            ("SHA256:a".to_string(), "id_main".to_string()),
+            ("SHA256:b".to_string(), "id_ci".to_string()),
+        ]);
+        let environments = HashMap::from([
+            ("web".to_string(), "production".to_string()),
+            ("db".to_string(), "production".to_string()),
+            ("dev-box".to_string(), "development".to_string()),
+        ]);
+        let exposures = build_exposures(&records, &names, &environments, 3);
+        assert_eq!(exposures.len(), 2);
+
+        let main = &exposures[0];
+        assert_eq!(main.key_name, "id_main");
+        assert_eq!(main.hosts.len(), 3);
+        assert!(main.wide);
+        assert_eq!(main.environments, vec!["development", "production"]);
+        assert_eq!(
+            main.suggestion.as_ref().map(|s| s.key.as_str()),
+            Some("exposure.splitByEnvironment")
+        );
+        let web = main.hosts.iter().find(|h| h.host_alias == "web").unwrap();
+        assert_eq!(web.source, KeyAccessSource::Scan);
+
+        let ci = &exposures[1];
+        assert!(!ci.wide);
+        assert!(ci.suggestion.is_none());
+    }
+}
@@ -1,5 +1,9 @@
 use crate::models::{KeyDetails, KeyType, SSHKeyInfo, SshBuddyError, SshResult};
-use crate::utils::validate_key_name;
+use crate::services::onboarding_service::{OnboardingService, OnboardingStep};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::trash_service::{DeletedKind, TrashService};
+use crate::services::workspace_service::WorkspaceService;
+use crate::utils::{key_randomart, ssh_dir, validate_key_name, write_atomic};
 use rand::rngs::OsRng;
 use serde::Deserialize;
 use ssh_key::{Algorithm, LineEnding, PrivateKey, PublicKey};
@@ -27,12 +31,12 @@ pub struct KeyManager {
 impl KeyManager {
     /// Create a new KeyManager instance
     pub fn new() -> SshResult<Self> {
-        let home = dirs::home_dir().ok_or(SshBuddyError::HomeDirNotFound)?;
-        let ssh_dir = home.join(".ssh");
-        Ok(Self { ssh_dir })
+        Ok(Self {
+            ssh_dir: ssh_dir()?,
+        })
     }
 
-    /// List all SSH keys
+    /// List the SSH keys the active workspace may see
     pub async fn list_keys(&self) -> SshResult<Vec<SSHKeyInfo>> {
         let mut keys = Vec::new();
 
@@ -63,29 +67,39 @@ impl KeyManager {
     /// Parse public key file and create SSHKeyInfo
     async fn parse_public_key_file(&self, pub_key_path: &PathBuf) -> Option<SSHKeyInfo> {
         let file_name = pub_key_path.file_stem()?.to_str()?;
+        if !WorkspaceService::is_key_visible(file_name) {
+            return None;
+        }
         let private_key_path = self.ssh_dir.join(file_name);
 
         // Read public key content
         let pub_key_content = fs::read_to_string(pub_key_path).await.ok()?;
 
         // Parse public key
-        let (key_type, fingerprint, comment, bit_size) =
+        let (key_type, fingerprint, comment, bit_size, randomart) =
             match PublicKey::from_openssh(&pub_key_content) {
                 Ok(pub_key) => {
                     let algo_str = pub_key.algorithm().as_str().to_string();
-                    log::info!("[key_manager] Parsed key algorithm: {}", algo_str);
+                    tracing::info!("[key_manager] Parsed key algorithm: {}", algo_str);
                     let key_type = KeyType::from(algo_str.as_str());
-                    log::info!("[key_manager] Mapped to KeyType: {:?}", key_type);
+                    tracing::info!("[key_manager] Mapped to KeyType: {:?}", key_type);
                     let fingerprint = pub_key.fingerprint(ssh_key::HashAlg::Sha256).to_string();
                     let comment = pub_key.comment().to_string();
                     let bit_size = self.get_key_bit_size(&pub_key);
-                    (key_type, Some(fingerprint), Some(comment), bit_size)
+                    let randomart = key_randomart(&pub_key);
+                    (
+                        key_type,
+                        Some(fingerprint),
+                        Some(comment),
+                        bit_size,
+                        Some(randomart),
+                    )
                 }
                 Err(e) => {
                     // Cannot parse, try to infer type from filename
-                    log::warn!("[key_manager] Failed to parse public key: {:?}", e);
+                    tracing::warn!("[key_manager] Failed to parse public key: {:?}", e);
                     let key_type = self.infer_key_type_from_name(file_name);
-                    (key_type, None, None, None)
+                    (key_type, None, None, None, None)
                 }
             };
 
@@ -98,6 +112,7 @@ impl KeyManager {
             fingerprint,
             comment,
             bit_size,
+            randomart,
         })
     }
 
@@ -144,7 +159,7 @@ impl KeyManager {
 
         let pub_key_path = self.ssh_dir.join(format!("{}.pub", key_name));
 
-        if !pub_key_path.exists() {
+        if !pub_key_path.exists() || !WorkspaceService::is_key_visible(key_name) {
             return Err(SshBuddyError::KeyNotFound {
                 path: pub_key_path.to_string_lossy().to_string(),
             });
@@ -175,6 +190,15 @@ impl KeyManager {
                 path: key_path.to_string(),
             });
         }
+        let key_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().trim_end_matches(".pub").to_string())
+            .unwrap_or_default();
+        if !WorkspaceService::is_key_visible(&key_name) {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_path.to_string(),
+            });
+        }
 
         // Read public key
         let content = fs::read_to_string(&path)
@@ -196,11 +220,117 @@ impl KeyManager {
             fingerprint,
             comment,
             key_type,
+            randomart: key_randomart(&pub_key),
         })
     }
 
+    /// Rewrite the comment of a key pair in both the private key and the .pub
+    /// Encrypted private keys need the passphrase; legacy PEM keys have no comment,
+    /// so only their .pub changes
+    pub async fn set_comment(
+        &self,
+        key_name: &str,
+        comment: &str,
+        passphrase: Option<&str>,
+    ) -> SshResult<SSHKeyInfo> {
+        ReadOnlyMode::ensure_writable("change SSH key comment")?;
+        validate_key_name(key_name)?;
+        if !WorkspaceService::is_key_visible(key_name) {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_name.to_string(),
+            });
+        }
+        let comment = comment.trim();
+        if comment.chars().any(char::is_control) {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Key comments can't contain control characters".to_string(),
+            });
+        }
+
+        let private_key_path = self.ssh_dir.join(key_name);
+        let public_key_path = self.ssh_dir.join(format!("{}.pub", key_name));
+        let mut public_key = match fs::read_to_string(&public_key_path).await {
+            Ok(content) => Some(PublicKey::from_openssh(&content)?),
+            Err(_) => None,
+        };
+
+        let private_content = fs::read_to_string(&private_key_path).await.ok();
+        let parsed = private_content
+            .as_deref()
+            .and_then(|content| PrivateKey::from_openssh(content).ok());
+        let new_private = match parsed {
+            Some(private_key) => {
+                let encrypted = private_key.is_encrypted();
+                let mut decrypted = if encrypted {
+                    let passphrase = passphrase.filter(|p| !p.is_empty()).ok_or_else(|| {
+                        SshBuddyError::PassphraseRequired {
+                            path: private_key_path.to_string_lossy().to_string(),
+                        }
+                    })?;
+                    private_key.decrypt(passphrase).map_err(|_| {
+                        SshBuddyError::PassphraseRequired {
+                            path: private_key_path.to_string_lossy().to_string(),
+                        }
+                    })?
+                } else {
+                    private_key
+                };
+                if let Some(public) = &public_key {
+                    if public.key_data() != decrypted.public_key().key_data() {
+                        return Err(SshBuddyError::InvalidKeyFormat {
+                            message: format!("{}.pub doesn't belong to {}", key_name, key_name),
+                        });
+                    }
+                }
+                decrypted.set_comment(comment);
+                public_key.get_or_insert_with(|| decrypted.public_key().clone());
+                let reencrypted = match passphrase.filter(|_| encrypted) {
+                    Some(passphrase) => decrypted.encrypt(&mut OsRng, passphrase).map_err(|e| {
+                        SshBuddyError::Unknown {
+                            message: format!("Failed to encrypt key: {}", e),
+                        }
+                    })?,
+                    None => decrypted,
+                };
+                let pem =
+                    reencrypted
+                        .to_openssh(LineEnding::LF)
+                        .map_err(|e| SshBuddyError::Unknown {
+                            message: format!("Failed to serialize key: {}", e),
+                        })?;
+                Some(pem.to_string())
+            }
+            None => None,
+        };
+
+        let Some(mut public_key) = public_key else {
+            return Err(SshBuddyError::KeyNotFound {
+                path: public_key_path.to_string_lossy().to_string(),
+            });
+        };
+        public_key.set_comment(comment);
+        let public_content = public_key
+            .to_openssh()
+            .map_err(|e| SshBuddyError::Unknown {
+                message: format!("Failed to serialize public key: {}", e),
+            })?;
+
+        if let Some(pem) = new_private {
+            write_atomic(&private_key_path, pem.as_bytes()).await?;
+        }
+        write_atomic(&public_key_path, format!("{}\n", public_content).as_bytes()).await?;
+        tracing::info!("[key_manager] Updated comment of key: {}", key_name);
+
+        self.parse_public_key_file(&public_key_path)
+            .await
+            .ok_or_else(|| SshBuddyError::KeyNotFound {
+                path: public_key_path.to_string_lossy().to_string(),
+            })
+    }
+
     /// Generate a new SSH key pair
     pub async fn generate_key(&self, options: GenerateKeyOptions) -> SshResult<SSHKeyInfo> {
+        ReadOnlyMode::ensure_writable("generate SSH key")?;
         // Validate key name
         validate_key_name(&options.name)?;
 
@@ -225,7 +355,7 @@ impl KeyManager {
         }
 
         // Generate private key
-        let private_key = match options.key_type.to_lowercase().as_str() {
+        let mut private_key = match options.key_type.to_lowercase().as_str() {
             "ed25519" => PrivateKey::random(&mut OsRng, Algorithm::Ed25519).map_err(|e| {
                 SshBuddyError::Unknown {
                     message: format!("Failed to generate Ed25519 key: {}", e),
@@ -256,8 +386,9 @@ impl KeyManager {
             }
         };
 
-        // Set comment
+        // Set comment (kept in both halves, like ssh-keygen does)
         let comment = options.comment.as_deref().unwrap_or("");
+        private_key.set_comment(comment);
 
         // Serialize private key (optionally encrypted)
         let private_key_pem = if let Some(passphrase) = &options.passphrase {
@@ -286,21 +417,14 @@ impl KeyManager {
                 })?
         };
 
-        // Serialize public key
+        // Serialize public key (format: <algorithm> <base64> [<comment>])
         let public_key = private_key.public_key();
-        let public_key_openssh = public_key
+        let public_key_content = public_key
             .to_openssh()
             .map_err(|e| SshBuddyError::Unknown {
                 message: format!("Failed to serialize public key: {}", e),
             })?;
 
-        // Public key format: <algorithm> <base64> <comment>
-        let public_key_content = if comment.is_empty() {
-            public_key_openssh
-        } else {
-            format!("{} {}", public_key_openssh.trim(), comment)
-        };
-
         // Write private key
         fs::write(&private_key_path, private_key_pem.as_bytes()).await?;
 
@@ -326,11 +450,13 @@ impl KeyManager {
         let fingerprint = public_key.fingerprint(ssh_key::HashAlg::Sha256).to_string();
         let bit_size = self.get_key_bit_size(public_key);
 
-        log::info!(
+        tracing::info!(
             "[key_manager] Generated {} key: {}",
             options.key_type,
             options.name
         );
+        WorkspaceService::claim_key(&options.name).await;
+        OnboardingService::complete(OnboardingStep::CreateKey).await;
 
         Ok(SSHKeyInfo {
             name: options.name,
@@ -345,39 +471,96 @@ impl KeyManager {
                 Some(comment.to_string())
             },
             bit_size,
+            randomart: Some(key_randomart(public_key)),
         })
     }
 
-    /// Delete SSH key pair
-    pub async fn delete_key(&self, key_name: &str) -> SshResult<()> {
-        // Validate key name
+    /// Rebuild `<key>.pub` from the private key (OpenSSH format keys keep their public
+    /// half unencrypted, so no passphrase is needed)
+    pub async fn regenerate_public_key(
+        &self,
+        key_name: &str,
+        overwrite: bool,
+    ) -> SshResult<SSHKeyInfo> {
+        ReadOnlyMode::ensure_writable("regenerate SSH public key")?;
         validate_key_name(key_name)?;
+        if !WorkspaceService::is_key_visible(key_name) {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_name.to_string(),
+            });
+        }
 
         let private_key_path = self.ssh_dir.join(key_name);
         let public_key_path = self.ssh_dir.join(format!("{}.pub", key_name));
+        if public_key_path.exists() && !overwrite {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("{}.pub already exists", key_name),
+            });
+        }
+        let content = fs::read_to_string(&private_key_path).await.map_err(|_| {
+            SshBuddyError::KeyNotFound {
+                path: private_key_path.to_string_lossy().to_string(),
+            }
+        })?;
+        let private_key =
+            PrivateKey::from_openssh(&content).map_err(|e| SshBuddyError::InvalidKeyFormat {
+                message: format!("{} is not an OpenSSH private key: {}", key_name, e),
+            })?;
+        let public_content =
+            private_key
+                .public_key()
+                .to_openssh()
+                .map_err(|e| SshBuddyError::Unknown {
+                    message: format!("Failed to serialize public key: {}", e),
+                })?;
+        write_atomic(&public_key_path, format!("{}\n", public_content).as_bytes()).await?;
+        tracing::info!("[key_manager] Regenerated public key: {}.pub", key_name);
 
-        let mut deleted = false;
+        self.parse_public_key_file(&public_key_path)
+            .await
+            .ok_or_else(|| SshBuddyError::KeyNotFound {
+                path: public_key_path.to_string_lossy().to_string(),
+            })
+    }
 
-        // Delete private key
-        if private_key_path.exists() {
-            fs::remove_file(&private_key_path).await?;
-            deleted = true;
-            log::info!("[key_manager] Deleted private key: {}", key_name);
+    /// Delete SSH key pair
+    pub async fn delete_key(&self, key_name: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete SSH key")?;
+        // Validate key name
+        validate_key_name(key_name)?;
+        if !WorkspaceService::is_key_visible(key_name) {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_name.to_string(),
+            });
         }
 
-        // Delete public key
-        if public_key_path.exists() {
-            fs::remove_file(&public_key_path).await?;
-            deleted = true;
-            log::info!("[key_manager] Deleted public key: {}.pub", key_name);
-        }
+        let private_key_path = self.ssh_dir.join(key_name);
+        let public_key_path = self.ssh_dir.join(format!("{}.pub", key_name));
 
-        if !deleted {
+        let paths: Vec<PathBuf> = [private_key_path, public_key_path]
+            .into_iter()
+            .filter(|path| path.exists())
+            .collect();
+        if paths.is_empty() {
             return Err(SshBuddyError::KeyNotFound {
                 path: key_name.to_string(),
             });
         }
 
+        // Through the OS trash when enabled
+        let trashed = TrashService::remove_files(&paths).await?;
+        tracing::info!(
+            "[key_manager] {} key: {}",
+            if trashed.is_empty() {
+                "Deleted"
+            } else {
+                "Moved to the trash"
+            },
+            key_name
+        );
+        TrashService::record(DeletedKind::Key, key_name, trashed, None).await;
+        WorkspaceService::release_key(key_name).await;
+
         Ok(())
     }
 }
@@ -624,6 +807,74 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_set_comment() {
+        let (manager, _temp) = create_test_manager();
+        let options = GenerateKeyOptions {
+            name: "test_comment".to_string(),
+            key_type: "ed25519".to_string(),
+            comment: Some("old@example.com".to_string()),
+            passphrase: Some("secret".to_string()),
+        };
+        manager.generate_key(options).await.unwrap();
+
+        let result = manager
+            .set_comment("test_comment", "new comment", None)
+            .await;
+        assert!(matches!(
+            result,
+            Err(SshBuddyError::PassphraseRequired { .. })
+        ));
+        let info = manager
+            .set_comment("test_comment", "new comment", Some("secret"))
+            .await
+            .unwrap();
+        assert_eq!(info.comment.as_deref(), Some("new comment"));
+
+        let private_key = PrivateKey::read_openssh_file(&manager.ssh_dir.join("test_comment"))
+            .unwrap()
+            .decrypt("secret")
+            .unwrap();
+        assert_eq!(private_key.comment(), "new comment");
+        let public_key = fs::read_to_string(manager.ssh_dir.join("test_comment.pub"))
+            .await
+            .unwrap();
+        assert!(public_key.trim_end().ends_with(" new comment"));
+
+        assert!(manager
+            .set_comment("test_comment", "bad\ncomment", Some("secret"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_public_key() {
+        let (manager, _temp) = create_test_manager();
+        let options = GenerateKeyOptions {
+            name: "id_regen".to_string(),
+            key_type: "ed25519".to_string(),
+            comment: Some("regen@test".to_string()),
+            passphrase: None,
+        };
+        let generated = manager.generate_key(options).await.unwrap();
+        let public_key_path = manager.ssh_dir.join("id_regen.pub");
+        fs::remove_file(&public_key_path).await.unwrap();
+
+        let regenerated = manager
+            .regenerate_public_key("id_regen", false)
+            .await
+            .unwrap();
+        assert_eq!(regenerated.fingerprint, generated.fingerprint);
+        assert!(manager
+            .regenerate_public_key("id_regen", false)
+            .await
+            .is_err());
+        assert!(manager
+            .regenerate_public_key("id_regen", true)
+            .await
+            .is_ok());
+    }
+
     #[tokio::test]
     async fn test_generate_key_invalid_type() {
         let (manager, _temp) = create_test_manager();
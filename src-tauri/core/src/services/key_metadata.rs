@@ -0,0 +1,136 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::registry_service::{now_millis, RegistryService};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Longest accepted text field
+const MAX_FIELD_LEN: usize = 200;
+
+/// App-level description of a key, stored in the registry by fingerprint so it
+/// survives renaming the key files
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub friendly_name: Option<String>,
+    /// e.g. "CI deploys", "personal GitHub"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Host aliases the key is meant for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub linked_hosts: Vec<String>,
+    /// Unix timestamp in milliseconds
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+/// Reads and writes key metadata in the host registry
+pub struct KeyMetadataService;
+
+impl KeyMetadataService {
+    /// Metadata of all keys by SHA256 fingerprint
+    pub async fn list() -> SshResult<HashMap<String, KeyMetadata>> {
+        Ok(RegistryService::load().await?.keys)
+    }
+
+    /// Set the metadata of a key (all fields empty removes it)
+    pub async fn set(fingerprint: &str, metadata: KeyMetadata) -> SshResult<Option<KeyMetadata>> {
+        if !fingerprint.starts_with("SHA256:") {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Not a SHA256 key fingerprint: {}", fingerprint),
+            });
+        }
+        let metadata = normalize(metadata)?;
+        let updated = RegistryService::update(|store| {
+            match &metadata {
+                Some(metadata) => {
+                    store.keys.insert(
+                        fingerprint.to_string(),
+                        KeyMetadata {
+                            updated_at: now_millis(),
+                            ..metadata.clone()
+                        },
+                    );
+                }
+                None => {
+                    store.keys.remove(fingerprint);
+                }
+            }
+            Ok(store.keys.get(fingerprint).cloned())
+        })
+        .await?;
+        tracing::info!("[key_metadata] Updated metadata of {}", fingerprint);
+        Ok(updated)
+    }
+}
+
+/// Trim the fields, drop empty ones and duplicate hosts (None if nothing is left)
+fn normalize(metadata: KeyMetadata) -> SshResult<Option<KeyMetadata>> {
+    let field = |value: Option<String>| -> SshResult<Option<String>> {
+        let value = value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        if value
+            .as_ref()
+            .is_some_and(|v| v.chars().count() > MAX_FIELD_LEN || v.chars().any(char::is_control))
+        {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "Key metadata fields must be single lines of at most {} characters",
+                    MAX_FIELD_LEN
+                ),
+            });
+        }
+        Ok(value)
+    };
+    let mut linked_hosts: Vec<String> = metadata
+        .linked_hosts
+        .iter()
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .collect();
+    linked_hosts.sort();
+    linked_hosts.dedup();
+
+    let normalized = KeyMetadata {
+        friendly_name: field(metadata.friendly_name)?,
+        purpose: field(metadata.purpose)?,
+        owner: field(metadata.owner)?,
+        linked_hosts,
+        updated_at: metadata.updated_at,
+    };
+    let empty = normalized.friendly_name.is_none()
+        && normalized.purpose.is_none()
+        && normalized.owner.is_none()
+        && normalized.linked_hosts.is_empty();
+    Ok((!empty).then_some(normalized))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let metadata = KeyMetadata {
+            friendly_name: Some("  Work laptop ".to_string()),
+            purpose: Some(" ".to_string()),
+            owner: None,
+            linked_hosts: vec!["web".to_string(), " db".to_string(), "web".to_string()],
+            updated_at: 0,
+        };
+        let normalized = normalize(metadata).unwrap().unwrap();
+        assert_eq!(normalized.friendly_name.as_deref(), Some("Work laptop"));
+        assert_eq!(normalized.purpose, None);
+        assert_eq!(normalized.linked_hosts, vec!["db", "web"]);
+
+        assert_eq!(normalize(KeyMetadata::default()).unwrap(), None);
+        let bad = KeyMetadata {
+            owner: Some("a\nb".to_string()),
+            ..Default::default()
+        };
+        assert!(normalize(bad).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::models::{LocalizedMessage, SshResult};
+use crate::services::doctor_service::{DoctorCheck, DoctorFinding, FindingSeverity};
+use crate::services::workspace_service::WorkspaceService;
+use crate::utils::ssh_dir;
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PrivateKey, PublicKey};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::fs;
+
+/// Files larger than this are not keys
+const MAX_KEY_FILE_SIZE: u64 = 64 * 1024;
+
+/// What is wrong with a key pair
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeypairIssueKind {
+    /// .pub whose private key is not in the SSH directory
+    OrphanedPublicKey,
+    /// Private key without a .pub
+    MissingPublicKey,
+    /// `<key>.pub` holds a different key than `<key>`
+    Mismatch,
+}
+
+/// Key pair problem found by the scan
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeypairIssue {
+    pub kind: KeypairIssueKind,
+    /// File name of the key (without .pub)
+    pub name: String,
+    /// Fingerprint of the .pub (orphaned, mismatch) or of the private key (missing)
+    pub fingerprint: Option<String>,
+    /// Private key under another name that does belong to the .pub (mismatch only)
+    pub matching_private_key: Option<String>,
+    /// The .pub can be rebuilt from the private key
+    pub can_regenerate: bool,
+}
+
+/// Result of a key pair scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeypairScan {
+    /// Number of consistent pairs
+    pub pairs: usize,
+    /// Legacy PEM keys with a .pub, whose public half can't be derived to compare
+    pub unverified: Vec<String>,
+    pub issues: Vec<KeypairIssue>,
+}
+
+/// Private key file; `fingerprint` is None for legacy PEM keys
+struct PrivateEntry {
+    name: String,
+    fingerprint: Option<String>,
+}
+
+/// Pairs public and private keys in the SSH directory by their key material
+pub struct KeypairAuditService;
+
+impl KeypairAuditService {
+    /// Scan the keys the active workspace may see
+    pub async fn scan() -> SshResult<KeypairScan> {
+        let dir = ssh_dir()?;
+        if !dir.is_dir() {
+            return Ok(KeypairScan::default());
+        }
+        let mut publics = Vec::new();
+        let mut privates = Vec::new();
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if !metadata.is_file() || metadata.len() > MAX_KEY_FILE_SIZE {
+                continue;
+            }
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if let Some(name) = file_name.strip_suffix(".pub") {
+                if !WorkspaceService::is_key_visible(name) {
+                    continue;
+                }
+                // Unparseable .pub files are reported by the key list itself
+                if let Ok(key) = read_public_key(&path).await {
+                    publics.push((name.to_string(), fingerprint(&key)));
+                }
+            } else if WorkspaceService::is_key_visible(file_name) {
+                if let Some(private) = read_private_key(&path, file_name).await {
+                    privates.push(private);
+                }
+            }
+        }
+        let scan = audit(&publics, &privates);
+        tracing::info!(
+            "[keypair_audit] {} pair(s), {} issue(s)",
+            scan.pairs,
+            scan.issues.len()
+        );
+        Ok(scan)
+    }
+
+    /// Key pair findings for the doctor
+    pub async fn check() -> Vec<DoctorFinding> {
+        match Self::scan().await {
+            Ok(scan) => scan.issues.iter().map(finding).collect(),
+            Err(e) => {
+                tracing::warn!("[keypair_audit] Scan failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+async fn read_public_key(path: &Path) -> SshResult<PublicKey> {
+    let content = fs::read_to_string(path).await?;
+    Ok(PublicKey::from_openssh(content.trim())?)
+}
+
+/// Private key in a file, if it holds one (config files etc. are skipped)
+async fn read_private_key(path: &Path, name: &str) -> Option<PrivateEntry> {
+    let content = fs::read_to_string(path).await.ok()?;
+    let header = content.trim_start();
+    if !header.starts_with("-----BEGIN ") || !header.contains("PRIVATE KEY-----") {
+        return None;
+    }
+    // The public half of OpenSSH keys is stored unencrypted
+    let fingerprint = PrivateKey::from_openssh(&content)
+        .ok()
+        .map(|key| fingerprint(key.public_key()));
+    Some(PrivateEntry {
+        name: name.to_string(),
+        fingerprint,
+    })
+}
+
+fn fingerprint(key: &PublicKey) -> String {
+    key.fingerprint(HashAlg::Sha256).to_string()
+}
+
+/// Pair `.pub` files (name, fingerprint) with private keys by fingerprint
+fn audit(publics: &[(String, String)], privates: &[PrivateEntry]) -> KeypairScan {
+    let private_by_name: HashMap<&str, &PrivateEntry> =
+        privates.iter().map(|p| (p.name.as_str(), p)).collect();
+    let private_with = |fp: &str| {
+        privates
+            .iter()
+            .find(|p| p.fingerprint.as_deref() == Some(fp))
+            .map(|p| p.name.clone())
+    };
+
+    let mut scan = KeypairScan::default();
+    for (name, fp) in publics {
+        match private_by_name.get(name.as_str()).map(|p| &p.fingerprint) {
+            Some(Some(private_fp)) if private_fp == fp => scan.pairs += 1,
+            Some(Some(_)) => scan.issues.push(KeypairIssue {
+                kind: KeypairIssueKind::Mismatch,
+                name: name.clone(),
+                fingerprint: Some(fp.clone()),
+                matching_private_key: private_with(fp),
+                can_regenerate: true,
+            }),
+            Some(None) => scan.unverified.push(name.clone()),
+            // A private key under another name still completes the pair
+            None if private_with(fp).is_some() => scan.pairs += 1,
+            None => scan.issues.push(KeypairIssue {
+                kind: KeypairIssueKind::OrphanedPublicKey,
+                name: name.clone(),
+                fingerprint: Some(fp.clone()),
+                matching_private_key: None,
+                can_regenerate: false,
+            }),
+        }
+    }
+
+    for private in privates {
+        let has_own_pub = publics.iter().any(|(name, _)| *name == private.name);
+        let covered = has_own_pub
+            || private
+                .fingerprint
+                .as_ref()
+                .is_some_and(|fp| publics.iter().any(|(_, pub_fp)| pub_fp == fp));
+        if !covered {
+            scan.issues.push(KeypairIssue {
+                kind: KeypairIssueKind::MissingPublicKey,
+                name: private.name.clone(),
+                fingerprint: private.fingerprint.clone(),
+                matching_private_key: None,
+                can_regenerate: private.fingerprint.is_some(),
+            });
+        }
+    }
+    scan.issues.sort_by(|a, b| a.name.cmp(&b.name));
+    scan.unverified.sort();
+    scan
+}
+
+fn finding(issue: &KeypairIssue) -> DoctorFinding {
+    let (problem, severity) = match issue.kind {
+        KeypairIssueKind::Mismatch => ("keypairMismatch", FindingSeverity::Error),
+        KeypairIssueKind::OrphanedPublicKey => ("orphanedPublicKey", FindingSeverity::Warning),
+        KeypairIssueKind::MissingPublicKey => ("missingPublicKey", FindingSeverity::Info),
+    };
+    DoctorFinding {
+        id: format!("keypairs.{}", problem),
+        check: DoctorCheck::Keypairs,
+        severity,
+        subject: Some(issue.name.clone()),
+        message: LocalizedMessage::new(&format!("doctor.{}", problem)).with("key", &issue.name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private(name: &str, fingerprint: Option<&str>) -> PrivateEntry {
+        PrivateEntry {
+            name: name.to_string(),
+            fingerprint: fingerprint.map(str::to_string),
+        }
+    }
+
+    fn public(name: &str, fingerprint: &str) -> (String, String) {
+        (name.to_string(), fingerprint.to_string())
+    }
+
+    #[test]
+    fn test_audit() {
+        let publics = vec![
+            public("id_ok", "SHA256:a"),
+            public("id_swapped", "SHA256:c"),
+            public("id_orphan", "SHA256:d"),
+            public("id_renamed", "SHA256:e"),
+            public("id_legacy", "SHA256:f"),
+        ];
+        let privates = vec![
+            private("id_ok", Some("SHA256:a")),
+            private("id_swapped", Some("SHA256:b")),
+            private("id_other", Some("SHA256:c")),
+            private("id_renamed_old", Some("SHA256:e")),
+            private("id_legacy", None),
+            private("id_nopub", Some("SHA256:g")),
+            private("id_legacy_nopub", None),
+        ];
+        let scan = audit(&publics, &privates);
+        assert_eq!(scan.pairs, 2);
+        assert_eq!(scan.unverified, vec!["id_legacy"]);
+
+        let summary: Vec<(&str, KeypairIssueKind, bool)> = scan
+            .issues
+            .iter()
+            .map(|i| (i.name.as_str(), i.kind, i.can_regenerate))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("id_legacy_nopub", KeypairIssueKind::MissingPublicKey, false),
+                ("id_nopub", KeypairIssueKind::MissingPublicKey, true),
+                ("id_orphan", KeypairIssueKind::OrphanedPublicKey, false),
+                ("id_swapped", KeypairIssueKind::Mismatch, true),
+            ]
+        );
+        assert_eq!(
+            scan.issues[3].matching_private_key.as_deref(),
+            Some("id_other")
+        );
+    }
+
+    #[test]
+    fn test_finding() {
+        let issue = KeypairIssue {
+            kind: KeypairIssueKind::Mismatch,
+            name: "id_work".to_string(),
+            fingerprint: None,
+            matching_private_key: None,
+            can_regenerate: true,
+        };
+        let finding = finding(&issue);
+        assert_eq!(finding.id, "keypairs.keypairMismatch");
+        assert_eq!(finding.subject.as_deref(), Some("id_work"));
+        assert_eq!(finding.severity, FindingSeverity::Error);
+    }
+}
@@ -1,32 +1,102 @@
-use crate::models::{SshBuddyError, SshResult};
+use crate::models::{LocalizedMessage, SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::revision_service::{ManagedFile, RevisionService};
+use crate::services::trash_service::{DeletedKind, TrashService};
+use crate::utils::{for_each_file_line, ssh_dir, write_atomic, IndexCache, Page, PageQuery};
 use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
 use std::net::ToSocketAddrs;
+use std::ops::ControlFlow;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use tokio::fs;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpStream;
 use tokio::time::{timeout, Duration};
 
+/// One known_hosts line, as listed page by page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostEntry {
+    /// 1-based line number
+    pub line: u32,
+    /// "@cert-authority" or "@revoked"
+    pub marker: Option<String>,
+    /// Host patterns; empty for hashed entries
+    pub hosts: Vec<String>,
+    pub hashed: bool,
+    pub key_type: String,
+    /// SHA256 fingerprint (None when the key doesn't parse)
+    pub fingerprint: Option<String>,
+    pub comment: Option<String>,
+}
+
 /// Known Hosts service
 pub struct KnownHostsService;
 
 impl KnownHostsService {
     /// Get known_hosts file path
     fn get_known_hosts_path() -> SshResult<PathBuf> {
-        let ssh_dir = dirs::home_dir()
-            .ok_or(SshBuddyError::HomeDirNotFound)?
-            .join(".ssh");
-        Ok(ssh_dir.join("known_hosts"))
+        Ok(ssh_dir()?.join("known_hosts"))
+    }
+
+    fn index_cache() -> &'static IndexCache {
+        static CACHE: OnceLock<IndexCache> = OnceLock::new();
+        CACHE.get_or_init(IndexCache::default)
+    }
+
+    /// A page of known_hosts entries, read by offset from an index of the file
+    /// With a search, the file is streamed and only the requested page is kept
+    pub async fn list_entries(query: &PageQuery) -> SshResult<Page<KnownHostEntry>> {
+        let path = Self::get_known_hosts_path()?;
+        let mut page = Page {
+            items: Vec::new(),
+            offset: query.offset,
+            total: 0,
+            matched: 0,
+        };
+        if !path.exists() {
+            return Ok(page);
+        }
+        let index = Self::index_cache().get(&path, is_known_hosts_entry).await?;
+        page.total = index.len();
+        let range = query.offset..query.offset.saturating_add(query.limit());
+
+        match query.needle() {
+            None => {
+                page.matched = index.len();
+                for (record, text) in index.read_records(&path, range.start, range.len()).await? {
+                    let first = text.lines().next().unwrap_or_default();
+                    if let Some(entry) = parse_known_hosts_entry(record.line, first) {
+                        page.items.push(entry);
+                    }
+                }
+            }
+            Some(needle) => {
+                for_each_file_line(&path, |line, _, text| {
+                    if is_known_hosts_entry(text) && text.to_lowercase().contains(&needle) {
+                        if range.contains(&page.matched) {
+                            page.items.extend(parse_known_hosts_entry(line, text));
+                        }
+                        page.matched += 1;
+                    }
+                    ControlFlow::Continue(())
+                })
+                .await?;
+            }
+        }
+        Ok(page)
     }
 
     /// Remove host from known_hosts
     pub async fn remove_host(hostname: &str) -> SshResult<RemoveHostResult> {
+        ReadOnlyMode::ensure_writable("remove host from known_hosts")?;
         let known_hosts_path = Self::get_known_hosts_path()?;
 
         if !known_hosts_path.exists() {
             return Ok(RemoveHostResult {
                 success: true,
-                message: "known_hosts file does not exist".to_string(),
+                message: LocalizedMessage::new("knownHosts.fileMissing"),
                 removed_count: 0,
             });
         }
@@ -41,7 +111,7 @@ impl KnownHostsService {
 
         // Filter out matching lines
         let hostname_lower = hostname.to_lowercase();
-        let mut removed_count = 0;
+        let mut removed_lines = Vec::new();
         let new_lines: Vec<&str> = content
             .lines()
             .filter(|line| {
@@ -71,7 +141,7 @@ impl KnownHostsService {
                 });
 
                 if matches {
-                    removed_count += 1;
+                    removed_lines.push(*line);
                     false // Remove this line
                 } else {
                     true // Keep this line
@@ -79,27 +149,95 @@ impl KnownHostsService {
             })
             .collect();
 
+        let removed_count = removed_lines.len();
+
         // Write back to file
         let new_content = new_lines.join("\n");
-        fs::write(&known_hosts_path, new_content)
+        fs::write(&known_hosts_path, &new_content)
             .await
             .map_err(|e| SshBuddyError::IoError {
                 message: format!("Failed to write known_hosts: {}", e),
             })?;
+        if removed_count > 0 {
+            RevisionService::record_change(
+                ManagedFile::KnownHosts,
+                Some(&content),
+                &new_content,
+                &format!("Remove {} from known_hosts", hostname),
+            )
+            .await;
+            let mut removed = removed_lines.join("\n");
+            removed.push('\n');
+            TrashService::record(DeletedKind::KnownHosts, hostname, Vec::new(), Some(removed))
+                .await;
+        }
 
         Ok(RemoveHostResult {
             success: true,
             message: if removed_count > 0 {
-                format!("Removed {} entries for {}", removed_count, hostname)
+                LocalizedMessage::new("knownHosts.removed")
+                    .with("count", removed_count)
+                    .with("host", hostname)
             } else {
-                format!("No entries found for {}", hostname)
+                LocalizedMessage::new("knownHosts.noEntries").with("host", hostname)
             },
             removed_count,
         })
     }
 
+    /// Remove one known_hosts line (1-based); `expected` is the entry as it was listed,
+    /// so nothing is removed if the file changed in between
+    pub async fn remove_line(line: u32, expected: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("remove entry from known_hosts")?;
+        let known_hosts_path = Self::get_known_hosts_path()?;
+
+        let content =
+            fs::read_to_string(&known_hosts_path)
+                .await
+                .map_err(|e| SshBuddyError::IoError {
+                    message: format!("Failed to read known_hosts: {}", e),
+                })?;
+        let mut lines: Vec<&str> = content.lines().collect();
+        let index = (line as usize)
+            .checked_sub(1)
+            .filter(|&i| lines.get(i).is_some_and(|l| l.trim() == expected.trim()));
+        let removed = match index {
+            Some(index) => lines.remove(index),
+            None => {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "known_hosts changed since it was read; reload and try again"
+                        .to_string(),
+                })
+            }
+        };
+
+        let mut new_content = lines.join("\n");
+        if content.ends_with('\n') && !new_content.is_empty() {
+            new_content.push('\n');
+        }
+        write_atomic(&known_hosts_path, new_content.as_bytes()).await?;
+        let name = removed.split_whitespace().next().unwrap_or_default();
+        RevisionService::record_change(
+            ManagedFile::KnownHosts,
+            Some(&content),
+            &new_content,
+            &format!("Remove {} from known_hosts", name),
+        )
+        .await;
+        TrashService::record(
+            DeletedKind::KnownHosts,
+            name,
+            Vec::new(),
+            Some(format!("{}\n", removed)),
+        )
+        .await;
+        tracing::info!("[known_hosts] Removed line {}", line);
+        Ok(())
+    }
+
     /// Scan and add host's SSH public key to known_hosts
     pub async fn add_host(hostname: &str, port: Option<u16>) -> SshResult<AddHostResult> {
+        ReadOnlyMode::ensure_writable("add host to known_hosts")?;
         let port = port.unwrap_or(22);
         let known_hosts_path = Self::get_known_hosts_path()?;
 
@@ -109,7 +247,9 @@ impl KnownHostsService {
         if host_keys.is_empty() {
             return Ok(AddHostResult {
                 success: false,
-                message: format!("Could not retrieve host keys from {}:{}", hostname, port),
+                message: LocalizedMessage::new("knownHosts.scanFailed")
+                    .with("host", hostname)
+                    .with("port", port),
                 keys_added: 0,
             });
         }
@@ -127,6 +267,8 @@ impl KnownHostsService {
             String::new()
         };
 
+        let previous_content = (!existing_content.is_empty()).then(|| existing_content.clone());
+
         // Add new host keys
         let mut keys_added = 0;
         for key in &host_keys {
@@ -153,10 +295,21 @@ impl KnownHostsService {
             .map_err(|e| SshBuddyError::IoError {
                 message: format!("Failed to write known_hosts: {}", e),
             })?;
+        if keys_added > 0 {
+            RevisionService::record_change(
+                ManagedFile::KnownHosts,
+                previous_content.as_deref(),
+                &existing_content,
+                &format!("Add {} to known_hosts", hostname),
+            )
+            .await;
+        }
 
         Ok(AddHostResult {
             success: true,
-            message: format!("Added {} key(s) for {}", keys_added, hostname),
+            message: LocalizedMessage::new("knownHosts.added")
+                .with("count", keys_added)
+                .with("host", hostname),
             keys_added,
         })
     }
@@ -250,12 +403,51 @@ impl KnownHostsService {
     }
 }
 
+/// known_hosts lines that hold an entry (not blank, not a comment)
+fn is_known_hosts_entry(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+/// Parse `[@marker] hosts key-type key [comment]`
+fn parse_known_hosts_entry(line_number: u32, line: &str) -> Option<KnownHostEntry> {
+    let mut fields = line.split_whitespace();
+    let mut first = fields.next()?;
+    let marker = if first.starts_with('@') {
+        let marker = first.to_string();
+        first = fields.next()?;
+        Some(marker)
+    } else {
+        None
+    };
+    let key_type = fields.next()?;
+    let key = fields.next()?;
+    let comment = fields.collect::<Vec<_>>().join(" ");
+    let hashed = first.starts_with("|1|");
+    Some(KnownHostEntry {
+        line: line_number,
+        marker,
+        hosts: if hashed {
+            Vec::new()
+        } else {
+            first.split(',').map(str::to_string).collect()
+        },
+        hashed,
+        key_type: key_type.to_string(),
+        fingerprint: PublicKey::from_openssh(&format!("{} {}", key_type, key))
+            .ok()
+            .map(|k| k.fingerprint(HashAlg::Sha256).to_string()),
+        comment: Some(comment).filter(|c| !c.is_empty()),
+    })
+}
+
 /// Result of removing host
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoveHostResult {
     pub success: bool,
-    pub message: String,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
     pub removed_count: usize,
 }
 
@@ -264,7 +456,8 @@ pub struct RemoveHostResult {
 #[serde(rename_all = "camelCase")]
 pub struct AddHostResult {
     pub success: bool,
-    pub message: String,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
     pub keys_added: usize,
 }
 
@@ -374,6 +567,32 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_known_hosts_entry() {
+        let entry = parse_known_hosts_entry(
+            3,
+            "github.com,140.82.112.3 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl",
+        )
+        .unwrap();
+        assert_eq!(entry.line, 3);
+        assert_eq!(entry.hosts, vec!["github.com", "140.82.112.3"]);
+        assert_eq!(entry.key_type, "ssh-ed25519");
+        assert!(entry.fingerprint.unwrap().starts_with("SHA256:"));
+        assert_eq!(entry.comment, None);
+
+        let entry =
+            parse_known_hosts_entry(1, "@cert-authority *.corp ssh-ed25519 AAAA ca key").unwrap();
+        assert_eq!(entry.marker.as_deref(), Some("@cert-authority"));
+        assert_eq!(entry.hosts, vec!["*.corp"]);
+        assert_eq!(entry.fingerprint, None);
+        assert_eq!(entry.comment.as_deref(), Some("ca key"));
+
+        let entry = parse_known_hosts_entry(1, "|1|abc=|def= ssh-rsa AAAA").unwrap();
+        assert!(entry.hashed);
+        assert!(entry.hosts.is_empty());
+        assert!(parse_known_hosts_entry(1, "github.com").is_none());
+    }
+
     // ========================================
     // Format generation tests
     // ========================================
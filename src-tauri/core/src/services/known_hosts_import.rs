@@ -0,0 +1,292 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::vm_discovery::sanitize_alias;
+use crate::utils::{ssh_dir, validate_hostname, SshConfigEditor, SshConfigParser};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::net::IpAddr;
+use tokio::fs;
+
+/// Registry tag given to imported hosts
+const KNOWN_HOSTS_TAG: &str = "known-hosts";
+
+/// Git forges are reached through git remotes, not Host entries
+const GIT_FORGES: &[&str] = &[
+    "github.com",
+    "gitlab.com",
+    "bitbucket.org",
+    "ssh.dev.azure.com",
+];
+
+/// Host with a trusted key in known_hosts but no Host entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostCandidate {
+    /// Proposed alias (may be changed before importing)
+    pub alias: String,
+    /// Name used as HostName (a DNS name if the entry lists one)
+    pub host: String,
+    /// From the `[host]:port` syntax
+    pub port: Option<u16>,
+    /// Other names/addresses listed for the same key
+    pub other_names: Vec<String>,
+    pub key_types: Vec<String>,
+}
+
+/// known_hosts import request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostsImportRequest {
+    pub hosts: Vec<KnownHostCandidate>,
+    pub user: Option<String>,
+}
+
+/// known_hosts import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostsImportResult {
+    pub created: Vec<String>,
+    /// Aliases skipped because they already exist or are invalid
+    pub skipped: Vec<String>,
+}
+
+/// Suggests Host entries from the unhashed entries of ~/.ssh/known_hosts
+pub struct KnownHostsImportService;
+
+impl KnownHostsImportService {
+    /// Hosts in known_hosts that have no Host entry (hashed entries can't be read)
+    pub async fn discover() -> SshResult<Vec<KnownHostCandidate>> {
+        let path = ssh_dir()?.join("known_hosts");
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(SshBuddyError::IoError {
+                    message: format!("Failed to read known_hosts: {}", e),
+                })
+            }
+        };
+        let editor = ConfigService::load_editor().await?;
+        let candidates = collect_candidates(&content, &editor);
+        tracing::info!(
+            "[known_hosts_import] Found {} host(s) missing from the config",
+            candidates.len()
+        );
+        Ok(candidates)
+    }
+
+    /// Create Host entries for the selected known_hosts entries
+    pub async fn import_hosts(
+        request: &KnownHostsImportRequest,
+    ) -> SshResult<KnownHostsImportResult> {
+        ReadOnlyMode::ensure_writable("import hosts from known_hosts")?;
+
+        let (created, skipped) = ConfigService::create_hosts_with_tags(KNOWN_HOSTS_TAG, |editor| {
+            let mut created = Vec::new();
+            let mut skipped = Vec::new();
+            for host in &request.hosts {
+                let alias = host.alias.trim();
+                if editor.has_host(alias)
+                    || ConfigService::validate_alias(alias).is_err()
+                    || validate_hostname(&host.host).is_err()
+                {
+                    skipped.push(host.alias.clone());
+                    continue;
+                }
+                editor.append_host(alias, &host_options(host, request.user.as_deref()));
+                created.push(alias.to_string());
+            }
+            (created, skipped)
+        })
+        .await?;
+
+        tracing::info!(
+            "[known_hosts_import] Imported {} host(s) ({} skipped)",
+            created.len(),
+            skipped.len()
+        );
+        Ok(KnownHostsImportResult { created, skipped })
+    }
+}
+
+/// `host` or `[host]:port` (None for hashed entries, patterns and loopback)
+fn parse_name(name: &str) -> Option<(String, Option<u16>)> {
+    if name.is_empty() || name.starts_with('|') || name.contains(['*', '?', '!']) {
+        return None;
+    }
+    let (host, port) = match name.strip_prefix('[') {
+        Some(rest) => {
+            let (host, port) = rest.split_once("]:")?;
+            let port = port.parse::<u16>().ok()?;
+            (host, (port != 22).then_some(port))
+        }
+        None => (name, None),
+    };
+    let host = host.to_lowercase();
+    let loopback = host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback());
+    if loopback || validate_hostname(&host).is_err() {
+        return None;
+    }
+    Some((host, port))
+}
+
+/// One candidate per host/port; DNS names are preferred over addresses as HostName
+fn collect_candidates(content: &str, editor: &SshConfigEditor) -> Vec<KnownHostCandidate> {
+    let configured: HashSet<String> = SshConfigParser::parse(&editor.render())
+        .iter()
+        .flat_map(|h| {
+            h.host_pattern
+                .split_whitespace()
+                .map(str::to_string)
+                .chain(h.hostname.clone())
+                .collect::<Vec<_>>()
+        })
+        .map(|name| name.to_lowercase())
+        .collect();
+
+    let mut found: BTreeMap<(String, Option<u16>), KnownHostCandidate> = BTreeMap::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(names), Some(key_type)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        // Comments and @cert-authority / @revoked markers
+        if names.starts_with('#') || names.starts_with('@') {
+            continue;
+        }
+        let mut parsed: Vec<(String, Option<u16>)> =
+            names.split(',').filter_map(parse_name).collect();
+        if parsed.is_empty()
+            || parsed
+                .iter()
+                .any(|(host, _)| configured.contains(host) || GIT_FORGES.contains(&host.as_str()))
+        {
+            continue;
+        }
+        // DNS names first, keeping the file order otherwise
+        parsed.sort_by_key(|(host, _)| host.parse::<IpAddr>().is_ok());
+        let (host, port) = parsed[0].clone();
+        let others: Vec<String> = parsed[1..].iter().map(|(h, _)| h.clone()).collect();
+
+        let candidate = found
+            .entry((host.clone(), port))
+            .or_insert_with(|| KnownHostCandidate {
+                alias: String::new(),
+                host,
+                port,
+                other_names: Vec::new(),
+                key_types: Vec::new(),
+            });
+        for other in others {
+            if !candidate.other_names.contains(&other) {
+                candidate.other_names.push(other);
+            }
+        }
+        if !candidate.key_types.iter().any(|t| t == key_type) {
+            candidate.key_types.push(key_type.to_string());
+        }
+    }
+
+    let mut taken: HashSet<String> = editor.host_aliases().into_iter().collect();
+    let mut candidates: Vec<KnownHostCandidate> = found.into_values().collect();
+    for candidate in &mut candidates {
+        candidate.alias = propose_alias(&candidate.host, candidate.port, &taken);
+        taken.insert(candidate.alias.clone());
+    }
+    candidates
+}
+
+/// First label of a hostname (whole IP addresses), then with the port, then a counter
+fn propose_alias(host: &str, port: Option<u16>, taken: &HashSet<String>) -> String {
+    let base = if host.parse::<IpAddr>().is_ok() {
+        sanitize_alias(host)
+    } else {
+        sanitize_alias(host.split('.').next().unwrap_or(host))
+    };
+    let mut candidates = vec![base.clone()];
+    if let Some(port) = port {
+        candidates.push(format!("{}-{}", base, port));
+    }
+    if let Some(alias) = candidates.into_iter().find(|a| !taken.contains(a)) {
+        return alias;
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|a| !taken.contains(a))
+        .unwrap_or(base)
+}
+
+/// Host block options for a known_hosts entry
+fn host_options(host: &KnownHostCandidate, user: Option<&str>) -> Vec<(String, String)> {
+    let mut options = vec![("HostName".to_string(), host.host.clone())];
+    if let Some(port) = host.port {
+        options.push(("Port".to_string(), port.to_string()));
+    }
+    if let Some(user) = user.map(str::trim).filter(|u| !u.is_empty()) {
+        options.push(("User".to_string(), user.to_string()));
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name() {
+        assert_eq!(
+            parse_name("Web.Example.com"),
+            Some(("web.example.com".to_string(), None))
+        );
+        assert_eq!(
+            parse_name("[db]:2222"),
+            Some(("db".to_string(), Some(2222)))
+        );
+        assert_eq!(parse_name("[db]:22"), Some(("db".to_string(), None)));
+        assert_eq!(parse_name("|1|abc=|def="), None);
+        assert_eq!(parse_name("*.corp"), None);
+        assert_eq!(parse_name("localhost"), None);
+        assert_eq!(parse_name("[127.0.0.1]:2200"), None);
+        assert_eq!(parse_name("[db]:x"), None);
+    }
+
+    #[test]
+    fn test_collect_candidates() {
+        let editor = SshConfigEditor::parse("Host web\n    HostName 10.0.0.1\n\nHost app\n");
+        let content = "\
+# comment
+10.0.0.1 ssh-ed25519 AAAA1
+10.0.0.9,app.corp ssh-ed25519 AAAA2
+app.corp ssh-rsa AAAA3
+[app.corp]:2222 ssh-ed25519 AAAA4
+github.com ssh-ed25519 AAAA5
+|1|abc=|def= ssh-ed25519 AAAA6
+@cert-authority *.corp ssh-ed25519 AAAA7
+[10.0.0.20]:2200 ecdsa-sha2-nistp256 AAAA8
+";
+        let candidates = collect_candidates(content, &editor);
+        let summary: Vec<(&str, &str, Option<u16>)> = candidates
+            .iter()
+            .map(|c| (c.alias.as_str(), c.host.as_str(), c.port))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("10.0.0.20", "10.0.0.20", Some(2200)),
+                ("app-2", "app.corp", None),
+                ("app-2222", "app.corp", Some(2222)),
+            ]
+        );
+        assert_eq!(candidates[1].other_names, vec!["10.0.0.9"]);
+        assert_eq!(candidates[1].key_types, vec!["ssh-ed25519", "ssh-rsa"]);
+        assert_eq!(
+            host_options(&candidates[2], Some("admin")),
+            vec![
+                ("HostName".to_string(), "app.corp".to_string()),
+                ("Port".to_string(), "2222".to_string()),
+                ("User".to_string(), "admin".to_string()),
+            ]
+        );
+    }
+}
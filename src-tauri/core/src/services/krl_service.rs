@@ -0,0 +1,258 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::key_manager::KeyManager;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::ssh_connection::SshConnectionService;
+use crate::utils::{public_key_blob, write_atomic, Krl};
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
+use std::path::Path;
+use std::time::Duration;
+use tokio::fs;
+
+/// Timeout for reading authorized_keys and sshd_config on a host
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Separates authorized_keys from the sshd RevokedKeys line in the script output
+const SSHD_MARKER: &str = "--sshd--";
+
+/// POSIX sh script run with `sh -s`
+const HOST_CHECK_SCRIPT: &str = r#"
+cat ~/.ssh/authorized_keys 2>/dev/null
+echo "--sshd--"
+grep -hi '^[[:space:]]*RevokedKeys' /etc/ssh/sshd_config /etc/ssh/sshd_config.d/*.conf 2>/dev/null | head -n 1
+"#;
+
+/// Client option rejecting revoked host keys (set for `Host *`)
+const REVOKED_HOST_KEYS: &str = "RevokedHostKeys";
+
+/// Overview of a KRL file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KrlSummary {
+    pub version: u64,
+    /// Unix timestamp in seconds
+    pub generated_at: u64,
+    pub comment: String,
+    pub revoked_keys: usize,
+    pub revoked_fingerprints: usize,
+    /// Certificate revocations are listed but not checked against keys
+    pub certificate_sections: usize,
+    pub signed: bool,
+}
+
+/// A key checked against a KRL
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRevocation {
+    /// Key name for local keys, comment for deployed keys
+    pub name: String,
+    pub fingerprint: String,
+    pub revoked: bool,
+}
+
+/// Keys authorized on a host checked against a KRL
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostRevocationReport {
+    pub host_alias: String,
+    pub keys: Vec<KeyRevocation>,
+    /// RevokedKeys value in the host's sshd_config, if set
+    pub sshd_revoked_keys: Option<String>,
+}
+
+/// Reads, checks and generates OpenSSH Key Revocation Lists
+pub struct KrlService;
+
+impl KrlService {
+    async fn load(path: &str) -> SshResult<Krl> {
+        let data = fs::read(path).await.map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to read {}: {}", path, e),
+        })?;
+        Krl::parse(&data)
+    }
+
+    /// Summary of a KRL (binary or plain key list)
+    pub async fn inspect(path: &str) -> SshResult<KrlSummary> {
+        Ok(summary(&Self::load(path).await?))
+    }
+
+    /// Check the local public keys in ~/.ssh against a KRL
+    pub async fn check_local_keys(krl_path: &str) -> SshResult<Vec<KeyRevocation>> {
+        let krl = Self::load(krl_path).await?;
+        let mut results = Vec::new();
+        for key in KeyManager::new()?.list_keys().await? {
+            let Ok(line) = fs::read_to_string(&key.public_key_path).await else {
+                continue;
+            };
+            if let Some(result) = check_key(&krl, &line, key.name) {
+                results.push(result);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Check the keys in a host's authorized_keys against a KRL and report the
+    /// host's sshd RevokedKeys setting
+    pub async fn check_host(host_alias: &str, krl_path: &str) -> SshResult<HostRevocationReport> {
+        let krl = Self::load(krl_path).await?;
+        let session = SshConnectionService::open_session(host_alias, None).await?;
+        let output = session
+            .exec(
+                "sh -s",
+                Some(HOST_CHECK_SCRIPT.as_bytes()),
+                CHECK_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+        let (keys, sshd_revoked_keys) = parse_host_check(&krl, &output?.stdout);
+        Ok(HostRevocationReport {
+            host_alias: host_alias.to_string(),
+            keys,
+            sshd_revoked_keys,
+        })
+    }
+
+    /// Write a KRL revoking the given public keys (OpenSSH format, one per entry)
+    pub async fn generate(
+        public_keys: &[String],
+        comment: &str,
+        output_path: &str,
+    ) -> SshResult<KrlSummary> {
+        ReadOnlyMode::ensure_writable("write a key revocation list")?;
+        let mut keys = Vec::new();
+        for line in public_keys {
+            let blob = public_key_blob(line).ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Not a public key: {}", line),
+            })?;
+            if !keys.contains(&blob) {
+                keys.push(blob);
+            }
+        }
+        let now = (now_millis() / 1000) as u64;
+        let krl = Krl {
+            // Bumped on every generation, like ssh-keygen does when updating a KRL
+            version: now,
+            generated_at: now,
+            comment: comment.to_string(),
+            keys,
+            ..Krl::default()
+        };
+        write_atomic(Path::new(output_path), &krl.encode()).await?;
+        tracing::info!(
+            "[krl_service] Wrote KRL with {} key(s) to {}",
+            krl.keys.len(),
+            output_path
+        );
+        Ok(summary(&krl))
+    }
+
+    /// RevokedHostKeys of `Host *` in ~/.ssh/config
+    pub async fn get_revoked_host_keys() -> SshResult<Option<String>> {
+        Ok(ConfigService::load_editor()
+            .await?
+            .get_option("*", REVOKED_HOST_KEYS))
+    }
+
+    /// Set or clear RevokedHostKeys for `Host *`, so ssh rejects revoked host keys
+    pub async fn set_revoked_host_keys(path: Option<&str>) -> SshResult<()> {
+        if let Some(path) = path {
+            if path.trim().is_empty() || path.contains(['\n', '\r']) {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "Invalid RevokedHostKeys path".to_string(),
+                });
+            }
+        }
+        ConfigService::update(|editor| {
+            match path {
+                Some(path) => {
+                    if !editor.set_option("*", REVOKED_HOST_KEYS, path) {
+                        editor
+                            .append_host("*", &[(REVOKED_HOST_KEYS.to_string(), path.to_string())]);
+                    }
+                }
+                None => {
+                    editor.remove_option("*", REVOKED_HOST_KEYS);
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn summary(krl: &Krl) -> KrlSummary {
+    KrlSummary {
+        version: krl.version,
+        generated_at: krl.generated_at,
+        comment: krl.comment.clone(),
+        revoked_keys: krl.keys.len(),
+        revoked_fingerprints: krl.sha1.len() + krl.sha256.len(),
+        certificate_sections: krl.certificate_sections,
+        signed: krl.signed,
+    }
+}
+
+/// Check one public key line (authorized_keys options allowed); None if it isn't a key
+fn check_key(krl: &Krl, line: &str, name: String) -> Option<KeyRevocation> {
+    let blob = public_key_blob(line)?;
+    let key = PublicKey::from_bytes(&blob).ok()?;
+    Some(KeyRevocation {
+        name,
+        fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+        revoked: krl.revokes(&blob),
+    })
+}
+
+fn parse_host_check(krl: &Krl, output: &str) -> (Vec<KeyRevocation>, Option<String>) {
+    let (authorized, sshd) = output.split_once(SSHD_MARKER).unwrap_or((output, ""));
+    let keys = authorized
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let comment = line.split_whitespace().last().unwrap_or_default();
+            check_key(krl, line, comment.to_string())
+        })
+        .collect();
+    let sshd_revoked_keys = sshd
+        .lines()
+        .find_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string);
+    (keys, sshd_revoked_keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl a@b";
+    const OTHER: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBG5Qh1pHZPhCFH5bz6hXNXwMLC4uI2PyzeRHQjVSlpc c@d";
+
+    #[test]
+    fn test_parse_host_check() {
+        let krl = Krl {
+            keys: vec![public_key_blob(KEY).unwrap()],
+            ..Krl::default()
+        };
+        let output = format!(
+            "# managed\nrestrict {}\n{}\ngarbage\n--sshd--\nRevokedKeys /etc/ssh/revoked.krl\n",
+            KEY, OTHER
+        );
+        let (keys, sshd) = parse_host_check(&krl, &output);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].name, "a@b");
+        assert!(keys[0].revoked);
+        assert!(keys[0].fingerprint.starts_with("SHA256:"));
+        assert!(!keys[1].revoked);
+        assert_eq!(sshd.as_deref(), Some("/etc/ssh/revoked.krl"));
+
+        let (keys, sshd) = parse_host_check(&krl, "--sshd--\n");
+        assert!(keys.is_empty());
+        assert_eq!(sshd, None);
+    }
+}
@@ -0,0 +1,380 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::onboarding_service::{OnboardingService, OnboardingStep};
+use crate::services::read_only::ReadOnlyMode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Timeout for a kubectl call (the API server may be slow or unreachable)
+const KUBECTL_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Context from the user's kubeconfig
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeContext {
+    pub name: String,
+    pub cluster: String,
+    /// API server URL
+    pub server: Option<String>,
+    pub current: bool,
+}
+
+/// Cluster node
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeNode {
+    pub name: String,
+    pub internal_ip: Option<String>,
+    pub external_ip: Option<String>,
+    pub os_image: Option<String>,
+    pub ready: bool,
+    /// From node-role.kubernetes.io/* labels
+    pub roles: Vec<String>,
+}
+
+/// Which node address becomes the HostName (the other one is the fallback)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeAddress {
+    #[default]
+    External,
+    /// Usually combined with a bastion ProxyJump
+    Internal,
+}
+
+/// Node import request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeImportRequest {
+    pub context: String,
+    /// Node names to import (all nodes when empty)
+    #[serde(default)]
+    pub nodes: Vec<String>,
+    #[serde(default)]
+    pub address: NodeAddress,
+    pub user: Option<String>,
+    pub identity_file: Option<String>,
+    /// Bastion host for ProxyJump
+    pub proxy_jump: Option<String>,
+    /// Alias prefix (defaults to "<context>-")
+    pub alias_prefix: Option<String>,
+}
+
+/// Node import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeImportResult {
+    pub created: Vec<String>,
+    /// Nodes skipped because the alias exists or the node has no address
+    pub skipped: Vec<String>,
+    /// Registry tag given to the imported hosts
+    pub tag: String,
+}
+
+/// Imports Kubernetes nodes as SSH hosts (through kubectl, so every kubeconfig
+/// auth method such as exec plugins works)
+pub struct KubeImportService;
+
+impl KubeImportService {
+    /// Run kubectl and return its stdout
+    async fn kubectl(args: Vec<String>) -> SshResult<String> {
+        let result = tokio::time::timeout(
+            KUBECTL_TIMEOUT,
+            tokio::task::spawn_blocking(move || {
+                Command::new("kubectl")
+                    .args(&args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .output()
+            }),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(Ok(output))) if output.status.success() => {
+                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            }
+            Ok(Ok(Ok(output))) => Err(SshBuddyError::Unknown {
+                message: format!(
+                    "kubectl failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            }),
+            Ok(Ok(Err(e))) => Err(SshBuddyError::IoError {
+                message: format!("Failed to run kubectl: {}", e),
+            }),
+            Ok(Err(e)) => Err(SshBuddyError::Unknown {
+                message: format!("Internal error: {}", e),
+            }),
+            Err(_) => Err(SshBuddyError::ConnectionTimeout),
+        }
+    }
+
+    fn parse_json(output: &str) -> SshResult<Value> {
+        serde_json::from_str(output).map_err(|e| SshBuddyError::Unknown {
+            message: format!("Invalid kubectl output: {}", e),
+        })
+    }
+
+    /// List kubeconfig contexts
+    pub async fn list_contexts() -> SshResult<Vec<KubeContext>> {
+        let output = Self::kubectl(vec![
+            "config".into(),
+            "view".into(),
+            "-o".into(),
+            "json".into(),
+        ])
+        .await?;
+        Ok(parse_contexts(&Self::parse_json(&output)?))
+    }
+
+    /// List the nodes of a context's cluster
+    pub async fn list_nodes(context: &str) -> SshResult<Vec<KubeNode>> {
+        let output = Self::kubectl(vec![
+            "--context".into(),
+            context.to_string(),
+            "get".into(),
+            "nodes".into(),
+            "-o".into(),
+            "json".into(),
+        ])
+        .await?;
+        let nodes = parse_nodes(&Self::parse_json(&output)?);
+        tracing::info!(
+            "[kube_import] Context {} has {} node(s)",
+            context,
+            nodes.len()
+        );
+        Ok(nodes)
+    }
+
+    /// Create Host entries for cluster nodes, tagged with the context name
+    pub async fn import_nodes(request: &KubeImportRequest) -> SshResult<KubeImportResult> {
+        ReadOnlyMode::ensure_writable("import Kubernetes nodes")?;
+        let nodes = Self::list_nodes(&request.context).await?;
+        let prefix = request
+            .alias_prefix
+            .clone()
+            .unwrap_or_else(|| format!("{}-", request.context));
+
+        let tag = format!("k8s:{}", request.context);
+        let (created, skipped) = ConfigService::create_hosts_with_tags(&tag, |editor| {
+            let mut created = Vec::new();
+            let mut skipped = Vec::new();
+            for node in nodes
+                .iter()
+                .filter(|n| request.nodes.is_empty() || request.nodes.contains(&n.name))
+            {
+                let alias = format!("{}{}", prefix, node.name);
+                let Some(options) = host_options(node, request) else {
+                    skipped.push(node.name.clone());
+                    continue;
+                };
+                if editor.has_host(&alias) || ConfigService::validate_alias(&alias).is_err() {
+                    skipped.push(node.name.clone());
+                    continue;
+                }
+                editor.append_host(&alias, &options);
+                created.push(alias);
+            }
+            (created, skipped)
+        })
+        .await?;
+
+        tracing::info!(
+            "[kube_import] Imported {} node(s) from {} ({} skipped)",
+            created.len(),
+            request.context,
+            skipped.len()
+        );
+        if !created.is_empty() {
+            OnboardingService::complete(OnboardingStep::ImportCloudHosts).await;
+        }
+        Ok(KubeImportResult {
+            created,
+            skipped,
+            tag,
+        })
+    }
+}
+
+/// Host block options for a node; None when the node has no usable address
+fn host_options(node: &KubeNode, request: &KubeImportRequest) -> Option<Vec<(String, String)>> {
+    let address = match request.address {
+        NodeAddress::External => node.external_ip.as_ref().or(node.internal_ip.as_ref()),
+        NodeAddress::Internal => node.internal_ip.as_ref().or(node.external_ip.as_ref()),
+    }?;
+
+    let mut options = vec![("HostName".to_string(), address.clone())];
+    let optional = [
+        ("User", &request.user),
+        ("IdentityFile", &request.identity_file),
+        ("ProxyJump", &request.proxy_jump),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            options.push((key.to_string(), value.to_string()));
+        }
+    }
+    Some(options)
+}
+
+/// Contexts from `kubectl config view -o json`
+fn parse_contexts(config: &Value) -> Vec<KubeContext> {
+    let current = config["current-context"].as_str().unwrap_or_default();
+    let empty = Vec::new();
+    let clusters = config["clusters"].as_array().unwrap_or(&empty);
+    config["contexts"]
+        .as_array()
+        .unwrap_or(&empty)
+        .iter()
+        .filter_map(|context| {
+            let name = context["name"].as_str()?;
+            let cluster = context["context"]["cluster"].as_str().unwrap_or_default();
+            let server = clusters
+                .iter()
+                .find(|c| c["name"].as_str() == Some(cluster))
+                .and_then(|c| c["cluster"]["server"].as_str())
+                .map(str::to_string);
+            Some(KubeContext {
+                name: name.to_string(),
+                cluster: cluster.to_string(),
+                server,
+                current: name == current,
+            })
+        })
+        .collect()
+}
+
+/// Nodes from `kubectl get nodes -o json`
+fn parse_nodes(list: &Value) -> Vec<KubeNode> {
+    let empty = Vec::new();
+    list["items"]
+        .as_array()
+        .unwrap_or(&empty)
+        .iter()
+        .filter_map(|item| {
+            let name = item["metadata"]["name"].as_str()?.to_string();
+            let address = |kind: &str| {
+                item["status"]["addresses"]
+                    .as_array()?
+                    .iter()
+                    .find(|a| a["type"].as_str() == Some(kind))
+                    .and_then(|a| a["address"].as_str())
+                    .map(str::to_string)
+            };
+            let ready = item["status"]["conditions"]
+                .as_array()
+                .is_some_and(|conditions| {
+                    conditions.iter().any(|c| {
+                        c["type"].as_str() == Some("Ready") && c["status"].as_str() == Some("True")
+                    })
+                });
+            let mut roles: Vec<String> = item["metadata"]["labels"]
+                .as_object()
+                .map(|labels| {
+                    labels
+                        .keys()
+                        .filter_map(|k| k.strip_prefix("node-role.kubernetes.io/"))
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            roles.sort();
+            Some(KubeNode {
+                name,
+                internal_ip: address("InternalIP"),
+                external_ip: address("ExternalIP"),
+                os_image: item["status"]["nodeInfo"]["osImage"]
+                    .as_str()
+                    .map(str::to_string),
+                ready,
+                roles,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(address: NodeAddress) -> KubeImportRequest {
+        KubeImportRequest {
+            context: "prod".to_string(),
+            nodes: Vec::new(),
+            address,
+            user: Some("ubuntu".to_string()),
+            identity_file: None,
+            proxy_jump: Some("bastion".to_string()),
+            alias_prefix: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_contexts() {
+        let config = serde_json::json!({
+            "current-context": "prod",
+            "clusters": [{ "name": "prod-cluster", "cluster": { "server": "https://10.0.0.1:6443" } }],
+            "contexts": [
+                { "name": "prod", "context": { "cluster": "prod-cluster", "user": "admin" } },
+                { "name": "dev", "context": { "cluster": "missing" } }
+            ]
+        });
+        let contexts = parse_contexts(&config);
+        assert_eq!(contexts.len(), 2);
+        assert!(contexts[0].current);
+        assert_eq!(contexts[0].server.as_deref(), Some("https://10.0.0.1:6443"));
+        assert_eq!(contexts[1].server, None);
+    }
+
+    #[test]
+    fn test_parse_nodes() {
+        let list = serde_json::json!({
+            "items": [{
+                "metadata": { "name": "node-1", "labels": { "node-role.kubernetes.io/control-plane": "" } },
+                "status": {
+                    "addresses": [
+                        { "type": "InternalIP", "address": "10.0.1.5" },
+                        { "type": "Hostname", "address": "node-1" }
+                    ],
+                    "conditions": [{ "type": "Ready", "status": "True" }],
+                    "nodeInfo": { "osImage": "Ubuntu 22.04.4 LTS" }
+                }
+            }]
+        });
+        let nodes = parse_nodes(&list);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].internal_ip.as_deref(), Some("10.0.1.5"));
+        assert_eq!(nodes[0].external_ip, None);
+        assert!(nodes[0].ready);
+        assert_eq!(nodes[0].roles, vec!["control-plane"]);
+    }
+
+    #[test]
+    fn test_host_options_prefer_address() {
+        let node = KubeNode {
+            name: "node-1".to_string(),
+            internal_ip: Some("10.0.1.5".to_string()),
+            external_ip: Some("203.0.113.5".to_string()),
+            os_image: None,
+            ready: true,
+            roles: Vec::new(),
+        };
+        let external = host_options(&node, &request(NodeAddress::External)).unwrap();
+        assert_eq!(external[0].1, "203.0.113.5");
+        let internal = host_options(&node, &request(NodeAddress::Internal)).unwrap();
+        assert_eq!(internal[0].1, "10.0.1.5");
+        assert!(internal.contains(&("ProxyJump".to_string(), "bastion".to_string())));
+
+        let no_address = KubeNode {
+            internal_ip: None,
+            external_ip: None,
+            ..node
+        };
+        assert!(host_options(&no_address, &request(NodeAddress::External)).is_none());
+    }
+}
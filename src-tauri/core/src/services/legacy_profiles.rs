@@ -0,0 +1,402 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::algorithm_check::AlgorithmOverride;
+use crate::services::config_service::ConfigService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::{now_millis, HostMetadata, RegistryService};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Registry tag given to hosts with a legacy exception
+const LEGACY_TAG: &str = "legacy";
+
+/// Exceptions expire after this many days unless the request says otherwise
+const DEFAULT_EXPIRY_DAYS: u32 = 90;
+const MAX_EXPIRY_DAYS: u32 = 365;
+
+/// Exceptions due within this many days are included in reminders
+const REMINDER_WINDOW_DAYS: i64 = 7;
+
+/// How often the reminder check runs
+const REMINDER_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// Options a profile may set; all of them are removed when the exception is lifted
+const LEGACY_OPTIONS: &[&str] = &[
+    "KexAlgorithms",
+    "HostKeyAlgorithms",
+    "PubkeyAcceptedAlgorithms",
+    "Ciphers",
+    "MACs",
+];
+
+/// Curated set of deprecated algorithms for a class of old devices
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyProfile {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// "+algorithm,..." values, appended to the client defaults
+    pub options: Vec<AlgorithmOverride>,
+}
+
+/// Legacy exception recorded in the host registry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyException {
+    pub profile_id: String,
+    /// Unix timestamps in milliseconds
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+/// Host with a legacy exception, for the review list and reminders
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyExceptionStatus {
+    pub host_alias: String,
+    pub profile_id: String,
+    pub expires_at: i64,
+    pub expired: bool,
+}
+
+/// Request to create a Host block for an old device
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyHostRequest {
+    pub alias: String,
+    pub hostname: String,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub profile_id: String,
+    pub expires_in_days: Option<u32>,
+}
+
+/// Curated profiles, from the narrowest to the broadest exception
+pub fn legacy_profiles() -> Vec<LegacyProfile> {
+    let profile =
+        |id: &str, name: &str, description: &str, options: &[(&str, &str)]| LegacyProfile {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            options: options
+                .iter()
+                .map(|(option, value)| AlgorithmOverride {
+                    option: option.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+        };
+    vec![
+        profile(
+            "ssh-rsa",
+            "SHA-1 RSA signatures",
+            "Servers that only sign with ssh-rsa (OpenSSH < 7.2, older Dropbear, NAS appliances)",
+            &[
+                ("HostKeyAlgorithms", "+ssh-rsa"),
+                ("PubkeyAcceptedAlgorithms", "+ssh-rsa"),
+            ],
+        ),
+        profile(
+            "dh-group14-sha1",
+            "SHA-1 key exchange",
+            "Switches and routers offering only diffie-hellman-group14-sha1",
+            &[("KexAlgorithms", "+diffie-hellman-group14-sha1")],
+        ),
+        profile(
+            "network-gear",
+            "Older network gear",
+            "Typical older Cisco IOS, HP ProCurve and Juniper images: SHA-1 key exchange, \
+             ssh-rsa host keys and CBC ciphers",
+            &[
+                ("KexAlgorithms", "+diffie-hellman-group14-sha1"),
+                ("HostKeyAlgorithms", "+ssh-rsa"),
+                ("PubkeyAcceptedAlgorithms", "+ssh-rsa"),
+                ("Ciphers", "+aes128-cbc"),
+            ],
+        ),
+        profile(
+            "ancient",
+            "Very old devices",
+            "Devices from before 2010 that only know diffie-hellman-group1-sha1 and CBC ciphers",
+            &[
+                (
+                    "KexAlgorithms",
+                    "+diffie-hellman-group1-sha1,diffie-hellman-group14-sha1",
+                ),
+                ("HostKeyAlgorithms", "+ssh-rsa"),
+                ("PubkeyAcceptedAlgorithms", "+ssh-rsa"),
+                ("Ciphers", "+aes128-cbc,3des-cbc"),
+            ],
+        ),
+    ]
+}
+
+/// Host blocks for old devices with deprecated algorithms, each with an expiry date
+pub struct LegacyProfileService;
+
+impl LegacyProfileService {
+    fn find_profile(profile_id: &str) -> SshResult<LegacyProfile> {
+        legacy_profiles()
+            .into_iter()
+            .find(|p| p.id == profile_id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Unknown legacy profile: {}", profile_id),
+            })
+    }
+
+    /// Create a Host block that enables the profile's algorithms for this host only
+    pub async fn create_host(request: &LegacyHostRequest) -> SshResult<LegacyExceptionStatus> {
+        ReadOnlyMode::ensure_writable("create legacy host")?;
+        ConfigService::validate_alias(&request.alias)?;
+        let profile = Self::find_profile(&request.profile_id)?;
+
+        let exception = new_exception(&profile.id, request.expires_in_days, now_millis());
+        let status = exception_status(&request.alias, &exception, exception.created_at);
+
+        ConfigService::update_with_registry(
+            |editor| {
+                if editor.has_host(&request.alias) {
+                    return Err(SshBuddyError::InvalidOption {
+                        message: format!("Host already exists: {}", request.alias),
+                    });
+                }
+                editor.append_host(&request.alias, &host_options(request, &profile));
+                Ok(())
+            },
+            |store, _| {
+                let metadata = store
+                    .hosts
+                    .entry(request.alias.clone())
+                    .or_insert_with(HostMetadata::new);
+                if !metadata.tags.iter().any(|t| t == LEGACY_TAG) {
+                    metadata.tags.push(LEGACY_TAG.to_string());
+                }
+                metadata.legacy_exception = Some(exception);
+                Ok(())
+            },
+        )
+        .await?;
+
+        tracing::info!(
+            "[legacy_profiles] Created {} with profile {} (expires {})",
+            request.alias,
+            profile.id,
+            status.expires_at
+        );
+        Ok(status)
+    }
+
+    /// All legacy exceptions, soonest expiry first
+    pub async fn list_exceptions() -> SshResult<Vec<LegacyExceptionStatus>> {
+        let store = RegistryService::load().await?;
+        let now = now_millis();
+        let mut exceptions: Vec<LegacyExceptionStatus> = store
+            .hosts
+            .iter()
+            .filter_map(|(alias, metadata)| {
+                let exception = metadata.legacy_exception.as_ref()?;
+                Some(exception_status(alias, exception, now))
+            })
+            .collect();
+        exceptions.sort_by_key(|e| e.expires_at);
+        Ok(exceptions)
+    }
+
+    /// Extend an exception after reviewing it
+    pub async fn renew(host_alias: &str, days: Option<u32>) -> SshResult<LegacyExceptionStatus> {
+        ReadOnlyMode::ensure_writable("renew legacy exception")?;
+        let status = RegistryService::update(|store| {
+            let exception = store
+                .hosts
+                .get_mut(host_alias)
+                .and_then(|m| m.legacy_exception.as_mut())
+                .ok_or_else(|| SshBuddyError::HostNotFound {
+                    alias: host_alias.to_string(),
+                })?;
+            let now = now_millis();
+            exception.expires_at = now + expiry_days(days) * DAY_MILLIS;
+            Ok(exception_status(host_alias, exception, now))
+        })
+        .await?;
+        tracing::info!("[legacy_profiles] Renewed exception of {}", host_alias);
+        Ok(status)
+    }
+
+    /// Remove the deprecated algorithm options from the host and forget the exception
+    pub async fn remove_exception(host_alias: &str) -> SshResult<()> {
+        ConfigService::update_with_registry(
+            |editor| {
+                if !editor.has_host(host_alias) {
+                    return Err(SshBuddyError::HostNotFound {
+                        alias: host_alias.to_string(),
+                    });
+                }
+                for option in LEGACY_OPTIONS {
+                    editor.remove_option(host_alias, option);
+                }
+                Ok(())
+            },
+            |store, _| {
+                if let Some(metadata) = store.hosts.get_mut(host_alias) {
+                    metadata.legacy_exception = None;
+                    metadata.tags.retain(|t| t != LEGACY_TAG);
+                    store.update_global_tags();
+                }
+                Ok(())
+            },
+        )
+        .await?;
+        tracing::info!(
+            "[legacy_profiles] Removed legacy exception of {}",
+            host_alias
+        );
+        Ok(())
+    }
+
+    /// Periodically report exceptions that expired or expire soon (spawned once at app setup)
+    pub async fn run_reminder_loop<F>(on_due: F)
+    where
+        F: Fn(Vec<LegacyExceptionStatus>) + Send + Sync + 'static,
+    {
+        loop {
+            match Self::list_exceptions().await {
+                Ok(exceptions) => {
+                    let due = due_for_reminder(exceptions, now_millis());
+                    if !due.is_empty() {
+                        tracing::info!(
+                            "[legacy_profiles] {} legacy exception(s) due for review",
+                            due.len()
+                        );
+                        on_due(due);
+                    }
+                }
+                Err(e) => tracing::warn!("[legacy_profiles] Reminder check failed: {}", e),
+            }
+            tokio::time::sleep(REMINDER_INTERVAL).await;
+        }
+    }
+}
+
+fn expiry_days(days: Option<u32>) -> i64 {
+    days.unwrap_or(DEFAULT_EXPIRY_DAYS)
+        .clamp(1, MAX_EXPIRY_DAYS) as i64
+}
+
+fn new_exception(profile_id: &str, days: Option<u32>, now: i64) -> LegacyException {
+    LegacyException {
+        profile_id: profile_id.to_string(),
+        created_at: now,
+        expires_at: now + expiry_days(days) * DAY_MILLIS,
+    }
+}
+
+fn exception_status(alias: &str, exception: &LegacyException, now: i64) -> LegacyExceptionStatus {
+    LegacyExceptionStatus {
+        host_alias: alias.to_string(),
+        profile_id: exception.profile_id.clone(),
+        expires_at: exception.expires_at,
+        expired: exception.expires_at <= now,
+    }
+}
+
+/// Exceptions that expired or expire within the reminder window
+fn due_for_reminder(
+    exceptions: Vec<LegacyExceptionStatus>,
+    now: i64,
+) -> Vec<LegacyExceptionStatus> {
+    exceptions
+        .into_iter()
+        .filter(|e| e.expires_at <= now + REMINDER_WINDOW_DAYS * DAY_MILLIS)
+        .collect()
+}
+
+/// Host block options: connection settings followed by the profile's algorithms
+fn host_options(request: &LegacyHostRequest, profile: &LegacyProfile) -> Vec<(String, String)> {
+    let mut options = vec![("HostName".to_string(), request.hostname.trim().to_string())];
+    if let Some(port) = request.port.filter(|p| *p != 22) {
+        options.push(("Port".to_string(), port.to_string()));
+    }
+    if let Some(user) = request
+        .user
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+    {
+        options.push(("User".to_string(), user.to_string()));
+    }
+    options.extend(
+        profile
+            .options
+            .iter()
+            .map(|o| (o.option.clone(), o.value.clone())),
+    );
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_only_append_algorithms() {
+        let profiles = legacy_profiles();
+        assert!(profiles.iter().any(|p| p.id == "network-gear"));
+        for profile in profiles {
+            for o in &profile.options {
+                assert!(LEGACY_OPTIONS.contains(&o.option.as_str()), "{}", o.option);
+                assert!(o.value.starts_with('+'), "{} {}", o.option, o.value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_host_options() {
+        let request = LegacyHostRequest {
+            alias: "core-switch".to_string(),
+            hostname: "10.0.0.2".to_string(),
+            port: Some(22),
+            user: Some("admin".to_string()),
+            profile_id: "dh-group14-sha1".to_string(),
+            expires_in_days: None,
+        };
+        let profile = LegacyProfileService::find_profile(&request.profile_id).unwrap();
+        assert_eq!(
+            host_options(&request, &profile),
+            vec![
+                ("HostName".to_string(), "10.0.0.2".to_string()),
+                ("User".to_string(), "admin".to_string()),
+                (
+                    "KexAlgorithms".to_string(),
+                    "+diffie-hellman-group14-sha1".to_string()
+                ),
+            ]
+        );
+        assert!(LegacyProfileService::find_profile("nope").is_err());
+    }
+
+    #[test]
+    fn test_expiry_and_reminders() {
+        let now = 1_700_000_000_000;
+        let exception = new_exception("ssh-rsa", None, now);
+        assert_eq!(exception.expires_at, now + 90 * DAY_MILLIS);
+        assert_eq!(
+            new_exception("ssh-rsa", Some(5000), now).expires_at,
+            now + 365 * DAY_MILLIS
+        );
+
+        let later = exception_status("old-nas", &exception, now);
+        let soon = exception_status("switch", &new_exception("ssh-rsa", Some(3), now), now);
+        let expired = exception_status(
+            "router",
+            &new_exception("ssh-rsa", Some(1), now),
+            now + 2 * DAY_MILLIS,
+        );
+        assert!(expired.expired && !soon.expired);
+
+        let due = due_for_reminder(vec![later, soon, expired], now);
+        let aliases: Vec<&str> = due.iter().map(|e| e.host_alias.as_str()).collect();
+        assert_eq!(aliases, vec!["switch", "router"]);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::models::SshResult;
+use crate::services::registry_service::{now_millis, HostMetadata, RegistryService};
+use crate::utils::unified_diff;
+use serde::{Deserialize, Serialize};
+
+/// Run after login on full shells; `/run/motd.dynamic` is what pam_motd shows on Ubuntu/Debian
+pub const MOTD_COMMAND: &str = "cat /run/motd.dynamic /etc/motd 2>/dev/null";
+
+/// Longer messages are cut, a banner is not meant to be a file transfer
+const MAX_MESSAGE_CHARS: usize = 16 * 1024;
+
+/// Lines of context in the diff between two captures
+const DIFF_CONTEXT: usize = 2;
+
+/// What the server showed around login
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginMessages {
+    /// Pre-authentication banner (sshd `Banner`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    /// Message of the day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motd: Option<String>,
+}
+
+impl LoginMessages {
+    pub fn new(banner: Option<&str>, motd: Option<&str>) -> Self {
+        Self {
+            banner: banner.and_then(clean_message),
+            motd: motd.and_then(clean_message),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.banner.is_none() && self.motd.is_none()
+    }
+
+    /// Both messages as one text, for diffing
+    fn text(&self) -> String {
+        let mut text = String::new();
+        if let Some(banner) = &self.banner {
+            text.push_str("# Banner\n");
+            text.push_str(banner);
+            text.push('\n');
+        }
+        if let Some(motd) = &self.motd {
+            text.push_str("# MOTD\n");
+            text.push_str(motd);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Login messages captured at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginMessageCapture {
+    #[serde(flatten)]
+    pub messages: LoginMessages,
+    /// Unix milliseconds
+    pub captured_at: i64,
+}
+
+/// Latest login messages of a host and the capture before they last changed,
+/// stored in the host registry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostLoginBanner {
+    pub latest: LoginMessageCapture,
+    /// Different messages seen before `latest`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<LoginMessageCapture>,
+    /// When the messages last changed (unix milliseconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<i64>,
+}
+
+/// Keeps the banner and MOTD servers show, so maintenance notices and unexpected
+/// changes stand out
+pub struct LoginBannerService;
+
+impl LoginBannerService {
+    /// Login messages of a host (None until a connection test captured some)
+    pub async fn get_host(host_alias: &str) -> SshResult<Option<HostLoginBanner>> {
+        let store = RegistryService::load().await?;
+        Ok(store
+            .hosts
+            .get(host_alias)
+            .and_then(|m| m.login_banner.clone()))
+    }
+
+    /// Unified diff from the previous capture to the latest one (empty if unchanged)
+    pub async fn diff_host(host_alias: &str) -> SshResult<String> {
+        Ok(Self::get_host(host_alias)
+            .await?
+            .map(|banner| diff_captures(&banner))
+            .unwrap_or_default())
+    }
+
+    /// Store what a connection test captured
+    pub async fn record(host_alias: &str, messages: &LoginMessages) -> SshResult<()> {
+        let capture = LoginMessageCapture {
+            messages: messages.clone(),
+            captured_at: now_millis(),
+        };
+        RegistryService::update(|store| {
+            let metadata = store
+                .hosts
+                .entry(host_alias.to_string())
+                .or_insert_with(HostMetadata::new);
+            metadata.login_banner = Some(match metadata.login_banner.take() {
+                Some(existing) => {
+                    let updated = apply_capture(existing, capture);
+                    if updated.changed_at == Some(updated.latest.captured_at) {
+                        tracing::warn!("[login_banner] Login messages of {} changed", host_alias);
+                    }
+                    updated
+                }
+                None => HostLoginBanner {
+                    latest: capture,
+                    previous: None,
+                    changed_at: None,
+                },
+            });
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Replace the latest capture; the old one becomes `previous` only when the messages differ
+fn apply_capture(mut banner: HostLoginBanner, capture: LoginMessageCapture) -> HostLoginBanner {
+    if banner.latest.messages != capture.messages {
+        banner.changed_at = Some(capture.captured_at);
+        banner.previous = Some(std::mem::replace(&mut banner.latest, capture));
+    } else {
+        banner.latest.captured_at = capture.captured_at;
+    }
+    banner
+}
+
+fn diff_captures(banner: &HostLoginBanner) -> String {
+    match &banner.previous {
+        Some(previous) => unified_diff(
+            &previous.messages.text(),
+            &banner.latest.messages.text(),
+            "previous",
+            "latest",
+            DIFF_CONTEXT,
+        ),
+        None => String::new(),
+    }
+}
+
+/// Drop terminal escape sequences and other control characters, trim and cap the length
+fn clean_message(text: &str) -> Option<String> {
+    let mut cleaned = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // CSI sequence: ESC [ parameters final-byte
+            '\u{1b}' if chars.peek() == Some(&'[') => {
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('\u{40}'..='\u{7e}').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            '\n' | '\t' => cleaned.push(c),
+            c if c.is_control() => {}
+            c => cleaned.push(c),
+        }
+    }
+    let cleaned = cleaned.trim_matches('\n').trim_end();
+    if cleaned.trim().is_empty() {
+        return None;
+    }
+    Some(cleaned.chars().take(MAX_MESSAGE_CHARS).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(motd: &str, at: i64) -> LoginMessageCapture {
+        LoginMessageCapture {
+            messages: LoginMessages::new(Some("Authorized use only"), Some(motd)),
+            captured_at: at,
+        }
+    }
+
+    #[test]
+    fn test_clean_message() {
+        assert_eq!(
+            clean_message("\r\n\u{1b}[1;31mWarning\u{1b}[0m: maintenance\r\n\n"),
+            Some("Warning: maintenance".to_string())
+        );
+        assert_eq!(clean_message(" \r\n\t\n"), None);
+        assert_eq!(
+            clean_message("  indented\n\tline"),
+            Some("  indented\n\tline".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_capture_keeps_previous_only_on_change() {
+        let banner = HostLoginBanner {
+            latest: capture("Welcome", 1),
+            previous: None,
+            changed_at: None,
+        };
+        let same = apply_capture(banner, capture("Welcome", 2));
+        assert_eq!(same.latest.captured_at, 2);
+        assert!(same.previous.is_none());
+        assert!(diff_captures(&same).is_empty());
+
+        let changed = apply_capture(same, capture("Welcome\nMaintenance on Friday", 3));
+        assert_eq!(changed.changed_at, Some(3));
+        assert_eq!(changed.previous.as_ref().map(|p| p.captured_at), Some(2));
+        let diff = diff_captures(&changed);
+        assert!(diff.contains("+Maintenance on Friday"));
+        assert!(!diff.contains("-Welcome"));
+    }
+
+    #[test]
+    fn test_empty_messages() {
+        assert!(LoginMessages::new(None, Some("\n")).is_empty());
+        assert!(!LoginMessages::new(Some("hi"), None).is_empty());
+    }
+}
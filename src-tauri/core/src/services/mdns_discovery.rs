@@ -0,0 +1,314 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::vm_discovery::sanitize_alias;
+use crate::utils::{build_ptr_query, parse_response, MdnsRecord, MdnsRecordData, MDNS_ADDR};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Duration, Instant};
+
+/// Service type advertised by SSH servers (e.g. Avahi's ssh.service on Raspberry Pi OS)
+const SSH_SERVICE: &str = "_ssh._tcp.local";
+
+/// Default and max listening time of a scan
+const DEFAULT_SCAN_SECS: u64 = 3;
+const MAX_SCAN_SECS: u64 = 15;
+
+/// The query is repeated once, since multicast packets get lost
+const QUERY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Registry tag given to imported hosts
+const MDNS_TAG: &str = "mdns";
+
+/// SSH server found on the local network
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MdnsHost {
+    /// Service instance name, e.g. "raspberrypi"
+    pub instance: String,
+    /// e.g. "raspberrypi.local"
+    pub hostname: String,
+    pub port: u16,
+    /// IPv4 addresses first
+    pub addresses: Vec<String>,
+    /// TXT metadata
+    pub txt: BTreeMap<String, String>,
+}
+
+/// mDNS host import request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MdnsImportRequest {
+    pub hosts: Vec<MdnsHost>,
+    pub user: Option<String>,
+    /// Use the first IP address as HostName instead of the .local name
+    #[serde(default)]
+    pub use_address: bool,
+}
+
+/// mDNS host import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MdnsImportResult {
+    pub created: Vec<String>,
+    /// Instances skipped because the alias already exists
+    pub skipped: Vec<String>,
+}
+
+/// Finds SSH servers advertised over mDNS/Bonjour (IPv4 multicast)
+pub struct MdnsDiscoveryService;
+
+impl MdnsDiscoveryService {
+    /// Query the local network for `_ssh._tcp` services and collect answers for `secs` seconds
+    pub async fn scan(secs: Option<u64>) -> SshResult<Vec<MdnsHost>> {
+        let secs = secs.unwrap_or(DEFAULT_SCAN_SECS).clamp(1, MAX_SCAN_SECS);
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to open mDNS socket: {}", e),
+            })?;
+        socket.set_multicast_ttl_v4(255).ok();
+
+        let query = build_ptr_query(SSH_SERVICE);
+        socket
+            .send_to(&query, MDNS_ADDR)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to send mDNS query: {}", e),
+            })?;
+
+        let deadline = Instant::now() + Duration::from_secs(secs);
+        let mut retry_at = Some(Instant::now() + QUERY_RETRY_DELAY);
+        let mut records = Vec::new();
+        let mut buf = vec![0u8; 9000];
+        loop {
+            let wake = retry_at.map_or(deadline, |at| at.min(deadline));
+            match timeout_at(wake, socket.recv_from(&mut buf)).await {
+                Ok(Ok((len, from))) => match parse_response(&buf[..len]) {
+                    Some(parsed) => records.extend(parsed),
+                    None => tracing::debug!("[mdns_discovery] Ignoring packet from {}", from),
+                },
+                Ok(Err(e)) => tracing::warn!("[mdns_discovery] Receive failed: {}", e),
+                Err(_) if Instant::now() >= deadline => break,
+                Err(_) => {
+                    retry_at = None;
+                    socket.send_to(&query, MDNS_ADDR).await.ok();
+                }
+            }
+        }
+
+        let hosts = collect_hosts(&records);
+        tracing::info!(
+            "[mdns_discovery] Found {} SSH service(s) in {}s",
+            hosts.len(),
+            secs
+        );
+        Ok(hosts)
+    }
+
+    /// Create Host entries for discovered services
+    pub async fn import_hosts(request: &MdnsImportRequest) -> SshResult<MdnsImportResult> {
+        ReadOnlyMode::ensure_writable("import mDNS hosts")?;
+
+        let (created, skipped) = ConfigService::create_hosts_with_tags(MDNS_TAG, |editor| {
+            let mut created = Vec::new();
+            let mut skipped = Vec::new();
+            for host in &request.hosts {
+                let alias = sanitize_alias(&host.instance);
+                if editor.has_host(&alias) || ConfigService::validate_alias(&alias).is_err() {
+                    skipped.push(host.instance.clone());
+                    continue;
+                }
+                editor.append_host(&alias, &host_options(host, request));
+                created.push(alias);
+            }
+            (created, skipped)
+        })
+        .await?;
+
+        tracing::info!(
+            "[mdns_discovery] Imported {} host(s) ({} skipped)",
+            created.len(),
+            skipped.len()
+        );
+        Ok(MdnsImportResult { created, skipped })
+    }
+}
+
+/// Join PTR, SRV, TXT and address records into hosts (sorted by instance name)
+fn collect_hosts(records: &[MdnsRecord]) -> Vec<MdnsHost> {
+    let suffix = format!(".{}", SSH_SERVICE);
+    let mut instances: Vec<String> = Vec::new();
+    let mut services: HashMap<&str, (&str, u16)> = HashMap::new();
+    let mut txts: HashMap<&str, &[String]> = HashMap::new();
+    let mut addresses: HashMap<&str, Vec<String>> = HashMap::new();
+    for record in records {
+        match &record.data {
+            MdnsRecordData::Ptr(instance) if record.name == SSH_SERVICE && record.ttl > 0 => {
+                if !instances.contains(instance) {
+                    instances.push(instance.clone());
+                }
+            }
+            MdnsRecordData::Srv { target, port } => {
+                services.insert(&record.name, (target.as_str(), *port));
+            }
+            MdnsRecordData::Txt(strings) => {
+                txts.insert(&record.name, strings);
+            }
+            MdnsRecordData::A(ip) => push_unique(&mut addresses, &record.name, ip.to_string()),
+            MdnsRecordData::Aaaa(ip) => push_unique(&mut addresses, &record.name, ip.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut hosts: Vec<MdnsHost> = instances
+        .iter()
+        .filter_map(|instance| {
+            let (target, port) = services.get(instance.as_str())?;
+            let mut host_addresses = addresses.get(target).cloned().unwrap_or_default();
+            host_addresses.sort_by_key(|address| address.contains(':'));
+            Some(MdnsHost {
+                instance: instance
+                    .strip_suffix(&suffix)
+                    .unwrap_or(instance)
+                    .to_string(),
+                hostname: target.to_string(),
+                port: *port,
+                addresses: host_addresses,
+                txt: txts
+                    .get(instance.as_str())
+                    .map(|strings| parse_txt(strings))
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+    hosts.sort_by(|a, b| a.instance.cmp(&b.instance));
+    hosts
+}
+
+fn push_unique<'a>(addresses: &mut HashMap<&'a str, Vec<String>>, name: &'a str, address: String) {
+    let list = addresses.entry(name).or_default();
+    if !list.contains(&address) {
+        list.push(address);
+    }
+}
+
+/// TXT strings as key/value pairs (a bare key gets an empty value)
+fn parse_txt(strings: &[String]) -> BTreeMap<String, String> {
+    strings
+        .iter()
+        .map(|s| match s.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (s.clone(), String::new()),
+        })
+        .collect()
+}
+
+/// Host block options for a discovered service
+fn host_options(host: &MdnsHost, request: &MdnsImportRequest) -> Vec<(String, String)> {
+    let hostname = match host.addresses.first() {
+        Some(address) if request.use_address => address.clone(),
+        _ => host.hostname.clone(),
+    };
+    let mut options = vec![("HostName".to_string(), hostname)];
+    if host.port != 22 {
+        options.push(("Port".to_string(), host.port.to_string()));
+    }
+    if let Some(user) = request
+        .user
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+    {
+        options.push(("User".to_string(), user.to_string()));
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    fn record(name: &str, data: MdnsRecordData) -> MdnsRecord {
+        MdnsRecord {
+            name: name.to_string(),
+            ttl: 120,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_collect_hosts() {
+        let records = vec![
+            record(
+                "_ssh._tcp.local",
+                MdnsRecordData::Ptr("pi._ssh._tcp.local".to_string()),
+            ),
+            record(
+                "pi._ssh._tcp.local",
+                MdnsRecordData::Srv {
+                    target: "raspberrypi.local".to_string(),
+                    port: 22,
+                },
+            ),
+            record(
+                "pi._ssh._tcp.local",
+                MdnsRecordData::Txt(vec!["model=Pi 4B".to_string(), "flag".to_string()]),
+            ),
+            record(
+                "raspberrypi.local",
+                MdnsRecordData::Aaaa(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+            ),
+            record(
+                "raspberrypi.local",
+                MdnsRecordData::A(Ipv4Addr::new(192, 168, 1, 42)),
+            ),
+            // Second response repeating the address
+            record(
+                "raspberrypi.local",
+                MdnsRecordData::A(Ipv4Addr::new(192, 168, 1, 42)),
+            ),
+            // PTR without SRV
+            record(
+                "_ssh._tcp.local",
+                MdnsRecordData::Ptr("nas._ssh._tcp.local".to_string()),
+            ),
+        ];
+        let hosts = collect_hosts(&records);
+        assert_eq!(hosts.len(), 1);
+        let pi = &hosts[0];
+        assert_eq!(pi.instance, "pi");
+        assert_eq!(pi.hostname, "raspberrypi.local");
+        assert_eq!(pi.addresses, vec!["192.168.1.42", "fe80::1"]);
+        assert_eq!(pi.txt.get("model").map(String::as_str), Some("Pi 4B"));
+        assert_eq!(pi.txt.get("flag").map(String::as_str), Some(""));
+    }
+
+    #[test]
+    fn test_host_options() {
+        let host = MdnsHost {
+            instance: "pi".to_string(),
+            hostname: "raspberrypi.local".to_string(),
+            port: 2222,
+            addresses: vec!["192.168.1.42".to_string()],
+            txt: BTreeMap::new(),
+        };
+        let mut request = MdnsImportRequest {
+            hosts: Vec::new(),
+            user: Some("pi".to_string()),
+            use_address: false,
+        };
+        assert_eq!(
+            host_options(&host, &request),
+            vec![
+                ("HostName".to_string(), "raspberrypi.local".to_string()),
+                ("Port".to_string(), "2222".to_string()),
+                ("User".to_string(), "pi".to_string()),
+            ]
+        );
+        request.use_address = true;
+        assert_eq!(host_options(&host, &request)[0].1, "192.168.1.42");
+    }
+}
@@ -0,0 +1,268 @@
+pub mod agent_service;
+pub mod algorithm_check;
+pub mod audit_service;
+pub mod auth_prompt;
+pub mod authorized_keys_audit;
+pub mod captures;
+pub mod cert_authority;
+pub mod config_service;
+pub mod config_suggestions;
+pub mod connection_hooks;
+pub mod console_server;
+pub mod db_handoff;
+pub mod deep_link;
+pub mod docker_service;
+pub mod doctor_service;
+pub mod export_service;
+pub mod export_signing;
+pub mod fingerprint_index;
+pub mod fleet_service;
+pub mod git_ssh_command;
+pub mod git_versioning;
+pub mod health_check;
+pub mod history_service;
+pub mod host_facts;
+pub mod host_key_rotation;
+pub mod host_notes;
+pub mod host_query;
+pub mod integrity;
+pub mod job_scheduler;
+pub mod kerberos_service;
+pub mod key_deploy;
+pub mod key_expiry;
+pub mod key_exposure;
+pub mod key_manager;
+pub mod key_metadata;
+pub mod keypair_audit;
+pub mod known_hosts;
+pub mod known_hosts_import;
+pub mod krl_service;
+pub mod kube_import;
+pub mod legacy_profiles;
+pub mod log_service;
+pub mod login_banner;
+pub mod mdns_discovery;
+pub mod multiplexer;
+pub mod network_requirement;
+pub mod notification_service;
+pub mod onboarding_service;
+pub mod palette_search;
+pub mod permission_service;
+pub mod port_scan;
+pub mod pq_readiness;
+pub mod privacy_service;
+pub mod proxy_service;
+pub mod qr_code;
+pub mod reachability;
+pub mod read_only;
+pub mod registry_service;
+pub mod remediation;
+pub mod remote_listeners;
+pub mod reverse_tunnel;
+pub mod revision_service;
+pub mod script_service;
+pub mod session_helper;
+pub mod session_restore;
+pub mod session_share;
+pub mod settings_service;
+pub mod sftp_stream;
+pub mod shell_access;
+pub mod shell_history;
+pub mod shell_session;
+pub mod short_codes;
+pub mod smart_groups;
+pub mod snippet_service;
+pub mod ssh_connection;
+pub mod ssh_engine;
+pub mod ssh_installations;
+pub mod sudo_service;
+pub mod tamper_detection;
+pub mod team_catalog;
+pub mod terminal_launcher;
+pub mod transfer_service;
+pub mod trash_service;
+pub mod tray_menu;
+pub mod tunnel_export;
+pub mod tunnel_http_check;
+pub mod tunnel_service;
+pub mod vault_service;
+pub mod vm_discovery;
+pub mod watcher_service;
+pub mod workspace_service;
+
+pub use agent_service::{AddKeyResult, AgentKeyInfo, AgentService, RemoveKeyResult};
+pub use algorithm_check::{
+    AlgorithmCheckService, AlgorithmCompatReport, AlgorithmOverride, CategoryCheck, CompatStatus,
+};
+pub use audit_service::{
+    AuditEvent, AuditKind, AuditService, LogExportFormat, LogExportRequest, LogSource,
+    SiemSettings, SiemTarget,
+};
+pub use auth_prompt::{AuthPromptBroker, AuthPromptRequest, AuthPrompter};
+pub use authorized_keys_audit::{
+    AuthorizedKeysAudit, AuthorizedKeysAuditRequest, AuthorizedKeysAuditService,
+};
+pub use captures::{CaptureRecord, CaptureRequest, CaptureService, CaptureTarget};
+pub use cert_authority::{CertAuthority, CertAuthorityService, HostTrust};
+pub use config_service::{
+    BulkUpdateResult, ConfigHostSummary, ConfigService, CreatedHost, GssapiOptions, HostFilter,
+    HostTemplate, OptionChange,
+};
+pub use config_suggestions::{ConfigSuggestion, ConfigSuggestionService, SuggestionKind};
+pub use connection_hooks::{ConnectionHookService, HookKind, HookRun, HostHooks};
+pub use console_server::{
+    ConsoleBackspace, ConsoleLineEnding, ConsoleServerService, ConsoleSession, HostConsole,
+};
+pub use db_handoff::{
+    DatabaseClient, DatabaseEngine, DatabaseHandoff, DatabaseHandoffService, TunnelDatabase,
+};
+pub use deep_link::{DeepLinkRequest, DeepLinkService};
+pub use docker_service::{DockerContainer, DockerContext, DockerService, DockerStatus};
+pub use doctor_service::{
+    DoctorCheck, DoctorComparison, DoctorFinding, DoctorReport, DoctorService, FindingSeverity,
+};
+pub use export_service::{ExportOptions, ExportResult, ExportService, OpenedBundle};
+pub use export_signing::{ExportSigningService, SignatureCheck, SigningKeyInfo, TrustedSigner};
+pub use fingerprint_index::{
+    FingerprintIndexEntry, FingerprintIndexService, IndexRefresh, IndexedKeyFile,
+};
+pub use fleet_service::{FleetExportFormat, FleetRequest, FleetService, FleetSummary};
+pub use git_ssh_command::{GitSshCommandService, GitSshCommandSpec, GitSshSource, GitSshStatus};
+pub use git_versioning::{GitCommitInfo, GitVersioningService, GitVersioningStatus};
+pub use health_check::{
+    HealthCheckKind, HealthCheckRun, HealthCheckService, HealthCheckSettings, HealthCheckStatus,
+};
+pub use history_service::{
+    ActivityQuery, ActivityStats, DailyActivity, FailureCount, HistoryService, HostActivity,
+    SessionRecord,
+};
+pub use host_facts::{HostFacts, HostFactsService};
+pub use host_key_rotation::{
+    HostKeyRotationRequest, HostKeyRotationResult, HostKeyRotationService, RotationStep,
+};
+pub use host_notes::{HostAttachment, HostNotesService};
+pub use host_query::{HostListItem, HostPage, HostQuery, HostQueryService, HostSort};
+pub use integrity::{IntegrityChange, IntegrityReport, IntegrityService};
+pub use job_scheduler::{
+    JobHostResult, JobRun, ScheduledJob, ScheduledJobService, ScheduledJobStatus,
+};
+pub use kerberos_service::{KerberosService, KerberosTicketStatus};
+pub use key_deploy::{KeyDeployRequest, KeyDeployResult, KeyDeployService};
+pub use key_expiry::KeyExpiryService;
+pub use key_exposure::{ExposureReport, HostKeyScan, KeyExposureService};
+pub use key_manager::{GenerateKeyOptions, KeyManager};
+pub use key_metadata::{KeyMetadata, KeyMetadataService};
+pub use keypair_audit::{KeypairAuditService, KeypairScan};
+pub use known_hosts::{
+    AddHostResult as KnownHostAddResult, KnownHostEntry, KnownHostsService,
+    RemoveHostResult as KnownHostRemoveResult,
+};
+pub use known_hosts_import::{
+    KnownHostCandidate, KnownHostsImportRequest, KnownHostsImportResult, KnownHostsImportService,
+};
+pub use krl_service::{HostRevocationReport, KeyRevocation, KrlService, KrlSummary};
+pub use kube_import::{
+    KubeContext, KubeImportRequest, KubeImportResult, KubeImportService, KubeNode,
+};
+pub use legacy_profiles::{
+    legacy_profiles, LegacyExceptionStatus, LegacyHostRequest, LegacyProfile, LegacyProfileService,
+};
+pub use log_service::{LogEntry, LogLevel, LogQuery, LogService, LogSettings};
+pub use login_banner::{HostLoginBanner, LoginBannerService};
+pub use mdns_discovery::{MdnsDiscoveryService, MdnsHost, MdnsImportRequest, MdnsImportResult};
+pub use multiplexer::{
+    HostMultiplexer, Multiplexer, MultiplexerService, MultiplexerSession, RemoteSessions,
+    SessionChoice,
+};
+pub use network_requirement::{
+    NetworkCheck, NetworkRequirement, NetworkRequirementService, NetworkStatus,
+};
+pub use notification_service::{
+    Notification, NotificationCategory, NotificationDelivery, NotificationHistoryQuery,
+    NotificationPreferences, NotificationRecord, NotificationService, QuietHours,
+};
+pub use onboarding_service::{
+    ChecklistItem, MachineScan, OnboardingReport, OnboardingService, OnboardingState,
+    OnboardingStep, StepStatus,
+};
+pub use palette_search::{PaletteItem, PaletteItemKind, PaletteSearchService, PaletteShortcut};
+pub use permission_service::{
+    PermissionBackend, PermissionCapabilities, PermissionCheckResult, PermissionFixResult,
+    PermissionOverride, PermissionPolicy, PermissionProfile, PermissionService,
+};
+pub use port_scan::{
+    PortScanResult, PortScanService, SubnetSweepRequest, SubnetSweepResult, SweepCandidate,
+    SweepProgress,
+};
+pub use pq_readiness::{
+    ClientPqSupport, PqReadinessReport, PqReadinessRequest, PqReadinessService,
+};
+pub use privacy_service::{PrivacyService, PrivacySettings, RetentionResult};
+pub use proxy_service::{ProxyService, ProxySettings};
+pub use qr_code::{QrCodeService, QrContent, QrFormat, ScannedPublicKey};
+pub use reachability::{
+    HostDown, ReachabilityHistory, ReachabilityPoint, ReachabilityService, ReachabilitySettings,
+};
+pub use read_only::{ReadOnlyMode, ReadOnlyStatus};
+pub use registry_service::{RegistryChange, RegistryService};
+pub use remediation::{
+    RemediationAction, RemediationPlaybook, RemediationProgress, RemediationRequest,
+    RemediationResult, RemediationService, RemediationStep, StepScope, StepStatus,
+};
+pub use remote_listeners::{
+    KnownService, ListenerDiscovery, RemoteListener, RemoteListenerService, ServiceKind,
+    TunnelSuggestion,
+};
+pub use reverse_tunnel::{
+    ReverseTunnelDefinition, ReverseTunnelFiles, ReverseTunnelInstallResult, ReverseTunnelService,
+    ReverseTunnelState, UnitScope,
+};
+pub use revision_service::{ManagedFile, Revision, RevisionDiff, RevisionService};
+pub use script_service::{ScriptRunRequest, ScriptRunResult, ScriptService};
+pub use session_helper::{
+    IsolationSettings, SessionHelperService, SessionHelperStatus, HELPER_ARG,
+};
+pub use session_restore::{
+    PinnedSession, RestoreSettings, RestoreSummary, RestoredItem, SessionRestoreService,
+};
+pub use session_share::{ObserveEvent, SessionShare, SessionShareService};
+pub use settings_service::{AppSettings, SettingsService};
+pub use sftp_stream::{RangeRead, RemoteFileChunk, SftpStreamService};
+pub use shell_access::{HostShellAccess, ShellAccess, ShellAccessService, ShellRoute};
+pub use shell_history::{
+    HistoryHost, HistoryImportRequest, HistoryImportResult, HistoryShell, ShellHistoryService,
+};
+pub use shell_session::{ShellEvent, ShellSessionManager};
+pub use short_codes::{HostShortCode, ShortCodeService};
+pub use smart_groups::{SmartGroup, SmartGroupMembers, SmartGroupService};
+pub use snippet_service::{Snippet, SnippetService};
+pub use ssh_connection::{ConnectionTestResult, OutputStream, RemoteSession, SshConnectionService};
+pub use ssh_engine::{
+    EngineCapabilities, EngineChoice, EngineFeature, EngineSettings, SshEngineKind,
+    SshEngineService,
+};
+pub use ssh_installations::{SshBinary, SshEnvironment, SshFlavor, SshInstallationsService};
+pub use sudo_service::{SudoAccess, SudoService};
+pub use tamper_detection::{QuarantinedFile, SuspiciousFile, TamperDetectionService};
+pub use team_catalog::{
+    CatalogKey, CatalogLocation, CatalogRefreshResult, CatalogSource, CatalogSubscribeRequest,
+    CatalogSubscription, TeamCatalogService,
+};
+pub use terminal_launcher::{ExternalTerminal, TerminalInfo, TerminalLauncher, TerminalSettings};
+pub use transfer_service::{
+    ScheduleState, ScheduledTransfer, TransferDirection, TransferManager, TransferRequest,
+    TransferSettings, TransferState, TransferStatus,
+};
+pub use trash_service::{DeletedItem, DeletedKind, TrashService, TrashSettings};
+pub use tray_menu::{
+    TrayAction, TrayAgentState, TrayHost, TrayMenuModel, TrayMenuService, TrayTunnel,
+};
+pub use tunnel_export::{
+    ServiceFormat, ServiceState, TunnelExportService, TunnelServiceExport, TunnelServiceStatus,
+};
+pub use tunnel_http_check::{HttpCheckResult, TunnelHttpCheck};
+pub use tunnel_service::{TunnelDefinition, TunnelEvent, TunnelManager, TunnelStatus};
+pub use vault_service::{LockReason, SecuritySettings, VaultService, VaultStatus};
+pub use vm_discovery::{DiscoveredVm, VmDiscoveryReport, VmDiscoveryService, VmImportResult};
+pub use watcher_service::{WatchedFile, WatcherService};
+pub use workspace_service::{Workspace, WorkspaceService, WorkspaceSettings, DEFAULT_WORKSPACE};
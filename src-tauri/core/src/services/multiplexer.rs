@@ -0,0 +1,374 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::registry_service::{HostMetadata, RegistryService};
+use crate::services::script_service::shell_quote;
+use crate::services::ssh_connection::SshConnectionService;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Timeout for listing remote sessions
+const LIST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// tmux session used when a host always wraps its shells and no name is set
+const DEFAULT_SESSION_NAME: &str = "sshbuddy";
+
+/// POSIX sh script run with `sh -s`; prints a marker line for each installed
+/// multiplexer followed by its sessions
+const LIST_SCRIPT: &str = r#"
+if command -v tmux >/dev/null 2>&1; then
+  echo "@tmux"
+  tmux list-sessions -F '#{session_name}	#{session_windows}	#{session_attached}	#{session_created}' 2>/dev/null
+fi
+if command -v screen >/dev/null 2>&1; then
+  echo "@screen"
+  screen -ls 2>/dev/null
+fi
+true
+"#;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+/// Running tmux/screen session on a remote host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiplexerSession {
+    pub multiplexer: Multiplexer,
+    /// Target to attach to (tmux session name, or "<pid>.<name>" for screen)
+    pub id: String,
+    pub name: String,
+    /// Window count (tmux only)
+    pub windows: Option<u32>,
+    /// Another client is attached
+    pub attached: bool,
+    /// Unix timestamp in milliseconds (tmux only)
+    pub created_at: Option<i64>,
+}
+
+/// Multiplexers installed on a host and their sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSessions {
+    pub tmux_installed: bool,
+    pub screen_installed: bool,
+    pub sessions: Vec<MultiplexerSession>,
+}
+
+/// How a shell session is started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SessionChoice {
+    /// Plain login shell
+    Plain,
+    /// Attach to an existing session
+    Attach {
+        multiplexer: Multiplexer,
+        id: String,
+    },
+    /// Start a new session (attaching instead if a tmux session with that name exists)
+    New {
+        multiplexer: Multiplexer,
+        name: Option<String>,
+    },
+}
+
+/// Per-host multiplexer preference, stored in the host registry
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMultiplexer {
+    /// Open every shell inside tmux so it survives disconnects
+    #[serde(default)]
+    pub always_tmux: bool,
+    /// tmux session to create or re-attach to (default "sshbuddy")
+    pub session_name: Option<String>,
+}
+
+/// Detects tmux/screen sessions on remote hosts and builds the commands to use them
+pub struct MultiplexerService;
+
+impl MultiplexerService {
+    /// List tmux and screen sessions on a host
+    pub async fn list_sessions(
+        host_alias: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<RemoteSessions> {
+        let session = SshConnectionService::open_session(host_alias, prompter).await?;
+        let output = session
+            .exec(
+                "sh -s",
+                Some(LIST_SCRIPT.as_bytes()),
+                LIST_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+
+        let sessions = parse_sessions(&output?.stdout);
+        tracing::info!(
+            "[multiplexer] {}: tmux={} screen={} sessions={}",
+            host_alias,
+            sessions.tmux_installed,
+            sessions.screen_installed,
+            sessions.sessions.len()
+        );
+        Ok(sessions)
+    }
+
+    /// Multiplexer preference of a host
+    pub async fn get_host_settings(host_alias: &str) -> SshResult<HostMultiplexer> {
+        let store = RegistryService::load().await?;
+        Ok(store
+            .hosts
+            .get(host_alias)
+            .and_then(|m| m.multiplexer.clone())
+            .unwrap_or_default())
+    }
+
+    /// Set a host's multiplexer preference
+    pub async fn set_host_settings(host_alias: &str, settings: HostMultiplexer) -> SshResult<()> {
+        let settings = HostMultiplexer {
+            session_name: settings
+                .session_name
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty()),
+            ..settings
+        };
+        if let Some(name) = &settings.session_name {
+            validate_session_name(name)?;
+        }
+
+        RegistryService::update(|store| {
+            store
+                .hosts
+                .entry(host_alias.to_string())
+                .or_insert_with(HostMetadata::new)
+                .multiplexer = (settings != HostMultiplexer::default()).then_some(settings);
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[multiplexer] Updated multiplexer settings of {}",
+            host_alias
+        );
+        Ok(())
+    }
+
+    /// Command to run on the shell's pseudo-terminal (None for a plain shell)
+    /// Without an explicit choice the host's "always tmux" preference applies
+    pub async fn shell_command(
+        host_alias: &str,
+        choice: Option<&SessionChoice>,
+    ) -> SshResult<Option<String>> {
+        if let Some(choice) = choice {
+            return choice_command(choice);
+        }
+        let settings = Self::get_host_settings(host_alias).await?;
+        if !settings.always_tmux {
+            return Ok(None);
+        }
+        let name = settings
+            .session_name
+            .as_deref()
+            .unwrap_or(DEFAULT_SESSION_NAME);
+        Ok(Some(wrapped_tmux_command(name)))
+    }
+}
+
+/// tmux reads '.' and ':' in a target as window/pane separators
+fn validate_session_name(name: &str) -> SshResult<()> {
+    if name.starts_with('-')
+        || name
+            .chars()
+            .any(|c| matches!(c, '.' | ':') || c.is_control())
+    {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!("Invalid session name: {}", name),
+        });
+    }
+    Ok(())
+}
+
+fn choice_command(choice: &SessionChoice) -> SshResult<Option<String>> {
+    let command = match choice {
+        SessionChoice::Plain => return Ok(None),
+        SessionChoice::Attach { multiplexer, id } => {
+            if id.is_empty() || id.starts_with('-') {
+                return Err(SshBuddyError::InvalidOption {
+                    message: format!("Invalid session: {}", id),
+                });
+            }
+            match multiplexer {
+                // "=" makes tmux match the name exactly instead of as a prefix
+                Multiplexer::Tmux => {
+                    format!(
+                        "tmux attach-session -t {}",
+                        shell_quote(&format!("={}", id))
+                    )
+                }
+                // -x attaches even when the session is attached elsewhere
+                Multiplexer::Screen => format!("screen -x {}", shell_quote(id)),
+            }
+        }
+        SessionChoice::New { multiplexer, name } => {
+            let name = name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+            if let Some(name) = name {
+                validate_session_name(name)?;
+            }
+            match (multiplexer, name) {
+                (Multiplexer::Tmux, Some(name)) => {
+                    format!("tmux new-session -A -s {}", shell_quote(name))
+                }
+                (Multiplexer::Tmux, None) => "tmux new-session".to_string(),
+                (Multiplexer::Screen, Some(name)) => format!("screen -S {}", shell_quote(name)),
+                (Multiplexer::Screen, None) => "screen".to_string(),
+            }
+        }
+    };
+    Ok(Some(command))
+}
+
+/// Attach to (or create) a named tmux session, falling back to a login shell
+/// on hosts without tmux
+fn wrapped_tmux_command(name: &str) -> String {
+    let script = format!(
+        "command -v tmux >/dev/null 2>&1 && exec tmux new-session -A -s {} || exec \"${{SHELL:-/bin/sh}}\" -l",
+        shell_quote(name)
+    );
+    format!("sh -c {}", shell_quote(&script))
+}
+
+fn parse_sessions(output: &str) -> RemoteSessions {
+    let mut result = RemoteSessions::default();
+    let mut current = None;
+    for line in output.lines() {
+        match line.trim() {
+            "@tmux" => {
+                result.tmux_installed = true;
+                current = Some(Multiplexer::Tmux);
+            }
+            "@screen" => {
+                result.screen_installed = true;
+                current = Some(Multiplexer::Screen);
+            }
+            _ => {
+                let session = match current {
+                    Some(Multiplexer::Tmux) => parse_tmux_line(line),
+                    Some(Multiplexer::Screen) => parse_screen_line(line),
+                    None => None,
+                };
+                result.sessions.extend(session);
+            }
+        }
+    }
+    result
+}
+
+/// `name<TAB>windows<TAB>attached clients<TAB>created (unix seconds)`
+fn parse_tmux_line(line: &str) -> Option<MultiplexerSession> {
+    let mut fields = line.split('\t');
+    let name = fields.next().filter(|n| !n.is_empty())?;
+    let windows = fields.next()?.parse().ok();
+    let attached = fields.next()?.parse::<u32>().map_or(false, |n| n > 0);
+    let created_at = fields
+        .next()
+        .and_then(|c| c.trim().parse::<i64>().ok())
+        .map(|secs| secs * 1000);
+    Some(MultiplexerSession {
+        multiplexer: Multiplexer::Tmux,
+        id: name.to_string(),
+        name: name.to_string(),
+        windows,
+        attached,
+        created_at,
+    })
+}
+
+/// `<TAB>12345.pts-0.web<TAB>(01/02/24 10:00:00)<TAB>(Detached)`
+fn parse_screen_line(line: &str) -> Option<MultiplexerSession> {
+    if !line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let id = line.split_whitespace().next()?;
+    let (pid, name) = id.split_once('.')?;
+    if pid.is_empty() || !pid.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(MultiplexerSession {
+        multiplexer: Multiplexer::Screen,
+        id: id.to_string(),
+        name: name.to_string(),
+        windows: None,
+        attached: line.contains("(Attached)") || line.contains("(Multi, attached)"),
+        created_at: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sessions() {
+        let output = "@tmux\nmain\t3\t1\t1700000000\nwork\t1\t0\t1700000100\n@screen\n\
+            There are screens on:\n\
+            \t4242.pts-0.build\t(11/14/2023 10:00:00 AM)\t(Detached)\n\
+            \t4343.logs\t(Attached)\n\
+            2 Sockets in /run/screen/S-deploy.\n";
+        let sessions = parse_sessions(output);
+        assert!(sessions.tmux_installed && sessions.screen_installed);
+        assert_eq!(sessions.sessions.len(), 4);
+        assert_eq!(sessions.sessions[0].name, "main");
+        assert_eq!(sessions.sessions[0].windows, Some(3));
+        assert!(sessions.sessions[0].attached);
+        assert_eq!(sessions.sessions[1].created_at, Some(1_700_000_100_000));
+        assert!(!sessions.sessions[1].attached);
+        assert_eq!(sessions.sessions[2].id, "4242.pts-0.build");
+        assert_eq!(sessions.sessions[2].name, "pts-0.build");
+        assert!(!sessions.sessions[2].attached);
+        assert!(sessions.sessions[3].attached);
+
+        // tmux installed without a server running
+        let sessions = parse_sessions("@tmux\n");
+        assert!(sessions.tmux_installed && !sessions.screen_installed);
+        assert!(sessions.sessions.is_empty());
+    }
+
+    #[test]
+    fn test_choice_command() {
+        let command = |choice: SessionChoice| choice_command(&choice).unwrap();
+        assert_eq!(command(SessionChoice::Plain), None);
+        assert_eq!(
+            command(SessionChoice::Attach {
+                multiplexer: Multiplexer::Tmux,
+                id: "my work".to_string(),
+            })
+            .as_deref(),
+            Some("tmux attach-session -t '=my work'")
+        );
+        assert_eq!(
+            command(SessionChoice::New {
+                multiplexer: Multiplexer::Screen,
+                name: Some(" ".to_string()),
+            })
+            .as_deref(),
+            Some("screen")
+        );
+        assert!(choice_command(&SessionChoice::New {
+            multiplexer: Multiplexer::Tmux,
+            name: Some("a:b".to_string()),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_wrapped_tmux_command() {
+        assert_eq!(
+            wrapped_tmux_command("main"),
+            r#"sh -c 'command -v tmux >/dev/null 2>&1 && exec tmux new-session -A -s main || exec "${SHELL:-/bin/sh}" -l'"#
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use crate::models::SshResult;
+use crate::services::connection_hooks::{ConnectionHookService, HookCommand, HookKind};
+use crate::services::registry_service::{HostMetadata, RegistryService};
+use crate::utils::glob_match;
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout, Duration, Instant};
+
+/// Timeout for the reachability probe and the tailscale CLI
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long to wait for the network to come up after the launch hook
+const LAUNCH_WAIT: Duration = Duration::from_secs(20);
+
+/// Poll interval while waiting for the network
+const LAUNCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How a required network is detected
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NetworkCheck {
+    /// A network interface is up; `name` may contain wildcards ("utun*", "wg0")
+    Interface { name: String },
+    /// A TCP port on the private network accepts connections (e.g. an internal DNS server)
+    Reachable { host: String, port: u16 },
+    /// Tailscale is running (and connected to `tailnet`, if set)
+    Tailscale { tailnet: Option<String> },
+}
+
+/// VPN or network a host can only be reached through
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkRequirement {
+    /// Display name, e.g. "Office VPN"
+    pub name: String,
+    pub check: NetworkCheck,
+    /// Command that starts the VPN client
+    pub launch_hook: Option<HookCommand>,
+    /// Run the launch hook automatically when the network is down
+    #[serde(default)]
+    pub auto_launch: bool,
+}
+
+/// Result of a network check
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub name: String,
+    pub connected: bool,
+    /// What was found, e.g. "Interface utun3 is up" or "Tailscale is Stopped"
+    pub detail: String,
+    /// Whether the launch hook was run during this check
+    pub launched: bool,
+}
+
+/// Detects whether the network a host depends on is connected
+pub struct NetworkRequirementService;
+
+impl NetworkRequirementService {
+    /// Network requirement of a host
+    pub async fn get(host_alias: &str) -> SshResult<Option<NetworkRequirement>> {
+        let store = RegistryService::load().await?;
+        Ok(store
+            .hosts
+            .get(host_alias)
+            .and_then(|m| m.network_requirement.clone()))
+    }
+
+    /// Set or clear a host's network requirement
+    pub async fn set(host_alias: &str, requirement: Option<NetworkRequirement>) -> SshResult<()> {
+        RegistryService::update(|store| {
+            store
+                .hosts
+                .entry(host_alias.to_string())
+                .or_insert_with(HostMetadata::new)
+                .network_requirement = requirement;
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[network_requirement] Updated network requirement of {}",
+            host_alias
+        );
+        Ok(())
+    }
+
+    /// Check the host's required network, launching it first when configured to
+    /// None when the host has no requirement
+    pub async fn check_host(host_alias: &str) -> SshResult<Option<NetworkStatus>> {
+        let Some(requirement) = Self::get(host_alias).await? else {
+            return Ok(None);
+        };
+        let status = Self::check(&requirement).await;
+        if status.connected || !requirement.auto_launch || requirement.launch_hook.is_none() {
+            return Ok(Some(status));
+        }
+        Ok(Some(Self::launch(host_alias, &requirement).await))
+    }
+
+    /// Run the launch hook and wait for the network to come up
+    pub async fn launch(host_alias: &str, requirement: &NetworkRequirement) -> NetworkStatus {
+        let Some(hook) = &requirement.launch_hook else {
+            return Self::check(requirement).await;
+        };
+        tracing::info!(
+            "[network_requirement] Launching {} for {}",
+            requirement.name,
+            host_alias
+        );
+        let run = ConnectionHookService::execute(host_alias, HookKind::PreConnect, hook).await;
+        if !run.success() {
+            let mut status = Self::check(requirement).await;
+            status.launched = true;
+            status.detail = format!(
+                "{}; the launch command failed ({})",
+                status.detail,
+                run.error
+                    .unwrap_or_else(|| format!("exit {:?}", run.exit_code))
+            );
+            return status;
+        }
+
+        // VPN clients usually return before the tunnel is up
+        let deadline = Instant::now() + LAUNCH_WAIT;
+        loop {
+            let mut status = Self::check(requirement).await;
+            status.launched = true;
+            if status.connected || Instant::now() >= deadline {
+                return status;
+            }
+            sleep(LAUNCH_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Check a requirement once
+    pub async fn check(requirement: &NetworkRequirement) -> NetworkStatus {
+        let (connected, detail) = match &requirement.check {
+            NetworkCheck::Interface { name } => check_interface(name).await,
+            NetworkCheck::Reachable { host, port } => {
+                match timeout(CHECK_TIMEOUT, TcpStream::connect((host.as_str(), *port))).await {
+                    Ok(Ok(_)) => (true, format!("{}:{} is reachable", host, port)),
+                    Ok(Err(e)) => (false, format!("{}:{} is not reachable: {}", host, port, e)),
+                    Err(_) => (false, format!("{}:{} did not answer", host, port)),
+                }
+            }
+            NetworkCheck::Tailscale { tailnet } => {
+                match run_tool("tailscale", &["status", "--json"]).await {
+                    Ok(output) => tailscale_connected(&output, tailnet.as_deref()),
+                    Err(e) => (false, e),
+                }
+            }
+        };
+        NetworkStatus {
+            name: requirement.name.clone(),
+            connected,
+            detail,
+            launched: false,
+        }
+    }
+}
+
+/// Run a CLI tool and return its stdout (errors as display text)
+async fn run_tool(program: &'static str, args: &'static [&'static str]) -> Result<String, String> {
+    let result = timeout(
+        CHECK_TIMEOUT,
+        tokio::task::spawn_blocking(move || {
+            Command::new(program)
+                .args(args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+        }),
+    )
+    .await;
+
+    match result {
+        Ok(Ok(Ok(output))) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Ok(Ok(Ok(output))) => Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Ok(Ok(Err(e))) => Err(format!("{} is not available: {}", program, e)),
+        Ok(Err(e)) => Err(format!("Internal error: {}", e)),
+        Err(_) => Err(format!("{} timed out", program)),
+    }
+}
+
+/// Names of the network interfaces that are up
+#[cfg(target_os = "linux")]
+async fn interfaces_up() -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut entries = tokio::fs::read_dir("/sys/class/net")
+        .await
+        .map_err(|e| format!("Failed to list interfaces: {}", e))?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let state = tokio::fs::read_to_string(entry.path().join("operstate"))
+            .await
+            .unwrap_or_default();
+        // Point-to-point tunnels (tun, wg) report "unknown" while working
+        if matches!(state.trim(), "up" | "unknown") {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(target_os = "macos")]
+async fn interfaces_up() -> Result<Vec<String>, String> {
+    let output = run_tool("ifconfig", &["-l", "-u"]).await?;
+    Ok(output.split_whitespace().map(str::to_string).collect())
+}
+
+#[cfg(windows)]
+async fn interfaces_up() -> Result<Vec<String>, String> {
+    let output = run_tool("netsh", &["interface", "show", "interface"]).await?;
+    Ok(parse_netsh_interfaces(&output))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+async fn interfaces_up() -> Result<Vec<String>, String> {
+    Err("Interface detection is not supported on this platform".to_string())
+}
+
+async fn check_interface(pattern: &str) -> (bool, String) {
+    match interfaces_up().await {
+        Ok(names) => match names.iter().find(|n| glob_match(pattern, n)) {
+            Some(name) => (true, format!("Interface {} is up", name)),
+            None => (false, format!("No interface matching {} is up", pattern)),
+        },
+        Err(e) => (false, e),
+    }
+}
+
+/// Connected interfaces from `netsh interface show interface`
+/// ("Admin State  State  Type  Interface Name" columns; the name may contain spaces)
+#[cfg_attr(not(any(windows, test)), allow(dead_code))]
+fn parse_netsh_interfaces(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_, state, _) = (fields.next()?, fields.next()?, fields.next()?);
+            let name = fields.collect::<Vec<_>>().join(" ");
+            (state.eq_ignore_ascii_case("connected") && !name.is_empty()).then_some(name)
+        })
+        .collect()
+}
+
+/// Interpret `tailscale status --json`
+fn tailscale_connected(json: &str, tailnet: Option<&str>) -> (bool, String) {
+    let Ok(status) = serde_json::from_str::<serde_json::Value>(json) else {
+        return (false, "Unreadable tailscale status".to_string());
+    };
+    let state = status["BackendState"].as_str().unwrap_or("Unknown");
+    if state != "Running" {
+        return (false, format!("Tailscale is {}", state));
+    }
+    let current = status["CurrentTailnet"]["Name"]
+        .as_str()
+        .unwrap_or_default();
+    match tailnet.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(expected) if !current.eq_ignore_ascii_case(expected) => (
+            false,
+            format!(
+                "Tailscale is connected to {} instead of {}",
+                current, expected
+            ),
+        ),
+        _ => (true, format!("Tailscale is connected to {}", current)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netsh_interfaces() {
+        let output = "\nAdmin State    State          Type             Interface Name\n-------------------------------------------------------------------------\nEnabled        Connected      Dedicated        Ethernet\nEnabled        Disconnected   Dedicated        Corp VPN\nEnabled        Connected      Dedicated        WireGuard Tunnel\n";
+        assert_eq!(
+            parse_netsh_interfaces(output),
+            vec!["Ethernet", "WireGuard Tunnel"]
+        );
+    }
+
+    #[test]
+    fn test_tailscale_connected() {
+        let running = r#"{"BackendState":"Running","CurrentTailnet":{"Name":"example.com"}}"#;
+        assert!(tailscale_connected(running, None).0);
+        assert!(tailscale_connected(running, Some("example.com")).0);
+        assert_eq!(
+            tailscale_connected(running, Some("other.org")),
+            (
+                false,
+                "Tailscale is connected to example.com instead of other.org".to_string()
+            )
+        );
+        assert_eq!(
+            tailscale_connected(r#"{"BackendState":"Stopped"}"#, None),
+            (false, "Tailscale is Stopped".to_string())
+        );
+        assert!(!tailscale_connected("not json", None).0);
+    }
+}
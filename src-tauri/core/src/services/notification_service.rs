@@ -0,0 +1,308 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Notifications kept in the in-memory history
+const MAX_HISTORY: usize = 200;
+
+/// What a notification is about; each category can be muted separately
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationCategory {
+    HostDown,
+    TunnelDropped,
+    KeyExpiring,
+    TransferFinished,
+    /// Possible tampering with the SSH directory
+    SecurityAlert,
+    /// New findings of a scheduled health check
+    HealthCheck,
+    /// Scheduled job that failed on a host or couldn't run
+    JobFailed,
+}
+
+/// Local time window without notifications, "HH:MM" (may span midnight, e.g. 22:00-07:00)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+/// Notification section of the settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPreferences {
+    /// Master switch
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Categories that are recorded in the history but not shown
+    #[serde(default)]
+    pub muted: Vec<NotificationCategory>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl NotificationPreferences {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        if let Some(quiet) = &self.quiet_hours {
+            parse_time(&quiet.start)?;
+            parse_time(&quiet.end)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            muted: Vec::new(),
+            quiet_hours: None,
+        }
+    }
+}
+
+/// Notification to show
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub host_alias: Option<String>,
+}
+
+/// What happened to a notification
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationDelivery {
+    Shown,
+    Disabled,
+    Muted,
+    QuietHours,
+}
+
+/// Notification history entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRecord {
+    pub id: u64,
+    #[serde(flatten)]
+    pub notification: Notification,
+    /// Unix timestamp in milliseconds
+    pub created_at: i64,
+    pub delivery: NotificationDelivery,
+}
+
+/// Notification history filter
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationHistoryQuery {
+    pub category: Option<NotificationCategory>,
+    pub host_alias: Option<String>,
+    /// Only notifications created after this time (Unix milliseconds)
+    pub since: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+/// Decides which notifications are shown and keeps their history
+/// (showing them is left to the caller, which owns the app handle)
+pub struct NotificationService;
+
+impl NotificationService {
+    fn history() -> &'static Mutex<VecDeque<NotificationRecord>> {
+        static HISTORY: OnceLock<Mutex<VecDeque<NotificationRecord>>> = OnceLock::new();
+        HISTORY.get_or_init(|| Mutex::new(VecDeque::new()))
+    }
+
+    /// Load preferences (defaults when none were saved)
+    pub async fn get_preferences() -> SshResult<NotificationPreferences> {
+        Ok(SettingsService::get().notifications)
+    }
+
+    /// Save preferences
+    pub async fn set_preferences(preferences: &NotificationPreferences) -> SshResult<()> {
+        preferences.validate()?;
+        SettingsService::update(|settings| {
+            settings.notifications = preferences.clone();
+            Ok(())
+        })
+        .await?;
+        tracing::info!("[notification_service] Updated notification preferences");
+        Ok(())
+    }
+
+    /// Record a notification and decide whether it should be shown
+    pub async fn submit(notification: Notification) -> NotificationRecord {
+        let preferences = Self::get_preferences().await.unwrap_or_else(|e| {
+            tracing::warn!("[notification_service] Using default preferences: {}", e);
+            NotificationPreferences::default()
+        });
+        let now = chrono::Local::now();
+        let minute_of_day = now.hour() * 60 + now.minute();
+        let delivery = decide(&preferences, notification.category, minute_of_day);
+
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let record = NotificationRecord {
+            id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+            notification,
+            created_at: now_millis(),
+            delivery,
+        };
+        let mut history = Self::history().lock().unwrap_or_else(|e| e.into_inner());
+        if history.len() >= MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(record.clone());
+        record
+    }
+
+    /// Notification history matching the query, newest first
+    pub fn query(query: &NotificationHistoryQuery) -> Vec<NotificationRecord> {
+        let history = Self::history().lock().unwrap_or_else(|e| e.into_inner());
+        history
+            .iter()
+            .rev()
+            .filter(|r| {
+                query
+                    .category
+                    .map_or(true, |c| r.notification.category == c)
+            })
+            .filter(|r| {
+                query
+                    .host_alias
+                    .as_deref()
+                    .map_or(true, |a| r.notification.host_alias.as_deref() == Some(a))
+            })
+            .filter(|r| query.since.map_or(true, |since| r.created_at > since))
+            .take(query.limit.unwrap_or(MAX_HISTORY))
+            .cloned()
+            .collect()
+    }
+
+    /// Clear the notification history
+    pub fn clear_history() {
+        Self::history()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+/// Minutes since midnight for "HH:MM"
+fn parse_time(value: &str) -> SshResult<u32> {
+    value
+        .trim()
+        .split_once(':')
+        .and_then(|(h, m)| Some((h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)))
+        .filter(|(h, m)| *h < 24 && *m < 60)
+        .map(|(h, m)| h * 60 + m)
+        .ok_or_else(|| SshBuddyError::InvalidOption {
+            message: format!("Invalid time \"{}\" (expected HH:MM)", value),
+        })
+}
+
+/// Whether `minute_of_day` falls inside the quiet hours (end is exclusive)
+fn in_quiet_hours(quiet: &QuietHours, minute_of_day: u32) -> bool {
+    let (Ok(start), Ok(end)) = (parse_time(&quiet.start), parse_time(&quiet.end)) else {
+        return false;
+    };
+    if start <= end {
+        (start..end).contains(&minute_of_day)
+    } else {
+        minute_of_day >= start || minute_of_day < end
+    }
+}
+
+fn decide(
+    preferences: &NotificationPreferences,
+    category: NotificationCategory,
+    minute_of_day: u32,
+) -> NotificationDelivery {
+    if !preferences.enabled {
+        NotificationDelivery::Disabled
+    } else if preferences.muted.contains(&category) {
+        NotificationDelivery::Muted
+    } else if preferences
+        .quiet_hours
+        .as_ref()
+        .is_some_and(|q| in_quiet_hours(q, minute_of_day))
+    {
+        NotificationDelivery::QuietHours
+    } else {
+        NotificationDelivery::Shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet(start: &str, end: &str) -> QuietHours {
+        QuietHours {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_in_quiet_hours() {
+        let overnight = quiet("22:00", "07:00");
+        assert!(in_quiet_hours(&overnight, 23 * 60));
+        assert!(in_quiet_hours(&overnight, 6 * 60 + 59));
+        assert!(!in_quiet_hours(&overnight, 7 * 60));
+        assert!(!in_quiet_hours(&overnight, 12 * 60));
+
+        let lunch = quiet("12:00", "13:30");
+        assert!(in_quiet_hours(&lunch, 12 * 60 + 15));
+        assert!(!in_quiet_hours(&lunch, 13 * 60 + 30));
+        assert!(!in_quiet_hours(&quiet("25:00", "07:00"), 23 * 60));
+    }
+
+    #[test]
+    fn test_decide() {
+        let mut preferences = NotificationPreferences {
+            muted: vec![NotificationCategory::TransferFinished],
+            quiet_hours: Some(quiet("22:00", "07:00")),
+            ..Default::default()
+        };
+        assert_eq!(
+            decide(&preferences, NotificationCategory::HostDown, 12 * 60),
+            NotificationDelivery::Shown
+        );
+        assert_eq!(
+            decide(&preferences, NotificationCategory::HostDown, 23 * 60),
+            NotificationDelivery::QuietHours
+        );
+        assert_eq!(
+            decide(
+                &preferences,
+                NotificationCategory::TransferFinished,
+                12 * 60
+            ),
+            NotificationDelivery::Muted
+        );
+        preferences.enabled = false;
+        assert_eq!(
+            decide(&preferences, NotificationCategory::HostDown, 12 * 60),
+            NotificationDelivery::Disabled
+        );
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("07:30").unwrap(), 450);
+        assert_eq!(parse_time("0:00").unwrap(), 0);
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("7").is_err());
+    }
+}
@@ -0,0 +1,488 @@
+use crate::models::{LocalizedMessage, SshResult};
+use crate::services::agent_service::AgentService;
+use crate::services::key_manager::KeyManager;
+use crate::services::permission_service::PermissionService;
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use crate::utils::{ssh_config_path, SshConfigEditor};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+#[cfg(windows)]
+use std::process::{Command, Stdio};
+#[cfg(windows)]
+use std::time::Duration;
+
+/// Configs with at least this many hosts get the "organize hosts" step
+const LARGE_CONFIG_HOSTS: usize = 25;
+
+/// Timeout for listing the PuTTY sessions in the Windows registry
+#[cfg(windows)]
+const REG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// PuTTY's built-in session holding the defaults, not a host
+const PUTTY_DEFAULT_SESSION: &str = "Default Settings";
+
+/// Cloud CLIs whose hosts can be brought into the SSH config
+const CLOUD_CLIS: &[(&str, &str)] = &[
+    ("aws", "AWS CLI"),
+    ("gcloud", "Google Cloud CLI"),
+    ("az", "Azure CLI"),
+    ("doctl", "DigitalOcean CLI"),
+    ("kubectl", "kubectl"),
+];
+
+/// Setup step, listed in the order of `OnboardingStep::ALL`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum OnboardingStep {
+    /// ~/.ssh is missing or readable by others
+    FixSshDir,
+    CreateKey,
+    StartAgent,
+    AddKeyToAgent,
+    AddHost,
+    ImportPutty,
+    ImportCloudHosts,
+    /// Tag and group the hosts of a large config
+    OrganizeHosts,
+}
+
+impl OnboardingStep {
+    /// Steps by priority
+    pub const ALL: [OnboardingStep; 8] = [
+        OnboardingStep::FixSshDir,
+        OnboardingStep::CreateKey,
+        OnboardingStep::StartAgent,
+        OnboardingStep::AddKeyToAgent,
+        OnboardingStep::AddHost,
+        OnboardingStep::ImportPutty,
+        OnboardingStep::ImportCloudHosts,
+        OnboardingStep::OrganizeHosts,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            OnboardingStep::FixSshDir => "fixSshDir",
+            OnboardingStep::CreateKey => "createKey",
+            OnboardingStep::StartAgent => "startAgent",
+            OnboardingStep::AddKeyToAgent => "addKeyToAgent",
+            OnboardingStep::AddHost => "addHost",
+            OnboardingStep::ImportPutty => "importPutty",
+            OnboardingStep::ImportCloudHosts => "importCloudHosts",
+            OnboardingStep::OrganizeHosts => "organizeHosts",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StepStatus {
+    Pending,
+    Done,
+    Skipped,
+}
+
+/// Onboarding section of the settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OnboardingState {
+    /// Steps reported as done by the services (or marked done by the user)
+    pub completed: Vec<OnboardingStep>,
+    pub skipped: Vec<OnboardingStep>,
+    /// When the user finished or dismissed the onboarding (Unix milliseconds)
+    pub finished_at: Option<i64>,
+}
+
+/// What was found on the machine
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MachineScan {
+    pub ssh_dir_ok: bool,
+    pub key_count: usize,
+    pub agent_running: bool,
+    pub agent_key_count: usize,
+    pub config_exists: bool,
+    pub config_host_count: usize,
+    pub config_lines: usize,
+    /// Saved PuTTY session names
+    pub putty_sessions: Vec<String>,
+    /// Display names of the installed cloud CLIs
+    pub cloud_clis: Vec<String>,
+}
+
+/// Checklist entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistItem {
+    pub step: OnboardingStep,
+    pub status: StepStatus,
+    /// Catalog key `onboarding.<step>` with the details found on the machine
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingReport {
+    /// The onboarding was never finished or dismissed
+    pub first_run: bool,
+    pub scan: MachineScan,
+    /// Steps that apply to this machine, by priority
+    pub checklist: Vec<ChecklistItem>,
+}
+
+/// Whether a step applies to the machine, and is already satisfied by it
+fn step_state(step: OnboardingStep, scan: &MachineScan) -> Option<bool> {
+    match step {
+        OnboardingStep::FixSshDir => Some(scan.ssh_dir_ok),
+        OnboardingStep::CreateKey => Some(scan.key_count > 0),
+        OnboardingStep::StartAgent => Some(scan.agent_running),
+        OnboardingStep::AddKeyToAgent => Some(scan.agent_key_count > 0),
+        OnboardingStep::AddHost => Some(scan.config_host_count > 0),
+        OnboardingStep::ImportPutty => (!scan.putty_sessions.is_empty()).then_some(false),
+        OnboardingStep::ImportCloudHosts => (!scan.cloud_clis.is_empty()).then_some(false),
+        OnboardingStep::OrganizeHosts => {
+            (scan.config_host_count >= LARGE_CONFIG_HOSTS).then_some(false)
+        }
+    }
+}
+
+fn step_message(step: OnboardingStep, scan: &MachineScan) -> LocalizedMessage {
+    let message = LocalizedMessage::new(&format!("onboarding.{}", step.id()));
+    match step {
+        OnboardingStep::AddKeyToAgent => message.with("count", scan.key_count),
+        OnboardingStep::ImportPutty => message.with("count", scan.putty_sessions.len()),
+        OnboardingStep::ImportCloudHosts => message.with("tools", scan.cloud_clis.join(", ")),
+        OnboardingStep::OrganizeHosts => message.with("count", scan.config_host_count),
+        _ => message,
+    }
+}
+
+/// Prioritized checklist of the steps that apply to the machine
+fn build_checklist(scan: &MachineScan, state: &OnboardingState) -> Vec<ChecklistItem> {
+    OnboardingStep::ALL
+        .iter()
+        .filter_map(|&step| {
+            let satisfied = step_state(step, scan)?;
+            let status = if satisfied || state.completed.contains(&step) {
+                StepStatus::Done
+            } else if state.skipped.contains(&step) {
+                StepStatus::Skipped
+            } else {
+                StepStatus::Pending
+            };
+            Some(ChecklistItem {
+                step,
+                status,
+                message: step_message(step, scan),
+            })
+        })
+        .collect()
+}
+
+/// Session names below HKCU\Software\SimonTatham\PuTTY\Sessions in `reg query` output
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_reg_sessions(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().rsplit_once("\\Sessions\\"))
+        .map(|(_, name)| decode_putty_name(name))
+        .filter(|name| name != PUTTY_DEFAULT_SESSION)
+        .collect()
+}
+
+/// PuTTY stores session names URL-encoded ("My%20Server")
+fn decode_putty_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| name.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Inspects the machine on first launch and tracks the guided setup
+pub struct OnboardingService;
+
+impl OnboardingService {
+    /// Scan the machine and build the setup checklist
+    pub async fn inspect() -> SshResult<OnboardingReport> {
+        let (ssh_dir, keys, agent_running, config, putty_sessions, cloud_clis) = tokio::join!(
+            PermissionService::check_ssh_dir_permissions(),
+            async { KeyManager::new()?.list_keys().await },
+            AgentService::is_running(),
+            Self::read_config(),
+            Self::putty_sessions(),
+            Self::cloud_clis()
+        );
+        let agent_key_count = if agent_running {
+            AgentService::list_keys().await.map_or(0, |keys| keys.len())
+        } else {
+            0
+        };
+        let (config_exists, config_host_count, config_lines) = config;
+        let scan = MachineScan {
+            ssh_dir_ok: ssh_dir.is_ok_and(|result| result.is_valid),
+            key_count: keys.map_or(0, |keys| keys.len()),
+            agent_running,
+            agent_key_count,
+            config_exists,
+            config_host_count,
+            config_lines,
+            putty_sessions,
+            cloud_clis,
+        };
+
+        let state = SettingsService::get().onboarding;
+        let checklist = build_checklist(&scan, &state);
+        tracing::info!(
+            "[onboarding_service] {} of {} setup step(s) pending",
+            checklist
+                .iter()
+                .filter(|item| item.status == StepStatus::Pending)
+                .count(),
+            checklist.len()
+        );
+        Ok(OnboardingReport {
+            first_run: state.finished_at.is_none(),
+            scan,
+            checklist,
+        })
+    }
+
+    /// (exists, host count, line count) of ~/.ssh/config
+    async fn read_config() -> (bool, usize, usize) {
+        let Ok(path) = ssh_config_path() else {
+            return (false, 0, 0);
+        };
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                let hosts = SshConfigEditor::parse(&content).host_aliases().len();
+                (true, hosts, content.lines().count())
+            }
+            Err(_) => (false, 0, 0),
+        }
+    }
+
+    #[cfg(windows)]
+    async fn putty_sessions() -> Vec<String> {
+        let query = tokio::task::spawn_blocking(|| {
+            Command::new("reg")
+                .args(["query", r"HKCU\Software\SimonTatham\PuTTY\Sessions"])
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .output()
+        });
+        match tokio::time::timeout(REG_TIMEOUT, query).await {
+            Ok(Ok(Ok(output))) if output.status.success() => {
+                parse_reg_sessions(&String::from_utf8_lossy(&output.stdout))
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// PuTTY on Unix keeps one file per session in ~/.putty/sessions
+    #[cfg(not(windows))]
+    async fn putty_sessions() -> Vec<String> {
+        let Some(dir) = dirs::home_dir().map(|home| home.join(".putty").join("sessions")) else {
+            return Vec::new();
+        };
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            return Vec::new();
+        };
+        let mut sessions = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = decode_putty_name(&entry.file_name().to_string_lossy());
+            if name != PUTTY_DEFAULT_SESSION && entry.file_type().await.is_ok_and(|t| t.is_file()) {
+                sessions.push(name);
+            }
+        }
+        sessions.sort();
+        sessions
+    }
+
+    async fn cloud_clis() -> Vec<String> {
+        tokio::task::spawn_blocking(|| {
+            CLOUD_CLIS
+                .iter()
+                .filter(|(program, _)| find_cli(program).is_some())
+                .map(|(_, name)| name.to_string())
+                .collect()
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Record a step as done (services call this after the action succeeded)
+    pub async fn complete(step: OnboardingStep) {
+        if SettingsService::get().onboarding.completed.contains(&step) {
+            return;
+        }
+        let saved = SettingsService::update(|settings| {
+            let state = &mut settings.onboarding;
+            state.skipped.retain(|s| *s != step);
+            if !state.completed.contains(&step) {
+                state.completed.push(step);
+            }
+            Ok(())
+        })
+        .await;
+        match saved {
+            Ok(_) => tracing::info!("[onboarding_service] Step {} done", step.id()),
+            Err(e) => tracing::warn!(
+                "[onboarding_service] Failed to record step {}: {}",
+                step.id(),
+                e
+            ),
+        }
+    }
+
+    /// Set a step's status by hand (skip it, undo a skip, or mark it done)
+    pub async fn set_step_status(step: OnboardingStep, status: StepStatus) -> SshResult<()> {
+        SettingsService::update(|settings| {
+            let state = &mut settings.onboarding;
+            state.completed.retain(|s| *s != step);
+            state.skipped.retain(|s| *s != step);
+            match status {
+                StepStatus::Done => state.completed.push(step),
+                StepStatus::Skipped => state.skipped.push(step),
+                StepStatus::Pending => {}
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Finish or dismiss the onboarding; unfinishing starts it over
+    pub async fn set_finished(finished: bool) -> SshResult<()> {
+        SettingsService::update(|settings| {
+            if finished {
+                settings.onboarding.finished_at = Some(now_millis());
+            } else {
+                settings.onboarding = OnboardingState::default();
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+/// Find a CLI on PATH (Windows CLIs are often .cmd wrappers)
+fn find_cli(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let names: Vec<String> = if cfg!(windows) {
+        ["exe", "cmd", "bat"]
+            .iter()
+            .map(|ext| format!("{}.{}", program, ext))
+            .collect()
+    } else {
+        vec![program.to_string()]
+    };
+    std::env::split_paths(&path)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh_machine() -> MachineScan {
+        MachineScan {
+            ssh_dir_ok: true,
+            ..MachineScan::default()
+        }
+    }
+
+    fn steps(checklist: &[ChecklistItem], status: StepStatus) -> Vec<OnboardingStep> {
+        checklist
+            .iter()
+            .filter(|item| item.status == status)
+            .map(|item| item.step)
+            .collect()
+    }
+
+    #[test]
+    fn test_checklist_fresh_machine() {
+        let checklist = build_checklist(&fresh_machine(), &OnboardingState::default());
+        assert_eq!(
+            steps(&checklist, StepStatus::Pending),
+            vec![
+                OnboardingStep::CreateKey,
+                OnboardingStep::StartAgent,
+                OnboardingStep::AddKeyToAgent,
+                OnboardingStep::AddHost,
+            ]
+        );
+        assert_eq!(
+            steps(&checklist, StepStatus::Done),
+            vec![OnboardingStep::FixSshDir]
+        );
+    }
+
+    #[test]
+    fn test_checklist_existing_setup() {
+        let scan = MachineScan {
+            key_count: 2,
+            agent_running: true,
+            agent_key_count: 1,
+            config_exists: true,
+            config_host_count: 40,
+            putty_sessions: vec!["prod".to_string()],
+            cloud_clis: vec!["AWS CLI".to_string(), "kubectl".to_string()],
+            ..fresh_machine()
+        };
+        let state = OnboardingState {
+            completed: vec![OnboardingStep::ImportCloudHosts],
+            skipped: vec![OnboardingStep::ImportPutty],
+            finished_at: None,
+        };
+        let checklist = build_checklist(&scan, &state);
+        assert_eq!(
+            steps(&checklist, StepStatus::Pending),
+            vec![OnboardingStep::OrganizeHosts]
+        );
+        assert_eq!(
+            steps(&checklist, StepStatus::Skipped),
+            vec![OnboardingStep::ImportPutty]
+        );
+        let cloud = checklist
+            .iter()
+            .find(|item| item.step == OnboardingStep::ImportCloudHosts)
+            .unwrap();
+        assert_eq!(cloud.status, StepStatus::Done);
+        assert_eq!(cloud.message.params["tools"], "AWS CLI, kubectl");
+    }
+
+    #[test]
+    fn test_parse_reg_sessions() {
+        let output =
+            "\r\nHKEY_CURRENT_USER\\Software\\SimonTatham\\PuTTY\\Sessions\\Default%20Settings\r\n\
+                      HKEY_CURRENT_USER\\Software\\SimonTatham\\PuTTY\\Sessions\\web%2D01\r\n";
+        assert_eq!(parse_reg_sessions(output), vec!["web-01".to_string()]);
+    }
+
+    #[test]
+    fn test_decode_putty_name() {
+        assert_eq!(decode_putty_name("My%20Server"), "My Server");
+        assert_eq!(decode_putty_name("100%"), "100%");
+        assert_eq!(decode_putty_name("a%zzb"), "a%zzb");
+    }
+}
@@ -0,0 +1,353 @@
+use crate::models::SshResult;
+use crate::services::config_service::ConfigService;
+use crate::services::registry_service::{now_millis, pinned_aliases, RegistryService};
+use crate::services::settings_service::SettingsService;
+use crate::services::short_codes::list_codes;
+use crate::services::snippet_service::{Snippet, SnippetService};
+use serde::{Deserialize, Serialize};
+
+/// Results returned unless the caller asks for a different number
+const DEFAULT_RESULT_LIMIT: usize = 20;
+
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+
+/// Text score of a query that is exactly a host's short code: above any fuzzy match
+const SHORT_CODE_SCORE: i64 = 1000;
+
+/// Global shortcut that opens the quick-connect palette
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteShortcut {
+    pub enabled: bool,
+    /// Accelerator, e.g. "CommandOrControl+Shift+K"
+    pub shortcut: String,
+}
+
+impl Default for PaletteShortcut {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shortcut: "CommandOrControl+Shift+K".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PaletteItemKind {
+    Host,
+    Snippet,
+}
+
+/// Palette search result
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteItem {
+    pub kind: PaletteItemKind,
+    /// Host alias or snippet id
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Host short code, shown next to the title
+    pub short_code: Option<String>,
+    pub score: f64,
+    /// Character positions in `title` that matched the query
+    pub highlights: Vec<usize>,
+}
+
+/// Searchable entry before scoring
+struct Candidate {
+    kind: PaletteItemKind,
+    id: String,
+    title: String,
+    subtitle: Option<String>,
+    /// Also searched, at half weight
+    keywords: Vec<String>,
+    /// Typing exactly this ranks the candidate first
+    short_code: Option<String>,
+    /// Position in the pin order of a favorite host
+    pin: Option<usize>,
+    use_count: u32,
+    last_used: Option<i64>,
+}
+
+/// Ranked fuzzy search over hosts and snippets for the quick-connect palette
+pub struct PaletteSearchService;
+
+impl PaletteSearchService {
+    /// Load the palette shortcut (default when none was saved)
+    pub async fn get_shortcut() -> SshResult<PaletteShortcut> {
+        Ok(SettingsService::get().palette)
+    }
+
+    /// Save the palette shortcut
+    pub async fn set_shortcut(shortcut: &PaletteShortcut) -> SshResult<()> {
+        SettingsService::update(|settings| {
+            settings.palette = shortcut.clone();
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[palette_search] Palette shortcut set to {} (enabled={})",
+            shortcut.shortcut,
+            shortcut.enabled
+        );
+        Ok(())
+    }
+
+    /// Search hosts and snippets, best match first
+    /// An empty query lists the most used items
+    pub async fn search(query: &str, limit: Option<usize>) -> SshResult<Vec<PaletteItem>> {
+        let store = RegistryService::load().await?;
+        let editor = ConfigService::load_editor().await?;
+        let codes = list_codes(&store, &editor);
+        let pinned = pinned_aliases(&store);
+        let mut candidates: Vec<Candidate> = editor
+            .host_aliases()
+            .into_iter()
+            .map(|alias| {
+                let metadata = store.hosts.get(&alias);
+                let hostname = editor.get_option(&alias, "HostName");
+                let subtitle = match (editor.get_option(&alias, "User"), &hostname) {
+                    (Some(user), Some(host)) => Some(format!("{}@{}", user, host)),
+                    (_, host) => host.clone(),
+                };
+                let mut keywords: Vec<String> = hostname.into_iter().collect();
+                keywords.extend(metadata.map(|m| m.tags.clone()).unwrap_or_default());
+                Candidate {
+                    kind: PaletteItemKind::Host,
+                    id: alias.clone(),
+                    title: alias,
+                    subtitle,
+                    keywords,
+                    short_code: codes
+                        .iter()
+                        .find(|c| c.alias == alias && !c.shadowed)
+                        .map(|c| c.code.clone()),
+                    pin: pinned.iter().position(|p| *p == alias),
+                    use_count: metadata.map_or(0, |m| m.use_count),
+                    last_used: metadata.and_then(|m| m.last_used),
+                }
+            })
+            .collect();
+        candidates.extend(
+            SnippetService::list()
+                .await?
+                .into_iter()
+                .map(snippet_candidate),
+        );
+
+        Ok(rank(
+            &candidates,
+            query,
+            now_millis(),
+            limit.unwrap_or(DEFAULT_RESULT_LIMIT),
+        ))
+    }
+}
+
+fn snippet_candidate(snippet: Snippet) -> Candidate {
+    let mut keywords = vec![snippet.command.clone()];
+    keywords.extend(snippet.tags);
+    Candidate {
+        kind: PaletteItemKind::Snippet,
+        id: snippet.id,
+        title: snippet.name,
+        subtitle: Some(snippet.description.unwrap_or(snippet.command)),
+        keywords,
+        short_code: None,
+        pin: None,
+        use_count: snippet.use_count,
+        last_used: snippet.last_used,
+    }
+}
+
+/// Fuzzy subsequence match of `query` in `text` (case-insensitive)
+/// Matches at the start, after separators and in runs score higher; gaps cost a little
+/// Returns the score and matched character positions
+fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let text: Vec<char> = text.chars().collect();
+    let mut positions = Vec::new();
+    let mut score: i64 = 0;
+    let mut next = 0;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_lowercase().next().unwrap_or(q);
+        let found = (next..text.len()).find(|&i| text[i].to_lowercase().next() == Some(q))?;
+        score += 1;
+        if found == 0 {
+            score += 8;
+        } else if matches!(text[found - 1], '-' | '_' | '.' | ' ' | '/' | '@' | ':') {
+            score += 5;
+        }
+        if positions.last().is_some_and(|&last| last + 1 == found) {
+            score += 4;
+        }
+        score -= (found - next).min(5) as i64;
+        positions.push(found);
+        next = found + 1;
+    }
+    if positions.len() == text.len() {
+        // Exact match
+        score += 20;
+    }
+    Some((score, positions))
+}
+
+/// Multiplier for pinned, frequently and recently used items
+fn usage_weight(pinned: bool, use_count: u32, last_used: Option<i64>, now: i64) -> f64 {
+    let frequency = 1.0 + 0.25 * (1.0 + f64::from(use_count)).ln();
+    let recency = match last_used.map(|t| now - t) {
+        Some(age) if age < 24 * HOUR_MILLIS => 0.2,
+        Some(age) if age < 7 * 24 * HOUR_MILLIS => 0.1,
+        _ => 0.0,
+    };
+    let pin = if pinned { 0.3 } else { 0.0 };
+    frequency + recency + pin
+}
+
+fn rank(candidates: &[Candidate], query: &str, now: i64, limit: usize) -> Vec<PaletteItem> {
+    let query = query.trim();
+    // Without a query the pinned hosts come first, in pin order
+    let mut items: Vec<(usize, PaletteItem)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let title = fuzzy_match(query, &candidate.title);
+            let keyword = candidate
+                .keywords
+                .iter()
+                .filter_map(|k| fuzzy_match(query, k))
+                .map(|(score, _)| score / 2)
+                .max();
+            let code_hit = !query.is_empty()
+                && candidate
+                    .short_code
+                    .as_deref()
+                    .is_some_and(|code| code.eq_ignore_ascii_case(query));
+            let (text_score, highlights) = match (title, keyword) {
+                _ if code_hit => (SHORT_CODE_SCORE, Vec::new()),
+                (Some((t, _)), Some(k)) if k > t => (k, Vec::new()),
+                (Some((t, positions)), _) => (t, positions),
+                (None, Some(k)) => (k, Vec::new()),
+                (None, None) => return None,
+            };
+            // Keep every match above zero so usage still orders weak matches
+            let base = (text_score.max(0) + 1) as f64;
+            let pin = candidate
+                .pin
+                .filter(|_| query.is_empty())
+                .unwrap_or(usize::MAX);
+            let item = PaletteItem {
+                kind: candidate.kind,
+                id: candidate.id.clone(),
+                title: candidate.title.clone(),
+                subtitle: candidate.subtitle.clone(),
+                short_code: candidate.short_code.clone(),
+                score: base
+                    * usage_weight(
+                        candidate.pin.is_some(),
+                        candidate.use_count,
+                        candidate.last_used,
+                        now,
+                    ),
+                highlights,
+            };
+            Some((pin, item))
+        })
+        .collect();
+    items.sort_by(|(pin_a, a), (pin_b, b)| {
+        pin_a
+            .cmp(pin_b)
+            .then_with(|| b.score.total_cmp(&a.score))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    items.truncate(limit);
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(alias: &str, use_count: u32) -> Candidate {
+        Candidate {
+            kind: PaletteItemKind::Host,
+            id: alias.to_string(),
+            title: alias.to_string(),
+            subtitle: None,
+            keywords: Vec::new(),
+            short_code: None,
+            pin: None,
+            use_count,
+            last_used: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        let (score, positions) = fuzzy_match("pdb", "prod-db").unwrap();
+        assert_eq!(positions, vec![0, 3, 6]);
+        assert!(score > fuzzy_match("pdb", "xprodxdxb").unwrap().0);
+        assert!(fuzzy_match("dbp", "prod-db").is_none());
+        assert_eq!(fuzzy_match("WEB", "web").unwrap().1, vec![0, 1, 2]);
+        assert!(fuzzy_match("web", "web").unwrap().0 > fuzzy_match("web", "webserver").unwrap().0);
+    }
+
+    #[test]
+    fn test_rank_weights_usage() {
+        let candidates = vec![host("web-1", 0), host("web-2", 40), host("db", 100)];
+        let ranked = rank(&candidates, "web", 0, 10);
+        let ids: Vec<&str> = ranked.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["web-2", "web-1"]);
+
+        // Empty query lists everything by usage
+        let ranked = rank(&candidates, "", 0, 2);
+        let ids: Vec<&str> = ranked.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["db", "web-2"]);
+    }
+
+    #[test]
+    fn test_rank_matches_keywords() {
+        let mut tagged = host("alpha", 0);
+        tagged.keywords = vec!["10.0.0.5".to_string(), "staging".to_string()];
+        let candidates = vec![tagged, host("beta", 0)];
+        let ranked = rank(&candidates, "staging", 0, 10);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].id, "alpha");
+        assert!(ranked[0].highlights.is_empty());
+    }
+
+    #[test]
+    fn test_rank_short_code_first() {
+        let mut coded = host("db-primary", 0);
+        coded.short_code = Some("p1".to_string());
+        let candidates = vec![host("p1-staging", 100), coded];
+        let ranked = rank(&candidates, "P1", 0, 10);
+        let ids: Vec<&str> = ranked.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["db-primary", "p1-staging"]);
+        assert_eq!(ranked[0].short_code.as_deref(), Some("p1"));
+
+        // Only the exact code counts
+        let ranked = rank(&candidates, "p", 0, 10);
+        assert_eq!(ranked[0].id, "p1-staging");
+    }
+
+    #[test]
+    fn test_rank_pinned_hosts() {
+        let mut first = host("zeta", 0);
+        first.pin = Some(0);
+        let mut second = host("alpha", 0);
+        second.pin = Some(1);
+        let candidates = vec![host("busy", 50), second, first];
+
+        let ranked = rank(&candidates, "", 0, 10);
+        let ids: Vec<&str> = ranked.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["zeta", "alpha", "busy"]);
+
+        // With a query pinning is a boost, not an override
+        let ranked = rank(&candidates, "a", 0, 10);
+        assert!(
+            ranked.iter().position(|i| i.id == "alpha")
+                < ranked.iter().position(|i| i.id == "zeta")
+        );
+    }
+}
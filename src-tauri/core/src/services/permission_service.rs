@@ -0,0 +1,1062 @@
+use crate::models::{LocalizedMessage, SshBuddyError, SshResult};
+use crate::services::doctor_service::{DoctorCheck, DoctorFinding, FindingSeverity};
+use crate::services::key_manager::KeyManager;
+use crate::services::onboarding_service::{OnboardingService, OnboardingStep};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::settings_service::SettingsService;
+use crate::utils::ssh_dir;
+#[cfg(windows)]
+use crate::utils::windows_acl;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// Which modes the permission checks accept
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionProfile {
+    /// Keys 600, SSH directory 700
+    #[default]
+    Strict,
+    /// Also group-readable keys (640) and directory (750), for shared service accounts
+    Relaxed,
+    /// Strict, except for the paths with an override
+    Custom,
+}
+
+impl PermissionProfile {
+    pub fn id(self) -> &'static str {
+        match self {
+            PermissionProfile::Strict => "strict",
+            PermissionProfile::Relaxed => "relaxed",
+            PermissionProfile::Custom => "custom",
+        }
+    }
+}
+
+/// Mode required for one path (custom profile)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionOverride {
+    /// Absolute, `~/...`, or relative to the SSH directory
+    pub path: String,
+    /// Octal, e.g. "640"
+    pub mode: String,
+}
+
+/// Permissions section of the settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PermissionPolicy {
+    pub profile: PermissionProfile,
+    pub overrides: Vec<PermissionOverride>,
+}
+
+impl PermissionPolicy {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        for item in &self.overrides {
+            if item.path.trim().is_empty() {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "Permission overrides need a path".to_string(),
+                });
+            }
+            parse_mode(&item.mode)?;
+        }
+        Ok(())
+    }
+
+    /// Modes accepted for a key file or the SSH directory; the first is what a fix sets
+    #[cfg_attr(not(unix), allow(dead_code))]
+    fn accepted_modes(&self, path: &Path, is_dir: bool) -> Vec<u32> {
+        if self.profile == PermissionProfile::Custom {
+            let mode = self
+                .overrides
+                .iter()
+                .find(|o| resolve_override_path(&o.path).as_deref() == Some(path))
+                .and_then(|o| parse_mode(&o.mode).ok());
+            if let Some(mode) = mode {
+                return vec![mode];
+            }
+        }
+        match (self.profile, is_dir) {
+            (PermissionProfile::Relaxed, false) => vec![0o600, 0o640],
+            (PermissionProfile::Relaxed, true) => vec![0o700, 0o750],
+            (_, false) => vec![0o600],
+            (_, true) => vec![0o700],
+        }
+    }
+}
+
+/// Octal mode of an override; never writable by group or others
+fn parse_mode(mode: &str) -> SshResult<u32> {
+    let invalid = || SshBuddyError::InvalidOption {
+        message: format!("Invalid permission mode: {}", mode),
+    };
+    let value = u32::from_str_radix(mode.trim(), 8).map_err(|_| invalid())?;
+    if value > 0o777 {
+        return Err(invalid());
+    }
+    if value & 0o022 != 0 {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!("Mode {} lets other users modify the file", mode.trim()),
+        });
+    }
+    Ok(value)
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+fn resolve_override_path(path: &str) -> Option<PathBuf> {
+    let path = path.trim();
+    if let Some(rest) = path.strip_prefix("~/") {
+        return dirs::home_dir().map(|home| home.join(rest));
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        ssh_dir().ok().map(|dir| dir.join(path))
+    }
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+fn format_mode(mode: u32) -> String {
+    format!("{:03o}", mode)
+}
+
+/// "600" or "600 or 640"
+#[cfg_attr(not(unix), allow(dead_code))]
+fn describe_modes(modes: &[u32]) -> String {
+    modes
+        .iter()
+        .map(|m| format_mode(*m))
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+/// Permission check result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionCheckResult {
+    pub is_valid: bool,
+    pub current_mode: Option<String>,
+    /// Mode a fix sets
+    pub expected_mode: String,
+    /// Every mode the policy accepts for this path
+    pub accepted_modes: Vec<String>,
+    pub profile: PermissionProfile,
+    /// How the permissions were read
+    pub backend: PermissionBackend,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
+}
+
+/// How file permissions are read and changed on this system
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionBackend {
+    /// Unix mode bits
+    Mode,
+    /// The icacls tool
+    Icacls,
+    /// The Win32 security API, when icacls is missing or blocked
+    Win32Acl,
+    /// Neither works (stripped-down Windows, locked-down policies)
+    Unavailable,
+}
+
+/// Permission checking support of this system
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionCapabilities {
+    pub backend: PermissionBackend,
+    /// Why the preferred tool isn't used
+    pub reason: Option<String>,
+}
+
+/// Permission fix result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionFixResult {
+    pub success: bool,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
+    pub new_mode: Option<String>,
+}
+
+/// Permission service
+pub struct PermissionService;
+
+impl PermissionService {
+    pub fn get_policy() -> PermissionPolicy {
+        SettingsService::get().permissions
+    }
+
+    pub async fn set_policy(policy: &PermissionPolicy) -> SshResult<()> {
+        policy.validate()?;
+        SettingsService::update(|settings| {
+            settings.permissions = policy.clone();
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[permission_service] Permission policy set to {} ({} override(s))",
+            policy.profile.id(),
+            policy.overrides.len()
+        );
+        Ok(())
+    }
+
+    /// How permissions are checked here; detected once (icacls, then the Win32 API)
+    pub fn capabilities() -> &'static PermissionCapabilities {
+        static CAPABILITIES: OnceLock<PermissionCapabilities> = OnceLock::new();
+        CAPABILITIES.get_or_init(detect_capabilities)
+    }
+
+    /// SSH directory and private keys that the policy doesn't allow, as doctor findings
+    pub async fn audit() -> SshResult<Vec<DoctorFinding>> {
+        let mut findings = Vec::new();
+        let ssh_dir = ssh_dir()?;
+        if let Some(folder) = synced_folder(&ssh_dir, &onedrive_roots()) {
+            // ACLs don't stop the sync client from uploading the keys
+            findings.push(DoctorFinding {
+                id: "permissions.sshDirSynced".to_string(),
+                check: DoctorCheck::Permissions,
+                severity: FindingSeverity::Warning,
+                subject: None,
+                message: LocalizedMessage::new("permission.sshDirSynced")
+                    .with("path", ssh_dir.display())
+                    .with("folder", folder.display()),
+            });
+        }
+        let capabilities = Self::capabilities();
+        if capabilities.backend == PermissionBackend::Unavailable {
+            findings.push(DoctorFinding {
+                id: "permissions.checkUnavailable".to_string(),
+                check: DoctorCheck::Permissions,
+                severity: FindingSeverity::Info,
+                subject: None,
+                message: unavailable_message("permission.checkUnavailable", capabilities),
+            });
+            return Ok(findings);
+        }
+        let dir = Self::check_ssh_dir_permissions().await?;
+        if !dir.is_valid && dir.current_mode.is_some() {
+            findings.push(DoctorFinding {
+                id: "permissions.sshDirTooOpen".to_string(),
+                check: DoctorCheck::Permissions,
+                severity: FindingSeverity::Warning,
+                subject: None,
+                message: dir.message,
+            });
+        }
+        for key in KeyManager::new()?.list_keys().await? {
+            if !Path::new(&key.private_key_path).exists() {
+                continue;
+            }
+            let check = Self::check_key_permissions(&key.private_key_path).await?;
+            if !check.is_valid {
+                findings.push(DoctorFinding {
+                    id: "permissions.keyTooOpen".to_string(),
+                    check: DoctorCheck::Permissions,
+                    severity: FindingSeverity::Error,
+                    subject: Some(key.name),
+                    message: check.message,
+                });
+            }
+        }
+        Ok(findings)
+    }
+
+    /// Check key file permissions
+    #[cfg(unix)]
+    pub async fn check_key_permissions(key_path: &str) -> SshResult<PermissionCheckResult> {
+        let path = Path::new(key_path);
+
+        if !path.exists() {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_path.to_string(),
+            });
+        }
+
+        let metadata = std::fs::metadata(path).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to read file metadata: {}", e),
+        })?;
+
+        let file_mode = metadata.permissions().mode() & 0o777; // Only get file permission bits
+        let mode_str = format_mode(file_mode);
+        let policy = Self::get_policy();
+        let accepted = policy.accepted_modes(path, false);
+        let is_valid = accepted.contains(&file_mode);
+
+        Ok(PermissionCheckResult {
+            is_valid,
+            current_mode: Some(mode_str.clone()),
+            expected_mode: format_mode(accepted[0]),
+            accepted_modes: accepted.iter().map(|m| format_mode(*m)).collect(),
+            profile: policy.profile,
+            backend: PermissionBackend::Mode,
+            message: if !is_valid {
+                LocalizedMessage::new("permission.keyTooOpen")
+                    .with("mode", &mode_str)
+                    .with("expected", describe_modes(&accepted))
+            } else if file_mode == 0o600 {
+                LocalizedMessage::new("permission.keyValid")
+            } else {
+                LocalizedMessage::new("permission.keyAllowedByPolicy")
+                    .with("mode", &mode_str)
+                    .with("profile", policy.profile.id())
+            },
+        })
+    }
+
+    #[cfg(windows)]
+    pub async fn check_key_permissions(key_path: &str) -> SshResult<PermissionCheckResult> {
+        let path = Path::new(key_path);
+
+        if !path.exists() {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_path.to_string(),
+            });
+        }
+
+        // A properly secured key should only have the current user with access
+        let capabilities = Self::capabilities();
+        let Some(grants) = read_grants(capabilities, path)? else {
+            return Ok(unchecked_result(capabilities, "permission.keyCheckFailed"));
+        };
+        let (user_has_access, has_other_users) = acl_access(&grants, &current_account());
+        let is_valid = user_has_access && !has_other_users;
+
+        Ok(PermissionCheckResult {
+            is_valid,
+            current_mode: Some("ACL".to_string()),
+            expected_mode: "User only".to_string(),
+            accepted_modes: vec!["User only".to_string()],
+            profile: PermissionProfile::Strict,
+            backend: capabilities.backend,
+            message: LocalizedMessage::new(if is_valid {
+                "permission.keyValidAcl"
+            } else if has_other_users {
+                "permission.keySharedAcl"
+            } else {
+                "permission.keyUnverified"
+            }),
+        })
+    }
+
+    /// Fix key file permissions
+    #[cfg(unix)]
+    pub async fn fix_key_permissions(key_path: &str) -> SshResult<PermissionFixResult> {
+        ReadOnlyMode::ensure_writable("fix key permissions")?;
+        let path = Path::new(key_path);
+
+        if !path.exists() {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_path.to_string(),
+            });
+        }
+
+        // Modes the policy accepts are left alone; anything else gets the policy's mode
+        let accepted = Self::get_policy().accepted_modes(path, false);
+        let current = std::fs::metadata(path)?.permissions().mode() & 0o777;
+        if !accepted.contains(&current) {
+            let permissions = std::fs::Permissions::from_mode(accepted[0]);
+            std::fs::set_permissions(path, permissions).map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to set permissions: {}", e),
+            })?;
+        }
+
+        // Verify new permissions
+        let metadata = std::fs::metadata(path).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to verify permissions: {}", e),
+        })?;
+
+        let new_mode = metadata.permissions().mode() & 0o777;
+        let mode_str = format_mode(new_mode);
+
+        Ok(PermissionFixResult {
+            success: accepted.contains(&new_mode),
+            message: LocalizedMessage::new("permission.keyModeSet").with("mode", &mode_str),
+            new_mode: Some(mode_str),
+        })
+    }
+
+    #[cfg(windows)]
+    pub async fn fix_key_permissions(key_path: &str) -> SshResult<PermissionFixResult> {
+        ReadOnlyMode::ensure_writable("fix key permissions")?;
+        let path = Path::new(key_path);
+
+        if !path.exists() {
+            return Err(SshBuddyError::KeyNotFound {
+                path: key_path.to_string(),
+            });
+        }
+
+        let current_user = current_account();
+        match restrict_to_user(Self::capabilities(), path, &current_user, false) {
+            Ok(()) => {
+                tracing::info!(
+                    "[permission_service] Windows: Fixed key permissions for {}",
+                    key_path
+                );
+                Ok(PermissionFixResult {
+                    success: true,
+                    message: LocalizedMessage::new("permission.keyRestricted")
+                        .with("user", &current_user.account),
+                    new_mode: Some("User only".to_string()),
+                })
+            }
+            Err(message) => {
+                tracing::warn!(
+                    "[permission_service] Windows: Failed to fix key permissions: {}",
+                    message.text
+                );
+                Ok(PermissionFixResult {
+                    success: false,
+                    message,
+                    new_mode: None,
+                })
+            }
+        }
+    }
+
+    /// Check SSH directory permissions
+    #[cfg(unix)]
+    pub async fn check_ssh_dir_permissions() -> SshResult<PermissionCheckResult> {
+        let ssh_dir = ssh_dir()?;
+        let policy = Self::get_policy();
+        let accepted = policy.accepted_modes(&ssh_dir, true);
+        let expected_mode = format_mode(accepted[0]);
+        let accepted_modes: Vec<String> = accepted.iter().map(|m| format_mode(*m)).collect();
+
+        if !ssh_dir.exists() {
+            return Ok(PermissionCheckResult {
+                is_valid: false,
+                current_mode: None,
+                expected_mode,
+                accepted_modes,
+                profile: policy.profile,
+                backend: PermissionBackend::Mode,
+                message: LocalizedMessage::new("permission.sshDirMissing"),
+            });
+        }
+
+        let metadata = std::fs::metadata(&ssh_dir).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to read directory metadata: {}", e),
+        })?;
+
+        let dir_mode = metadata.permissions().mode() & 0o777;
+        let mode_str = format_mode(dir_mode);
+        let is_valid = accepted.contains(&dir_mode);
+
+        Ok(PermissionCheckResult {
+            is_valid,
+            current_mode: Some(mode_str.clone()),
+            expected_mode,
+            accepted_modes,
+            profile: policy.profile,
+            backend: PermissionBackend::Mode,
+            message: if !is_valid {
+                LocalizedMessage::new("permission.sshDirTooOpen")
+                    .with("mode", &mode_str)
+                    .with("expected", describe_modes(&accepted))
+            } else if dir_mode == 0o700 {
+                LocalizedMessage::new("permission.sshDirValid")
+            } else {
+                LocalizedMessage::new("permission.sshDirAllowedByPolicy")
+                    .with("mode", &mode_str)
+                    .with("profile", policy.profile.id())
+            },
+        })
+    }
+
+    #[cfg(windows)]
+    pub async fn check_ssh_dir_permissions() -> SshResult<PermissionCheckResult> {
+        let ssh_dir = ssh_dir()?;
+        let capabilities = Self::capabilities();
+
+        if !ssh_dir.exists() {
+            return Ok(PermissionCheckResult {
+                is_valid: false,
+                current_mode: None,
+                expected_mode: "User only".to_string(),
+                accepted_modes: vec!["User only".to_string()],
+                profile: PermissionProfile::Strict,
+                backend: capabilities.backend,
+                message: LocalizedMessage::new("permission.sshDirMissing"),
+            });
+        }
+
+        if read_grants(capabilities, &ssh_dir)?.is_none() {
+            return Ok(unchecked_result(
+                capabilities,
+                "permission.sshDirCheckFailed",
+            ));
+        }
+
+        // For Windows, we consider the directory permissions valid if its ACL is readable
+        // More detailed ACL checking could be added if needed
+        Ok(PermissionCheckResult {
+            is_valid: true,
+            current_mode: Some("ACL".to_string()),
+            expected_mode: "User only".to_string(),
+            accepted_modes: vec!["User only".to_string()],
+            profile: PermissionProfile::Strict,
+            backend: capabilities.backend,
+            message: LocalizedMessage::new("permission.sshDirAcl"),
+        })
+    }
+
+    /// Fix SSH directory permissions
+    #[cfg(unix)]
+    pub async fn fix_ssh_dir_permissions() -> SshResult<PermissionFixResult> {
+        ReadOnlyMode::ensure_writable("fix ~/.ssh permissions")?;
+        let ssh_dir = ssh_dir()?;
+
+        if !ssh_dir.exists() {
+            // Create directory
+            std::fs::create_dir_all(&ssh_dir).map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to create SSH directory: {}", e),
+            })?;
+        }
+
+        let accepted = Self::get_policy().accepted_modes(&ssh_dir, true);
+        let mut mode = std::fs::metadata(&ssh_dir)?.permissions().mode() & 0o777;
+        if !accepted.contains(&mode) {
+            mode = accepted[0];
+            let permissions = std::fs::Permissions::from_mode(mode);
+            std::fs::set_permissions(&ssh_dir, permissions).map_err(|e| {
+                SshBuddyError::IoError {
+                    message: format!("Failed to set directory permissions: {}", e),
+                }
+            })?;
+        }
+        OnboardingService::complete(OnboardingStep::FixSshDir).await;
+
+        Ok(PermissionFixResult {
+            success: true,
+            message: LocalizedMessage::new("permission.sshDirModeSet")
+                .with("mode", format_mode(mode)),
+            new_mode: Some(format_mode(mode)),
+        })
+    }
+
+    #[cfg(windows)]
+    pub async fn fix_ssh_dir_permissions() -> SshResult<PermissionFixResult> {
+        ReadOnlyMode::ensure_writable("fix ~/.ssh permissions")?;
+        let ssh_dir = ssh_dir()?;
+
+        // Create directory if it doesn't exist
+        if !ssh_dir.exists() {
+            std::fs::create_dir_all(&ssh_dir).map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to create SSH directory: {}", e),
+            })?;
+        }
+
+        let current_user = current_account();
+        match restrict_to_user(Self::capabilities(), &ssh_dir, &current_user, true) {
+            Ok(()) => {
+                tracing::info!(
+                    "[permission_service] Windows: Fixed SSH directory permissions for {:?}",
+                    ssh_dir
+                );
+                OnboardingService::complete(OnboardingStep::FixSshDir).await;
+                Ok(PermissionFixResult {
+                    success: true,
+                    message: LocalizedMessage::new("permission.sshDirRestricted")
+                        .with("user", &current_user.account),
+                    new_mode: Some("User only".to_string()),
+                })
+            }
+            Err(message) => {
+                tracing::warn!(
+                    "[permission_service] Windows: Failed to fix SSH directory permissions: {}",
+                    message.text
+                );
+                Ok(PermissionFixResult {
+                    success: false,
+                    message,
+                    new_mode: None,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn detect_capabilities() -> PermissionCapabilities {
+    PermissionCapabilities {
+        backend: PermissionBackend::Mode,
+        reason: None,
+    }
+}
+
+#[cfg(windows)]
+fn detect_capabilities() -> PermissionCapabilities {
+    let probe = std::env::temp_dir();
+    let icacls = match std::process::Command::new("icacls").arg(&probe).output() {
+        Ok(output) if output.status.success() => {
+            return PermissionCapabilities {
+                backend: PermissionBackend::Icacls,
+                reason: None,
+            }
+        }
+        Ok(output) => format!(
+            "icacls failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => format!("icacls can't run: {}", e),
+    };
+    let capabilities = match windows_acl::read_grants(&probe) {
+        Ok(_) => PermissionCapabilities {
+            backend: PermissionBackend::Win32Acl,
+            reason: Some(icacls),
+        },
+        Err(e) => PermissionCapabilities {
+            backend: PermissionBackend::Unavailable,
+            reason: Some(format!("{}; Win32 ACL API failed: {}", icacls, e)),
+        },
+    };
+    tracing::warn!(
+        "[permission_service] Checking permissions with {:?}: {}",
+        capabilities.backend,
+        capabilities.reason.as_deref().unwrap_or_default()
+    );
+    capabilities
+}
+
+/// "Cannot check/change on this system" message with the detection details
+fn unavailable_message(key: &str, capabilities: &PermissionCapabilities) -> LocalizedMessage {
+    LocalizedMessage::new(key).with(
+        "reason",
+        capabilities
+            .reason
+            .as_deref()
+            .unwrap_or("no permission API"),
+    )
+}
+
+/// Result of a check that couldn't read the ACL; `failed_key` when the backend works
+/// but this path couldn't be read
+#[cfg(windows)]
+fn unchecked_result(
+    capabilities: &PermissionCapabilities,
+    failed_key: &str,
+) -> PermissionCheckResult {
+    PermissionCheckResult {
+        is_valid: false,
+        current_mode: None,
+        expected_mode: "User only".to_string(),
+        accepted_modes: vec!["User only".to_string()],
+        profile: PermissionProfile::Strict,
+        backend: capabilities.backend,
+        message: if capabilities.backend == PermissionBackend::Unavailable {
+            unavailable_message("permission.checkUnavailable", capabilities)
+        } else {
+            LocalizedMessage::new(failed_key)
+        },
+    }
+}
+
+/// Accounts granted access to `path`; None when the ACL can't be read
+#[cfg(windows)]
+fn read_grants(
+    capabilities: &PermissionCapabilities,
+    path: &Path,
+) -> SshResult<Option<Vec<AclGrant>>> {
+    match capabilities.backend {
+        PermissionBackend::Icacls => {
+            let output = std::process::Command::new("icacls")
+                .arg(path)
+                .output()
+                .map_err(|e| SshBuddyError::IoError {
+                    message: format!("Failed to run icacls: {}", e),
+                })?;
+            Ok(output.status.success().then(|| {
+                parse_icacls(
+                    &String::from_utf8_lossy(&output.stdout),
+                    &path.to_string_lossy(),
+                )
+            }))
+        }
+        PermissionBackend::Win32Acl => match windows_acl::read_grants(path) {
+            Ok(grants) => Ok(Some(
+                grants
+                    .into_iter()
+                    .map(|g| AclGrant {
+                        account: g.account,
+                        sid: g.sid,
+                        privileged: g.privileged,
+                    })
+                    .collect(),
+            )),
+            Err(e) => {
+                tracing::warn!(
+                    "[permission_service] Failed to read ACL of {:?}: {}",
+                    path,
+                    e
+                );
+                Ok(None)
+            }
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Account the app runs as: SID and `DOMAIN\name` from the process token, the
+/// plain user name when the token can't be read
+#[cfg(windows)]
+fn current_account() -> AccountId {
+    match windows_acl::current_user() {
+        Ok(user) => AccountId {
+            sid: Some(user.sid),
+            account: user.account,
+        },
+        Err(e) => {
+            tracing::warn!(
+                "[permission_service] Failed to read the current user's SID: {}",
+                e
+            );
+            AccountId {
+                sid: None,
+                account: whoami::username(),
+            }
+        }
+    }
+}
+
+/// Give `user` full control of `path` and remove every other entry; granted by SID,
+/// so domain and Azure AD accounts (`AzureAD\Jane Doe`) work too
+#[cfg(windows)]
+fn restrict_to_user(
+    capabilities: &PermissionCapabilities,
+    path: &Path,
+    user: &AccountId,
+    is_dir: bool,
+) -> Result<(), LocalizedMessage> {
+    let failed_key = if is_dir {
+        "permission.sshDirSetFailed"
+    } else {
+        "permission.keySetFailed"
+    };
+    match capabilities.backend {
+        PermissionBackend::Icacls => {
+            // `*SID` needs no name lookup and survives spaces in the account name
+            let trustee = match &user.sid {
+                Some(sid) => format!("*{}", sid),
+                None => user.account.clone(),
+            };
+            let output = std::process::Command::new("icacls")
+                .arg(path)
+                .args([
+                    "/inheritance:r", // Remove inheritance
+                    "/grant:r",
+                    &format!("{}:F", trustee), // Only give current user full control
+                ])
+                .output()
+                .map_err(|e| LocalizedMessage::new(failed_key).with("error", e))?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(LocalizedMessage::new(failed_key)
+                    .with("error", String::from_utf8_lossy(&output.stderr)))
+            }
+        }
+        PermissionBackend::Win32Acl => match &user.sid {
+            Some(sid) => windows_acl::restrict_to_sid(path, sid, is_dir)
+                .map_err(|e| LocalizedMessage::new(failed_key).with("error", e)),
+            None => Err(LocalizedMessage::new(failed_key)
+                .with("error", "the current user's SID is unknown")),
+        },
+        _ => Err(unavailable_message(
+            "permission.fixUnavailable",
+            capabilities,
+        )),
+    }
+}
+
+/// Account granted access in a Windows ACL
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone, PartialEq)]
+struct AclGrant {
+    /// `DOMAIN\name` (icacls and the Win32 API both print the domain)
+    account: String,
+    /// Only known through the Win32 API, or when icacls can't resolve the SID
+    sid: Option<String>,
+    /// Administrators or SYSTEM, usually acceptable
+    privileged: bool,
+}
+
+/// Account to compare ACL entries with
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone, PartialEq)]
+struct AccountId {
+    sid: Option<String>,
+    account: String,
+}
+
+/// Grants listed by `icacls <path>`: the first line starts with the path, then one
+/// `ACCOUNT:(rights)` per line; account names may contain spaces
+#[cfg(any(windows, test))]
+fn parse_icacls(stdout: &str, path: &str) -> Vec<AclGrant> {
+    stdout
+        .lines()
+        .map(|line| line.strip_prefix(path).unwrap_or(line).trim())
+        .filter(|line| !line.is_empty() && !line.starts_with("Successfully"))
+        .filter_map(|line| {
+            let (account, _) = line.split_once(":(")?;
+            let account = account.trim().to_string();
+            let privileged = account.eq_ignore_ascii_case("BUILTIN\\Administrators")
+                || account.eq_ignore_ascii_case("NT AUTHORITY\\SYSTEM");
+            // icacls prints accounts it can't resolve as their SID
+            let sid = account.starts_with("S-1-").then(|| account.clone());
+            Some(AclGrant {
+                account,
+                sid,
+                privileged,
+            })
+        })
+        .collect()
+}
+
+/// Same account: by SID when both are known, else by the full `DOMAIN\name`; a bare
+/// name only matches the name part of a qualified one
+#[cfg(any(windows, test))]
+fn same_account(grant: &AclGrant, user: &AccountId) -> bool {
+    if let (Some(a), Some(b)) = (&grant.sid, &user.sid) {
+        return a.eq_ignore_ascii_case(b);
+    }
+    let (a, b) = (grant.account.to_lowercase(), user.account.to_lowercase());
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    match (a.split_once('\\'), b.split_once('\\')) {
+        (Some(_), Some(_)) | (None, None) => a == b,
+        (Some((_, name)), None) => name == b,
+        (None, Some((_, name))) => a == name,
+    }
+}
+
+/// (current user has access, another non-privileged account has access)
+#[cfg(any(windows, test))]
+fn acl_access(grants: &[AclGrant], user: &AccountId) -> (bool, bool) {
+    let user_has_access = grants.iter().any(|g| same_account(g, user));
+    let has_other_users = grants
+        .iter()
+        .any(|g| !g.privileged && !same_account(g, user));
+    (user_has_access, has_other_users)
+}
+
+/// Cloud-synced folder holding `path`: below one of `roots` (the OneDrive folders from
+/// the environment) or a folder named like OneDrive's ("OneDrive - Contoso",
+/// "OneDrive-Personal")
+fn synced_folder(path: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    if let Some(root) = roots.iter().find(|root| path.starts_with(root)) {
+        return Some(root.clone());
+    }
+    let mut prefix = PathBuf::new();
+    for component in path.components() {
+        prefix.push(component);
+        if component
+            .as_os_str()
+            .to_string_lossy()
+            .to_lowercase()
+            .starts_with("onedrive")
+        {
+            return Some(prefix);
+        }
+    }
+    None
+}
+
+/// OneDrive folders set by the OneDrive client
+fn onedrive_roots() -> Vec<PathBuf> {
+    ["OneDrive", "OneDriveCommercial", "OneDriveConsumer"]
+        .iter()
+        .filter_map(|name| std::env::var_os(name).filter(|v| !v.is_empty()))
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_modes() {
+        let key = Path::new("/home/me/.ssh/id_ed25519");
+        let strict = PermissionPolicy::default();
+        assert_eq!(strict.accepted_modes(key, false), vec![0o600]);
+        assert_eq!(strict.accepted_modes(key, true), vec![0o700]);
+
+        let relaxed = PermissionPolicy {
+            profile: PermissionProfile::Relaxed,
+            overrides: Vec::new(),
+        };
+        assert_eq!(relaxed.accepted_modes(key, false), vec![0o600, 0o640]);
+        assert_eq!(
+            describe_modes(&relaxed.accepted_modes(key, false)),
+            "600 or 640"
+        );
+    }
+
+    #[test]
+    fn test_custom_overrides() {
+        let policy = PermissionPolicy {
+            profile: PermissionProfile::Custom,
+            overrides: vec![PermissionOverride {
+                path: "/srv/deploy/.ssh/id_deploy".to_string(),
+                mode: "640".to_string(),
+            }],
+        };
+        assert!(policy.validate().is_ok());
+        assert_eq!(
+            policy.accepted_modes(Path::new("/srv/deploy/.ssh/id_deploy"), false),
+            vec![0o640]
+        );
+        assert_eq!(
+            policy.accepted_modes(Path::new("/srv/deploy/.ssh/id_other"), false),
+            vec![0o600]
+        );
+
+        // Overrides only count in the custom profile
+        let strict = PermissionPolicy {
+            profile: PermissionProfile::Strict,
+            ..policy
+        };
+        assert_eq!(
+            strict.accepted_modes(Path::new("/srv/deploy/.ssh/id_deploy"), false),
+            vec![0o600]
+        );
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("640").unwrap(), 0o640);
+        assert_eq!(parse_mode(" 400 ").unwrap(), 0o400);
+        assert!(parse_mode("660").is_err());
+        assert!(parse_mode("606").is_err());
+        assert!(parse_mode("1000").is_err());
+        assert!(parse_mode("rw").is_err());
+    }
+
+    #[test]
+    fn test_parse_icacls() {
+        let path = r"C:\Users\alice\.ssh\id_ed25519";
+        let stdout = format!(
+            "{} DESKTOP-1\\alice:(F)\n{}NT AUTHORITY\\SYSTEM:(F)\n{}BUILTIN\\Administrators:(F)\n\nSuccessfully processed 1 files; Failed processing 0 files\n",
+            path,
+            " ".repeat(path.len() + 1),
+            " ".repeat(path.len() + 1)
+        );
+        let grants = parse_icacls(&stdout, path);
+        assert_eq!(grants.len(), 3);
+        assert_eq!(grants[0].account, "DESKTOP-1\\alice");
+        assert!(!grants[0].privileged);
+        assert!(grants[1].privileged && grants[2].privileged);
+        assert_eq!(acl_access(&grants, &user(None, "alice")), (true, false));
+        assert_eq!(acl_access(&grants, &user(None, "bob")), (false, true));
+    }
+
+    fn user(sid: Option<&str>, account: &str) -> AccountId {
+        AccountId {
+            sid: sid.map(str::to_string),
+            account: account.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_acl_access_shared() {
+        let grant = |account: &str, privileged| AclGrant {
+            account: account.to_string(),
+            sid: None,
+            privileged,
+        };
+        let grants = vec![
+            grant("DESKTOP-1\\alice", false),
+            grant("BUILTIN\\Users", false),
+        ];
+        assert_eq!(acl_access(&grants, &user(None, "Alice")), (true, true));
+        // A SID that no longer resolves counts as another account
+        assert_eq!(
+            acl_access(&[grant("", false)], &user(None, "alice")),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn test_acl_access_domain_accounts() {
+        let grant = |account: &str, sid: Option<&str>| AclGrant {
+            account: account.to_string(),
+            sid: sid.map(str::to_string),
+            privileged: false,
+        };
+        // Same name in another domain is another account
+        let grants = vec![grant("CONTOSO\\alice", None)];
+        assert_eq!(
+            acl_access(&grants, &user(None, "FABRIKAM\\alice")),
+            (false, true)
+        );
+        assert_eq!(
+            acl_access(&grants, &user(None, "contoso\\Alice")),
+            (true, false)
+        );
+
+        // Azure AD names have spaces and don't match the profile folder name
+        let stdout = "C:\\Users\\JaneDoe\\.ssh AzureAD\\Jane Doe:(OI)(CI)(F)\n";
+        let grants = parse_icacls(stdout, "C:\\Users\\JaneDoe\\.ssh");
+        assert_eq!(grants[0].account, "AzureAD\\Jane Doe");
+        assert_eq!(
+            acl_access(&grants, &user(None, "AzureAD\\Jane Doe")),
+            (true, false)
+        );
+        assert_eq!(acl_access(&grants, &user(None, "JaneDoe")), (false, true));
+
+        // SIDs win over names, and unresolved icacls entries carry the SID
+        let sid = "S-1-12-1-1111-2222-3333-4444";
+        let grants = parse_icacls(&format!("key {}:(F)\n", sid), "key");
+        assert_eq!(grants[0].sid.as_deref(), Some(sid));
+        assert_eq!(
+            acl_access(&grants, &user(Some(sid), "AzureAD\\Jane Doe")),
+            (true, false)
+        );
+        let grants = vec![grant("AzureAD\\Jane Doe", Some("S-1-12-1-9"))];
+        assert_eq!(
+            acl_access(&grants, &user(Some(sid), "AzureAD\\Jane Doe")),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn test_synced_folder() {
+        let roots = vec![PathBuf::from("/c/Users/alice/OneDrive - Contoso")];
+        assert_eq!(
+            synced_folder(Path::new("/c/Users/alice/OneDrive - Contoso/.ssh"), &roots),
+            Some(roots[0].clone())
+        );
+        // Redirected folders are found by name without the environment
+        assert_eq!(
+            synced_folder(Path::new("/d/OneDrive-Personal/home/.ssh"), &[]),
+            Some(PathBuf::from("/d/OneDrive-Personal"))
+        );
+        assert_eq!(
+            synced_folder(Path::new("/c/Users/alice/.ssh"), &roots),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unavailable_message() {
+        let capabilities = PermissionCapabilities {
+            backend: PermissionBackend::Unavailable,
+            reason: Some("icacls can't run: not found".to_string()),
+        };
+        let message = unavailable_message("permission.checkUnavailable", &capabilities);
+        assert_eq!(message.key, "permission.checkUnavailable");
+        assert!(message.text.contains("icacls can't run"));
+    }
+}
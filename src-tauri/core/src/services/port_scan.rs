@@ -0,0 +1,383 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::utils::{
+    banner_software, connect_happy_eyeballs, resolve_addresses, AddressFamily,
+    CONNECTION_ATTEMPT_DELAY,
+};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Semaphore;
+use tokio::time::{interval, timeout, Duration, MissedTickBehavior};
+
+/// Alternate ports commonly used by hardened SSH servers
+pub const DEFAULT_ALT_SSH_PORTS: &[u16] = &[2222, 2022, 22022];
+
+/// Per-port probe timeout (connect + banner)
+const PROBE_TIMEOUT_SECS: u64 = 3;
+
+/// Max lines to read while looking for the SSH banner (servers may send text before it)
+const MAX_BANNER_LINES: usize = 5;
+
+/// Smallest subnet prefix accepted by a sweep (a /16 is 65536 addresses)
+const MIN_SWEEP_PREFIX: u8 = 16;
+
+/// Default and max probes started per second during a sweep
+const DEFAULT_SWEEP_RATE: u32 = 50;
+const MAX_SWEEP_RATE: u32 = 500;
+
+/// Default and max probes in flight during a sweep
+const DEFAULT_SWEEP_CONCURRENCY: usize = 64;
+const MAX_SWEEP_CONCURRENCY: usize = 256;
+
+/// Result of probing a single port
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PortProbe {
+    pub port: u16,
+    pub open: bool,
+    /// Identification string, e.g. "SSH-2.0-OpenSSH_9.6" (None if not an SSH server)
+    pub ssh_banner: Option<String>,
+}
+
+/// SSH port scan result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortScanResult {
+    pub hostname: String,
+    /// First port that answered with an SSH banner
+    pub ssh_port: Option<u16>,
+    pub probes: Vec<PortProbe>,
+}
+
+/// User-initiated subnet sweep
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubnetSweepRequest {
+    /// IPv4 range, e.g. "192.168.1.0/24"
+    pub cidr: String,
+    /// Ports to probe on every address (22 when empty)
+    #[serde(default)]
+    pub ports: Vec<u16>,
+    /// Probes started per second
+    pub rate_per_sec: Option<u32>,
+    pub concurrency: Option<usize>,
+}
+
+/// Address answering with an SSH banner
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepCandidate {
+    pub address: String,
+    pub port: u16,
+    pub banner: String,
+    /// Server software from the banner, e.g. "OpenSSH_9.6p1"
+    pub software: Option<String>,
+}
+
+/// Sweep progress, reported after every probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepProgress {
+    pub probed: usize,
+    pub total: usize,
+    /// Set when this probe found an SSH server
+    pub found: Option<SweepCandidate>,
+}
+
+/// Subnet sweep result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubnetSweepResult {
+    pub cidr: String,
+    /// Probes made (addresses x ports)
+    pub probed: usize,
+    /// Sorted by address and port
+    pub candidates: Vec<SweepCandidate>,
+}
+
+/// SSH port scanner for discovered/imported hosts
+pub struct PortScanService;
+
+impl PortScanService {
+    /// Find the SSH port of a host: port 22 first, then the alternate ports in parallel
+    /// `extra_ports` are user-configured ports probed in addition to the defaults
+    pub async fn scan_ssh_ports(hostname: &str, extra_ports: &[u16]) -> SshResult<PortScanResult> {
+        // Resolve once; the port is filled in per probe
+        let addrs = resolve_addresses(hostname, 22, AddressFamily::Any).await?;
+
+        let default_probe = Self::probe_port(&addrs, 22).await;
+        if default_probe.ssh_banner.is_some() {
+            tracing::info!("[port_scan] {} has SSH on port 22", hostname);
+            return Ok(PortScanResult {
+                hostname: hostname.to_string(),
+                ssh_port: Some(22),
+                probes: vec![default_probe],
+            });
+        }
+
+        let mut ports: Vec<u16> = Vec::new();
+        for &port in DEFAULT_ALT_SSH_PORTS.iter().chain(extra_ports) {
+            if port != 22 && !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+
+        let handles: Vec<_> = ports
+            .into_iter()
+            .map(|port| {
+                let addrs = addrs.clone();
+                tokio::spawn(async move { Self::probe_port(&addrs, port).await })
+            })
+            .collect();
+
+        let mut probes = vec![default_probe];
+        for handle in handles {
+            if let Ok(probe) = handle.await {
+                probes.push(probe);
+            }
+        }
+
+        let ssh_port = probes
+            .iter()
+            .find(|probe| probe.ssh_banner.is_some())
+            .map(|probe| probe.port);
+        tracing::info!(
+            "[port_scan] {} scanned {} ports, SSH port: {:?}",
+            hostname,
+            probes.len(),
+            ssh_port
+        );
+
+        Ok(PortScanResult {
+            hostname: hostname.to_string(),
+            ssh_port,
+            probes,
+        })
+    }
+
+    /// Probe every address of a subnet for SSH servers, at most `rate_per_sec` new
+    /// connections per second
+    pub async fn sweep_subnet<F>(
+        request: &SubnetSweepRequest,
+        on_progress: F,
+    ) -> SshResult<SubnetSweepResult>
+    where
+        F: Fn(SweepProgress) + Send + Sync + 'static,
+    {
+        let addresses = parse_cidr(&request.cidr)?;
+        let ports = if request.ports.is_empty() {
+            vec![22]
+        } else {
+            request.ports.clone()
+        };
+        let rate = request
+            .rate_per_sec
+            .unwrap_or(DEFAULT_SWEEP_RATE)
+            .clamp(1, MAX_SWEEP_RATE);
+        let concurrency = request
+            .concurrency
+            .unwrap_or(DEFAULT_SWEEP_CONCURRENCY)
+            .clamp(1, MAX_SWEEP_CONCURRENCY);
+        let total = addresses.len() * ports.len();
+        tracing::info!(
+            "[port_scan] Sweeping {} ({} probes, {}/s)",
+            request.cidr,
+            total,
+            rate
+        );
+
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let probed = Arc::new(AtomicUsize::new(0));
+        let on_progress = Arc::new(on_progress);
+        let mut ticker = interval(Duration::from_secs(1) / rate);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut handles = Vec::with_capacity(total);
+        for address in &addresses {
+            for &port in &ports {
+                ticker.tick().await;
+                let permit = semaphore.clone().acquire_owned().await;
+                let addr = SocketAddr::new(IpAddr::V4(*address), port);
+                let probed = probed.clone();
+                let on_progress = on_progress.clone();
+                handles.push(tokio::spawn(async move {
+                    let _permit = permit;
+                    let probe = Self::probe_port(&[addr], port).await;
+                    let found = probe.ssh_banner.map(|banner| SweepCandidate {
+                        address: addr.ip().to_string(),
+                        port,
+                        software: banner_software(&banner),
+                        banner,
+                    });
+                    on_progress(SweepProgress {
+                        probed: probed.fetch_add(1, Ordering::SeqCst) + 1,
+                        total,
+                        found: found.clone(),
+                    });
+                    found
+                }));
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for handle in handles {
+            if let Ok(Some(candidate)) = handle.await {
+                candidates.push(candidate);
+            }
+        }
+        // Awaited in spawn order, so candidates stay sorted by address and port
+        tracing::info!(
+            "[port_scan] Sweep of {} found {} SSH server(s)",
+            request.cidr,
+            candidates.len()
+        );
+        Ok(SubnetSweepResult {
+            cidr: request.cidr.clone(),
+            probed: total,
+            candidates,
+        })
+    }
+
+    /// Probe one port: connect and look for an SSH identification string
+    async fn probe_port(addrs: &[SocketAddr], port: u16) -> PortProbe {
+        let addrs: Vec<SocketAddr> = addrs
+            .iter()
+            .map(|addr| SocketAddr::new(addr.ip(), port))
+            .collect();
+
+        let connected = timeout(
+            Duration::from_secs(PROBE_TIMEOUT_SECS),
+            connect_happy_eyeballs(&addrs, CONNECTION_ATTEMPT_DELAY),
+        )
+        .await;
+        let stream = match connected {
+            Ok(Ok((stream, _))) => stream,
+            _ => {
+                return PortProbe {
+                    port,
+                    open: false,
+                    ssh_banner: None,
+                }
+            }
+        };
+
+        let ssh_banner = timeout(Duration::from_secs(PROBE_TIMEOUT_SECS), async {
+            let mut reader = BufReader::new(stream);
+            for _ in 0..MAX_BANNER_LINES {
+                let mut line = String::new();
+                match reader.read_line(&mut line).await {
+                    Ok(0) | Err(_) => return None,
+                    Ok(_) if line.starts_with("SSH-") => return Some(line.trim_end().to_string()),
+                    Ok(_) => continue,
+                }
+            }
+            None
+        })
+        .await
+        .unwrap_or(None);
+
+        PortProbe {
+            port,
+            open: true,
+            ssh_banner,
+        }
+    }
+}
+
+/// Host addresses of an IPv4 CIDR range (without network and broadcast address
+/// for prefixes up to /30)
+fn parse_cidr(cidr: &str) -> SshResult<Vec<Ipv4Addr>> {
+    let invalid = |reason: &str| SshBuddyError::InvalidOption {
+        message: format!("Invalid subnet {}: {}", cidr, reason),
+    };
+    let (address, prefix) = cidr.trim().split_once('/').unwrap_or((cidr.trim(), "32"));
+    let address: Ipv4Addr = address
+        .parse()
+        .map_err(|_| invalid("expected an IPv4 address"))?;
+    let prefix: u8 = prefix
+        .parse()
+        .ok()
+        .filter(|p| *p <= 32)
+        .ok_or_else(|| invalid("prefix must be 0-32"))?;
+    if prefix < MIN_SWEEP_PREFIX {
+        return Err(invalid(&format!(
+            "ranges larger than /{} aren't swept",
+            MIN_SWEEP_PREFIX
+        )));
+    }
+
+    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+    let network = u32::from(address) & mask;
+    let broadcast = network | !mask;
+    let (first, last) = if prefix <= 30 {
+        (network + 1, broadcast - 1)
+    } else {
+        (network, broadcast)
+    };
+    Ok((first..=last).map(Ipv4Addr::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    async fn serve_once(greeting: &'static [u8]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(greeting).await.unwrap();
+        });
+        port
+    }
+
+    fn localhost() -> Vec<SocketAddr> {
+        vec!["127.0.0.1:0".parse().unwrap()]
+    }
+
+    #[tokio::test]
+    async fn test_probe_detects_ssh_banner() {
+        let port = serve_once(b"Welcome\r\nSSH-2.0-OpenSSH_9.6\r\n").await;
+        let probe = PortScanService::probe_port(&localhost(), port).await;
+        assert!(probe.open);
+        assert_eq!(probe.ssh_banner.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_open_but_not_ssh() {
+        let port = serve_once(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        let probe = PortScanService::probe_port(&localhost(), port).await;
+        assert!(probe.open);
+        assert_eq!(probe.ssh_banner, None);
+    }
+
+    #[test]
+    fn test_parse_cidr() {
+        let hosts = parse_cidr("192.168.1.77/24").unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(hosts[253], Ipv4Addr::new(192, 168, 1, 254));
+        assert_eq!(parse_cidr("10.0.0.4/31").unwrap().len(), 2);
+        assert_eq!(
+            parse_cidr("10.0.0.4").unwrap(),
+            vec![Ipv4Addr::new(10, 0, 0, 4)]
+        );
+        assert!(parse_cidr("10.0.0.0/8").is_err());
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+        assert!(parse_cidr("fd00::/120").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_probe_closed_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let probe = PortScanService::probe_port(&localhost(), port).await;
+        assert!(!probe.open);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::algorithm_check::{split_list, AlgorithmCategory, AlgorithmCheckService};
+use crate::services::fleet_service::{FleetRequest, FleetService};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Hybrid post-quantum key exchange methods, preferred first
+/// (ML-KEM is the default since OpenSSH 10.0, sntrup761 since 9.0)
+pub const PQ_KEX_ALGORITHMS: &[&str] = &[
+    "mlkem768x25519-sha256",
+    "sntrup761x25519-sha512",
+    "sntrup761x25519-sha512@openssh.com",
+];
+
+/// Hosts probed at the same time unless the request says otherwise
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Upper bound for the requested concurrency
+const MAX_CONCURRENCY: usize = 32;
+
+/// Post-quantum readiness of one host
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PqStatus {
+    /// A hybrid PQ method will be negotiated
+    Hybrid,
+    /// Both sides support a PQ method but the host's KexAlgorithms doesn't enable it
+    DisabledByConfig,
+    /// The local client supports PQ kex but the server doesn't
+    ServerMissing,
+    /// The server offers PQ kex but the local client is too old
+    ClientMissing,
+    /// Neither side supports PQ kex
+    Unsupported,
+    /// The server couldn't be probed
+    Unreachable,
+}
+
+/// PQ support of the local OpenSSH client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientPqSupport {
+    pub version: String,
+    /// PQ methods the client supports
+    pub supported: Vec<String>,
+}
+
+/// PQ readiness of one host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostPqStatus {
+    pub host_alias: String,
+    pub status: PqStatus,
+    pub server_banner: Option<String>,
+    /// PQ methods the server offers
+    pub server_pq: Vec<String>,
+    /// Key exchange the client would negotiate
+    pub negotiated_kex: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Hosts to check (tag members and/or explicit aliases)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PqReadinessRequest {
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub host_aliases: Vec<String>,
+    pub concurrency: Option<usize>,
+}
+
+/// Fleet-wide PQ readiness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PqReadinessReport {
+    pub client: ClientPqSupport,
+    pub hosts: Vec<HostPqStatus>,
+    /// Hosts that negotiate a hybrid PQ method
+    pub ready: usize,
+    pub total: usize,
+}
+
+/// Reports hybrid post-quantum key exchange support of the client and the servers
+pub struct PqReadinessService;
+
+impl PqReadinessService {
+    /// PQ methods the local client supports
+    pub async fn client_support() -> SshResult<ClientPqSupport> {
+        let version = AlgorithmCheckService::client_version().await?;
+        let supported = pq_only(&AlgorithmCheckService::supported(AlgorithmCategory::Kex).await?);
+        Ok(ClientPqSupport { version, supported })
+    }
+
+    /// Check the client and every selected host
+    pub async fn report(request: &PqReadinessRequest) -> SshResult<PqReadinessReport> {
+        let targets = FleetService::resolve_targets(&FleetRequest {
+            tag: request.tag.clone(),
+            host_aliases: request.host_aliases.clone(),
+            ..Default::default()
+        })
+        .await?;
+        if targets.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "No hosts match the selection".to_string(),
+            });
+        }
+
+        let client = Self::client_support().await?;
+        let concurrency = request
+            .concurrency
+            .unwrap_or(DEFAULT_CONCURRENCY)
+            .clamp(1, MAX_CONCURRENCY);
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|alias| {
+                let semaphore = semaphore.clone();
+                let alias = alias.clone();
+                let client_pq = client.supported.clone();
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    Self::check_host(&alias, &client_pq).await
+                })
+            })
+            .collect();
+
+        let mut hosts = Vec::with_capacity(handles.len());
+        for (alias, handle) in targets.iter().zip(handles) {
+            hosts.push(
+                handle
+                    .await
+                    .unwrap_or_else(|e| unreachable_host(alias, format!("Task failed: {}", e))),
+            );
+        }
+
+        let ready = hosts
+            .iter()
+            .filter(|h| h.status == PqStatus::Hybrid)
+            .count();
+        tracing::info!(
+            "[pq_readiness] {}/{} host(s) negotiate hybrid PQ kex ({})",
+            ready,
+            hosts.len(),
+            client.version
+        );
+        Ok(PqReadinessReport {
+            client,
+            total: hosts.len(),
+            ready,
+            hosts,
+        })
+    }
+
+    /// Probe one host; failures end up in the host status
+    async fn check_host(alias: &str, client_pq: &[String]) -> HostPqStatus {
+        let effective = match AlgorithmCheckService::effective_config(alias).await {
+            Ok(effective) => effective,
+            Err(e) => return unreachable_host(alias, e.to_string()),
+        };
+        let hostname = effective
+            .get("hostname")
+            .cloned()
+            .unwrap_or_else(|| alias.to_string());
+        let port = effective
+            .get("port")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(22);
+        let enabled = split_list(
+            effective
+                .get("kexalgorithms")
+                .map(String::as_str)
+                .unwrap_or_default(),
+        );
+
+        match AlgorithmCheckService::probe_server(&hostname, port).await {
+            Ok((banner, offer)) => {
+                let mut status = classify(client_pq, &enabled, &offer.kex);
+                status.host_alias = alias.to_string();
+                status.server_banner = Some(banner);
+                status
+            }
+            Err(e) => unreachable_host(alias, e.to_string()),
+        }
+    }
+}
+
+fn pq_only(algorithms: &[String]) -> Vec<String> {
+    algorithms
+        .iter()
+        .filter(|name| PQ_KEX_ALGORITHMS.contains(&name.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn unreachable_host(alias: &str, error: String) -> HostPqStatus {
+    HostPqStatus {
+        host_alias: alias.to_string(),
+        status: PqStatus::Unreachable,
+        server_banner: None,
+        server_pq: Vec::new(),
+        negotiated_kex: None,
+        error: Some(error),
+    }
+}
+
+/// Classify a host from the client's PQ support, the host's enabled kex list and the
+/// server's offer (the client's order decides the negotiated method)
+fn classify(client_pq: &[String], enabled: &[String], server_kex: &[String]) -> HostPqStatus {
+    let server_pq = pq_only(server_kex);
+    let negotiated_kex = enabled
+        .iter()
+        .find(|name| server_kex.contains(name))
+        .cloned();
+    let status = if negotiated_kex
+        .as_deref()
+        .is_some_and(|kex| PQ_KEX_ALGORITHMS.contains(&kex))
+    {
+        PqStatus::Hybrid
+    } else if server_pq.iter().any(|name| client_pq.contains(name)) {
+        PqStatus::DisabledByConfig
+    } else if !server_pq.is_empty() {
+        PqStatus::ClientMissing
+    } else if !client_pq.is_empty() {
+        PqStatus::ServerMissing
+    } else {
+        PqStatus::Unsupported
+    };
+    HostPqStatus {
+        host_alias: String::new(),
+        status,
+        server_banner: None,
+        server_pq,
+        negotiated_kex,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &str) -> Vec<String> {
+        split_list(list)
+    }
+
+    #[test]
+    fn test_classify_hybrid_and_missing() {
+        let client = names("mlkem768x25519-sha256,sntrup761x25519-sha512");
+        let enabled = names("mlkem768x25519-sha256,sntrup761x25519-sha512,curve25519-sha256");
+
+        let modern = classify(
+            &client,
+            &enabled,
+            &names("sntrup761x25519-sha512,curve25519-sha256"),
+        );
+        assert_eq!(modern.status, PqStatus::Hybrid);
+        assert_eq!(
+            modern.negotiated_kex.as_deref(),
+            Some("sntrup761x25519-sha512")
+        );
+        assert_eq!(modern.server_pq, vec!["sntrup761x25519-sha512"]);
+
+        let old = classify(&client, &enabled, &names("curve25519-sha256"));
+        assert_eq!(old.status, PqStatus::ServerMissing);
+        assert_eq!(old.negotiated_kex.as_deref(), Some("curve25519-sha256"));
+
+        let old_client = classify(
+            &[],
+            &names("curve25519-sha256"),
+            &names("mlkem768x25519-sha256,curve25519-sha256"),
+        );
+        assert_eq!(old_client.status, PqStatus::ClientMissing);
+
+        let neither = classify(
+            &[],
+            &names("curve25519-sha256"),
+            &names("curve25519-sha256"),
+        );
+        assert_eq!(neither.status, PqStatus::Unsupported);
+    }
+
+    #[test]
+    fn test_classify_disabled_by_config() {
+        // Host pinned to classical kex although both sides could do PQ
+        let status = classify(
+            &names("mlkem768x25519-sha256"),
+            &names("curve25519-sha256"),
+            &names("mlkem768x25519-sha256,curve25519-sha256"),
+        );
+        assert_eq!(status.status, PqStatus::DisabledByConfig);
+        assert_eq!(status.negotiated_kex.as_deref(), Some("curve25519-sha256"));
+    }
+}
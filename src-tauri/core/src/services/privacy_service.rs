@@ -0,0 +1,249 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::audit_service::AuditService;
+use crate::services::history_service::HistoryService;
+use crate::services::notification_service::NotificationService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use crate::utils::app_data_dir;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// How often the retention policy is applied while the app runs
+const RETENTION_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Longest retention that can be configured (10 years)
+const MAX_RETENTION_DAYS: u32 = 3650;
+
+fn default_true() -> bool {
+    true
+}
+
+/// Privacy section of the settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacySettings {
+    /// Days session history and audit events are kept; None keeps them forever
+    pub retention_days: Option<u32>,
+    /// Record sessions in the history (and audit log)
+    #[serde(default = "default_true")]
+    pub record_history: bool,
+    /// Include command text in audit events of commands run on hosts
+    #[serde(default = "default_true")]
+    pub record_commands: bool,
+    /// Replace host aliases with stable pseudonyms in history/audit exports
+    #[serde(default)]
+    pub redact_hostnames: bool,
+    /// Allow reading the shell histories to propose hosts (opt-in)
+    #[serde(default)]
+    pub scan_shell_history: bool,
+}
+
+impl Default for PrivacySettings {
+    fn default() -> Self {
+        Self {
+            retention_days: None,
+            record_history: true,
+            record_commands: true,
+            redact_hostnames: false,
+            scan_shell_history: false,
+        }
+    }
+}
+
+impl PrivacySettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        if self
+            .retention_days
+            .is_some_and(|days| days == 0 || days > MAX_RETENTION_DAYS)
+        {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "Retention must be between 1 and {} days",
+                    MAX_RETENTION_DAYS
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Result of applying the retention policy
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionResult {
+    pub history_removed: usize,
+    pub audit_removed: usize,
+}
+
+/// Retention policy and privacy controls of the locally recorded data
+pub struct PrivacyService;
+
+impl PrivacyService {
+    fn get_data_dir() -> SshResult<PathBuf> {
+        app_data_dir()
+    }
+
+    /// Privacy settings
+    pub async fn get_settings() -> PrivacySettings {
+        SettingsService::get().privacy
+    }
+
+    /// Save the settings and apply a shortened retention right away
+    pub async fn set_settings(settings: &PrivacySettings) -> SshResult<RetentionResult> {
+        settings.validate()?;
+        SettingsService::update(|current| {
+            current.privacy = settings.clone();
+            Ok(())
+        })
+        .await?;
+        Self::apply_retention().await
+    }
+
+    /// Remove history and audit entries older than the retention period
+    pub async fn apply_retention() -> SshResult<RetentionResult> {
+        let Some(days) = Self::get_settings().await.retention_days else {
+            return Ok(RetentionResult::default());
+        };
+        let cutoff = now_millis() - i64::from(days) * DAY_MS;
+        let result = RetentionResult {
+            history_removed: HistoryService::purge_before(cutoff).await?,
+            audit_removed: AuditService::purge_before(cutoff).await?,
+        };
+        if result != RetentionResult::default() {
+            tracing::info!(
+                "[privacy_service] Retention of {} days removed {} session(s) and {} audit event(s)",
+                days,
+                result.history_removed,
+                result.audit_removed
+            );
+        }
+        Ok(result)
+    }
+
+    /// Apply the retention policy at startup and periodically (runs for the app lifetime)
+    pub async fn run_retention() {
+        loop {
+            if let Err(e) = Self::apply_retention().await {
+                tracing::warn!("[privacy_service] Failed to apply retention: {}", e);
+            }
+            tokio::time::sleep(RETENTION_INTERVAL).await;
+        }
+    }
+
+    /// Overwrite and delete everything the app stored locally (history, audit log,
+    /// registry, vault, settings, revisions, logs); ~/.ssh is not touched
+    ///
+    /// Overwriting can't guarantee the old blocks are gone on SSDs or copy-on-write
+    /// filesystems, but keeps the content out of plain undelete tools.
+    pub async fn delete_all_data() -> SshResult<usize> {
+        ReadOnlyMode::ensure_writable("delete all local data")?;
+        let dir = Self::get_data_dir()?;
+        let mut wiped = 0;
+        if dir.exists() {
+            wiped = wipe_dir(&dir).await?;
+            fs::remove_dir_all(&dir).await?;
+        }
+        NotificationService::clear_history();
+        SettingsService::reset();
+        tracing::info!("[privacy_service] Deleted all local data ({} files)", wiped);
+        Ok(wiped)
+    }
+}
+
+/// Stable pseudonym of a host alias for exports ("host-" and 8 hex digits)
+pub fn redact_host(alias: &str) -> String {
+    let digest = Sha256::digest(alias.as_bytes());
+    format!(
+        "host-{}",
+        digest[..4]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    )
+}
+
+/// Overwrite every regular file below `dir` with zeros; returns the number of files
+async fn wipe_dir(dir: &Path) -> SshResult<usize> {
+    let mut pending = vec![dir.to_path_buf()];
+    let mut wiped = 0;
+    while let Some(dir) = pending.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                wipe_file(&entry.path()).await?;
+                wiped += 1;
+            }
+        }
+    }
+    Ok(wiped)
+}
+
+async fn wipe_file(path: &Path) -> SshResult<()> {
+    let len = fs::metadata(path).await?.len();
+    let mut file = fs::OpenOptions::new().write(true).open(path).await?;
+    let zeros = vec![0u8; 64 * 1024];
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..n]).await?;
+        remaining -= n as u64;
+    }
+    file.sync_all().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_redact_host() {
+        let pseudonym = redact_host("db-prod");
+        assert!(pseudonym.starts_with("host-"));
+        assert_eq!(pseudonym.len(), 13);
+        assert_eq!(pseudonym, redact_host("db-prod"));
+        assert_ne!(pseudonym, redact_host("db-staging"));
+    }
+
+    #[test]
+    fn test_settings_defaults() {
+        let settings: PrivacySettings = serde_json::from_str(r#"{"retentionDays":90}"#).unwrap();
+        assert_eq!(settings.retention_days, Some(90));
+        assert!(settings.record_history);
+        assert!(settings.record_commands);
+        assert!(!settings.redact_hostnames);
+    }
+
+    #[tokio::test]
+    async fn test_wipe_dir() {
+        let temp = TempDir::new().unwrap();
+        let nested = temp.path().join("revisions").join("config");
+        fs::create_dir_all(&nested).await.unwrap();
+        fs::write(temp.path().join("vault.json"), "secret")
+            .await
+            .unwrap();
+        fs::write(nested.join("1.rev"), vec![7u8; 100_000])
+            .await
+            .unwrap();
+
+        assert_eq!(wipe_dir(temp.path()).await.unwrap(), 2);
+        assert_eq!(
+            fs::read(temp.path().join("vault.json")).await.unwrap(),
+            vec![0u8; 6]
+        );
+        let rev = fs::read(nested.join("1.rev")).await.unwrap();
+        assert_eq!(rev.len(), 100_000);
+        assert!(rev.iter().all(|b| *b == 0));
+    }
+}
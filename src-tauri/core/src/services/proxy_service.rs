@@ -0,0 +1,544 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::settings_service::SettingsService;
+use crate::utils::{
+    connect_happy_eyeballs, resolve_addresses, AddressFamily, CONNECTION_ATTEMPT_DELAY,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Max size of an HTTP CONNECT response header
+const MAX_HTTP_RESPONSE: usize = 8192;
+
+/// Proxy protocol
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyType {
+    /// HTTP CONNECT
+    Http,
+    Socks5,
+}
+
+/// Outbound proxy settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySettings {
+    pub proxy_type: ProxyType,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Proxy service (app-level settings, tunnelling and ProxyCommand generation)
+pub struct ProxyService;
+
+impl ProxyService {
+    /// Load app-level proxy settings (None = direct connections)
+    pub async fn load_app_proxy() -> SshResult<Option<ProxySettings>> {
+        Ok(SettingsService::get().proxy)
+    }
+
+    /// Save app-level proxy settings (None removes them)
+    pub async fn save_app_proxy(settings: Option<&ProxySettings>) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("change proxy settings")?;
+        SettingsService::update(|current| {
+            current.proxy = settings.cloned();
+            Ok(())
+        })
+        .await?;
+
+        let Some(settings) = settings else {
+            tracing::info!("[proxy_service] App proxy disabled");
+            return Ok(());
+        };
+        tracing::info!(
+            "[proxy_service] App proxy set to {:?} {}:{}",
+            settings.proxy_type,
+            settings.host,
+            settings.port
+        );
+        Ok(())
+    }
+
+    /// Pick the proxy for a host: a recognised ProxyCommand wins over the app-level proxy
+    /// Hosts with any other ProxyCommand/ProxyJump are left alone
+    pub async fn resolve_for_host(
+        host_options: &HashMap<String, String>,
+    ) -> SshResult<Option<ProxySettings>> {
+        if let Some(command) = host_options.get("proxycommand") {
+            return Ok(Self::from_proxy_command(command));
+        }
+        if host_options.contains_key("proxyjump") {
+            return Ok(None);
+        }
+        Self::load_app_proxy().await
+    }
+
+    /// Open a TCP stream to target through the proxy
+    pub async fn connect(
+        proxy: &ProxySettings,
+        target_host: &str,
+        target_port: u16,
+    ) -> SshResult<TcpStream> {
+        let proxy_addr = format_host_port(&proxy.host, proxy.port);
+        let addrs = resolve_addresses(&proxy.host, proxy.port, AddressFamily::Any).await?;
+        let (mut stream, _) = connect_happy_eyeballs(&addrs, CONNECTION_ATTEMPT_DELAY)
+            .await
+            .map_err(|e| SshBuddyError::ProxyError {
+                message: format!("Failed to connect to proxy {}: {}", proxy_addr, e),
+            })?;
+
+        match proxy.proxy_type {
+            ProxyType::Http => {
+                Self::http_connect(&mut stream, proxy, target_host, target_port).await?
+            }
+            ProxyType::Socks5 => {
+                Self::socks5_connect(&mut stream, proxy, target_host, target_port).await?
+            }
+        }
+
+        tracing::info!(
+            "[proxy_service] Tunnel to {}:{} established via {}",
+            target_host,
+            target_port,
+            proxy_addr
+        );
+        Ok(stream)
+    }
+
+    /// HTTP CONNECT handshake
+    async fn http_connect<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: &mut S,
+        proxy: &ProxySettings,
+        target_host: &str,
+        target_port: u16,
+    ) -> SshResult<()> {
+        let target = format_host_port(target_host, target_port);
+        let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", target, target);
+        if let Some(username) = &proxy.username {
+            let credentials = format!(
+                "{}:{}",
+                username,
+                proxy.password.as_deref().unwrap_or_default()
+            );
+            request.push_str(&format!(
+                "Proxy-Authorization: Basic {}\r\n",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            ));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // Read byte by byte so nothing after the header (the SSH banner) is consumed
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_HTTP_RESPONSE {
+                return Err(SshBuddyError::ProxyError {
+                    message: "HTTP proxy response too large".to_string(),
+                });
+            }
+            let byte = stream
+                .read_u8()
+                .await
+                .map_err(|e| SshBuddyError::ProxyError {
+                    message: format!("HTTP proxy closed the connection: {}", e),
+                })?;
+            response.push(byte);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok());
+
+        match status {
+            Some(200..=299) => Ok(()),
+            Some(407) => Err(SshBuddyError::ProxyError {
+                message: "HTTP proxy requires authentication (407)".to_string(),
+            }),
+            _ => Err(SshBuddyError::ProxyError {
+                message: format!("HTTP proxy refused CONNECT: {}", status_line),
+            }),
+        }
+    }
+
+    /// SOCKS5 handshake (RFC 1928) with optional username/password auth (RFC 1929)
+    async fn socks5_connect<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: &mut S,
+        proxy: &ProxySettings,
+        target_host: &str,
+        target_port: u16,
+    ) -> SshResult<()> {
+        let socks_err = |message: String| SshBuddyError::ProxyError { message };
+        let io_err = |e: std::io::Error| SshBuddyError::ProxyError {
+            message: format!("SOCKS5 proxy I/O error: {}", e),
+        };
+
+        // Greeting: offer "no auth", plus username/password if configured
+        let greeting: &[u8] = if proxy.username.is_some() {
+            &[0x05, 0x02, 0x00, 0x02]
+        } else {
+            &[0x05, 0x01, 0x00]
+        };
+        stream.write_all(greeting).await.map_err(io_err)?;
+
+        let mut choice = [0u8; 2];
+        stream.read_exact(&mut choice).await.map_err(io_err)?;
+        if choice[0] != 0x05 {
+            return Err(socks_err("Not a SOCKS5 proxy".to_string()));
+        }
+
+        match choice[1] {
+            0x00 => {}
+            0x02 => {
+                let username = proxy.username.as_deref().unwrap_or_default();
+                let password = proxy.password.as_deref().unwrap_or_default();
+                if username.len() > 255 || password.len() > 255 {
+                    return Err(socks_err("SOCKS5 credentials too long".to_string()));
+                }
+
+                let mut auth = vec![0x01, username.len() as u8];
+                auth.extend_from_slice(username.as_bytes());
+                auth.push(password.len() as u8);
+                auth.extend_from_slice(password.as_bytes());
+                stream.write_all(&auth).await.map_err(io_err)?;
+
+                let mut reply = [0u8; 2];
+                stream.read_exact(&mut reply).await.map_err(io_err)?;
+                if reply[1] != 0x00 {
+                    return Err(socks_err(
+                        "SOCKS5 proxy rejected the username/password".to_string(),
+                    ));
+                }
+            }
+            _ => {
+                return Err(socks_err(
+                    "SOCKS5 proxy requires an unsupported authentication method".to_string(),
+                ))
+            }
+        }
+
+        // CONNECT request
+        let mut request = vec![0x05, 0x01, 0x00];
+        match target_host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(0x01);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(0x04);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                // Let the proxy resolve the name (works for internal-only DNS)
+                if target_host.len() > 255 {
+                    return Err(socks_err("Hostname too long for SOCKS5".to_string()));
+                }
+                request.push(0x03);
+                request.push(target_host.len() as u8);
+                request.extend_from_slice(target_host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&target_port.to_be_bytes());
+        stream.write_all(&request).await.map_err(io_err)?;
+
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await.map_err(io_err)?;
+        if header[1] != 0x00 {
+            return Err(socks_err(format!(
+                "SOCKS5 connect failed: {}",
+                socks5_reply_message(header[1])
+            )));
+        }
+
+        // Skip the bound address and port
+        let addr_len = match header[3] {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => stream.read_u8().await.map_err(io_err)? as usize,
+            other => {
+                return Err(socks_err(format!(
+                    "SOCKS5 proxy returned unknown address type {}",
+                    other
+                )))
+            }
+        };
+        let mut bound = vec![0u8; addr_len + 2];
+        stream.read_exact(&mut bound).await.map_err(io_err)?;
+
+        Ok(())
+    }
+
+    /// Build an equivalent ProxyCommand for plain ssh (OpenBSD netcat syntax)
+    /// Passwords can't be passed to nc; ssh will fail if the proxy requires one
+    pub fn to_proxy_command(proxy: &ProxySettings) -> String {
+        let proxy_addr = format_host_port(&proxy.host, proxy.port);
+        match proxy.proxy_type {
+            ProxyType::Http => match &proxy.username {
+                Some(username) => format!("nc -X connect -x {} -P {} %h %p", proxy_addr, username),
+                None => format!("nc -X connect -x {} %h %p", proxy_addr),
+            },
+            ProxyType::Socks5 => format!("nc -X 5 -x {} %h %p", proxy_addr),
+        }
+    }
+
+    /// Parse a ProxyCommand generated by `to_proxy_command` (or written the same way by hand)
+    pub fn from_proxy_command(command: &str) -> Option<ProxySettings> {
+        let args: Vec<&str> = command.split_whitespace().collect();
+        let program = args.first()?;
+        if !(program.ends_with("nc") || program.ends_with("ncat") || program.ends_with("netcat")) {
+            return None;
+        }
+
+        let mut proxy_type = None;
+        let mut proxy_addr = None;
+        let mut username = None;
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match *arg {
+                "-X" => {
+                    proxy_type = match *iter.next()? {
+                        "connect" => Some(ProxyType::Http),
+                        "5" => Some(ProxyType::Socks5),
+                        _ => return None,
+                    }
+                }
+                "-x" => proxy_addr = Some(*iter.next()?),
+                "-P" => username = Some(iter.next()?.to_string()),
+                _ => {}
+            }
+        }
+
+        let (host, port) = split_host_port(proxy_addr?)?;
+        Some(ProxySettings {
+            // nc defaults to SOCKS5 when -X is omitted
+            proxy_type: proxy_type.unwrap_or(ProxyType::Socks5),
+            host,
+            port: port.unwrap_or(match proxy_type {
+                Some(ProxyType::Http) => 3128,
+                _ => 1080,
+            }),
+            username,
+            password: None,
+        })
+    }
+}
+
+/// Format host:port, bracketing IPv6 literals
+fn format_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Split "host:port" / "[v6]:port" / "host"
+fn split_host_port(value: &str) -> Option<(String, Option<u16>)> {
+    if let Some(rest) = value.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => Some(port.parse().ok()?),
+            None => None,
+        };
+        return Some((host.to_string(), port));
+    }
+
+    match value.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => {
+            Some((host.to_string(), Some(port.parse().ok()?)))
+        }
+        _ => Some((value.to_string(), None)),
+    }
+}
+
+/// Human readable SOCKS5 reply code
+fn socks5_reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy(proxy_type: ProxyType, username: Option<&str>) -> ProxySettings {
+        ProxySettings {
+            proxy_type,
+            host: "proxy.corp".to_string(),
+            port: 8080,
+            username: username.map(|u| u.to_string()),
+            password: username.map(|_| "secret".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_with_auth() {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        let settings = proxy(ProxyType::Http, Some("alice"));
+
+        let server_task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 1024];
+            let n = server.read(&mut buf).await.unwrap();
+            server
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nSSH-2.0-OpenSSH")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+
+        ProxyService::http_connect(&mut client, &settings, "example.com", 22)
+            .await
+            .unwrap();
+        let request = server_task.await.unwrap();
+        assert!(request.starts_with("CONNECT example.com:22 HTTP/1.1\r\n"));
+        // base64("alice:secret")
+        assert!(request.contains("Proxy-Authorization: Basic YWxpY2U6c2VjcmV0\r\n"));
+
+        // The SSH banner after the header must not be consumed
+        let mut rest = [0u8; 3];
+        client.read_exact(&mut rest).await.unwrap();
+        assert_eq!(&rest, b"SSH");
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_rejected() {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        let settings = proxy(ProxyType::Http, None);
+
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1024];
+            let _ = server.read(&mut buf).await.unwrap();
+            server
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let err = ProxyService::http_connect(&mut client, &settings, "example.com", 22)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("407"));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect_with_auth() {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        let settings = proxy(ProxyType::Socks5, Some("alice"));
+
+        let server_task = tokio::spawn(async move {
+            let mut greeting = [0u8; 4];
+            server.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [0x05, 0x02, 0x00, 0x02]);
+            server.write_all(&[0x05, 0x02]).await.unwrap();
+
+            let mut auth = [0u8; 1 + 1 + 5 + 1 + 6];
+            server.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth[2..7], b"alice");
+            assert_eq!(&auth[8..], b"secret");
+            server.write_all(&[0x01, 0x00]).await.unwrap();
+
+            let mut request = [0u8; 5 + 11 + 2];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..5], &[0x05, 0x01, 0x00, 0x03, 11]);
+            assert_eq!(&request[5..16], b"example.com");
+            assert_eq!(&request[16..], &22u16.to_be_bytes());
+
+            server
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 10, 0, 0, 1, 0x1f, 0x90])
+                .await
+                .unwrap();
+        });
+
+        ProxyService::socks5_connect(&mut client, &settings, "example.com", 22)
+            .await
+            .unwrap();
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect_refused() {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        let settings = proxy(ProxyType::Socks5, None);
+
+        tokio::spawn(async move {
+            let mut greeting = [0u8; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            server.write_all(&[0x05, 0x00]).await.unwrap();
+
+            let mut request = [0u8; 4 + 4 + 2];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[3], 0x01);
+            server
+                .write_all(&[0x05, 0x05, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+        });
+
+        let err = ProxyService::socks5_connect(&mut client, &settings, "10.1.2.3", 22)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("connection refused"));
+    }
+
+    #[test]
+    fn test_proxy_command_round_trip() {
+        let http = proxy(ProxyType::Http, Some("alice"));
+        let command = ProxyService::to_proxy_command(&http);
+        assert_eq!(command, "nc -X connect -x proxy.corp:8080 -P alice %h %p");
+
+        let parsed = ProxyService::from_proxy_command(&command).unwrap();
+        assert_eq!(parsed.proxy_type, ProxyType::Http);
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, 8080);
+        assert_eq!(parsed.username.as_deref(), Some("alice"));
+
+        let socks = proxy(ProxyType::Socks5, None);
+        assert_eq!(
+            ProxyService::from_proxy_command(&ProxyService::to_proxy_command(&socks)),
+            Some(socks)
+        );
+    }
+
+    #[test]
+    fn test_from_proxy_command_ignores_other_commands() {
+        assert_eq!(
+            ProxyService::from_proxy_command("ssh -W %h:%p bastion"),
+            None
+        );
+        assert_eq!(
+            ProxyService::from_proxy_command("cloudflared access ssh --hostname %h"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            split_host_port("[::1]:1080"),
+            Some(("::1".to_string(), Some(1080)))
+        );
+        assert_eq!(
+            split_host_port("proxy:3128"),
+            Some(("proxy".to_string(), Some(3128)))
+        );
+        assert_eq!(split_host_port("proxy"), Some(("proxy".to_string(), None)));
+        assert_eq!(format_host_port("::1", 22), "[::1]:22");
+    }
+}
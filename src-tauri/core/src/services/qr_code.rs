@@ -0,0 +1,190 @@
+use crate::models::{KeyType, SshBuddyError, SshResult};
+use crate::services::key_manager::KeyManager;
+use image::{GrayImage, ImageFormat, Luma};
+use qrcode::{EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
+use std::io::Cursor;
+
+/// Pixels per QR module in PNG output
+const PNG_MODULE_SIZE: u32 = 8;
+
+/// Light modules around the code, as the spec requires
+const QUIET_ZONE: u32 = 4;
+
+/// Smallest side of the SVG output, in pixels
+const SVG_MIN_SIZE: u32 = 256;
+
+/// Larger images are refused before decoding
+const MAX_SCAN_BYTES: usize = 20 * 1024 * 1024;
+
+/// What the QR code holds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QrContent {
+    /// The whole `.pub` line, for importing the key
+    PublicKey,
+    /// `SHA256:...`, for comparing the key on another device
+    Fingerprint,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QrFormat {
+    Png,
+    Svg,
+}
+
+/// Public key read from a QR code
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedPublicKey {
+    /// Normalized `<type> <base64> [comment]` line
+    pub public_key: String,
+    pub key_type: KeyType,
+    pub fingerprint: String,
+    pub comment: String,
+}
+
+/// Renders public keys as QR codes and reads them back from photos or screenshots
+pub struct QrCodeService;
+
+impl QrCodeService {
+    /// QR code of a key's public key or fingerprint
+    pub async fn render_key(
+        key_name: &str,
+        content: QrContent,
+        format: QrFormat,
+    ) -> SshResult<Vec<u8>> {
+        let public_key = KeyManager::new()?.read_public_key(key_name).await?;
+        let text = match content {
+            QrContent::PublicKey => public_key,
+            QrContent::Fingerprint => PublicKey::from_openssh(&public_key)?
+                .fingerprint(HashAlg::Sha256)
+                .to_string(),
+        };
+        render(&text, format)
+    }
+
+    /// Public key in a QR image (PNG, JPEG, ...)
+    pub async fn scan(image: Vec<u8>) -> SshResult<ScannedPublicKey> {
+        if image.len() > MAX_SCAN_BYTES {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Image too large (max {} MB)", MAX_SCAN_BYTES / 1024 / 1024),
+            });
+        }
+        tokio::task::spawn_blocking(move || scan(&image))
+            .await
+            .map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?
+    }
+}
+
+/// QR code of `text` as PNG or SVG bytes
+fn render(text: &str, format: QrFormat) -> SshResult<Vec<u8>> {
+    // Medium error correction fits a 4096-bit RSA key; low is the fallback for longer text
+    let code = QrCode::with_error_correction_level(text, EcLevel::M)
+        .or_else(|_| QrCode::with_error_correction_level(text, EcLevel::L))
+        .map_err(|e| SshBuddyError::InvalidOption {
+            message: format!("Can't encode as a QR code: {}", e),
+        })?;
+    match format {
+        QrFormat::Svg => Ok(code
+            .render::<qrcode::render::svg::Color>()
+            .min_dimensions(SVG_MIN_SIZE, SVG_MIN_SIZE)
+            .build()
+            .into_bytes()),
+        QrFormat::Png => {
+            let width = code.width() as u32;
+            let colors = code.to_colors();
+            let side = (width + 2 * QUIET_ZONE) * PNG_MODULE_SIZE;
+            let image = GrayImage::from_fn(side, side, |x, y| {
+                let (column, row) = (x / PNG_MODULE_SIZE, y / PNG_MODULE_SIZE);
+                let inside = (QUIET_ZONE..width + QUIET_ZONE).contains(&column)
+                    && (QUIET_ZONE..width + QUIET_ZONE).contains(&row);
+                let dark = inside
+                    && colors[((row - QUIET_ZONE) * width + column - QUIET_ZONE) as usize]
+                        == qrcode::Color::Dark;
+                Luma([if dark { 0 } else { 255 }])
+            });
+            let mut png = Cursor::new(Vec::new());
+            image
+                .write_to(&mut png, ImageFormat::Png)
+                .map_err(|e| SshBuddyError::Unknown {
+                    message: format!("Failed to encode PNG: {}", e),
+                })?;
+            Ok(png.into_inner())
+        }
+    }
+}
+
+fn scan(image: &[u8]) -> SshResult<ScannedPublicKey> {
+    let image = image::load_from_memory(image)
+        .map_err(|e| SshBuddyError::InvalidOption {
+            message: format!("Can't read the image: {}", e),
+        })?
+        .to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+    let grids = prepared.detect_grids();
+    if grids.is_empty() {
+        return Err(SshBuddyError::InvalidOption {
+            message: "No QR code found in the image".to_string(),
+        });
+    }
+    // A photo may show several codes; the first holding a public key wins
+    grids
+        .iter()
+        .filter_map(|grid| grid.decode().ok())
+        .find_map(|(_, text)| parse_scanned_key(&text))
+        .ok_or_else(|| SshBuddyError::InvalidOption {
+            message: "The QR code doesn't contain an SSH public key".to_string(),
+        })
+}
+
+fn parse_scanned_key(text: &str) -> Option<ScannedPublicKey> {
+    let key = PublicKey::from_openssh(text.trim()).ok()?;
+    Some(ScannedPublicKey {
+        public_key: key.to_openssh().ok()?,
+        key_type: KeyType::from(key.algorithm().as_str()),
+        fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+        comment: key.comment().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl phone@example";
+
+    #[test]
+    fn test_render_and_scan_png() {
+        let png = render(KEY, QrFormat::Png).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let scanned = scan(&png).unwrap();
+        assert_eq!(scanned.public_key, KEY);
+        assert_eq!(scanned.key_type, KeyType::Ed25519);
+        assert_eq!(scanned.comment, "phone@example");
+        assert!(scanned.fingerprint.starts_with("SHA256:"));
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let svg = String::from_utf8(render("SHA256:abc", QrFormat::Svg).unwrap()).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+
+    #[test]
+    fn test_scan_rejects_other_text() {
+        let png = render("https://example.com", QrFormat::Png).unwrap();
+        assert!(scan(&png).is_err());
+        assert!(scan(b"not an image").is_err());
+        assert!(parse_scanned_key(&format!("  {}\n", KEY)).is_some());
+    }
+}
@@ -0,0 +1,527 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use crate::utils::{
+    connect_happy_eyeballs, resolve_addresses, workspace_data_path, write_atomic, AddressFamily,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::sync::Semaphore;
+
+/// A host that doesn't accept a TCP connection within this time counts as down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay before racing the next address of a host
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Hosts probed at the same time
+const PROBE_CONCURRENCY: usize = 8;
+
+const HOUR_SECS: i64 = 60 * 60;
+const DAY_SECS: i64 = 24 * HOUR_SECS;
+
+/// Samples older than this are merged into hourly rollups
+const RAW_RETENTION_SECS: i64 = DAY_SECS;
+
+/// Rollups older than this are dropped
+const ROLLUP_RETENTION_SECS: i64 = 90 * DAY_SECS;
+
+/// Most points a range query returns when no step is given
+const DEFAULT_POINTS: i64 = 300;
+
+/// Reachability monitor section of the settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReachabilitySettings {
+    pub enabled: bool,
+    pub interval_minutes: u32,
+    /// Hosts to probe; empty probes every host of the config
+    pub host_aliases: Vec<String>,
+}
+
+impl Default for ReachabilitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 5,
+            host_aliases: Vec::new(),
+        }
+    }
+}
+
+impl ReachabilitySettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        if !(1..=24 * 60).contains(&self.interval_minutes) {
+            return Err(SshBuddyError::InvalidOption {
+                message: "The reachability interval must be between 1 minute and 24 hours"
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Probe result: Unix seconds and the TCP connect time in milliseconds
+/// (None: unreachable), stored as a two-element array
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+struct Sample(i64, Option<u32>);
+
+/// Samples of one hour (or of one query step) folded together
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Rollup {
+    /// Unix seconds
+    start: i64,
+    samples: u32,
+    /// Samples where the host was reachable
+    up: u32,
+    /// Sum of the latencies of the reachable samples
+    latency_sum: u64,
+    latency_max: u32,
+}
+
+impl Rollup {
+    fn from_sample(start: i64, sample: &Sample) -> Self {
+        Self {
+            start,
+            samples: 1,
+            up: u32::from(sample.1.is_some()),
+            latency_sum: sample.1.map_or(0, u64::from),
+            latency_max: sample.1.unwrap_or(0),
+        }
+    }
+
+    fn merge(&mut self, other: &Rollup) {
+        self.samples += other.samples;
+        self.up += other.up;
+        self.latency_sum += other.latency_sum;
+        self.latency_max = self.latency_max.max(other.latency_max);
+    }
+}
+
+/// Recent samples and hourly rollups of one host, both oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct HostSeries {
+    #[serde(default)]
+    samples: Vec<Sample>,
+    #[serde(default)]
+    rollups: Vec<Rollup>,
+}
+
+impl HostSeries {
+    fn is_up(&self) -> Option<bool> {
+        self.samples.last().map(|s| s.1.is_some())
+    }
+
+    /// Fold samples older than a day into hourly rollups and drop expired rollups
+    fn compact(&mut self, now: i64) {
+        let raw_cutoff = now - RAW_RETENTION_SECS;
+        let keep_from = self.samples.partition_point(|s| s.0 < raw_cutoff);
+        for sample in self.samples.drain(..keep_from) {
+            let rollup = Rollup::from_sample(sample.0 - sample.0.rem_euclid(HOUR_SECS), &sample);
+            match self.rollups.last_mut() {
+                Some(last) if last.start == rollup.start => last.merge(&rollup),
+                _ => self.rollups.push(rollup),
+            }
+        }
+        let rollup_cutoff = now - ROLLUP_RETENTION_SECS;
+        self.rollups.retain(|r| r.start >= rollup_cutoff);
+    }
+
+    /// Data between `from` and `to` (Unix seconds) in steps of `step` seconds
+    fn points(&self, from: i64, to: i64, step: i64) -> Vec<Rollup> {
+        let bucket = |t: i64| from + (t - from) / step * step;
+        let in_range = |t: i64| t >= from && t < to;
+        let rollups = self.rollups.iter().filter(|r| in_range(r.start)).copied();
+        let samples = self
+            .samples
+            .iter()
+            .filter(|s| in_range(s.0))
+            .map(|s| Rollup::from_sample(s.0, s));
+
+        let mut points: Vec<Rollup> = Vec::new();
+        for mut rollup in rollups.chain(samples) {
+            rollup.start = bucket(rollup.start);
+            match points.last_mut() {
+                Some(last) if last.start == rollup.start => last.merge(&rollup),
+                _ => points.push(rollup),
+            }
+        }
+        points
+    }
+}
+
+/// reachability.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReachabilityStore {
+    #[serde(default)]
+    hosts: BTreeMap<String, HostSeries>,
+}
+
+/// One point of an uptime/latency chart
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachabilityPoint {
+    /// Start of the step, Unix milliseconds
+    pub at: i64,
+    pub samples: u32,
+    /// Share of reachable samples, 0 to 1
+    pub uptime: f64,
+    /// Average connect time of the reachable samples
+    pub avg_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<u32>,
+}
+
+impl From<&Rollup> for ReachabilityPoint {
+    fn from(rollup: &Rollup) -> Self {
+        Self {
+            at: rollup.start * 1000,
+            samples: rollup.samples,
+            uptime: f64::from(rollup.up) / f64::from(rollup.samples.max(1)),
+            avg_latency_ms: (rollup.up > 0)
+                .then(|| rollup.latency_sum as f64 / f64::from(rollup.up)),
+            max_latency_ms: (rollup.up > 0).then_some(rollup.latency_max),
+        }
+    }
+}
+
+/// Uptime and latency of a host over a time range
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachabilityHistory {
+    pub host_alias: String,
+    /// Unix milliseconds
+    pub from: i64,
+    pub to: i64,
+    pub step_ms: i64,
+    /// Steps without samples are left out
+    pub points: Vec<ReachabilityPoint>,
+    /// Over the whole range; None without samples
+    pub uptime: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+}
+
+/// Host that stopped answering
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostDown {
+    pub host_alias: String,
+    pub hostname: String,
+    pub port: u16,
+    pub error: String,
+}
+
+/// Probes hosts' SSH ports on an interval and keeps the results as a time series:
+/// raw samples for a day, hourly rollups for 90 days
+pub struct ReachabilityService;
+
+impl ReachabilityService {
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("reachability.json")
+    }
+
+    /// Probe rounds and queries don't interleave their read-modify-write
+    fn lock() -> &'static tokio::sync::Mutex<()> {
+        static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+    }
+
+    async fn load_store() -> SshResult<ReachabilityStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(ReachabilityStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse reachability history: {}", e),
+        })
+    }
+
+    async fn save_store(store: &ReachabilityStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        // Not pretty-printed: the file holds thousands of samples
+        let content = serde_json::to_string(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    pub fn get_settings() -> ReachabilitySettings {
+        SettingsService::get().reachability
+    }
+
+    pub async fn set_settings(settings: &ReachabilitySettings) -> SshResult<()> {
+        settings.validate()?;
+        SettingsService::update(|current| {
+            current.reachability = settings.clone();
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[reachability] Monitor {} (every {} min)",
+            if settings.enabled { "on" } else { "off" },
+            settings.interval_minutes
+        );
+        Ok(())
+    }
+
+    /// Uptime and latency of a host between `from` and `to` (Unix milliseconds)
+    /// `step_ms` defaults to a step giving at most a few hundred points
+    pub async fn history(
+        alias: &str,
+        from: i64,
+        to: i64,
+        step_ms: Option<i64>,
+    ) -> SshResult<ReachabilityHistory> {
+        if to <= from {
+            return Err(SshBuddyError::InvalidOption {
+                message: "The end of the range must be after its start".to_string(),
+            });
+        }
+        let step_secs = step_ms
+            .map(|ms| ms / 1000)
+            .unwrap_or_else(|| default_step(to / 1000 - from / 1000))
+            .max(60);
+        let store = {
+            let _guard = Self::lock().lock().await;
+            Self::load_store().await?
+        };
+        let rollups = store
+            .hosts
+            .get(alias)
+            .map(|series| series.points(from / 1000, to / 1000, step_secs))
+            .unwrap_or_default();
+        let mut total: Option<Rollup> = None;
+        for rollup in &rollups {
+            match total.as_mut() {
+                Some(total) => total.merge(rollup),
+                None => total = Some(*rollup),
+            }
+        }
+        let total = total.as_ref().map(ReachabilityPoint::from);
+        Ok(ReachabilityHistory {
+            host_alias: alias.to_string(),
+            from,
+            to,
+            step_ms: step_secs * 1000,
+            points: rollups.iter().map(ReachabilityPoint::from).collect(),
+            uptime: total.as_ref().map(|t| t.uptime),
+            avg_latency_ms: total.and_then(|t| t.avg_latency_ms),
+        })
+    }
+
+    /// Probe the monitored hosts once and record the samples
+    /// Returns the hosts that were up on the previous round and are down now
+    pub async fn probe_round() -> SshResult<Vec<HostDown>> {
+        let settings = Self::get_settings();
+        let editor = ConfigService::load_editor().await?;
+        let mut aliases = editor.host_aliases();
+        if !settings.host_aliases.is_empty() {
+            aliases.retain(|a| settings.host_aliases.contains(a));
+        }
+        // Hosts behind a jump host or proxy command can't be probed directly
+        let targets: Vec<(String, String, u16)> = aliases
+            .into_iter()
+            .filter(|alias| {
+                editor.get_option(alias, "ProxyJump").is_none()
+                    && editor.get_option(alias, "ProxyCommand").is_none()
+            })
+            .map(|alias| {
+                let hostname = editor
+                    .get_option(&alias, "HostName")
+                    .unwrap_or_else(|| alias.clone());
+                let port = editor
+                    .get_option(&alias, "Port")
+                    .and_then(|p| p.parse().ok())
+                    .unwrap_or(22);
+                (alias, hostname, port)
+            })
+            .collect();
+
+        let semaphore = Arc::new(Semaphore::new(PROBE_CONCURRENCY));
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|(_, hostname, port)| {
+                let semaphore = semaphore.clone();
+                let hostname = hostname.clone();
+                let port = *port;
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    probe(&hostname, port).await
+                })
+            })
+            .collect();
+        let now = now_millis() / 1000;
+        let mut results = Vec::with_capacity(handles.len());
+        for ((alias, hostname, port), handle) in targets.into_iter().zip(handles) {
+            let result = handle.await.unwrap_or_else(|e| Err(e.to_string()));
+            results.push((alias, hostname, port, result));
+        }
+
+        let _guard = Self::lock().lock().await;
+        let mut store = Self::load_store().await?;
+        let mut down = Vec::new();
+        for (alias, hostname, port, result) in results {
+            let series = store.hosts.entry(alias.clone()).or_default();
+            if series.is_up() == Some(true) {
+                if let Err(error) = &result {
+                    down.push(HostDown {
+                        host_alias: alias,
+                        hostname,
+                        port,
+                        error: error.clone(),
+                    });
+                }
+            }
+            series.samples.push(Sample(now, result.ok()));
+        }
+        for series in store.hosts.values_mut() {
+            series.compact(now);
+        }
+        store
+            .hosts
+            .retain(|_, s| !s.samples.is_empty() || !s.rollups.is_empty());
+        Self::save_store(&store).await?;
+        tracing::debug!("[reachability] Probed hosts, {} went down", down.len());
+        Ok(down)
+    }
+
+    /// Probe on the configured interval; `on_down` gets hosts that stopped answering
+    /// (called once at app setup)
+    pub async fn run_monitor<F>(on_down: F)
+    where
+        F: Fn(HostDown) + Send + Sync + 'static,
+    {
+        loop {
+            let settings = Self::get_settings();
+            tokio::time::sleep(Duration::from_secs(
+                u64::from(settings.interval_minutes.max(1)) * 60,
+            ))
+            .await;
+            if !Self::get_settings().enabled {
+                continue;
+            }
+            match Self::probe_round().await {
+                Ok(down) => down.into_iter().for_each(&on_down),
+                Err(e) => tracing::warn!("[reachability] Probe round failed: {}", e),
+            }
+        }
+    }
+}
+
+/// TCP connect time to the host's SSH port in milliseconds, or why it failed
+async fn probe(hostname: &str, port: u16) -> Result<u32, String> {
+    let addrs = resolve_addresses(hostname, port, AddressFamily::Any)
+        .await
+        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, connect_happy_eyeballs(&addrs, ATTEMPT_DELAY)).await {
+        Ok(Ok(_)) => Ok(started.elapsed().as_millis().min(u128::from(u32::MAX)) as u32),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("No answer within {}s", PROBE_TIMEOUT.as_secs())),
+    }
+}
+
+/// Step (seconds) giving at most `DEFAULT_POINTS` points, rounded up to a whole
+/// number of minutes, hours or days
+fn default_step(range_secs: i64) -> i64 {
+    let step = (range_secs + DEFAULT_POINTS - 1) / DEFAULT_POINTS;
+    let unit = if step <= HOUR_SECS {
+        60
+    } else if step <= DAY_SECS {
+        HOUR_SECS
+    } else {
+        DAY_SECS
+    };
+    ((step + unit - 1) / unit * unit).max(60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_000 * DAY_SECS;
+
+    /// Samples every 5 minutes over the last `hours`, down every 4th time
+    fn series(hours: i64) -> HostSeries {
+        let samples = (0..hours * 12)
+            .map(|i| {
+                let latency = (i % 4 != 0).then_some(20 + (i % 3) as u32);
+                Sample(NOW - hours * HOUR_SECS + i * 300, latency)
+            })
+            .collect();
+        HostSeries {
+            samples,
+            rollups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut series = series(48);
+        let before = series.clone();
+        series.compact(NOW);
+
+        assert_eq!(series.samples.len(), 24 * 12);
+        assert_eq!(series.rollups.len(), 24);
+        let first = series.rollups[0];
+        assert_eq!((first.samples, first.up), (12, 9));
+        assert_eq!(first.start % HOUR_SECS, 0);
+
+        // Compacting loses resolution, not data
+        let total = |s: &HostSeries| s.points(0, NOW + 1, 100 * DAY_SECS)[0];
+        assert_eq!(total(&series), total(&before));
+
+        series.compact(NOW + ROLLUP_RETENTION_SECS - 12 * HOUR_SECS);
+        assert!(series.samples.is_empty());
+        assert_eq!(series.rollups.len(), 12);
+    }
+
+    #[test]
+    fn test_points() {
+        let mut series = series(48);
+        series.compact(NOW);
+        let points = series.points(NOW - 48 * HOUR_SECS, NOW, 6 * HOUR_SECS);
+        assert_eq!(points.len(), 8);
+        assert!(points.iter().all(|p| p.samples == 72 && p.up == 54));
+
+        let point = ReachabilityPoint::from(&points[0]);
+        assert_eq!(point.uptime, 0.75);
+        assert_eq!(point.max_latency_ms, Some(22));
+        assert!(point
+            .avg_latency_ms
+            .is_some_and(|l| (20.0..=22.0).contains(&l)));
+
+        // A range before any data
+        assert!(series.points(0, DAY_SECS, HOUR_SECS).is_empty());
+    }
+
+    #[test]
+    fn test_default_step() {
+        assert_eq!(default_step(HOUR_SECS), 60);
+        assert_eq!(default_step(DAY_SECS), 5 * 60);
+        assert_eq!(default_step(7 * DAY_SECS), 34 * 60);
+        assert_eq!(default_step(365 * DAY_SECS), 2 * DAY_SECS);
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(ReachabilitySettings::default().validate().is_ok());
+        assert!(ReachabilitySettings {
+            interval_minutes: 0,
+            ..ReachabilitySettings::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::settings_service::SettingsService;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Setting this environment variable to "1" or "true" forces read-only mode
+/// (for shared machines where the user shouldn't be able to turn it off)
+const READ_ONLY_ENV: &str = "SSH_BUDDY_READ_ONLY";
+
+/// Read-only mode status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadOnlyStatus {
+    pub enabled: bool,
+    /// Enabled by the environment; can't be turned off from the app
+    pub forced: bool,
+}
+
+/// App-wide read-only mode, checked by every service that changes files or agent state
+pub struct ReadOnlyMode;
+
+impl ReadOnlyMode {
+    /// Persisted toggle, loaded on first use
+    fn state() -> &'static AtomicBool {
+        static STATE: OnceLock<AtomicBool> = OnceLock::new();
+        STATE.get_or_init(|| AtomicBool::new(SettingsService::get().read_only))
+    }
+
+    fn is_forced() -> bool {
+        std::env::var(READ_ONLY_ENV).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+    }
+
+    /// Get read-only mode status
+    pub fn status() -> ReadOnlyStatus {
+        let forced = Self::is_forced();
+        ReadOnlyStatus {
+            enabled: forced || Self::state().load(Ordering::SeqCst),
+            forced,
+        }
+    }
+
+    /// Turn read-only mode on or off (persisted)
+    pub async fn set_enabled(enabled: bool) -> SshResult<ReadOnlyStatus> {
+        if !enabled && Self::is_forced() {
+            return Err(SshBuddyError::ReadOnlyMode {
+                operation: format!("disable read-only mode (forced by {})", READ_ONLY_ENV),
+            });
+        }
+
+        SettingsService::update(|settings| {
+            settings.read_only = enabled;
+            Ok(())
+        })
+        .await?;
+
+        Self::state().store(enabled, Ordering::SeqCst);
+        tracing::info!(
+            "[read_only] Read-only mode {}",
+            if enabled { "on" } else { "off" }
+        );
+        Ok(Self::status())
+    }
+
+    /// Reject a mutating operation while read-only mode is on
+    pub fn ensure_writable(operation: &str) -> SshResult<()> {
+        check_writable(Self::status().enabled, operation)
+    }
+}
+
+fn check_writable(read_only: bool, operation: &str) -> SshResult<()> {
+    if read_only {
+        tracing::warn!("[read_only] Rejected: {}", operation);
+        return Err(SshBuddyError::ReadOnlyMode {
+            operation: operation.to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_writable() {
+        assert!(check_writable(false, "delete key").is_ok());
+        let err = check_writable(true, "delete key").unwrap_err();
+        assert_eq!(err.error_type(), "ReadOnlyMode");
+        assert_eq!(err.to_string(), "Read-only mode is on, cannot delete key");
+    }
+}
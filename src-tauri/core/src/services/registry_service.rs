@@ -0,0 +1,803 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::connection_hooks::HostHooks;
+use crate::services::console_server::HostConsole;
+use crate::services::host_facts::HostFacts;
+use crate::services::host_notes::{normalize_notes, HostAttachment, HostNotesService};
+use crate::services::key_metadata::KeyMetadata;
+use crate::services::legacy_profiles::LegacyException;
+use crate::services::login_banner::HostLoginBanner;
+use crate::services::multiplexer::HostMultiplexer;
+use crate::services::network_requirement::NetworkRequirement;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::shell_access::HostShellAccess;
+use crate::services::team_catalog::CatalogSource;
+use crate::utils::{workspace_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::sync::MutexGuard;
+
+/// Schema version written by the frontend metadata service
+const METADATA_SCHEMA_VERSION: u32 = 1;
+
+/// Deferred changes arriving within this delay are written together
+const WRITE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Per-host metadata (same layout as the frontend's metadata.json)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMetadata {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// Position among the favorites (pinned hosts), lowest first; set by reordering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin_order: Option<u32>,
+    /// Unix timestamp in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<i64>,
+    /// Times the host was connected to (weights palette search)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub use_count: u32,
+    /// Unix timestamp in milliseconds
+    #[serde(default)]
+    pub created_at: i64,
+    /// Markdown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Files kept in the attachments directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<HostAttachment>,
+    /// Short code typed in quick-connect and links instead of the alias (e.g. "p1")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_code: Option<String>,
+    /// Last collected system facts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facts: Option<HostFacts>,
+    /// Set for hosts imported from a local VM ("<provider>:<machine id>");
+    /// they are removed again when the VM disappears
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ephemeral_source: Option<String>,
+    /// Set for hosts merged from a subscribed team catalog; they are read-only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog_source: Option<CatalogSource>,
+    /// Deprecated algorithms enabled for an old device, with a review date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_exception: Option<LegacyException>,
+    /// Local commands run before connecting and after disconnecting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HostHooks>,
+    /// VPN/network the host is only reachable through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_requirement: Option<NetworkRequirement>,
+    /// Profile used when the host is opened in an external terminal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal_profile: Option<String>,
+    /// tmux preference for shells opened in the app
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiplexer: Option<HostMultiplexer>,
+    /// Serial console options when the host is a console server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub console: Option<HostConsole>,
+    /// Restricted or disabled shell found by the last connection test
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell_access: Option<HostShellAccess>,
+    /// Banner and MOTD seen by the last connection tests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login_banner: Option<HostLoginBanner>,
+    /// Fields this version doesn't know about, kept as-is
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl HostMetadata {
+    /// New metadata created now
+    pub fn new() -> Self {
+        Self {
+            tags: Vec::new(),
+            is_favorite: false,
+            pin_order: None,
+            last_used: None,
+            use_count: 0,
+            created_at: now_millis(),
+            notes: None,
+            attachments: Vec::new(),
+            short_code: None,
+            facts: None,
+            ephemeral_source: None,
+            catalog_source: None,
+            legacy_exception: None,
+            hooks: None,
+            network_requirement: None,
+            terminal_profile: None,
+            multiplexer: None,
+            console: None,
+            shell_access: None,
+            login_banner: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl Default for HostMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// metadata.json contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataStore {
+    pub version: u32,
+    #[serde(default)]
+    pub hosts: HashMap<String, HostMetadata>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Tags created in the tag list that no host uses yet (listed until deleted)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unused_tags: Vec<String>,
+    /// App-level state (onboarding etc.), owned by the frontend
+    #[serde(default)]
+    pub app: Value,
+    /// Key metadata by SHA256 fingerprint
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub keys: HashMap<String, KeyMetadata>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for MetadataStore {
+    fn default() -> Self {
+        Self {
+            version: METADATA_SCHEMA_VERSION,
+            hosts: HashMap::new(),
+            tags: Vec::new(),
+            unused_tags: Vec::new(),
+            app: serde_json::json!({
+                "onboardingCompleted": false,
+                "onboardingSkipped": false,
+            }),
+            keys: HashMap::new(),
+            extra: Map::new(),
+        }
+    }
+}
+
+impl MetadataStore {
+    /// Rebuild the global tag list from all host tags and the created, unused ones
+    pub fn update_global_tags(&mut self) {
+        let used: BTreeSet<&String> = self.hosts.values().flat_map(|h| &h.tags).collect();
+        self.unused_tags.retain(|t| !used.contains(t));
+        let tags: BTreeSet<&String> = used.into_iter().chain(&self.unused_tags).collect();
+        self.tags = tags.into_iter().cloned().collect();
+    }
+}
+
+/// Change of a batch applied to the registry in one write
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RegistryChange {
+    /// Replace the tags of a host
+    SetTags {
+        alias: String,
+        tags: Vec<String>,
+    },
+    AddTags {
+        aliases: Vec<String>,
+        tags: Vec<String>,
+    },
+    RemoveTags {
+        aliases: Vec<String>,
+        tags: Vec<String>,
+    },
+    /// Favorites are the pinned hosts; a new one goes to the end of the pin order
+    SetFavorite {
+        alias: String,
+        is_favorite: bool,
+    },
+    /// Pin these hosts in this order, ahead of the other pinned hosts
+    ReorderFavorites {
+        aliases: Vec<String>,
+    },
+    /// Markdown; blank clears the notes
+    SetNotes {
+        alias: String,
+        notes: Option<String>,
+    },
+    /// Count a connection (sets `last_used` to now)
+    RecordUse {
+        alias: String,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    Remove {
+        alias: String,
+    },
+    /// Add a tag to the tag list before any host uses it
+    CreateTag {
+        tag: String,
+    },
+    /// Remove a tag from every host and from the tag list
+    DeleteTag {
+        tag: String,
+    },
+    /// Rename a tag on every host and in the tag list
+    RenameTag {
+        from: String,
+        to: String,
+    },
+    /// Merge values into the app-level state (onboarding flags etc.)
+    UpdateApp {
+        values: Map<String, Value>,
+    },
+    /// Set `firstRunAt` to now, unless it's already set
+    MarkFirstRun,
+}
+
+/// Registry changes made while a transaction is open (or from a debounced write)
+type DeferredChange = Box<dyn FnOnce(&mut MetadataStore) + Send>;
+
+/// Serializes read-modify-write of metadata.json
+static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Changes waiting for the debounced write
+static DEFERRED: Mutex<Vec<DeferredChange>> = Mutex::new(Vec::new());
+
+/// Registry loaded for a batch of changes: other writers wait until it's committed,
+/// and dropping it without committing discards the changes
+pub struct RegistryTransaction {
+    store: MetadataStore,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl RegistryTransaction {
+    pub fn store(&mut self) -> &mut MetadataStore {
+        &mut self.store
+    }
+
+    /// Write all changes at once
+    pub async fn commit(mut self) -> SshResult<()> {
+        self.store.update_global_tags();
+        RegistryService::save(&self.store).await
+    }
+}
+
+/// Host registry service (app metadata stored next to, not inside, ~/.ssh/config)
+pub struct RegistryService;
+
+impl RegistryService {
+    /// Get metadata.json path (data dir of the active workspace)
+    fn get_metadata_path() -> SshResult<PathBuf> {
+        workspace_data_path("metadata.json")
+    }
+
+    /// Load the registry (defaults if missing)
+    pub async fn load() -> SshResult<MetadataStore> {
+        let path = Self::get_metadata_path()?;
+        if !path.exists() {
+            return Ok(MetadataStore::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to read metadata: {}", e),
+            })?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse metadata: {}", e),
+        })
+    }
+
+    /// Save the registry; callers go through `update`, `begin` or `defer`,
+    /// which hold the store lock
+    async fn save(store: &MetadataStore) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("save host metadata")?;
+        let path = Self::get_metadata_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Load the registry for a batch of changes that is written once on commit
+    pub async fn begin() -> SshResult<RegistryTransaction> {
+        let guard = STORE_LOCK.lock().await;
+        Ok(RegistryTransaction {
+            store: Self::load().await?,
+            _guard: guard,
+        })
+    }
+
+    /// Apply `change` and write the registry once (nothing is written if it fails)
+    pub async fn update<T, F>(change: F) -> SshResult<T>
+    where
+        F: FnOnce(&mut MetadataStore) -> SshResult<T>,
+    {
+        let mut transaction = Self::begin().await?;
+        let value = change(transaction.store())?;
+        transaction.commit().await?;
+        Ok(value)
+    }
+
+    /// Queue a best-effort change; changes queued within a short delay of each other
+    /// are written together (e.g. facts collected from a whole fleet)
+    pub fn defer<F>(change: F)
+    where
+        F: FnOnce(&mut MetadataStore) + Send + 'static,
+    {
+        let first = {
+            let mut deferred = DEFERRED.lock().unwrap_or_else(|e| e.into_inner());
+            deferred.push(Box::new(change));
+            deferred.len() == 1
+        };
+        if first {
+            tokio::spawn(async {
+                tokio::time::sleep(WRITE_DEBOUNCE).await;
+                if let Err(e) = Self::flush().await {
+                    tracing::warn!("[registry_service] Deferred changes not written: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Write the queued changes now
+    pub async fn flush() -> SshResult<()> {
+        let mut transaction = Self::begin().await?;
+        let changes = std::mem::take(&mut *DEFERRED.lock().unwrap_or_else(|e| e.into_inner()));
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let count = changes.len();
+        for change in changes {
+            change(transaction.store());
+        }
+        transaction.commit().await?;
+        tracing::info!("[registry_service] Wrote {} deferred change(s)", count);
+        Ok(())
+    }
+
+    /// Apply a batch of changes in one write; returns the aliases that changed
+    pub async fn apply(changes: &[RegistryChange]) -> SshResult<Vec<String>> {
+        let changed = Self::update(|store| {
+            let mut changed = BTreeSet::new();
+            for change in changes {
+                apply_change(store, change, now_millis(), &mut changed)?;
+            }
+            Ok(changed)
+        })
+        .await?;
+        tracing::info!(
+            "[registry_service] Applied {} change(s) to {} host(s)",
+            changes.len(),
+            changed.len()
+        );
+        if changes
+            .iter()
+            .any(|c| matches!(c, RegistryChange::Remove { .. }))
+        {
+            // Under the store lock: an attachment being added is registered before
+            // its file is copied, so a file it registered can't be pruned
+            let pruned = match Self::begin().await {
+                Ok(mut transaction) => {
+                    HostNotesService::prune_attachments(transaction.store()).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = pruned {
+                tracing::warn!("[registry_service] Attachments not pruned: {}", e);
+            }
+        }
+        Ok(changed.into_iter().collect())
+    }
+}
+
+/// Metadata of a host that is about to change (created when missing)
+fn host<'a>(
+    store: &'a mut MetadataStore,
+    changed: &mut BTreeSet<String>,
+    alias: &str,
+) -> &'a mut HostMetadata {
+    changed.insert(alias.to_string());
+    store
+        .hosts
+        .entry(alias.to_string())
+        .or_insert_with(HostMetadata::new)
+}
+
+fn apply_change(
+    store: &mut MetadataStore,
+    change: &RegistryChange,
+    now: i64,
+    changed: &mut BTreeSet<String>,
+) -> SshResult<()> {
+    match change {
+        RegistryChange::SetTags { alias, tags } => {
+            let metadata = host(store, changed, alias);
+            metadata.tags.clear();
+            for tag in tags {
+                if !metadata.tags.contains(tag) {
+                    metadata.tags.push(tag.clone());
+                }
+            }
+        }
+        RegistryChange::AddTags { aliases, tags } => {
+            for alias in aliases {
+                let metadata = host(store, changed, alias);
+                for tag in tags {
+                    if !metadata.tags.contains(tag) {
+                        metadata.tags.push(tag.clone());
+                    }
+                }
+            }
+        }
+        RegistryChange::RemoveTags { aliases, tags } => {
+            for alias in aliases {
+                host(store, changed, alias)
+                    .tags
+                    .retain(|t| !tags.contains(t));
+            }
+        }
+        RegistryChange::SetFavorite { alias, is_favorite } => {
+            let next = next_pin_order(store);
+            let metadata = host(store, changed, alias);
+            if !*is_favorite {
+                metadata.pin_order = None;
+            } else if !metadata.is_favorite {
+                metadata.pin_order = Some(next);
+            }
+            metadata.is_favorite = *is_favorite;
+        }
+        RegistryChange::ReorderFavorites { aliases } => {
+            let rest: Vec<String> = pinned_aliases(store)
+                .into_iter()
+                .filter(|a| !aliases.contains(a))
+                .collect();
+            let mut position = 0;
+            for alias in aliases.iter().chain(&rest) {
+                let metadata = host(store, changed, alias);
+                metadata.is_favorite = true;
+                metadata.pin_order = Some(position);
+                position += 1;
+            }
+        }
+        RegistryChange::SetNotes { alias, notes } => {
+            host(store, changed, alias).notes = normalize_notes(notes.as_deref())?;
+        }
+        RegistryChange::RecordUse { alias } => {
+            let metadata = host(store, changed, alias);
+            metadata.use_count = metadata.use_count.saturating_add(1);
+            metadata.last_used = Some(now);
+        }
+        RegistryChange::Rename { from, to } => {
+            if store.hosts.contains_key(to) {
+                return Err(SshBuddyError::InvalidOption {
+                    message: format!("Host {} already has metadata", to),
+                });
+            }
+            if let Some(metadata) = store.hosts.remove(from) {
+                store.hosts.insert(to.clone(), metadata);
+                changed.insert(from.clone());
+                changed.insert(to.clone());
+            }
+        }
+        RegistryChange::Remove { alias } => {
+            if store.hosts.remove(alias).is_some() {
+                changed.insert(alias.clone());
+            }
+        }
+        RegistryChange::CreateTag { tag } => {
+            let tag = valid_tag(tag)?;
+            let used = store.hosts.values().any(|h| h.tags.contains(&tag));
+            if !used && !store.unused_tags.contains(&tag) {
+                store.unused_tags.push(tag);
+            }
+        }
+        RegistryChange::DeleteTag { tag } => {
+            for (alias, metadata) in store.hosts.iter_mut() {
+                if metadata.tags.contains(tag) {
+                    metadata.tags.retain(|t| t != tag);
+                    changed.insert(alias.clone());
+                }
+            }
+            store.unused_tags.retain(|t| t != tag);
+        }
+        RegistryChange::RenameTag { from, to } => {
+            let to = valid_tag(to)?;
+            if *from == to {
+                return Ok(());
+            }
+            for (alias, metadata) in store.hosts.iter_mut() {
+                if let Some(index) = metadata.tags.iter().position(|t| t == from) {
+                    if metadata.tags.contains(&to) {
+                        metadata.tags.remove(index);
+                    } else {
+                        metadata.tags[index] = to.clone();
+                    }
+                    changed.insert(alias.clone());
+                }
+            }
+            if store.unused_tags.contains(from) {
+                store.unused_tags.retain(|t| t != from && *t != to);
+                store.unused_tags.push(to);
+            }
+        }
+        RegistryChange::UpdateApp { values } => {
+            let mut app = take_app_state(store);
+            app.extend(values.clone());
+            store.app = Value::Object(app);
+        }
+        RegistryChange::MarkFirstRun => {
+            let mut app = take_app_state(store);
+            app.entry("firstRunAt").or_insert_with(|| Value::from(now));
+            store.app = Value::Object(app);
+        }
+    }
+    Ok(())
+}
+
+/// Tag name without surrounding whitespace (blank names are rejected)
+fn valid_tag(tag: &str) -> SshResult<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(SshBuddyError::InvalidOption {
+            message: "Tag names can't be empty".to_string(),
+        });
+    }
+    Ok(tag.to_string())
+}
+
+/// App-level state taken out of the store (anything but an object starts over empty)
+fn take_app_state(store: &mut MetadataStore) -> Map<String, Value> {
+    match std::mem::take(&mut store.app) {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Favorite (pinned) hosts in pin order; hosts never reordered come last, by alias
+pub fn pinned_aliases(store: &MetadataStore) -> Vec<String> {
+    let mut pinned: Vec<(&String, &HostMetadata)> =
+        store.hosts.iter().filter(|(_, m)| m.is_favorite).collect();
+    pinned.sort_by(|a, b| {
+        let order = |m: &HostMetadata| m.pin_order.unwrap_or(u32::MAX);
+        order(a.1).cmp(&order(b.1)).then_with(|| a.0.cmp(b.0))
+    });
+    pinned.into_iter().map(|(alias, _)| alias.clone()).collect()
+}
+
+fn next_pin_order(store: &MetadataStore) -> u32 {
+    store
+        .hosts
+        .values()
+        .filter(|m| m.is_favorite)
+        .filter_map(|m| m.pin_order)
+        .max()
+        .map_or(0, |max| max.saturating_add(1))
+}
+
+/// serde skip_serializing_if helper
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// Current time as a Unix timestamp in milliseconds (like JS Date.now())
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frontend_metadata() {
+        let json = r#"{
+            "version": 1,
+            "hosts": {
+                "github": { "tags": ["work"], "isFavorite": true, "createdAt": 1700000000000, "color": "red" }
+            },
+            "tags": ["work"],
+            "app": { "onboardingCompleted": true, "onboardingSkipped": false }
+        }"#;
+
+        let store: MetadataStore = serde_json::from_str(json).unwrap();
+        let host = &store.hosts["github"];
+        assert!(host.is_favorite);
+        assert_eq!(host.tags, vec!["work"]);
+        assert_eq!(host.last_used, None);
+
+        // Unknown fields survive a round trip
+        let out = serde_json::to_value(&store).unwrap();
+        assert_eq!(out["hosts"]["github"]["color"], "red");
+        assert_eq!(out["app"]["onboardingCompleted"], true);
+        assert!(out["hosts"]["github"].get("lastUsed").is_none());
+    }
+
+    #[test]
+    fn test_update_global_tags() {
+        let mut store = MetadataStore::default();
+        let mut a = HostMetadata::new();
+        a.tags = vec!["prod".to_string(), "db".to_string()];
+        let mut b = HostMetadata::new();
+        b.tags = vec!["prod".to_string()];
+        store.hosts.insert("a".to_string(), a);
+        store.hosts.insert("b".to_string(), b);
+
+        store.update_global_tags();
+        assert_eq!(store.tags, vec!["db", "prod"]);
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let mut store = MetadataStore::default();
+        let mut changed = BTreeSet::new();
+        let changes = [
+            RegistryChange::AddTags {
+                aliases: vec!["a".to_string(), "b".to_string()],
+                tags: vec!["prod".to_string(), "web".to_string()],
+            },
+            RegistryChange::RemoveTags {
+                aliases: vec!["b".to_string()],
+                tags: vec!["web".to_string()],
+            },
+            RegistryChange::SetFavorite {
+                alias: "a".to_string(),
+                is_favorite: true,
+            },
+            RegistryChange::RecordUse {
+                alias: "b".to_string(),
+            },
+            RegistryChange::Rename {
+                from: "a".to_string(),
+                to: "c".to_string(),
+            },
+        ];
+        for change in &changes {
+            apply_change(&mut store, change, 42, &mut changed).unwrap();
+        }
+
+        assert!(!store.hosts.contains_key("a"));
+        assert!(store.hosts["c"].is_favorite);
+        assert_eq!(store.hosts["c"].tags, vec!["prod", "web"]);
+        assert_eq!(store.hosts["b"].tags, vec!["prod"]);
+        assert_eq!(store.hosts["b"].use_count, 1);
+        assert_eq!(store.hosts["b"].last_used, Some(42));
+        assert_eq!(changed.into_iter().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+
+        let clash = RegistryChange::Rename {
+            from: "b".to_string(),
+            to: "c".to_string(),
+        };
+        assert!(apply_change(&mut store, &clash, 42, &mut BTreeSet::new()).is_err());
+    }
+
+    #[test]
+    fn test_tag_and_app_changes() {
+        let mut store = MetadataStore::default();
+        let mut a = HostMetadata::new();
+        a.tags = vec!["prod".to_string(), "db".to_string()];
+        store.hosts.insert("a".to_string(), a);
+        let mut changed = BTreeSet::new();
+        let changes = [
+            RegistryChange::CreateTag {
+                tag: " later ".to_string(),
+            },
+            RegistryChange::CreateTag {
+                tag: "prod".to_string(),
+            },
+            RegistryChange::RenameTag {
+                from: "db".to_string(),
+                to: "prod".to_string(),
+            },
+            RegistryChange::UpdateApp {
+                values: serde_json::from_str(r#"{"onboardingCompleted": true}"#).unwrap(),
+            },
+            RegistryChange::MarkFirstRun,
+        ];
+        for change in &changes {
+            apply_change(&mut store, change, 42, &mut changed).unwrap();
+        }
+        store.update_global_tags();
+
+        assert_eq!(store.hosts["a"].tags, vec!["prod"]);
+        assert_eq!(store.tags, vec!["later", "prod"]);
+        assert_eq!(store.app["onboardingCompleted"], true);
+        assert_eq!(store.app["onboardingSkipped"], false);
+        assert_eq!(store.app["firstRunAt"], 42);
+
+        // A created tag follows the hosts once one uses it
+        let add = RegistryChange::AddTags {
+            aliases: vec!["a".to_string()],
+            tags: vec!["later".to_string()],
+        };
+        apply_change(&mut store, &add, 43, &mut changed).unwrap();
+        store.update_global_tags();
+        assert!(store.unused_tags.is_empty());
+
+        let delete = RegistryChange::DeleteTag {
+            tag: "prod".to_string(),
+        };
+        apply_change(&mut store, &delete, 43, &mut changed).unwrap();
+        apply_change(&mut store, &RegistryChange::MarkFirstRun, 44, &mut changed).unwrap();
+        store.update_global_tags();
+        assert_eq!(store.tags, vec!["later"]);
+        assert_eq!(store.app["firstRunAt"], 42);
+        assert!(apply_change(
+            &mut store,
+            &RegistryChange::CreateTag {
+                tag: " ".to_string()
+            },
+            44,
+            &mut changed
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_registry_change() {
+        let change: RegistryChange =
+            serde_json::from_str(r#"{ "type": "setFavorite", "alias": "a", "isFavorite": true }"#)
+                .unwrap();
+        assert_eq!(
+            change,
+            RegistryChange::SetFavorite {
+                alias: "a".to_string(),
+                is_favorite: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_reorder_favorites() {
+        let mut store = MetadataStore::default();
+        let mut changed = BTreeSet::new();
+        for alias in ["a", "b", "c"] {
+            let change = RegistryChange::SetFavorite {
+                alias: alias.to_string(),
+                is_favorite: true,
+            };
+            apply_change(&mut store, &change, 42, &mut changed).unwrap();
+        }
+        assert_eq!(pinned_aliases(&store), vec!["a", "b", "c"]);
+
+        let reorder = RegistryChange::ReorderFavorites {
+            aliases: vec!["c".to_string(), "d".to_string()],
+        };
+        let mut changed = BTreeSet::new();
+        apply_change(&mut store, &reorder, 42, &mut changed).unwrap();
+        assert_eq!(pinned_aliases(&store), vec!["c", "d", "a", "b"]);
+        assert!(store.hosts["d"].is_favorite);
+
+        let unpin = RegistryChange::SetFavorite {
+            alias: "d".to_string(),
+            is_favorite: false,
+        };
+        apply_change(&mut store, &unpin, 42, &mut changed).unwrap();
+        assert_eq!(store.hosts["d"].pin_order, None);
+        let repin = RegistryChange::SetFavorite {
+            alias: "d".to_string(),
+            is_favorite: true,
+        };
+        apply_change(&mut store, &repin, 42, &mut changed).unwrap();
+        assert_eq!(pinned_aliases(&store), vec!["c", "a", "b", "d"]);
+    }
+}
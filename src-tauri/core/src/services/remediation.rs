@@ -0,0 +1,610 @@
+use crate::models::{LocalizedMessage, SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::integrity::IntegrityService;
+use crate::services::key_deploy::{KeyDeployRequest, KeyDeployService};
+use crate::services::key_exposure::KeyExposureService;
+use crate::services::key_manager::KeyManager;
+use crate::services::permission_service::PermissionService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::tamper_detection::TamperDetectionService;
+use crate::utils::{ssh_dir, write_atomic, AuthorizedKeyOptions};
+use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
+use std::path::PathBuf;
+use tokio::fs;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// What a playbook step does
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RemediationAction {
+    /// Rebuild `<key>.pub` from the private key
+    RegeneratePublicKey {
+        key_name: String,
+    },
+    /// Delete a file in the SSH directory
+    RemoveFile {
+        name: String,
+    },
+    /// Move a suspicious file to the quarantine
+    QuarantineFile {
+        name: String,
+    },
+    FixKeyPermissions {
+        key_name: String,
+    },
+    FixSshDirPermissions,
+    /// Install `<key>.pub` in a host's authorized_keys
+    DeployPublicKey {
+        key_name: String,
+        host_alias: String,
+    },
+    /// Something only the user can do; confirming the step records it as done
+    Manual,
+}
+
+/// Where a step makes its changes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StepScope {
+    /// Files on this machine; rolled back when a later step fails
+    Local,
+    /// A remote host; can't be rolled back
+    Remote,
+    Manual,
+}
+
+/// One step of a playbook
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationStep {
+    /// Position-based id (`step-1`, ...), used to confirm the step
+    pub id: String,
+    pub action: RemediationAction,
+    pub scope: StepScope,
+    /// The step only runs when the request lists it as confirmed
+    pub requires_confirmation: bool,
+    /// Catalog key `remediation.<action>`
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
+}
+
+/// Ordered steps that fix a doctor finding
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationPlaybook {
+    pub finding_id: String,
+    pub subject: Option<String>,
+    pub steps: Vec<RemediationStep>,
+}
+
+/// Apply the playbook of a finding
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationRequest {
+    pub finding_id: String,
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// Ids of the steps the user confirmed
+    #[serde(default)]
+    pub confirmed_steps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+    /// Undone after a later step failed
+    RolledBack,
+    /// Done, but a later step failed and remote changes can't be undone
+    NotRolledBack,
+}
+
+/// Progress of one step (streamed while a playbook runs)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationProgress {
+    pub run_id: String,
+    pub step_id: String,
+    pub index: usize,
+    pub total: usize,
+    pub status: StepStatus,
+    pub error: Option<String>,
+}
+
+/// Result of applying a playbook
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationResult {
+    pub run_id: String,
+    pub playbook: RemediationPlaybook,
+    /// Final status per step, in playbook order
+    pub statuses: Vec<StepStatus>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Content and mode of a file before a step changed it (None if it didn't exist)
+#[derive(Debug, Clone)]
+struct FileSnapshot {
+    path: PathBuf,
+    content: Option<Vec<u8>>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    mode: Option<u32>,
+}
+
+impl FileSnapshot {
+    async fn take(path: PathBuf) -> SshResult<Self> {
+        match fs::read(&path).await {
+            Ok(content) => {
+                #[cfg(unix)]
+                let mode = Some(fs::metadata(&path).await?.permissions().mode() & 0o777);
+                #[cfg(not(unix))]
+                let mode = None;
+                Ok(Self {
+                    path,
+                    content: Some(content),
+                    mode,
+                })
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self {
+                path,
+                content: None,
+                mode: None,
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn restore(&self) -> SshResult<()> {
+        match &self.content {
+            Some(content) => {
+                write_atomic(&self.path, content).await?;
+                #[cfg(unix)]
+                if let Some(mode) = self.mode {
+                    fs::set_permissions(&self.path, std::fs::Permissions::from_mode(mode)).await?;
+                }
+            }
+            None if self.path.exists() => fs::remove_file(&self.path).await?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// How to undo a step that ran
+enum Undo {
+    Files(Vec<FileSnapshot>),
+    Unquarantine(String),
+    Nothing,
+}
+
+/// Maps doctor findings to playbooks and applies them step by step
+pub struct RemediationService;
+
+impl RemediationService {
+    /// Playbook for a finding (None when it can't be fixed from here)
+    pub async fn playbook(
+        finding_id: &str,
+        subject: Option<&str>,
+    ) -> SshResult<Option<RemediationPlaybook>> {
+        let subject_required = || {
+            subject
+                .map(str::to_string)
+                .ok_or_else(|| SshBuddyError::InvalidOption {
+                    message: format!("{} needs a subject", finding_id),
+                })
+        };
+        let actions = match finding_id {
+            "keypairs.keypairMismatch" => {
+                let key_name = subject_required()?;
+                let mut actions = vec![RemediationAction::RegeneratePublicKey {
+                    key_name: key_name.clone(),
+                }];
+                // Hosts that got the wrong .pub get the right one
+                for host_alias in Self::hosts_with_public_key(&key_name).await? {
+                    actions.push(RemediationAction::DeployPublicKey {
+                        key_name: key_name.clone(),
+                        host_alias,
+                    });
+                }
+                actions
+            }
+            "keypairs.missingPublicKey" => vec![RemediationAction::RegeneratePublicKey {
+                key_name: subject_required()?,
+            }],
+            "keypairs.orphanedPublicKey" => vec![RemediationAction::RemoveFile {
+                name: format!("{}.pub", subject_required()?),
+            }],
+            "sshDirectory.executableInSshDir"
+            | "sshDirectory.sshRcFile"
+            | "sshDirectory.hiddenFileInSshDir" => vec![RemediationAction::QuarantineFile {
+                name: subject_required()?,
+            }],
+            "permissions.keyTooOpen" => vec![RemediationAction::FixKeyPermissions {
+                key_name: subject_required()?,
+            }],
+            "permissions.sshDirTooOpen" => vec![RemediationAction::FixSshDirPermissions],
+            "sshDirectory.externalConfigChange"
+            | "keyExpiry.certificateExpired"
+            | "keyExpiry.certificateExpiring"
+            | "sshInstallations.noClient" => vec![RemediationAction::Manual],
+            _ => return Ok(None),
+        };
+        let steps = actions
+            .into_iter()
+            .enumerate()
+            .map(|(index, action)| step(index, action, finding_id, subject))
+            .collect();
+        Ok(Some(RemediationPlaybook {
+            finding_id: finding_id.to_string(),
+            subject: subject.map(str::to_string),
+            steps,
+        }))
+    }
+
+    /// Hosts the current `<key>.pub` was deployed to
+    async fn hosts_with_public_key(key_name: &str) -> SshResult<Vec<String>> {
+        let path = ssh_dir()?.join(format!("{}.pub", key_name));
+        let Ok(content) = fs::read_to_string(&path).await else {
+            return Ok(Vec::new());
+        };
+        match PublicKey::from_openssh(content.trim()) {
+            Ok(key) => {
+                KeyExposureService::hosts_with(&key.fingerprint(HashAlg::Sha256).to_string()).await
+            }
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Run the playbook of a finding; local changes are rolled back if a step fails
+    pub async fn apply<F>(
+        request: &RemediationRequest,
+        prompter: Option<&dyn AuthPrompter>,
+        on_progress: F,
+    ) -> SshResult<RemediationResult>
+    where
+        F: Fn(RemediationProgress) + Send + Sync,
+    {
+        let playbook = Self::playbook(&request.finding_id, request.subject.as_deref())
+            .await?
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("No remediation for {}", request.finding_id),
+            })?;
+        let unconfirmed = unconfirmed_steps(&playbook, &request.confirmed_steps);
+        if !unconfirmed.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Confirm these steps first: {}", unconfirmed.join(", ")),
+            });
+        }
+        if playbook.steps.iter().any(|s| s.scope == StepScope::Local) {
+            ReadOnlyMode::ensure_writable("apply a remediation")?;
+        }
+
+        let run_id = now_millis().to_string();
+        let total = playbook.steps.len();
+        let mut statuses = vec![StepStatus::Pending; total];
+        let report = |index: usize, status: StepStatus, error: Option<String>| {
+            on_progress(RemediationProgress {
+                run_id: run_id.clone(),
+                step_id: playbook.steps[index].id.clone(),
+                index,
+                total,
+                status,
+                error,
+            })
+        };
+        tracing::info!(
+            "[remediation] Applying {} step(s) for {} {:?}",
+            total,
+            playbook.finding_id,
+            playbook.subject
+        );
+
+        let mut undo_log: Vec<(usize, Undo)> = Vec::new();
+        let mut failure = None;
+        for (index, step) in playbook.steps.iter().enumerate() {
+            statuses[index] = StepStatus::Running;
+            report(index, StepStatus::Running, None);
+            match execute(&step.action, prompter).await {
+                Ok(undo) => {
+                    statuses[index] = StepStatus::Done;
+                    report(index, StepStatus::Done, None);
+                    undo_log.push((index, undo));
+                }
+                Err(e) => {
+                    tracing::warn!("[remediation] {} failed: {}", step.id, e);
+                    statuses[index] = StepStatus::Failed;
+                    report(index, StepStatus::Failed, Some(e.to_string()));
+                    failure = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        if failure.is_some() {
+            for (index, undo) in undo_log.into_iter().rev() {
+                let status = match rollback(undo).await {
+                    Ok(true) => StepStatus::RolledBack,
+                    Ok(false) => StepStatus::NotRolledBack,
+                    Err(e) => {
+                        tracing::error!(
+                            "[remediation] Rolling back {} failed: {}",
+                            playbook.steps[index].id,
+                            e
+                        );
+                        StepStatus::NotRolledBack
+                    }
+                };
+                statuses[index] = status;
+                report(index, status, None);
+            }
+        }
+
+        Ok(RemediationResult {
+            run_id: run_id.clone(),
+            success: failure.is_none(),
+            error: failure,
+            statuses,
+            playbook,
+        })
+    }
+}
+
+fn step(
+    index: usize,
+    action: RemediationAction,
+    finding_id: &str,
+    subject: Option<&str>,
+) -> RemediationStep {
+    let (scope, requires_confirmation, message) = match &action {
+        RemediationAction::RegeneratePublicKey { key_name } => (
+            StepScope::Local,
+            // Overwrites the mismatched .pub
+            finding_id == "keypairs.keypairMismatch",
+            LocalizedMessage::new("remediation.regeneratePublicKey").with("key", key_name),
+        ),
+        RemediationAction::RemoveFile { name } => (
+            StepScope::Local,
+            true,
+            LocalizedMessage::new("remediation.removeFile").with("name", name),
+        ),
+        RemediationAction::QuarantineFile { name } => (
+            StepScope::Local,
+            true,
+            LocalizedMessage::new("remediation.quarantineFile").with("name", name),
+        ),
+        RemediationAction::FixKeyPermissions { key_name } => (
+            StepScope::Local,
+            false,
+            LocalizedMessage::new("remediation.fixKeyPermissions").with("key", key_name),
+        ),
+        RemediationAction::FixSshDirPermissions => (
+            StepScope::Local,
+            false,
+            LocalizedMessage::new("remediation.fixSshDirPermissions"),
+        ),
+        RemediationAction::DeployPublicKey {
+            key_name,
+            host_alias,
+        } => (
+            StepScope::Remote,
+            true,
+            LocalizedMessage::new("remediation.deployPublicKey")
+                .with("key", key_name)
+                .with("host", host_alias),
+        ),
+        RemediationAction::Manual => (
+            StepScope::Manual,
+            true,
+            manual_message(finding_id, subject.unwrap_or_default()),
+        ),
+    };
+    RemediationStep {
+        id: format!("step-{}", index + 1),
+        action,
+        scope,
+        requires_confirmation,
+        message,
+    }
+}
+
+fn manual_message(finding_id: &str, subject: &str) -> LocalizedMessage {
+    match finding_id {
+        "sshDirectory.externalConfigChange" => {
+            LocalizedMessage::new("remediation.reviewConfigChange").with("name", subject)
+        }
+        "sshInstallations.noClient" => LocalizedMessage::new("remediation.installOpenSsh"),
+        _ => LocalizedMessage::new("remediation.renewCertificate").with("name", subject),
+    }
+}
+
+/// Steps that need a confirmation the request doesn't have
+fn unconfirmed_steps(playbook: &RemediationPlaybook, confirmed: &[String]) -> Vec<String> {
+    playbook
+        .steps
+        .iter()
+        .filter(|s| s.requires_confirmation && !confirmed.contains(&s.id))
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+async fn execute(
+    action: &RemediationAction,
+    prompter: Option<&dyn AuthPrompter>,
+) -> SshResult<Undo> {
+    match action {
+        RemediationAction::RegeneratePublicKey { key_name } => {
+            let snapshot = FileSnapshot::take(ssh_dir()?.join(format!("{}.pub", key_name))).await?;
+            KeyManager::new()?
+                .regenerate_public_key(key_name, true)
+                .await?;
+            IntegrityService::record_key_write(key_name).await;
+            Ok(Undo::Files(vec![snapshot]))
+        }
+        RemediationAction::RemoveFile { name } => {
+            let path = ssh_dir()?.join(name);
+            if path.file_name().and_then(|n| n.to_str()) != Some(name.as_str()) {
+                return Err(SshBuddyError::InvalidPath {
+                    message: format!("{} is not a file name", name),
+                });
+            }
+            let snapshot = FileSnapshot::take(path.clone()).await?;
+            fs::remove_file(&path).await?;
+            IntegrityService::record_app_write(&path).await;
+            tracing::info!("[remediation] Removed {:?}", path);
+            Ok(Undo::Files(vec![snapshot]))
+        }
+        RemediationAction::QuarantineFile { name } => {
+            let entry = TamperDetectionService::quarantine(name).await?;
+            Ok(Undo::Unquarantine(entry.id))
+        }
+        RemediationAction::FixKeyPermissions { key_name } => {
+            let path = ssh_dir()?.join(key_name);
+            let snapshot = FileSnapshot::take(path.clone()).await?;
+            let result = PermissionService::fix_key_permissions(&path.to_string_lossy()).await?;
+            if !result.success {
+                return Err(SshBuddyError::PermissionDenied {
+                    reason: result.message.text,
+                });
+            }
+            Ok(Undo::Files(vec![snapshot]))
+        }
+        RemediationAction::FixSshDirPermissions => {
+            #[cfg(unix)]
+            let previous_mode = {
+                let dir = ssh_dir()?;
+                match fs::metadata(&dir).await {
+                    Ok(metadata) => Some((dir, metadata.permissions().mode() & 0o777)),
+                    Err(_) => None,
+                }
+            };
+            let result = PermissionService::fix_ssh_dir_permissions().await?;
+            if !result.success {
+                return Err(SshBuddyError::PermissionDenied {
+                    reason: result.message.text,
+                });
+            }
+            #[cfg(unix)]
+            if let Some((dir, mode)) = previous_mode {
+                return Ok(Undo::Files(vec![FileSnapshot {
+                    path: dir,
+                    content: None,
+                    mode: Some(mode),
+                }]));
+            }
+            Ok(Undo::Nothing)
+        }
+        RemediationAction::DeployPublicKey {
+            key_name,
+            host_alias,
+        } => {
+            let request = KeyDeployRequest {
+                host_alias: host_alias.clone(),
+                key_name: key_name.clone(),
+                options: AuthorizedKeyOptions::default(),
+            };
+            KeyDeployService::deploy(&request, prompter).await?;
+            Ok(Undo::Nothing)
+        }
+        RemediationAction::Manual => Ok(Undo::Nothing),
+    }
+}
+
+/// Undo a step; false when it can't be undone
+async fn rollback(undo: Undo) -> SshResult<bool> {
+    match undo {
+        Undo::Files(snapshots) => {
+            for snapshot in snapshots.iter().rev() {
+                if snapshot.path.is_dir() {
+                    // Directory snapshots only hold the mode
+                    #[cfg(unix)]
+                    if let Some(mode) = snapshot.mode {
+                        fs::set_permissions(&snapshot.path, std::fs::Permissions::from_mode(mode))
+                            .await?;
+                    }
+                } else {
+                    snapshot.restore().await?;
+                    IntegrityService::record_app_write(&snapshot.path).await;
+                }
+            }
+            Ok(true)
+        }
+        Undo::Unquarantine(id) => {
+            TamperDetectionService::restore(&id).await?;
+            Ok(true)
+        }
+        Undo::Nothing => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unconfirmed_steps() {
+        let playbook = RemediationPlaybook {
+            finding_id: "keypairs.keypairMismatch".to_string(),
+            subject: Some("id_ed25519".to_string()),
+            steps: vec![
+                step(
+                    0,
+                    RemediationAction::RegeneratePublicKey {
+                        key_name: "id_ed25519".to_string(),
+                    },
+                    "keypairs.keypairMismatch",
+                    Some("id_ed25519"),
+                ),
+                step(
+                    1,
+                    RemediationAction::DeployPublicKey {
+                        key_name: "id_ed25519".to_string(),
+                        host_alias: "web".to_string(),
+                    },
+                    "keypairs.keypairMismatch",
+                    Some("id_ed25519"),
+                ),
+            ],
+        };
+        assert_eq!(playbook.steps[1].scope, StepScope::Remote);
+        assert_eq!(
+            unconfirmed_steps(&playbook, &["step-2".to_string()]),
+            vec!["step-1".to_string()]
+        );
+        assert!(
+            unconfirmed_steps(&playbook, &["step-1".to_string(), "step-2".to_string()]).is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restore() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let existing = dir.path().join("id_ed25519.pub");
+        std::fs::write(&existing, "before").unwrap();
+        let created = dir.path().join("new.pub");
+
+        let snapshots = vec![
+            FileSnapshot::take(existing.clone()).await.unwrap(),
+            FileSnapshot::take(created.clone()).await.unwrap(),
+        ];
+        std::fs::write(&existing, "after").unwrap();
+        std::fs::write(&created, "after").unwrap();
+
+        for snapshot in &snapshots {
+            snapshot.restore().await.unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "before");
+        assert!(!created.exists());
+    }
+}
@@ -0,0 +1,404 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::ssh_connection::SshConnectionService;
+use crate::services::tunnel_service::{TunnelDefinition, TunnelManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Timeout for listing the remote listeners
+const DISCOVER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Local ports tried after the remote port is taken: port + offset
+const LOCAL_PORT_OFFSETS: [u16; 3] = [10000, 20000, 30000];
+
+/// POSIX sh script run with `sh -s`; the first line names the tool whose output follows
+/// (`ss` on current Linux, `netstat` on older Linux, BSD and macOS)
+const LISTENERS_SCRIPT: &str = r#"
+if command -v ss >/dev/null 2>&1; then
+  echo '#ss'
+  ss -tlnp 2>/dev/null || ss -tln
+elif command -v netstat >/dev/null 2>&1; then
+  echo '#netstat'
+  netstat -tlnp 2>/dev/null || netstat -an -p tcp 2>/dev/null || netstat -an
+else
+  echo '#none'
+fi
+"#;
+
+/// What a known service is, for grouping suggestions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceKind {
+    Database,
+    Cache,
+    Queue,
+    WebAdmin,
+    Monitoring,
+    Web,
+}
+
+/// Service recognized by port or process name
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownService {
+    pub name: String,
+    pub kind: ServiceKind,
+}
+
+/// TCP socket listening on the remote host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteListener {
+    /// Address as printed, e.g. "127.0.0.1", "::" or "*"
+    pub address: String,
+    pub port: u16,
+    /// Only shown for processes the login user may see
+    pub process: Option<String>,
+    pub pid: Option<u32>,
+    pub service: Option<KnownService>,
+}
+
+/// One-click forward to a discovered service
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelSuggestion {
+    pub service: KnownService,
+    /// Unsaved definition (empty id) ready for `save_tunnel`
+    pub definition: TunnelDefinition,
+    /// Saved tunnel already forwarding to this port
+    pub existing_tunnel_id: Option<String>,
+}
+
+/// Listeners found on a host and the forwards offered for them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerDiscovery {
+    pub host_alias: String,
+    /// "ss" or "netstat"
+    pub source: String,
+    pub listeners: Vec<RemoteListener>,
+    pub suggestions: Vec<TunnelSuggestion>,
+}
+
+/// Finds listening services on a remote host to offer tunnels to them
+pub struct RemoteListenerService;
+
+impl RemoteListenerService {
+    /// List the host's TCP listeners with `ss`/`netstat` and suggest tunnels
+    pub async fn discover(
+        host_alias: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<ListenerDiscovery> {
+        let session = SshConnectionService::open_session(host_alias, prompter).await?;
+        let output = session
+            .exec(
+                "sh -s",
+                Some(LISTENERS_SCRIPT.as_bytes()),
+                DISCOVER_TIMEOUT,
+                |_, _| {},
+            )
+            .await;
+        session.close().await;
+        let output = output?;
+
+        let (source, listeners) = parse_listeners(&output.stdout);
+        let Some(source) = source else {
+            return Err(SshBuddyError::Unknown {
+                message: format!("{} has neither ss nor netstat", host_alias),
+            });
+        };
+
+        let tunnels = TunnelManager::list_definitions().await?;
+        let suggestions = suggest_tunnels(host_alias, &listeners, &tunnels, local_port_free);
+        tracing::info!(
+            "[remote_listeners] {} listener(s) on {}, {} suggestion(s)",
+            listeners.len(),
+            host_alias,
+            suggestions.len()
+        );
+        Ok(ListenerDiscovery {
+            host_alias: host_alias.to_string(),
+            source: source.to_string(),
+            listeners,
+            suggestions,
+        })
+    }
+}
+
+/// Whether a local port can be bound on the loopback address
+fn local_port_free(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// Tool name and listeners from the script output
+fn parse_listeners(output: &str) -> (Option<&'static str>, Vec<RemoteListener>) {
+    let mut lines = output.lines();
+    let source = match lines.find(|l| l.starts_with('#')).map(str::trim) {
+        Some("#ss") => "ss",
+        Some("#netstat") => "netstat",
+        _ => return (None, Vec::new()),
+    };
+    let mut seen = HashSet::new();
+    let listeners = lines
+        .filter_map(|line| match source {
+            "ss" => parse_ss_line(line),
+            _ => parse_netstat_line(line),
+        })
+        .filter(|l| seen.insert((l.address.clone(), l.port)))
+        .collect();
+    (Some(source), listeners)
+}
+
+/// `LISTEN 0 244 127.0.0.1:5432 0.0.0.0:* users:(("postgres",pid=812,fd=6))`
+fn parse_ss_line(line: &str) -> Option<RemoteListener> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.first() != Some(&"LISTEN") {
+        return None;
+    }
+    let (address, port) = split_address(fields.get(3)?)?;
+    let users = fields.iter().find(|f| f.starts_with("users:"));
+    let process = users.and_then(|u| {
+        let start = u.find("((\"")? + 3;
+        let end = start + u[start..].find('"')?;
+        Some(u[start..end].to_string())
+    });
+    let pid = users.and_then(|u| {
+        let start = u.find("pid=")? + 4;
+        let digits: String = u[start..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse().ok()
+    });
+    Some(listener(address, port, process, pid))
+}
+
+/// Linux: `tcp 0 0 127.0.0.1:5432 0.0.0.0:* LISTEN 812/postgres`
+/// BSD/macOS: `tcp4 0 0 127.0.0.1.5432 *.* LISTEN`
+fn parse_netstat_line(line: &str) -> Option<RemoteListener> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if !fields.first()?.starts_with("tcp") || !fields.contains(&"LISTEN") {
+        return None;
+    }
+    let (address, port) = split_address(fields.get(3)?)?;
+    let (pid, process) = match fields.get(6).and_then(|p| p.split_once('/')) {
+        Some((pid, process)) => (pid.parse().ok(), Some(process.to_string())),
+        None => (None, None),
+    };
+    Some(listener(address, port, process, pid))
+}
+
+fn listener(
+    address: String,
+    port: u16,
+    process: Option<String>,
+    pid: Option<u32>,
+) -> RemoteListener {
+    let service = known_service(port, process.as_deref());
+    RemoteListener {
+        address,
+        port,
+        process,
+        pid,
+        service,
+    }
+}
+
+/// Address and port of `127.0.0.1:22`, `[::1]:22`, `*:80`, `:::22` or `127.0.0.1.22` (BSD)
+fn split_address(local: &str) -> Option<(String, u16)> {
+    let separator = if local.contains(':') { ':' } else { '.' };
+    let (address, port) = local.rsplit_once(separator)?;
+    let port = port.parse().ok()?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    // ss appends the interface: 127.0.0.53%lo
+    let address = address.split('%').next().unwrap_or(address);
+    let address = if address.is_empty() { "::" } else { address };
+    Some((address.to_string(), port))
+}
+
+fn known_service(port: u16, process: Option<&str>) -> Option<KnownService> {
+    let by_process = process.and_then(|p| {
+        Some(match p {
+            "postgres" | "postmaster" => ("PostgreSQL", ServiceKind::Database),
+            "mysqld" | "mariadbd" => ("MySQL", ServiceKind::Database),
+            "mongod" => ("MongoDB", ServiceKind::Database),
+            "redis-server" => ("Redis", ServiceKind::Cache),
+            "memcached" => ("Memcached", ServiceKind::Cache),
+            "grafana" | "grafana-server" => ("Grafana", ServiceKind::Monitoring),
+            "prometheus" => ("Prometheus", ServiceKind::Monitoring),
+            _ => return None,
+        })
+    });
+    let (name, kind) = by_process.or(match port {
+        5432 => Some(("PostgreSQL", ServiceKind::Database)),
+        3306 => Some(("MySQL", ServiceKind::Database)),
+        27017 => Some(("MongoDB", ServiceKind::Database)),
+        1433 => Some(("SQL Server", ServiceKind::Database)),
+        1521 => Some(("Oracle", ServiceKind::Database)),
+        9200 => Some(("Elasticsearch", ServiceKind::Database)),
+        8086 => Some(("InfluxDB", ServiceKind::Database)),
+        6379 => Some(("Redis", ServiceKind::Cache)),
+        11211 => Some(("Memcached", ServiceKind::Cache)),
+        5672 => Some(("RabbitMQ", ServiceKind::Queue)),
+        15672 => Some(("RabbitMQ management", ServiceKind::WebAdmin)),
+        10000 => Some(("Webmin", ServiceKind::WebAdmin)),
+        9090 => Some(("Cockpit/Prometheus", ServiceKind::WebAdmin)),
+        5601 => Some(("Kibana", ServiceKind::Monitoring)),
+        3000 => Some(("Grafana/web app", ServiceKind::Web)),
+        80 | 8080 | 8000 | 8888 => Some(("HTTP", ServiceKind::Web)),
+        443 | 8443 => Some(("HTTPS", ServiceKind::Web)),
+        _ => None,
+    })?;
+    Some(KnownService {
+        name: name.to_string(),
+        kind,
+    })
+}
+
+/// Destination for a forward, as seen from the SSH server
+fn forward_destination(address: &str) -> String {
+    match address {
+        "*" | "0.0.0.0" | "::" | "127.0.0.1" | "localhost" => "127.0.0.1".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Forwards to the known services, one per port, with local ports that are free
+/// and not taken by another saved tunnel
+fn suggest_tunnels(
+    host_alias: &str,
+    listeners: &[RemoteListener],
+    tunnels: &[TunnelDefinition],
+    port_free: impl Fn(u16) -> bool,
+) -> Vec<TunnelSuggestion> {
+    let mut taken: HashSet<u16> = tunnels.iter().map(|t| t.local_port).collect();
+    let mut ports = HashSet::new();
+    let mut suggestions = Vec::new();
+    for listener in listeners {
+        let Some(service) = &listener.service else {
+            continue;
+        };
+        if !ports.insert(listener.port) {
+            continue;
+        }
+        let remote_host = forward_destination(&listener.address);
+        let existing_tunnel_id = tunnels
+            .iter()
+            .find(|t| t.host_alias == host_alias && t.remote_port == listener.port)
+            .map(|t| t.id.clone());
+        let local_port = std::iter::once(listener.port)
+            .chain(
+                LOCAL_PORT_OFFSETS
+                    .iter()
+                    .filter_map(|offset| listener.port.checked_add(*offset)),
+            )
+            .find(|port| !taken.contains(port) && port_free(*port))
+            .unwrap_or(0);
+        if local_port == 0 {
+            continue;
+        }
+        taken.insert(local_port);
+        suggestions.push(TunnelSuggestion {
+            service: service.clone(),
+            definition: TunnelDefinition {
+                id: String::new(),
+                name: format!("{} on {}", service.name, host_alias),
+                host_alias: host_alias.to_string(),
+                bind_address: "127.0.0.1".to_string(),
+                local_port,
+                remote_host,
+                remote_port: listener.port,
+                database: None,
+                http_check: None,
+                restore_on_launch: None,
+            },
+            existing_tunnel_id,
+        });
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SS_OUTPUT: &str = "#ss
+State  Recv-Q Send-Q Local Address:Port  Peer Address:Port Process
+LISTEN 0      244        127.0.0.1:5432       0.0.0.0:*     users:((\"postgres\",pid=812,fd=6))
+LISTEN 0      4096       127.0.0.53%lo:53     0.0.0.0:*
+LISTEN 0      128          0.0.0.0:22         0.0.0.0:*
+LISTEN 0      128             [::]:22            [::]:*
+LISTEN 0      511                *:8080             *:*
+";
+
+    #[test]
+    fn test_parse_ss() {
+        let (source, listeners) = parse_listeners(SS_OUTPUT);
+        assert_eq!(source, Some("ss"));
+        assert_eq!(listeners.len(), 5);
+        assert_eq!(listeners[0].address, "127.0.0.1");
+        assert_eq!(listeners[0].process.as_deref(), Some("postgres"));
+        assert_eq!(listeners[0].pid, Some(812));
+        assert_eq!(
+            listeners[0].service.as_ref().map(|s| s.kind),
+            Some(ServiceKind::Database)
+        );
+        assert_eq!(listeners[1].address, "127.0.0.53");
+        assert_eq!(listeners[3].address, "::");
+        assert!(listeners[2].service.is_none());
+    }
+
+    #[test]
+    fn test_parse_netstat() {
+        let output = "#netstat
+Proto Recv-Q Send-Q Local Address Foreign Address State PID/Program name
+tcp        0      0 127.0.0.1:3306  0.0.0.0:*  LISTEN  901/mysqld
+tcp6       0      0 :::22           :::*       LISTEN  -
+tcp4       0      0 127.0.0.1.6379  *.*        LISTEN
+";
+        let (source, listeners) = parse_listeners(output);
+        assert_eq!(source, Some("netstat"));
+        assert_eq!(listeners.len(), 3);
+        assert_eq!(listeners[0].process.as_deref(), Some("mysqld"));
+        assert_eq!(listeners[0].pid, Some(901));
+        assert_eq!(listeners[1].address, "::");
+        assert_eq!(listeners[1].port, 22);
+        assert_eq!(listeners[2].port, 6379);
+        assert_eq!(
+            listeners[2].service.as_ref().map(|s| s.name.as_str()),
+            Some("Redis")
+        );
+        assert_eq!(parse_listeners("#none\n"), (None, Vec::new()));
+    }
+
+    #[test]
+    fn test_suggest_tunnels() {
+        let (_, listeners) = parse_listeners(SS_OUTPUT);
+        let existing = TunnelDefinition {
+            id: "t1".to_string(),
+            name: "db".to_string(),
+            host_alias: "web".to_string(),
+            bind_address: "127.0.0.1".to_string(),
+            local_port: 5432,
+            remote_host: "127.0.0.1".to_string(),
+            remote_port: 5432,
+            database: None,
+            http_check: None,
+            restore_on_launch: None,
+        };
+        // 8080 is busy locally
+        let suggestions = suggest_tunnels("web", &listeners, &[existing], |port| port != 8080);
+        assert_eq!(suggestions.len(), 2);
+
+        let postgres = &suggestions[0].definition;
+        assert_eq!(postgres.local_port, 15432);
+        assert_eq!(postgres.remote_host, "127.0.0.1");
+        assert_eq!(suggestions[0].existing_tunnel_id.as_deref(), Some("t1"));
+
+        let http = &suggestions[1].definition;
+        assert_eq!(http.local_port, 18080);
+        assert_eq!(http.remote_port, 8080);
+        assert_eq!(http.name, "HTTP on web");
+    }
+}
@@ -0,0 +1,855 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::key_deploy::KeyDeployService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::script_service::shell_quote;
+use crate::services::ssh_connection::{ExecOutput, RemoteSession, SshConnectionService};
+use crate::services::sudo_service::SudoService;
+use crate::services::tunnel_export::systemd_quote;
+use crate::utils::{quote_config_arg, workspace_data_path, write_atomic, AuthorizedKeyOptions};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs;
+
+/// Timeout of one step on the device or relay
+const STEP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Seconds between keepalives; the tunnel is restarted after three go unanswered
+const KEEPALIVE_INTERVAL_SECS: u32 = 30;
+
+fn default_target_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_target_port() -> u16 {
+    22
+}
+
+/// Where the systemd unit is installed on the device
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UnitScope {
+    /// `systemctl --user` unit of the login account (lingering is enabled so it starts at boot)
+    #[default]
+    User,
+    /// System unit running as the login account; needs sudo to install
+    System,
+}
+
+/// Persistent remote forward (`ssh -R relay_port:target_host:target_port relay`)
+/// kept up by autossh on a device behind NAT
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseTunnelDefinition {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Host alias the device is reached by while installing
+    pub device_alias: String,
+    /// Host alias of the relay the device connects out to
+    pub relay_alias: String,
+    /// Port opened on the relay's loopback
+    pub relay_port: u16,
+    /// Destination as seen from the device
+    #[serde(default = "default_target_host")]
+    pub target_host: String,
+    #[serde(default = "default_target_port")]
+    pub target_port: u16,
+    #[serde(default)]
+    pub scope: UnitScope,
+    /// Key generated on the device and authorized on the relay, set by install
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_public_key: Option<String>,
+    /// Unix milliseconds of the last install
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_at: Option<i64>,
+}
+
+/// reverse_tunnels.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReverseTunnelStore {
+    #[serde(default)]
+    tunnels: Vec<ReverseTunnelDefinition>,
+}
+
+/// Relay address as the device has to dial it
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RelayEndpoint {
+    pub hostname: String,
+    pub port: u16,
+    pub user: Option<String>,
+}
+
+/// Facts about the device the files are rendered with
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DeviceEnvironment {
+    pub home: String,
+    pub user: String,
+    pub autossh: String,
+}
+
+/// Files installed on the device, plus the ssh config block reaching it through the relay
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseTunnelFiles {
+    pub unit_name: String,
+    pub unit_path: String,
+    pub unit: String,
+    pub config_path: String,
+    pub config: String,
+    pub key_path: String,
+    /// Host block for the local ssh config (ProxyJump through the relay)
+    pub access_config: String,
+}
+
+/// What install did
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseTunnelInstallResult {
+    pub definition: ReverseTunnelDefinition,
+    pub files: ReverseTunnelFiles,
+    /// systemd reported the unit active after enabling it
+    pub active: bool,
+    /// Problems that didn't stop the install (e.g. lingering couldn't be enabled)
+    pub warnings: Vec<String>,
+}
+
+/// State of an installed reverse tunnel as systemd on the device reports it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseTunnelState {
+    pub id: String,
+    /// `systemctl is-active` output ("active", "activating", "failed", ...)
+    pub unit_state: String,
+    pub active: bool,
+}
+
+/// Stores reverse tunnel definitions and installs them on devices
+pub struct ReverseTunnelService;
+
+impl ReverseTunnelService {
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("reverse_tunnels.json")
+    }
+
+    async fn load_store() -> SshResult<ReverseTunnelStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(ReverseTunnelStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse reverse tunnels: {}", e),
+        })
+    }
+
+    async fn save_store(store: &ReverseTunnelStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Saved reverse tunnel definitions
+    pub async fn list() -> SshResult<Vec<ReverseTunnelDefinition>> {
+        Ok(Self::load_store().await?.tunnels)
+    }
+
+    async fn get(id: &str) -> SshResult<ReverseTunnelDefinition> {
+        Self::list()
+            .await?
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Reverse tunnel {} not found", id),
+            })
+    }
+
+    /// Add or replace a definition (matched by id); install state is kept
+    pub async fn save(
+        mut definition: ReverseTunnelDefinition,
+    ) -> SshResult<ReverseTunnelDefinition> {
+        ReadOnlyMode::ensure_writable("save reverse tunnel")?;
+        if definition.id.trim().is_empty() {
+            definition.id = format!("{:08x}", rand::random::<u32>());
+        }
+        definition.target_host = definition.target_host.trim().to_string();
+        if definition.target_host.is_empty() {
+            definition.target_host = default_target_host();
+        }
+
+        let mut store = Self::load_store().await?;
+        validate_definition(&definition, &store.tunnels)?;
+        match store.tunnels.iter_mut().find(|t| t.id == definition.id) {
+            Some(existing) => {
+                definition.device_public_key = existing.device_public_key.clone();
+                definition.installed_at = existing.installed_at;
+                *existing = definition.clone();
+            }
+            None => store.tunnels.push(definition.clone()),
+        }
+        Self::save_store(&store).await?;
+        tracing::info!(
+            "[reverse_tunnel] Saved reverse tunnel {} ({})",
+            definition.id,
+            definition.name
+        );
+        Ok(definition)
+    }
+
+    /// Remove a definition; an installed tunnel keeps running until uninstalled
+    pub async fn delete(id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete reverse tunnel")?;
+        let mut store = Self::load_store().await?;
+        store.tunnels.retain(|t| t.id != id);
+        Self::save_store(&store).await?;
+        tracing::info!("[reverse_tunnel] Deleted reverse tunnel {}", id);
+        Ok(())
+    }
+
+    async fn relay_endpoint(relay_alias: &str) -> SshResult<RelayEndpoint> {
+        let config = SshConnectionService::resolve_host(relay_alias).await?;
+        Ok(RelayEndpoint {
+            hostname: config.get_hostname().to_string(),
+            port: config.get_port(),
+            user: config.user.clone(),
+        })
+    }
+
+    /// Files install would write, without connecting; device paths use placeholders
+    pub async fn preview(id: &str) -> SshResult<ReverseTunnelFiles> {
+        let definition = Self::get(id).await?;
+        let relay = Self::relay_endpoint(&definition.relay_alias).await?;
+        let device = DeviceEnvironment {
+            home: "$HOME".to_string(),
+            user: "$USER".to_string(),
+            autossh: "/usr/bin/autossh".to_string(),
+        };
+        Ok(render(&definition, &relay, &device))
+    }
+
+    /// Install the tunnel on the device: generate its key, write the ssh config and
+    /// systemd unit, authorize the key on the relay (remote forward to the relay port
+    /// only) and enable the unit
+    pub async fn install(
+        id: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<ReverseTunnelInstallResult> {
+        ReadOnlyMode::ensure_writable("install reverse tunnel")?;
+        let mut definition = Self::get(id).await?;
+        let relay = Self::relay_endpoint(&definition.relay_alias).await?;
+
+        let session =
+            SshConnectionService::open_session(&definition.device_alias, prompter).await?;
+        let prepared = Self::prepare_device(&session, &definition).await;
+        let (device, public_key) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                session.close().await;
+                return Err(e);
+            }
+        };
+        let files = render(&definition, &relay, &device);
+
+        // The relay must accept the key before the unit starts dialing it
+        let options = AuthorizedKeyOptions {
+            restrict: true,
+            permit_listen: vec![definition.relay_port.to_string()],
+            ..AuthorizedKeyOptions::default()
+        };
+        let installed = async {
+            KeyDeployService::deploy_public_key(
+                &definition.relay_alias,
+                &public_key,
+                &options,
+                prompter,
+            )
+            .await?;
+            Self::install_files(&session, &definition, &files, prompter).await
+        }
+        .await;
+        session.close().await;
+        let (active, warnings) = installed?;
+
+        definition.device_public_key = Some(public_key);
+        definition.installed_at = Some(now_millis());
+        let mut store = Self::load_store().await?;
+        if let Some(existing) = store.tunnels.iter_mut().find(|t| t.id == definition.id) {
+            *existing = definition.clone();
+        }
+        Self::save_store(&store).await?;
+        tracing::info!(
+            "[reverse_tunnel] Installed {} on {} (relay {}:{}, active={})",
+            definition.id,
+            definition.device_alias,
+            definition.relay_alias,
+            definition.relay_port,
+            active
+        );
+        Ok(ReverseTunnelInstallResult {
+            definition,
+            files,
+            active,
+            warnings,
+        })
+    }
+
+    /// Check autossh, generate the device key and read the device's home and user
+    async fn prepare_device(
+        session: &RemoteSession,
+        definition: &ReverseTunnelDefinition,
+    ) -> SshResult<(DeviceEnvironment, String)> {
+        let output = session
+            .exec(
+                "sh -s",
+                Some(prepare_script(definition).as_bytes()),
+                STEP_TIMEOUT,
+                |_, _| {},
+            )
+            .await?;
+        if !output.success() {
+            return Err(step_error("Preparing the device", &output));
+        }
+        parse_prepare_output(&output.stdout).ok_or_else(|| SshBuddyError::Unknown {
+            message: format!(
+                "Unexpected output while preparing the device: {}",
+                output.stdout.trim()
+            ),
+        })
+    }
+
+    /// Upload the config and unit, then enable the unit; returns whether it's active
+    async fn install_files(
+        session: &RemoteSession,
+        definition: &ReverseTunnelDefinition,
+        files: &ReverseTunnelFiles,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<(bool, Vec<String>)> {
+        session
+            .upload_file(&files.config_path, files.config.as_bytes(), 0o600)
+            .await?;
+        let mut warnings = Vec::new();
+        let unit = shell_quote(&files.unit_name);
+        match definition.scope {
+            UnitScope::User => {
+                session
+                    .upload_file(&files.unit_path, files.unit.as_bytes(), 0o644)
+                    .await?;
+                let command = format!(
+                    "systemctl --user daemon-reload && systemctl --user enable --now {}",
+                    unit
+                );
+                let output = session
+                    .exec(&command, None, STEP_TIMEOUT, |_, _| {})
+                    .await?;
+                if !output.success() {
+                    return Err(step_error("Enabling the unit", &output));
+                }
+                // Without lingering, user units stop at logout and don't start at boot
+                let output = session
+                    .exec("loginctl enable-linger", None, STEP_TIMEOUT, |_, _| {})
+                    .await?;
+                if !output.success() {
+                    warnings.push(format!(
+                        "Lingering couldn't be enabled, so the tunnel only runs while the account is logged in: {}",
+                        output.stderr.trim()
+                    ));
+                }
+            }
+            UnitScope::System => {
+                // SFTP can't write to /etc, so the unit goes next to the key first
+                let staged = format!("{}.service", files.key_path);
+                session
+                    .upload_file(&staged, files.unit.as_bytes(), 0o644)
+                    .await?;
+                let command = format!(
+                    "install -m 644 {staged} {path} && rm -f {staged} \
+                     && systemctl daemon-reload && systemctl enable --now {unit}",
+                    staged = shell_quote(&staged),
+                    path = shell_quote(&files.unit_path),
+                    unit = unit
+                );
+                let output = sudo(session, &command, prompter).await?;
+                if !output.success() {
+                    return Err(step_error("Installing the unit", &output));
+                }
+            }
+        }
+
+        let output = session
+            .exec(
+                &format!("{} is-active {}", systemctl(definition.scope), unit),
+                None,
+                STEP_TIMEOUT,
+                |_, _| {},
+            )
+            .await?;
+        Ok((output.stdout.trim() == "active", warnings))
+    }
+
+    /// Ask systemd on the device whether the tunnel is running
+    pub async fn state(
+        id: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<ReverseTunnelState> {
+        let definition = Self::get(id).await?;
+        let session =
+            SshConnectionService::open_session(&definition.device_alias, prompter).await?;
+        let command = format!(
+            "{} is-active {}",
+            systemctl(definition.scope),
+            shell_quote(&unit_name(&definition.id))
+        );
+        let output = session.exec(&command, None, STEP_TIMEOUT, |_, _| {}).await;
+        session.close().await;
+
+        let unit_state = output?.stdout.trim().to_string();
+        Ok(ReverseTunnelState {
+            id: definition.id,
+            active: unit_state == "active",
+            unit_state,
+        })
+    }
+
+    /// Stop and remove the unit, config and key from the device, and the key from the relay
+    pub async fn uninstall(
+        id: &str,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<ReverseTunnelDefinition> {
+        ReadOnlyMode::ensure_writable("uninstall reverse tunnel")?;
+        let mut definition = Self::get(id).await?;
+        let unit = shell_quote(&unit_name(&definition.id));
+
+        let session =
+            SshConnectionService::open_session(&definition.device_alias, prompter).await?;
+        let removed = async {
+            let files = format!(
+                "rm -f ~/.ssh/{name}.conf ~/.ssh/{name} ~/.ssh/{name}.pub",
+                name = unit_stem(&definition.id)
+            );
+            let output = match definition.scope {
+                UnitScope::User => {
+                    let command = format!(
+                        "systemctl --user disable --now {unit}; \
+                         rm -f ~/.config/systemd/user/{unit} && systemctl --user daemon-reload && {files}",
+                        unit = unit,
+                        files = files
+                    );
+                    session.exec(&command, None, STEP_TIMEOUT, |_, _| {}).await?
+                }
+                UnitScope::System => {
+                    let command = format!(
+                        "systemctl disable --now {unit}; \
+                         rm -f /etc/systemd/system/{unit} && systemctl daemon-reload",
+                        unit = unit
+                    );
+                    let output = sudo(&session, &command, prompter).await?;
+                    if !output.success() {
+                        return Err(step_error("Removing the unit", &output));
+                    }
+                    session.exec(&files, None, STEP_TIMEOUT, |_, _| {}).await?
+                }
+            };
+            if !output.success() {
+                return Err(step_error("Removing the tunnel", &output));
+            }
+            Ok::<_, SshBuddyError>(())
+        }
+        .await;
+        session.close().await;
+        removed?;
+
+        if let Some(public_key) = definition.device_public_key.take() {
+            KeyDeployService::revoke_public_key(&definition.relay_alias, &public_key, prompter)
+                .await?;
+        }
+        definition.installed_at = None;
+        let mut store = Self::load_store().await?;
+        if let Some(existing) = store.tunnels.iter_mut().find(|t| t.id == definition.id) {
+            *existing = definition.clone();
+        }
+        Self::save_store(&store).await?;
+        tracing::info!(
+            "[reverse_tunnel] Uninstalled {} from {}",
+            definition.id,
+            definition.device_alias
+        );
+        Ok(definition)
+    }
+}
+
+async fn sudo(
+    session: &RemoteSession,
+    command: &str,
+    prompter: Option<&dyn AuthPrompter>,
+) -> SshResult<ExecOutput> {
+    let marker = SudoService::new_marker();
+    let command = SudoService::wrap_command(&format!("sh -c {}", shell_quote(command)), &marker);
+    SudoService::exec(
+        session,
+        &command,
+        &marker,
+        prompter,
+        STEP_TIMEOUT,
+        |_, _| {},
+    )
+    .await
+}
+
+fn step_error(step: &str, output: &ExecOutput) -> SshBuddyError {
+    SshBuddyError::Unknown {
+        message: format!(
+            "{} failed (exit {:?}): {}",
+            step,
+            output.exit_code,
+            output.stderr.trim()
+        ),
+    }
+}
+
+fn systemctl(scope: UnitScope) -> &'static str {
+    match scope {
+        UnitScope::User => "systemctl --user",
+        UnitScope::System => "systemctl",
+    }
+}
+
+/// Base name of the device's key, config and unit
+fn unit_stem(id: &str) -> String {
+    format!("ssh-buddy-reverse-{}", id)
+}
+
+fn unit_name(id: &str) -> String {
+    format!("{}.service", unit_stem(id))
+}
+
+/// Host alias used inside the device's config file
+fn relay_host_alias(id: &str) -> String {
+    format!("ssh-buddy-relay-{}", id)
+}
+
+fn validate_definition(
+    definition: &ReverseTunnelDefinition,
+    existing: &[ReverseTunnelDefinition],
+) -> SshResult<()> {
+    let invalid = |message: String| Err(SshBuddyError::InvalidOption { message });
+    // The id ends up in file names and shell commands on the device
+    if !definition
+        .id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return invalid("Reverse tunnel id may only contain letters, digits and -".to_string());
+    }
+    if definition.name.trim().is_empty() || definition.name.chars().any(|c| c.is_control()) {
+        return invalid("Reverse tunnel name is required".to_string());
+    }
+    if definition.device_alias.trim().is_empty() || definition.relay_alias.trim().is_empty() {
+        return invalid("Device and relay hosts are required".to_string());
+    }
+    if definition.device_alias == definition.relay_alias {
+        return invalid("The relay must be a different host than the device".to_string());
+    }
+    // Only root may listen below 1024 on the relay
+    if definition.relay_port < 1024 {
+        return invalid("Relay port must be 1024 or higher".to_string());
+    }
+    if definition.target_port == 0 {
+        return invalid("Target port is required".to_string());
+    }
+    if definition
+        .target_host
+        .chars()
+        .any(|c| c.is_whitespace() || c.is_control())
+    {
+        return invalid("Target host must not contain spaces".to_string());
+    }
+    if let Some(other) = existing.iter().find(|t| {
+        t.id != definition.id
+            && t.relay_alias == definition.relay_alias
+            && t.relay_port == definition.relay_port
+    }) {
+        return invalid(format!(
+            "Relay port {} is already used by {}",
+            definition.relay_port, other.name
+        ));
+    }
+    Ok(())
+}
+
+/// Script run on the device before anything is written
+/// Prints `home=`, `user=`, `autossh=` and `pubkey=` lines
+fn prepare_script(definition: &ReverseTunnelDefinition) -> String {
+    let stem = unit_stem(&definition.id);
+    let mut script = String::from(
+        "set -e\n\
+         AUTOSSH=$(command -v autossh) || { echo 'autossh is not installed on the device' >&2; exit 3; }\n\
+         umask 077\n\
+         mkdir -p ~/.ssh\n",
+    );
+    script.push_str(&format!(
+        "KEY=~/.ssh/{stem}\n\
+         [ -f \"$KEY\" ] || ssh-keygen -q -t ed25519 -N '' -C \"{stem}@$(uname -n)\" -f \"$KEY\"\n",
+        stem = stem
+    ));
+    if definition.scope == UnitScope::User {
+        script.push_str("umask 022\nmkdir -p ~/.config/systemd/user\n");
+    }
+    script.push_str(
+        "echo \"home=$HOME\"\n\
+         echo \"user=$(id -un)\"\n\
+         echo \"autossh=$AUTOSSH\"\n\
+         echo \"pubkey=$(cat \"$KEY.pub\")\"\n",
+    );
+    script
+}
+
+fn parse_prepare_output(stdout: &str) -> Option<(DeviceEnvironment, String)> {
+    let value = |key: &str| {
+        stdout
+            .lines()
+            .find_map(|l| l.trim().strip_prefix(key)?.strip_prefix('='))
+            .map(str::to_string)
+            .filter(|v| !v.is_empty())
+    };
+    Some((
+        DeviceEnvironment {
+            home: value("home")?,
+            user: value("user")?,
+            autossh: value("autossh")?,
+        },
+        value("pubkey")?,
+    ))
+}
+
+/// Render the device's ssh config and systemd unit
+pub(crate) fn render(
+    definition: &ReverseTunnelDefinition,
+    relay: &RelayEndpoint,
+    device: &DeviceEnvironment,
+) -> ReverseTunnelFiles {
+    let stem = unit_stem(&definition.id);
+    let unit_name = unit_name(&definition.id);
+    let alias = relay_host_alias(&definition.id);
+    let config_path = format!("{}/.ssh/{}.conf", device.home, stem);
+    let key_path = format!("{}/.ssh/{}", device.home, stem);
+
+    let mut config = format!(
+        "# Reverse tunnel \"{}\" managed by SSH Buddy\nHost {}\n  HostName {}\n  Port {}\n",
+        definition.name, alias, relay.hostname, relay.port
+    );
+    if let Some(user) = &relay.user {
+        config.push_str(&format!("  User {}\n", user));
+    }
+    config.push_str(&format!(
+        "  IdentityFile {key}\n  IdentitiesOnly yes\n  RemoteForward {port} {host}:{target}\n  \
+         ExitOnForwardFailure yes\n  ServerAliveInterval {keepalive}\n  ServerAliveCountMax 3\n  \
+         StrictHostKeyChecking accept-new\n",
+        key = quote_config_arg(&key_path),
+        port = definition.relay_port,
+        host = definition.target_host,
+        target = definition.target_port,
+        keepalive = KEEPALIVE_INTERVAL_SECS
+    ));
+
+    let (unit_path, user_line, wanted_by) = match definition.scope {
+        UnitScope::User => (
+            format!("{}/.config/systemd/user/{}", device.home, unit_name),
+            String::new(),
+            "default.target",
+        ),
+        UnitScope::System => (
+            format!("/etc/systemd/system/{}", unit_name),
+            format!("User={}\n", device.user),
+            "multi-user.target",
+        ),
+    };
+    // AUTOSSH_GATETIME=0 keeps retrying when the first connection fails (relay down at boot)
+    let unit = format!(
+        "[Unit]\n\
+         Description=SSH Buddy reverse tunnel {name} (relay port {port})\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         {user_line}\
+         Environment=AUTOSSH_GATETIME=0\n\
+         ExecStart={autossh} -M 0 -N -F {config} {alias}\n\
+         Restart=always\n\
+         RestartSec=10\n\
+         \n\
+         [Install]\n\
+         WantedBy={wanted_by}\n",
+        name = definition.name,
+        port = definition.relay_port,
+        user_line = user_line,
+        autossh = systemd_quote(&device.autossh),
+        config = systemd_quote(&config_path),
+        alias = alias,
+        wanted_by = wanted_by
+    );
+
+    let access_config = format!(
+        "Host {device}-via-relay\n  HostName localhost\n  Port {port}\n  ProxyJump {relay}\n",
+        device = definition.device_alias,
+        port = definition.relay_port,
+        relay = definition.relay_alias
+    );
+
+    ReverseTunnelFiles {
+        unit_name,
+        unit_path,
+        unit,
+        config_path,
+        config,
+        key_path,
+        access_config,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(scope: UnitScope) -> ReverseTunnelDefinition {
+        ReverseTunnelDefinition {
+            id: "1a2b3c4d".to_string(),
+            name: "Kiosk".to_string(),
+            device_alias: "kiosk".to_string(),
+            relay_alias: "relay".to_string(),
+            relay_port: 2222,
+            target_host: "localhost".to_string(),
+            target_port: 22,
+            scope,
+            device_public_key: None,
+            installed_at: None,
+        }
+    }
+
+    fn relay() -> RelayEndpoint {
+        RelayEndpoint {
+            hostname: "relay.example.com".to_string(),
+            port: 22,
+            user: Some("tunnel".to_string()),
+        }
+    }
+
+    fn device() -> DeviceEnvironment {
+        DeviceEnvironment {
+            home: "/home/pi".to_string(),
+            user: "pi".to_string(),
+            autossh: "/usr/bin/autossh".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_user_unit() {
+        let files = render(&definition(UnitScope::User), &relay(), &device());
+        assert_eq!(files.unit_name, "ssh-buddy-reverse-1a2b3c4d.service");
+        assert_eq!(
+            files.unit_path,
+            "/home/pi/.config/systemd/user/ssh-buddy-reverse-1a2b3c4d.service"
+        );
+        assert_eq!(
+            files.config_path,
+            "/home/pi/.ssh/ssh-buddy-reverse-1a2b3c4d.conf"
+        );
+        assert!(files.config.contains("Host ssh-buddy-relay-1a2b3c4d\n"));
+        assert!(files.config.contains("  HostName relay.example.com\n"));
+        assert!(files.config.contains("  User tunnel\n"));
+        assert!(files.config.contains("  RemoteForward 2222 localhost:22\n"));
+        assert!(files.config.contains("  ExitOnForwardFailure yes\n"));
+        assert!(files.unit.contains(
+            "ExecStart=/usr/bin/autossh -M 0 -N -F /home/pi/.ssh/ssh-buddy-reverse-1a2b3c4d.conf ssh-buddy-relay-1a2b3c4d\n"
+        ));
+        assert!(files.unit.contains("WantedBy=default.target\n"));
+        assert!(!files.unit.contains("User="));
+        assert!(files.access_config.contains("ProxyJump relay\n"));
+        assert!(files.access_config.contains("Port 2222\n"));
+    }
+
+    #[test]
+    fn test_render_home_with_spaces() {
+        let device = DeviceEnvironment {
+            home: "/home/王 小明".to_string(),
+            ..device()
+        };
+        let files = render(&definition(UnitScope::User), &relay(), &device);
+        assert!(files
+            .config
+            .contains("  IdentityFile \"/home/王 小明/.ssh/ssh-buddy-reverse-1a2b3c4d\"\n"));
+        assert!(files.unit.contains(
+            "-F \"/home/王 小明/.ssh/ssh-buddy-reverse-1a2b3c4d.conf\" ssh-buddy-relay-1a2b3c4d\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_system_unit() {
+        let files = render(&definition(UnitScope::System), &relay(), &device());
+        assert_eq!(
+            files.unit_path,
+            "/etc/systemd/system/ssh-buddy-reverse-1a2b3c4d.service"
+        );
+        assert!(files.unit.contains("User=pi\n"));
+        assert!(files.unit.contains("WantedBy=multi-user.target\n"));
+    }
+
+    #[test]
+    fn test_prepare_script_and_output() {
+        let script = prepare_script(&definition(UnitScope::User));
+        assert!(script.contains("KEY=~/.ssh/ssh-buddy-reverse-1a2b3c4d\n"));
+        assert!(script.contains("mkdir -p ~/.config/systemd/user"));
+        assert!(!prepare_script(&definition(UnitScope::System)).contains("systemd"));
+
+        let (device, key) = parse_prepare_output(
+            "home=/home/pi\nuser=pi\nautossh=/usr/bin/autossh\npubkey=ssh-ed25519 AAAA pi@kiosk\n",
+        )
+        .unwrap();
+        assert_eq!(device, self::device());
+        assert_eq!(key, "ssh-ed25519 AAAA pi@kiosk");
+        assert!(parse_prepare_output("home=/home/pi\nuser=pi\n").is_none());
+    }
+
+    #[test]
+    fn test_validate_definition() {
+        let base = definition(UnitScope::User);
+        assert!(validate_definition(&base, &[]).is_ok());
+
+        for invalid in [
+            ReverseTunnelDefinition {
+                id: "a b; rm".to_string(),
+                ..base.clone()
+            },
+            ReverseTunnelDefinition {
+                relay_alias: "kiosk".to_string(),
+                ..base.clone()
+            },
+            ReverseTunnelDefinition {
+                relay_port: 22,
+                ..base.clone()
+            },
+            ReverseTunnelDefinition {
+                target_host: "local host".to_string(),
+                ..base.clone()
+            },
+        ] {
+            assert!(validate_definition(&invalid, &[]).is_err());
+        }
+
+        // Two devices can't share a port on the same relay
+        let other = ReverseTunnelDefinition {
+            id: "ffff0000".to_string(),
+            device_alias: "camera".to_string(),
+            ..base.clone()
+        };
+        assert!(validate_definition(&other, &[base]).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::audit_service::{AuditKind, AuditService};
+use crate::services::git_versioning::GitVersioningService;
+use crate::services::integrity::IntegrityService;
+use crate::services::registry_service::now_millis;
+use crate::utils::{
+    app_data_dir, split_directive, ssh_config_path, ssh_dir, unified_diff, workspace_data_dir,
+    write_atomic,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Revisions kept per file; older ones are pruned
+const MAX_REVISIONS: usize = 50;
+
+/// Revision id meaning "the file as it is on disk now"
+pub const CURRENT_REVISION: &str = "current";
+
+/// Files whose history is tracked
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ManagedFile {
+    SshConfig,
+    KnownHosts,
+}
+
+impl ManagedFile {
+    /// File name (also used for the revisions directory)
+    pub fn id(self) -> &'static str {
+        match self {
+            ManagedFile::SshConfig => "config",
+            ManagedFile::KnownHosts => "known_hosts",
+        }
+    }
+
+    /// Path of the live file (the SSH config of the active workspace)
+    pub fn live_path(self) -> SshResult<PathBuf> {
+        match self {
+            ManagedFile::SshConfig => ssh_config_path(),
+            ManagedFile::KnownHosts => Ok(ssh_dir()?.join(self.id())),
+        }
+    }
+}
+
+/// Stored revision of a managed file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    pub id: String,
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub size: u64,
+}
+
+/// How a Host block (or known_hosts entry) changed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Change of a single block between two revisions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockChange {
+    /// Host line value ("web-1 web-2"), known_hosts host field, or "(global)"
+    pub block: String,
+    pub kind: BlockChangeKind,
+    /// Option lines present only in the newer revision ("User deploy")
+    pub added: Vec<String>,
+    /// Option lines present only in the older revision
+    pub removed: Vec<String>,
+}
+
+/// Structured diff between two revisions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionDiff {
+    pub from: String,
+    pub to: String,
+    pub blocks: Vec<BlockChange>,
+    /// Unified text diff
+    pub text: String,
+}
+
+/// Block name for options before the first Host/Match line
+const GLOBAL_BLOCK: &str = "(global)";
+
+/// Revision history of the files SSH Buddy writes
+pub struct RevisionService;
+
+impl RevisionService {
+    /// Get the revisions directory of a file; each workspace has its own config history
+    fn get_revisions_dir(file: ManagedFile) -> SshResult<PathBuf> {
+        let base = match file {
+            ManagedFile::SshConfig => workspace_data_dir()?,
+            ManagedFile::KnownHosts => app_data_dir()?,
+        };
+        Ok(base.join("revisions").join(file.id()))
+    }
+
+    /// Record a write: the previous content is stored first if the history is empty
+    /// Also commits to the git history when git versioning is enabled, updates the integrity
+    /// baseline and adds an audit event
+    /// Failures are logged but never fail the write itself
+    pub async fn record_change(
+        file: ManagedFile,
+        previous: Option<&str>,
+        current: &str,
+        message: &str,
+    ) {
+        if let Err(e) = Self::try_record_change(file, previous, current).await {
+            tracing::warn!(
+                "[revision_service] Failed to record {} revision: {}",
+                file.id(),
+                e
+            );
+        }
+        if let Ok(path) = file.live_path() {
+            IntegrityService::record_app_write(&path).await;
+        }
+        GitVersioningService::commit_file(file, current, message).await;
+        AuditService::record(AuditKind::FileChange, file.id(), message).await;
+    }
+
+    async fn try_record_change(
+        file: ManagedFile,
+        previous: Option<&str>,
+        current: &str,
+    ) -> SshResult<()> {
+        let dir = Self::get_revisions_dir(file)?;
+        fs::create_dir_all(&dir).await?;
+
+        let revisions = Self::list_revisions(file).await?;
+        if revisions.is_empty() {
+            if let Some(previous) = previous {
+                Self::store(&dir, previous).await?;
+            }
+        }
+        Self::store(&dir, current).await?;
+        Self::prune(&dir).await
+    }
+
+    /// Store a snapshot under a fresh, time-ordered id
+    async fn store(dir: &Path, content: &str) -> SshResult<()> {
+        let mut timestamp = now_millis();
+        while dir.join(format!("{}.rev", timestamp)).exists() {
+            timestamp += 1;
+        }
+        write_atomic(&dir.join(format!("{}.rev", timestamp)), content.as_bytes()).await
+    }
+
+    /// Remove the oldest revisions beyond MAX_REVISIONS
+    async fn prune(dir: &Path) -> SshResult<()> {
+        let mut ids = Self::revision_ids(dir).await?;
+        if ids.len() <= MAX_REVISIONS {
+            return Ok(());
+        }
+        ids.sort_unstable();
+        for id in &ids[..ids.len() - MAX_REVISIONS] {
+            fs::remove_file(dir.join(format!("{}.rev", id))).await?;
+        }
+        Ok(())
+    }
+
+    /// Timestamps of the stored revisions (unsorted)
+    async fn revision_ids(dir: &Path) -> SshResult<Vec<i64>> {
+        let mut ids = Vec::new();
+        if !dir.exists() {
+            return Ok(ids);
+        }
+
+        let mut entries = fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some(id) = name
+                .strip_suffix(".rev")
+                .and_then(|id| id.parse::<i64>().ok())
+            {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// List revisions, newest first
+    pub async fn list_revisions(file: ManagedFile) -> SshResult<Vec<Revision>> {
+        let dir = Self::get_revisions_dir(file)?;
+        let mut ids = Self::revision_ids(&dir).await?;
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut revisions = Vec::with_capacity(ids.len());
+        for id in ids {
+            let size = fs::metadata(dir.join(format!("{}.rev", id)))
+                .await
+                .map(|m| m.len())
+                .unwrap_or_default();
+            revisions.push(Revision {
+                id: id.to_string(),
+                timestamp: id,
+                size,
+            });
+        }
+        Ok(revisions)
+    }
+
+    /// Read a revision ("current" reads the live file)
+    pub async fn read_revision(file: ManagedFile, revision_id: &str) -> SshResult<String> {
+        let path = if revision_id == CURRENT_REVISION {
+            let path = file.live_path()?;
+            if !path.exists() {
+                return Ok(String::new());
+            }
+            path
+        } else {
+            // Ids are plain timestamps; anything else could escape the directory
+            let id: i64 = revision_id
+                .parse()
+                .map_err(|_| SshBuddyError::InvalidPath {
+                    message: format!("Invalid revision id: {}", revision_id),
+                })?;
+            Self::get_revisions_dir(file)?.join(format!("{}.rev", id))
+        };
+
+        fs::read_to_string(&path)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to read revision {}: {}", revision_id, e),
+            })
+    }
+
+    /// Diff two revisions per block
+    pub async fn diff_revisions(
+        file: ManagedFile,
+        from: &str,
+        to: &str,
+    ) -> SshResult<RevisionDiff> {
+        let old = Self::read_revision(file, from).await?;
+        let new = Self::read_revision(file, to).await?;
+
+        Ok(RevisionDiff {
+            from: from.to_string(),
+            to: to.to_string(),
+            blocks: diff_blocks(&parse_blocks(file, &old), &parse_blocks(file, &new)),
+            text: unified_diff(&old, &new, from, to, 3),
+        })
+    }
+}
+
+/// Split a file into named blocks of normalized option lines
+fn parse_blocks(file: ManagedFile, content: &str) -> Vec<(String, Vec<String>)> {
+    let mut blocks: Vec<(String, Vec<String>)> = Vec::new();
+
+    match file {
+        ManagedFile::SshConfig => {
+            for line in content.lines() {
+                let Some((key, value)) = split_directive(line) else {
+                    continue;
+                };
+                if key.eq_ignore_ascii_case("host") || key.eq_ignore_ascii_case("match") {
+                    let name = if key.eq_ignore_ascii_case("match") {
+                        format!("Match {}", value)
+                    } else {
+                        value
+                    };
+                    blocks.push((name, Vec::new()));
+                } else {
+                    if blocks.is_empty() {
+                        blocks.push((GLOBAL_BLOCK.to_string(), Vec::new()));
+                    }
+                    if let Some((_, options)) = blocks.last_mut() {
+                        options.push(format!("{} {}", key, value));
+                    }
+                }
+            }
+        }
+        ManagedFile::KnownHosts => {
+            for line in content.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let mut parts = line.split_whitespace();
+                let mut host = parts.next().unwrap_or_default();
+                // @cert-authority / @revoked markers come before the host field
+                let marker = if host.starts_with('@') {
+                    let marker = host;
+                    host = parts.next().unwrap_or_default();
+                    Some(marker)
+                } else {
+                    None
+                };
+                let entry: Vec<&str> = marker.into_iter().chain(parts.take(2)).collect();
+
+                match blocks.iter_mut().find(|(name, _)| name.as_str() == host) {
+                    Some((_, entries)) => entries.push(entry.join(" ")),
+                    None => blocks.push((host.to_string(), vec![entry.join(" ")])),
+                }
+            }
+        }
+    }
+
+    // Duplicate block names are kept apart by their position
+    let mut seen: Vec<String> = Vec::new();
+    for (name, _) in blocks.iter_mut() {
+        let count = seen.iter().filter(|n| n.as_str() == name.as_str()).count();
+        seen.push(name.clone());
+        if count > 0 {
+            *name = format!("{} #{}", name, count + 1);
+        }
+    }
+    blocks
+}
+
+/// Compare two block lists
+fn diff_blocks(old: &[(String, Vec<String>)], new: &[(String, Vec<String>)]) -> Vec<BlockChange> {
+    let mut changes = Vec::new();
+
+    for (name, old_options) in old {
+        match new.iter().find(|(n, _)| n == name) {
+            None => changes.push(BlockChange {
+                block: name.clone(),
+                kind: BlockChangeKind::Removed,
+                added: Vec::new(),
+                removed: old_options.clone(),
+            }),
+            Some((_, new_options)) => {
+                let added = multiset_difference(new_options, old_options);
+                let removed = multiset_difference(old_options, new_options);
+                if !added.is_empty() || !removed.is_empty() {
+                    changes.push(BlockChange {
+                        block: name.clone(),
+                        kind: BlockChangeKind::Modified,
+                        added,
+                        removed,
+                    });
+                }
+            }
+        }
+    }
+
+    for (name, new_options) in new {
+        if !old.iter().any(|(n, _)| n == name) {
+            changes.push(BlockChange {
+                block: name.clone(),
+                kind: BlockChangeKind::Added,
+                added: new_options.clone(),
+                removed: Vec::new(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Lines of `a` not matched by a line of `b` (each line of `b` matches once)
+fn multiset_difference(a: &[String], b: &[String]) -> Vec<String> {
+    let mut remaining: Vec<&String> = b.iter().collect();
+    a.iter()
+        .filter(|line| match remaining.iter().position(|r| r == line) {
+            Some(idx) => {
+                remaining.remove(idx);
+                false
+            }
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_block_diff() {
+        let old =
+            "AddKeysToAgent yes\n\nHost web\n    User root\n    Port 22\n\nHost old\n    User x\n";
+        let new = "AddKeysToAgent yes\n\nHost web\n    User deploy\n    Port 22\n\nHost new\n    User y\n";
+
+        let changes = diff_blocks(
+            &parse_blocks(ManagedFile::SshConfig, old),
+            &parse_blocks(ManagedFile::SshConfig, new),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                BlockChange {
+                    block: "web".to_string(),
+                    kind: BlockChangeKind::Modified,
+                    added: vec!["User deploy".to_string()],
+                    removed: vec!["User root".to_string()],
+                },
+                BlockChange {
+                    block: "old".to_string(),
+                    kind: BlockChangeKind::Removed,
+                    added: vec![],
+                    removed: vec!["User x".to_string()],
+                },
+                BlockChange {
+                    block: "new".to_string(),
+                    kind: BlockChangeKind::Added,
+                    added: vec!["User y".to_string()],
+                    removed: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_formatting_only_changes_are_ignored() {
+        let old = "Host web\n    User root\n";
+        let new = "# comment\nHost web\n\tUser=root\n";
+        let changes = diff_blocks(
+            &parse_blocks(ManagedFile::SshConfig, old),
+            &parse_blocks(ManagedFile::SshConfig, new),
+        );
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_known_hosts_blocks() {
+        let content = "github.com ssh-ed25519 AAAA1\n\
+                       github.com ssh-rsa AAAA2\n\
+                       @cert-authority *.corp ssh-ed25519 AAAA3 ca\n";
+        let blocks = parse_blocks(ManagedFile::KnownHosts, content);
+        assert_eq!(
+            blocks,
+            vec![
+                (
+                    "github.com".to_string(),
+                    vec!["ssh-ed25519 AAAA1".to_string(), "ssh-rsa AAAA2".to_string()]
+                ),
+                (
+                    "*.corp".to_string(),
+                    vec!["@cert-authority ssh-ed25519 AAAA3".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiset_difference() {
+        let a = vec!["x".to_string(), "x".to_string(), "y".to_string()];
+        let b = vec!["x".to_string()];
+        assert_eq!(multiset_difference(&a, &b), vec!["x", "y"]);
+    }
+}
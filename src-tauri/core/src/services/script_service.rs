@@ -0,0 +1,212 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::ssh_connection::{OutputStream, RemoteSession, SshConnectionService};
+use crate::services::sudo_service::SudoService;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs;
+
+/// Largest script that will be uploaded
+const MAX_SCRIPT_SIZE: u64 = 1024 * 1024;
+
+/// Script run timeout unless the request says otherwise
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Timeout for the helper commands (mktemp, rm)
+const HELPER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Remote script run request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptRunRequest {
+    pub host_alias: String,
+    /// Absolute path of the local script
+    pub script_path: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Run under sudo (see `SudoService` for how the password is found)
+    #[serde(default)]
+    pub sudo: bool,
+    pub timeout_secs: Option<u64>,
+}
+
+/// Remote script run result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptRunResult {
+    pub host_alias: String,
+    /// Temp path the script was uploaded to
+    pub remote_path: String,
+    pub success: bool,
+    pub exit_code: Option<u32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether the uploaded script was removed afterwards
+    pub cleaned_up: bool,
+}
+
+/// Uploads a local script to a host, runs it and removes it again
+pub struct ScriptService;
+
+impl ScriptService {
+    /// Read the local script (must be an absolute path to a regular, reasonably small file)
+    async fn read_script(script_path: &str) -> SshResult<Vec<u8>> {
+        let path = PathBuf::from(script_path);
+        if !path.is_absolute() {
+            return Err(SshBuddyError::InvalidPath {
+                message: format!("Script path must be absolute: {}", script_path),
+            });
+        }
+        let metadata = fs::metadata(&path)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to read script: {}", e),
+            })?;
+        if !metadata.is_file() {
+            return Err(SshBuddyError::InvalidPath {
+                message: format!("Not a file: {}", script_path),
+            });
+        }
+        if metadata.len() > MAX_SCRIPT_SIZE {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Script is larger than {} KiB", MAX_SCRIPT_SIZE / 1024),
+            });
+        }
+        Ok(fs::read(&path).await?)
+    }
+
+    /// Upload, run and clean up a script, passing output chunks to `on_output`
+    /// Without a prompter, sudo only gets a password saved in the vault
+    pub async fn run<F>(
+        request: &ScriptRunRequest,
+        prompter: Option<&dyn AuthPrompter>,
+        on_output: F,
+    ) -> SshResult<ScriptRunResult>
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let content = Self::read_script(&request.script_path).await?;
+        let session = SshConnectionService::open_session(&request.host_alias, prompter).await?;
+        let result = Self::run_in_session(&session, request, &content, prompter, on_output).await;
+        session.close().await;
+        result
+    }
+
+    async fn run_in_session<F>(
+        session: &RemoteSession,
+        request: &ScriptRunRequest,
+        content: &[u8],
+        prompter: Option<&dyn AuthPrompter>,
+        on_output: F,
+    ) -> SshResult<ScriptRunResult>
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let temp = session
+            .exec(
+                "mktemp /tmp/ssh-buddy-script.XXXXXXXX",
+                None,
+                HELPER_TIMEOUT,
+                |_, _| {},
+            )
+            .await?;
+        let remote_path = temp.stdout.trim().to_string();
+        if !temp.success() || remote_path.is_empty() {
+            return Err(SshBuddyError::IoError {
+                message: format!("Failed to create remote temp file: {}", temp.stderr.trim()),
+            });
+        }
+
+        let uploaded = session.upload_file(&remote_path, content, 0o700).await;
+        let output = match uploaded {
+            Ok(()) => {
+                let command = build_command(&remote_path, &request.args);
+                let limit =
+                    Duration::from_secs(request.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+                if request.sudo {
+                    let marker = SudoService::new_marker();
+                    let command = SudoService::wrap_command(&command, &marker);
+                    SudoService::exec(session, &command, &marker, prompter, limit, on_output).await
+                } else {
+                    session.exec(&command, None, limit, on_output).await
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        // Always try to remove the script, even if the run failed
+        let cleaned_up = session
+            .exec(
+                &format!("rm -f {}", shell_quote(&remote_path)),
+                None,
+                HELPER_TIMEOUT,
+                |_, _| {},
+            )
+            .await
+            .is_ok_and(|rm| rm.success());
+        if !cleaned_up {
+            tracing::warn!(
+                "[script_service] Could not remove {} on {}",
+                remote_path,
+                request.host_alias
+            );
+        }
+
+        let output = output?;
+        tracing::info!(
+            "[script_service] Script on {} exited with {:?}",
+            request.host_alias,
+            output.exit_code
+        );
+        Ok(ScriptRunResult {
+            host_alias: request.host_alias.clone(),
+            remote_path,
+            success: output.success(),
+            exit_code: output.exit_code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            cleaned_up,
+        })
+    }
+}
+
+/// Quote a word for a POSIX shell
+pub fn shell_quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c))
+    {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+/// Command line that runs the uploaded script
+fn build_command(remote_path: &str, args: &[String]) -> String {
+    let mut words = vec![shell_quote(remote_path)];
+    words.extend(args.iter().map(|arg| shell_quote(arg)));
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/tmp/a.sh"), "/tmp/a.sh");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote("$(rm -rf ~)"), "'$(rm -rf ~)'");
+    }
+
+    #[test]
+    fn test_build_command() {
+        let args = vec!["--name".to_string(), "web 1".to_string()];
+        assert_eq!(build_command("/tmp/s", &args), "/tmp/s --name 'web 1'");
+        assert_eq!(build_command("/tmp/s", &[]), "/tmp/s");
+    }
+}
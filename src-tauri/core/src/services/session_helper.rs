@@ -0,0 +1,629 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::settings_service::SettingsService;
+use crate::services::ssh_connection::{
+    ConnectionTestResult, ExecOutput, OutputStream, SshConnectionService,
+};
+use crate::utils::{app_data_dir, SSH_DIR_ENV};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+/// Command line flag starting the executable as the SSH helper
+pub const HELPER_ARG: &str = "--ssh-helper";
+
+/// Environment passed to the helper; everything else (tokens, app secrets) is dropped
+const KEPT_ENV: &[&str] = &[
+    "HOME",
+    "USER",
+    "USERNAME",
+    "LOGNAME",
+    "PATH",
+    "LANG",
+    "LC_ALL",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "SSH_AUTH_SOCK",
+    "KRB5CCNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "SYSTEMROOT",
+    "XDG_DATA_HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_RUNTIME_DIR",
+    "RUST_LOG",
+    SSH_DIR_ENV,
+];
+
+/// Requests without a reply for this long fail (connection tests time out sooner)
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Extra time on top of a command's own limit
+const EXEC_GRACE: Duration = Duration::from_secs(30);
+
+/// This many crashes within `CRASH_WINDOW` stop restarts; connections then run in the
+/// app process until the window has passed
+const MAX_CRASHES: usize = 3;
+const CRASH_WINDOW: Duration = Duration::from_secs(60);
+
+/// Isolation section of the settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IsolationSettings {
+    /// Run non-interactive SSH connections in a separate helper process
+    pub enabled: bool,
+}
+
+impl Default for IsolationSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// State of the helper process
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHelperStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub pid: Option<u32>,
+    /// Crashes within the last minute
+    pub recent_crashes: usize,
+    /// Restarts are paused after repeated crashes; connections run in the app meanwhile
+    pub fallback: bool,
+}
+
+/// Operation sent to the helper
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum HelperRequest {
+    TestConnection {
+        host_alias: String,
+    },
+    Exec {
+        host_alias: String,
+        command: String,
+        timeout_ms: u64,
+    },
+}
+
+/// Message from the helper; `Output` may come any number of times before the
+/// final reply of a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum HelperReply {
+    Output { stream: OutputStream, data: String },
+    ConnectionTest { result: ConnectionTestResult },
+    Exec { output: ExecOutput },
+    Error { error_type: String, message: String },
+}
+
+/// One line of the stdin/stdout protocol
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope<T> {
+    id: u64,
+    #[serde(flatten)]
+    body: T,
+}
+
+type Pending = Arc<Mutex<HashMap<u64, mpsc::UnboundedSender<HelperReply>>>>;
+
+/// Running helper process
+struct Helper {
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    pending: Pending,
+    alive: Arc<AtomicBool>,
+    pid: Option<u32>,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Exit times of helpers that died, for the restart backoff
+static CRASHES: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+
+/// Runs SSH connections in a supervised child process (the app's own executable
+/// started with `--ssh-helper`), so a crash in the SSH stack only takes the helper
+/// down; the helper gets a scrubbed environment and is restarted on the next request
+pub struct SessionHelperService;
+
+impl SessionHelperService {
+    fn current() -> &'static tokio::sync::Mutex<Option<Arc<Helper>>> {
+        static CURRENT: OnceLock<tokio::sync::Mutex<Option<Arc<Helper>>>> = OnceLock::new();
+        CURRENT.get_or_init(|| tokio::sync::Mutex::new(None))
+    }
+
+    pub fn get_settings() -> IsolationSettings {
+        SettingsService::get().isolation
+    }
+
+    pub async fn set_settings(settings: &IsolationSettings) -> SshResult<()> {
+        SettingsService::update(|current| {
+            current.isolation = settings.clone();
+            Ok(())
+        })
+        .await?;
+        if !settings.enabled {
+            // Closing stdin stops the helper once its running requests are done
+            Self::current().lock().await.take();
+        }
+        tracing::info!(
+            "[session_helper] SSH process isolation {}",
+            if settings.enabled { "on" } else { "off" }
+        );
+        Ok(())
+    }
+
+    pub async fn status() -> SessionHelperStatus {
+        let helper = Self::current().lock().await.clone();
+        let running = helper
+            .as_ref()
+            .is_some_and(|h| h.alive.load(Ordering::SeqCst));
+        let recent_crashes = recent_crashes(Instant::now());
+        SessionHelperStatus {
+            enabled: Self::get_settings().enabled,
+            running,
+            pid: helper.filter(|_| running).and_then(|h| h.pid),
+            recent_crashes,
+            fallback: recent_crashes >= MAX_CRASHES,
+        }
+    }
+
+    /// Non-interactive connection test, in the helper when isolation is on
+    pub async fn test_connection(host_alias: &str) -> SshResult<ConnectionTestResult> {
+        let request = HelperRequest::TestConnection {
+            host_alias: host_alias.to_string(),
+        };
+        match Self::call(&request, REQUEST_TIMEOUT, |_, _| {}).await? {
+            Some(HelperReply::ConnectionTest { result }) => Ok(result),
+            Some(reply) => Err(reply_error(reply)),
+            None => SshConnectionService::test_connection(host_alias).await,
+        }
+    }
+
+    /// Connect, run `command` and disconnect, in the helper when isolation is on
+    pub async fn exec<F>(
+        host_alias: &str,
+        command: &str,
+        limit: Duration,
+        mut on_output: F,
+    ) -> SshResult<ExecOutput>
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let request = HelperRequest::Exec {
+            host_alias: host_alias.to_string(),
+            command: command.to_string(),
+            timeout_ms: limit.as_millis() as u64,
+        };
+        match Self::call(&request, limit + EXEC_GRACE, &mut on_output).await? {
+            Some(HelperReply::Exec { output }) => Ok(output),
+            Some(reply) => Err(reply_error(reply)),
+            None => exec_in_process(host_alias, command, limit, on_output).await,
+        }
+    }
+
+    /// Send a request and wait for its final reply; None when the helper is off or
+    /// can't run (the caller then works in-process)
+    async fn call<F>(
+        request: &HelperRequest,
+        limit: Duration,
+        mut on_output: F,
+    ) -> SshResult<Option<HelperReply>>
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let Some(helper) = Self::helper().await else {
+            return Ok(None);
+        };
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        lock(&helper.pending).insert(id, tx);
+
+        let line = encode(&Envelope {
+            id,
+            body: request.clone(),
+        })?;
+        let written = {
+            let mut stdin = helper.stdin.lock().await;
+            match stdin.write_all(line.as_bytes()).await {
+                Ok(()) => stdin.flush().await,
+                Err(e) => Err(e),
+            }
+        };
+        if let Err(e) = written {
+            // Nothing was sent, so running it here can't run it twice
+            lock(&helper.pending).remove(&id);
+            tracing::warn!("[session_helper] Helper is not accepting requests: {}", e);
+            return Ok(None);
+        }
+
+        let wait = async {
+            while let Some(reply) = rx.recv().await {
+                match reply {
+                    HelperReply::Output { stream, data } => on_output(stream, &data),
+                    reply => return Ok(Some(reply)),
+                }
+            }
+            Err(SshBuddyError::Unknown {
+                message: "The SSH helper process exited during the request".to_string(),
+            })
+        };
+        let result = timeout(limit, wait).await;
+        lock(&helper.pending).remove(&id);
+        result.map_err(|_| SshBuddyError::ConnectionTimeout)?
+    }
+
+    /// Entry point of the helper process: answer requests from stdin until the app
+    /// closes it
+    pub fn serve() {
+        let _ = tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_ansi(false)
+            .without_time()
+            .with_target(false)
+            .try_init();
+        match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime.block_on(serve_requests()),
+            Err(e) => tracing::error!("[session_helper] Failed to start the runtime: {}", e),
+        }
+    }
+
+    /// Running helper, starting one when needed
+    async fn helper() -> Option<Arc<Helper>> {
+        if !Self::get_settings().enabled {
+            return None;
+        }
+        let mut current = Self::current().lock().await;
+        if let Some(helper) = current.as_ref().filter(|h| h.alive.load(Ordering::SeqCst)) {
+            return Some(helper.clone());
+        }
+        if recent_crashes(Instant::now()) >= MAX_CRASHES {
+            return None;
+        }
+        match spawn_helper() {
+            Ok(helper) => {
+                let helper = Arc::new(helper);
+                tracing::info!("[session_helper] Started SSH helper (pid {:?})", helper.pid);
+                *current = Some(helper.clone());
+                Some(helper)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "[session_helper] Failed to start SSH helper, connecting in-process: {}",
+                    e
+                );
+                record_crash();
+                None
+            }
+        }
+    }
+}
+
+fn spawn_helper() -> SshResult<Helper> {
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    command
+        .arg(HELPER_ARG)
+        .env_clear()
+        .envs(std::env::vars_os().filter(|(name, _)| keep_env(&name.to_string_lossy())))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Ok(dir) = app_data_dir() {
+        if dir.is_dir() {
+            command.current_dir(dir);
+        }
+    }
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    let (Some(stdin), Some(stdout), Some(stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        return Err(SshBuddyError::Unknown {
+            message: "SSH helper has no stdio".to_string(),
+        });
+    };
+    let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+    let alive = Arc::new(AtomicBool::new(true));
+
+    // Helper logs go to stderr; forward them to the app log
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::info!("[session_helper] helper: {}", line);
+        }
+    });
+
+    let (reader_pending, reader_alive) = (pending.clone(), alive.clone());
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            match serde_json::from_str::<Envelope<HelperReply>>(&line) {
+                Ok(reply) => {
+                    if let Some(tx) = lock(&reader_pending).get(&reply.id) {
+                        let _ = tx.send(reply.body);
+                    }
+                }
+                Err(e) => tracing::warn!("[session_helper] Unreadable helper reply: {}", e),
+            }
+        }
+        reader_alive.store(false, Ordering::SeqCst);
+        // Dropping the senders fails the requests still waiting
+        lock(&reader_pending).clear();
+        match child.wait().await {
+            Ok(status) if status.success() => {
+                tracing::info!("[session_helper] SSH helper stopped")
+            }
+            Ok(status) => {
+                tracing::warn!("[session_helper] SSH helper crashed ({})", status);
+                record_crash();
+            }
+            Err(e) => {
+                tracing::warn!("[session_helper] SSH helper lost: {}", e);
+                record_crash();
+            }
+        }
+    });
+
+    Ok(Helper {
+        stdin: tokio::sync::Mutex::new(stdin),
+        pending,
+        alive,
+        pid,
+    })
+}
+
+async fn serve_requests() {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Envelope<HelperReply>>();
+    tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(reply) = rx.recv().await {
+            let Ok(line) = encode(&reply) else {
+                continue;
+            };
+            if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let request = match serde_json::from_str::<Envelope<HelperRequest>>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::warn!("[session_helper] Ignoring unreadable request: {}", e);
+                continue;
+            }
+        };
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let id = request.id;
+            let output_tx = tx.clone();
+            let body = handle(request.body, move |stream, data| {
+                let _ = output_tx.send(Envelope {
+                    id,
+                    body: HelperReply::Output {
+                        stream,
+                        data: data.to_string(),
+                    },
+                });
+            })
+            .await;
+            let _ = tx.send(Envelope { id, body });
+        });
+    }
+}
+
+/// Run one request in the helper
+async fn handle<F>(request: HelperRequest, on_output: F) -> HelperReply
+where
+    F: FnMut(OutputStream, &str),
+{
+    // Proxy, workspace and SSH root may have changed in the app since the last request
+    SettingsService::reload();
+    let result = match request {
+        HelperRequest::TestConnection { host_alias } => {
+            SshConnectionService::test_connection(&host_alias)
+                .await
+                .map(|result| HelperReply::ConnectionTest { result })
+        }
+        HelperRequest::Exec {
+            host_alias,
+            command,
+            timeout_ms,
+        } => exec_in_process(
+            &host_alias,
+            &command,
+            Duration::from_millis(timeout_ms),
+            on_output,
+        )
+        .await
+        .map(|output| HelperReply::Exec { output }),
+    };
+    result.unwrap_or_else(|e| HelperReply::Error {
+        error_type: e.error_type().to_string(),
+        message: e.to_string(),
+    })
+}
+
+async fn exec_in_process<F>(
+    host_alias: &str,
+    command: &str,
+    limit: Duration,
+    on_output: F,
+) -> SshResult<ExecOutput>
+where
+    F: FnMut(OutputStream, &str),
+{
+    let session = SshConnectionService::open_session(host_alias, None).await?;
+    let output = session.exec(command, None, limit, on_output).await;
+    session.close().await;
+    output
+}
+
+/// Error for a reply that isn't the expected result
+fn reply_error(reply: HelperReply) -> SshBuddyError {
+    match reply {
+        HelperReply::Error { error_type, .. } if error_type == "ConnectionTimeout" => {
+            SshBuddyError::ConnectionTimeout
+        }
+        HelperReply::Error { message, .. } => SshBuddyError::Unknown { message },
+        _ => SshBuddyError::Unknown {
+            message: "Unexpected reply from the SSH helper".to_string(),
+        },
+    }
+}
+
+fn encode<T: Serialize>(message: &T) -> SshResult<String> {
+    let mut line = serde_json::to_string(message).map_err(|e| SshBuddyError::Unknown {
+        message: e.to_string(),
+    })?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Variables the helper needs: home and user lookup, the agent and Kerberos
+/// sockets, the SSH root override, and proxies
+fn keep_env(name: &str) -> bool {
+    KEPT_ENV.iter().any(|kept| kept.eq_ignore_ascii_case(name))
+        || name.to_ascii_uppercase().ends_with("_PROXY")
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn record_crash() {
+    let now = Instant::now();
+    let mut crashes = lock(&CRASHES);
+    crashes.push_back(now);
+    while crashes.len() > MAX_CRASHES {
+        crashes.pop_front();
+    }
+}
+
+fn recent_crashes(now: Instant) -> usize {
+    lock(&CRASHES)
+        .iter()
+        .filter(|at| now.duration_since(**at) < CRASH_WINDOW)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_encoding() {
+        let line = encode(&Envelope {
+            id: 7,
+            body: HelperRequest::Exec {
+                host_alias: "web".to_string(),
+                command: "uptime".to_string(),
+                timeout_ms: 5000,
+            },
+        })
+        .unwrap();
+        assert!(line.ends_with('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["id"], 7);
+        assert_eq!(value["type"], "exec");
+        assert_eq!(value["hostAlias"], "web");
+        assert_eq!(value["timeoutMs"], 5000);
+
+        let decoded: Envelope<HelperRequest> = serde_json::from_str(&line).unwrap();
+        assert_eq!(decoded.id, 7);
+        assert_eq!(
+            decoded.body,
+            HelperRequest::Exec {
+                host_alias: "web".to_string(),
+                command: "uptime".to_string(),
+                timeout_ms: 5000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_reply_decoding() {
+        let output: Envelope<HelperReply> =
+            serde_json::from_str(r#"{"id":3,"type":"output","stream":"stderr","data":"x"}"#)
+                .unwrap();
+        assert_eq!(output.id, 3);
+        assert!(matches!(
+            output.body,
+            HelperReply::Output {
+                stream: OutputStream::Stderr,
+                ..
+            }
+        ));
+
+        let line = encode(&Envelope {
+            id: 4,
+            body: HelperReply::Exec {
+                output: ExecOutput {
+                    exit_code: Some(0),
+                    stdout: "up 3 days".to_string(),
+                    stderr: String::new(),
+                },
+            },
+        })
+        .unwrap();
+        let decoded: Envelope<HelperReply> = serde_json::from_str(&line).unwrap();
+        match decoded.body {
+            HelperReply::Exec { output } => assert_eq!(output.stdout, "up 3 days"),
+            other => panic!("unexpected reply {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reply_error() {
+        let timeout = reply_error(HelperReply::Error {
+            error_type: "ConnectionTimeout".to_string(),
+            message: "Connection timeout".to_string(),
+        });
+        assert!(matches!(timeout, SshBuddyError::ConnectionTimeout));
+        let other = reply_error(HelperReply::Error {
+            error_type: "HostNotFound".to_string(),
+            message: "Host not found in SSH config: web".to_string(),
+        });
+        assert!(matches!(
+            other,
+            SshBuddyError::Unknown { message } if message == "Host not found in SSH config: web"
+        ));
+    }
+
+    #[test]
+    fn test_keep_env() {
+        assert!(keep_env("HOME"));
+        assert!(keep_env("SSH_AUTH_SOCK"));
+        assert!(keep_env("https_proxy"));
+        assert!(keep_env("Path"));
+        assert!(keep_env(SSH_DIR_ENV));
+        assert!(!keep_env("GITHUB_TOKEN"));
+        assert!(!keep_env("AWS_SECRET_ACCESS_KEY"));
+    }
+}
@@ -0,0 +1,325 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use crate::services::tunnel_service::{TunnelDefinition, TunnelManager};
+use crate::utils::{workspace_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::fs;
+
+/// Restore section of the settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RestoreSettings {
+    /// Re-establish the tunnels and pinned sessions that were active at shutdown
+    /// (tunnels and sessions can override this one by one)
+    pub on_launch: bool,
+}
+
+/// Shell session kept open across restarts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedSession {
+    pub session_id: String,
+    pub host_alias: String,
+    /// Command the session runs instead of a login shell (a tmux attach is re-attached)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Overrides the global restore-on-launch setting for this session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restore_on_launch: Option<bool>,
+    /// Unix milliseconds
+    pub pinned_at: i64,
+}
+
+/// session_restore.json contents: what is active right now, so whatever is left at
+/// shutdown (or a crash) is what gets restored
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreStore {
+    /// Ids of running tunnels
+    #[serde(default)]
+    tunnels: Vec<String>,
+    #[serde(default)]
+    sessions: Vec<PinnedSession>,
+}
+
+impl PinnedSession {
+    /// Record for a session that was just pinned
+    pub fn new(
+        session_id: &str,
+        host_alias: &str,
+        command: Option<String>,
+        restore_on_launch: Option<bool>,
+    ) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            host_alias: host_alias.to_string(),
+            command,
+            restore_on_launch,
+            pinned_at: now_millis(),
+        }
+    }
+}
+
+/// What to re-establish at launch
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RestorePlan {
+    pub tunnels: Vec<TunnelDefinition>,
+    pub sessions: Vec<PinnedSession>,
+}
+
+/// One tunnel or session the restore went through
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredItem {
+    /// Tunnel id, or the new session id of a restored session
+    pub id: String,
+    /// Tunnel name or session host
+    pub name: String,
+    pub restored: bool,
+    pub error: Option<String>,
+}
+
+/// Outcome of the restore at launch
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSummary {
+    /// Unix milliseconds
+    pub finished_at: i64,
+    pub tunnels: Vec<RestoredItem>,
+    pub sessions: Vec<RestoredItem>,
+}
+
+impl RestoreSummary {
+    pub fn failed(&self) -> usize {
+        self.tunnels
+            .iter()
+            .chain(&self.sessions)
+            .filter(|i| !i.restored)
+            .count()
+    }
+}
+
+/// Summary of this launch's restore, kept for a frontend that wasn't listening yet
+static LAST_SUMMARY: Mutex<Option<RestoreSummary>> = Mutex::new(None);
+
+/// Serializes read-modify-write of the store (tunnel events arrive concurrently)
+static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Tracks active tunnels and pinned sessions and re-establishes them at launch
+pub struct SessionRestoreService;
+
+impl SessionRestoreService {
+    pub fn get_settings() -> RestoreSettings {
+        SettingsService::get().restore
+    }
+
+    pub async fn set_settings(settings: &RestoreSettings) -> SshResult<()> {
+        SettingsService::update(|current| {
+            current.restore = settings.clone();
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[session_restore] Restore on launch {}",
+            if settings.on_launch { "on" } else { "off" }
+        );
+        Ok(())
+    }
+
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("session_restore.json")
+    }
+
+    async fn load_store() -> SshResult<RestoreStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(RestoreStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse session restore state: {}", e),
+        })
+    }
+
+    async fn save_store(store: &RestoreStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    async fn update<F>(change: F) -> SshResult<()>
+    where
+        F: FnOnce(&mut RestoreStore),
+    {
+        let _guard = STORE_LOCK.lock().await;
+        let mut store = Self::load_store().await?;
+        change(&mut store);
+        Self::save_store(&store).await
+    }
+
+    /// Record a tunnel as running
+    pub async fn tunnel_started(id: &str) -> SshResult<()> {
+        Self::update(|store| {
+            if !store.tunnels.iter().any(|t| t == id) {
+                store.tunnels.push(id.to_string());
+            }
+        })
+        .await
+    }
+
+    /// Record a tunnel as no longer running (stopped or dropped)
+    pub async fn tunnel_ended(id: &str) -> SshResult<()> {
+        Self::update(|store| store.tunnels.retain(|t| t != id)).await
+    }
+
+    /// Pinned sessions that are open
+    pub async fn pinned_sessions() -> SshResult<Vec<PinnedSession>> {
+        Ok(Self::load_store().await?.sessions)
+    }
+
+    /// Pin a session (or update its restore override)
+    pub async fn pin_session(session: PinnedSession) -> SshResult<()> {
+        tracing::info!(
+            "[session_restore] Pinned session {} on {}",
+            session.session_id,
+            session.host_alias
+        );
+        Self::update(|store| {
+            store
+                .sessions
+                .retain(|s| s.session_id != session.session_id);
+            store.sessions.push(session);
+        })
+        .await
+    }
+
+    /// Unpin a session; also called when a pinned session closes
+    pub async fn unpin_session(session_id: &str) -> SshResult<()> {
+        Self::update(|store| store.sessions.retain(|s| s.session_id != session_id)).await
+    }
+
+    /// What was active at shutdown and is set to be restored
+    /// The store is cleared: restored tunnels and sessions record themselves again
+    /// once they're up, so whatever isn't restored is forgotten
+    pub async fn take_plan() -> SshResult<RestorePlan> {
+        let store = {
+            let _guard = STORE_LOCK.lock().await;
+            let store = Self::load_store().await?;
+            Self::save_store(&RestoreStore::default()).await?;
+            store
+        };
+        let definitions = TunnelManager::list_definitions().await?;
+        Ok(plan(&store, &definitions, Self::get_settings().on_launch))
+    }
+
+    /// Stamp and keep the summary of the restore at launch
+    pub fn finish(mut summary: RestoreSummary) -> RestoreSummary {
+        summary.finished_at = now_millis();
+        tracing::info!(
+            "[session_restore] Restored {} tunnel(s) and {} session(s), {} failed",
+            summary.tunnels.iter().filter(|t| t.restored).count(),
+            summary.sessions.iter().filter(|s| s.restored).count(),
+            summary.failed()
+        );
+        if let Ok(mut last) = LAST_SUMMARY.lock() {
+            *last = Some(summary.clone());
+        }
+        summary
+    }
+
+    /// Summary of the restore at this launch (None while it runs or when nothing was restored)
+    pub fn last_summary() -> Option<RestoreSummary> {
+        LAST_SUMMARY.lock().ok().and_then(|last| last.clone())
+    }
+}
+
+/// Apply the global setting and per-item overrides; tunnels deleted since are skipped
+fn plan(store: &RestoreStore, definitions: &[TunnelDefinition], on_launch: bool) -> RestorePlan {
+    let tunnels = store
+        .tunnels
+        .iter()
+        .filter_map(|id| definitions.iter().find(|d| &d.id == id))
+        .filter(|d| d.restore_on_launch.unwrap_or(on_launch))
+        .cloned()
+        .collect();
+    let sessions = store
+        .sessions
+        .iter()
+        .filter(|s| s.restore_on_launch.unwrap_or(on_launch))
+        .cloned()
+        .collect();
+    RestorePlan { tunnels, sessions }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tunnel(id: &str, restore_on_launch: Option<bool>) -> TunnelDefinition {
+        TunnelDefinition {
+            id: id.to_string(),
+            name: id.to_string(),
+            host_alias: "web".to_string(),
+            bind_address: "127.0.0.1".to_string(),
+            local_port: 8080,
+            remote_host: "localhost".to_string(),
+            remote_port: 80,
+            database: None,
+            http_check: None,
+            restore_on_launch,
+        }
+    }
+
+    fn session(id: &str, restore_on_launch: Option<bool>) -> PinnedSession {
+        PinnedSession {
+            restore_on_launch,
+            ..PinnedSession::new(id, "web", None, None)
+        }
+    }
+
+    #[test]
+    fn test_plan_follows_global_setting() {
+        let store = RestoreStore {
+            tunnels: vec!["a".to_string(), "deleted".to_string()],
+            sessions: vec![session("s1", None)],
+        };
+        let definitions = vec![tunnel("a", None), tunnel("b", None)];
+
+        let restored = plan(&store, &definitions, true);
+        assert_eq!(restored.tunnels, vec![tunnel("a", None)]);
+        assert_eq!(restored.sessions.len(), 1);
+
+        assert_eq!(plan(&store, &definitions, false), RestorePlan::default());
+    }
+
+    #[test]
+    fn test_plan_per_item_overrides() {
+        let store = RestoreStore {
+            tunnels: vec!["always".to_string(), "never".to_string()],
+            sessions: vec![session("s1", Some(true)), session("s2", Some(false))],
+        };
+        let definitions = vec![tunnel("always", Some(true)), tunnel("never", Some(false))];
+
+        let off = plan(&store, &definitions, false);
+        assert_eq!(off.tunnels, vec![tunnel("always", Some(true))]);
+        assert_eq!(
+            off.sessions
+                .iter()
+                .map(|s| s.session_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["s1"]
+        );
+
+        let on = plan(&store, &definitions, true);
+        assert_eq!(on.tunnels.len(), 1);
+        assert_eq!(on.sessions.len(), 1);
+    }
+}
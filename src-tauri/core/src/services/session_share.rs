@@ -0,0 +1,883 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::registry_service::now_millis;
+use crate::services::shell_session::ShellSessionManager;
+use crate::utils::{
+    decrypt_with_key, encrypt_with_key, parse_deep_link, DeepLink, SealedValue, KEY_LEN,
+};
+use base64::Engine;
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::time::timeout;
+
+/// Sent first by the sharing side; also mixed into the stream key
+const PROTOCOL: &str = "ssh-buddy-observe/1";
+
+/// Longest a share stays open
+pub const MAX_SHARE_MINUTES: u32 = 240;
+
+/// Observers connected to one share at the same time
+const MAX_VIEWERS: usize = 4;
+
+/// Connections of one share still in the handshake; they don't take observer slots
+const MAX_PENDING_HANDSHAKES: usize = 8;
+
+/// Time to connect and prove the key
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest handshake message (bytes)
+const MAX_HANDSHAKE_LINE: usize = 4 * 1024;
+
+/// Longest encrypted frame (bytes); output is sent in chunks well below it
+const MAX_FRAME_LINE: usize = 256 * 1024;
+
+/// Characters of terminal output per frame
+const OUTPUT_CHUNK_CHARS: usize = 16 * 1024;
+
+const CHALLENGE_LEN: usize = 32;
+
+/// Why an observed stream ended
+const REASON_STOPPED: &str = "stopped";
+const REASON_EXPIRED: &str = "expired";
+const REASON_SESSION_CLOSED: &str = "sessionClosed";
+const REASON_DISCONNECTED: &str = "disconnected";
+const REASON_FULL: &str = "full";
+
+/// A terminal session mirrored read-only to other app instances on the LAN
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionShare {
+    pub id: String,
+    pub session_id: String,
+    pub host_alias: String,
+    /// `sshbuddy://observe?...` link for the colleague; it carries the secret key
+    pub link: String,
+    /// `<LAN address>:<port>` observers connect to
+    pub address: String,
+    /// Unix milliseconds
+    pub expires_at: i64,
+    /// Observers currently connected
+    pub viewers: usize,
+}
+
+/// What an observer receives, forwarded to its frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ObserveEvent {
+    #[serde(rename_all = "camelCase")]
+    Started {
+        viewer_id: String,
+        host_alias: String,
+        expires_at: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Output { viewer_id: String, data: String },
+    /// `reason` is "stopped", "expired", "sessionClosed" or "disconnected"
+    #[serde(rename_all = "camelCase")]
+    Ended { viewer_id: String, reason: String },
+}
+
+/// First message of the sharing side
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Challenge {
+    protocol: String,
+    /// base64, random per connection
+    challenge: String,
+}
+
+/// Observer's answer: the challenge encrypted with the share key proves it has the link
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Hello {
+    share_id: String,
+    /// base64, random per connection; with the challenge it makes the stream key unique
+    nonce: String,
+    proof: SealedValue,
+}
+
+/// Encrypted frame content; `seq` counts up from 0 so dropped or replayed frames are noticed
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Frame {
+    seq: u64,
+    body: FrameBody,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum FrameBody {
+    #[serde(rename_all = "camelCase")]
+    Start {
+        host_alias: String,
+        expires_at: i64,
+    },
+    Output {
+        data: String,
+    },
+    End {
+        reason: String,
+    },
+}
+
+struct ShareEntry {
+    share: SessionShare,
+    viewers: Arc<AtomicUsize>,
+    /// Set to the reason when the share stops
+    stop: watch::Sender<Option<&'static str>>,
+}
+
+/// Shares a terminal session with, and watches sessions shared by, other instances of the
+/// app on the local network
+/// The link holds a random 256-bit key: observers prove they know it before anything is
+/// sent, and the stream is AES-256-GCM encrypted with a key derived from it per connection
+/// Observers can't type into the session; anything they send is ignored
+pub struct SessionShareService {
+    shares: Mutex<HashMap<String, ShareEntry>>,
+    observing: Mutex<HashMap<String, watch::Sender<bool>>>,
+}
+
+impl SessionShareService {
+    /// Global service
+    pub fn global() -> &'static SessionShareService {
+        static SERVICE: OnceLock<SessionShareService> = OnceLock::new();
+        SERVICE.get_or_init(|| SessionShareService {
+            shares: Mutex::new(HashMap::new()),
+            observing: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Start mirroring a shell session for `minutes`; returns the link to hand over
+    pub async fn start(&'static self, session_id: &str, minutes: u32) -> SshResult<SessionShare> {
+        if minutes == 0 || minutes > MAX_SHARE_MINUTES {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "Sharing time must be between 1 and {} minutes",
+                    MAX_SHARE_MINUTES
+                ),
+            });
+        }
+        // Kept to notice the session closing, which ends the share
+        let (host_alias, _, session_output) =
+            ShellSessionManager::global().subscribe(session_id).await?;
+
+        let ip = lan_address()?;
+        let listener = TcpListener::bind((ip, 0)).await?;
+        let port = listener.local_addr()?.port();
+        let address = match ip {
+            IpAddr::V6(_) => format!("[{}]:{}", ip, port),
+            IpAddr::V4(_) => format!("{}:{}", ip, port),
+        };
+
+        let mut secret = [0u8; KEY_LEN];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let share_id = format!("{:016x}", rand::random::<u64>());
+        let expires_at = now_millis() + i64::from(minutes) * 60_000;
+        let share = SessionShare {
+            link: format!(
+                "sshbuddy://observe?addr={}&share={}&key={}",
+                address,
+                share_id,
+                base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(secret)
+            ),
+            id: share_id.clone(),
+            session_id: session_id.to_string(),
+            host_alias,
+            address,
+            expires_at,
+            viewers: 0,
+        };
+
+        let (stop, stop_rx) = watch::channel(None);
+        let viewers = Arc::new(AtomicUsize::new(0));
+        self.shares.lock().await.insert(
+            share_id.clone(),
+            ShareEntry {
+                share: share.clone(),
+                viewers: viewers.clone(),
+                stop,
+            },
+        );
+        tracing::info!(
+            "[session_share] Sharing {} ({}) on {} for {} min",
+            session_id,
+            share.host_alias,
+            share.address,
+            minutes
+        );
+
+        let served = share.clone();
+        tokio::spawn(async move {
+            let reason = self
+                .accept_viewers(listener, &served, secret, viewers, session_output, stop_rx)
+                .await;
+            self.shares.lock().await.remove(&served.id);
+            tracing::info!("[session_share] Share {} ended ({})", served.id, reason);
+        });
+        Ok(share)
+    }
+
+    /// Accept observers until the share is stopped or expires; returns why it ended
+    async fn accept_viewers(
+        &'static self,
+        listener: TcpListener,
+        share: &SessionShare,
+        secret: [u8; KEY_LEN],
+        viewers: Arc<AtomicUsize>,
+        mut session_output: broadcast::Receiver<String>,
+        mut stop: watch::Receiver<Option<&'static str>>,
+    ) -> &'static str {
+        let remaining = Duration::from_millis((share.expires_at - now_millis()).max(0) as u64);
+        let expiry = tokio::time::sleep(remaining);
+        tokio::pin!(expiry);
+        let pending = Arc::new(AtomicUsize::new(0));
+        loop {
+            tokio::select! {
+                _ = &mut expiry => {
+                    if let Some(entry) = self.shares.lock().await.get(&share.id) {
+                        let _ = entry.stop.send(Some(REASON_EXPIRED));
+                    }
+                    return REASON_EXPIRED;
+                }
+                _ = stop.changed() => return stop.borrow().unwrap_or(REASON_STOPPED),
+                output = session_output.recv() => {
+                    if let Err(broadcast::error::RecvError::Closed) = output {
+                        return REASON_SESSION_CLOSED;
+                    }
+                }
+                accepted = listener.accept() => {
+                    let Ok((stream, peer)) = accepted else { continue };
+                    let Some(handshake) = Slot::claim(&pending, MAX_PENDING_HANDSHAKES) else {
+                        tracing::warn!("[session_share] Refused {}: too many handshakes", peer);
+                        continue;
+                    };
+                    let viewers = viewers.clone();
+                    let stop = stop.clone();
+                    let share = share.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) =
+                            serve_viewer(stream, &share, &secret, handshake, &viewers, stop).await
+                        {
+                            tracing::warn!(
+                                "[session_share] Observer {} of {}: {}",
+                                peer,
+                                share.id,
+                                e
+                            );
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// Stop a share and disconnect its observers
+    pub async fn stop(&self, share_id: &str) -> SshResult<()> {
+        let shares = self.shares.lock().await;
+        let entry = shares
+            .get(share_id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Session share not found: {}", share_id),
+            })?;
+        let _ = entry.stop.send(Some(REASON_STOPPED));
+        Ok(())
+    }
+
+    /// Running shares with their current observer count
+    pub async fn list(&self) -> Vec<SessionShare> {
+        let mut shares: Vec<SessionShare> = self
+            .shares
+            .lock()
+            .await
+            .values()
+            .map(|entry| SessionShare {
+                viewers: entry.viewers.load(Ordering::SeqCst),
+                ..entry.share.clone()
+            })
+            .collect();
+        shares.sort_by_key(|s| s.expires_at);
+        shares
+    }
+
+    /// Connect to a shared session from its link; returns the viewer id once the stream
+    /// started, further output and the end arrive through `on_event`
+    pub async fn observe<F>(&'static self, link: &str, on_event: F) -> SshResult<String>
+    where
+        F: Fn(ObserveEvent) + Send + Sync + 'static,
+    {
+        let DeepLink::ObserveSession {
+            address,
+            share_id,
+            key,
+        } = parse_deep_link(link)?
+        else {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Not a session sharing link".to_string(),
+            });
+        };
+        let secret: [u8; KEY_LEN] = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(&key)
+            .ok()
+            .and_then(|k| k.try_into().ok())
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: "The sharing link has an invalid key".to_string(),
+            })?;
+
+        let stream = timeout(HANDSHAKE_TIMEOUT, TcpStream::connect(&address))
+            .await
+            .map_err(|_| SshBuddyError::ConnectionTimeout)??;
+        let (read, mut write) = stream.into_split();
+        let mut reader = BufReader::new(read);
+        let (stream_key, mut frames, start) = timeout(
+            HANDSHAKE_TIMEOUT,
+            observer_handshake(&mut reader, &mut write, &share_id, &secret),
+        )
+        .await
+        .map_err(|_| SshBuddyError::ConnectionTimeout)??;
+        let (host_alias, expires_at) = match start {
+            FrameBody::Start {
+                host_alias,
+                expires_at,
+            } => (host_alias, expires_at),
+            FrameBody::End { reason } if reason == REASON_FULL => {
+                return Err(SshBuddyError::InvalidOption {
+                    message: format!("The share already has {} observers", MAX_VIEWERS),
+                })
+            }
+            _ => return Err(protocol_error("expected the stream to start")),
+        };
+
+        let viewer_id = format!("{:016x}", rand::random::<u64>());
+        let (stop, mut stop_rx) = watch::channel(false);
+        self.observing.lock().await.insert(viewer_id.clone(), stop);
+        tracing::info!(
+            "[session_share] Observing {} on {} as {}",
+            host_alias,
+            address,
+            viewer_id
+        );
+        on_event(ObserveEvent::Started {
+            viewer_id: viewer_id.clone(),
+            host_alias,
+            expires_at,
+        });
+
+        let id = viewer_id.clone();
+        tokio::spawn(async move {
+            // The write half stays open until the end; closing it tells the sharer we left
+            let _write = write;
+            let reason = loop {
+                let frame = tokio::select! {
+                    _ = stop_rx.changed() => break REASON_STOPPED.to_string(),
+                    frame = frames.read(&mut reader, &stream_key) => frame,
+                };
+                match frame {
+                    Ok(Some(FrameBody::Output { data })) => on_event(ObserveEvent::Output {
+                        viewer_id: id.clone(),
+                        data,
+                    }),
+                    Ok(Some(FrameBody::End { reason })) => break reason,
+                    Ok(Some(FrameBody::Start { .. })) | Ok(None) => {
+                        break REASON_DISCONNECTED.to_string()
+                    }
+                    Err(e) => {
+                        tracing::warn!("[session_share] Stream of {} broke: {}", id, e);
+                        break REASON_DISCONNECTED.to_string();
+                    }
+                }
+            };
+            self.observing.lock().await.remove(&id);
+            tracing::info!("[session_share] Stopped observing {} ({})", id, reason);
+            on_event(ObserveEvent::Ended {
+                viewer_id: id,
+                reason,
+            });
+        });
+        Ok(viewer_id)
+    }
+
+    /// Stop watching a shared session
+    pub async fn stop_observing(&self, viewer_id: &str) -> SshResult<()> {
+        let observing = self.observing.lock().await;
+        let stop = observing
+            .get(viewer_id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Not observing: {}", viewer_id),
+            })?;
+        let _ = stop.send(true);
+        Ok(())
+    }
+}
+
+/// Counted use of a limited resource, given back when dropped
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn claim(count: &Arc<AtomicUsize>, limit: usize) -> Option<Self> {
+        count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < limit).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(count.clone()))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Send the challenge, check the observer's proof, then mirror the session until it
+/// closes, the share stops or the observer disconnects
+///
+/// Only an observer that proved the key takes one of the `MAX_VIEWERS` slots, so
+/// idle connections can't lock real observers out.
+async fn serve_viewer(
+    stream: TcpStream,
+    share: &SessionShare,
+    secret: &[u8; KEY_LEN],
+    handshake: Slot,
+    viewers: &Arc<AtomicUsize>,
+    mut stop: watch::Receiver<Option<&'static str>>,
+) -> SshResult<()> {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let stream_key = timeout(
+        HANDSHAKE_TIMEOUT,
+        sharer_handshake(&mut reader, &mut write, &share.id, secret),
+    )
+    .await
+    .map_err(|_| protocol_error("handshake timed out"))??;
+    drop(handshake);
+
+    let mut frames = FrameWriter::default();
+    let Some(_viewer) = Slot::claim(viewers, MAX_VIEWERS) else {
+        frames
+            .send(
+                &mut write,
+                &stream_key,
+                FrameBody::End {
+                    reason: REASON_FULL.to_string(),
+                },
+            )
+            .await?;
+        let _ = write.shutdown().await;
+        return Err(protocol_error("too many observers"));
+    };
+
+    let (_, scrollback, mut output) = ShellSessionManager::global()
+        .subscribe(&share.session_id)
+        .await?;
+    frames
+        .send(
+            &mut write,
+            &stream_key,
+            FrameBody::Start {
+                host_alias: share.host_alias.clone(),
+                expires_at: share.expires_at,
+            },
+        )
+        .await?;
+    frames
+        .send_output(&mut write, &stream_key, &scrollback)
+        .await?;
+
+    let mut ignored = [0u8; 1024];
+    let reason = loop {
+        tokio::select! {
+            _ = stop.changed() => break stop.borrow().unwrap_or(REASON_STOPPED),
+            received = output.recv() => match received {
+                Ok(text) => frames.send_output(&mut write, &stream_key, &text).await?,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "[session_share] Observer of {} missed {} chunks",
+                        share.id,
+                        skipped
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => break REASON_SESSION_CLOSED,
+            },
+            // Read-only: whatever the observer sends is dropped, EOF means it left
+            read = reader.read(&mut ignored) => match read {
+                Ok(0) | Err(_) => return Ok(()),
+                Ok(_) => {}
+            },
+        }
+    };
+    frames
+        .send(
+            &mut write,
+            &stream_key,
+            FrameBody::End {
+                reason: reason.to_string(),
+            },
+        )
+        .await?;
+    let _ = write.shutdown().await;
+    Ok(())
+}
+
+/// Sharing side of the handshake; returns the stream key
+async fn sharer_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    share_id: &str,
+    secret: &[u8; KEY_LEN],
+) -> SshResult<[u8; KEY_LEN]>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let mut challenge = [0u8; CHALLENGE_LEN];
+    rand::thread_rng().fill_bytes(&mut challenge);
+    write_json(
+        writer,
+        &Challenge {
+            protocol: PROTOCOL.to_string(),
+            challenge: b64.encode(challenge),
+        },
+    )
+    .await?;
+
+    let hello: Hello = read_json(reader, MAX_HANDSHAKE_LINE)
+        .await?
+        .ok_or_else(|| protocol_error("observer left during the handshake"))?;
+    let proven = hello.share_id == share_id
+        && decrypt_with_key(secret, &hello.proof).is_ok_and(|p| p == challenge);
+    if !proven {
+        return Err(SshBuddyError::PermissionDenied {
+            reason: "Observer doesn't have the sharing link".to_string(),
+        });
+    }
+    let nonce = b64
+        .decode(&hello.nonce)
+        .map_err(|_| protocol_error("bad nonce"))?;
+    Ok(stream_key(secret, &challenge, &nonce))
+}
+
+/// Observing side of the handshake; returns the stream key, the frame reader and the
+/// first frame
+async fn observer_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    share_id: &str,
+    secret: &[u8; KEY_LEN],
+) -> SshResult<([u8; KEY_LEN], FrameReader, FrameBody)>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let challenge: Challenge = read_json(reader, MAX_HANDSHAKE_LINE)
+        .await?
+        .ok_or_else(|| protocol_error("the share is no longer available"))?;
+    if challenge.protocol != PROTOCOL {
+        return Err(protocol_error(&format!(
+            "unsupported protocol {}",
+            challenge.protocol
+        )));
+    }
+    let challenge = b64
+        .decode(&challenge.challenge)
+        .map_err(|_| protocol_error("bad challenge"))?;
+    let mut nonce = [0u8; CHALLENGE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    write_json(
+        writer,
+        &Hello {
+            share_id: share_id.to_string(),
+            nonce: b64.encode(nonce),
+            proof: encrypt_with_key(secret, &challenge)?,
+        },
+    )
+    .await?;
+
+    // A wrong key or share id makes the sharer hang up without a frame
+    let key = stream_key(secret, &challenge, &nonce);
+    let mut frames = FrameReader::default();
+    let first = frames
+        .read(reader, &key)
+        .await
+        .map_err(|_| invalid_link())?;
+    let first = first.ok_or_else(invalid_link)?;
+    Ok((key, frames, first))
+}
+
+/// Per-connection key: both sides contribute randomness, so a recorded stream can't be
+/// replayed to an observer
+fn stream_key(secret: &[u8; KEY_LEN], challenge: &[u8], nonce: &[u8]) -> [u8; KEY_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(PROTOCOL.as_bytes());
+    hasher.update(secret);
+    hasher.update(challenge);
+    hasher.update(nonce);
+    hasher.finalize().into()
+}
+
+#[derive(Default)]
+struct FrameWriter {
+    seq: u64,
+}
+
+impl FrameWriter {
+    async fn send<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+        key: &[u8; KEY_LEN],
+        body: FrameBody,
+    ) -> SshResult<()> {
+        let frame = Frame {
+            seq: self.seq,
+            body,
+        };
+        self.seq += 1;
+        let plaintext = serde_json::to_vec(&frame).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_json(writer, &encrypt_with_key(key, &plaintext)?).await
+    }
+
+    async fn send_output<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+        key: &[u8; KEY_LEN],
+        text: &str,
+    ) -> SshResult<()> {
+        for chunk in output_chunks(text, OUTPUT_CHUNK_CHARS) {
+            self.send(
+                writer,
+                key,
+                FrameBody::Output {
+                    data: chunk.to_string(),
+                },
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct FrameReader {
+    seq: u64,
+}
+
+impl FrameReader {
+    /// Next frame (None when the sharer hung up)
+    async fn read<R: AsyncBufRead + Unpin>(
+        &mut self,
+        reader: &mut R,
+        key: &[u8; KEY_LEN],
+    ) -> SshResult<Option<FrameBody>> {
+        let Some(sealed) = read_json::<SealedValue, _>(reader, MAX_FRAME_LINE).await? else {
+            return Ok(None);
+        };
+        let plaintext = decrypt_with_key(key, &sealed)?;
+        let frame: Frame =
+            serde_json::from_slice(&plaintext).map_err(|_| protocol_error("bad frame"))?;
+        if frame.seq != self.seq {
+            return Err(protocol_error("frames out of order"));
+        }
+        self.seq += 1;
+        Ok(Some(frame.body))
+    }
+}
+
+/// Split `text` into pieces of at most `max_chars` characters
+fn output_chunks(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest
+            .char_indices()
+            .nth(max_chars)
+            .map(|(idx, _)| idx)
+            .unwrap_or(rest.len());
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    chunks
+}
+
+async fn write_json<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    value: &T,
+) -> SshResult<()> {
+    let mut line = serde_json::to_vec(value).map_err(|e| SshBuddyError::Unknown {
+        message: e.to_string(),
+    })?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Next JSON line (None at EOF); longer lines than `limit` are refused
+async fn read_json<T: DeserializeOwned, R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    limit: usize,
+) -> SshResult<Option<T>> {
+    let mut line = Vec::new();
+    (&mut *reader)
+        .take(limit as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .await?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.len() > limit {
+        return Err(protocol_error("message too long"));
+    }
+    serde_json::from_slice(&line)
+        .map(Some)
+        .map_err(|_| protocol_error("malformed message"))
+}
+
+/// Address of the interface with the default route
+fn lan_address() -> SshResult<IpAddr> {
+    let socket = std::net::UdpSocket::bind(("0.0.0.0", 0))?;
+    // Connecting a UDP socket sends nothing, it only picks the outgoing interface
+    socket
+        .connect(("192.0.2.1", 9))
+        .map_err(|_| SshBuddyError::InvalidOption {
+            message: "No network connection to share the session on".to_string(),
+        })?;
+    Ok(socket.local_addr()?.ip())
+}
+
+fn protocol_error(message: &str) -> SshBuddyError {
+    SshBuddyError::Unknown {
+        message: format!("Session sharing: {}", message),
+    }
+}
+
+fn invalid_link() -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: "The sharing link is wrong or has expired".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_chunks() {
+        assert_eq!(output_chunks("abcdé", 2), vec!["ab", "cd", "é"]);
+        assert!(output_chunks("", 2).is_empty());
+    }
+
+    #[test]
+    fn test_slots() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let first = Slot::claim(&count, 2).unwrap();
+        let _second = Slot::claim(&count, 2).unwrap();
+        assert!(Slot::claim(&count, 2).is_none());
+
+        drop(first);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(Slot::claim(&count, 2).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_frames() {
+        let secret = [7u8; KEY_LEN];
+        let (sharer, observer) = tokio::io::duplex(64 * 1024);
+        let (sharer_read, mut sharer_write) = tokio::io::split(sharer);
+        let (observer_read, mut observer_write) = tokio::io::split(observer);
+
+        let sharing = tokio::spawn(async move {
+            let mut reader = BufReader::new(sharer_read);
+            let key = sharer_handshake(&mut reader, &mut sharer_write, "s1", &secret)
+                .await
+                .unwrap();
+            let mut frames = FrameWriter::default();
+            frames
+                .send(
+                    &mut sharer_write,
+                    &key,
+                    FrameBody::Start {
+                        host_alias: "web".to_string(),
+                        expires_at: 1,
+                    },
+                )
+                .await
+                .unwrap();
+            frames
+                .send_output(&mut sharer_write, &key, "$ uptime")
+                .await
+                .unwrap();
+        });
+
+        let mut reader = BufReader::new(observer_read);
+        let (key, mut frames, first) =
+            observer_handshake(&mut reader, &mut observer_write, "s1", &secret)
+                .await
+                .unwrap();
+        assert_eq!(
+            first,
+            FrameBody::Start {
+                host_alias: "web".to_string(),
+                expires_at: 1
+            }
+        );
+        assert_eq!(
+            frames.read(&mut reader, &key).await.unwrap(),
+            Some(FrameBody::Output {
+                data: "$ uptime".to_string()
+            })
+        );
+        sharing.await.unwrap();
+        assert_eq!(frames.read(&mut reader, &key).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejects_wrong_key() {
+        let (sharer, observer) = tokio::io::duplex(64 * 1024);
+        let (sharer_read, mut sharer_write) = tokio::io::split(sharer);
+        let (observer_read, mut observer_write) = tokio::io::split(observer);
+
+        let sharing = tokio::spawn(async move {
+            let mut reader = BufReader::new(sharer_read);
+            sharer_handshake(&mut reader, &mut sharer_write, "s1", &[1u8; KEY_LEN]).await
+        });
+        let mut reader = BufReader::new(observer_read);
+        let observed =
+            observer_handshake(&mut reader, &mut observer_write, "s1", &[2u8; KEY_LEN]).await;
+        assert!(matches!(
+            sharing.await.unwrap(),
+            Err(SshBuddyError::PermissionDenied { .. })
+        ));
+        assert!(observed.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_frames_out_of_order_are_refused() {
+        let key = [3u8; KEY_LEN];
+        let mut buffer = Vec::new();
+        let mut writer = FrameWriter { seq: 1 };
+        writer
+            .send(&mut buffer, &key, FrameBody::Output { data: "x".into() })
+            .await
+            .unwrap();
+        let mut reader = BufReader::new(buffer.as_slice());
+        assert!(FrameReader::default()
+            .read(&mut reader, &key)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,573 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::audit_service::{SiemSettings, SiemTarget};
+use crate::services::export_signing::ExportSigningService;
+use crate::services::health_check::HealthCheckSettings;
+use crate::services::log_service::LogSettings;
+use crate::services::notification_service::NotificationPreferences;
+use crate::services::onboarding_service::OnboardingState;
+use crate::services::palette_search::PaletteShortcut;
+use crate::services::permission_service::PermissionPolicy;
+use crate::services::privacy_service::PrivacySettings;
+use crate::services::proxy_service::ProxySettings;
+use crate::services::reachability::ReachabilitySettings;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::session_helper::IsolationSettings;
+use crate::services::session_restore::RestoreSettings;
+use crate::services::ssh_engine::EngineSettings;
+use crate::services::terminal_launcher::TerminalSettings;
+use crate::services::transfer_service::TransferSettings;
+use crate::services::trash_service::TrashSettings;
+use crate::services::vault_service::SecuritySettings;
+use crate::services::workspace_service::WorkspaceSettings;
+use crate::utils::{
+    app_data_dir, app_paths, portable_root, resolve_ssh_root, set_ssh_root, write_atomic,
+    write_atomic_private, write_atomic_private_sync, AppPaths, SSH_DIR_ENV,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tokio::fs;
+
+/// Current settings.json schema version
+pub const SETTINGS_VERSION: u32 = 1;
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Previous settings.json, restored when the current one is unreadable
+const BACKUP_FILE: &str = "settings.json.bak";
+/// Unreadable settings.json kept for inspection
+const CORRUPT_FILE: &str = "settings.corrupt.json";
+
+/// Settings files written before settings.json, with the section each became
+const LEGACY_FILES: &[(&str, &str)] = &[
+    ("readOnly", "read_only.json"),
+    ("notifications", "notifications.json"),
+    ("palette", "palette.json"),
+    ("proxy", "proxy.json"),
+    ("terminal", "terminal.json"),
+    ("security", "security.json"),
+    ("transfers", "transfers.json"),
+    ("privacy", "privacy.json"),
+    ("logging", "logging.json"),
+    ("siem", "siem.json"),
+];
+
+/// Upgrades a settings document by one version; gets the content of legacy files
+type Migration = fn(&mut Map<String, Value>, &dyn Fn(&str) -> Option<Value>);
+
+/// `MIGRATIONS[n]` upgrades version n to n + 1
+const MIGRATIONS: &[Migration] = &[migrate_legacy_files];
+
+/// settings.json contents; every service reads its section from here
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    pub version: u32,
+    pub read_only: bool,
+    /// SSH root replacing ~/.ssh; relative paths are relative to the executable in
+    /// portable mode
+    pub ssh_root: Option<String>,
+    pub notifications: NotificationPreferences,
+    pub palette: PaletteShortcut,
+    /// App-level outbound proxy; None connects directly
+    pub proxy: Option<ProxySettings>,
+    pub terminal: TerminalSettings,
+    pub security: SecuritySettings,
+    pub permissions: PermissionPolicy,
+    pub transfers: TransferSettings,
+    pub privacy: PrivacySettings,
+    pub logging: LogSettings,
+    pub siem: SiemSettings,
+    pub health_checks: HealthCheckSettings,
+    pub reachability: ReachabilitySettings,
+    pub onboarding: OnboardingState,
+    pub workspaces: WorkspaceSettings,
+    pub restore: RestoreSettings,
+    pub isolation: IsolationSettings,
+    pub engine: EngineSettings,
+    pub trash: TrashSettings,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            read_only: false,
+            ssh_root: None,
+            notifications: NotificationPreferences::default(),
+            palette: PaletteShortcut::default(),
+            proxy: None,
+            terminal: TerminalSettings::default(),
+            security: SecuritySettings::default(),
+            permissions: PermissionPolicy::default(),
+            transfers: TransferSettings::default(),
+            privacy: PrivacySettings::default(),
+            logging: LogSettings::default(),
+            siem: SiemSettings::default(),
+            health_checks: HealthCheckSettings::default(),
+            reachability: ReachabilitySettings::default(),
+            onboarding: OnboardingState::default(),
+            workspaces: WorkspaceSettings::default(),
+            restore: RestoreSettings::default(),
+            isolation: IsolationSettings::default(),
+            engine: EngineSettings::default(),
+            trash: TrashSettings::default(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// Validate every section (the services validate their own section on change)
+    pub fn validate(&self) -> SshResult<()> {
+        self.notifications.validate()?;
+        self.security.validate()?;
+        self.permissions.validate()?;
+        self.transfers.validate()?;
+        self.privacy.validate()?;
+        self.logging.validate()?;
+        self.siem.validate()?;
+        self.health_checks.validate()?;
+        self.reachability.validate()?;
+        self.workspaces.validate()?;
+        self.engine.validate()?;
+        if self
+            .ssh_root
+            .as_deref()
+            .is_some_and(|root| root.trim().is_empty() || root.chars().any(char::is_control))
+        {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Invalid SSH directory".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Copy without secrets, for moving settings to another machine
+    fn without_secrets(&self) -> Self {
+        let mut settings = self.clone();
+        if let Some(proxy) = settings.proxy.as_mut() {
+            proxy.password = None;
+        }
+        if let Some(SiemTarget::Http { authorization, .. }) = settings.siem.target.as_mut() {
+            *authorization = None;
+        }
+        settings
+    }
+}
+
+/// v0 (one file per service) to v1: move the files into their sections
+fn migrate_legacy_files(doc: &mut Map<String, Value>, legacy: &dyn Fn(&str) -> Option<Value>) {
+    for (section, file) in LEGACY_FILES {
+        if doc.contains_key(*section) {
+            continue;
+        }
+        let Some(value) = legacy(file) else {
+            continue;
+        };
+        let value = match *section {
+            // read_only.json held {"enabled": bool}
+            "readOnly" => value.get("enabled").cloned().unwrap_or(Value::Bool(false)),
+            _ => value,
+        };
+        doc.insert(section.to_string(), value);
+    }
+}
+
+fn document_version(doc: &Map<String, Value>) -> u32 {
+    doc.get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |v| v.min(u64::from(u32::MAX)) as u32)
+}
+
+/// Upgrade a document to the current schema (documents of newer versions are left alone)
+fn migrate(
+    mut doc: Map<String, Value>,
+    legacy: &dyn Fn(&str) -> Option<Value>,
+) -> Map<String, Value> {
+    let mut version = document_version(&doc);
+    while let Some(migration) = MIGRATIONS.get(version as usize) {
+        migration(&mut doc, legacy);
+        version += 1;
+    }
+    doc.insert("version".to_string(), Value::from(version));
+    doc
+}
+
+/// Typed settings; a section that doesn't parse falls back to its default
+fn decode(doc: Map<String, Value>) -> AppSettings {
+    let mut valid = Map::new();
+    for (key, value) in doc {
+        let mut single = Map::new();
+        single.insert(key.clone(), value.clone());
+        if serde_json::from_value::<AppSettings>(Value::Object(single)).is_ok() {
+            valid.insert(key, value);
+        } else {
+            tracing::warn!(
+                "[settings_service] Ignoring invalid settings section {}",
+                key
+            );
+        }
+    }
+    serde_json::from_value(Value::Object(valid)).unwrap_or_default()
+}
+
+/// Settings to apply from an exported file; secrets and machine-specific values
+/// of this machine are kept
+fn prepare_import(content: &str, current: &AppSettings) -> SshResult<AppSettings> {
+    let doc: Map<String, Value> =
+        serde_json::from_str(content).map_err(|e| SshBuddyError::InvalidOption {
+            message: format!("Not a settings file: {}", e),
+        })?;
+    if document_version(&doc) > SETTINGS_VERSION {
+        return Err(SshBuddyError::InvalidOption {
+            message: "The settings were exported by a newer version of SSH Buddy".to_string(),
+        });
+    }
+    let mut settings = decode(migrate(doc, &|_| None));
+
+    if let (Some(imported), Some(local)) = (settings.proxy.as_mut(), current.proxy.as_ref()) {
+        if imported.password.is_none()
+            && imported.host == local.host
+            && imported.port == local.port
+            && imported.username == local.username
+        {
+            imported.password = local.password.clone();
+        }
+    }
+    if let (
+        Some(SiemTarget::Http { url, authorization }),
+        Some(SiemTarget::Http {
+            url: local_url,
+            authorization: local_authorization,
+        }),
+    ) = (settings.siem.target.as_mut(), current.siem.target.as_ref())
+    {
+        if authorization.is_none() && url == local_url {
+            *authorization = local_authorization.clone();
+        }
+    }
+    if !settings.terminal.terminal.supported() {
+        settings.terminal = TerminalSettings::default();
+    }
+    // Read-only mode, the SSH root, the onboarding progress and the workspaces
+    // (their config files and keys) belong to this machine
+    settings.read_only = current.read_only;
+    settings.ssh_root = current.ssh_root.clone();
+    settings.onboarding = current.onboarding.clone();
+    settings.workspaces = current.workspaces.clone();
+
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Point the path helpers at the configured SSH root and the active workspace
+fn apply_paths(settings: &AppSettings) {
+    set_ssh_root(settings.ssh_root.as_deref());
+    settings.workspaces.apply();
+}
+
+enum Stored {
+    Missing,
+    Unreadable,
+    Document(Map<String, Value>),
+}
+
+fn read_document(path: &Path) -> Stored {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Stored::Document)
+            .unwrap_or(Stored::Unreadable),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Stored::Missing,
+        Err(_) => Stored::Unreadable,
+    }
+}
+
+/// Versioned settings store (settings.json) shared by all services
+///
+/// Reads come from memory; changes are serialized, written atomically and the
+/// previous file is kept as a backup that is used if settings.json gets corrupted.
+pub struct SettingsService;
+
+impl SettingsService {
+    fn get_data_dir() -> SshResult<PathBuf> {
+        app_data_dir()
+    }
+
+    fn current() -> &'static Mutex<AppSettings> {
+        static CURRENT: OnceLock<Mutex<AppSettings>> = OnceLock::new();
+        CURRENT.get_or_init(|| Mutex::new(Self::load()))
+    }
+
+    /// Serializes read-modify-write of the settings
+    fn write_lock() -> &'static tokio::sync::Mutex<()> {
+        static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+    }
+
+    /// Load settings.json (falling back to the backup), migrating older schemas and
+    /// the legacy per-service files; blocking, runs once on first use
+    fn load() -> AppSettings {
+        let Ok(dir) = Self::get_data_dir() else {
+            return AppSettings::default();
+        };
+        let path = dir.join(SETTINGS_FILE);
+        let (doc, restored) = match read_document(&path) {
+            Stored::Document(doc) => (doc, false),
+            Stored::Missing => (Map::new(), false),
+            Stored::Unreadable => {
+                tracing::warn!("[settings_service] settings.json is unreadable, using the backup");
+                let _ = std::fs::copy(&path, dir.join(CORRUPT_FILE));
+                match read_document(&dir.join(BACKUP_FILE)) {
+                    Stored::Document(doc) => (doc, true),
+                    _ => (Map::new(), true),
+                }
+            }
+        };
+
+        let from_version = document_version(&doc);
+        let read_legacy = |file: &str| match read_document(&dir.join(file)) {
+            Stored::Document(doc) => Some(Value::Object(doc)),
+            _ => None,
+        };
+        let settings = decode(migrate(doc, &read_legacy));
+        if restored || from_version < SETTINGS_VERSION {
+            Self::save_blocking(&dir, &settings);
+        }
+        apply_paths(&settings);
+        settings
+    }
+
+    /// Save migrated or restored settings, then remove the legacy files
+    fn save_blocking(dir: &Path, settings: &AppSettings) {
+        let path = dir.join(SETTINGS_FILE);
+        let saved = std::fs::create_dir_all(dir)
+            .map_err(SshBuddyError::from)
+            .and_then(|_| {
+                serde_json::to_string_pretty(settings).map_err(|e| SshBuddyError::Unknown {
+                    message: e.to_string(),
+                })
+            })
+            // Holds the proxy password and SIEM token
+            .and_then(|json| write_atomic_private_sync(&path, json.as_bytes()));
+        match saved {
+            Ok(()) => {
+                // Only once the migrated settings are safely written
+                for (_, file) in LEGACY_FILES {
+                    let _ = std::fs::remove_file(dir.join(file));
+                }
+                tracing::info!(
+                    "[settings_service] Saved settings version {}",
+                    SETTINGS_VERSION
+                );
+            }
+            Err(e) => tracing::warn!("[settings_service] Failed to save settings: {}", e),
+        }
+    }
+
+    /// Current settings
+    pub fn get() -> AppSettings {
+        Self::current()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Re-read settings.json; the SSH helper process uses it to pick up changes made
+    /// by the app since it started
+    pub(crate) fn reload() {
+        let settings = Self::load();
+        *Self::current().lock().unwrap_or_else(|e| e.into_inner()) = settings;
+    }
+
+    /// Apply a change and save it; nothing is saved when `change` fails
+    pub async fn update<F>(change: F) -> SshResult<AppSettings>
+    where
+        F: FnOnce(&mut AppSettings) -> SshResult<()>,
+    {
+        let _guard = Self::write_lock().lock().await;
+        let mut settings = Self::get();
+        change(&mut settings)?;
+        settings.version = SETTINGS_VERSION;
+        Self::save(&settings).await?;
+        apply_paths(&settings);
+        *Self::current().lock().unwrap_or_else(|e| e.into_inner()) = settings.clone();
+        Ok(settings)
+    }
+
+    async fn save(settings: &AppSettings) -> SshResult<()> {
+        let dir = Self::get_data_dir()?;
+        fs::create_dir_all(&dir).await?;
+        let path = dir.join(SETTINGS_FILE);
+        // Both files hold the proxy password and SIEM token
+        if path.exists() {
+            let previous = fs::read(&path).await?;
+            write_atomic_private(&dir.join(BACKUP_FILE), &previous).await?;
+        }
+        let json = serde_json::to_string_pretty(settings).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic_private(&path, json.as_bytes()).await
+    }
+
+    /// Use another SSH root instead of ~/.ssh (None goes back to ~/.ssh)
+    pub async fn change_ssh_root(root: Option<String>) -> SshResult<AppPaths> {
+        ReadOnlyMode::ensure_writable("change the SSH directory")?;
+        if std::env::var_os(SSH_DIR_ENV).is_some_and(|v| !v.is_empty()) {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("The SSH directory is set by {}", SSH_DIR_ENV),
+            });
+        }
+        let root = root.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+        if let Some(root) = &root {
+            let dir = resolve_ssh_root(root, portable_root());
+            if !dir.is_dir() {
+                return Err(SshBuddyError::InvalidPath {
+                    message: format!("Not a directory: {}", dir.display()),
+                });
+            }
+        }
+        Self::update(|settings| {
+            settings.ssh_root = root;
+            Ok(())
+        })
+        .await?;
+        let paths = app_paths()?;
+        tracing::info!("[settings_service] SSH directory set to {}", paths.ssh_dir);
+        Ok(paths)
+    }
+
+    /// Forget the settings in memory (after all local data was deleted)
+    pub fn reset() {
+        apply_paths(&AppSettings::default());
+        *Self::current().lock().unwrap_or_else(|e| e.into_inner()) = AppSettings::default();
+    }
+
+    /// Write the settings without secrets (proxy password, SIEM authorization) to a file,
+    /// optionally with a `.sig` made with the app signing key
+    pub async fn export(destination: &Path, sign: bool) -> SshResult<()> {
+        let json = serde_json::to_string_pretty(&Self::get().without_secrets()).map_err(|e| {
+            SshBuddyError::Unknown {
+                message: e.to_string(),
+            }
+        })?;
+        write_atomic(destination, json.as_bytes()).await?;
+        ExportSigningService::finish_export(destination, sign).await?;
+        tracing::info!("[settings_service] Exported settings to {:?}", destination);
+        Ok(())
+    }
+
+    /// Replace the settings with an exported file (refused if its signature doesn't check out)
+    pub async fn import(source: &Path) -> SshResult<AppSettings> {
+        ReadOnlyMode::ensure_writable("import settings")?;
+        ExportSigningService::ensure_importable(source).await?;
+        let content = fs::read_to_string(source).await?;
+        let settings = Self::update(|settings| {
+            *settings = prepare_import(&content, settings)?;
+            Ok(())
+        })
+        .await?;
+        tracing::info!("[settings_service] Imported settings from {:?}", source);
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::log_service::LogLevel;
+    use crate::services::proxy_service::ProxyType;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("not an object"),
+        }
+    }
+
+    #[test]
+    fn test_migrations_reach_current_version() {
+        assert_eq!(MIGRATIONS.len() as u32, SETTINGS_VERSION);
+        assert_eq!(AppSettings::default().version, SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_legacy_files() {
+        let legacy = |file: &str| match file {
+            "read_only.json" => Some(json!({ "enabled": true })),
+            "transfers.json" => Some(json!({ "globalRateLimit": 1024 })),
+            "logging.json" => Some(json!({ "defaultLevel": "debug" })),
+            _ => None,
+        };
+        let settings = decode(migrate(Map::new(), &legacy));
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert!(settings.read_only);
+        assert_eq!(settings.transfers.global_rate_limit, Some(1024));
+        assert_eq!(
+            settings.transfers.chunk_size,
+            TransferSettings::default().chunk_size
+        );
+        assert_eq!(settings.logging.default_level, LogLevel::Debug);
+        assert_eq!(settings.privacy, PrivacySettings::default());
+
+        // A current document ignores leftover legacy files
+        let current = object(json!({ "version": SETTINGS_VERSION, "readOnly": false }));
+        assert!(!decode(migrate(current, &legacy)).read_only);
+    }
+
+    #[test]
+    fn test_decode_drops_invalid_sections() {
+        let doc = object(json!({
+            "version": 1,
+            "privacy": { "retentionDays": "forever" },
+            "transfers": { "globalRateLimit": 2048 },
+            "unknownSection": 5,
+        }));
+        let settings = decode(doc);
+        assert_eq!(settings.privacy, PrivacySettings::default());
+        assert_eq!(settings.transfers.global_rate_limit, Some(2048));
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let mut local = AppSettings {
+            read_only: true,
+            proxy: Some(ProxySettings {
+                proxy_type: ProxyType::Socks5,
+                host: "proxy.local".to_string(),
+                port: 1080,
+                username: Some("bob".to_string()),
+                password: Some("hunter2".to_string()),
+            }),
+            ..AppSettings::default()
+        };
+        local.siem = SiemSettings {
+            enabled: true,
+            target: Some(SiemTarget::Http {
+                url: "https://siem.example.com/events".to_string(),
+                authorization: Some("Bearer abc".to_string()),
+            }),
+        };
+        let exported = serde_json::to_string(&local.without_secrets()).unwrap();
+        assert!(!exported.contains("hunter2"));
+        assert!(!exported.contains("Bearer abc"));
+
+        // Same proxy and SIEM target on the other machine keep its secrets
+        let imported = prepare_import(&exported, &local).unwrap();
+        assert_eq!(imported, local);
+
+        let other = AppSettings::default();
+        let imported = prepare_import(&exported, &other).unwrap();
+        assert_eq!(imported.proxy.unwrap().password, None);
+        assert!(!imported.read_only);
+    }
+
+    #[test]
+    fn test_import_rejects_bad_files() {
+        let current = AppSettings::default();
+        assert!(prepare_import("not json", &current).is_err());
+        assert!(prepare_import(r#"{"version": 99}"#, &current).is_err());
+        let invalid = r#"{"version": 1, "transfers": {"channels": 100}}"#;
+        assert!(prepare_import(invalid, &current).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::ssh_connection::{sftp_error, RemoteSession, SshConnectionService};
+use crate::utils::percent_decode;
+use russh_sftp::client::SftpSession;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Mutex;
+
+/// Largest body of one response; longer reads are answered with a partial range and
+/// the client asks for the rest
+pub const MAX_RESPONSE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// SFTP sessions unused for this long are closed (on the next request)
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Bytes asked for by an HTTP `Range` header (single ranges only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `bytes=<start>-[<end>]`, end inclusive
+    From { start: u64, end: Option<u64> },
+    /// `bytes=-<len>`: the last `len` bytes
+    Suffix(u64),
+}
+
+/// Remote file read asked for by a `sftp://` URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteFileRequest {
+    pub host_alias: String,
+    pub path: String,
+    pub range: Option<ByteRange>,
+}
+
+/// Bytes read from a remote file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteFileChunk {
+    pub data: Vec<u8>,
+    /// Offset of the first byte
+    pub start: u64,
+    /// File size
+    pub total: u64,
+}
+
+impl RemoteFileChunk {
+    /// Less than the whole file
+    pub fn is_partial(&self) -> bool {
+        self.start > 0 || (self.data.len() as u64) < self.total
+    }
+}
+
+/// Outcome of a ranged read
+#[derive(Debug, Clone, PartialEq)]
+pub enum RangeRead {
+    Chunk(RemoteFileChunk),
+    /// The range starts past the end of the file
+    Unsatisfiable {
+        total: u64,
+    },
+}
+
+/// SFTP session kept open for the next request to the same host
+struct Connection {
+    session: RemoteSession,
+    sftp: SftpSession,
+}
+
+type ConnectionCache = Mutex<HashMap<String, (Arc<Connection>, Instant)>>;
+
+/// Serves remote file bytes to the webview as raw response bodies (previews,
+/// downloads), without going through the JSON IPC
+pub struct SftpStreamService;
+
+impl SftpStreamService {
+    fn connections() -> &'static ConnectionCache {
+        static CONNECTIONS: OnceLock<ConnectionCache> = OnceLock::new();
+        CONNECTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    /// Parse `/file?host=<alias>&path=<remote path>` and an optional Range header
+    pub fn parse_request(
+        query: Option<&str>,
+        range_header: Option<&str>,
+    ) -> SshResult<RemoteFileRequest> {
+        let mut host_alias = None;
+        let mut path = None;
+        for pair in query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|p| !p.is_empty())
+        {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(&value.replace('+', " "))?;
+            match key {
+                "host" => host_alias = Some(value),
+                "path" => path = Some(value),
+                _ => {}
+            }
+        }
+        let host_alias =
+            host_alias
+                .filter(|h| !h.is_empty())
+                .ok_or_else(|| SshBuddyError::InvalidOption {
+                    message: "Remote file URL has no host".to_string(),
+                })?;
+        let path = path
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: "Remote file URL has no path".to_string(),
+            })?;
+        let range = match range_header {
+            Some(header) => {
+                Some(
+                    parse_range(header).ok_or_else(|| SshBuddyError::InvalidOption {
+                        message: format!("Unsupported range: {}", header),
+                    })?,
+                )
+            }
+            None => None,
+        };
+        Ok(RemoteFileRequest {
+            host_alias,
+            path,
+            range,
+        })
+    }
+
+    /// Read the requested bytes (at most `MAX_RESPONSE_BYTES`) over a cached SFTP session
+    pub async fn read(request: &RemoteFileRequest) -> SshResult<RangeRead> {
+        let connection = Self::connection(&request.host_alias).await?;
+        let result = read_range(&connection, request).await;
+        if result.is_err() {
+            // The session may be gone; the next request reconnects
+            Self::evict(&request.host_alias).await;
+        }
+        result
+    }
+
+    /// Content type for previews, from the file extension
+    pub fn content_type(path: &str) -> &'static str {
+        let extension = path
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "txt" | "log" | "conf" | "cfg" | "ini" | "sh" | "md" | "csv" | "yaml" | "yml"
+            | "toml" => "text/plain; charset=utf-8",
+            "json" => "application/json",
+            "html" | "htm" => "text/html; charset=utf-8",
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "svg" => "image/svg+xml",
+            "webp" => "image/webp",
+            "pdf" => "application/pdf",
+            "mp4" => "video/mp4",
+            "webm" => "video/webm",
+            "mp3" => "audio/mpeg",
+            _ => "application/octet-stream",
+        }
+    }
+
+    /// Cached session of a host, opening one when needed; idle sessions are closed
+    async fn connection(host_alias: &str) -> SshResult<Arc<Connection>> {
+        let mut connections = Self::connections().lock().await;
+        let now = Instant::now();
+        let idle: Vec<String> = connections
+            .iter()
+            .filter(|(_, (_, used))| now.duration_since(*used) > IDLE_TIMEOUT)
+            .map(|(alias, _)| alias.clone())
+            .collect();
+        for alias in idle {
+            if let Some((connection, _)) = connections.remove(&alias) {
+                tokio::spawn(close(connection));
+            }
+        }
+
+        if let Some((connection, used)) = connections.get_mut(host_alias) {
+            *used = now;
+            return Ok(connection.clone());
+        }
+        let session = SshConnectionService::open_session(host_alias, None).await?;
+        let sftp = match session.open_sftp().await {
+            Ok(sftp) => sftp,
+            Err(e) => {
+                session.close().await;
+                return Err(e);
+            }
+        };
+        tracing::info!("[sftp_stream] Opened SFTP session to {}", host_alias);
+        let connection = Arc::new(Connection { session, sftp });
+        connections.insert(host_alias.to_string(), (connection.clone(), now));
+        Ok(connection)
+    }
+
+    async fn evict(host_alias: &str) {
+        let removed = Self::connections().lock().await.remove(host_alias);
+        if let Some((connection, _)) = removed {
+            tokio::spawn(close(connection));
+        }
+    }
+}
+
+async fn read_range(connection: &Connection, request: &RemoteFileRequest) -> SshResult<RangeRead> {
+    let sftp = &connection.sftp;
+    let total = sftp
+        .metadata(&request.path)
+        .await
+        .map_err(sftp_error)?
+        .size
+        .unwrap_or(0);
+    let Some((start, end)) = resolve_range(request.range, total) else {
+        return Ok(RangeRead::Unsatisfiable { total });
+    };
+
+    let mut data = vec![0u8; (end - start) as usize];
+    if !data.is_empty() {
+        let mut file = sftp.open(&request.path).await.map_err(sftp_error)?;
+        file.seek(SeekFrom::Start(start)).await?;
+        file.read_exact(&mut data).await?;
+    }
+    connection.session.add_transferred(data.len() as u64);
+    Ok(RangeRead::Chunk(RemoteFileChunk { data, start, total }))
+}
+
+/// Close a session once no request uses it any more
+async fn close(connection: Arc<Connection>) {
+    if let Ok(connection) = Arc::try_unwrap(connection) {
+        let _ = connection.sftp.close().await;
+        connection.session.close().await;
+    }
+}
+
+/// Parse a single-range `Range` header
+fn parse_range(header: &str) -> Option<ByteRange> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    if start.is_empty() {
+        return end.parse().ok().map(ByteRange::Suffix);
+    }
+    let start = start.parse().ok()?;
+    let end = match end {
+        "" => None,
+        end => Some(end.parse().ok().filter(|end| *end >= start)?),
+    };
+    Some(ByteRange::From { start, end })
+}
+
+/// Byte offsets `start..end` to read from a file of `total` bytes, capped at
+/// `MAX_RESPONSE_BYTES`; None when the range starts past the end
+fn resolve_range(range: Option<ByteRange>, total: u64) -> Option<(u64, u64)> {
+    let (start, end) = match range {
+        None => (0, total),
+        Some(ByteRange::From { start, end }) => {
+            if start >= total && !(start == 0 && total == 0) {
+                return None;
+            }
+            (
+                start,
+                end.map_or(total, |end| end.saturating_add(1).min(total)),
+            )
+        }
+        Some(ByteRange::Suffix(len)) => (total.saturating_sub(len), total),
+    };
+    Some((start, end.min(start.saturating_add(MAX_RESPONSE_BYTES))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = SftpStreamService::parse_request(
+            Some("host=web&path=%2Fvar%2Flog%2Fmy+app.log"),
+            Some("bytes=10-"),
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            RemoteFileRequest {
+                host_alias: "web".to_string(),
+                path: "/var/log/my app.log".to_string(),
+                range: Some(ByteRange::From {
+                    start: 10,
+                    end: None
+                }),
+            }
+        );
+        assert!(SftpStreamService::parse_request(Some("path=/etc/hosts"), None).is_err());
+        assert!(SftpStreamService::parse_request(Some("host=web"), None).is_err());
+        assert!(
+            SftpStreamService::parse_request(Some("host=web&path=/a"), Some("bytes=0-1,5-6"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-499"),
+            Some(ByteRange::From {
+                start: 0,
+                end: Some(499)
+            })
+        );
+        assert_eq!(parse_range("bytes=-500"), Some(ByteRange::Suffix(500)));
+        assert_eq!(parse_range("bytes=5-1"), None);
+        assert_eq!(parse_range("items=0-1"), None);
+    }
+
+    #[test]
+    fn test_resolve_range() {
+        assert_eq!(resolve_range(None, 100), Some((0, 100)));
+        let from = |start, end| Some(ByteRange::From { start, end });
+        assert_eq!(resolve_range(from(10, Some(19)), 100), Some((10, 20)));
+        assert_eq!(resolve_range(from(90, Some(500)), 100), Some((90, 100)));
+        assert_eq!(
+            resolve_range(Some(ByteRange::Suffix(30)), 100),
+            Some((70, 100))
+        );
+        assert_eq!(resolve_range(from(100, None), 100), None);
+        assert_eq!(resolve_range(from(0, None), 0), Some((0, 0)));
+
+        let large = MAX_RESPONSE_BYTES * 3;
+        assert_eq!(
+            resolve_range(from(5, None), large),
+            Some((5, 5 + MAX_RESPONSE_BYTES))
+        );
+    }
+
+    #[test]
+    fn test_chunk_is_partial() {
+        let chunk = |start, len: usize, total| RemoteFileChunk {
+            data: vec![0; len],
+            start,
+            total,
+        };
+        assert!(!chunk(0, 10, 10).is_partial());
+        assert!(chunk(0, 5, 10).is_partial());
+        assert!(chunk(5, 5, 10).is_partial());
+        assert_eq!(SftpStreamService::content_type("/tmp/A.PNG"), "image/png");
+        assert_eq!(
+            SftpStreamService::content_type("/bin/ls"),
+            "application/octet-stream"
+        );
+    }
+}
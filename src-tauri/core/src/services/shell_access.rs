@@ -0,0 +1,176 @@
+use crate::models::SshResult;
+use crate::services::registry_service::{HostMetadata, RegistryService};
+use serde::{Deserialize, Serialize};
+
+/// Printed by the probe when a shell runs it; split in the command so a forced
+/// command echoing `$SSH_ORIGINAL_COMMAND` doesn't look like a shell
+const PROBE_MARKER: &str = "SSHBUDDY_PROBE";
+const RESTRICTED_MARKER: &str = "SSHBUDDY_RESTRICTED";
+
+/// Run by the connection test to find out what the account's "shell" is
+/// No redirections or slashes, which rbash refuses before running anything
+pub const SHELL_PROBE_COMMAND: &str =
+    "echo SSHBUDDY_\"\"PROBE; shopt -q restricted_shell && echo SSHBUDDY_\"\"RESTRICTED";
+
+/// What an account gets when it asks for a shell
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ShellAccess {
+    Full,
+    /// rbash and similar: a shell, but commands with paths and redirections are refused
+    Restricted,
+    /// `ForceCommand internal-sftp` or an sftp-only login shell
+    SftpOnly,
+    /// ForceCommand (git-shell, a backup script, ...) runs whatever is requested
+    ForcedCommand,
+}
+
+/// Shell access found by the last successful connection test, stored in the host registry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostShellAccess {
+    pub access: ShellAccess,
+    /// Unix milliseconds
+    pub detected_at: i64,
+    /// What the server answered the probe with (first line)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Where the UI should take a host instead of an interactive shell
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ShellRoute {
+    /// Terminal with the usual shell (and tmux, if preferred)
+    Shell,
+    /// Terminal without tmux or other commands, since the shell would refuse them
+    PlainShell,
+    /// Terminal showing the forced command's output
+    ForcedCommand,
+    /// File browser; there is no terminal
+    Sftp,
+}
+
+impl ShellAccess {
+    pub fn route(self) -> ShellRoute {
+        match self {
+            ShellAccess::Full => ShellRoute::Shell,
+            ShellAccess::Restricted => ShellRoute::PlainShell,
+            ShellAccess::ForcedCommand => ShellRoute::ForcedCommand,
+            ShellAccess::SftpOnly => ShellRoute::Sftp,
+        }
+    }
+}
+
+/// Labels hosts whose shell is disabled or restricted
+pub struct ShellAccessService;
+
+impl ShellAccessService {
+    /// Label of a host (None until a connection test detected it)
+    pub async fn get_host(host_alias: &str) -> SshResult<Option<HostShellAccess>> {
+        let store = RegistryService::load().await?;
+        Ok(store
+            .hosts
+            .get(host_alias)
+            .and_then(|m| m.shell_access.clone()))
+    }
+
+    /// Where to open a host; unknown hosts get a normal shell
+    pub async fn route(host_alias: &str) -> SshResult<ShellRoute> {
+        Ok(Self::get_host(host_alias)
+            .await?
+            .map(|s| s.access.route())
+            .unwrap_or(ShellRoute::Shell))
+    }
+
+    /// Store what a connection test detected
+    pub async fn record(host_alias: &str, access: &HostShellAccess) -> SshResult<()> {
+        RegistryService::update(|store| {
+            let metadata = store
+                .hosts
+                .entry(host_alias.to_string())
+                .or_insert_with(HostMetadata::new);
+            if metadata.shell_access.as_ref().map(|s| s.access) != Some(access.access) {
+                tracing::info!(
+                    "[shell_access] {} has {:?} access",
+                    host_alias,
+                    access.access
+                );
+            }
+            metadata.shell_access = Some(access.clone());
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Classify the probe's output; `sftp_available` is whether an SFTP session started
+pub fn classify_shell_probe(output: &str, sftp_available: bool) -> ShellAccess {
+    if output.contains(PROBE_MARKER) {
+        if output.contains(RESTRICTED_MARKER) {
+            ShellAccess::Restricted
+        } else {
+            ShellAccess::Full
+        }
+    } else if sftp_available {
+        // internal-sftp refuses commands ("This service allows sftp connections only.")
+        ShellAccess::SftpOnly
+    } else {
+        ShellAccess::ForcedCommand
+    }
+}
+
+/// First line of the probe output that isn't a marker, kept as the label's detail
+pub fn probe_detail(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && *l != PROBE_MARKER && *l != RESTRICTED_MARKER)
+        .map(|l| l.chars().take(200).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_shell_probe() {
+        assert_eq!(
+            classify_shell_probe("SSHBUDDY_PROBE\n", true),
+            ShellAccess::Full
+        );
+        assert_eq!(
+            classify_shell_probe("SSHBUDDY_PROBE\nSSHBUDDY_RESTRICTED\n", true),
+            ShellAccess::Restricted
+        );
+        assert_eq!(
+            classify_shell_probe("This service allows sftp connections only.\n", true),
+            ShellAccess::SftpOnly
+        );
+        assert_eq!(
+            classify_shell_probe("fatal: unrecognized command 'echo'\n", false),
+            ShellAccess::ForcedCommand
+        );
+        // A forced command echoing the request doesn't print the joined marker
+        assert_eq!(
+            classify_shell_probe(SHELL_PROBE_COMMAND, false),
+            ShellAccess::ForcedCommand
+        );
+    }
+
+    #[test]
+    fn test_route() {
+        assert_eq!(ShellAccess::Full.route(), ShellRoute::Shell);
+        assert_eq!(ShellAccess::Restricted.route(), ShellRoute::PlainShell);
+        assert_eq!(ShellAccess::SftpOnly.route(), ShellRoute::Sftp);
+    }
+
+    #[test]
+    fn test_probe_detail() {
+        assert_eq!(
+            probe_detail("\n  This service allows sftp connections only.\nbye"),
+            Some("This service allows sftp connections only.".to_string())
+        );
+        assert_eq!(probe_detail(" \nSSHBUDDY_PROBE\n"), None);
+    }
+}
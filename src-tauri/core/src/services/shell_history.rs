@@ -0,0 +1,563 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::settings_service::SettingsService;
+use crate::services::vm_discovery::sanitize_alias;
+use crate::utils::{validate_hostname, SshConfigEditor, SshConfigParser};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Only the tail of large history files is read
+const MAX_HISTORY_BYTES: usize = 8 * 1024 * 1024;
+
+/// Hosts connected to fewer times than this are not proposed by default
+const DEFAULT_MIN_COUNT: usize = 2;
+
+/// Registry tag given to imported hosts
+const HISTORY_TAG: &str = "history";
+
+/// ssh options that take an argument (from ssh(1))
+const SSH_ARG_FLAGS: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+/// Shell whose history a host was found in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryShell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+/// Host the user connects to from a shell but has no Host entry for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryHost {
+    /// Proposed alias (may be changed before importing)
+    pub alias: String,
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Number of ssh invocations found
+    pub count: usize,
+    /// Most recent invocation (Unix milliseconds), if the history has timestamps
+    pub last_used: Option<i64>,
+    pub shells: Vec<HistoryShell>,
+}
+
+/// Shell history import request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryImportRequest {
+    pub hosts: Vec<HistoryHost>,
+}
+
+/// Shell history import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryImportResult {
+    pub created: Vec<String>,
+    /// Aliases skipped because they already exist or are invalid
+    pub skipped: Vec<String>,
+}
+
+/// ssh invocation parsed from a history line
+#[derive(Debug, Clone, PartialEq)]
+struct SshInvocation {
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+}
+
+/// Proposes hosts from ssh invocations in the shell histories (opt-in, read only)
+pub struct ShellHistoryService;
+
+impl ShellHistoryService {
+    /// Scan the bash, zsh, fish and PowerShell histories for hosts missing from the config
+    /// Requires the `privacy.scanShellHistory` setting; nothing read is stored
+    pub async fn scan(min_count: Option<usize>) -> SshResult<Vec<HistoryHost>> {
+        if !SettingsService::get().privacy.scan_shell_history {
+            return Err(SshBuddyError::PermissionDenied {
+                reason: "Shell history scanning is turned off in the privacy settings".to_string(),
+            });
+        }
+
+        let mut entries = Vec::new();
+        for (shell, path) in history_files() {
+            let Some(content) = read_tail(&path).await else {
+                continue;
+            };
+            let commands = match shell {
+                HistoryShell::Bash => parse_bash(&content),
+                HistoryShell::Zsh => parse_zsh(&content),
+                HistoryShell::Fish => parse_fish(&content),
+                HistoryShell::PowerShell => {
+                    content.lines().map(|l| (l.to_string(), None)).collect()
+                }
+            };
+            tracing::info!(
+                "[shell_history] Read {} command(s) from {:?} history",
+                commands.len(),
+                shell
+            );
+            entries.extend(
+                commands
+                    .into_iter()
+                    .map(|(command, at)| (shell, command, at)),
+            );
+        }
+
+        let editor = ConfigService::load_editor().await?;
+        let hosts = collect_hosts(&entries, &editor, min_count.unwrap_or(DEFAULT_MIN_COUNT));
+        tracing::info!("[shell_history] Proposing {} host(s)", hosts.len());
+        Ok(hosts)
+    }
+
+    /// Create Host entries for the selected history hosts
+    pub async fn import_hosts(request: &HistoryImportRequest) -> SshResult<HistoryImportResult> {
+        ReadOnlyMode::ensure_writable("import hosts from shell history")?;
+
+        let (created, skipped) = ConfigService::create_hosts_with_tags(HISTORY_TAG, |editor| {
+            let mut created = Vec::new();
+            let mut skipped = Vec::new();
+            for host in &request.hosts {
+                let alias = host.alias.trim();
+                if editor.has_host(alias)
+                    || ConfigService::validate_alias(alias).is_err()
+                    || validate_hostname(&host.host).is_err()
+                {
+                    skipped.push(host.alias.clone());
+                    continue;
+                }
+                editor.append_host(alias, &host_options(host));
+                created.push(alias.to_string());
+            }
+            (created, skipped)
+        })
+        .await?;
+
+        tracing::info!(
+            "[shell_history] Imported {} host(s) ({} skipped)",
+            created.len(),
+            skipped.len()
+        );
+        Ok(HistoryImportResult { created, skipped })
+    }
+}
+
+/// History files of the supported shells (missing ones are skipped when read)
+fn history_files() -> Vec<(HistoryShell, PathBuf)> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let mut files = vec![
+        (HistoryShell::Bash, home.join(".bash_history")),
+        (HistoryShell::Zsh, home.join(".zsh_history")),
+        (HistoryShell::Zsh, home.join(".histfile")),
+        (
+            HistoryShell::Fish,
+            home.join(".local/share/fish/fish_history"),
+        ),
+    ];
+    if let Some(zdotdir) = std::env::var_os("ZDOTDIR").map(PathBuf::from) {
+        if zdotdir != home {
+            files.push((HistoryShell::Zsh, zdotdir.join(".zsh_history")));
+        }
+    }
+    let psreadline = if cfg!(windows) {
+        dirs::data_dir().map(|d| d.join("Microsoft\\Windows\\PowerShell\\PSReadLine"))
+    } else {
+        Some(home.join(".local/share/powershell/PSReadLine"))
+    };
+    if let Some(dir) = psreadline {
+        files.push((
+            HistoryShell::PowerShell,
+            dir.join("ConsoleHost_history.txt"),
+        ));
+    }
+    files
+}
+
+/// Last MAX_HISTORY_BYTES of a history file (zsh stores non-UTF-8 bytes, so lossy)
+async fn read_tail(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).await.ok()?;
+    let start = bytes.len().saturating_sub(MAX_HISTORY_BYTES);
+    Some(String::from_utf8_lossy(&bytes[start..]).into_owned())
+}
+
+/// Bash history; `#<epoch>` lines precede commands when HISTTIMEFORMAT is set
+fn parse_bash(content: &str) -> Vec<(String, Option<i64>)> {
+    let mut commands = Vec::new();
+    let mut at = None;
+    for line in content.lines() {
+        if let Some(secs) = line.strip_prefix('#').and_then(|s| s.parse::<i64>().ok()) {
+            at = Some(secs * 1000);
+            continue;
+        }
+        commands.push((line.to_string(), at.take()));
+    }
+    commands
+}
+
+/// Zsh history, plain or in the extended `: <epoch>:<duration>;<command>` format
+fn parse_zsh(content: &str) -> Vec<(String, Option<i64>)> {
+    content
+        .lines()
+        .map(|line| {
+            let extended = line.strip_prefix(": ").and_then(|rest| {
+                let (meta, command) = rest.split_once(';')?;
+                let secs = meta.split(':').next()?.parse::<i64>().ok()?;
+                Some((command.to_string(), Some(secs * 1000)))
+            });
+            extended.unwrap_or_else(|| (line.to_string(), None))
+        })
+        .collect()
+}
+
+/// Fish history (YAML-like `- cmd:` / `  when:` entries)
+fn parse_fish(content: &str) -> Vec<(String, Option<i64>)> {
+    let mut commands: Vec<(String, Option<i64>)> = Vec::new();
+    for line in content.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            commands.push((command.replace("\\n", "\n").replace("\\\\", "\\"), None));
+        } else if let Some(secs) = line.trim_start().strip_prefix("when: ") {
+            if let Some(last) = commands.last_mut() {
+                last.1 = secs.trim().parse::<i64>().ok().map(|s| s * 1000);
+            }
+        }
+    }
+    commands
+}
+
+/// Split a command line into words (quotes group, no expansion)
+pub(crate) fn split_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// ssh invocations in a history line (one per `;`, `&&`, `||` or `|` separated command)
+fn parse_ssh_commands(line: &str) -> Vec<SshInvocation> {
+    line.split([';', '|', '&', '\n'])
+        .filter_map(|segment| parse_ssh(&split_words(segment)))
+        .collect()
+}
+
+/// Destination of an `ssh [options] destination [command]` invocation
+fn parse_ssh(words: &[String]) -> Option<SshInvocation> {
+    // Skip environment assignments and wrappers like `sudo` / `exec`
+    let start = words.iter().position(|w| {
+        !w.contains('=') && !matches!(w.as_str(), "sudo" | "exec" | "command" | "noglob")
+    })?;
+    let program = words[start].rsplit(['/', '\\']).next()?;
+    if !program.eq_ignore_ascii_case("ssh") && !program.eq_ignore_ascii_case("ssh.exe") {
+        return None;
+    }
+
+    let mut user = None;
+    let mut port = None;
+    let mut args = words[start + 1..].iter();
+    let destination = loop {
+        let arg = args.next()?;
+        let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty()) else {
+            break arg;
+        };
+        if flags == "-" {
+            break args.next()?;
+        }
+        for (i, flag) in flags.char_indices() {
+            if !SSH_ARG_FLAGS.contains(flag) {
+                continue;
+            }
+            let rest = &flags[i + flag.len_utf8()..];
+            let value = if rest.is_empty() {
+                args.next()?.clone()
+            } else {
+                rest.to_string()
+            };
+            match flag {
+                'p' => port = Some(value.parse::<u16>().ok()?),
+                'l' => user = Some(value),
+                'o' => match value.split_once(['=', ' ']) {
+                    Some((key, v)) if key.eq_ignore_ascii_case("port") => {
+                        port = Some(v.trim().parse::<u16>().ok()?)
+                    }
+                    Some((key, v)) if key.eq_ignore_ascii_case("user") => {
+                        user = Some(v.trim().to_string())
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+            break;
+        }
+    };
+
+    let (dest_user, host, dest_port) = parse_destination(destination)?;
+    validate_hostname(&host).ok()?;
+    Some(SshInvocation {
+        user: dest_user.or(user).filter(|u| !u.is_empty()),
+        host,
+        port: dest_port.or(port),
+    })
+}
+
+/// `[user@]host` or `ssh://[user@]host[:port]`
+fn parse_destination(destination: &str) -> Option<(Option<String>, String, Option<u16>)> {
+    let (uri, rest) = match destination.strip_prefix("ssh://") {
+        Some(rest) => (true, rest.trim_end_matches('/')),
+        None => (false, destination),
+    };
+    let (user, host) = match rest.rsplit_once('@') {
+        Some((user, host)) => (Some(user.to_string()), host),
+        None => (None, rest),
+    };
+    let (host, port) = match host.rsplit_once(':') {
+        Some((h, p)) if uri && !h.contains(':') => (h, Some(p.parse::<u16>().ok()?)),
+        _ => (host, None),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return None;
+    }
+    Some((user, host.to_lowercase(), port))
+}
+
+/// Aggregate invocations by user/host/port, drop hosts already in the config, most used first
+fn collect_hosts(
+    entries: &[(HistoryShell, String, Option<i64>)],
+    editor: &SshConfigEditor,
+    min_count: usize,
+) -> Vec<HistoryHost> {
+    let configured: HashSet<String> = {
+        let parsed = SshConfigParser::parse(&editor.render());
+        parsed
+            .iter()
+            .flat_map(|h| {
+                h.host_pattern
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .chain(h.hostname.clone())
+                    .collect::<Vec<_>>()
+            })
+            .map(|name| name.to_lowercase())
+            .collect()
+    };
+
+    let mut found: BTreeMap<(String, Option<String>, Option<u16>), HistoryHost> = BTreeMap::new();
+    for (shell, line, at) in entries {
+        for invocation in parse_ssh_commands(line) {
+            if configured.contains(&invocation.host) {
+                continue;
+            }
+            let key = (
+                invocation.host.clone(),
+                invocation.user.clone(),
+                invocation.port,
+            );
+            let host = found.entry(key).or_insert_with(|| HistoryHost {
+                alias: String::new(),
+                host: invocation.host,
+                user: invocation.user,
+                port: invocation.port,
+                count: 0,
+                last_used: None,
+                shells: Vec::new(),
+            });
+            host.count += 1;
+            if at.is_some() && host.last_used < *at {
+                host.last_used = *at;
+            }
+            if !host.shells.contains(shell) {
+                host.shells.push(*shell);
+                host.shells.sort();
+            }
+        }
+    }
+
+    let mut hosts: Vec<HistoryHost> = found
+        .into_values()
+        .filter(|h| h.count >= min_count.max(1))
+        .collect();
+    hosts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.host.cmp(&b.host)));
+
+    let mut taken: HashSet<String> = editor.host_aliases().into_iter().collect();
+    for host in &mut hosts {
+        host.alias = propose_alias(host, &taken);
+        taken.insert(host.alias.clone());
+    }
+    hosts
+}
+
+/// First label of a hostname (whole IP addresses), then the user, then a counter
+fn propose_alias(host: &HistoryHost, taken: &HashSet<String>) -> String {
+    let is_ip = host.host.parse::<std::net::IpAddr>().is_ok();
+    let base = if is_ip {
+        sanitize_alias(&host.host)
+    } else {
+        sanitize_alias(host.host.split('.').next().unwrap_or(&host.host))
+    };
+    let mut candidates = vec![base.clone()];
+    if let Some(user) = &host.user {
+        candidates.push(sanitize_alias(&format!("{}-{}", base, user)));
+    }
+    if let Some(alias) = candidates.into_iter().find(|a| !taken.contains(a)) {
+        return alias;
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|a| !taken.contains(a))
+        .unwrap_or(base)
+}
+
+/// Host block options for a history host
+fn host_options(host: &HistoryHost) -> Vec<(String, String)> {
+    let mut options = vec![("HostName".to_string(), host.host.clone())];
+    if let Some(port) = host.port.filter(|p| *p != 22) {
+        options.push(("Port".to_string(), port.to_string()));
+    }
+    if let Some(user) = &host.user {
+        options.push(("User".to_string(), user.clone()));
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ssh(line: &str) -> Option<SshInvocation> {
+        parse_ssh_commands(line).into_iter().next()
+    }
+
+    #[test]
+    fn test_parse_ssh() {
+        assert_eq!(
+            ssh("ssh -p 2222 deploy@web1.example.com uptime"),
+            Some(SshInvocation {
+                user: Some("deploy".to_string()),
+                host: "web1.example.com".to_string(),
+                port: Some(2222),
+            })
+        );
+        assert_eq!(
+            ssh("/usr/bin/ssh -vA -l admin -p2200 10.0.0.5"),
+            Some(SshInvocation {
+                user: Some("admin".to_string()),
+                host: "10.0.0.5".to_string(),
+                port: Some(2200),
+            })
+        );
+        assert_eq!(
+            ssh("TERM=xterm ssh -i ~/.ssh/id -o Port=2022 ssh://root@db:2023"),
+            Some(SshInvocation {
+                user: Some("root".to_string()),
+                host: "db".to_string(),
+                port: Some(2023),
+            })
+        );
+        assert_eq!(
+            ssh("cd /tmp && ssh.exe Pi@RaspberryPi.local").unwrap().host,
+            "raspberrypi.local"
+        );
+        assert!(ssh("ssh-keygen -t ed25519").is_none());
+        assert!(ssh("ssh -p").is_none());
+        assert!(ssh("ssh $HOST").is_none());
+        assert!(ssh("echo ssh web1").is_none());
+    }
+
+    #[test]
+    fn test_parse_history_formats() {
+        assert_eq!(
+            parse_bash("#1700000000\nssh web\nls\n"),
+            vec![
+                ("ssh web".to_string(), Some(1_700_000_000_000)),
+                ("ls".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            parse_zsh(": 1700000000:0;ssh web\nssh db\n"),
+            vec![
+                ("ssh web".to_string(), Some(1_700_000_000_000)),
+                ("ssh db".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            parse_fish("- cmd: ssh web\n  when: 1700000000\n- cmd: ls\n"),
+            vec![
+                ("ssh web".to_string(), Some(1_700_000_000_000)),
+                ("ls".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_hosts() {
+        let editor = SshConfigEditor::parse("Host web1\n    HostName 10.0.0.1\n\nHost db\n");
+        let entries: Vec<(HistoryShell, String, Option<i64>)> = vec![
+            (HistoryShell::Bash, "ssh web1".to_string(), Some(1)),
+            (HistoryShell::Bash, "ssh root@10.0.0.1".to_string(), None),
+            (
+                HistoryShell::Bash,
+                "ssh admin@app.corp".to_string(),
+                Some(5),
+            ),
+            (HistoryShell::Zsh, "ssh admin@app.corp".to_string(), Some(9)),
+            (HistoryShell::Zsh, "ssh app.corp".to_string(), None),
+            (HistoryShell::Fish, "ssh app.corp".to_string(), None),
+            (HistoryShell::Fish, "ssh db.corp".to_string(), None),
+            (HistoryShell::Fish, "ssh db.corp".to_string(), None),
+            (HistoryShell::Fish, "ssh once.corp".to_string(), None),
+        ];
+        let hosts = collect_hosts(&entries, &editor, 2);
+        let summary: Vec<(&str, &str, usize)> = hosts
+            .iter()
+            .map(|h| (h.alias.as_str(), h.host.as_str(), h.count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("app", "app.corp", 2),
+                ("app-admin", "app.corp", 2),
+                ("db-2", "db.corp", 2),
+            ]
+        );
+        let admin = &hosts[1];
+        assert_eq!(admin.last_used, Some(9));
+        assert_eq!(admin.shells, vec![HistoryShell::Bash, HistoryShell::Zsh]);
+        assert_eq!(
+            host_options(admin),
+            vec![
+                ("HostName".to_string(), "app.corp".to_string()),
+                ("User".to_string(), "admin".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,527 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::console_server::ConsoleSession;
+use crate::services::ssh_connection::{ExecChannel, RemoteSession, SshConnectionService};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+/// Reconnect attempts after a dropped connection before the session is closed
+const RECONNECT_ATTEMPTS: u32 = 6;
+
+/// Delay before the first reconnect attempt; doubled after each failure
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound for the reconnect delay
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Output kept per session so a terminal view can be restored (bytes)
+const MAX_SCROLLBACK: usize = 256 * 1024;
+
+/// Output chunks buffered for each observer of a shared session
+const MIRROR_CAPACITY: usize = 256;
+
+/// Input sent to a running shell
+enum ShellInput {
+    Data(Vec<u8>),
+    Resize { cols: u32, rows: u32 },
+    Close,
+}
+
+/// Shell session output/lifecycle event
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ShellEvent {
+    #[serde(rename_all = "camelCase")]
+    Output { session_id: String, data: String },
+    /// The connection dropped; the next attempt starts after `delay_ms`
+    #[serde(rename_all = "camelCase")]
+    Reconnecting {
+        session_id: String,
+        attempt: u32,
+        max_attempts: u32,
+        delay_ms: u64,
+    },
+    /// A new connection took over the session (same id, scrollback kept)
+    #[serde(rename_all = "camelCase")]
+    Reconnected { session_id: String },
+    #[serde(rename_all = "camelCase")]
+    Closed {
+        session_id: String,
+        exit_code: Option<u32>,
+    },
+}
+
+/// How a channel stopped
+enum PumpEnd {
+    /// The remote side closed it (exit status if one was sent)
+    Ended(Option<u32>),
+    /// The user closed the session
+    Closed,
+}
+
+struct SessionEntry {
+    host_alias: String,
+    /// Command run instead of the login shell (e.g. a tmux attach)
+    command: Option<String>,
+    input: mpsc::UnboundedSender<ShellInput>,
+    scrollback: Arc<std::sync::Mutex<String>>,
+    /// Set when the host is a console server
+    console: Option<Arc<ConsoleSession>>,
+    /// Output copied to read-only observers of the session
+    mirror: broadcast::Sender<String>,
+}
+
+/// Interactive SSH shells on a pseudo-terminal, keyed by session id
+pub struct ShellSessionManager {
+    sessions: Mutex<HashMap<String, SessionEntry>>,
+}
+
+impl ShellSessionManager {
+    /// Global manager
+    pub fn global() -> &'static ShellSessionManager {
+        static MANAGER: OnceLock<ShellSessionManager> = OnceLock::new();
+        MANAGER.get_or_init(|| ShellSessionManager {
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Open a shell (or run `command` on a terminal) and return its session id
+    /// Output and the final exit status are reported through `on_event`
+    /// With `reconnect`, a dropped connection is re-established with backoff under the
+    /// same session id (`command` is run again, so a tmux command re-attaches)
+    /// With `console`, input goes through the console's line discipline and output is
+    /// logged if the host asks for it
+    #[allow(clippy::too_many_arguments)]
+    pub async fn open<F>(
+        &'static self,
+        host_alias: &str,
+        command: Option<String>,
+        cols: u32,
+        rows: u32,
+        prompter: Option<Arc<dyn AuthPrompter>>,
+        reconnect: bool,
+        console: Option<ConsoleSession>,
+        on_event: F,
+    ) -> SshResult<String>
+    where
+        F: Fn(ShellEvent) + Send + Sync + 'static,
+    {
+        let (session, channel) = Self::connect(
+            host_alias,
+            command.as_deref(),
+            cols,
+            rows,
+            prompter.as_deref(),
+        )
+        .await?;
+
+        let session_id = format!("{:016x}", rand::random::<u64>());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let scrollback = Arc::new(std::sync::Mutex::new(String::new()));
+        let console = console.map(Arc::new);
+        let (mirror, _) = broadcast::channel(MIRROR_CAPACITY);
+        self.sessions.lock().await.insert(
+            session_id.clone(),
+            SessionEntry {
+                host_alias: host_alias.to_string(),
+                command: command.clone(),
+                input: tx,
+                scrollback: scrollback.clone(),
+                console: console.clone(),
+                mirror: mirror.clone(),
+            },
+        );
+        tracing::info!(
+            "[shell_session] Opened {} on {} ({}x{})",
+            session_id,
+            host_alias,
+            cols,
+            rows
+        );
+
+        let id = session_id.clone();
+        let host_alias = host_alias.to_string();
+        tokio::spawn(async move {
+            let mut session = session;
+            let mut channel = channel;
+            let mut size = (cols, rows);
+            let exit_code = loop {
+                let end = Self::pump(
+                    channel,
+                    &mut rx,
+                    &id,
+                    &scrollback,
+                    console.as_deref(),
+                    &mirror,
+                    &mut size,
+                    &on_event,
+                )
+                .await;
+                let dropped = session.is_closed();
+                session.close().await;
+                let code = match end {
+                    PumpEnd::Closed => break None,
+                    PumpEnd::Ended(code) => code,
+                };
+                if code.is_some() || !dropped || !reconnect {
+                    break code;
+                }
+
+                tracing::warn!("[shell_session] Connection of {} dropped", id);
+                let restored = Self::reconnect(
+                    &host_alias,
+                    command.as_deref(),
+                    &mut size,
+                    prompter.as_deref(),
+                    &mut rx,
+                    &id,
+                    &on_event,
+                )
+                .await;
+                match restored {
+                    Some((new_session, new_channel)) => {
+                        session = new_session;
+                        channel = new_channel;
+                        on_event(ShellEvent::Reconnected {
+                            session_id: id.clone(),
+                        });
+                    }
+                    None => break None,
+                }
+            };
+            self.sessions.lock().await.remove(&id);
+            tracing::info!("[shell_session] Closed {} (exit {:?})", id, exit_code);
+            on_event(ShellEvent::Closed {
+                session_id: id,
+                exit_code,
+            });
+        });
+        Ok(session_id)
+    }
+
+    async fn connect(
+        host_alias: &str,
+        command: Option<&str>,
+        cols: u32,
+        rows: u32,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> SshResult<(RemoteSession, ExecChannel)> {
+        let session = SshConnectionService::open_session(host_alias, prompter).await?;
+        match session.start_pty(command, cols, rows).await {
+            Ok(channel) => Ok((session, channel)),
+            Err(e) => {
+                session.close().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Retry the connection with backoff; None when every attempt failed or the user
+    /// closed the session meanwhile (input typed while disconnected is dropped)
+    async fn reconnect<F>(
+        host_alias: &str,
+        command: Option<&str>,
+        size: &mut (u32, u32),
+        prompter: Option<&dyn AuthPrompter>,
+        rx: &mut mpsc::UnboundedReceiver<ShellInput>,
+        session_id: &str,
+        on_event: &F,
+    ) -> Option<(RemoteSession, ExecChannel)>
+    where
+        F: Fn(ShellEvent),
+    {
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            let delay = reconnect_delay(attempt);
+            on_event(ShellEvent::Reconnecting {
+                session_id: session_id.to_string(),
+                attempt,
+                max_attempts: RECONNECT_ATTEMPTS,
+                delay_ms: delay.as_millis() as u64,
+            });
+
+            let wait = tokio::time::sleep(delay);
+            tokio::pin!(wait);
+            loop {
+                tokio::select! {
+                    _ = &mut wait => break,
+                    input = rx.recv() => match input {
+                        Some(ShellInput::Resize { cols, rows }) => *size = (cols, rows),
+                        Some(ShellInput::Data(_)) => {}
+                        Some(ShellInput::Close) | None => return None,
+                    },
+                }
+            }
+
+            match Self::connect(host_alias, command, size.0, size.1, prompter).await {
+                Ok(connection) => {
+                    tracing::info!(
+                        "[shell_session] Reconnected {} on attempt {}",
+                        session_id,
+                        attempt
+                    );
+                    return Some(connection);
+                }
+                Err(e) => tracing::warn!(
+                    "[shell_session] Reconnect attempt {} of {} failed: {}",
+                    attempt,
+                    session_id,
+                    e
+                ),
+            }
+        }
+        None
+    }
+
+    /// Forward input to the channel and output to `on_event` until either side closes
+    async fn pump<F>(
+        mut channel: ExecChannel,
+        rx: &mut mpsc::UnboundedReceiver<ShellInput>,
+        session_id: &str,
+        scrollback: &std::sync::Mutex<String>,
+        console: Option<&ConsoleSession>,
+        mirror: &broadcast::Sender<String>,
+        size: &mut (u32, u32),
+        on_event: &F,
+    ) -> PumpEnd
+    where
+        F: Fn(ShellEvent),
+    {
+        let mut pending = Vec::new();
+        loop {
+            tokio::select! {
+                output = channel.next_output() => match output {
+                    Some((_, data)) => {
+                        pending.extend_from_slice(&data);
+                        let text = take_utf8(&mut pending);
+                        if !text.is_empty() {
+                            if let Ok(mut buffer) = scrollback.lock() {
+                                push_scrollback(&mut buffer, &text, MAX_SCROLLBACK);
+                                // Sent under the lock so `subscribe` sees each chunk once
+                                if mirror.receiver_count() > 0 {
+                                    let _ = mirror.send(text.clone());
+                                }
+                            }
+                            if let Some(console) = console {
+                                console.log_output(&text);
+                            }
+                            on_event(ShellEvent::Output {
+                                session_id: session_id.to_string(),
+                                data: text,
+                            });
+                        }
+                    }
+                    None => return PumpEnd::Ended(channel.exit_code()),
+                },
+                input = rx.recv() => {
+                    let result = match input {
+                        Some(ShellInput::Data(data)) => channel.write(&data).await,
+                        Some(ShellInput::Resize { cols, rows }) => {
+                            *size = (cols, rows);
+                            channel.resize(cols, rows).await
+                        }
+                        Some(ShellInput::Close) | None => {
+                            channel.abort().await;
+                            return PumpEnd::Closed;
+                        }
+                    };
+                    if let Err(e) = result {
+                        tracing::warn!("[shell_session] {}: {}", session_id, e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn send(&self, session_id: &str, input: ShellInput) -> SshResult<()> {
+        self.sessions
+            .lock()
+            .await
+            .get(session_id)
+            .and_then(|entry| entry.input.send(input).ok())
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Shell session not found: {}", session_id),
+            })
+    }
+
+    async fn console(&self, session_id: &str) -> SshResult<Option<Arc<ConsoleSession>>> {
+        self.sessions
+            .lock()
+            .await
+            .get(session_id)
+            .map(|entry| entry.console.clone())
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Shell session not found: {}", session_id),
+            })
+    }
+
+    /// Send keyboard input (translated by the line discipline on console servers)
+    pub async fn write(&self, session_id: &str, data: &str) -> SshResult<()> {
+        let data = match self.console(session_id).await? {
+            Some(console) => console.translate_input(data.as_bytes()),
+            None => data.as_bytes().to_vec(),
+        };
+        self.send(session_id, ShellInput::Data(data)).await
+    }
+
+    /// Send the console server's break sequence
+    pub async fn send_break(&self, session_id: &str) -> SshResult<()> {
+        let console =
+            self.console(session_id)
+                .await?
+                .ok_or_else(|| SshBuddyError::InvalidOption {
+                    message: format!("{} is not a console session", session_id),
+                })?;
+        tracing::info!("[shell_session] Sending break to {}", session_id);
+        self.send(session_id, ShellInput::Data(console.break_bytes().to_vec()))
+            .await
+    }
+
+    /// File a console session's output is logged to
+    pub async fn console_log_path(&self, session_id: &str) -> SshResult<Option<String>> {
+        Ok(self
+            .console(session_id)
+            .await?
+            .and_then(|c| c.log_path().map(|p| p.to_string_lossy().to_string())))
+    }
+
+    /// Resize the terminal
+    pub async fn resize(&self, session_id: &str, cols: u32, rows: u32) -> SshResult<()> {
+        self.send(session_id, ShellInput::Resize { cols, rows })
+            .await
+    }
+
+    /// Close the session
+    pub async fn close(&self, session_id: &str) -> SshResult<()> {
+        self.send(session_id, ShellInput::Close).await
+    }
+
+    /// Host and command a session was opened with
+    pub async fn describe(&self, session_id: &str) -> SshResult<(String, Option<String>)> {
+        self.sessions
+            .lock()
+            .await
+            .get(session_id)
+            .map(|entry| (entry.host_alias.clone(), entry.command.clone()))
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Shell session not found: {}", session_id),
+            })
+    }
+
+    /// Recent output of a session and a receiver for everything printed after it,
+    /// for mirroring the session read-only
+    /// The receiver closes when the session does
+    pub async fn subscribe(
+        &self,
+        session_id: &str,
+    ) -> SshResult<(String, String, broadcast::Receiver<String>)> {
+        let sessions = self.sessions.lock().await;
+        let entry = sessions
+            .get(session_id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Shell session not found: {}", session_id),
+            })?;
+        // Output is mirrored under the scrollback lock, so nothing is missed or repeated
+        let scrollback = entry
+            .scrollback
+            .lock()
+            .map(|b| b.clone())
+            .unwrap_or_default();
+        Ok((
+            entry.host_alias.clone(),
+            scrollback,
+            entry.mirror.subscribe(),
+        ))
+    }
+
+    /// Recent output of a session (kept across reconnects) to restore its terminal view
+    pub async fn scrollback(&self, session_id: &str) -> SshResult<String> {
+        self.sessions
+            .lock()
+            .await
+            .get(session_id)
+            .and_then(|entry| entry.scrollback.lock().ok().map(|b| b.clone()))
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Shell session not found: {}", session_id),
+            })
+    }
+}
+
+/// Delay before reconnect attempt `attempt` (1-based)
+fn reconnect_delay(attempt: u32) -> Duration {
+    RECONNECT_BASE_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(RECONNECT_MAX_DELAY)
+}
+
+/// Append output, dropping the oldest text beyond `limit` bytes
+fn push_scrollback(buffer: &mut String, text: &str, limit: usize) {
+    buffer.push_str(text);
+    if buffer.len() > limit {
+        let mut cut = buffer.len() - limit;
+        while !buffer.is_char_boundary(cut) {
+            cut += 1;
+        }
+        buffer.drain(..cut);
+    }
+}
+
+/// Decode the valid UTF-8 part of `buf`, keeping an incomplete character at the end
+/// for the next chunk (invalid bytes are replaced)
+fn take_utf8(buf: &mut Vec<u8>) -> String {
+    let keep = match std::str::from_utf8(buf) {
+        Ok(_) => 0,
+        // error_len() is None when the input just ends mid-character
+        Err(e) if e.error_len().is_none() => buf.len() - e.valid_up_to(),
+        Err(_) => 0,
+    };
+    let rest = buf.split_off(buf.len() - keep);
+    let text = String::from_utf8_lossy(buf).to_string();
+    *buf = rest;
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_utf8_keeps_split_character() {
+        let bytes = "héllo".as_bytes();
+        let mut buf = bytes[..2].to_vec();
+        assert_eq!(take_utf8(&mut buf), "h");
+        assert_eq!(buf, vec![0xc3]);
+
+        buf.extend_from_slice(&bytes[2..]);
+        assert_eq!(take_utf8(&mut buf), "éllo");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_reconnect_delay_backs_off() {
+        let delays: Vec<u64> = (1..=7).map(|a| reconnect_delay(a).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+    }
+
+    #[test]
+    fn test_push_scrollback_trims_oldest() {
+        let mut buffer = String::new();
+        push_scrollback(&mut buffer, "abc", 5);
+        push_scrollback(&mut buffer, "déf", 5);
+        assert_eq!(buffer, "cdéf");
+
+        // Cutting 2 bytes of "aébc" would split "é"
+        let mut buffer = "aé".to_string();
+        push_scrollback(&mut buffer, "bc", 3);
+        assert_eq!(buffer, "bc");
+    }
+
+    #[test]
+    fn test_take_utf8_replaces_invalid_bytes() {
+        let mut buf = vec![b'a', 0xff, b'b'];
+        assert_eq!(take_utf8(&mut buf), "a\u{fffd}b");
+        assert!(buf.is_empty());
+    }
+}
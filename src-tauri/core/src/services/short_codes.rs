@@ -0,0 +1,228 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::registry_service::{HostMetadata, MetadataStore, RegistryService};
+use crate::utils::SshConfigEditor;
+use serde::{Deserialize, Serialize};
+
+/// Longest short code; anything longer is no faster to type than the alias
+const MAX_CODE_LEN: usize = 8;
+
+/// Short code assigned to a host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostShortCode {
+    pub code: String,
+    pub alias: String,
+    /// A Host alias with the same name was added to the config after the code;
+    /// the alias wins, so the code no longer resolves
+    pub shadowed: bool,
+}
+
+/// User-defined short codes (`p1`, `dbx`) resolved by quick-connect and links
+pub struct ShortCodeService;
+
+impl ShortCodeService {
+    /// All short codes, sorted by code
+    pub async fn list() -> SshResult<Vec<HostShortCode>> {
+        let store = RegistryService::load().await?;
+        let editor = ConfigService::load_editor().await?;
+        Ok(list_codes(&store, &editor))
+    }
+
+    /// Set or clear (None) a host's short code
+    /// The code must not be taken by another host or be a Host alias in the config
+    pub async fn set(alias: &str, code: Option<&str>) -> SshResult<Option<String>> {
+        let editor = ConfigService::load_editor().await?;
+        if !editor.has_host(alias) {
+            return Err(SshBuddyError::HostNotFound {
+                alias: alias.to_string(),
+            });
+        }
+        let code = code.map(normalize_code).filter(|c| !c.is_empty());
+        if let Some(code) = &code {
+            validate_code(code)?;
+            check_config_collision(code, &editor)?;
+        }
+
+        let code = RegistryService::update(|store| {
+            if let Some(code) = &code {
+                check_registry_collision(code, alias, store)?;
+            }
+            store
+                .hosts
+                .entry(alias.to_string())
+                .or_insert_with(HostMetadata::new)
+                .short_code = code.clone();
+            Ok(code)
+        })
+        .await?;
+        tracing::info!("[short_codes] Short code of {} set to {:?}", alias, code);
+        Ok(code)
+    }
+
+    /// Host alias for what was typed: a configured alias as-is, otherwise the host
+    /// whose short code it is (case-insensitive)
+    pub async fn resolve(input: &str) -> SshResult<Option<String>> {
+        let editor = ConfigService::load_editor().await?;
+        if editor.has_host(input) {
+            return Ok(Some(input.to_string()));
+        }
+        let store = RegistryService::load().await?;
+        Ok(find_code(&store, &editor, input))
+    }
+}
+
+/// Codes are stored lowercase without surrounding whitespace
+fn normalize_code(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+/// Letters, digits, `-` and `_`, starting with a letter or digit
+fn validate_code(code: &str) -> SshResult<()> {
+    let valid_chars = code
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    let valid_start = code
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric());
+    if !valid_chars || !valid_start {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "Short code \"{}\" may only contain letters, digits, '-' and '_'",
+                code
+            ),
+        });
+    }
+    if code.chars().count() > MAX_CODE_LEN {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "Short code \"{}\" is longer than {} characters",
+                code, MAX_CODE_LEN
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn check_config_collision(code: &str, editor: &SshConfigEditor) -> SshResult<()> {
+    match editor
+        .host_aliases()
+        .into_iter()
+        .find(|a| a.eq_ignore_ascii_case(code))
+    {
+        Some(alias) => Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "Short code \"{}\" is already the Host alias {}",
+                code, alias
+            ),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn check_registry_collision(code: &str, alias: &str, store: &MetadataStore) -> SshResult<()> {
+    match store
+        .hosts
+        .iter()
+        .find(|(a, m)| a.as_str() != alias && m.short_code.as_deref() == Some(code))
+    {
+        Some((other, _)) => Err(SshBuddyError::InvalidOption {
+            message: format!("Short code \"{}\" is already used by {}", code, other),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Codes of hosts that are in the config, sorted by code
+pub(crate) fn list_codes(store: &MetadataStore, editor: &SshConfigEditor) -> Vec<HostShortCode> {
+    let aliases = editor.host_aliases();
+    let mut codes: Vec<HostShortCode> = store
+        .hosts
+        .iter()
+        .filter(|(alias, _)| aliases.contains(alias))
+        .filter_map(|(alias, metadata)| {
+            let code = metadata.short_code.clone()?;
+            Some(HostShortCode {
+                shadowed: aliases.iter().any(|a| a.eq_ignore_ascii_case(&code)),
+                code,
+                alias: alias.clone(),
+            })
+        })
+        .collect();
+    codes.sort_by(|a, b| a.code.cmp(&b.code));
+    codes
+}
+
+/// Host whose (not shadowed) short code is `input`
+fn find_code(store: &MetadataStore, editor: &SshConfigEditor, input: &str) -> Option<String> {
+    let input = normalize_code(input);
+    list_codes(store, editor)
+        .into_iter()
+        .find(|c| !c.shadowed && c.code == input)
+        .map(|c| c.alias)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "Host prod-web-1
+    HostName 10.0.0.1
+
+Host db-primary dbx
+    HostName 10.0.0.2
+";
+
+    fn store_with(codes: &[(&str, &str)]) -> MetadataStore {
+        let mut store = MetadataStore::default();
+        for (alias, code) in codes {
+            let mut metadata = HostMetadata::new();
+            metadata.short_code = Some(code.to_string());
+            store.hosts.insert(alias.to_string(), metadata);
+        }
+        store
+    }
+
+    #[test]
+    fn test_validate_code() {
+        assert!(validate_code("p1").is_ok());
+        assert!(validate_code("db_2-x").is_ok());
+        assert!(validate_code("-p").is_err());
+        assert!(validate_code("p 1").is_err());
+        assert!(validate_code("toolongcode").is_err());
+        assert_eq!(normalize_code("  P1 "), "p1");
+    }
+
+    #[test]
+    fn test_collisions() {
+        let editor = SshConfigEditor::parse(CONFIG);
+        assert!(check_config_collision("dbx", &editor).is_err());
+        assert!(check_config_collision("p1", &editor).is_ok());
+
+        let store = store_with(&[("prod-web-1", "p1")]);
+        assert!(check_registry_collision("p1", "db-primary", &store).is_err());
+        // Setting the same code again on its own host is fine
+        assert!(check_registry_collision("p1", "prod-web-1", &store).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_codes() {
+        let editor = SshConfigEditor::parse(CONFIG);
+        let store = store_with(&[("prod-web-1", "p1"), ("db-primary", "dbx"), ("gone", "g")]);
+
+        assert_eq!(
+            find_code(&store, &editor, "P1").as_deref(),
+            Some("prod-web-1")
+        );
+        // "dbx" became a Host alias: the alias wins
+        assert_eq!(find_code(&store, &editor, "dbx"), None);
+        // Codes of hosts no longer in the config are ignored
+        assert_eq!(find_code(&store, &editor, "g"), None);
+
+        let codes = list_codes(&store, &editor);
+        assert_eq!(codes.len(), 2);
+        assert!(codes[0].shadowed);
+        assert_eq!(codes[1].code, "p1");
+    }
+}
@@ -0,0 +1,346 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::history_service::{HistoryService, SessionRecord};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::{now_millis, MetadataStore, RegistryService};
+use crate::utils::{
+    ssh_config_path, workspace_data_path, write_atomic, GroupHost, GroupQuery, SshConfigEditor,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tokio::fs;
+
+/// Time windows like "within 7d" move on, so results are recomputed this often
+/// even when no input file changed
+const CACHE_TTL_MS: i64 = 60 * 1000;
+
+/// Host group whose members are the hosts matching a query
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartGroup {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// See `GroupQuery` for the syntax
+    pub query: String,
+}
+
+/// Smart group with its current members
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartGroupMembers {
+    #[serde(flatten)]
+    pub group: SmartGroup,
+    /// Matching host aliases, sorted
+    pub aliases: Vec<String>,
+    /// When the members were computed, Unix milliseconds
+    pub evaluated_at: i64,
+}
+
+/// smart_groups.json contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SmartGroupStore {
+    #[serde(default)]
+    groups: Vec<SmartGroup>,
+}
+
+impl Default for SmartGroupStore {
+    /// Groups shown before the user saved any
+    fn default() -> Self {
+        Self {
+            groups: vec![SmartGroup {
+                id: "recent".to_string(),
+                name: "Recently connected".to_string(),
+                query: "connected within 7d".to_string(),
+            }],
+        }
+    }
+}
+
+/// Modification time and size of an input file (None when missing)
+type FileStamp = Option<(i64, u64)>;
+
+/// Host snapshot the members were computed from
+struct Evaluation {
+    /// Registry, history and SSH config files, in that order
+    inputs: Vec<(PathBuf, FileStamp)>,
+    at: i64,
+    hosts: Vec<GroupHost>,
+    /// Members by query text
+    members: HashMap<String, Vec<String>>,
+}
+
+static CACHE: Mutex<Option<Evaluation>> = Mutex::new(None);
+
+/// Saved queries over the hosts, evaluated against the registry and session history
+/// when they're listed; results are cached until an input changes or they get stale
+pub struct SmartGroupService;
+
+impl SmartGroupService {
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("smart_groups.json")
+    }
+
+    async fn load_store() -> SshResult<SmartGroupStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(SmartGroupStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse smart groups: {}", e),
+        })
+    }
+
+    async fn save_store(store: &SmartGroupStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// All groups with their members
+    pub async fn list() -> SshResult<Vec<SmartGroupMembers>> {
+        let store = Self::load_store().await?;
+        let queries: Vec<&str> = store.groups.iter().map(|g| g.query.as_str()).collect();
+        let (members, evaluated_at) = Self::evaluate(&queries).await?;
+        Ok(store
+            .groups
+            .into_iter()
+            .zip(members)
+            .map(|(group, aliases)| SmartGroupMembers {
+                group,
+                aliases,
+                evaluated_at,
+            })
+            .collect())
+    }
+
+    /// Hosts a query matches right now, without saving it
+    pub async fn preview(query: &str) -> SshResult<Vec<String>> {
+        GroupQuery::parse(query)?;
+        let (mut members, _) = Self::evaluate(&[query]).await?;
+        Ok(members.pop().unwrap_or_default())
+    }
+
+    /// Add or replace a group (matched by id); the query must parse
+    pub async fn save(mut group: SmartGroup) -> SshResult<SmartGroup> {
+        ReadOnlyMode::ensure_writable("save smart group")?;
+        group.name = group.name.trim().to_string();
+        group.query = group.query.trim().to_string();
+        if group.name.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Smart group name is required".to_string(),
+            });
+        }
+        GroupQuery::parse(&group.query)?;
+        if group.id.trim().is_empty() {
+            group.id = format!("{:08x}", rand::random::<u32>());
+        }
+
+        let mut store = Self::load_store().await?;
+        match store.groups.iter_mut().find(|g| g.id == group.id) {
+            Some(existing) => *existing = group.clone(),
+            None => store.groups.push(group.clone()),
+        }
+        Self::save_store(&store).await?;
+        tracing::info!("[smart_groups] Saved smart group {}", group.id);
+        Ok(group)
+    }
+
+    /// Delete a group
+    pub async fn delete(id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete smart group")?;
+        let mut store = Self::load_store().await?;
+        store.groups.retain(|g| g.id != id);
+        Self::save_store(&store).await?;
+        tracing::info!("[smart_groups] Deleted smart group {}", id);
+        Ok(())
+    }
+
+    /// Members of each query, in order, and when the host snapshot was taken
+    /// A query that no longer parses has no members
+    async fn evaluate(queries: &[&str]) -> SshResult<(Vec<Vec<String>>, i64)> {
+        let inputs = input_stamps().await?;
+        let now = now_millis();
+        let fresh = CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|cached| cached.inputs == inputs && now - cached.at < CACHE_TTL_MS);
+        if !fresh {
+            let hosts = load_hosts().await?;
+            tracing::debug!("[smart_groups] Evaluating against {} host(s)", hosts.len());
+            *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Evaluation {
+                inputs,
+                at: now,
+                hosts,
+                members: HashMap::new(),
+            });
+        }
+
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(cached) = cache.as_mut() else {
+            return Ok((Vec::new(), now));
+        };
+        let members = queries
+            .iter()
+            .map(|query| {
+                if let Some(members) = cached.members.get(*query) {
+                    return members.clone();
+                }
+                let members = match GroupQuery::parse(query) {
+                    Ok(parsed) => members_of(&parsed, &cached.hosts, cached.at),
+                    Err(_) => Vec::new(),
+                };
+                cached.members.insert(query.to_string(), members.clone());
+                members
+            })
+            .collect();
+        Ok((members, cached.at))
+    }
+}
+
+async fn stamp(path: PathBuf) -> (PathBuf, FileStamp) {
+    let stamp = fs::metadata(&path).await.ok().map(|metadata| {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as i64);
+        (modified, metadata.len())
+    });
+    (path, stamp)
+}
+
+/// The files members depend on; paths change with the active workspace
+async fn input_stamps() -> SshResult<Vec<(PathBuf, FileStamp)>> {
+    Ok(vec![
+        stamp(workspace_data_path("metadata.json")?).await,
+        stamp(workspace_data_path("history.jsonl")?).await,
+        stamp(ssh_config_path()?).await,
+    ])
+}
+
+async fn load_hosts() -> SshResult<Vec<GroupHost>> {
+    let store = RegistryService::load().await?;
+    let editor = ConfigService::load_editor().await?;
+    let history = HistoryService::load().await?;
+    Ok(group_hosts(&store, &editor, &history))
+}
+
+/// Hosts of the config with their tags and last successful and failed connections;
+/// the registry's last use counts as a connection when history recording is off
+fn group_hosts(
+    store: &MetadataStore,
+    editor: &SshConfigEditor,
+    history: &[SessionRecord],
+) -> Vec<GroupHost> {
+    let mut hosts: Vec<GroupHost> = editor
+        .host_aliases()
+        .into_iter()
+        .map(|alias| {
+            let metadata = store.hosts.get(&alias);
+            GroupHost {
+                hostname: editor.get_option(&alias, "HostName"),
+                user: editor.get_option(&alias, "User"),
+                tags: metadata.map(|m| m.tags.clone()).unwrap_or_default(),
+                favorite: metadata.is_some_and(|m| m.is_favorite),
+                last_connected: metadata.and_then(|m| m.last_used),
+                last_failed: None,
+                alias,
+            }
+        })
+        .collect();
+
+    let index: HashMap<String, usize> = hosts
+        .iter()
+        .enumerate()
+        .map(|(i, host)| (host.alias.clone(), i))
+        .collect();
+    for record in history {
+        let Some(&i) = index.get(&record.host_alias) else {
+            continue;
+        };
+        let last = match record.failure {
+            Some(_) => &mut hosts[i].last_failed,
+            None => &mut hosts[i].last_connected,
+        };
+        *last = Some(last.map_or(record.started_at, |t| t.max(record.started_at)));
+    }
+    hosts
+}
+
+fn members_of(query: &GroupQuery, hosts: &[GroupHost], now: i64) -> Vec<String> {
+    let mut aliases: Vec<String> = hosts
+        .iter()
+        .filter(|host| query.matches(host, now))
+        .map(|host| host.alias.clone())
+        .collect();
+    aliases.sort();
+    aliases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::registry_service::HostMetadata;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+    const NOW: i64 = 100 * DAY;
+
+    fn record(alias: &str, started_at: i64, failure: Option<&str>) -> SessionRecord {
+        SessionRecord {
+            host_alias: alias.to_string(),
+            started_at,
+            ended_at: started_at,
+            bytes_transferred: 0,
+            failure: failure.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_group_members() {
+        let editor = SshConfigEditor::parse(
+            "Host web db legacy\n    User deploy\n\nHost api\n    HostName api.example.com\n",
+        );
+        let mut store = MetadataStore::default();
+        let mut prod = HostMetadata::new();
+        prod.tags = vec!["prod".to_string()];
+        store.hosts.insert("db".to_string(), prod.clone());
+        store.hosts.insert("api".to_string(), prod);
+        let mut used = HostMetadata::new();
+        used.last_used = Some(NOW - 2 * DAY);
+        store.hosts.insert("legacy".to_string(), used);
+        let history = vec![
+            record("web", NOW - DAY, None),
+            record("db", NOW - 20 * DAY, None),
+            record("db", NOW - DAY, Some("ConnectionTimeout")),
+            record("api", NOW - 30 * DAY, Some("PermissionDenied")),
+            record("gone", NOW - DAY, None),
+        ];
+        let hosts = group_hosts(&store, &editor, &history);
+
+        let members = |query: &str| members_of(&GroupQuery::parse(query).unwrap(), &hosts, NOW);
+        assert_eq!(members("connected within 7d"), vec!["legacy", "web"]);
+        assert_eq!(members("tag=prod AND failed recently"), vec!["db"]);
+        assert_eq!(members("NOT connected"), vec!["api"]);
+        assert_eq!(members("hostname~example"), vec!["api"]);
+    }
+
+    #[test]
+    fn test_default_groups_parse() {
+        for group in SmartGroupStore::default().groups {
+            assert!(GroupQuery::parse(&group.query).is_ok(), "{}", group.query);
+        }
+    }
+}
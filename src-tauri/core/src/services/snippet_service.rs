@@ -0,0 +1,120 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::utils::{app_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Saved command, run on a host from the palette or a terminal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Times the snippet was used
+    #[serde(default)]
+    pub use_count: u32,
+    /// Unix timestamp in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<i64>,
+}
+
+/// snippets.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnippetStore {
+    #[serde(default)]
+    snippets: Vec<Snippet>,
+}
+
+/// Stores command snippets in the app data directory
+pub struct SnippetService;
+
+impl SnippetService {
+    fn get_store_path() -> SshResult<PathBuf> {
+        app_data_path("snippets.json")
+    }
+
+    async fn load_store() -> SshResult<SnippetStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(SnippetStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse snippets: {}", e),
+        })
+    }
+
+    async fn save_store(store: &SnippetStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// All snippets
+    pub async fn list() -> SshResult<Vec<Snippet>> {
+        Ok(Self::load_store().await?.snippets)
+    }
+
+    /// Add or replace a snippet (matched by id); usage counters are kept
+    pub async fn save(mut snippet: Snippet) -> SshResult<Snippet> {
+        ReadOnlyMode::ensure_writable("save snippet")?;
+        snippet.name = snippet.name.trim().to_string();
+        if snippet.name.is_empty() || snippet.command.trim().is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Snippet name and command are required".to_string(),
+            });
+        }
+        if snippet.id.trim().is_empty() {
+            snippet.id = format!("{:08x}", rand::random::<u32>());
+        }
+
+        let mut store = Self::load_store().await?;
+        match store.snippets.iter_mut().find(|s| s.id == snippet.id) {
+            Some(existing) => {
+                snippet.use_count = existing.use_count;
+                snippet.last_used = existing.last_used;
+                *existing = snippet.clone();
+            }
+            None => store.snippets.push(snippet.clone()),
+        }
+        Self::save_store(&store).await?;
+        tracing::info!("[snippet_service] Saved snippet {}", snippet.id);
+        Ok(snippet)
+    }
+
+    /// Delete a snippet
+    pub async fn delete(id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete snippet")?;
+        let mut store = Self::load_store().await?;
+        store.snippets.retain(|s| s.id != id);
+        Self::save_store(&store).await?;
+        tracing::info!("[snippet_service] Deleted snippet {}", id);
+        Ok(())
+    }
+
+    /// Count a use of the snippet (ranks it higher in searches)
+    pub async fn record_use(id: &str) -> SshResult<()> {
+        let mut store = Self::load_store().await?;
+        let Some(snippet) = store.snippets.iter_mut().find(|s| s.id == id) else {
+            return Ok(());
+        };
+        snippet.use_count = snippet.use_count.saturating_add(1);
+        snippet.last_used = Some(now_millis());
+        Self::save_store(&store).await
+    }
+}
@@ -0,0 +1,612 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::algorithm_check::AlgorithmCheckService;
+use crate::services::proxy_service::ProxyService;
+use crate::services::session_helper::SessionHelperService;
+use crate::services::settings_service::SettingsService;
+use crate::services::ssh_connection::{
+    ConnectionTestResult, ExecOutput, OutputStream, SshConnectionService, SshErrorDetails,
+    SshErrorType,
+};
+use crate::utils::ssh_client_options;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// Connect timeout of system ssh connection tests, in seconds
+const TEST_CONNECT_TIMEOUT: u32 = 15;
+
+/// Limit of a system ssh connection test as a whole
+const TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// OpenSSH exits with 255 when the connection itself failed
+const SSH_CONNECTION_FAILED: i32 = 255;
+
+/// Connection backend
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SshEngineKind {
+    /// Embedded Rust implementation
+    #[default]
+    Embedded,
+    /// The `ssh` binary on PATH
+    System,
+    /// Embedded unless the host uses options only the system client handles
+    Auto,
+}
+
+/// What an engine can do; operations and client features share one list
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EngineFeature {
+    TestConnection,
+    Exec,
+    Shell,
+    PortForward,
+    Sftp,
+    /// Password and keyboard-interactive prompts in the app
+    InteractiveAuth,
+    ProxyJump,
+    Gssapi,
+    Pkcs11,
+    SecurityKeys,
+}
+
+/// Engine section of the settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EngineSettings {
+    pub engine: SshEngineKind,
+    /// Per-host choice overriding `engine`
+    pub hosts: BTreeMap<String, SshEngineKind>,
+}
+
+impl EngineSettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        if let Some(alias) = self
+            .hosts
+            .keys()
+            .find(|alias| alias.trim().is_empty() || alias.chars().any(char::is_whitespace))
+        {
+            return Err(SshBuddyError::InvalidHostAlias {
+                alias: alias.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Choice for a host before `Auto` is resolved
+    fn for_host(&self, host_alias: &str) -> SshEngineKind {
+        self.hosts.get(host_alias).copied().unwrap_or(self.engine)
+    }
+}
+
+/// Detected support of one engine
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineCapabilities {
+    pub engine: SshEngineKind,
+    pub available: bool,
+    /// `ssh -V` for the system engine
+    pub version: Option<String>,
+    pub features: Vec<EngineFeature>,
+}
+
+impl EngineCapabilities {
+    fn supports(&self, feature: EngineFeature) -> bool {
+        self.available && self.features.contains(&feature)
+    }
+}
+
+/// Engine an operation on a host will use, and why
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineChoice {
+    pub host_alias: String,
+    /// Setting for the host (global or per-host)
+    pub configured: SshEngineKind,
+    /// Embedded or System
+    pub engine: SshEngineKind,
+    /// Host options the embedded engine ignores
+    pub unsupported_options: Vec<String>,
+    pub reason: Option<String>,
+}
+
+/// Detected capabilities, kept until the next refresh
+static CAPABILITIES: tokio::sync::Mutex<Option<Vec<EngineCapabilities>>> =
+    tokio::sync::Mutex::const_new(None);
+
+/// Picks the embedded client or the system OpenSSH client per host and runs
+/// connection tests and commands with it
+pub struct SshEngineService;
+
+impl SshEngineService {
+    pub fn get_settings() -> EngineSettings {
+        SettingsService::get().engine
+    }
+
+    pub async fn set_settings(settings: &EngineSettings) -> SshResult<()> {
+        settings.validate()?;
+        SettingsService::update(|current| {
+            current.engine = settings.clone();
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[ssh_engine] Engine set to {:?} ({} host override(s))",
+            settings.engine,
+            settings.hosts.len()
+        );
+        Ok(())
+    }
+
+    /// Capabilities of both engines; the system client is probed once unless `refresh`
+    pub async fn capabilities(refresh: bool) -> Vec<EngineCapabilities> {
+        let mut cached = CAPABILITIES.lock().await;
+        if refresh || cached.is_none() {
+            *cached = Some(vec![embedded_capabilities(), detect_system().await]);
+        }
+        cached.clone().unwrap_or_default()
+    }
+
+    /// Engine to use for `feature` on a host; falls back to the embedded engine when
+    /// the system client is missing or can't do the operation
+    pub async fn resolve(host_alias: &str, feature: EngineFeature) -> SshResult<EngineChoice> {
+        let configured = Self::get_settings().for_host(host_alias);
+        let host = SshConnectionService::resolve_host(host_alias).await?;
+        let unsupported_options = unsupported_options(&host.options);
+        // Only probe the system client when it may be used
+        let capabilities = match configured {
+            SshEngineKind::Embedded => Vec::new(),
+            _ => Self::capabilities(false).await,
+        };
+        let system = capabilities
+            .iter()
+            .find(|c| c.engine == SshEngineKind::System);
+        let system_supports = system.is_some_and(|c| c.supports(feature));
+
+        let (engine, reason) = match configured {
+            SshEngineKind::Embedded => (SshEngineKind::Embedded, None),
+            SshEngineKind::System if system_supports => (SshEngineKind::System, None),
+            SshEngineKind::System => (
+                SshEngineKind::Embedded,
+                Some(if system.is_some_and(|c| c.available) {
+                    format!("The system ssh client is not used for {:?}", feature)
+                } else {
+                    "No system ssh client found".to_string()
+                }),
+            ),
+            SshEngineKind::Auto if !unsupported_options.is_empty() && system_supports => (
+                SshEngineKind::System,
+                Some(format!(
+                    "The embedded client doesn't support {}",
+                    unsupported_options.join(", ")
+                )),
+            ),
+            SshEngineKind::Auto => (SshEngineKind::Embedded, None),
+        };
+        Ok(EngineChoice {
+            host_alias: host_alias.to_string(),
+            configured,
+            engine,
+            unsupported_options,
+            reason,
+        })
+    }
+
+    /// Non-interactive connection test with the engine chosen for the host
+    pub async fn test_connection(host_alias: &str) -> SshResult<ConnectionTestResult> {
+        let choice = Self::resolve(host_alias, EngineFeature::TestConnection).await?;
+        if choice.engine != SshEngineKind::System {
+            return SessionHelperService::test_connection(host_alias).await;
+        }
+        tracing::info!("[ssh_engine] Testing {} with system ssh", host_alias);
+        system_test(host_alias).await
+    }
+
+    /// Connect, run `command` and disconnect with the engine chosen for the host
+    pub async fn exec<F>(
+        host_alias: &str,
+        command: &str,
+        limit: Duration,
+        on_output: F,
+    ) -> SshResult<ExecOutput>
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let choice = Self::resolve(host_alias, EngineFeature::Exec).await?;
+        if choice.engine != SshEngineKind::System {
+            return SessionHelperService::exec(host_alias, command, limit, on_output).await;
+        }
+        let output = system_exec(host_alias, command, limit, on_output).await?;
+        if output.exit_code == Some(SSH_CONNECTION_FAILED as u32) {
+            let error_type = classify_stderr(&output.stderr);
+            if error_type != SshErrorType::Unknown {
+                return Err(connection_error(error_type, host_alias, &output.stderr));
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn embedded_capabilities() -> EngineCapabilities {
+    EngineCapabilities {
+        engine: SshEngineKind::Embedded,
+        available: true,
+        version: None,
+        features: vec![
+            EngineFeature::TestConnection,
+            EngineFeature::Exec,
+            EngineFeature::Shell,
+            EngineFeature::PortForward,
+            EngineFeature::Sftp,
+            EngineFeature::InteractiveAuth,
+        ],
+    }
+}
+
+/// Probe the system client: `ssh -V` for presence, `ssh -G` for compiled-in features
+async fn detect_system() -> EngineCapabilities {
+    let version = AlgorithmCheckService::client_version()
+        .await
+        .ok()
+        .filter(|v| v.starts_with("OpenSSH"));
+    let mut features = Vec::new();
+    if version.is_some() {
+        features.extend([EngineFeature::TestConnection, EngineFeature::Exec]);
+        match AlgorithmCheckService::effective_config("localhost").await {
+            Ok(config) => features.extend(config_features(&config)),
+            Err(e) => tracing::warn!("[ssh_engine] Failed to read system ssh features: {}", e),
+        }
+    }
+    tracing::info!(
+        "[ssh_engine] System ssh: {}",
+        version.as_deref().unwrap_or("not found")
+    );
+    EngineCapabilities {
+        engine: SshEngineKind::System,
+        available: version.is_some(),
+        version,
+        features,
+    }
+}
+
+/// Features a client has, from the option names its `ssh -G` prints (options of
+/// features that weren't compiled in are left out)
+fn config_features(config: &HashMap<String, String>) -> Vec<EngineFeature> {
+    [
+        ("proxyjump", EngineFeature::ProxyJump),
+        ("gssapiauthentication", EngineFeature::Gssapi),
+        ("pkcs11provider", EngineFeature::Pkcs11),
+        ("securitykeyprovider", EngineFeature::SecurityKeys),
+    ]
+    .into_iter()
+    .filter(|(option, _)| config.contains_key(*option))
+    .map(|(_, feature)| feature)
+    .collect()
+}
+
+/// Host options (as written in the config) the embedded engine ignores
+fn unsupported_options(options: &HashMap<String, String>) -> Vec<String> {
+    let set = |key: &str| {
+        options
+            .get(key)
+            .is_some_and(|v| !v.eq_ignore_ascii_case("none") && !v.eq_ignore_ascii_case("no"))
+    };
+    let mut unsupported = Vec::new();
+    if set("proxyjump") {
+        unsupported.push("ProxyJump".to_string());
+    }
+    if options.get("proxycommand").is_some_and(|c| {
+        !c.eq_ignore_ascii_case("none") && ProxyService::from_proxy_command(c).is_none()
+    }) {
+        unsupported.push("ProxyCommand".to_string());
+    }
+    if set("gssapiauthentication") {
+        unsupported.push("GSSAPIAuthentication".to_string());
+    }
+    if set("pkcs11provider") {
+        unsupported.push("PKCS11Provider".to_string());
+    }
+    if set("securitykeyprovider") {
+        unsupported.push("SecurityKeyProvider".to_string());
+    }
+    unsupported
+}
+
+fn ssh_command(host_alias: &str, options: &[&str], command: &str) -> Command {
+    let mut ssh = Command::new("ssh");
+    ssh.args(ssh_client_options())
+        .args(["-T", "-o", "BatchMode=yes"])
+        .args(options)
+        .arg("--")
+        .arg(host_alias)
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        ssh.creation_flags(CREATE_NO_WINDOW);
+    }
+    ssh
+}
+
+/// Run a command through the system client, streaming its output
+async fn system_exec<F>(
+    host_alias: &str,
+    command: &str,
+    limit: Duration,
+    on_output: F,
+) -> SshResult<ExecOutput>
+where
+    F: FnMut(OutputStream, &str),
+{
+    run_ssh(ssh_command(host_alias, &[], command), limit, on_output).await
+}
+
+async fn run_ssh<F>(mut ssh: Command, limit: Duration, mut on_output: F) -> SshResult<ExecOutput>
+where
+    F: FnMut(OutputStream, &str),
+{
+    let mut child = ssh.spawn().map_err(|e| SshBuddyError::IoError {
+        message: format!("Failed to run the OpenSSH client: {}", e),
+    })?;
+    let (Some(mut stdout), Some(mut stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return Err(SshBuddyError::Unknown {
+            message: "ssh has no output pipes".to_string(),
+        });
+    };
+
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let finished = timeout(limit, async {
+        let (mut out_open, mut err_open) = (true, true);
+        let (mut out_buf, mut err_buf) = ([0u8; 8192], [0u8; 8192]);
+        while out_open || err_open {
+            tokio::select! {
+                read = read_some(&mut stdout, &mut out_buf), if out_open => match read {
+                    Some(n) => {
+                        on_output(OutputStream::Stdout, &String::from_utf8_lossy(&out_buf[..n]));
+                        out.extend_from_slice(&out_buf[..n]);
+                    }
+                    None => out_open = false,
+                },
+                read = read_some(&mut stderr, &mut err_buf), if err_open => match read {
+                    Some(n) => {
+                        on_output(OutputStream::Stderr, &String::from_utf8_lossy(&err_buf[..n]));
+                        err.extend_from_slice(&err_buf[..n]);
+                    }
+                    None => err_open = false,
+                },
+            }
+        }
+        child.wait().await
+    })
+    .await;
+
+    let status = match finished {
+        Ok(status) => status?,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(SshBuddyError::ConnectionTimeout);
+        }
+    };
+    Ok(ExecOutput {
+        exit_code: status.code().and_then(|code| u32::try_from(code).ok()),
+        stdout: String::from_utf8_lossy(&out).to_string(),
+        stderr: String::from_utf8_lossy(&err).to_string(),
+    })
+}
+
+/// Bytes read, None at end of stream or on error
+async fn read_some<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> Option<usize> {
+    match reader.read(buf).await {
+        Ok(0) | Err(_) => None,
+        Ok(n) => Some(n),
+    }
+}
+
+/// Log in with the system client and run nothing
+async fn system_test(host_alias: &str) -> SshResult<ConnectionTestResult> {
+    let connect_timeout = format!("ConnectTimeout={}", TEST_CONNECT_TIMEOUT);
+    let ssh = ssh_command(host_alias, &["-o", &connect_timeout], "exit 0");
+    let output = run_ssh(ssh, TEST_TIMEOUT, |_, _| {}).await?;
+    let stderr = output.stderr.trim().to_string();
+    if output.exit_code == Some(0) {
+        return Ok(ConnectionTestResult {
+            success: true,
+            output: "Connected with the system ssh client".to_string(),
+            debug_log: (!stderr.is_empty()).then_some(stderr),
+            ..Default::default()
+        });
+    }
+
+    let error_type = classify_stderr(&stderr);
+    Ok(ConnectionTestResult {
+        success: false,
+        output: if stderr.is_empty() {
+            format!("ssh exited with {:?}", output.exit_code)
+        } else {
+            stderr.clone()
+        },
+        error_type: Some(error_type.clone()),
+        error_details: Some(SshErrorDetails {
+            suggestion: suggestion(&error_type).to_string(),
+            error_type,
+            raw_message: stderr.clone(),
+            can_auto_fix: false,
+            fix_type: None,
+            fix_params: None,
+        }),
+        debug_log: Some(stderr),
+        ..Default::default()
+    })
+}
+
+/// Error type of an OpenSSH client failure, from its stderr
+fn classify_stderr(stderr: &str) -> SshErrorType {
+    let lower = stderr.to_lowercase();
+    if lower.contains("remote host identification has changed") {
+        SshErrorType::HostKeyChanged
+    } else if lower.contains("host key verification failed") {
+        SshErrorType::HostKeyUnknown
+    } else if lower.contains("could not resolve hostname")
+        || lower.contains("name or service not known")
+    {
+        SshErrorType::DnsFailed
+    } else if lower.contains("connection refused") {
+        SshErrorType::ConnectionRefused
+    } else if lower.contains("timed out") {
+        SshErrorType::Timeout
+    } else if lower.contains("no such identity") {
+        SshErrorType::IdentityFileNotFound
+    } else if lower.contains("permission denied") {
+        SshErrorType::PermissionDenied
+    } else {
+        SshErrorType::Unknown
+    }
+}
+
+fn suggestion(error_type: &SshErrorType) -> &'static str {
+    match error_type {
+        SshErrorType::HostKeyChanged => {
+            "The server's host key changed; verify it before removing the old key from known_hosts"
+        }
+        SshErrorType::HostKeyUnknown => {
+            "The host key is not in known_hosts; connect once with the embedded engine to review it"
+        }
+        SshErrorType::DnsFailed => "Check the HostName of this host",
+        SshErrorType::ConnectionRefused => "Check that the SSH server runs and the Port is right",
+        SshErrorType::Timeout => "Check the network path and firewall to this host",
+        SshErrorType::IdentityFileNotFound => "Check the IdentityFile of this host",
+        SshErrorType::PermissionDenied => {
+            "Authentication failed; the system client can't ask for passwords here"
+        }
+        _ => "See the ssh output for details",
+    }
+}
+
+/// Error of a command whose connection failed
+fn connection_error(error_type: SshErrorType, host_alias: &str, stderr: &str) -> SshBuddyError {
+    let message = stderr.trim().to_string();
+    match error_type {
+        SshErrorType::HostKeyChanged => SshBuddyError::HostKeyChanged {
+            hostname: host_alias.to_string(),
+        },
+        SshErrorType::HostKeyUnknown => SshBuddyError::HostKeyUnknown {
+            hostname: host_alias.to_string(),
+        },
+        SshErrorType::DnsFailed => SshBuddyError::DnsResolutionFailed {
+            hostname: host_alias.to_string(),
+        },
+        SshErrorType::ConnectionRefused => SshBuddyError::ConnectionRefused { message },
+        SshErrorType::Timeout => SshBuddyError::ConnectionTimeout,
+        SshErrorType::PermissionDenied | SshErrorType::IdentityFileNotFound => {
+            SshBuddyError::PermissionDenied { reason: message }
+        }
+        _ => SshBuddyError::Unknown { message },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        assert!(unsupported_options(&options(&[("user", "deploy")])).is_empty());
+        assert_eq!(
+            unsupported_options(&options(&[
+                ("proxyjump", "bastion"),
+                ("gssapiauthentication", "yes"),
+            ])),
+            vec!["ProxyJump", "GSSAPIAuthentication"]
+        );
+        assert!(unsupported_options(&options(&[
+            ("gssapiauthentication", "no"),
+            ("proxyjump", "none"),
+        ]))
+        .is_empty());
+        // ProxyCommands the embedded engine understands itself
+        assert!(
+            unsupported_options(&options(&[("proxycommand", "nc -X 5 -x proxy:1080 %h %p")]))
+                .is_empty()
+        );
+        assert_eq!(
+            unsupported_options(&options(&[("proxycommand", "cloudflared access ssh")])),
+            vec!["ProxyCommand"]
+        );
+    }
+
+    #[test]
+    fn test_config_features() {
+        let features = config_features(&options(&[
+            ("proxyjump", "none"),
+            ("gssapiauthentication", "no"),
+            ("user", "me"),
+        ]));
+        assert_eq!(
+            features,
+            vec![EngineFeature::ProxyJump, EngineFeature::Gssapi]
+        );
+    }
+
+    #[test]
+    fn test_classify_stderr() {
+        assert_eq!(
+            classify_stderr("ssh: Could not resolve hostname nope: Name or service not known"),
+            SshErrorType::DnsFailed
+        );
+        assert_eq!(
+            classify_stderr("deploy@web: Permission denied (publickey)."),
+            SshErrorType::PermissionDenied
+        );
+        assert_eq!(
+            classify_stderr(
+                "@@@ WARNING: REMOTE HOST IDENTIFICATION HAS CHANGED! @@@\nHost key verification failed."
+            ),
+            SshErrorType::HostKeyChanged
+        );
+        assert_eq!(
+            classify_stderr("Host key verification failed."),
+            SshErrorType::HostKeyUnknown
+        );
+        assert_eq!(
+            classify_stderr("ssh: connect to host web port 22: Connection timed out"),
+            SshErrorType::Timeout
+        );
+        assert_eq!(
+            classify_stderr("bash: foo: command not found"),
+            SshErrorType::Unknown
+        );
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut settings = EngineSettings::default();
+        settings
+            .hosts
+            .insert("kerberized".to_string(), SshEngineKind::System);
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.for_host("kerberized"), SshEngineKind::System);
+        assert_eq!(settings.for_host("web"), SshEngineKind::Embedded);
+
+        settings
+            .hosts
+            .insert("bad host".to_string(), SshEngineKind::Auto);
+        assert!(settings.validate().is_err());
+    }
+}
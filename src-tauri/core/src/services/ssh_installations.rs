@@ -0,0 +1,547 @@
+use crate::models::LocalizedMessage;
+use crate::services::doctor_service::{DoctorCheck, DoctorFinding, FindingSeverity};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// OpenSSH tools looked up on PATH
+const TOOLS: &[&str] = &["ssh", "ssh-agent", "ssh-add"];
+
+/// Timeout for `ssh -V`
+const VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Named pipe prefix used by the Windows OpenSSH agent (and compatible agents)
+const WINDOWS_PIPE_PREFIX: &str = r"\\.\pipe\";
+
+/// Distribution an SSH binary comes from, derived from its (resolved) path
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SshFlavor {
+    /// /usr/bin, /bin (the OS package)
+    System,
+    /// C:\Windows\System32\OpenSSH
+    WindowsOpenSsh,
+    /// Bundled with Git for Windows
+    GitForWindows,
+    Msys,
+    Cygwin,
+    Homebrew,
+    MacPorts,
+    Nix,
+    Other,
+}
+
+impl SshFlavor {
+    /// MSYS-based clients talk to agents over Unix sockets, not named pipes
+    fn uses_unix_sockets(self) -> bool {
+        !matches!(self, SshFlavor::WindowsOpenSsh)
+    }
+}
+
+/// OpenSSH tool found on PATH
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshBinary {
+    /// "ssh", "ssh-agent" or "ssh-add"
+    pub tool: String,
+    pub path: String,
+    pub flavor: SshFlavor,
+    /// `ssh -V` of this binary (or of the ssh next to it for the agent tools)
+    pub version: Option<String>,
+}
+
+/// Program git uses for SSH (GIT_SSH_COMMAND, else GIT_SSH)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSshSetting {
+    pub variable: String,
+    pub value: String,
+    /// Program path; None if it can't be found
+    pub resolved: Option<String>,
+    pub flavor: Option<SshFlavor>,
+}
+
+/// SSH clients and agent settings of the app's environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshEnvironment {
+    /// In PATH order; the first of each tool is the one a shell runs
+    pub binaries: Vec<SshBinary>,
+    pub auth_sock: Option<String>,
+    pub auth_sock_exists: bool,
+    pub git_ssh: Option<GitSshSetting>,
+}
+
+impl SshEnvironment {
+    fn first(&self, tool: &str) -> Option<&SshBinary> {
+        self.binaries.iter().find(|b| b.tool == tool)
+    }
+}
+
+/// Finds every ssh/ssh-agent/ssh-add on PATH and spots mismatched combinations
+pub struct SshInstallationsService;
+
+impl SshInstallationsService {
+    /// Enumerate the OpenSSH tools on PATH and the agent/git settings
+    pub async fn inspect() -> SshEnvironment {
+        let mut binaries = find_binaries();
+        for binary in binaries.iter_mut().filter(|b| b.tool == "ssh") {
+            binary.version = ssh_version(PathBuf::from(&binary.path)).await;
+        }
+        // ssh-agent and ssh-add have no version flag; use the ssh of the same install
+        let versions: Vec<(PathBuf, Option<String>)> = binaries
+            .iter()
+            .filter(|b| b.tool == "ssh")
+            .map(|b| (parent(&b.path), b.version.clone()))
+            .collect();
+        for binary in binaries.iter_mut().filter(|b| b.tool != "ssh") {
+            let dir = parent(&binary.path);
+            binary.version = versions
+                .iter()
+                .find(|(d, _)| *d == dir)
+                .and_then(|(_, v)| v.clone());
+        }
+
+        let auth_sock = std::env::var("SSH_AUTH_SOCK")
+            .ok()
+            .filter(|s| !s.is_empty());
+        let auth_sock_exists = auth_sock
+            .as_deref()
+            .is_some_and(|s| s.starts_with(WINDOWS_PIPE_PREFIX) || Path::new(s).exists());
+        let git_ssh = ["GIT_SSH_COMMAND", "GIT_SSH"].iter().find_map(|variable| {
+            let value = std::env::var(variable)
+                .ok()
+                .filter(|v| !v.trim().is_empty())?;
+            let program = git_ssh_program(variable, &value);
+            let resolved = resolve_program(&program);
+            Some(GitSshSetting {
+                variable: variable.to_string(),
+                value,
+                flavor: resolved.as_deref().map(classify),
+                resolved: resolved.map(|p| p.to_string_lossy().into_owned()),
+            })
+        });
+
+        let env = SshEnvironment {
+            binaries,
+            auth_sock,
+            auth_sock_exists,
+            git_ssh,
+        };
+        tracing::info!(
+            "[ssh_installations] Found {} OpenSSH tool(s) on PATH",
+            env.binaries.len()
+        );
+        env
+    }
+
+    /// Doctor findings for the inspected environment
+    pub async fn check() -> Vec<DoctorFinding> {
+        findings(&Self::inspect().await, cfg!(windows))
+    }
+}
+
+fn parent(path: &str) -> PathBuf {
+    Path::new(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// All OpenSSH tools on PATH, in PATH order, without duplicates reached through symlinks
+fn find_binaries() -> Vec<SshBinary> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut seen: Vec<PathBuf> = Vec::new();
+    let mut binaries = Vec::new();
+    for dir in std::env::split_paths(&path) {
+        for tool in TOOLS {
+            let candidate = dir.join(executable_name(tool));
+            if !candidate.is_file() {
+                continue;
+            }
+            let real = std::fs::canonicalize(&candidate).unwrap_or_else(|_| candidate.clone());
+            if seen.contains(&real) {
+                continue;
+            }
+            seen.push(real.clone());
+            binaries.push(SshBinary {
+                tool: tool.to_string(),
+                path: candidate.to_string_lossy().into_owned(),
+                flavor: classify(&real),
+                version: None,
+            });
+        }
+    }
+    binaries
+}
+
+fn executable_name(tool: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", tool)
+    } else {
+        tool.to_string()
+    }
+}
+
+/// Distribution of a binary by its resolved path
+fn classify(path: &Path) -> SshFlavor {
+    let path = path.to_string_lossy().replace('\\', "/").to_lowercase();
+    let path = path.trim_start_matches("//?/");
+    if path.contains("/windows/system32/openssh/") {
+        SshFlavor::WindowsOpenSsh
+    } else if path.contains("/git/usr/bin/")
+        || path.contains("/git/bin/")
+        || path.contains("/git/mingw64/")
+    {
+        SshFlavor::GitForWindows
+    } else if path.contains("/msys64/") || path.contains("/msys2/") {
+        SshFlavor::Msys
+    } else if path.contains("/cygwin") {
+        SshFlavor::Cygwin
+    } else if path.starts_with("/opt/homebrew/")
+        || path.starts_with("/usr/local/cellar/")
+        || path.starts_with("/home/linuxbrew/")
+    {
+        SshFlavor::Homebrew
+    } else if path.starts_with("/opt/local/") {
+        SshFlavor::MacPorts
+    } else if path.starts_with("/nix/") || path.contains("/.nix-profile/") {
+        SshFlavor::Nix
+    } else if ["/usr/bin/", "/bin/", "/usr/sbin/", "/sbin/"]
+        .iter()
+        .any(|dir| path.starts_with(dir))
+    {
+        SshFlavor::System
+    } else {
+        SshFlavor::Other
+    }
+}
+
+/// `ssh -V` of a binary (printed to stderr)
+async fn ssh_version(path: PathBuf) -> Option<String> {
+    let output = timeout(
+        VERSION_TIMEOUT,
+        tokio::task::spawn_blocking(move || {
+            Command::new(path)
+                .arg("-V")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .output()
+        }),
+    )
+    .await
+    .ok()?
+    .ok()?
+    .ok()?;
+    let version = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .next()?
+        .trim()
+        .to_string();
+    (!version.is_empty()).then_some(version)
+}
+
+/// Program part of GIT_SSH_COMMAND (a shell command) or GIT_SSH (a path)
+fn git_ssh_program(variable: &str, value: &str) -> String {
+    let value = value.trim();
+    if variable == "GIT_SSH" {
+        return value.to_string();
+    }
+    match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => value[1..]
+            .split(quote)
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        _ => value
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// A program path, or a bare name looked up on PATH
+pub(crate) fn resolve_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let names = if cfg!(windows) && path.extension().is_none() {
+        vec![format!("{}.exe", program)]
+    } else {
+        vec![program.to_string()]
+    };
+    let dirs = std::env::var_os("PATH")?;
+    std::env::split_paths(&dirs)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+        .map(|found| std::fs::canonicalize(&found).unwrap_or(found))
+}
+
+fn flavor_name(flavor: SshFlavor) -> &'static str {
+    match flavor {
+        SshFlavor::System => "system OpenSSH",
+        SshFlavor::WindowsOpenSsh => "Windows OpenSSH",
+        SshFlavor::GitForWindows => "Git for Windows",
+        SshFlavor::Msys => "MSYS2",
+        SshFlavor::Cygwin => "Cygwin",
+        SshFlavor::Homebrew => "Homebrew",
+        SshFlavor::MacPorts => "MacPorts",
+        SshFlavor::Nix => "Nix",
+        SshFlavor::Other => "other",
+    }
+}
+
+fn finding(id: &str, severity: FindingSeverity, message: LocalizedMessage) -> DoctorFinding {
+    DoctorFinding {
+        id: format!("sshInstallations.{}", id),
+        check: DoctorCheck::SshInstallations,
+        severity,
+        subject: None,
+        message,
+    }
+}
+
+/// Mismatches between the clients on PATH, the agent socket and git's ssh
+fn findings(env: &SshEnvironment, windows: bool) -> Vec<DoctorFinding> {
+    let mut findings = Vec::new();
+    let Some(ssh) = env.first("ssh") else {
+        findings.push(finding(
+            "noClient",
+            FindingSeverity::Error,
+            LocalizedMessage::new("doctor.noSshClient"),
+        ));
+        return findings;
+    };
+
+    let clients: Vec<&SshBinary> = env.binaries.iter().filter(|b| b.tool == "ssh").collect();
+    let mut versions: Vec<Option<&str>> = clients.iter().map(|b| b.version.as_deref()).collect();
+    versions.sort_unstable();
+    versions.dedup();
+    if clients.len() > 1 {
+        let severity = if versions.len() > 1 {
+            FindingSeverity::Warning
+        } else {
+            FindingSeverity::Info
+        };
+        findings.push(finding(
+            "multipleClients",
+            severity,
+            LocalizedMessage::new("doctor.multipleClients")
+                .with("count", clients.len())
+                .with("path", &ssh.path),
+        ));
+    }
+
+    if let Some(ssh_add) = env.first("ssh-add").filter(|add| add.flavor != ssh.flavor) {
+        findings.push(finding(
+            "agentToolMismatch",
+            FindingSeverity::Warning,
+            LocalizedMessage::new("doctor.agentToolMismatch")
+                .with("ssh", flavor_name(ssh.flavor))
+                .with("sshAdd", flavor_name(ssh_add.flavor)),
+        ));
+    }
+
+    if let Some(sock) = &env.auth_sock {
+        let pipe = sock.starts_with(WINDOWS_PIPE_PREFIX);
+        if windows && pipe == ssh.flavor.uses_unix_sockets() {
+            findings.push(finding(
+                "authSockMismatch",
+                FindingSeverity::Warning,
+                LocalizedMessage::new(if pipe {
+                    "doctor.authSockPipe"
+                } else {
+                    "doctor.authSockSocket"
+                })
+                .with("client", flavor_name(ssh.flavor)),
+            ));
+        } else if !env.auth_sock_exists {
+            findings.push(finding(
+                "authSockMissing",
+                FindingSeverity::Warning,
+                LocalizedMessage::new("doctor.authSockMissing").with("path", sock),
+            ));
+        }
+    }
+
+    if let Some(git) = &env.git_ssh {
+        match git.flavor {
+            None => findings.push(finding(
+                "gitSshMissing",
+                FindingSeverity::Error,
+                LocalizedMessage::new("doctor.gitSshMissing")
+                    .with("variable", &git.variable)
+                    .with("value", &git.value),
+            )),
+            Some(flavor) if flavor != ssh.flavor && flavor != SshFlavor::Other => {
+                findings.push(finding(
+                    "gitSshMismatch",
+                    FindingSeverity::Warning,
+                    LocalizedMessage::new("doctor.gitSshMismatch")
+                        .with("variable", &git.variable)
+                        .with("git", flavor_name(flavor))
+                        .with("ssh", flavor_name(ssh.flavor)),
+                ))
+            }
+            _ => {}
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(tool: &str, path: &str, version: &str) -> SshBinary {
+        SshBinary {
+            tool: tool.to_string(),
+            path: path.to_string(),
+            flavor: classify(Path::new(path)),
+            version: Some(version.to_string()),
+        }
+    }
+
+    fn ids(findings: &[DoctorFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(Path::new(r"C:\Windows\System32\OpenSSH\ssh.exe")),
+            SshFlavor::WindowsOpenSsh
+        );
+        assert_eq!(
+            classify(Path::new(r"C:\Program Files\Git\usr\bin\ssh.exe")),
+            SshFlavor::GitForWindows
+        );
+        assert_eq!(
+            classify(Path::new(r"C:\msys64\usr\bin\ssh.exe")),
+            SshFlavor::Msys
+        );
+        assert_eq!(
+            classify(Path::new("/opt/homebrew/Cellar/openssh/9.9p1/bin/ssh")),
+            SshFlavor::Homebrew
+        );
+        assert_eq!(classify(Path::new("/usr/bin/ssh")), SshFlavor::System);
+        assert_eq!(
+            classify(Path::new("/home/me/.nix-profile/bin/ssh")),
+            SshFlavor::Nix
+        );
+        assert_eq!(classify(Path::new("/opt/tools/ssh")), SshFlavor::Other);
+    }
+
+    #[test]
+    fn test_git_ssh_program() {
+        assert_eq!(
+            git_ssh_program(
+                "GIT_SSH_COMMAND",
+                "ssh -i ~/.ssh/work -o IdentitiesOnly=yes"
+            ),
+            "ssh"
+        );
+        assert_eq!(
+            git_ssh_program(
+                "GIT_SSH_COMMAND",
+                r#""C:\Windows\System32\OpenSSH\ssh.exe" -v"#
+            ),
+            r"C:\Windows\System32\OpenSSH\ssh.exe"
+        );
+        assert_eq!(
+            git_ssh_program("GIT_SSH", r"C:\Program Files\PuTTY\plink.exe"),
+            r"C:\Program Files\PuTTY\plink.exe"
+        );
+    }
+
+    #[test]
+    fn test_findings_consistent_install() {
+        let env = SshEnvironment {
+            binaries: vec![
+                binary("ssh", "/usr/bin/ssh", "OpenSSH_9.6p1"),
+                binary("ssh-agent", "/usr/bin/ssh-agent", "OpenSSH_9.6p1"),
+                binary("ssh-add", "/usr/bin/ssh-add", "OpenSSH_9.6p1"),
+            ],
+            auth_sock: Some("/tmp/ssh-x/agent.1".to_string()),
+            auth_sock_exists: true,
+            git_ssh: None,
+        };
+        assert!(findings(&env, false).is_empty());
+        assert_eq!(
+            ids(&findings(&SshEnvironment::default(), false)),
+            vec!["sshInstallations.noClient"]
+        );
+    }
+
+    #[test]
+    fn test_findings_mixed_windows_install() {
+        let env = SshEnvironment {
+            binaries: vec![
+                binary(
+                    "ssh",
+                    r"C:\Windows\System32\OpenSSH\ssh.exe",
+                    "OpenSSH_for_Windows_9.5p1",
+                ),
+                binary(
+                    "ssh",
+                    r"C:\Program Files\Git\usr\bin\ssh.exe",
+                    "OpenSSH_9.8p1",
+                ),
+                binary(
+                    "ssh-add",
+                    r"C:\Program Files\Git\usr\bin\ssh-add.exe",
+                    "OpenSSH_9.8p1",
+                ),
+            ],
+            auth_sock: Some("/tmp/ssh-XXXX/agent.42".to_string()),
+            auth_sock_exists: false,
+            git_ssh: Some(GitSshSetting {
+                variable: "GIT_SSH".to_string(),
+                value: r"C:\Program Files\Git\usr\bin\ssh.exe".to_string(),
+                resolved: Some(r"C:\Program Files\Git\usr\bin\ssh.exe".to_string()),
+                flavor: Some(SshFlavor::GitForWindows),
+            }),
+        };
+        let found = findings(&env, true);
+        assert_eq!(
+            ids(&found),
+            vec![
+                "sshInstallations.multipleClients",
+                "sshInstallations.agentToolMismatch",
+                "sshInstallations.authSockMismatch",
+                "sshInstallations.gitSshMismatch",
+            ]
+        );
+        assert_eq!(found[0].severity, FindingSeverity::Warning);
+        assert_eq!(found[2].message.key, "doctor.authSockSocket");
+    }
+
+    #[test]
+    fn test_findings_missing_git_ssh() {
+        let env = SshEnvironment {
+            binaries: vec![binary("ssh", "/usr/bin/ssh", "OpenSSH_9.6p1")],
+            auth_sock: Some("/tmp/gone".to_string()),
+            auth_sock_exists: false,
+            git_ssh: Some(GitSshSetting {
+                variable: "GIT_SSH_COMMAND".to_string(),
+                value: "/opt/missing/ssh -v".to_string(),
+                resolved: None,
+                flavor: None,
+            }),
+        };
+        assert_eq!(
+            ids(&findings(&env, false)),
+            vec![
+                "sshInstallations.authSockMissing",
+                "sshInstallations.gitSshMissing",
+            ]
+        );
+    }
+}
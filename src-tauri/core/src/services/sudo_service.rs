@@ -0,0 +1,279 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::{
+    AuthPromptField, AuthPromptKind, AuthPromptRequest, AuthPrompter,
+};
+use crate::services::script_service::shell_quote;
+use crate::services::ssh_connection::{
+    ExecOutput, OutputStream, RemoteSession, SshConnectionService,
+};
+use crate::services::vault_service::VaultService;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Vault entries named "sudo:<host alias>" hold saved sudo passwords
+const VAULT_ENTRY_PREFIX: &str = "sudo:";
+
+/// Password attempts before giving up (sudo's own default is 3)
+const MAX_PASSWORD_ATTEMPTS: usize = 3;
+
+/// Timeout for the NOPASSWD probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What sudo allows on a host
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SudoAccess {
+    /// NOPASSWD rule: sudo works without a password
+    NoPassword,
+    PasswordRequired,
+    /// User is not in the sudoers file
+    NotAllowed,
+    /// sudo isn't installed
+    Unavailable,
+}
+
+/// Finds sudo prompts in stderr output and strips them
+/// A marker split across chunks is held back until the next chunk
+struct PromptDetector {
+    marker: String,
+    pending: String,
+}
+
+impl PromptDetector {
+    fn new(marker: &str) -> Self {
+        Self {
+            marker: marker.to_string(),
+            pending: String::new(),
+        }
+    }
+
+    /// Returns the chunk without prompts and the number of prompts seen
+    fn feed(&mut self, chunk: &str) -> (String, usize) {
+        let mut text = std::mem::take(&mut self.pending) + chunk;
+        let prompts = text.matches(self.marker.as_str()).count();
+        if prompts > 0 {
+            text = text.replace(self.marker.as_str(), "");
+        }
+        if let Some(keep) = (1..self.marker.len())
+            .rev()
+            .find(|&n| text.ends_with(&self.marker[..n]))
+        {
+            self.pending = text.split_off(text.len() - keep);
+        }
+        (text, prompts)
+    }
+
+    /// Text held back at the end of the output
+    fn finish(&mut self) -> String {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Runs remote commands under sudo, answering its password prompts
+pub struct SudoService;
+
+impl SudoService {
+    /// Vault entry holding the sudo password of a host
+    pub fn vault_entry_name(host_alias: &str) -> String {
+        format!("{}{}", VAULT_ENTRY_PREFIX, host_alias)
+    }
+
+    /// Fresh prompt marker, so prompts can't be confused with command output
+    pub fn new_marker() -> String {
+        format!("ssh-buddy-sudo-{:016x}:", rand::random::<u64>())
+    }
+
+    /// Prefix a (shell-quoted) command with sudo reading its password from stdin
+    pub fn wrap_command(command: &str, marker: &str) -> String {
+        format!("sudo -S -p {} -- {}", shell_quote(marker), command)
+    }
+
+    /// Password for one prompt: the vault first, then the prompter
+    /// The password is never logged
+    async fn obtain_password(
+        host_alias: &str,
+        attempt: usize,
+        prompter: Option<&dyn AuthPrompter>,
+    ) -> Option<String> {
+        if attempt == 1 {
+            let name = Self::vault_entry_name(host_alias);
+            if let Ok(password) = VaultService::global().get_entry(&name).await {
+                tracing::info!(
+                    "[sudo_service] Using saved sudo password for {}",
+                    host_alias
+                );
+                return Some(password);
+            }
+        }
+
+        let mut request = AuthPromptRequest::new(host_alias, AuthPromptKind::Sudo);
+        if attempt > 1 {
+            request.instructions = "Sorry, try again.".to_string();
+        }
+        request.prompts.push(AuthPromptField {
+            prompt: format!("[sudo] password on {}:", host_alias),
+            echo: false,
+        });
+        prompter?.prompt(request).await?.into_iter().next()
+    }
+
+    /// Run a command built with `wrap_command`, answering sudo prompts as they appear
+    /// Nothing is asked when sudo doesn't need a password (NOPASSWD or cached credentials)
+    pub async fn exec<F>(
+        session: &RemoteSession,
+        command: &str,
+        marker: &str,
+        prompter: Option<&dyn AuthPrompter>,
+        limit: Duration,
+        mut on_output: F,
+    ) -> SshResult<ExecOutput>
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let host_alias = session.host_alias().to_string();
+        let mut exec = session.start(command).await?;
+        let mut detector = PromptDetector::new(marker);
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let mut attempts = 0;
+
+        let finished = timeout(limit, async {
+            while let Some((stream, data)) = exec.next_output().await {
+                let text = String::from_utf8_lossy(&data).to_string();
+                let (text, prompts) = match stream {
+                    OutputStream::Stdout => (text, 0),
+                    OutputStream::Stderr => detector.feed(&text),
+                };
+                if !text.is_empty() {
+                    on_output(stream, &text);
+                    match stream {
+                        OutputStream::Stdout => stdout.push_str(&text),
+                        OutputStream::Stderr => stderr.push_str(&text),
+                    }
+                }
+
+                for _ in 0..prompts {
+                    attempts += 1;
+                    let password = if attempts <= MAX_PASSWORD_ATTEMPTS {
+                        Self::obtain_password(&host_alias, attempts, prompter).await
+                    } else {
+                        None
+                    };
+                    match password {
+                        Some(password) => exec.write(format!("{}\n", password).as_bytes()).await?,
+                        // sudo gives up once its stdin is closed
+                        None => exec.close_stdin().await?,
+                    }
+                }
+            }
+            Ok::<_, SshBuddyError>(())
+        })
+        .await;
+
+        match finished {
+            Ok(result) => result?,
+            Err(_) => {
+                exec.abort().await;
+                return Err(SshBuddyError::ConnectionTimeout);
+            }
+        }
+        stderr.push_str(&detector.finish());
+
+        tracing::info!(
+            "[sudo_service] sudo command on {} exited with {:?} ({} password prompt(s))",
+            host_alias,
+            exec.exit_code(),
+            attempts
+        );
+        Ok(ExecOutput {
+            exit_code: exec.exit_code(),
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Check whether sudo works on a host without a password
+    pub async fn check_access(host_alias: &str) -> SshResult<SudoAccess> {
+        let session = SshConnectionService::open_session(host_alias, None).await?;
+        let probe = session
+            .exec("sudo -n true", None, PROBE_TIMEOUT, |_, _| {})
+            .await;
+        session.close().await;
+
+        let probe = probe?;
+        let access = classify_probe(probe.exit_code, &probe.stderr);
+        tracing::info!("[sudo_service] sudo on {}: {:?}", host_alias, access);
+        Ok(access)
+    }
+}
+
+/// Interpret the result of `sudo -n true`
+fn classify_probe(exit_code: Option<u32>, stderr: &str) -> SudoAccess {
+    let stderr = stderr.to_lowercase();
+    match exit_code {
+        Some(0) => SudoAccess::NoPassword,
+        Some(127) => SudoAccess::Unavailable,
+        _ if stderr.contains("command not found") || stderr.contains("sudo: not found") => {
+            SudoAccess::Unavailable
+        }
+        _ if stderr.contains("not in the sudoers") || stderr.contains("not allowed") => {
+            SudoAccess::NotAllowed
+        }
+        _ => SudoAccess::PasswordRequired,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detector_strips_prompts() {
+        let mut detector = PromptDetector::new("MARK:");
+        assert_eq!(detector.feed("MARK:"), (String::new(), 1));
+        assert_eq!(
+            detector.feed("Sorry, try again.\nMARK:"),
+            ("Sorry, try again.\n".to_string(), 1)
+        );
+        assert_eq!(
+            detector.feed("plain error\n"),
+            ("plain error\n".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn test_detector_split_marker() {
+        let mut detector = PromptDetector::new("MARK:");
+        assert_eq!(detector.feed("warning MA"), ("warning ".to_string(), 0));
+        assert_eq!(detector.feed("RK:"), (String::new(), 1));
+        assert_eq!(detector.feed("end M"), ("end ".to_string(), 0));
+        assert_eq!(detector.finish(), "M");
+    }
+
+    #[test]
+    fn test_wrap_command() {
+        assert_eq!(
+            SudoService::wrap_command("/tmp/s 'a b'", "mark:"),
+            "sudo -S -p mark: -- /tmp/s 'a b'"
+        );
+        assert!(SudoService::new_marker().starts_with("ssh-buddy-sudo-"));
+    }
+
+    #[test]
+    fn test_classify_probe() {
+        assert_eq!(classify_probe(Some(0), ""), SudoAccess::NoPassword);
+        assert_eq!(
+            classify_probe(Some(1), "sudo: a password is required\n"),
+            SudoAccess::PasswordRequired
+        );
+        assert_eq!(
+            classify_probe(Some(1), "alice is not in the sudoers file.\n"),
+            SudoAccess::NotAllowed
+        );
+        assert_eq!(
+            classify_probe(Some(127), "bash: sudo: command not found\n"),
+            SudoAccess::Unavailable
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use crate::models::{LocalizedMessage, SshBuddyError, SshResult};
+use crate::services::doctor_service::{DoctorCheck, DoctorFinding, FindingSeverity};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::revision_service::{ManagedFile, RevisionService};
+use crate::utils::{app_data_dir, ssh_config_path, ssh_dir, write_atomic};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::fs;
+
+/// How often the SSH directory is checked in the background
+const WATCH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Extensions of files Windows (or a shell) would run
+const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "bat", "cmd", "com", "ps1", "vbs", "sh", "scr"];
+
+/// Files sshd reads from ~/.ssh at every login to this machine
+const RC_FILES: &[&str] = &["rc", "environment"];
+
+/// Hidden files created by file managers
+const IGNORED_HIDDEN_FILES: &[&str] = &[".DS_Store"];
+
+/// Why a file in the SSH directory looks suspicious
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum SuspicionReason {
+    /// Executable bit or an executable extension
+    Executable,
+    /// ~/.ssh/rc or ~/.ssh/environment
+    RcFile,
+    HiddenFile,
+    /// The SSH config differs from what SSH Buddy last wrote
+    ExternalConfigChange,
+}
+
+impl SuspicionReason {
+    /// Doctor problem name, also the catalog key `doctor.<problem>`
+    fn problem(self) -> &'static str {
+        match self {
+            SuspicionReason::Executable => "executableInSshDir",
+            SuspicionReason::RcFile => "sshRcFile",
+            SuspicionReason::HiddenFile => "hiddenFileInSshDir",
+            SuspicionReason::ExternalConfigChange => "externalConfigChange",
+        }
+    }
+
+    fn severity(self) -> FindingSeverity {
+        match self {
+            SuspicionReason::Executable | SuspicionReason::RcFile => FindingSeverity::Error,
+            SuspicionReason::HiddenFile => FindingSeverity::Warning,
+            SuspicionReason::ExternalConfigChange => FindingSeverity::Info,
+        }
+    }
+}
+
+/// Unexpected file in the SSH directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspiciousFile {
+    pub path: String,
+    pub name: String,
+    pub reason: SuspicionReason,
+    pub severity: FindingSeverity,
+    /// Unix timestamp in milliseconds
+    pub modified_at: Option<i64>,
+    pub size: u64,
+    /// The config is never moved away (restore a revision instead)
+    pub can_quarantine: bool,
+    #[serde(flatten)]
+    pub message: LocalizedMessage,
+}
+
+/// File moved to the quarantine folder
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedFile {
+    pub id: String,
+    pub original_path: String,
+    /// Where the file is kept for review
+    pub quarantined_path: String,
+    pub reason: SuspicionReason,
+    /// Unix timestamp in milliseconds
+    pub quarantined_at: i64,
+    /// SHA-256 of the content, hex
+    pub sha256: String,
+}
+
+/// quarantine/index.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuarantineIndex {
+    #[serde(default)]
+    files: Vec<QuarantinedFile>,
+}
+
+/// Looks for files in the SSH directory that may have been planted, and keeps
+/// quarantined files in a review folder
+pub struct TamperDetectionService;
+
+impl TamperDetectionService {
+    fn quarantine_dir() -> SshResult<PathBuf> {
+        Ok(app_data_dir()?.join("quarantine"))
+    }
+
+    /// Check the top level of the SSH directory and the config
+    pub async fn scan() -> SshResult<Vec<SuspiciousFile>> {
+        let dir = ssh_dir()?;
+        let mut found = Vec::new();
+        if dir.is_dir() {
+            let mut entries = fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let Ok(metadata) = fs::symlink_metadata(entry.path()).await else {
+                    continue;
+                };
+                // Directories (config.d, control sockets) and sockets are left alone
+                if !metadata.is_file() {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(reason) = classify(&name, is_executable(&metadata)) {
+                    found.push(suspicious(&entry.path(), &name, reason, &metadata, true));
+                }
+            }
+        }
+        if let Some(file) = Self::external_config_change().await {
+            found.push(file);
+        }
+        found.sort_by(|a, b| a.severity.cmp(&b.severity).then(a.name.cmp(&b.name)));
+        Ok(found)
+    }
+
+    /// The config if its content isn't the last revision SSH Buddy wrote
+    async fn external_config_change() -> Option<SuspiciousFile> {
+        let latest = RevisionService::list_revisions(ManagedFile::SshConfig)
+            .await
+            .ok()?
+            .into_iter()
+            .next()?;
+        let stored = RevisionService::read_revision(ManagedFile::SshConfig, &latest.id)
+            .await
+            .ok()?;
+        let path = ssh_config_path().ok()?;
+        let current = fs::read_to_string(&path).await.ok()?;
+        if current == stored {
+            return None;
+        }
+        let metadata = fs::metadata(&path).await.ok()?;
+        let name = path.file_name()?.to_string_lossy().to_string();
+        Some(suspicious(
+            &path,
+            &name,
+            SuspicionReason::ExternalConfigChange,
+            &metadata,
+            false,
+        ))
+    }
+
+    /// Suspicious files as doctor findings
+    pub async fn check() -> Vec<DoctorFinding> {
+        match Self::scan().await {
+            Ok(files) => files
+                .into_iter()
+                .map(|file| DoctorFinding {
+                    id: format!("sshDirectory.{}", file.reason.problem()),
+                    check: DoctorCheck::SshDirectory,
+                    severity: file.severity,
+                    subject: Some(file.name),
+                    message: file.message,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!("[tamper_detection] Scan failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn load_index() -> SshResult<QuarantineIndex> {
+        let path = Self::quarantine_dir()?.join("index.json");
+        match fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to parse the quarantine index: {}", e),
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(QuarantineIndex::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save_index(index: &QuarantineIndex) -> SshResult<()> {
+        let dir = Self::quarantine_dir()?;
+        fs::create_dir_all(&dir).await?;
+        let content = serde_json::to_string_pretty(index).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&dir.join("index.json"), content.as_bytes()).await
+    }
+
+    /// Files in quarantine, most recent first
+    pub async fn list_quarantine() -> SshResult<Vec<QuarantinedFile>> {
+        let mut files = Self::load_index().await?.files;
+        files.sort_by(|a, b| b.quarantined_at.cmp(&a.quarantined_at));
+        Ok(files)
+    }
+
+    /// Move a file that is still suspicious out of the SSH directory for review
+    pub async fn quarantine(name: &str) -> SshResult<QuarantinedFile> {
+        ReadOnlyMode::ensure_writable("quarantine a file")?;
+        let file = Self::scan()
+            .await?
+            .into_iter()
+            .find(|f| f.name == name && f.can_quarantine)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("{} is not a suspicious file in the SSH directory", name),
+            })?;
+
+        let source = PathBuf::from(&file.path);
+        let content = fs::read(&source).await?;
+        let quarantined_at = now_millis();
+        let id = quarantined_at.to_string();
+        let target_dir = Self::quarantine_dir()?.join(&id);
+        fs::create_dir_all(&target_dir).await?;
+        let target = target_dir.join(&file.name);
+        move_file(&source, &target).await?;
+
+        let entry = QuarantinedFile {
+            id,
+            original_path: file.path.clone(),
+            quarantined_path: target.to_string_lossy().to_string(),
+            reason: file.reason,
+            quarantined_at,
+            sha256: format!("{:x}", Sha256::digest(&content)),
+        };
+        let mut index = Self::load_index().await?;
+        index.files.push(entry.clone());
+        Self::save_index(&index).await?;
+        tracing::warn!(
+            "[tamper_detection] Quarantined {} ({:?})",
+            file.path,
+            file.reason
+        );
+        Ok(entry)
+    }
+
+    /// Put a quarantined file back where it was (fails if something took its place)
+    pub async fn restore(id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("restore a quarantined file")?;
+        let mut index = Self::load_index().await?;
+        let position = index.files.iter().position(|f| f.id == id).ok_or_else(|| {
+            SshBuddyError::InvalidOption {
+                message: format!("Unknown quarantined file: {}", id),
+            }
+        })?;
+        let entry = &index.files[position];
+        let original = PathBuf::from(&entry.original_path);
+        if original.exists() {
+            return Err(SshBuddyError::InvalidPath {
+                message: format!("{} already exists", entry.original_path),
+            });
+        }
+        move_file(Path::new(&entry.quarantined_path), &original).await?;
+        tracing::info!("[tamper_detection] Restored {}", entry.original_path);
+        let _ = fs::remove_dir(Self::quarantine_dir()?.join(id)).await;
+        index.files.remove(position);
+        Self::save_index(&index).await
+    }
+
+    /// Delete a quarantined file for good
+    pub async fn delete(id: &str) -> SshResult<()> {
+        let mut index = Self::load_index().await?;
+        let before = index.files.len();
+        index.files.retain(|f| f.id != id);
+        if index.files.len() == before {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Unknown quarantined file: {}", id),
+            });
+        }
+        // Ids are timestamps; anything else could escape the quarantine folder
+        if id.parse::<i64>().is_ok() {
+            let dir = Self::quarantine_dir()?.join(id);
+            if dir.exists() {
+                fs::remove_dir_all(&dir).await?;
+            }
+        }
+        tracing::info!("[tamper_detection] Deleted quarantined file {}", id);
+        Self::save_index(&index).await
+    }
+
+    /// Scan periodically and report files that weren't suspicious before
+    /// (spawned once at app setup)
+    pub async fn run_watch_loop<F>(on_found: F)
+    where
+        F: Fn(Vec<SuspiciousFile>) + Send + Sync + 'static,
+    {
+        let mut known: HashSet<(String, SuspicionReason)> = HashSet::new();
+        let mut first = true;
+        loop {
+            if let Ok(files) = Self::scan().await {
+                let current: HashSet<(String, SuspicionReason)> =
+                    files.iter().map(|f| (f.path.clone(), f.reason)).collect();
+                let new: Vec<SuspiciousFile> = files
+                    .into_iter()
+                    .filter(|f| !known.contains(&(f.path.clone(), f.reason)))
+                    .collect();
+                // Findings present at startup are the doctor's business, not an alert
+                if !first && !new.is_empty() {
+                    on_found(new);
+                }
+                known = current;
+                first = false;
+            }
+            tokio::time::sleep(WATCH_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Why a file in the SSH directory is suspicious, if it is
+fn classify(name: &str, executable: bool) -> Option<SuspicionReason> {
+    if RC_FILES.contains(&name) {
+        return Some(SuspicionReason::RcFile);
+    }
+    let extension = Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase());
+    if executable
+        || extension
+            .as_deref()
+            .is_some_and(|e| EXECUTABLE_EXTENSIONS.contains(&e))
+    {
+        return Some(SuspicionReason::Executable);
+    }
+    if name.starts_with('.') && !IGNORED_HIDDEN_FILES.contains(&name) {
+        return Some(SuspicionReason::HiddenFile);
+    }
+    None
+}
+
+fn suspicious(
+    path: &Path,
+    name: &str,
+    reason: SuspicionReason,
+    metadata: &std::fs::Metadata,
+    can_quarantine: bool,
+) -> SuspiciousFile {
+    SuspiciousFile {
+        path: path.to_string_lossy().to_string(),
+        name: name.to_string(),
+        reason,
+        severity: reason.severity(),
+        modified_at: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64),
+        size: metadata.len(),
+        can_quarantine,
+        message: LocalizedMessage::new(&format!("doctor.{}", reason.problem())).with("name", name),
+    }
+}
+
+/// Rename, or copy and remove when the quarantine is on another file system
+async fn move_file(from: &Path, to: &Path) -> SshResult<()> {
+    if fs::rename(from, to).await.is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).await?;
+    fs::remove_file(from).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("rc", false), Some(SuspicionReason::RcFile));
+        assert_eq!(
+            classify("environment", false),
+            Some(SuspicionReason::RcFile)
+        );
+        assert_eq!(
+            classify("id_ed25519", true),
+            Some(SuspicionReason::Executable)
+        );
+        assert_eq!(
+            classify("update.PS1", false),
+            Some(SuspicionReason::Executable)
+        );
+        assert_eq!(classify(".hook", false), Some(SuspicionReason::HiddenFile));
+        assert_eq!(classify(".DS_Store", false), None);
+        assert_eq!(classify("id_ed25519", false), None);
+        assert_eq!(classify("id_ed25519.pub", false), None);
+        assert_eq!(classify("known_hosts", false), None);
+    }
+
+    #[test]
+    fn test_severity_order() {
+        assert!(SuspicionReason::RcFile.severity() < SuspicionReason::HiddenFile.severity());
+        assert!(
+            SuspicionReason::HiddenFile.severity()
+                < SuspicionReason::ExternalConfigChange.severity()
+        );
+    }
+}
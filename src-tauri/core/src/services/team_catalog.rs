@@ -0,0 +1,979 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::{now_millis, HostMetadata, RegistryService};
+use crate::utils::{workspace_data_dir, workspace_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use ssh_key::{PublicKey, SshSig};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::fs;
+use tokio::process::Command;
+
+/// Namespace catalogs are signed with (`ssh-keygen -Y sign -n ssh-buddy-catalog`)
+pub const CATALOG_NAMESPACE: &str = "ssh-buddy-catalog";
+
+/// Catalogs larger than this are refused
+const MAX_CATALOG_BYTES: usize = 4 * 1024 * 1024;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often the refresh loop looks for catalogs that are due
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+const DEFAULT_REFRESH_MINUTES: u32 = 60;
+const MIN_REFRESH_MINUTES: u32 = 5;
+
+/// Options a catalog may not set: they run local commands or change how the
+/// config is read
+const BLOCKED_OPTIONS: &[&str] = &[
+    "host",
+    "match",
+    "include",
+    "proxycommand",
+    "localcommand",
+    "permitlocalcommand",
+    "knownhostscommand",
+];
+
+/// Where a catalog is published; the signature sits next to it (`<file>.sig`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CatalogLocation {
+    /// HTTPS URL of a JSON or YAML file
+    Url { url: String },
+    /// File in a git repository
+    Git {
+        url: String,
+        #[serde(default)]
+        branch: Option<String>,
+        path: String,
+    },
+}
+
+/// A subscribed team catalog
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogSubscription {
+    pub id: String,
+    pub name: String,
+    pub location: CatalogLocation,
+    /// OpenSSH public key the catalog must be signed with
+    pub signer: String,
+    pub refresh_minutes: u32,
+    /// Unix timestamp in milliseconds
+    #[serde(default)]
+    pub last_refresh: Option<i64>,
+    /// Revision stated by the catalog
+    #[serde(default)]
+    pub revision: Option<String>,
+    #[serde(default)]
+    pub host_count: usize,
+    /// Error of the last refresh; the hosts of the refresh before stay
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Team keys from the last successful refresh
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<CatalogKey>,
+}
+
+impl CatalogSubscription {
+    fn is_due(&self, now: i64) -> bool {
+        self.last_refresh.map_or(true, |last| {
+            now - last >= i64::from(self.refresh_minutes) * 60_000
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogSubscribeRequest {
+    pub name: String,
+    pub location: CatalogLocation,
+    pub signer: String,
+    #[serde(default)]
+    pub refresh_minutes: Option<u32>,
+}
+
+/// Provenance of a host merged from a catalog
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogSource {
+    /// Subscription id
+    pub catalog: String,
+    /// Catalog name shown as the host's label
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// Digest of the host's options as last written, to skip unchanged hosts
+    #[serde(default)]
+    pub digest: String,
+}
+
+/// Published catalog document (JSON or YAML)
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Catalog {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Any string or number identifying the catalog version
+    #[serde(default)]
+    pub revision: Option<Value>,
+    #[serde(default)]
+    pub hosts: Vec<CatalogHost>,
+    /// Public keys of the team, used to audit authorized_keys
+    #[serde(default)]
+    pub keys: Vec<CatalogKey>,
+}
+
+/// Public key of a team member published in a catalog
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogKey {
+    /// Person or system the key belongs to
+    pub owner: String,
+    pub public_key: String,
+    /// Key of a former member (or otherwise retired) that must no longer have access
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogHost {
+    pub alias: String,
+    pub host_name: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Further ssh_config options (e.g. ProxyJump)
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl CatalogHost {
+    /// Host block options; blocked options are left out
+    fn config_options(&self) -> (Vec<(String, String)>, Vec<String>) {
+        let mut options = vec![("HostName".to_string(), self.host_name.clone())];
+        if let Some(user) = &self.user {
+            options.push(("User".to_string(), user.clone()));
+        }
+        if let Some(port) = self.port {
+            options.push(("Port".to_string(), port.to_string()));
+        }
+        let mut dropped = Vec::new();
+        for (key, value) in &self.options {
+            let lower = key.to_ascii_lowercase();
+            if BLOCKED_OPTIONS.contains(&lower.as_str()) {
+                dropped.push(format!("{}: {}", self.alias, key));
+            } else if !["hostname", "user", "port"].contains(&lower.as_str()) {
+                options.push((key.clone(), value.clone()));
+            }
+        }
+        (options, dropped)
+    }
+}
+
+/// Digest of a host block's options
+fn options_digest(options: &[(String, String)]) -> String {
+    let mut hasher = Sha256::new();
+    for (key, value) in options {
+        hasher.update(key.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Outcome of a catalog refresh
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogRefreshResult {
+    pub catalog: String,
+    pub revision: Option<String>,
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    /// Catalog hosts skipped because a local host or another catalog has the alias
+    pub conflicts: Vec<String>,
+    /// "alias: option" pairs left out of the config
+    pub dropped_options: Vec<String>,
+}
+
+impl CatalogRefreshResult {
+    pub fn changed(&self) -> bool {
+        !(self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty())
+    }
+}
+
+/// catalogs.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CatalogStore {
+    #[serde(default)]
+    subscriptions: Vec<CatalogSubscription>,
+}
+
+/// What a refresh does to the config
+#[derive(Debug, Default, PartialEq)]
+struct MergePlan {
+    upsert: Vec<String>,
+    remove: Vec<String>,
+    conflicts: Vec<String>,
+}
+
+/// Catalog hosts go in when the alias is free or already theirs; hosts the
+/// catalog no longer lists are removed
+fn plan_merge(
+    catalog_id: &str,
+    hosts: &[CatalogHost],
+    existing: &HashSet<String>,
+    owners: &HashMap<String, String>,
+) -> MergePlan {
+    let mut plan = MergePlan::default();
+    for host in hosts {
+        match owners.get(&host.alias) {
+            Some(owner) if owner == catalog_id => plan.upsert.push(host.alias.clone()),
+            None if !existing.contains(&host.alias) => plan.upsert.push(host.alias.clone()),
+            _ => plan.conflicts.push(host.alias.clone()),
+        }
+    }
+    let listed: HashSet<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+    let mut remove: Vec<String> = owners
+        .iter()
+        .filter(|(alias, owner)| *owner == catalog_id && !listed.contains(alias.as_str()))
+        .map(|(alias, _)| alias.clone())
+        .collect();
+    remove.sort();
+    plan.remove = remove;
+    plan
+}
+
+/// Parse a catalog (JSON, or YAML when it isn't JSON) and check its hosts
+pub fn parse_catalog(content: &str) -> SshResult<Catalog> {
+    let catalog: Catalog = match serde_json::from_str(content) {
+        Ok(catalog) => catalog,
+        Err(_) => serde_yaml::from_str(content).map_err(|e| SshBuddyError::InvalidOption {
+            message: format!("Invalid catalog: {}", e),
+        })?,
+    };
+    let mut seen = HashSet::new();
+    for host in &catalog.hosts {
+        ConfigService::validate_alias(&host.alias)?;
+        if !seen.insert(host.alias.as_str()) {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Duplicate host in catalog: {}", host.alias),
+            });
+        }
+        let values = [Some(&host.host_name), host.user.as_ref()]
+            .into_iter()
+            .flatten()
+            .chain(host.options.values());
+        let keys_ok = host
+            .options
+            .keys()
+            .all(|k| !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric()));
+        let mut values_ok = true;
+        for value in values {
+            values_ok &= !value.trim().is_empty() && !value.chars().any(char::is_control);
+        }
+        if !keys_ok || !values_ok {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Invalid options for catalog host {}", host.alias),
+            });
+        }
+    }
+    for key in &catalog.keys {
+        if key.owner.trim().is_empty() || PublicKey::from_openssh(key.public_key.trim()).is_err() {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Invalid catalog key of {:?}", key.owner),
+            });
+        }
+    }
+    Ok(catalog)
+}
+
+/// Check an armored SSH signature (`ssh-keygen -Y sign`) of the catalog
+pub fn verify_catalog(content: &[u8], signature: &str, signer: &str) -> SshResult<()> {
+    let signer = PublicKey::from_openssh(signer.trim())?;
+    let signature = SshSig::from_pem(signature.trim())?;
+    signer
+        .verify(CATALOG_NAMESPACE, content, &signature)
+        .map_err(|_| SshBuddyError::PermissionDenied {
+            reason: "The catalog is not signed by the trusted key".to_string(),
+        })
+}
+
+/// Relative path inside the repository, without `..`
+fn validate_repo_path(path: &str) -> SshResult<()> {
+    let ok = !path.trim().is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if !ok {
+        return Err(SshBuddyError::InvalidPath {
+            message: format!("Invalid catalog path: {}", path),
+        });
+    }
+    Ok(())
+}
+
+fn validate_location(location: &CatalogLocation) -> SshResult<()> {
+    let invalid = |url: &str| SshBuddyError::InvalidOption {
+        message: format!("Invalid catalog URL: {}", url),
+    };
+    match location {
+        CatalogLocation::Url { url } => {
+            if !url.starts_with("https://") || url.chars().any(char::is_whitespace) {
+                return Err(invalid(url));
+            }
+        }
+        CatalogLocation::Git { url, branch, path } => {
+            // A leading dash would be read as a git option
+            let bad = |value: &str| {
+                value.is_empty()
+                    || value.starts_with('-')
+                    || value.chars().any(|c| c.is_whitespace() || c.is_control())
+            };
+            if bad(url) || branch.as_deref().is_some_and(bad) {
+                return Err(invalid(url));
+            }
+            validate_repo_path(path)?;
+        }
+    }
+    Ok(())
+}
+
+pub struct TeamCatalogService;
+
+impl TeamCatalogService {
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("catalogs.json")
+    }
+
+    /// Refreshes (and the merges they do) run one at a time
+    fn refresh_lock() -> &'static tokio::sync::Mutex<()> {
+        static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+    }
+
+    async fn load_store() -> SshResult<CatalogStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(CatalogStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse catalogs: {}", e),
+        })
+    }
+
+    async fn save_store(store: &CatalogStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    pub async fn list() -> SshResult<Vec<CatalogSubscription>> {
+        Ok(Self::load_store().await?.subscriptions)
+    }
+
+    /// Aliases managed by a catalog (read-only for the user)
+    pub async fn locked_aliases() -> SshResult<HashSet<String>> {
+        Ok(RegistryService::load()
+            .await?
+            .hosts
+            .into_iter()
+            .filter(|(_, m)| m.catalog_source.is_some())
+            .map(|(alias, _)| alias)
+            .collect())
+    }
+
+    /// Refuse changes to a host that comes from a catalog
+    pub async fn ensure_editable(alias: &str) -> SshResult<()> {
+        let store = RegistryService::load().await?;
+        match store
+            .hosts
+            .get(alias)
+            .and_then(|m| m.catalog_source.as_ref())
+        {
+            Some(source) => Err(SshBuddyError::PermissionDenied {
+                reason: format!("{} is managed by the catalog {}", alias, source.label),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Subscribe to a catalog; it is fetched and verified before it is saved
+    pub async fn subscribe(request: CatalogSubscribeRequest) -> SshResult<CatalogRefreshResult> {
+        ReadOnlyMode::ensure_writable("subscribe to a catalog")?;
+        validate_location(&request.location)?;
+        PublicKey::from_openssh(request.signer.trim())?;
+        if request.name.trim().is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Catalog name cannot be empty".to_string(),
+            });
+        }
+        let subscription = CatalogSubscription {
+            id: format!("catalog-{}", now_millis()),
+            name: request.name.trim().to_string(),
+            location: request.location,
+            signer: request.signer.trim().to_string(),
+            refresh_minutes: request
+                .refresh_minutes
+                .unwrap_or(DEFAULT_REFRESH_MINUTES)
+                .max(MIN_REFRESH_MINUTES),
+            last_refresh: None,
+            revision: None,
+            host_count: 0,
+            last_error: None,
+            keys: Vec::new(),
+        };
+
+        let _guard = Self::refresh_lock().lock().await;
+        let catalog = Self::fetch(&subscription).await?;
+        let mut store = Self::load_store().await?;
+        store.subscriptions.push(subscription.clone());
+        Self::save_store(&store).await?;
+        let result = Self::merge(&subscription, &catalog).await?;
+        Self::record(&subscription.id, Ok((&result, &catalog))).await?;
+        tracing::info!(
+            "[team_catalog] Subscribed to {} ({} host(s))",
+            subscription.name,
+            catalog.hosts.len()
+        );
+        Ok(result)
+    }
+
+    /// Unsubscribe and remove the catalog's hosts
+    pub async fn unsubscribe(id: &str) -> SshResult<CatalogRefreshResult> {
+        ReadOnlyMode::ensure_writable("unsubscribe from a catalog")?;
+        let _guard = Self::refresh_lock().lock().await;
+        let mut store = Self::load_store().await?;
+        let subscription = Self::find(&store, id)?.clone();
+        let empty = Catalog {
+            name: None,
+            revision: None,
+            hosts: Vec::new(),
+            keys: Vec::new(),
+        };
+        let result = Self::merge(&subscription, &empty).await?;
+        store.subscriptions.retain(|s| s.id != id);
+        Self::save_store(&store).await?;
+        if let Ok(dir) = Self::repo_dir(id) {
+            if dir.exists() {
+                let _ = fs::remove_dir_all(&dir).await;
+            }
+        }
+        tracing::info!("[team_catalog] Unsubscribed from {}", subscription.name);
+        Ok(result)
+    }
+
+    /// Fetch, verify and merge one catalog now
+    pub async fn refresh(id: &str) -> SshResult<CatalogRefreshResult> {
+        ReadOnlyMode::ensure_writable("refresh a catalog")?;
+        let _guard = Self::refresh_lock().lock().await;
+        let subscription = Self::find(&Self::load_store().await?, id)?.clone();
+        let refreshed = async {
+            let catalog = Self::fetch(&subscription).await?;
+            let result = Self::merge(&subscription, &catalog).await?;
+            Ok((result, catalog))
+        }
+        .await;
+        match refreshed {
+            Ok((result, catalog)) => {
+                Self::record(id, Ok((&result, &catalog))).await?;
+                Ok(result)
+            }
+            Err(e) => {
+                Self::record(id, Err(&e)).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Refresh the catalogs that are due; failures are recorded on the subscription
+    pub async fn refresh_due() -> Vec<CatalogRefreshResult> {
+        let Ok(store) = Self::load_store().await else {
+            return Vec::new();
+        };
+        let now = now_millis();
+        let mut results = Vec::new();
+        for subscription in store.subscriptions.iter().filter(|s| s.is_due(now)) {
+            match Self::refresh(&subscription.id).await {
+                Ok(result) => results.push(result),
+                Err(e) => tracing::warn!(
+                    "[team_catalog] Refreshing {} failed: {}",
+                    subscription.name,
+                    e
+                ),
+            }
+        }
+        results
+    }
+
+    /// Periodically refresh the subscribed catalogs (spawned once at app setup)
+    pub async fn run_refresh_loop<F>(on_refreshed: F)
+    where
+        F: Fn(Vec<CatalogRefreshResult>) + Send + Sync + 'static,
+    {
+        loop {
+            tokio::time::sleep(REFRESH_CHECK_INTERVAL).await;
+            if ReadOnlyMode::status().enabled {
+                continue;
+            }
+            let changed: Vec<CatalogRefreshResult> = Self::refresh_due()
+                .await
+                .into_iter()
+                .filter(CatalogRefreshResult::changed)
+                .collect();
+            if !changed.is_empty() {
+                on_refreshed(changed);
+            }
+        }
+    }
+
+    fn find<'a>(store: &'a CatalogStore, id: &str) -> SshResult<&'a CatalogSubscription> {
+        store
+            .subscriptions
+            .iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Unknown catalog: {}", id),
+            })
+    }
+
+    /// Save the outcome of a refresh on the subscription
+    async fn record(
+        id: &str,
+        outcome: Result<(&CatalogRefreshResult, &Catalog), &SshBuddyError>,
+    ) -> SshResult<()> {
+        let mut store = Self::load_store().await?;
+        if let Some(subscription) = store.subscriptions.iter_mut().find(|s| s.id == id) {
+            subscription.last_refresh = Some(now_millis());
+            match outcome {
+                Ok((result, catalog)) => {
+                    subscription.revision = result.revision.clone();
+                    subscription.host_count = catalog.hosts.len();
+                    subscription.keys = catalog.keys.clone();
+                    subscription.last_error = None;
+                }
+                Err(e) => subscription.last_error = Some(e.to_string()),
+            }
+        }
+        Self::save_store(&store).await
+    }
+
+    /// Download the catalog and its signature, verify and parse it
+    async fn fetch(subscription: &CatalogSubscription) -> SshResult<Catalog> {
+        let (content, signature) = match &subscription.location {
+            CatalogLocation::Url { url } => {
+                let content = Self::download(url).await?;
+                let signature = Self::download(&format!("{}.sig", url)).await?;
+                (content, signature)
+            }
+            CatalogLocation::Git { url, branch, path } => {
+                let dir = Self::sync_repo(&subscription.id, url, branch.as_deref()).await?;
+                let file = dir.join(path);
+                let content = fs::read(&file).await?;
+                let signature = fs::read(dir.join(format!("{}.sig", path))).await?;
+                (content, signature)
+            }
+        };
+        if content.len() > MAX_CATALOG_BYTES {
+            return Err(SshBuddyError::InvalidOption {
+                message: "The catalog is too large".to_string(),
+            });
+        }
+        verify_catalog(
+            &content,
+            &String::from_utf8_lossy(&signature),
+            &subscription.signer,
+        )?;
+        let text = String::from_utf8(content).map_err(|_| SshBuddyError::InvalidOption {
+            message: "The catalog is not UTF-8".to_string(),
+        })?;
+        parse_catalog(&text)
+    }
+
+    async fn download(url: &str) -> SshResult<Vec<u8>> {
+        let network_error = |e: String| SshBuddyError::ConnectionRefused { message: e };
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| network_error(e.to_string()))?;
+        let response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| network_error(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(network_error(format!(
+                "{} answered {}",
+                url,
+                response.status()
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| network_error(e.to_string()))?;
+        Ok(body.to_vec())
+    }
+
+    fn repo_dir(id: &str) -> SshResult<PathBuf> {
+        Ok(workspace_data_dir()?.join("catalogs").join(id))
+    }
+
+    /// Shallow clone of the catalog repository, updated to the latest commit
+    async fn sync_repo(id: &str, url: &str, branch: Option<&str>) -> SshResult<PathBuf> {
+        let dir = Self::repo_dir(id)?;
+        if dir.join(".git").exists() {
+            let mut fetch = vec!["-C", path_str(&dir)?, "fetch", "--quiet", "--depth", "1"];
+            fetch.extend(["origin", branch.unwrap_or("HEAD")]);
+            Self::git(&fetch).await?;
+            Self::git(&[
+                "-C",
+                path_str(&dir)?,
+                "reset",
+                "--hard",
+                "--quiet",
+                "FETCH_HEAD",
+            ])
+            .await?;
+        } else {
+            if let Some(parent) = dir.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let mut clone = vec!["clone", "--quiet", "--depth", "1"];
+            if let Some(branch) = branch {
+                clone.extend(["--branch", branch]);
+            }
+            clone.extend(["--", url, path_str(&dir)?]);
+            Self::git(&clone).await?;
+        }
+        Ok(dir)
+    }
+
+    async fn git(args: &[&str]) -> SshResult<()> {
+        let output = tokio::time::timeout(
+            GIT_TIMEOUT,
+            Command::new("git")
+                .args(args)
+                .env("GIT_TERMINAL_PROMPT", "0")
+                .stdin(Stdio::null())
+                .output(),
+        )
+        .await
+        .map_err(|_| SshBuddyError::ConnectionTimeout)?
+        .map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to run git: {}", e),
+        })?;
+        if !output.status.success() {
+            return Err(SshBuddyError::IoError {
+                message: format!(
+                    "git failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Write the catalog's hosts to the config and the registry
+    async fn merge(
+        subscription: &CatalogSubscription,
+        catalog: &Catalog,
+    ) -> SshResult<CatalogRefreshResult> {
+        let revision = catalog.revision.as_ref().map(|r| match r {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        });
+        let store = RegistryService::load().await?;
+        let owners: HashMap<String, String> = store
+            .hosts
+            .iter()
+            .filter_map(|(alias, m)| {
+                Some((alias.clone(), m.catalog_source.as_ref()?.catalog.clone()))
+            })
+            .collect();
+        let digests: HashMap<&str, &str> = store
+            .hosts
+            .iter()
+            .filter_map(|(alias, m)| {
+                Some((alias.as_str(), m.catalog_source.as_ref()?.digest.as_str()))
+            })
+            .collect();
+
+        // The config isn't rewritten when nothing changed, so a refresh doesn't
+        // reorder an unchanged config
+        let (_, result, _) = ConfigService::update_with_registry(
+            |editor| {
+                let existing: HashSet<String> = editor.host_aliases().into_iter().collect();
+                let plan = plan_merge(&subscription.id, &catalog.hosts, &existing, &owners);
+
+                let mut result = CatalogRefreshResult {
+                    catalog: subscription.id.clone(),
+                    revision: revision.clone(),
+                    conflicts: plan.conflicts.clone(),
+                    ..Default::default()
+                };
+                let mut written: HashMap<String, String> = HashMap::new();
+                for alias in &plan.upsert {
+                    let Some(host) = catalog.hosts.iter().find(|h| &h.alias == alias) else {
+                        continue;
+                    };
+                    let (options, dropped) = host.config_options();
+                    result.dropped_options.extend(dropped);
+                    let digest = options_digest(&options);
+                    let existed = editor.has_host(alias);
+                    if existed && digests.get(alias.as_str()) == Some(&digest.as_str()) {
+                        written.insert(alias.clone(), digest);
+                        continue;
+                    }
+                    editor.remove_host(alias);
+                    editor.append_host(alias, &options);
+                    if existed {
+                        result.updated.push(alias.clone());
+                    } else {
+                        result.added.push(alias.clone());
+                    }
+                    written.insert(alias.clone(), digest);
+                }
+                for alias in &plan.remove {
+                    if editor.remove_host(alias) {
+                        result.removed.push(alias.clone());
+                    }
+                }
+                Ok((plan, result, written))
+            },
+            |store, (plan, _, written)| {
+                for alias in &plan.upsert {
+                    let Some(host) = catalog.hosts.iter().find(|h| &h.alias == alias) else {
+                        continue;
+                    };
+                    let metadata = store
+                        .hosts
+                        .entry(alias.clone())
+                        .or_insert_with(HostMetadata::new);
+                    metadata.tags = host.tags.clone();
+                    metadata.notes = host.notes.clone();
+                    metadata.catalog_source = Some(CatalogSource {
+                        catalog: subscription.id.clone(),
+                        label: catalog
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| subscription.name.clone()),
+                        revision: revision.clone(),
+                        digest: written.get(alias).cloned().unwrap_or_default(),
+                    });
+                }
+                for alias in &plan.remove {
+                    store.hosts.remove(alias);
+                }
+                Ok(())
+            },
+        )
+        .await?;
+
+        tracing::info!(
+            "[team_catalog] {}: {} added, {} updated, {} removed, {} conflict(s)",
+            subscription.name,
+            result.added.len(),
+            result.updated.len(),
+            result.removed.len(),
+            result.conflicts.len()
+        );
+        Ok(result)
+    }
+}
+
+fn path_str(path: &Path) -> SshResult<&str> {
+    path.to_str().ok_or_else(|| SshBuddyError::InvalidPath {
+        message: path.to_string_lossy().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use ssh_key::{Algorithm, HashAlg, LineEnding, PrivateKey};
+
+    const YAML: &str = "name: Platform team\nrevision: 42\nhosts:\n  - alias: bastion\n    hostName: bastion.example.com\n    user: ops\n    port: 2222\n    options:\n      ProxyCommand: nc %h %p\n      ForwardAgent: \"no\"\n    tags: [prod]\n  - alias: db\n    hostName: 10.0.0.5\n    options:\n      ProxyJump: bastion\n";
+
+    fn host(alias: &str) -> CatalogHost {
+        CatalogHost {
+            alias: alias.to_string(),
+            host_name: format!("{}.example.com", alias),
+            user: None,
+            port: None,
+            options: BTreeMap::new(),
+            tags: Vec::new(),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_yaml_and_json() {
+        let catalog = parse_catalog(YAML).unwrap();
+        assert_eq!(catalog.name.as_deref(), Some("Platform team"));
+        assert_eq!(catalog.revision, Some(Value::from(42)));
+        assert_eq!(catalog.hosts.len(), 2);
+        assert_eq!(catalog.hosts[0].port, Some(2222));
+
+        let json = r#"{"hosts": [{"alias": "web", "hostName": "web.example.com"}]}"#;
+        assert_eq!(parse_catalog(json).unwrap().hosts[0].alias, "web");
+
+        let duplicate =
+            r#"{"hosts": [{"alias": "a", "hostName": "x"}, {"alias": "a", "hostName": "y"}]}"#;
+        assert!(parse_catalog(duplicate).is_err());
+        let injected = r#"{"hosts": [{"alias": "a", "hostName": "x\nProxyCommand evil"}]}"#;
+        assert!(parse_catalog(injected).is_err());
+        let bad_alias = r#"{"hosts": [{"alias": "a b", "hostName": "x"}]}"#;
+        assert!(parse_catalog(bad_alias).is_err());
+    }
+
+    #[test]
+    fn test_parse_catalog_keys() {
+        let json = r#"{"keys": [
+            {"owner": "alice", "publicKey": "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl alice"},
+            {"owner": "bob", "publicKey": "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBG5Qh1pHZPhCFH5bz6hXNXwMLC4uI2PyzeRHQjVSlpc", "revoked": true}
+        ]}"#;
+        let catalog = parse_catalog(json).unwrap();
+        assert_eq!(catalog.keys.len(), 2);
+        assert!(!catalog.keys[0].revoked);
+        assert!(catalog.keys[1].revoked);
+
+        let bad_key = r#"{"keys": [{"owner": "alice", "publicKey": "ssh-ed25519 garbage"}]}"#;
+        assert!(parse_catalog(bad_key).is_err());
+    }
+
+    #[test]
+    fn test_config_options_drop_blocked() {
+        let catalog = parse_catalog(YAML).unwrap();
+        let (options, dropped) = catalog.hosts[0].config_options();
+        assert_eq!(
+            options,
+            vec![
+                ("HostName".to_string(), "bastion.example.com".to_string()),
+                ("User".to_string(), "ops".to_string()),
+                ("Port".to_string(), "2222".to_string()),
+                ("ForwardAgent".to_string(), "no".to_string()),
+            ]
+        );
+        assert_eq!(dropped, vec!["bastion: ProxyCommand".to_string()]);
+    }
+
+    #[test]
+    fn test_verify_catalog() {
+        let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).unwrap();
+        let signer = key.public_key().to_openssh().unwrap();
+        let signature = key
+            .sign(CATALOG_NAMESPACE, HashAlg::Sha512, YAML.as_bytes())
+            .unwrap()
+            .to_pem(LineEnding::LF)
+            .unwrap();
+        assert!(verify_catalog(YAML.as_bytes(), &signature, &signer).is_ok());
+
+        let tampered = YAML.replace("10.0.0.5", "10.6.6.6");
+        assert!(verify_catalog(tampered.as_bytes(), &signature, &signer).is_err());
+
+        let other = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).unwrap();
+        let other_signer = other.public_key().to_openssh().unwrap();
+        assert!(verify_catalog(YAML.as_bytes(), &signature, &other_signer).is_err());
+
+        let wrong_namespace = key
+            .sign("file", HashAlg::Sha512, YAML.as_bytes())
+            .unwrap()
+            .to_pem(LineEnding::LF)
+            .unwrap();
+        assert!(verify_catalog(YAML.as_bytes(), &wrong_namespace, &signer).is_err());
+    }
+
+    #[test]
+    fn test_plan_merge() {
+        let hosts = vec![host("bastion"), host("db"), host("mine"), host("theirs")];
+        let existing: HashSet<String> = ["db", "mine", "theirs", "old"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let owners: HashMap<String, String> =
+            [("db", "team"), ("theirs", "other"), ("old", "team")]
+                .iter()
+                .map(|(a, o)| (a.to_string(), o.to_string()))
+                .collect();
+
+        let plan = plan_merge("team", &hosts, &existing, &owners);
+        assert_eq!(plan.upsert, vec!["bastion".to_string(), "db".to_string()]);
+        assert_eq!(
+            plan.conflicts,
+            vec!["mine".to_string(), "theirs".to_string()]
+        );
+        assert_eq!(plan.remove, vec!["old".to_string()]);
+
+        let unsubscribed = plan_merge("team", &[], &existing, &owners);
+        assert_eq!(
+            unsubscribed.remove,
+            vec!["db".to_string(), "old".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_validate_location() {
+        let url = |url: &str| CatalogLocation::Url {
+            url: url.to_string(),
+        };
+        assert!(validate_location(&url("https://example.com/hosts.yaml")).is_ok());
+        assert!(validate_location(&url("http://example.com/hosts.yaml")).is_err());
+
+        let git = |url: &str, path: &str| CatalogLocation::Git {
+            url: url.to_string(),
+            branch: None,
+            path: path.to_string(),
+        };
+        assert!(validate_location(&git("git@example.com:team/hosts.git", "hosts.yaml")).is_ok());
+        assert!(validate_location(&git("--upload-pack=evil", "hosts.yaml")).is_err());
+        assert!(validate_location(&git("git@example.com:team/hosts.git", "../hosts")).is_err());
+        assert!(validate_location(&git("git@example.com:team/hosts.git", "/etc/hosts")).is_err());
+    }
+
+    #[test]
+    fn test_is_due() {
+        let mut subscription = CatalogSubscription {
+            id: "team".to_string(),
+            name: "Team".to_string(),
+            location: CatalogLocation::Url {
+                url: "https://example.com/hosts.json".to_string(),
+            },
+            signer: String::new(),
+            refresh_minutes: 60,
+            last_refresh: None,
+            revision: None,
+            host_count: 0,
+            last_error: None,
+            keys: Vec::new(),
+        };
+        assert!(subscription.is_due(0));
+        subscription.last_refresh = Some(1_000);
+        assert!(!subscription.is_due(1_000 + 59 * 60_000));
+        assert!(subscription.is_due(1_000 + 60 * 60_000));
+    }
+}
@@ -0,0 +1,616 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::registry_service::{HostMetadata, RegistryService};
+use crate::services::script_service::shell_quote;
+use crate::services::settings_service::SettingsService;
+use crate::services::shell_access::{ShellAccessService, ShellRoute};
+use crate::utils::ssh_client_options;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Terminal emulator a host can be opened in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExternalTerminal {
+    /// The platform default (Terminal.app, a new console window, or the xdg terminal)
+    #[default]
+    System,
+    WindowsTerminal,
+    /// macOS Terminal.app
+    MacTerminal,
+    Iterm2,
+    Kitty,
+    GnomeTerminal,
+}
+
+impl ExternalTerminal {
+    const ALL: [ExternalTerminal; 6] = [
+        ExternalTerminal::System,
+        ExternalTerminal::WindowsTerminal,
+        ExternalTerminal::MacTerminal,
+        ExternalTerminal::Iterm2,
+        ExternalTerminal::Kitty,
+        ExternalTerminal::GnomeTerminal,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ExternalTerminal::System => "System default",
+            ExternalTerminal::WindowsTerminal => "Windows Terminal",
+            ExternalTerminal::MacTerminal => "Terminal",
+            ExternalTerminal::Iterm2 => "iTerm2",
+            ExternalTerminal::Kitty => "kitty",
+            ExternalTerminal::GnomeTerminal => "GNOME Terminal",
+        }
+    }
+
+    /// Whether the terminal exists on this platform at all
+    pub(crate) fn supported(self) -> bool {
+        match self {
+            ExternalTerminal::System => true,
+            ExternalTerminal::WindowsTerminal => cfg!(windows),
+            ExternalTerminal::MacTerminal | ExternalTerminal::Iterm2 => cfg!(target_os = "macos"),
+            ExternalTerminal::Kitty => cfg!(unix),
+            ExternalTerminal::GnomeTerminal => cfg!(all(unix, not(target_os = "macos"))),
+        }
+    }
+
+    fn installed(self) -> bool {
+        match self {
+            ExternalTerminal::System | ExternalTerminal::MacTerminal => true,
+            ExternalTerminal::WindowsTerminal => find_program("wt.exe").is_some(),
+            ExternalTerminal::Iterm2 => app_bundle_exists("iTerm.app"),
+            ExternalTerminal::Kitty => {
+                find_program("kitty").is_some() || app_bundle_exists("kitty.app")
+            }
+            ExternalTerminal::GnomeTerminal => find_program("gnome-terminal").is_some(),
+        }
+    }
+}
+
+/// Which terminal hosts are opened in
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalSettings {
+    pub terminal: ExternalTerminal,
+    /// Profile used for hosts without their own (Windows Terminal/iTerm2/Terminal/GNOME Terminal
+    /// profile name, or a kitty config file)
+    pub default_profile: Option<String>,
+}
+
+/// Terminal offered in the settings
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalInfo {
+    pub terminal: ExternalTerminal,
+    pub name: String,
+    pub installed: bool,
+}
+
+/// Program and arguments that open the terminal; arguments are passed as-is, not through a shell
+#[derive(Debug, Clone, PartialEq)]
+struct LaunchCommand {
+    program: String,
+    args: Vec<String>,
+    /// Give the program its own console window (Windows)
+    new_console: bool,
+}
+
+impl LaunchCommand {
+    fn new(program: &str, args: Vec<String>) -> Self {
+        Self {
+            program: program.to_string(),
+            args,
+            new_console: false,
+        }
+    }
+}
+
+/// Opens hosts in the user's own terminal emulator
+pub struct TerminalLauncher;
+
+impl TerminalLauncher {
+    /// Load the terminal settings (system default when none were saved)
+    pub async fn get_settings() -> SshResult<TerminalSettings> {
+        Ok(SettingsService::get().terminal)
+    }
+
+    /// Save the terminal settings
+    pub async fn set_settings(settings: TerminalSettings) -> SshResult<()> {
+        if !settings.terminal.supported() {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "{} is not available on this system",
+                    settings.terminal.name()
+                ),
+            });
+        }
+        let settings = TerminalSettings {
+            default_profile: normalize_profile(settings.default_profile)?,
+            ..settings
+        };
+        let terminal = settings.terminal;
+        SettingsService::update(|current| {
+            current.terminal = settings;
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[terminal_launcher] External terminal set to {:?}",
+            terminal
+        );
+        Ok(())
+    }
+
+    /// Terminals supported on this platform and whether they are installed
+    pub fn available_terminals() -> Vec<TerminalInfo> {
+        ExternalTerminal::ALL
+            .into_iter()
+            .filter(|t| t.supported())
+            .map(|terminal| TerminalInfo {
+                terminal,
+                name: terminal.name().to_string(),
+                installed: terminal.installed(),
+            })
+            .collect()
+    }
+
+    /// Terminal profile of a host
+    pub async fn get_host_profile(host_alias: &str) -> SshResult<Option<String>> {
+        let store = RegistryService::load().await?;
+        Ok(store
+            .hosts
+            .get(host_alias)
+            .and_then(|m| m.terminal_profile.clone()))
+    }
+
+    /// Set or clear the terminal profile of a host
+    pub async fn set_host_profile(host_alias: &str, profile: Option<String>) -> SshResult<()> {
+        let profile = normalize_profile(profile)?;
+        RegistryService::update(|store| {
+            store
+                .hosts
+                .entry(host_alias.to_string())
+                .or_insert_with(HostMetadata::new)
+                .terminal_profile = profile;
+            Ok(())
+        })
+        .await?;
+        tracing::info!(
+            "[terminal_launcher] Updated terminal profile of {}",
+            host_alias
+        );
+        Ok(())
+    }
+
+    /// Open `ssh <alias>` in the configured terminal (`sftp <alias>` for SFTP-only hosts)
+    pub async fn launch(host_alias: &str) -> SshResult<()> {
+        ConfigService::validate_alias(host_alias)?;
+        let editor = ConfigService::load_editor().await?;
+        if !editor.has_host(host_alias) {
+            return Err(SshBuddyError::HostNotFound {
+                alias: host_alias.to_string(),
+            });
+        }
+
+        let settings = Self::get_settings().await?;
+        let profile = Self::get_host_profile(host_alias)
+            .await?
+            .or(settings.default_profile);
+        let client = match ShellAccessService::route(host_alias).await? {
+            ShellRoute::Sftp => Client::Sftp,
+            _ => Client::Ssh,
+        };
+        let argv = client_argv(client, host_alias, &ssh_client_options());
+        let command = match settings.terminal {
+            ExternalTerminal::System => system_command(host_alias, &argv, profile.as_deref())?,
+            terminal => terminal_command(terminal, host_alias, &argv, profile.as_deref()),
+        };
+        tracing::info!(
+            "[terminal_launcher] Opening {} in {} ({})",
+            host_alias,
+            settings.terminal.name(),
+            client.name()
+        );
+        spawn(&command).map_err(|e| SshBuddyError::Unknown {
+            message: format!("Failed to start {}: {}", settings.terminal.name(), e),
+        })
+    }
+
+    /// Run a program (e.g. a database client) in the configured terminal
+    /// Nothing but `argv` reaches the program: Terminal.app and iTerm2 start it from
+    /// AppleScript without the app's environment
+    pub async fn launch_program(title: &str, argv: Vec<String>) -> SshResult<()> {
+        if argv.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "No program to run".to_string(),
+            });
+        }
+        let settings = Self::get_settings().await?;
+        let profile = settings.default_profile.as_deref();
+        let command = match settings.terminal {
+            ExternalTerminal::System => system_command(title, &argv, profile)?,
+            terminal => terminal_command(terminal, title, &argv, profile),
+        };
+        tracing::info!(
+            "[terminal_launcher] Running {} in {}",
+            argv.first().map(String::as_str).unwrap_or_default(),
+            settings.terminal.name()
+        );
+        spawn(&command).map_err(|e| SshBuddyError::Unknown {
+            message: format!("Failed to start {}: {}", settings.terminal.name(), e),
+        })
+    }
+}
+
+fn normalize_profile(profile: Option<String>) -> SshResult<Option<String>> {
+    let profile = profile
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    if let Some(p) = &profile {
+        if p.chars().any(char::is_control) {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Terminal profile contains control characters".to_string(),
+            });
+        }
+    }
+    Ok(profile)
+}
+
+/// Client started in the terminal
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Client {
+    Ssh,
+    /// For hosts that only allow SFTP
+    Sftp,
+}
+
+impl Client {
+    fn name(self) -> &'static str {
+        match self {
+            Client::Ssh => "ssh",
+            Client::Sftp => "sftp",
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match (self, cfg!(windows)) {
+            (Client::Ssh, true) => "ssh.exe",
+            (Client::Sftp, true) => "sftp.exe",
+            (client, false) => client.name(),
+        }
+    }
+}
+
+/// Quote a string literal for AppleScript
+fn applescript_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+/// Windows Terminal splits its command line on `;` unless escaped
+fn wt_escape(value: &str) -> String {
+    value.replace(';', r"\;")
+}
+
+/// `osascript` call running one script line per `-e`
+fn osascript(lines: Vec<String>) -> LaunchCommand {
+    let args = lines
+        .into_iter()
+        .flat_map(|l| ["-e".to_string(), l])
+        .collect();
+    LaunchCommand::new("osascript", args)
+}
+
+/// `ssh <options> -- <alias>` (or sftp)
+fn client_argv(client: Client, alias: &str, options: &[String]) -> Vec<String> {
+    std::iter::once(client.program().to_string())
+        .chain(options.iter().cloned())
+        .chain(["--".to_string(), alias.to_string()])
+        .collect()
+}
+
+/// Terminal command running `argv` in a window titled `title`
+fn terminal_command(
+    terminal: ExternalTerminal,
+    title: &str,
+    argv: &[String],
+    profile: Option<&str>,
+) -> LaunchCommand {
+    // Shell command line for terminals that take one string
+    let line = argv
+        .iter()
+        .map(|a| shell_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    match terminal {
+        ExternalTerminal::WindowsTerminal => {
+            let mut args = vec!["new-tab".to_string()];
+            if let Some(profile) = profile {
+                args.extend(["--profile".to_string(), wt_escape(profile)]);
+            }
+            args.extend(["--title".to_string(), wt_escape(title)]);
+            args.extend(argv.iter().map(|a| wt_escape(a)));
+            LaunchCommand::new("wt.exe", args)
+        }
+        ExternalTerminal::MacTerminal => {
+            let mut lines = vec![
+                "tell application \"Terminal\"".to_string(),
+                "activate".to_string(),
+                format!("set newTab to do script {}", applescript_quote(&line)),
+            ];
+            if let Some(profile) = profile {
+                lines.push(format!(
+                    "set current settings of newTab to settings set {}",
+                    applescript_quote(profile)
+                ));
+            }
+            lines.push("end tell".to_string());
+            osascript(lines)
+        }
+        ExternalTerminal::Iterm2 => {
+            let window = match profile {
+                Some(profile) => format!("profile {}", applescript_quote(profile)),
+                None => "default profile".to_string(),
+            };
+            osascript(vec![
+                "tell application \"iTerm2\"".to_string(),
+                "activate".to_string(),
+                format!(
+                    "create window with {} command {}",
+                    window,
+                    applescript_quote(&line)
+                ),
+                "end tell".to_string(),
+            ])
+        }
+        ExternalTerminal::Kitty => {
+            let mut args = vec!["--title".to_string(), title.to_string()];
+            if let Some(profile) = profile {
+                args.extend(["--config".to_string(), profile.to_string()]);
+            }
+            args.extend(argv.iter().cloned());
+            LaunchCommand::new("kitty", args)
+        }
+        ExternalTerminal::GnomeTerminal => {
+            let mut args = Vec::new();
+            if let Some(profile) = profile {
+                args.push(format!("--profile={}", profile));
+            }
+            args.push("--".to_string());
+            args.extend(argv.iter().cloned());
+            LaunchCommand::new("gnome-terminal", args)
+        }
+        // New console window running the program (Windows)
+        ExternalTerminal::System => {
+            let mut command = LaunchCommand::new(&argv[0], argv[1..].to_vec());
+            command.new_console = true;
+            command
+        }
+    }
+}
+
+/// The platform's own terminal
+fn system_command(title: &str, argv: &[String], profile: Option<&str>) -> SshResult<LaunchCommand> {
+    if cfg!(target_os = "macos") {
+        return Ok(terminal_command(
+            ExternalTerminal::MacTerminal,
+            title,
+            argv,
+            profile,
+        ));
+    }
+    if cfg!(windows) {
+        return Ok(terminal_command(
+            ExternalTerminal::System,
+            title,
+            argv,
+            profile,
+        ));
+    }
+    let terminal_args = |prefix: &[&str]| {
+        prefix
+            .iter()
+            .map(|s| s.to_string())
+            .chain(argv.iter().cloned())
+            .collect::<Vec<_>>()
+    };
+    // xdg-terminal-exec is the freedesktop default-terminal launcher; Debian has an alternative
+    if find_program("xdg-terminal-exec").is_some() {
+        return Ok(LaunchCommand::new("xdg-terminal-exec", terminal_args(&[])));
+    }
+    if find_program("x-terminal-emulator").is_some() {
+        return Ok(LaunchCommand::new(
+            "x-terminal-emulator",
+            terminal_args(&["-e"]),
+        ));
+    }
+    Err(SshBuddyError::Unknown {
+        message: "No default terminal found; install xdg-terminal-exec or choose a terminal"
+            .to_string(),
+    })
+}
+
+fn spawn(command: &LaunchCommand) -> std::io::Result<()> {
+    let mut process = Command::new(&command.program);
+    process
+        .args(&command.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    if command.new_console {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_CONSOLE: u32 = 0x0000_0010;
+        process.creation_flags(CREATE_NEW_CONSOLE);
+    }
+    // The terminal outlives the app; the child handle is dropped without waiting
+    process.spawn().map(drop)
+}
+
+/// Find an executable on PATH
+pub(crate) fn find_program(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+pub(crate) fn app_bundle_exists(bundle: &str) -> bool {
+    if !cfg!(target_os = "macos") {
+        return false;
+    }
+    let user_apps = dirs::home_dir().map(|home| home.join("Applications"));
+    [Some(PathBuf::from("/Applications")), user_apps]
+        .into_iter()
+        .flatten()
+        .any(|dir| dir.join(bundle).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_command(
+        terminal: ExternalTerminal,
+        client: Client,
+        alias: &str,
+        profile: Option<&str>,
+        options: &[String],
+    ) -> LaunchCommand {
+        terminal_command(
+            terminal,
+            alias,
+            &client_argv(client, alias, options),
+            profile,
+        )
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(applescript_quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(wt_escape("a;b"), r"a\;b");
+    }
+
+    #[test]
+    fn test_build_command_passes_alias_as_one_argument() {
+        let ssh = Client::Ssh.program();
+        let command = build_command(
+            ExternalTerminal::Kitty,
+            Client::Ssh,
+            "web;rm",
+            Some("~/kitty-prod.conf"),
+            &[],
+        );
+        assert_eq!(command.program, "kitty");
+        assert_eq!(
+            command.args,
+            vec![
+                "--title",
+                "web;rm",
+                "--config",
+                "~/kitty-prod.conf",
+                ssh,
+                "--",
+                "web;rm"
+            ]
+        );
+
+        let command = build_command(
+            ExternalTerminal::WindowsTerminal,
+            Client::Ssh,
+            "a;b",
+            Some("Prod"),
+            &[],
+        );
+        assert_eq!(
+            command.args,
+            vec![
+                "new-tab",
+                "--profile",
+                "Prod",
+                "--title",
+                r"a\;b",
+                ssh,
+                "--",
+                r"a\;b"
+            ]
+        );
+
+        let command = build_command(
+            ExternalTerminal::GnomeTerminal,
+            Client::Ssh,
+            "web",
+            None,
+            &[],
+        );
+        assert_eq!(command.args, vec!["--", ssh, "--", "web"]);
+    }
+
+    #[test]
+    fn test_build_command_sftp() {
+        let command = build_command(ExternalTerminal::Kitty, Client::Sftp, "files", None, &[]);
+        assert_eq!(
+            command.args,
+            vec!["--title", "files", Client::Sftp.program(), "--", "files"]
+        );
+
+        let command = build_command(
+            ExternalTerminal::MacTerminal,
+            Client::Sftp,
+            "files",
+            None,
+            &[],
+        );
+        assert!(command.args[5].contains("sftp -- files"));
+    }
+
+    #[test]
+    fn test_build_command_ssh_options() {
+        let options = vec!["-F".to_string(), "/media/usb/ssh/config".to_string()];
+        let command = build_command(ExternalTerminal::Kitty, Client::Ssh, "web", None, &options);
+        assert_eq!(
+            command.args,
+            vec![
+                "--title",
+                "web",
+                Client::Ssh.program(),
+                "-F",
+                "/media/usb/ssh/config",
+                "--",
+                "web"
+            ]
+        );
+
+        let command = build_command(
+            ExternalTerminal::MacTerminal,
+            Client::Ssh,
+            "web",
+            None,
+            &options,
+        );
+        assert!(command.args[5].contains("ssh -F /media/usb/ssh/config -- web"));
+    }
+
+    #[test]
+    fn test_build_command_applescript() {
+        let command = build_command(
+            ExternalTerminal::Iterm2,
+            Client::Ssh,
+            "o'neil",
+            Some("Red \"prod\""),
+            &[],
+        );
+        assert_eq!(command.program, "osascript");
+        assert_eq!(
+            command.args[5],
+            r#"create window with profile "Red \"prod\"" command "ssh -- 'o'\\''neil'""#
+        );
+
+        let command = build_command(ExternalTerminal::MacTerminal, Client::Ssh, "web", None, &[]);
+        assert!(!command.args.iter().any(|a| a.contains("settings set")));
+        assert!(command
+            .args
+            .contains(&r#"set newTab to do script "ssh -- web""#.to_string()));
+    }
+}
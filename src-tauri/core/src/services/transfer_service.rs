@@ -0,0 +1,1030 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::settings_service::SettingsService;
+use crate::services::ssh_connection::{sftp_error, RemoteSession, SshConnectionService};
+use crate::utils::{app_data_path, write_atomic, TokenBucket};
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::OpenFlags;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+
+/// Bytes read and written per step
+const CHUNK_SIZE: usize = 32 * 1024;
+
+/// Minimum time between progress reports of a transfer
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+const MIN_CHUNK_SIZE: u32 = 32 * 1024;
+const MAX_CHUNK_SIZE: u32 = 8 * 1024 * 1024;
+const MAX_REQUESTS_PER_CHANNEL: u32 = 64;
+const MAX_CHANNELS: u32 = 8;
+
+/// Chunk buffers of one transfer (chunk size x requests per channel x channels)
+const MAX_BUFFER_BYTES: u64 = 64 * 1024 * 1024;
+
+/// How often the scheduler looks for due transfers
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// Single file copy between this machine and a host over SFTP
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRequest {
+    pub host_alias: String,
+    pub direction: TransferDirection,
+    pub local_path: String,
+    pub remote_path: String,
+    /// Cap for this transfer in bytes per second (on top of the global cap)
+    pub rate_limit: Option<u64>,
+}
+
+fn default_chunk_size() -> u32 {
+    256 * 1024
+}
+
+fn default_requests_per_channel() -> u32 {
+    8
+}
+
+fn default_channels() -> u32 {
+    2
+}
+
+/// transfers.json contents
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferSettings {
+    /// Cap shared by all running transfers in bytes per second
+    pub global_rate_limit: Option<u64>,
+    /// Bytes per read/write request when a file is copied in parallel chunks
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: u32,
+    /// Requests in flight per SFTP channel (the sliding window)
+    #[serde(default = "default_requests_per_channel")]
+    pub requests_per_channel: u32,
+    /// SFTP channels per transfer; fewer are used when the server refuses more
+    #[serde(default = "default_channels")]
+    pub channels: u32,
+}
+
+impl Default for TransferSettings {
+    fn default() -> Self {
+        Self {
+            global_rate_limit: None,
+            chunk_size: default_chunk_size(),
+            requests_per_channel: default_requests_per_channel(),
+            channels: default_channels(),
+        }
+    }
+}
+
+impl TransferSettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        validate_rate_limit(self.global_rate_limit)?;
+        let invalid = |message: &str| {
+            Err(SshBuddyError::InvalidOption {
+                message: message.to_string(),
+            })
+        };
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&self.chunk_size) {
+            return invalid("Chunk size must be between 32 KiB and 8 MiB");
+        }
+        if !(1..=MAX_REQUESTS_PER_CHANNEL).contains(&self.requests_per_channel) {
+            return invalid("Requests per channel must be between 1 and 64");
+        }
+        if !(1..=MAX_CHANNELS).contains(&self.channels) {
+            return invalid("Channels must be between 1 and 8");
+        }
+        let buffers = u64::from(self.chunk_size)
+            * u64::from(self.requests_per_channel)
+            * u64::from(self.channels);
+        if buffers > MAX_BUFFER_BYTES {
+            return invalid("Chunk size x requests per channel x channels must not exceed 64 MiB");
+        }
+        Ok(())
+    }
+
+    /// Whether a file of `total` bytes is worth splitting into chunks
+    fn parallel_for(&self, total: Option<u64>) -> bool {
+        let workers = self.requests_per_channel * self.channels;
+        total.map_or(false, |total| {
+            workers > 1 && total >= 2 * u64::from(self.chunk_size)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Progress of a transfer (also sent as its events)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferStatus {
+    pub id: String,
+    #[serde(flatten)]
+    pub request: TransferRequest,
+    pub state: TransferState,
+    pub transferred: u64,
+    /// File size, when known
+    pub total: Option<u64>,
+    /// Average speed since the start
+    pub bytes_per_sec: u64,
+    /// Unix timestamp in milliseconds
+    pub started_at: i64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScheduleState {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Transfer to start at a later time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTransfer {
+    pub id: String,
+    pub request: TransferRequest,
+    /// Unix timestamp in milliseconds
+    pub start_at: i64,
+    pub state: ScheduleState,
+    /// Id of the transfer once started
+    pub transfer_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// scheduled_transfers.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScheduleStore {
+    #[serde(default)]
+    transfers: Vec<ScheduledTransfer>,
+}
+
+struct RunningTransfer {
+    status: Arc<std::sync::Mutex<TransferStatus>>,
+    cancel: Arc<AtomicBool>,
+    /// Per-transfer cap, changeable while running
+    limit: Arc<std::sync::Mutex<Option<TokenBucket>>>,
+}
+
+/// Shared state of a chunked transfer; workers claim chunks in order
+#[derive(Clone)]
+struct ChunkJob {
+    direction: TransferDirection,
+    local_path: Arc<str>,
+    remote_path: Arc<str>,
+    total: u64,
+    chunk_size: u64,
+    next_chunk: Arc<AtomicU64>,
+    transferred: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    /// Set when a worker failed so the others stop
+    failed: Arc<AtomicBool>,
+    limit: Arc<std::sync::Mutex<Option<TokenBucket>>>,
+}
+
+impl ChunkJob {
+    /// Offset and length of the next chunk; None when done or stopped
+    fn claim(&self) -> Option<(u64, usize)> {
+        if self.cancel.load(Ordering::Relaxed) || self.failed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let index = self.next_chunk.fetch_add(1, Ordering::Relaxed);
+        chunk_range(index, self.chunk_size, self.total)
+    }
+}
+
+/// Runs SFTP transfers with per-transfer and global bandwidth caps, and starts
+/// scheduled transfers when they are due
+pub struct TransferManager {
+    running: Mutex<HashMap<String, RunningTransfer>>,
+    global_limit: std::sync::Mutex<Option<TokenBucket>>,
+}
+
+impl TransferManager {
+    /// Global manager
+    pub fn global() -> &'static TransferManager {
+        static MANAGER: OnceLock<TransferManager> = OnceLock::new();
+        MANAGER.get_or_init(|| TransferManager {
+            running: Mutex::new(HashMap::new()),
+            global_limit: std::sync::Mutex::new(None),
+        })
+    }
+
+    fn data_path(file: &str) -> SshResult<PathBuf> {
+        app_data_path(file)
+    }
+
+    async fn write_json<T: Serialize>(file: &str, value: &T) -> SshResult<()> {
+        let path = Self::data_path(file)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(value).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Load the transfer settings
+    pub async fn get_settings() -> SshResult<TransferSettings> {
+        Ok(SettingsService::get().transfers)
+    }
+
+    /// Save the transfer settings; the global cap applies to running transfers at once,
+    /// the chunking settings to transfers started afterwards
+    pub async fn set_settings(&self, settings: TransferSettings) -> SshResult<()> {
+        settings.validate()?;
+        let global_rate_limit = settings.global_rate_limit;
+        SettingsService::update(|current| {
+            current.transfers = settings;
+            Ok(())
+        })
+        .await?;
+        self.apply_global_limit(global_rate_limit);
+        tracing::info!(
+            "[transfer_service] Global rate limit set to {:?}",
+            global_rate_limit
+        );
+        Ok(())
+    }
+
+    pub fn apply_global_limit(&self, rate_limit: Option<u64>) {
+        if let Ok(mut bucket) = self.global_limit.lock() {
+            if bucket.as_ref().map(TokenBucket::rate) != rate_limit {
+                *bucket = rate_limit.map(|rate| TokenBucket::new(rate, Instant::now()));
+            }
+        }
+    }
+
+    /// Running transfers, oldest first
+    pub async fn list(&self) -> Vec<TransferStatus> {
+        let running = self.running.lock().await;
+        let mut transfers: Vec<TransferStatus> = running
+            .values()
+            .filter_map(|t| t.status.lock().ok().map(|s| s.clone()))
+            .collect();
+        transfers.sort_by_key(|t| t.started_at);
+        transfers
+    }
+
+    /// Change the cap of a running transfer (None removes it)
+    pub async fn set_rate_limit(&self, id: &str, rate_limit: Option<u64>) -> SshResult<()> {
+        validate_rate_limit(rate_limit)?;
+        let running = self.running.lock().await;
+        let transfer = running.get(id).ok_or_else(|| not_found(id))?;
+        if let Ok(mut limit) = transfer.limit.lock() {
+            *limit = rate_limit.map(|rate| TokenBucket::new(rate, Instant::now()));
+        }
+        if let Ok(mut status) = transfer.status.lock() {
+            status.request.rate_limit = rate_limit;
+        }
+        Ok(())
+    }
+
+    /// Ask a running transfer to stop
+    pub async fn cancel(&self, id: &str) -> SshResult<()> {
+        let running = self.running.lock().await;
+        let transfer = running.get(id).ok_or_else(|| not_found(id))?;
+        transfer.cancel.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Start a transfer and return its id; progress and the final state are
+    /// reported through `on_event`
+    pub async fn start<F>(
+        &'static self,
+        request: TransferRequest,
+        prompter: Option<&dyn AuthPrompter>,
+        on_event: F,
+    ) -> SshResult<String>
+    where
+        F: Fn(TransferStatus) + Send + Sync + 'static,
+    {
+        validate_rate_limit(request.rate_limit)?;
+        let settings = Self::get_settings().await?;
+        self.apply_global_limit(settings.global_rate_limit);
+        let session = SshConnectionService::open_session(&request.host_alias, prompter).await?;
+
+        let id = format!("{:016x}", rand::random::<u64>());
+        let status = Arc::new(std::sync::Mutex::new(TransferStatus {
+            id: id.clone(),
+            request: request.clone(),
+            state: TransferState::Running,
+            transferred: 0,
+            total: None,
+            bytes_per_sec: 0,
+            started_at: now_millis(),
+            error: None,
+        }));
+        let transfer = RunningTransfer {
+            status: status.clone(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            limit: Arc::new(std::sync::Mutex::new(
+                request
+                    .rate_limit
+                    .map(|rate| TokenBucket::new(rate, Instant::now())),
+            )),
+        };
+        let cancel = transfer.cancel.clone();
+        let limit = transfer.limit.clone();
+        self.running.lock().await.insert(id.clone(), transfer);
+        tracing::info!(
+            "[transfer_service] Started {} {:?} {} <-> {}:{}",
+            id,
+            request.direction,
+            request.local_path,
+            request.host_alias,
+            request.remote_path
+        );
+
+        let transfer_id = id.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let report = |transferred: u64, total: Option<u64>| {
+                let snapshot = status.lock().ok().map(|mut s| {
+                    s.transferred = transferred;
+                    s.total = total.or(s.total);
+                    s.bytes_per_sec = average_speed(transferred, started.elapsed());
+                    s.clone()
+                });
+                if let Some(snapshot) = snapshot {
+                    on_event(snapshot);
+                }
+            };
+            let result = self
+                .run(&session, &request, &settings, &cancel, &limit, &report)
+                .await;
+            if let Ok(s) = status.lock() {
+                session.add_transferred(s.transferred);
+            }
+            session.close().await;
+
+            let (state, error) = match result {
+                Ok(true) => (TransferState::Completed, None),
+                Ok(false) => (TransferState::Cancelled, None),
+                Err(e) => (TransferState::Failed, Some(e.to_string())),
+            };
+            tracing::info!(
+                "[transfer_service] Transfer {} finished: {:?} {:?}",
+                transfer_id,
+                state,
+                error
+            );
+            let snapshot = status.lock().ok().map(|mut s| {
+                s.state = state;
+                s.error = error;
+                s.clone()
+            });
+            self.running.lock().await.remove(&transfer_id);
+            if let Some(snapshot) = snapshot {
+                on_event(snapshot);
+            }
+        });
+        Ok(id)
+    }
+
+    /// Copy the file; false when cancelled
+    async fn run<P>(
+        &'static self,
+        session: &RemoteSession,
+        request: &TransferRequest,
+        settings: &TransferSettings,
+        cancel: &Arc<AtomicBool>,
+        limit: &Arc<std::sync::Mutex<Option<TokenBucket>>>,
+        report: &P,
+    ) -> SshResult<bool>
+    where
+        P: Fn(u64, Option<u64>),
+    {
+        let sftp = session.open_sftp().await?;
+        let total = match request.direction {
+            TransferDirection::Upload => fs::metadata(&request.local_path).await?.len().into(),
+            TransferDirection::Download => sftp
+                .metadata(&request.remote_path)
+                .await
+                .ok()
+                .and_then(|m| m.size),
+        };
+        report(0, total);
+
+        if let (true, Some(total)) = (settings.parallel_for(total), total) {
+            let job = ChunkJob {
+                direction: request.direction,
+                local_path: request.local_path.as_str().into(),
+                remote_path: request.remote_path.as_str().into(),
+                total,
+                chunk_size: u64::from(settings.chunk_size),
+                next_chunk: Arc::new(AtomicU64::new(0)),
+                transferred: Arc::new(AtomicU64::new(0)),
+                cancel: cancel.clone(),
+                failed: Arc::new(AtomicBool::new(false)),
+                limit: limit.clone(),
+            };
+            return self
+                .copy_parallel(session, sftp, job, settings, report)
+                .await;
+        }
+
+        let result = match request.direction {
+            TransferDirection::Upload => {
+                let mut local = fs::File::open(&request.local_path).await?;
+                let mut remote = sftp
+                    .create(&request.remote_path)
+                    .await
+                    .map_err(sftp_error)?;
+                self.copy(&mut local, &mut remote, cancel, limit, total, report)
+                    .await
+            }
+            TransferDirection::Download => {
+                let mut remote = sftp.open(&request.remote_path).await.map_err(sftp_error)?;
+                let mut local = fs::File::create(&request.local_path).await?;
+                self.copy(&mut remote, &mut local, cancel, limit, total, report)
+                    .await
+            }
+        };
+        let _ = sftp.close().await;
+        result
+    }
+
+    /// Copy fixed-size chunks at their offsets with several requests in flight per
+    /// channel and several channels where the server allows them
+    async fn copy_parallel<P>(
+        &'static self,
+        session: &RemoteSession,
+        primary: SftpSession,
+        job: ChunkJob,
+        settings: &TransferSettings,
+        report: &P,
+    ) -> SshResult<bool>
+    where
+        P: Fn(u64, Option<u64>),
+    {
+        // Workers write into a destination of the final size
+        match job.direction {
+            TransferDirection::Upload => {
+                let mut remote = primary
+                    .create(job.remote_path.as_ref())
+                    .await
+                    .map_err(sftp_error)?;
+                remote.shutdown().await?;
+            }
+            TransferDirection::Download => {
+                let local = fs::File::create(job.local_path.as_ref()).await?;
+                local.set_len(job.total).await?;
+            }
+        }
+
+        let mut channels = vec![Arc::new(primary)];
+        while channels.len() < settings.channels as usize {
+            match session.open_sftp().await {
+                Ok(sftp) => channels.push(Arc::new(sftp)),
+                Err(e) => {
+                    tracing::info!(
+                        "[transfer_service] Using {} SFTP channel(s): {}",
+                        channels.len(),
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+
+        let mut workers = JoinSet::new();
+        for sftp in &channels {
+            for _ in 0..settings.requests_per_channel {
+                workers.spawn(self.copy_chunks(sftp.clone(), job.clone()));
+            }
+        }
+        let mut failure = None;
+        let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+        loop {
+            tokio::select! {
+                joined = workers.join_next() => match joined {
+                    None => break,
+                    Some(Ok(Ok(()))) => {}
+                    Some(Ok(Err(e))) => {
+                        job.failed.store(true, Ordering::Relaxed);
+                        failure.get_or_insert(e);
+                    }
+                    Some(Err(e)) => {
+                        job.failed.store(true, Ordering::Relaxed);
+                        failure.get_or_insert(SshBuddyError::Unknown {
+                            message: format!("Transfer worker failed: {}", e),
+                        });
+                    }
+                },
+                _ = progress.tick() => {
+                    report(job.transferred.load(Ordering::Relaxed), Some(job.total));
+                }
+            }
+        }
+        for sftp in channels {
+            if let Ok(sftp) = Arc::try_unwrap(sftp) {
+                let _ = sftp.close().await;
+            }
+        }
+
+        report(job.transferred.load(Ordering::Relaxed), Some(job.total));
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(!job.cancel.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Worker: claim the next chunk until none are left, copying each over its own handles
+    async fn copy_chunks(&'static self, sftp: Arc<SftpSession>, job: ChunkJob) -> SshResult<()> {
+        let mut remote = match job.direction {
+            TransferDirection::Upload => sftp
+                .open_with_flags(job.remote_path.as_ref(), OpenFlags::WRITE)
+                .await
+                .map_err(sftp_error)?,
+            TransferDirection::Download => sftp
+                .open(job.remote_path.as_ref())
+                .await
+                .map_err(sftp_error)?,
+        };
+        let mut local = match job.direction {
+            TransferDirection::Upload => fs::File::open(job.local_path.as_ref()).await?,
+            TransferDirection::Download => {
+                fs::OpenOptions::new()
+                    .write(true)
+                    .open(job.local_path.as_ref())
+                    .await?
+            }
+        };
+
+        match job.direction {
+            TransferDirection::Upload => {
+                self.copy_claimed(&mut local, &mut remote, &job).await?;
+                remote.shutdown().await?;
+            }
+            TransferDirection::Download => {
+                self.copy_claimed(&mut remote, &mut local, &job).await?;
+                local.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the chunks this worker claims from `source` to the same offsets of
+    /// `destination`
+    async fn copy_claimed<S, D>(
+        &self,
+        source: &mut S,
+        destination: &mut D,
+        job: &ChunkJob,
+    ) -> SshResult<()>
+    where
+        S: AsyncRead + AsyncSeek + Unpin,
+        D: AsyncWrite + AsyncSeek + Unpin,
+    {
+        let mut buffer = vec![0u8; job.chunk_size as usize];
+        while let Some((offset, len)) = job.claim() {
+            let chunk = &mut buffer[..len];
+            source.seek(SeekFrom::Start(offset)).await?;
+            source.read_exact(chunk).await?;
+            let wait = self.reserve(&job.limit, len);
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+            destination.seek(SeekFrom::Start(offset)).await?;
+            destination.write_all(chunk).await?;
+            job.transferred.fetch_add(len as u64, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    async fn copy<R, W, P>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+        cancel: &AtomicBool,
+        limit: &std::sync::Mutex<Option<TokenBucket>>,
+        total: Option<u64>,
+        report: &P,
+    ) -> SshResult<bool>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+        P: Fn(u64, Option<u64>),
+    {
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut transferred = 0u64;
+        let mut last_report = Instant::now();
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                return Ok(false);
+            }
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            let wait = self.reserve(limit, read);
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+            writer.write_all(&buffer[..read]).await?;
+            transferred += read as u64;
+            if last_report.elapsed() >= PROGRESS_INTERVAL {
+                report(transferred, total);
+                last_report = Instant::now();
+            }
+        }
+        writer.shutdown().await?;
+        report(transferred, total);
+        Ok(true)
+    }
+
+    /// Take `amount` bytes from the transfer's and the global bucket; returns the
+    /// longer of the two waits
+    fn reserve(&self, limit: &std::sync::Mutex<Option<TokenBucket>>, amount: usize) -> Duration {
+        let now = Instant::now();
+        let take = |bucket: &std::sync::Mutex<Option<TokenBucket>>| {
+            bucket
+                .lock()
+                .ok()
+                .and_then(|mut b| b.as_mut().map(|b| b.reserve(amount, now)))
+                .unwrap_or_default()
+        };
+        take(limit).max(take(&self.global_limit))
+    }
+
+    async fn load_schedule() -> SshResult<ScheduleStore> {
+        let path = Self::data_path("scheduled_transfers.json")?;
+        if !path.exists() {
+            return Ok(ScheduleStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse scheduled transfers: {}", e),
+        })
+    }
+
+    /// Scheduled transfers, soonest first
+    pub async fn list_scheduled() -> SshResult<Vec<ScheduledTransfer>> {
+        let mut transfers = Self::load_schedule().await?.transfers;
+        transfers.sort_by_key(|t| t.start_at);
+        Ok(transfers)
+    }
+
+    /// Schedule a transfer to start at `start_at` (Unix timestamp in milliseconds)
+    pub async fn schedule(request: TransferRequest, start_at: i64) -> SshResult<ScheduledTransfer> {
+        ReadOnlyMode::ensure_writable("schedule transfer")?;
+        validate_rate_limit(request.rate_limit)?;
+        let scheduled = ScheduledTransfer {
+            id: format!("{:08x}", rand::random::<u32>()),
+            request,
+            start_at,
+            state: ScheduleState::Pending,
+            transfer_id: None,
+            error: None,
+        };
+        let mut store = Self::load_schedule().await?;
+        store.transfers.push(scheduled.clone());
+        Self::write_json("scheduled_transfers.json", &store).await?;
+        tracing::info!(
+            "[transfer_service] Scheduled transfer {} for {}",
+            scheduled.id,
+            start_at
+        );
+        Ok(scheduled)
+    }
+
+    /// Remove a scheduled transfer (a started one keeps running)
+    pub async fn delete_scheduled(id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete scheduled transfer")?;
+        let mut store = Self::load_schedule().await?;
+        store.transfers.retain(|t| t.id != id);
+        Self::write_json("scheduled_transfers.json", &store).await
+    }
+
+    async fn update_scheduled<U>(id: &str, update: U)
+    where
+        U: FnOnce(&mut ScheduledTransfer),
+    {
+        let result = async {
+            let mut store = Self::load_schedule().await?;
+            if let Some(scheduled) = store.transfers.iter_mut().find(|t| t.id == id) {
+                update(scheduled);
+            }
+            Self::write_json("scheduled_transfers.json", &store).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!(
+                "[transfer_service] Failed to update scheduled transfer {}: {}",
+                id,
+                e
+            );
+        }
+    }
+
+    /// Start scheduled transfers when they are due (runs for the lifetime of the app)
+    /// Jobs that were running when the app quit are started again
+    /// Scheduled transfers can't answer authentication prompts
+    pub async fn run_scheduler<F>(&'static self, on_event: F)
+    where
+        F: Fn(TransferStatus) + Clone + Send + Sync + 'static,
+    {
+        // Nothing is running yet, so "running" jobs were cut off by the last exit
+        if let Ok(store) = Self::load_schedule().await {
+            for scheduled in store.transfers {
+                if scheduled.state == ScheduleState::Running {
+                    Self::update_scheduled(&scheduled.id, |t| {
+                        t.state = ScheduleState::Pending;
+                        t.transfer_id = None;
+                    })
+                    .await;
+                }
+            }
+        }
+
+        let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+        loop {
+            interval.tick().await;
+            let due: Vec<ScheduledTransfer> = match Self::load_schedule().await {
+                Ok(store) => store
+                    .transfers
+                    .into_iter()
+                    .filter(|t| is_due(t, now_millis()))
+                    .collect(),
+                Err(e) => {
+                    tracing::warn!("[transfer_service] {}", e);
+                    continue;
+                }
+            };
+            for scheduled in due {
+                self.start_scheduled(scheduled, on_event.clone()).await;
+            }
+        }
+    }
+
+    async fn start_scheduled<F>(&'static self, scheduled: ScheduledTransfer, on_event: F)
+    where
+        F: Fn(TransferStatus) + Send + Sync + 'static,
+    {
+        tracing::info!(
+            "[transfer_service] Starting scheduled transfer {}",
+            scheduled.id
+        );
+        let schedule_id = scheduled.id.clone();
+        let finished = move |status: TransferStatus| {
+            if status.state != TransferState::Running {
+                let schedule_id = schedule_id.clone();
+                let (state, error) = (status.state, status.error.clone());
+                tokio::spawn(async move {
+                    Self::update_scheduled(&schedule_id, |t| {
+                        t.state = match state {
+                            TransferState::Completed => ScheduleState::Completed,
+                            TransferState::Cancelled => ScheduleState::Cancelled,
+                            _ => ScheduleState::Failed,
+                        };
+                        t.error = error;
+                    })
+                    .await;
+                });
+            }
+            on_event(status);
+        };
+        Self::update_scheduled(&scheduled.id, |t| t.state = ScheduleState::Running).await;
+        match self.start(scheduled.request, None, finished).await {
+            Ok(transfer_id) => {
+                Self::update_scheduled(&scheduled.id, |t| t.transfer_id = Some(transfer_id)).await
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "[transfer_service] Scheduled transfer {} failed to start: {}",
+                    scheduled.id,
+                    e
+                );
+                Self::update_scheduled(&scheduled.id, |t| {
+                    t.state = ScheduleState::Failed;
+                    t.error = Some(e.to_string());
+                })
+                .await
+            }
+        }
+    }
+}
+
+fn not_found(id: &str) -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: format!("Transfer not found: {}", id),
+    }
+}
+
+fn validate_rate_limit(rate_limit: Option<u64>) -> SshResult<()> {
+    if rate_limit == Some(0) {
+        return Err(SshBuddyError::InvalidOption {
+            message: "Rate limit must be greater than zero".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn is_due(scheduled: &ScheduledTransfer, now: i64) -> bool {
+    scheduled.state == ScheduleState::Pending && scheduled.start_at <= now
+}
+
+/// Byte range of chunk `index`, the last one possibly shorter
+fn chunk_range(index: u64, chunk_size: u64, total: u64) -> Option<(u64, usize)> {
+    let offset = index.checked_mul(chunk_size)?;
+    if offset >= total {
+        return None;
+    }
+    Some((offset, chunk_size.min(total - offset) as usize))
+}
+
+fn average_speed(transferred: u64, elapsed: Duration) -> u64 {
+    let secs = elapsed.as_secs_f64();
+    if secs < 0.001 {
+        return 0;
+    }
+    (transferred as f64 / secs) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduled(state: ScheduleState, start_at: i64) -> ScheduledTransfer {
+        ScheduledTransfer {
+            id: "t".to_string(),
+            request: TransferRequest {
+                host_alias: "web".to_string(),
+                direction: TransferDirection::Upload,
+                local_path: "/tmp/a".to_string(),
+                remote_path: "/tmp/b".to_string(),
+                rate_limit: None,
+            },
+            start_at,
+            state,
+            transfer_id: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_is_due() {
+        assert!(is_due(&scheduled(ScheduleState::Pending, 100), 100));
+        assert!(!is_due(&scheduled(ScheduleState::Pending, 101), 100));
+        assert!(!is_due(&scheduled(ScheduleState::Running, 0), 100));
+    }
+
+    #[test]
+    fn test_chunk_range() {
+        assert_eq!(chunk_range(0, 100, 250), Some((0, 100)));
+        assert_eq!(chunk_range(2, 100, 250), Some((200, 50)));
+        assert_eq!(chunk_range(3, 100, 250), None);
+        assert_eq!(chunk_range(u64::MAX, 100, 250), None);
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = TransferSettings::default();
+        assert!(settings.validate().is_ok());
+        assert!(settings.parallel_for(Some(10 * 1024 * 1024)));
+        assert!(!settings.parallel_for(Some(100 * 1024)));
+        assert!(!settings.parallel_for(None));
+
+        let sequential = TransferSettings {
+            requests_per_channel: 1,
+            channels: 1,
+            ..TransferSettings::default()
+        };
+        assert!(!sequential.parallel_for(Some(10 * 1024 * 1024)));
+
+        let too_many = TransferSettings {
+            channels: 9,
+            ..TransferSettings::default()
+        };
+        assert!(too_many.validate().is_err());
+
+        // Each limit on its own is fine, together they'd buffer 4 GiB
+        let too_much_memory = TransferSettings {
+            chunk_size: MAX_CHUNK_SIZE,
+            requests_per_channel: MAX_REQUESTS_PER_CHANNEL,
+            channels: MAX_CHANNELS,
+            ..TransferSettings::default()
+        };
+        assert!(too_much_memory.validate().is_err());
+        let largest = TransferSettings {
+            chunk_size: MAX_CHUNK_SIZE,
+            requests_per_channel: 4,
+            channels: 2,
+            ..TransferSettings::default()
+        };
+        assert!(largest.validate().is_ok());
+
+        // Settings saved before chunking was configurable
+        let old: TransferSettings = serde_json::from_str(r#"{"globalRateLimit":1000}"#).unwrap();
+        assert_eq!(old.chunk_size, 256 * 1024);
+        assert_eq!(old.channels, 2);
+    }
+
+    fn chunk_job(total: u64, chunk_size: u64) -> ChunkJob {
+        ChunkJob {
+            direction: TransferDirection::Download,
+            local_path: "".into(),
+            remote_path: "".into(),
+            total,
+            chunk_size,
+            next_chunk: Arc::new(AtomicU64::new(0)),
+            transferred: Arc::new(AtomicU64::new(0)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            failed: Arc::new(AtomicBool::new(false)),
+            limit: Arc::new(std::sync::Mutex::new(None)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_claimed_chunks() {
+        let manager = TransferManager {
+            running: Mutex::new(HashMap::new()),
+            global_limit: std::sync::Mutex::new(None),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let source_path = dir.path().join("source");
+        let destination_path = dir.path().join("destination");
+        let content: Vec<u8> = (0..100_003u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&source_path, &content).await.unwrap();
+        let total = content.len() as u64;
+        fs::File::create(&destination_path)
+            .await
+            .unwrap()
+            .set_len(total)
+            .await
+            .unwrap();
+
+        // Workers on their own handles, as over several SFTP requests and channels
+        let job = chunk_job(total, 4096);
+        let (manager, job, source_path, destination_path) =
+            (&manager, &job, &source_path, &destination_path);
+        let worker = || async move {
+            let mut source = fs::File::open(&source_path).await.unwrap();
+            let mut destination = fs::OpenOptions::new()
+                .write(true)
+                .open(&destination_path)
+                .await
+                .unwrap();
+            manager
+                .copy_claimed(&mut source, &mut destination, job)
+                .await?;
+            destination.flush().await?;
+            SshResult::Ok(())
+        };
+        let (a, b, c) = tokio::join!(worker(), worker(), worker());
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(job.transferred.load(Ordering::Relaxed), total);
+        assert_eq!(fs::read(&destination_path).await.unwrap(), content);
+
+        // A cancelled job copies nothing more
+        let cancelled = chunk_job(total, 4096);
+        cancelled.cancel.store(true, Ordering::Relaxed);
+        let mut source = fs::File::open(&source_path).await.unwrap();
+        let mut destination = fs::File::create(dir.path().join("cancelled"))
+            .await
+            .unwrap();
+        manager
+            .copy_claimed(&mut source, &mut destination, &cancelled)
+            .await
+            .unwrap();
+        assert_eq!(cancelled.transferred.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_average_speed() {
+        assert_eq!(average_speed(1000, Duration::from_millis(500)), 2000);
+        assert_eq!(average_speed(1000, Duration::ZERO), 0);
+    }
+}
@@ -0,0 +1,449 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::config_service::ConfigService;
+use crate::services::integrity::IntegrityService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::revision_service::{ManagedFile, RevisionService};
+use crate::services::settings_service::SettingsService;
+use crate::utils::{app_data_dir, split_directive, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Items older than this drop out of the recently deleted list
+const RETENTION_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// Most items kept in the recently deleted list
+const MAX_ITEMS: usize = 200;
+
+/// Trash section of the settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TrashSettings {
+    /// Move deleted key files to the OS trash and remember removed hosts and
+    /// known_hosts entries so they can be restored
+    pub enabled: bool,
+}
+
+/// What was deleted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DeletedKind {
+    Key,
+    Host,
+    KnownHosts,
+}
+
+/// Entry of the recently deleted list
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedItem {
+    pub id: String,
+    pub kind: DeletedKind,
+    /// Key name, host alias or known_hosts host name
+    pub name: String,
+    /// Unix timestamp in milliseconds
+    pub deleted_at: i64,
+    /// Files moved to the OS trash (keys)
+    #[serde(default)]
+    pub trashed_paths: Vec<String>,
+    /// Removed Host block or known_hosts lines
+    #[serde(default)]
+    pub content: Option<String>,
+    /// The app can put it back on this system (keys trashed on macOS are restored
+    /// from the Finder's Trash)
+    #[serde(default)]
+    pub restorable: bool,
+}
+
+/// recently_deleted.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeletedIndex {
+    #[serde(default)]
+    items: Vec<DeletedItem>,
+}
+
+/// Whether the trash crate can list and restore trashed files here
+const CAN_RESTORE_FROM_TRASH: bool = cfg!(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+));
+
+/// Sends deleted key files to the OS trash and keeps a list of recent deletions
+/// that can be restored
+pub struct TrashService;
+
+impl TrashService {
+    pub fn get_settings() -> TrashSettings {
+        SettingsService::get().trash
+    }
+
+    pub async fn set_settings(settings: &TrashSettings) -> SshResult<()> {
+        SettingsService::update(|current| {
+            current.trash = settings.clone();
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    fn index_path() -> SshResult<PathBuf> {
+        Ok(app_data_dir()?.join("recently_deleted.json"))
+    }
+
+    async fn load_index() -> SshResult<DeletedIndex> {
+        let path = Self::index_path()?;
+        match fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to parse the recently deleted list: {}", e),
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DeletedIndex::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save_index(index: &DeletedIndex) -> SshResult<()> {
+        let path = Self::index_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(index).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Delete files, through the OS trash when enabled
+    /// Returns the paths that went to the trash (empty when deleted for good)
+    pub async fn remove_files(paths: &[PathBuf]) -> SshResult<Vec<String>> {
+        if !Self::get_settings().enabled {
+            for path in paths {
+                fs::remove_file(path).await?;
+            }
+            return Ok(Vec::new());
+        }
+        let targets = paths.to_vec();
+        tokio::task::spawn_blocking(move || trash::delete_all(&targets))
+            .await
+            .map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!(
+                    "Failed to move to the trash (turn off the trash option to delete for good): {}",
+                    e
+                ),
+            })?;
+        Ok(paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect())
+    }
+
+    /// Add a deletion to the list (nothing is kept when the trash is disabled)
+    /// Failures are logged but never fail the deletion itself
+    pub async fn record(
+        kind: DeletedKind,
+        name: &str,
+        trashed_paths: Vec<String>,
+        content: Option<String>,
+    ) {
+        if !Self::get_settings().enabled {
+            return;
+        }
+        let deleted_at = now_millis();
+        let item = DeletedItem {
+            id: format!("{}-{}", deleted_at, rand::random::<u32>()),
+            kind,
+            name: name.to_string(),
+            deleted_at,
+            trashed_paths,
+            content,
+            restorable: false,
+        };
+        let result = async {
+            let mut index = Self::load_index().await?;
+            index.items.push(item);
+            prune(&mut index.items, deleted_at);
+            Self::save_index(&index).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("[trash_service] Failed to record deleted {}: {}", name, e);
+        }
+    }
+
+    /// Recently deleted items, most recent first
+    pub async fn list() -> SshResult<Vec<DeletedItem>> {
+        let mut items = Self::load_index().await?.items;
+        prune(&mut items, now_millis());
+        for item in &mut items {
+            item.restorable = is_restorable(item);
+        }
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(items)
+    }
+
+    /// Put a deleted item back (fails if something took its place)
+    pub async fn restore(id: &str) -> SshResult<DeletedItem> {
+        ReadOnlyMode::ensure_writable("restore a deleted item")?;
+        let mut index = Self::load_index().await?;
+        let position = index
+            .items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| unknown_item(id))?;
+        let item = index.items[position].clone();
+        if !is_restorable(&item) {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "{} can't be restored here; put it back from the system Trash",
+                    item.name
+                ),
+            });
+        }
+        match item.kind {
+            DeletedKind::Key => {
+                let paths: Vec<PathBuf> = item.trashed_paths.iter().map(PathBuf::from).collect();
+                if let Some(existing) = paths.iter().find(|p| p.exists()) {
+                    return Err(SshBuddyError::InvalidPath {
+                        message: format!("{} already exists", existing.display()),
+                    });
+                }
+                tokio::task::spawn_blocking(move || restore_from_trash(&paths))
+                    .await
+                    .map_err(|e| SshBuddyError::Unknown {
+                        message: e.to_string(),
+                    })??;
+                IntegrityService::record_key_write(&item.name).await;
+            }
+            DeletedKind::Host => {
+                restore_host(&item.name, item.content.as_deref().unwrap_or_default()).await?
+            }
+            DeletedKind::KnownHosts => {
+                restore_known_hosts(&item.name, item.content.as_deref().unwrap_or_default()).await?
+            }
+        }
+        tracing::info!("[trash_service] Restored {:?} {}", item.kind, item.name);
+        index.items.remove(position);
+        Self::save_index(&index).await?;
+        Ok(item)
+    }
+
+    /// Drop an item from the list (trashed files stay in the OS trash)
+    pub async fn forget(id: &str) -> SshResult<()> {
+        let mut index = Self::load_index().await?;
+        let before = index.items.len();
+        index.items.retain(|item| item.id != id);
+        if index.items.len() == before {
+            return Err(unknown_item(id));
+        }
+        Self::save_index(&index).await
+    }
+}
+
+fn unknown_item(id: &str) -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: format!("Unknown deleted item: {}", id),
+    }
+}
+
+/// Drop expired items and keep at most MAX_ITEMS, the newest ones
+fn prune(items: &mut Vec<DeletedItem>, now: i64) {
+    items.retain(|item| now - item.deleted_at < RETENTION_MS);
+    items.sort_by_key(|item| item.deleted_at);
+    if items.len() > MAX_ITEMS {
+        items.drain(..items.len() - MAX_ITEMS);
+    }
+}
+
+fn is_restorable(item: &DeletedItem) -> bool {
+    match item.kind {
+        DeletedKind::Key => CAN_RESTORE_FROM_TRASH && !item.trashed_paths.is_empty(),
+        DeletedKind::Host | DeletedKind::KnownHosts => item
+            .content
+            .as_deref()
+            .is_some_and(|c| !c.trim().is_empty()),
+    }
+}
+
+/// Put files back from the OS trash (the most recent deletion of each path)
+#[cfg(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn restore_from_trash(paths: &[PathBuf]) -> SshResult<()> {
+    let trashed = trash::os_limited::list().map_err(|e| SshBuddyError::IoError {
+        message: format!("Failed to read the trash: {}", e),
+    })?;
+    let mut items = Vec::new();
+    for path in paths {
+        let item = trashed
+            .iter()
+            .filter(|item| item.original_path() == *path)
+            .max_by_key(|item| item.time_deleted)
+            .ok_or_else(|| SshBuddyError::InvalidPath {
+                message: format!("{} is no longer in the trash", path.display()),
+            })?;
+        items.push(item.clone());
+    }
+    trash::os_limited::restore_all(items).map_err(|e| SshBuddyError::IoError {
+        message: format!("Failed to restore from the trash: {}", e),
+    })
+}
+
+#[cfg(not(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn restore_from_trash(_paths: &[PathBuf]) -> SshResult<()> {
+    Err(SshBuddyError::InvalidOption {
+        message: "Restoring from the trash isn't supported on this system".to_string(),
+    })
+}
+
+/// Options of a removed Host block (its Host line and comments left out)
+fn block_options(block: &str) -> Vec<(String, String)> {
+    block
+        .lines()
+        .filter_map(split_directive)
+        .filter(|(key, _)| !key.eq_ignore_ascii_case("host"))
+        .collect()
+}
+
+async fn restore_host(alias: &str, block: &str) -> SshResult<()> {
+    ConfigService::update(|editor| {
+        if editor.has_host(alias) {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!("Host {} already exists", alias),
+            });
+        }
+        editor.append_host(alias, &block_options(block));
+        Ok(())
+    })
+    .await
+}
+
+/// known_hosts with `lines` added back (lines still present aren't duplicated)
+fn merge_known_hosts(current: &str, lines: &str) -> String {
+    let mut merged = current.to_string();
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    for line in lines.lines().filter(|l| !l.trim().is_empty()) {
+        if !current.lines().any(|existing| existing == line) {
+            merged.push_str(line);
+            merged.push('\n');
+        }
+    }
+    merged
+}
+
+async fn restore_known_hosts(hostname: &str, lines: &str) -> SshResult<()> {
+    let path = ManagedFile::KnownHosts.live_path()?;
+    let current = match fs::read_to_string(&path).await {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let merged = merge_known_hosts(current.as_deref().unwrap_or_default(), lines);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    write_atomic(&path, merged.as_bytes()).await?;
+    RevisionService::record_change(
+        ManagedFile::KnownHosts,
+        current.as_deref(),
+        &merged,
+        &format!("Restore {} to known_hosts", hostname),
+    )
+    .await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, deleted_at: i64) -> DeletedItem {
+        DeletedItem {
+            id: id.to_string(),
+            kind: DeletedKind::Host,
+            name: id.to_string(),
+            deleted_at,
+            trashed_paths: Vec::new(),
+            content: Some("Host a\n".to_string()),
+            restorable: false,
+        }
+    }
+
+    #[test]
+    fn test_prune() {
+        let now = RETENTION_MS * 2;
+        let mut items = vec![item("new", now - 1), item("old", now - RETENTION_MS)];
+        prune(&mut items, now);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "new");
+
+        let mut items: Vec<_> = (0..MAX_ITEMS as i64 + 5)
+            .map(|i| item(&i.to_string(), now - i))
+            .collect();
+        prune(&mut items, now);
+        assert_eq!(items.len(), MAX_ITEMS);
+        assert!(items.iter().all(|i| i.deleted_at > now - MAX_ITEMS as i64));
+    }
+
+    #[test]
+    fn test_is_restorable() {
+        let mut key = item("key", 0);
+        key.kind = DeletedKind::Key;
+        key.content = None;
+        assert!(!is_restorable(&key));
+        key.trashed_paths = vec!["/home/me/.ssh/id_ed25519".to_string()];
+        assert_eq!(is_restorable(&key), CAN_RESTORE_FROM_TRASH);
+
+        let mut host = item("host", 0);
+        assert!(is_restorable(&host));
+        host.content = Some("  \n".to_string());
+        assert!(!is_restorable(&host));
+    }
+
+    #[test]
+    fn test_block_options() {
+        let block = "Host web prod\n    # main server\n    HostName 10.0.0.5\n    IdentityFile \"~/.ssh/my key\"\n";
+        assert_eq!(
+            block_options(block),
+            vec![
+                ("HostName".to_string(), "10.0.0.5".to_string()),
+                ("IdentityFile".to_string(), "\"~/.ssh/my key\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_known_hosts() {
+        let current = "a.example ssh-ed25519 AAAA";
+        let lines = "b.example ssh-ed25519 BBBB\na.example ssh-ed25519 AAAA\n";
+        assert_eq!(
+            merge_known_hosts(current, lines),
+            "a.example ssh-ed25519 AAAA\nb.example ssh-ed25519 BBBB\n"
+        );
+        assert_eq!(merge_known_hosts("", "c ssh-rsa CC\n"), "c ssh-rsa CC\n");
+    }
+}
@@ -0,0 +1,205 @@
+use crate::models::SshResult;
+use crate::services::agent_service::AgentService;
+use crate::services::config_service::ConfigService;
+use crate::services::registry_service::{pinned_aliases, MetadataStore, RegistryService};
+use crate::services::tunnel_service::{TunnelManager, TunnelStatus};
+use serde::Serialize;
+
+/// Recent hosts shown below the pinned ones
+const MAX_RECENT_HOSTS: usize = 5;
+
+/// Host entry in the tray menu
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayHost {
+    pub alias: String,
+}
+
+/// Tunnel toggle in the tray menu
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayTunnel {
+    pub id: String,
+    pub name: String,
+    pub running: bool,
+}
+
+/// SSH agent state shown in the tray menu
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TrayAgentState {
+    NotRunning,
+    Unlocked,
+    Locked,
+}
+
+/// Everything the tray menu shows
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayMenuModel {
+    /// Favorite hosts, in pin order
+    pub pinned: Vec<TrayHost>,
+    /// Most recently used hosts that aren't pinned
+    pub recent: Vec<TrayHost>,
+    pub tunnels: Vec<TrayTunnel>,
+    pub agent: TrayAgentState,
+}
+
+/// Action behind a tray menu item; the item id is its string form
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrayAction {
+    Connect(String),
+    ToggleTunnel(String),
+    LockAgent,
+    UnlockAgent,
+    ShowWindow,
+    Quit,
+}
+
+impl TrayAction {
+    pub fn id(&self) -> String {
+        match self {
+            TrayAction::Connect(alias) => format!("connect:{}", alias),
+            TrayAction::ToggleTunnel(id) => format!("tunnel:{}", id),
+            TrayAction::LockAgent => "agent:lock".to_string(),
+            TrayAction::UnlockAgent => "agent:unlock".to_string(),
+            TrayAction::ShowWindow => "show".to_string(),
+            TrayAction::Quit => "quit".to_string(),
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        if let Some(alias) = id.strip_prefix("connect:") {
+            return Some(TrayAction::Connect(alias.to_string()));
+        }
+        if let Some(tunnel) = id.strip_prefix("tunnel:") {
+            return Some(TrayAction::ToggleTunnel(tunnel.to_string()));
+        }
+        match id {
+            "agent:lock" => Some(TrayAction::LockAgent),
+            "agent:unlock" => Some(TrayAction::UnlockAgent),
+            "show" => Some(TrayAction::ShowWindow),
+            "quit" => Some(TrayAction::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Builds the tray menu model from the host registry, tunnels and agent
+pub struct TrayMenuService;
+
+impl TrayMenuService {
+    /// Current menu model
+    pub async fn build() -> SshResult<TrayMenuModel> {
+        let store = RegistryService::load().await?;
+        let aliases = ConfigService::load_editor().await?.host_aliases();
+        let tunnels = TunnelManager::global().status().await?;
+        let agent = if !AgentService::is_running().await {
+            TrayAgentState::NotRunning
+        } else if AgentService::is_locked() {
+            TrayAgentState::Locked
+        } else {
+            TrayAgentState::Unlocked
+        };
+        Ok(build_model(&store, &aliases, &tunnels, agent))
+    }
+}
+
+/// Only hosts still in the SSH config are listed
+fn build_model(
+    store: &MetadataStore,
+    aliases: &[String],
+    tunnels: &[TunnelStatus],
+    agent: TrayAgentState,
+) -> TrayMenuModel {
+    let known = |alias: &&String| aliases.contains(alias);
+
+    let pinned: Vec<String> = pinned_aliases(store)
+        .into_iter()
+        .filter(|alias| known(&alias))
+        .collect();
+
+    let mut recent: Vec<(&String, i64)> = store
+        .hosts
+        .iter()
+        .filter(|(_, m)| !m.is_favorite)
+        .filter_map(|(alias, m)| Some((alias, m.last_used?)))
+        .filter(|(alias, _)| known(alias))
+        .collect();
+    recent.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    TrayMenuModel {
+        pinned: pinned.into_iter().map(|alias| TrayHost { alias }).collect(),
+        recent: recent
+            .into_iter()
+            .take(MAX_RECENT_HOSTS)
+            .map(|(alias, _)| TrayHost {
+                alias: alias.clone(),
+            })
+            .collect(),
+        tunnels: tunnels
+            .iter()
+            .map(|t| TrayTunnel {
+                id: t.definition.id.clone(),
+                name: t.definition.name.clone(),
+                running: t.running,
+            })
+            .collect(),
+        agent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::registry_service::HostMetadata;
+
+    fn host(favorite: bool, last_used: Option<i64>) -> HostMetadata {
+        let mut metadata = HostMetadata::new();
+        metadata.is_favorite = favorite;
+        metadata.last_used = last_used;
+        metadata
+    }
+
+    #[test]
+    fn test_build_model() {
+        let mut store = MetadataStore::default();
+        store.hosts.insert("web".to_string(), host(true, Some(50)));
+        store.hosts.insert("db".to_string(), host(true, None));
+        store.hosts.insert("api".to_string(), host(true, None));
+        store.hosts.get_mut("web").unwrap().pin_order = Some(0);
+        store.hosts.insert("old".to_string(), host(false, Some(10)));
+        store.hosts.insert("new".to_string(), host(false, Some(30)));
+        store.hosts.insert("never".to_string(), host(false, None));
+        store
+            .hosts
+            .insert("removed".to_string(), host(true, Some(99)));
+        let aliases: Vec<String> = ["web", "db", "api", "old", "new", "never"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+
+        let model = build_model(&store, &aliases, &[], TrayAgentState::Locked);
+        let names = |hosts: &[TrayHost]| hosts.iter().map(|h| h.alias.clone()).collect::<Vec<_>>();
+        // Reordered hosts first, then the rest by alias
+        assert_eq!(names(&model.pinned), vec!["web", "api", "db"]);
+        assert_eq!(names(&model.recent), vec!["new", "old"]);
+        assert_eq!(model.agent, TrayAgentState::Locked);
+    }
+
+    #[test]
+    fn test_tray_action_ids() {
+        let actions = [
+            TrayAction::Connect("web:8022".to_string()),
+            TrayAction::ToggleTunnel("1a2b".to_string()),
+            TrayAction::LockAgent,
+            TrayAction::UnlockAgent,
+            TrayAction::ShowWindow,
+            TrayAction::Quit,
+        ];
+        for action in actions {
+            assert_eq!(TrayAction::parse(&action.id()), Some(action));
+        }
+        assert_eq!(TrayAction::parse("separator"), None);
+    }
+}
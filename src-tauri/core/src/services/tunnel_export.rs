@@ -0,0 +1,845 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::terminal_launcher::find_program;
+use crate::services::tunnel_service::{TunnelDefinition, TunnelManager};
+use crate::utils::{app_data_path, ssh_client_options, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::fs;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// Timeout of one systemctl / launchctl / schtasks call
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Seconds between keepalives; ssh exits (and is restarted) after three go unanswered
+const KEEPALIVE_INTERVAL_SECS: u32 = 30;
+
+/// Seconds the service manager waits before restarting ssh
+const RESTART_DELAY_SECS: u32 = 10;
+
+/// Service manager a tunnel is exported to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceFormat {
+    /// systemd user unit (Linux)
+    Systemd,
+    /// LaunchAgent plist (macOS)
+    Launchd,
+    /// Task Scheduler task started at logon (Windows)
+    ScheduledTask,
+}
+
+impl ServiceFormat {
+    /// Format of the OS the app runs on
+    pub fn native() -> Option<Self> {
+        if cfg!(target_os = "linux") {
+            Some(ServiceFormat::Systemd)
+        } else if cfg!(target_os = "macos") {
+            Some(ServiceFormat::Launchd)
+        } else if cfg!(windows) {
+            Some(ServiceFormat::ScheduledTask)
+        } else {
+            None
+        }
+    }
+}
+
+/// A tunnel rendered for a service manager
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelServiceExport {
+    pub tunnel_id: String,
+    pub format: ServiceFormat,
+    /// Unit name, launchd label or task name
+    pub service_name: String,
+    /// Where the file is written
+    pub path: String,
+    pub content: String,
+    /// Commands run in order after writing the file
+    pub install_commands: Vec<Vec<String>>,
+    /// Commands run in order before removing the file
+    pub uninstall_commands: Vec<Vec<String>>,
+}
+
+/// Whether an exported tunnel is installed and running
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceState {
+    NotInstalled,
+    /// Installed but not running (failed, waiting to restart, not loaded)
+    Stopped,
+    Running,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelServiceStatus {
+    pub tunnel_id: String,
+    pub format: ServiceFormat,
+    pub state: ServiceState,
+    /// What the service manager reported (unit state, last exit status, task status)
+    pub detail: Option<String>,
+}
+
+/// Paths the files are rendered with
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExportEnvironment {
+    pub home: PathBuf,
+    pub ssh: String,
+    /// `-F` / known_hosts options of the active workspace and SSH root
+    pub ssh_options: Vec<String>,
+    /// Where the scheduled task XML is kept for schtasks
+    pub task_file: PathBuf,
+}
+
+/// Turns app-managed tunnels into services that run without the app
+/// ssh runs with BatchMode, so the host must authenticate without prompts (agent or
+/// unencrypted key)
+pub struct TunnelExportService;
+
+impl TunnelExportService {
+    async fn definition(id: &str) -> SshResult<TunnelDefinition> {
+        TunnelManager::list_definitions()
+            .await?
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Tunnel {} not found", id),
+            })
+    }
+
+    fn environment(tunnel_id: &str) -> SshResult<ExportEnvironment> {
+        let home = dirs::home_dir().ok_or_else(|| SshBuddyError::IoError {
+            message: "Home directory not found".to_string(),
+        })?;
+        let ssh = if cfg!(windows) {
+            find_program("ssh.exe")
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| r"C:\Windows\System32\OpenSSH\ssh.exe".to_string())
+        } else {
+            find_program("ssh")
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| "/usr/bin/ssh".to_string())
+        };
+        Ok(ExportEnvironment {
+            home,
+            ssh,
+            ssh_options: ssh_client_options(),
+            task_file: app_data_path(&format!("tunnel-{}.xml", tunnel_id))?,
+        })
+    }
+
+    /// Render a tunnel for a service manager (the native one when `format` is None)
+    pub async fn preview(
+        id: &str,
+        format: Option<ServiceFormat>,
+    ) -> SshResult<TunnelServiceExport> {
+        let definition = Self::definition(id).await?;
+        let format = format
+            .or_else(ServiceFormat::native)
+            .ok_or_else(unsupported)?;
+        render(&definition, format, &Self::environment(&definition.id)?)
+    }
+
+    /// Write the service file for this OS and start it
+    pub async fn install(id: &str) -> SshResult<TunnelServiceStatus> {
+        ReadOnlyMode::ensure_writable("install tunnel service")?;
+        let definition = Self::definition(id).await?;
+        if TunnelManager::global().is_running(id).await {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "Stop tunnel {} in the app first; both would listen on port {}",
+                    definition.name, definition.local_port
+                ),
+            });
+        }
+        let format = ServiceFormat::native().ok_or_else(unsupported)?;
+        let export = render(&definition, format, &Self::environment(&definition.id)?)?;
+
+        let path = PathBuf::from(&export.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = match format {
+            // schtasks reads task XML as UTF-16
+            ServiceFormat::ScheduledTask => utf16_with_bom(&export.content),
+            _ => export.content.clone().into_bytes(),
+        };
+        write_atomic(&path, &content).await?;
+
+        for argv in &export.install_commands {
+            let (success, stdout, stderr) = run(argv).await?;
+            if !success {
+                return Err(SshBuddyError::Unknown {
+                    message: format!(
+                        "{} failed: {}",
+                        argv.join(" "),
+                        if stderr.trim().is_empty() {
+                            stdout
+                        } else {
+                            stderr
+                        }
+                        .trim()
+                    ),
+                });
+            }
+        }
+        tracing::info!(
+            "[tunnel_export] Installed tunnel {} as {:?} service {}",
+            definition.id,
+            format,
+            export.service_name
+        );
+        Self::status(id).await
+    }
+
+    /// Stop the service and remove its file
+    pub async fn uninstall(id: &str) -> SshResult<TunnelServiceStatus> {
+        ReadOnlyMode::ensure_writable("uninstall tunnel service")?;
+        let definition = Self::definition(id).await?;
+        let format = ServiceFormat::native().ok_or_else(unsupported)?;
+        let export = render(&definition, format, &Self::environment(&definition.id)?)?;
+
+        // Stopping fails when it's already stopped or half-removed; carry on regardless
+        for argv in &export.uninstall_commands {
+            if let Ok((false, _, stderr)) = run(argv).await {
+                tracing::warn!(
+                    "[tunnel_export] {} failed: {}",
+                    argv.join(" "),
+                    stderr.trim()
+                );
+            }
+        }
+        let path = PathBuf::from(&export.path);
+        if path.exists() {
+            fs::remove_file(&path).await?;
+        }
+        if format == ServiceFormat::Systemd {
+            let _ = run(&systemctl(&["daemon-reload"])).await;
+        }
+
+        let status = Self::status(id).await?;
+        if status.state != ServiceState::NotInstalled {
+            return Err(SshBuddyError::Unknown {
+                message: format!(
+                    "Service {} is still installed: {}",
+                    export.service_name,
+                    status.detail.unwrap_or_default()
+                ),
+            });
+        }
+        tracing::info!(
+            "[tunnel_export] Uninstalled service {} of tunnel {}",
+            export.service_name,
+            definition.id
+        );
+        Ok(status)
+    }
+
+    /// Ask the service manager whether the exported tunnel is installed and running
+    pub async fn status(id: &str) -> SshResult<TunnelServiceStatus> {
+        let definition = Self::definition(id).await?;
+        let format = ServiceFormat::native().ok_or_else(unsupported)?;
+        let export = render(&definition, format, &Self::environment(&definition.id)?)?;
+        let (state, detail) = match format {
+            ServiceFormat::Systemd | ServiceFormat::Launchd
+                if !PathBuf::from(&export.path).exists() =>
+            {
+                (ServiceState::NotInstalled, None)
+            }
+            ServiceFormat::Systemd => {
+                let (_, stdout, _) =
+                    run(&systemctl(&["is-active", export.service_name.as_str()])).await?;
+                parse_systemctl_state(&stdout)
+            }
+            ServiceFormat::Launchd => {
+                let argv = ["launchctl", "list", export.service_name.as_str()].map(String::from);
+                match run(&argv).await? {
+                    (true, stdout, _) => parse_launchctl_list(&stdout),
+                    (false, _, _) => (ServiceState::Stopped, Some("not loaded".to_string())),
+                }
+            }
+            ServiceFormat::ScheduledTask => {
+                let argv = [
+                    "schtasks",
+                    "/Query",
+                    "/TN",
+                    export.service_name.as_str(),
+                    "/FO",
+                    "CSV",
+                    "/NH",
+                ]
+                .map(String::from);
+                match run(&argv).await? {
+                    (true, stdout, _) => parse_schtasks_query(&stdout),
+                    (false, _, _) => (ServiceState::NotInstalled, None),
+                }
+            }
+        };
+        Ok(TunnelServiceStatus {
+            tunnel_id: definition.id,
+            format,
+            state,
+            detail,
+        })
+    }
+}
+
+fn unsupported() -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: "Tunnel services aren't supported on this OS".to_string(),
+    }
+}
+
+/// Run a command, returning (success, stdout, stderr)
+async fn run(argv: &[String]) -> SshResult<(bool, String, String)> {
+    let (program, args) = argv.split_first().ok_or_else(|| SshBuddyError::Unknown {
+        message: "Empty command".to_string(),
+    })?;
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = timeout(COMMAND_TIMEOUT, command.output())
+        .await
+        .map_err(|_| SshBuddyError::Unknown {
+            message: format!("{} timed out", program),
+        })??;
+    Ok((
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    ))
+}
+
+fn systemctl(args: &[&str]) -> Vec<String> {
+    ["systemctl", "--user"]
+        .iter()
+        .chain(args)
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// `[host]` for IPv6 addresses in a -L spec
+fn forward_host(host: &str) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+/// ssh command line keeping the tunnel's forward open
+pub(crate) fn ssh_argv(
+    definition: &TunnelDefinition,
+    environment: &ExportEnvironment,
+) -> Vec<String> {
+    let mut argv = vec![environment.ssh.clone(), "-N".to_string()];
+    argv.extend(environment.ssh_options.iter().cloned());
+    for option in [
+        "BatchMode=yes".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+        format!("ServerAliveInterval={}", KEEPALIVE_INTERVAL_SECS),
+        "ServerAliveCountMax=3".to_string(),
+    ] {
+        argv.extend(["-o".to_string(), option]);
+    }
+    argv.extend([
+        "-L".to_string(),
+        format!(
+            "{}:{}:{}:{}",
+            forward_host(&definition.bind_address),
+            definition.local_port,
+            forward_host(&definition.remote_host),
+            definition.remote_port
+        ),
+        definition.host_alias.clone(),
+    ]);
+    argv
+}
+
+/// Render a tunnel for `format`
+pub(crate) fn render(
+    definition: &TunnelDefinition,
+    format: ServiceFormat,
+    environment: &ExportEnvironment,
+) -> SshResult<TunnelServiceExport> {
+    // The id ends up in file, unit and task names
+    if definition.id.is_empty()
+        || !definition
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "Tunnel id {} can't be used as a service name",
+                definition.id
+            ),
+        });
+    }
+    let argv = ssh_argv(definition, environment);
+    let export = match format {
+        ServiceFormat::Systemd => {
+            let unit = format!("ssh-buddy-tunnel-{}.service", definition.id);
+            TunnelServiceExport {
+                path: environment
+                    .home
+                    .join(".config/systemd/user")
+                    .join(&unit)
+                    .to_string_lossy()
+                    .to_string(),
+                content: systemd_unit(definition, &argv),
+                install_commands: vec![
+                    systemctl(&["daemon-reload"]),
+                    systemctl(&["enable", "--now", unit.as_str()]),
+                ],
+                uninstall_commands: vec![systemctl(&["disable", "--now", unit.as_str()])],
+                service_name: unit,
+                tunnel_id: definition.id.clone(),
+                format,
+            }
+        }
+        ServiceFormat::Launchd => {
+            let label = format!("com.ssh-buddy.tunnel.{}", definition.id);
+            let path = environment
+                .home
+                .join("Library/LaunchAgents")
+                .join(format!("{}.plist", label))
+                .to_string_lossy()
+                .to_string();
+            let log = environment
+                .home
+                .join("Library/Logs")
+                .join(format!("ssh-buddy-tunnel-{}.log", definition.id));
+            TunnelServiceExport {
+                content: launchd_plist(&label, &argv, &log.to_string_lossy()),
+                install_commands: vec![["launchctl", "load", "-w", path.as_str()]
+                    .map(String::from)
+                    .to_vec()],
+                uninstall_commands: vec![["launchctl", "unload", "-w", path.as_str()]
+                    .map(String::from)
+                    .to_vec()],
+                path,
+                service_name: label,
+                tunnel_id: definition.id.clone(),
+                format,
+            }
+        }
+        ServiceFormat::ScheduledTask => {
+            let task = format!(r"\SSH Buddy\Tunnel {}", definition.id);
+            let path = environment.task_file.to_string_lossy().to_string();
+            TunnelServiceExport {
+                content: task_xml(definition, &argv),
+                install_commands: vec![
+                    [
+                        "schtasks",
+                        "/Create",
+                        "/TN",
+                        task.as_str(),
+                        "/XML",
+                        path.as_str(),
+                        "/F",
+                    ]
+                    .map(String::from)
+                    .to_vec(),
+                    ["schtasks", "/Run", "/TN", task.as_str()]
+                        .map(String::from)
+                        .to_vec(),
+                ],
+                uninstall_commands: vec![
+                    ["schtasks", "/End", "/TN", task.as_str()]
+                        .map(String::from)
+                        .to_vec(),
+                    ["schtasks", "/Delete", "/TN", task.as_str(), "/F"]
+                        .map(String::from)
+                        .to_vec(),
+                ],
+                path,
+                service_name: task,
+                tunnel_id: definition.id.clone(),
+                format,
+            }
+        }
+    };
+    Ok(export)
+}
+
+/// Quote an ExecStart argument; `%` starts a specifier in unit files
+pub(crate) fn systemd_quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if arg.is_empty()
+        || arg
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '\\' || c == '\'')
+    {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg
+    }
+}
+
+fn systemd_unit(definition: &TunnelDefinition, argv: &[String]) -> String {
+    let exec = argv
+        .iter()
+        .map(|a| systemd_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "[Unit]\n\
+         Description=SSH Buddy tunnel {name} ({bind}:{port} to {host})\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={exec}\n\
+         Restart=always\n\
+         RestartSec={delay}\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        name = definition.name.replace(['\n', '\r'], " "),
+        bind = definition.bind_address,
+        port = definition.local_port,
+        host = definition.host_alias,
+        exec = exec,
+        delay = RESTART_DELAY_SECS
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn launchd_plist(label: &str, argv: &[String], log: &str) -> String {
+    let arguments = argv
+        .iter()
+        .map(|a| format!("        <string>{}</string>\n", xml_escape(a)))
+        .collect::<String>();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n    \
+             <key>Label</key>\n    \
+             <string>{label}</string>\n    \
+             <key>ProgramArguments</key>\n    \
+             <array>\n\
+         {arguments}    \
+             </array>\n    \
+             <key>RunAtLoad</key>\n    \
+             <true/>\n    \
+             <key>KeepAlive</key>\n    \
+             <true/>\n    \
+             <key>ThrottleInterval</key>\n    \
+             <integer>{delay}</integer>\n    \
+             <key>StandardErrorPath</key>\n    \
+             <string>{log}</string>\n\
+         </dict>\n\
+         </plist>\n",
+        label = xml_escape(label),
+        arguments = arguments,
+        delay = RESTART_DELAY_SECS,
+        log = xml_escape(log)
+    )
+}
+
+/// Quote an argument the way the MSVC runtime splits a command line
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+fn task_xml(definition: &TunnelDefinition, argv: &[String]) -> String {
+    let arguments = argv[1..]
+        .iter()
+        .map(|a| windows_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // RestartOnFailure covers ssh exiting after a dropped connection
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n\
+         <Task version=\"1.2\" xmlns=\"http://schemas.microsoft.com/windows/2004/02/mit/task\">\n  \
+           <RegistrationInfo>\n    \
+             <Description>SSH Buddy tunnel {name}</Description>\n  \
+           </RegistrationInfo>\n  \
+           <Triggers>\n    \
+             <LogonTrigger>\n      \
+               <Enabled>true</Enabled>\n    \
+             </LogonTrigger>\n  \
+           </Triggers>\n  \
+           <Principals>\n    \
+             <Principal id=\"Author\">\n      \
+               <LogonType>InteractiveToken</LogonType>\n      \
+               <RunLevel>LeastPrivilege</RunLevel>\n    \
+             </Principal>\n  \
+           </Principals>\n  \
+           <Settings>\n    \
+             <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>\n    \
+             <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>\n    \
+             <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>\n    \
+             <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>\n    \
+             <Hidden>true</Hidden>\n    \
+             <RestartOnFailure>\n      \
+               <Interval>PT1M</Interval>\n      \
+               <Count>999</Count>\n    \
+             </RestartOnFailure>\n  \
+           </Settings>\n  \
+           <Actions Context=\"Author\">\n    \
+             <Exec>\n      \
+               <Command>{command}</Command>\n      \
+               <Arguments>{arguments}</Arguments>\n    \
+             </Exec>\n  \
+           </Actions>\n\
+         </Task>\n",
+        name = xml_escape(&definition.name),
+        command = xml_escape(&argv[0]),
+        arguments = xml_escape(&arguments)
+    )
+}
+
+fn utf16_with_bom(text: &str) -> Vec<u8> {
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+    bytes
+}
+
+/// `systemctl is-active` prints "active", "inactive", "failed", "activating", ...
+fn parse_systemctl_state(stdout: &str) -> (ServiceState, Option<String>) {
+    let state = stdout.trim();
+    let service_state = if state == "active" {
+        ServiceState::Running
+    } else {
+        ServiceState::Stopped
+    };
+    (
+        service_state,
+        Some(state.to_string()).filter(|s| !s.is_empty()),
+    )
+}
+
+/// `launchctl list <label>` prints a plist-like dict with `"PID" = 123;` while running
+fn parse_launchctl_list(stdout: &str) -> (ServiceState, Option<String>) {
+    let field = |name: &str| {
+        stdout.lines().find_map(|line| {
+            let value = line.trim().strip_prefix(&format!("\"{}\" = ", name))?;
+            Some(value.trim_end_matches(';').trim().to_string())
+        })
+    };
+    match field("PID") {
+        Some(pid) => (ServiceState::Running, Some(format!("pid {}", pid))),
+        None => (
+            ServiceState::Stopped,
+            field("LastExitStatus").map(|status| format!("last exit status {}", status)),
+        ),
+    }
+}
+
+/// `schtasks /Query /FO CSV /NH` prints `"\SSH Buddy\Tunnel x","N/A","Running"`
+fn parse_schtasks_query(stdout: &str) -> (ServiceState, Option<String>) {
+    let status = stdout
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .and_then(|l| l.trim().rsplit(',').next())
+        .map(|s| s.trim_matches('"').to_string());
+    match status {
+        Some(status) if status == "Running" => (ServiceState::Running, Some(status)),
+        status => (ServiceState::Stopped, status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition() -> TunnelDefinition {
+        TunnelDefinition {
+            id: "1a2b3c4d".to_string(),
+            name: "Grafana".to_string(),
+            host_alias: "monitoring".to_string(),
+            bind_address: "127.0.0.1".to_string(),
+            local_port: 3000,
+            remote_host: "localhost".to_string(),
+            remote_port: 3000,
+            database: None,
+            http_check: None,
+            restore_on_launch: None,
+        }
+    }
+
+    fn environment() -> ExportEnvironment {
+        ExportEnvironment {
+            home: PathBuf::from("/home/dev"),
+            ssh: "/usr/bin/ssh".to_string(),
+            ssh_options: vec!["-F".to_string(), "/home/dev/My Configs/ssh".to_string()],
+            task_file: PathBuf::from("/data/tunnel-1a2b3c4d.xml"),
+        }
+    }
+
+    #[test]
+    fn test_ssh_argv() {
+        let argv = ssh_argv(&definition(), &environment());
+        assert_eq!(
+            argv[..4],
+            ["/usr/bin/ssh", "-N", "-F", "/home/dev/My Configs/ssh"]
+        );
+        assert!(argv.contains(&"BatchMode=yes".to_string()));
+        assert!(argv.contains(&"ExitOnForwardFailure=yes".to_string()));
+        assert_eq!(
+            argv[argv.len() - 2..],
+            ["-L", "127.0.0.1:3000:localhost:3000", "monitoring"]
+        );
+
+        let ipv6 = TunnelDefinition {
+            bind_address: "::1".to_string(),
+            remote_host: "fd00::5".to_string(),
+            ..definition()
+        };
+        assert!(ssh_argv(&ipv6, &environment()).contains(&"[::1]:3000:[fd00::5]:3000".to_string()));
+    }
+
+    #[test]
+    fn test_render_systemd() {
+        let export = render(&definition(), ServiceFormat::Systemd, &environment()).unwrap();
+        assert_eq!(export.service_name, "ssh-buddy-tunnel-1a2b3c4d.service");
+        assert_eq!(
+            export.path,
+            "/home/dev/.config/systemd/user/ssh-buddy-tunnel-1a2b3c4d.service"
+        );
+        assert!(export.content.contains(
+            "ExecStart=/usr/bin/ssh -N -F \"/home/dev/My Configs/ssh\" -o BatchMode=yes"
+        ));
+        assert!(export.content.contains("Restart=always\n"));
+        assert_eq!(
+            export.install_commands[1],
+            [
+                "systemctl",
+                "--user",
+                "enable",
+                "--now",
+                "ssh-buddy-tunnel-1a2b3c4d.service"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_launchd() {
+        let export = render(&definition(), ServiceFormat::Launchd, &environment()).unwrap();
+        assert_eq!(
+            export.path,
+            "/home/dev/Library/LaunchAgents/com.ssh-buddy.tunnel.1a2b3c4d.plist"
+        );
+        assert!(export
+            .content
+            .contains("<string>com.ssh-buddy.tunnel.1a2b3c4d</string>"));
+        assert!(export
+            .content
+            .contains("        <string>127.0.0.1:3000:localhost:3000</string>\n"));
+        assert!(export.content.contains("<key>KeepAlive</key>\n    <true/>"));
+        assert_eq!(export.install_commands[0][..3], ["launchctl", "load", "-w"]);
+    }
+
+    #[test]
+    fn test_render_scheduled_task() {
+        let environment = ExportEnvironment {
+            ssh: r"C:\Windows\System32\OpenSSH\ssh.exe".to_string(),
+            ssh_options: vec!["-F".to_string(), r"C:\Users\dev\My Configs\ssh".to_string()],
+            ..environment()
+        };
+        let export = render(&definition(), ServiceFormat::ScheduledTask, &environment).unwrap();
+        assert_eq!(export.service_name, r"\SSH Buddy\Tunnel 1a2b3c4d");
+        assert!(export
+            .content
+            .contains(r"<Command>C:\Windows\System32\OpenSSH\ssh.exe</Command>"));
+        assert!(export
+            .content
+            .contains(r"<Arguments>-N -F &quot;C:\Users\dev\My Configs\ssh&quot; -o"));
+        assert_eq!(utf16_with_bom("<")[..4], [0xFF, 0xFE, b'<', 0]);
+    }
+
+    #[test]
+    fn test_render_rejects_unsafe_id() {
+        let definition = TunnelDefinition {
+            id: "../x".to_string(),
+            ..definition()
+        };
+        assert!(render(&definition, ServiceFormat::Systemd, &environment()).is_err());
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(systemd_quote("50%"), "50%%");
+        assert_eq!(systemd_quote("a b"), "\"a b\"");
+        assert_eq!(windows_quote(r"C:\a b\"), r#""C:\a b\\""#);
+        assert_eq!(windows_quote("plain"), "plain");
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(
+            parse_systemctl_state("active\n"),
+            (ServiceState::Running, Some("active".to_string()))
+        );
+        assert_eq!(parse_systemctl_state("failed\n").0, ServiceState::Stopped);
+
+        let running = "{\n\t\"LimitLoadToSessionType\" = \"Aqua\";\n\t\"PID\" = 4242;\n\t\"LastExitStatus\" = 0;\n};\n";
+        assert_eq!(
+            parse_launchctl_list(running),
+            (ServiceState::Running, Some("pid 4242".to_string()))
+        );
+        let stopped = "{\n\t\"LastExitStatus\" = 65280;\n};\n";
+        assert_eq!(
+            parse_launchctl_list(stopped),
+            (
+                ServiceState::Stopped,
+                Some("last exit status 65280".to_string())
+            )
+        );
+
+        assert_eq!(
+            parse_schtasks_query("\r\n\"\\SSH Buddy\\Tunnel 1a2b3c4d\",\"N/A\",\"Running\"\r\n").0,
+            ServiceState::Running
+        );
+        assert_eq!(
+            parse_schtasks_query("\"\\SSH Buddy\\Tunnel 1a2b3c4d\",\"N/A\",\"Ready\"\r\n"),
+            (ServiceState::Stopped, Some("Ready".to_string()))
+        );
+    }
+}
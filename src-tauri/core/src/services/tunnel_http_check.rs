@@ -0,0 +1,215 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::registry_service::now_millis;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Timeout of one request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shortest allowed time between checks
+const MIN_INTERVAL_SECS: u64 = 5;
+
+/// HTTP(S) request made through the local end of a tunnel to see whether the app
+/// behind it answers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TunnelHttpCheck {
+    pub https: bool,
+    /// Request path, e.g. "/healthz"
+    pub path: String,
+    /// Redirects aren't followed, so a login redirect counts as its 3xx status
+    pub expected_status: u16,
+    /// Off for self-signed certificates, or ones not issued for the local address
+    pub verify_tls: bool,
+    pub interval_secs: u64,
+}
+
+impl Default for TunnelHttpCheck {
+    fn default() -> Self {
+        Self {
+            https: false,
+            path: "/".to_string(),
+            expected_status: 200,
+            verify_tls: true,
+            interval_secs: 30,
+        }
+    }
+}
+
+impl TunnelHttpCheck {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        let invalid = |message: &str| {
+            Err(SshBuddyError::InvalidOption {
+                message: message.to_string(),
+            })
+        };
+        if !self.path.starts_with('/')
+            || self
+                .path
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_control())
+        {
+            return invalid("Health check path must start with / and contain no spaces");
+        }
+        if !(100..=599).contains(&self.expected_status) {
+            return invalid("Expected status must be between 100 and 599");
+        }
+        if self.interval_secs < MIN_INTERVAL_SECS {
+            return invalid("Health checks can run at most every 5 seconds");
+        }
+        Ok(())
+    }
+
+    /// URL of the check on the tunnel's local end
+    pub fn url(&self, bind_address: &str, local_port: u16) -> String {
+        // A tunnel listening on every interface is reachable on loopback
+        let host = match bind_address {
+            "0.0.0.0" | "" => "127.0.0.1".to_string(),
+            "::" => "[::1]".to_string(),
+            address if address.contains(':') => format!("[{}]", address),
+            address => address.to_string(),
+        };
+        let scheme = if self.https { "https" } else { "http" };
+        format!("{}://{}:{}{}", scheme, host, local_port, self.path)
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(MIN_INTERVAL_SECS))
+    }
+}
+
+/// Outcome of the latest check
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpCheckResult {
+    /// Unix milliseconds
+    pub checked_at: i64,
+    /// The expected status came back
+    pub healthy: bool,
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    /// Why no response came back (connection refused, TLS, timeout)
+    pub error: Option<String>,
+}
+
+/// Request the check's URL once
+pub async fn probe(check: &TunnelHttpCheck, url: &str) -> HttpCheckResult {
+    let started = Instant::now();
+    let response = async {
+        // The local end of the tunnel must never go through the app's proxy
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .no_proxy()
+            .redirect(reqwest::redirect::Policy::none())
+            .danger_accept_invalid_certs(!check.verify_tls)
+            .build()?;
+        client.get(url).send().await
+    }
+    .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match response {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            HttpCheckResult {
+                checked_at: now_millis(),
+                healthy: status == check.expected_status,
+                status_code: Some(status),
+                latency_ms,
+                error: None,
+            }
+        }
+        Err(e) => HttpCheckResult {
+            checked_at: now_millis(),
+            healthy: false,
+            status_code: None,
+            latency_ms,
+            error: Some(error_text(&e)),
+        },
+    }
+}
+
+/// reqwest's outermost message ("error sending request") hides the cause
+fn error_text(error: &reqwest::Error) -> String {
+    let mut text = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        text.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer one request with `response` and return the port
+    async fn serve_once(response: &'static [u8]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let _ = socket.write_all(response).await;
+        });
+        port
+    }
+
+    #[test]
+    fn test_url_and_validate() {
+        let check = TunnelHttpCheck {
+            path: "/healthz".to_string(),
+            ..TunnelHttpCheck::default()
+        };
+        assert!(check.validate().is_ok());
+        assert_eq!(check.url("0.0.0.0", 8080), "http://127.0.0.1:8080/healthz");
+        assert_eq!(
+            TunnelHttpCheck {
+                https: true,
+                ..check.clone()
+            }
+            .url("::1", 8443),
+            "https://[::1]:8443/healthz"
+        );
+
+        for invalid in [
+            TunnelHttpCheck {
+                path: "healthz".to_string(),
+                ..check.clone()
+            },
+            TunnelHttpCheck {
+                expected_status: 42,
+                ..check.clone()
+            },
+            TunnelHttpCheck {
+                interval_secs: 1,
+                ..check
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe() {
+        let check = TunnelHttpCheck::default();
+        let port =
+            serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .await;
+        let result = probe(&check, &check.url("127.0.0.1", port)).await;
+        assert!(result.healthy);
+        assert_eq!(result.status_code, Some(200));
+
+        // Tunnel up, app down: the remote end answers 502
+        let port = serve_once(
+            b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        let result = probe(&check, &check.url("127.0.0.1", port)).await;
+        assert!(!result.healthy);
+        assert_eq!(result.status_code, Some(502));
+    }
+}
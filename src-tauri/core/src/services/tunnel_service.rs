@@ -0,0 +1,500 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::auth_prompt::AuthPrompter;
+use crate::services::db_handoff::TunnelDatabase;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::now_millis;
+use crate::services::ssh_connection::{RemoteSession, SshConnectionService};
+use crate::services::tunnel_http_check::{self, HttpCheckResult, TunnelHttpCheck};
+use crate::utils::{workspace_data_path, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::fs;
+use tokio::net::TcpListener;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinSet;
+
+/// How often a running tunnel checks that its SSH connection is still alive
+const HEALTH_INTERVAL: Duration = Duration::from_secs(2);
+
+fn default_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+/// Local port forward (`ssh -L bind:local_port:remote_host:remote_port host`) managed by the app
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelDefinition {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub host_alias: String,
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    pub local_port: u16,
+    /// Destination as seen from the SSH server
+    pub remote_host: String,
+    pub remote_port: u16,
+    /// Login handed to database clients when the destination is a database
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<TunnelDatabase>,
+    /// HTTP(S) check of the app behind a tunnel to a web service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_check: Option<TunnelHttpCheck>,
+    /// Overrides the global restore-on-launch setting for this tunnel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restore_on_launch: Option<bool>,
+}
+
+/// tunnels.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TunnelStore {
+    #[serde(default)]
+    tunnels: Vec<TunnelDefinition>,
+}
+
+/// Tunnel definition with its runtime state
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelStatus {
+    #[serde(flatten)]
+    pub definition: TunnelDefinition,
+    pub running: bool,
+    /// Unix timestamp in milliseconds
+    pub started_at: Option<i64>,
+    /// Connections forwarded since the tunnel started (HTTP checks included)
+    pub connections: u64,
+    /// Latest HTTP check, telling "tunnel up but app down" apart
+    pub http: Option<HttpCheckResult>,
+}
+
+/// Tunnel lifecycle event
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TunnelEvent {
+    Started {
+        id: String,
+        name: String,
+    },
+    Stopped {
+        id: String,
+        name: String,
+    },
+    /// The tunnel ended without being stopped
+    #[serde(rename_all = "camelCase")]
+    Dropped {
+        id: String,
+        name: String,
+        host_alias: String,
+        reason: String,
+    },
+}
+
+struct RunningTunnel {
+    stop: oneshot::Sender<()>,
+    started_at: i64,
+    connections: Arc<AtomicU64>,
+    http: Arc<std::sync::Mutex<Option<HttpCheckResult>>>,
+}
+
+/// Stores tunnel definitions and runs them over in-process SSH sessions
+pub struct TunnelManager {
+    running: Mutex<HashMap<String, RunningTunnel>>,
+}
+
+impl TunnelManager {
+    /// Global manager
+    pub fn global() -> &'static TunnelManager {
+        static MANAGER: OnceLock<TunnelManager> = OnceLock::new();
+        MANAGER.get_or_init(|| TunnelManager {
+            running: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn get_store_path() -> SshResult<PathBuf> {
+        workspace_data_path("tunnels.json")
+    }
+
+    async fn load_store() -> SshResult<TunnelStore> {
+        let path = Self::get_store_path()?;
+        if !path.exists() {
+            return Ok(TunnelStore::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse tunnels: {}", e),
+        })
+    }
+
+    async fn save_store(store: &TunnelStore) -> SshResult<()> {
+        let path = Self::get_store_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(store).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&path, content.as_bytes()).await
+    }
+
+    /// Saved tunnel definitions
+    pub async fn list_definitions() -> SshResult<Vec<TunnelDefinition>> {
+        Ok(Self::load_store().await?.tunnels)
+    }
+
+    /// Add or replace a tunnel definition (matched by id)
+    pub async fn save_definition(mut definition: TunnelDefinition) -> SshResult<TunnelDefinition> {
+        ReadOnlyMode::ensure_writable("save tunnel")?;
+        if definition.id.trim().is_empty() {
+            definition.id = format!("{:08x}", rand::random::<u32>());
+        }
+        definition.bind_address = definition.bind_address.trim().to_string();
+        if definition.bind_address.is_empty() {
+            definition.bind_address = default_bind_address();
+        }
+
+        let mut store = Self::load_store().await?;
+        validate_definition(&definition, &store.tunnels)?;
+        match store.tunnels.iter_mut().find(|t| t.id == definition.id) {
+            Some(existing) => *existing = definition.clone(),
+            None => store.tunnels.push(definition.clone()),
+        }
+        Self::save_store(&store).await?;
+        tracing::info!(
+            "[tunnel_service] Saved tunnel {} ({})",
+            definition.id,
+            definition.name
+        );
+        Ok(definition)
+    }
+
+    /// Stop and remove a tunnel definition
+    pub async fn delete_definition(&self, id: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete tunnel")?;
+        self.stop(id).await;
+        let mut store = Self::load_store().await?;
+        store.tunnels.retain(|t| t.id != id);
+        Self::save_store(&store).await?;
+        tracing::info!("[tunnel_service] Deleted tunnel {}", id);
+        Ok(())
+    }
+
+    /// All tunnel definitions with their runtime state
+    pub async fn status(&self) -> SshResult<Vec<TunnelStatus>> {
+        let definitions = Self::list_definitions().await?;
+        let running = self.running.lock().await;
+        Ok(definitions
+            .into_iter()
+            .map(|definition| {
+                let state = running.get(&definition.id);
+                TunnelStatus {
+                    running: state.is_some(),
+                    started_at: state.map(|s| s.started_at),
+                    connections: state.map_or(0, |s| s.connections.load(Ordering::Relaxed)),
+                    http: state.and_then(|s| s.http.lock().ok().and_then(|h| h.clone())),
+                    definition,
+                }
+            })
+            .collect())
+    }
+
+    /// Whether a tunnel is running
+    pub async fn is_running(&self, id: &str) -> bool {
+        self.running.lock().await.contains_key(id)
+    }
+
+    /// Start a tunnel (no-op when it's already running)
+    /// Started/stopped/dropped events are reported through `on_event`
+    pub async fn start<F>(
+        &'static self,
+        id: &str,
+        prompter: Option<&dyn AuthPrompter>,
+        on_event: F,
+    ) -> SshResult<()>
+    where
+        F: Fn(TunnelEvent) + Send + Sync + 'static,
+    {
+        if self.is_running(id).await {
+            return Ok(());
+        }
+        let definition = Self::list_definitions()
+            .await?
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Tunnel {} not found", id),
+            })?;
+
+        let listener = TcpListener::bind((definition.bind_address.as_str(), definition.local_port))
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!(
+                    "Failed to listen on {}:{}: {}",
+                    definition.bind_address, definition.local_port, e
+                ),
+            })?;
+        let session = SshConnectionService::open_session(&definition.host_alias, prompter).await?;
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let connections = Arc::new(AtomicU64::new(0));
+        let http = Arc::new(std::sync::Mutex::new(None));
+        {
+            let mut running = self.running.lock().await;
+            if running.contains_key(id) {
+                // Started concurrently
+                drop(running);
+                session.close().await;
+                return Ok(());
+            }
+            running.insert(
+                definition.id.clone(),
+                RunningTunnel {
+                    stop: stop_tx,
+                    started_at: now_millis(),
+                    connections: connections.clone(),
+                    http: http.clone(),
+                },
+            );
+        }
+        tracing::info!(
+            "[tunnel_service] Started tunnel {} ({}:{} -> {}:{} via {})",
+            definition.id,
+            definition.bind_address,
+            definition.local_port,
+            definition.remote_host,
+            definition.remote_port,
+            definition.host_alias
+        );
+        on_event(TunnelEvent::Started {
+            id: definition.id.clone(),
+            name: definition.name.clone(),
+        });
+
+        // Runs beside `serve`, whose listener it connects to
+        let http_checks = definition.http_check.clone().map(|check| {
+            let url = check.url(&definition.bind_address, definition.local_port);
+            let id = definition.id.clone();
+            tokio::spawn(run_http_checks(id, check, url, http))
+        });
+
+        tokio::spawn(async move {
+            let failure = serve(&definition, listener, &session, stop_rx, &connections).await;
+            if let Some(task) = http_checks {
+                task.abort();
+            }
+            session.close().await;
+            {
+                // Already removed by `stop`, possibly restarted since
+                let mut running = self.running.lock().await;
+                if running
+                    .get(&definition.id)
+                    .is_some_and(|t| Arc::ptr_eq(&t.connections, &connections))
+                {
+                    running.remove(&definition.id);
+                }
+            }
+            let TunnelDefinition {
+                id,
+                name,
+                host_alias,
+                ..
+            } = definition;
+            match failure {
+                None => {
+                    tracing::info!("[tunnel_service] Stopped tunnel {}", id);
+                    on_event(TunnelEvent::Stopped { id, name });
+                }
+                Some(reason) => {
+                    tracing::warn!("[tunnel_service] Tunnel {} dropped: {}", id, reason);
+                    on_event(TunnelEvent::Dropped {
+                        id,
+                        name,
+                        host_alias,
+                        reason,
+                    });
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Stop a running tunnel; false when it wasn't running
+    pub async fn stop(&self, id: &str) -> bool {
+        match self.running.lock().await.remove(id) {
+            Some(tunnel) => {
+                let _ = tunnel.stop.send(());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Check the app behind a tunnel until aborted, keeping the latest result in `latest`
+async fn run_http_checks(
+    id: String,
+    check: TunnelHttpCheck,
+    url: String,
+    latest: Arc<std::sync::Mutex<Option<HttpCheckResult>>>,
+) {
+    let mut interval = tokio::time::interval(check.interval());
+    loop {
+        interval.tick().await;
+        let result = tunnel_http_check::probe(&check, &url).await;
+        let Ok(mut latest) = latest.lock() else {
+            return;
+        };
+        if latest.as_ref().map(|r| r.healthy) != Some(result.healthy) {
+            tracing::info!(
+                "[tunnel_service] {} of tunnel {}: {}",
+                url,
+                id,
+                match (&result.status_code, &result.error) {
+                    (_, Some(error)) => error.clone(),
+                    (Some(status), None) => format!("HTTP {}", status),
+                    (None, None) => "no response".to_string(),
+                }
+            );
+        }
+        *latest = Some(result);
+    }
+}
+
+/// Accept local connections until stopped; returns why the tunnel failed otherwise
+async fn serve(
+    definition: &TunnelDefinition,
+    listener: TcpListener,
+    session: &RemoteSession,
+    mut stop: oneshot::Receiver<()>,
+    connections: &AtomicU64,
+) -> Option<String> {
+    let lost = || format!("Connection to {} lost", definition.host_alias);
+    // Dropping the set on return closes the forwarded connections
+    let mut forwards = JoinSet::new();
+    let mut health = tokio::time::interval(HEALTH_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut stop => return None,
+            _ = health.tick() => {
+                if session.is_closed() {
+                    return Some(lost());
+                }
+            }
+            accepted = listener.accept() => {
+                let (mut socket, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => return Some(format!("Local listener failed: {}", e)),
+                };
+                match session
+                    .open_direct_tcpip(&definition.remote_host, definition.remote_port, peer)
+                    .await
+                {
+                    Ok(mut channel) => {
+                        connections.fetch_add(1, Ordering::Relaxed);
+                        forwards.spawn(async move {
+                            let _ = tokio::io::copy_bidirectional(&mut socket, &mut channel).await;
+                        });
+                    }
+                    Err(_) if session.is_closed() => return Some(lost()),
+                    Err(e) => tracing::warn!(
+                        "[tunnel_service] Forward to {}:{} refused: {}",
+                        definition.remote_host,
+                        definition.remote_port,
+                        e
+                    ),
+                }
+            }
+            Some(_) = forwards.join_next(), if !forwards.is_empty() => {}
+        }
+    }
+}
+
+fn validate_definition(
+    definition: &TunnelDefinition,
+    existing: &[TunnelDefinition],
+) -> SshResult<()> {
+    let invalid = |message: String| Err(SshBuddyError::InvalidOption { message });
+    if definition.name.trim().is_empty() {
+        return invalid("Tunnel name is required".to_string());
+    }
+    if definition.host_alias.trim().is_empty() {
+        return invalid("Tunnel host is required".to_string());
+    }
+    if definition.remote_host.trim().is_empty() {
+        return invalid("Tunnel destination host is required".to_string());
+    }
+    if definition.local_port == 0 || definition.remote_port == 0 {
+        return invalid("Tunnel ports must be between 1 and 65535".to_string());
+    }
+    if let Some(check) = &definition.http_check {
+        check.validate()?;
+    }
+    if let Some(other) = existing.iter().find(|t| {
+        t.id != definition.id
+            && t.local_port == definition.local_port
+            && t.bind_address == definition.bind_address
+    }) {
+        return invalid(format!(
+            "Local port {} is already used by tunnel \"{}\"",
+            definition.local_port, other.name
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tunnel(id: &str, local_port: u16) -> TunnelDefinition {
+        TunnelDefinition {
+            id: id.to_string(),
+            name: format!("tunnel {}", id),
+            host_alias: "bastion".to_string(),
+            bind_address: default_bind_address(),
+            local_port,
+            remote_host: "db.internal".to_string(),
+            remote_port: 5432,
+            database: None,
+            http_check: None,
+            restore_on_launch: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_definition() {
+        let existing = vec![tunnel("a", 15432)];
+        assert!(validate_definition(&tunnel("a", 15432), &existing).is_ok());
+        assert!(validate_definition(&tunnel("b", 15433), &existing).is_ok());
+
+        let err = validate_definition(&tunnel("b", 15432), &existing).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("already used by tunnel \"tunnel a\""));
+
+        let mut other_address = tunnel("b", 15432);
+        other_address.bind_address = "0.0.0.0".to_string();
+        assert!(validate_definition(&other_address, &existing).is_ok());
+
+        let mut no_port = tunnel("c", 0);
+        assert!(validate_definition(&no_port, &existing).is_err());
+        no_port.local_port = 1;
+        no_port.remote_host = " ".to_string();
+        assert!(validate_definition(&no_port, &existing).is_err());
+    }
+
+    #[test]
+    fn test_definition_defaults() {
+        let definition: TunnelDefinition = serde_json::from_str(
+            r#"{"name":"db","hostAlias":"bastion","localPort":15432,"remoteHost":"localhost","remotePort":5432}"#,
+        )
+        .unwrap();
+        assert_eq!(definition.bind_address, "127.0.0.1");
+        assert!(definition.id.is_empty());
+    }
+}
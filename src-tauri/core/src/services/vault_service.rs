@@ -0,0 +1,524 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::settings_service::SettingsService;
+use crate::utils::{
+    decrypt_with_key, derive_key, encrypt_with_key, generate_salt, workspace_data_dir,
+    write_atomic, KdfParams, SealedValue, KEY_LEN,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::fs;
+use tokio::sync::Mutex;
+
+/// Vault file format version
+const VAULT_VERSION: u32 = 1;
+
+/// Known plaintext sealed with the vault key to check the master password
+const CHECK_VALUE: &[u8] = b"ssh-buddy-vault";
+
+/// How often the auto-lock watcher wakes up
+const WATCH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Wall clock running this far ahead of the monotonic clock means the machine was suspended
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Longest allowed idle timeout (one day)
+const MAX_AUTO_LOCK_MINUTES: u32 = 24 * 60;
+
+type VaultKey = [u8; KEY_LEN];
+
+/// Security settings (vault key derivation and auto-lock)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SecuritySettings {
+    /// Used when the vault is created or its master password changes
+    pub kdf: KdfParams,
+    /// Lock after this many idle minutes (0 = never)
+    pub auto_lock_minutes: u32,
+    pub lock_on_suspend: bool,
+}
+
+impl SecuritySettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        self.kdf.validate()?;
+        if self.auto_lock_minutes > MAX_AUTO_LOCK_MINUTES {
+            return Err(SshBuddyError::InvalidOption {
+                message: format!(
+                    "Auto-lock timeout must be at most {} minutes",
+                    MAX_AUTO_LOCK_MINUTES
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for SecuritySettings {
+    fn default() -> Self {
+        Self {
+            kdf: KdfParams::default(),
+            auto_lock_minutes: 15,
+            lock_on_suspend: true,
+        }
+    }
+}
+
+/// Vault status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub exists: bool,
+    pub unlocked: bool,
+    pub entry_count: usize,
+    pub auto_lock_minutes: u32,
+    pub lock_on_suspend: bool,
+}
+
+/// Why the vault was locked
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LockReason {
+    Manual,
+    Idle,
+    Suspend,
+    /// Another workspace became active
+    Workspace,
+}
+
+/// vault.json contents (entry names are plain, values are sealed)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    check: SealedValue,
+    #[serde(default)]
+    entries: BTreeMap<String, SealedValue>,
+}
+
+impl VaultFile {
+    /// Empty vault sealed with a key
+    fn new(key: &VaultKey, salt: &[u8], kdf: KdfParams) -> SshResult<Self> {
+        Ok(Self {
+            version: VAULT_VERSION,
+            kdf,
+            salt: base64::engine::general_purpose::STANDARD.encode(salt),
+            check: encrypt_with_key(key, CHECK_VALUE)?,
+            entries: BTreeMap::new(),
+        })
+    }
+
+    fn salt_bytes(&self) -> SshResult<Vec<u8>> {
+        base64::engine::general_purpose::STANDARD
+            .decode(&self.salt)
+            .map_err(|_| SshBuddyError::Unknown {
+                message: "Invalid vault salt".to_string(),
+            })
+    }
+
+    /// Check a key against the sealed check value
+    fn verify(&self, key: &VaultKey) -> bool {
+        decrypt_with_key(key, &self.check).is_ok_and(|v| v == CHECK_VALUE)
+    }
+
+    /// Re-encrypt every entry under a new key
+    fn rekey(
+        &self,
+        old_key: &VaultKey,
+        new_key: &VaultKey,
+        new_salt: &[u8],
+        kdf: KdfParams,
+    ) -> SshResult<Self> {
+        let mut rekeyed = Self::new(new_key, new_salt, kdf)?;
+        for (name, sealed) in &self.entries {
+            let value = decrypt_with_key(old_key, sealed)?;
+            rekeyed
+                .entries
+                .insert(name.clone(), encrypt_with_key(new_key, &value)?);
+        }
+        Ok(rekeyed)
+    }
+}
+
+/// Key of the unlocked vault (only ever kept in memory)
+struct Session {
+    key: VaultKey,
+    last_activity: Instant,
+}
+
+/// Encrypted vault for secrets like passwords and passphrases
+pub struct VaultService {
+    session: Mutex<Option<Session>>,
+}
+
+impl VaultService {
+    fn new() -> Self {
+        Self {
+            session: Mutex::new(None),
+        }
+    }
+
+    /// Get the process-wide vault
+    pub fn global() -> &'static VaultService {
+        static VAULT: OnceLock<VaultService> = OnceLock::new();
+        VAULT.get_or_init(VaultService::new)
+    }
+
+    /// Each workspace has its own vault
+    fn get_app_dir() -> SshResult<PathBuf> {
+        workspace_data_dir()
+    }
+
+    /// Load security settings (defaults if missing)
+    pub async fn load_settings() -> SshResult<SecuritySettings> {
+        Ok(SettingsService::get().security)
+    }
+
+    /// Save security settings (new KDF parameters apply at the next password change)
+    pub async fn save_settings(settings: &SecuritySettings) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("change security settings")?;
+        settings.validate()?;
+        SettingsService::update(|current| {
+            current.security = settings.clone();
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn load_file() -> SshResult<VaultFile> {
+        let path = Self::get_app_dir()?.join("vault.json");
+        if !path.exists() {
+            return Err(SshBuddyError::VaultNotFound);
+        }
+
+        let content = fs::read_to_string(&path)
+            .await
+            .map_err(|e| SshBuddyError::IoError {
+                message: format!("Failed to read vault: {}", e),
+            })?;
+        serde_json::from_str(&content).map_err(|e| SshBuddyError::IoError {
+            message: format!("Failed to parse vault: {}", e),
+        })
+    }
+
+    async fn save_file(file: &VaultFile) -> SshResult<()> {
+        let dir = Self::get_app_dir()?;
+        fs::create_dir_all(&dir).await?;
+        let content = serde_json::to_string_pretty(file).map_err(|e| SshBuddyError::Unknown {
+            message: e.to_string(),
+        })?;
+        write_atomic(&dir.join("vault.json"), content.as_bytes()).await
+    }
+
+    /// Argon2id is deliberately slow; keep it off the async workers
+    async fn derive(password: &str, salt: Vec<u8>, kdf: KdfParams) -> SshResult<VaultKey> {
+        let password = password.to_string();
+        tokio::task::spawn_blocking(move || derive_key(&password, &salt, &kdf))
+            .await
+            .map_err(|e| SshBuddyError::Unknown {
+                message: e.to_string(),
+            })?
+    }
+
+    fn check_new_password(password: &str) -> SshResult<()> {
+        if password.is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Master password must not be empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Get vault status
+    pub async fn status(&self) -> SshResult<VaultStatus> {
+        let settings = Self::load_settings().await?;
+        let entry_count = match Self::load_file().await {
+            Ok(file) => Some(file.entries.len()),
+            Err(SshBuddyError::VaultNotFound) => None,
+            Err(e) => return Err(e),
+        };
+
+        Ok(VaultStatus {
+            exists: entry_count.is_some(),
+            unlocked: self.session.lock().await.is_some(),
+            entry_count: entry_count.unwrap_or(0),
+            auto_lock_minutes: settings.auto_lock_minutes,
+            lock_on_suspend: settings.lock_on_suspend,
+        })
+    }
+
+    /// Create an empty vault and unlock it
+    pub async fn create(&self, master_password: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("create vault")?;
+        Self::check_new_password(master_password)?;
+        let mut session = self.session.lock().await;
+        match Self::load_file().await {
+            Err(SshBuddyError::VaultNotFound) => {}
+            Ok(_) => {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "Vault already exists".to_string(),
+                })
+            }
+            Err(e) => return Err(e),
+        }
+
+        let settings = Self::load_settings().await?;
+        let salt = generate_salt();
+        let key = Self::derive(master_password, salt.to_vec(), settings.kdf).await?;
+        Self::save_file(&VaultFile::new(&key, &salt, settings.kdf)?).await?;
+
+        *session = Some(Session {
+            key,
+            last_activity: Instant::now(),
+        });
+        tracing::info!("[vault_service] Vault created");
+        Ok(())
+    }
+
+    /// Unlock the vault with the master password
+    pub async fn unlock(&self, master_password: &str) -> SshResult<()> {
+        let file = Self::load_file().await?;
+        let key = Self::derive(master_password, file.salt_bytes()?, file.kdf).await?;
+        if !file.verify(&key) {
+            return Err(SshBuddyError::InvalidMasterPassword);
+        }
+
+        *self.session.lock().await = Some(Session {
+            key,
+            last_activity: Instant::now(),
+        });
+        tracing::info!("[vault_service] Vault unlocked");
+        Ok(())
+    }
+
+    /// Lock the vault (drops the key); returns whether it was unlocked
+    pub async fn lock(&self, reason: LockReason) -> bool {
+        let was_unlocked = self.session.lock().await.take().is_some();
+        if was_unlocked {
+            tracing::info!("[vault_service] Vault locked ({:?})", reason);
+        }
+        was_unlocked
+    }
+
+    /// Key of the unlocked vault; counts as activity for auto-lock
+    fn active_key(session: &mut Option<Session>) -> SshResult<VaultKey> {
+        let session = session.as_mut().ok_or(SshBuddyError::VaultLocked)?;
+        session.last_activity = Instant::now();
+        Ok(session.key)
+    }
+
+    /// List entry names
+    pub async fn list_entries(&self) -> SshResult<Vec<String>> {
+        let mut session = self.session.lock().await;
+        Self::active_key(&mut session)?;
+        Ok(Self::load_file().await?.entries.into_keys().collect())
+    }
+
+    /// Read an entry
+    pub async fn get_entry(&self, name: &str) -> SshResult<String> {
+        let mut session = self.session.lock().await;
+        let key = Self::active_key(&mut session)?;
+        let file = Self::load_file().await?;
+        let sealed = file
+            .entries
+            .get(name)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Vault entry not found: {}", name),
+            })?;
+
+        String::from_utf8(decrypt_with_key(&key, sealed)?).map_err(|_| SshBuddyError::Unknown {
+            message: format!("Vault entry is not valid UTF-8: {}", name),
+        })
+    }
+
+    /// Create or replace an entry
+    pub async fn set_entry(&self, name: &str, value: &str) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("modify vault")?;
+        if name.trim().is_empty() {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Vault entry name must not be empty".to_string(),
+            });
+        }
+
+        let mut session = self.session.lock().await;
+        let key = Self::active_key(&mut session)?;
+        let mut file = Self::load_file().await?;
+        file.entries
+            .insert(name.to_string(), encrypt_with_key(&key, value.as_bytes())?);
+        Self::save_file(&file).await
+    }
+
+    /// Delete an entry; returns whether it existed
+    pub async fn delete_entry(&self, name: &str) -> SshResult<bool> {
+        ReadOnlyMode::ensure_writable("modify vault")?;
+        let mut session = self.session.lock().await;
+        Self::active_key(&mut session)?;
+        let mut file = Self::load_file().await?;
+        let existed = file.entries.remove(name).is_some();
+        if existed {
+            Self::save_file(&file).await?;
+        }
+        Ok(existed)
+    }
+
+    /// Change the master password, re-encrypting every entry with the current KDF settings
+    pub async fn change_master_password(
+        &self,
+        current_password: &str,
+        new_password: &str,
+    ) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("change master password")?;
+        Self::check_new_password(new_password)?;
+        let mut session = self.session.lock().await;
+
+        let file = Self::load_file().await?;
+        let old_key = Self::derive(current_password, file.salt_bytes()?, file.kdf).await?;
+        if !file.verify(&old_key) {
+            return Err(SshBuddyError::InvalidMasterPassword);
+        }
+
+        let settings = Self::load_settings().await?;
+        let new_salt = generate_salt();
+        let new_key = Self::derive(new_password, new_salt.to_vec(), settings.kdf).await?;
+        let rekeyed = file.rekey(&old_key, &new_key, &new_salt, settings.kdf)?;
+        Self::save_file(&rekeyed).await?;
+
+        *session = Some(Session {
+            key: new_key,
+            last_activity: Instant::now(),
+        });
+        tracing::info!(
+            "[vault_service] Master password changed ({} entries re-encrypted)",
+            rekeyed.entries.len()
+        );
+        Ok(())
+    }
+
+    /// Watch for idle timeout and system suspend, locking the vault when either happens
+    /// Runs for the lifetime of the app; `on_lock` is called after an automatic lock
+    pub async fn run_auto_lock<F>(&'static self, on_lock: F)
+    where
+        F: Fn(LockReason) + Send + Sync + 'static,
+    {
+        let mut last_wall = SystemTime::now();
+        let mut last_mono = Instant::now();
+
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+
+            let wall_elapsed = SystemTime::now()
+                .duration_since(last_wall)
+                .unwrap_or_default();
+            let mono_elapsed = last_mono.elapsed();
+            last_wall = SystemTime::now();
+            last_mono = Instant::now();
+
+            let settings = match Self::load_settings().await {
+                Ok(settings) => settings,
+                Err(e) => {
+                    tracing::warn!("[vault_service] Using default security settings: {}", e);
+                    SecuritySettings::default()
+                }
+            };
+
+            let reason = {
+                let session = self.session.lock().await;
+                let Some(session) = session.as_ref() else {
+                    continue;
+                };
+                if settings.lock_on_suspend && was_suspended(wall_elapsed, mono_elapsed) {
+                    Some(LockReason::Suspend)
+                } else if is_idle(session.last_activity.elapsed(), settings.auto_lock_minutes) {
+                    Some(LockReason::Idle)
+                } else {
+                    None
+                }
+            };
+
+            if let Some(reason) = reason {
+                if self.lock(reason).await {
+                    on_lock(reason);
+                }
+            }
+        }
+    }
+}
+
+/// The monotonic clock stops while suspended but the wall clock doesn't
+fn was_suspended(wall_elapsed: Duration, mono_elapsed: Duration) -> bool {
+    wall_elapsed > mono_elapsed + SUSPEND_THRESHOLD
+}
+
+fn is_idle(idle: Duration, auto_lock_minutes: u32) -> bool {
+    auto_lock_minutes > 0 && idle >= Duration::from_secs(u64::from(auto_lock_minutes) * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_kdf() -> KdfParams {
+        KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_verify_and_rekey() {
+        let kdf = test_kdf();
+        let salt = generate_salt();
+        let key = derive_key("old", &salt, &kdf).unwrap();
+        let mut file = VaultFile::new(&key, &salt, kdf).unwrap();
+        file.entries.insert(
+            "host:prod:password".to_string(),
+            encrypt_with_key(&key, b"hunter2").unwrap(),
+        );
+        assert!(file.verify(&key));
+        assert!(!file.verify(&derive_key("wrong", &salt, &kdf).unwrap()));
+
+        let new_salt = generate_salt();
+        let new_key = derive_key("new", &new_salt, &kdf).unwrap();
+        let rekeyed = file.rekey(&key, &new_key, &new_salt, kdf).unwrap();
+        assert!(rekeyed.verify(&new_key));
+        assert!(!rekeyed.verify(&key));
+        assert_eq!(rekeyed.salt_bytes().unwrap(), new_salt);
+        assert_eq!(
+            decrypt_with_key(&new_key, &rekeyed.entries["host:prod:password"]).unwrap(),
+            b"hunter2"
+        );
+    }
+
+    #[test]
+    fn test_was_suspended() {
+        let secs = Duration::from_secs;
+        assert!(!was_suspended(secs(15), secs(15)));
+        assert!(!was_suspended(secs(40), secs(15)));
+        assert!(was_suspended(secs(3600), secs(15)));
+    }
+
+    #[test]
+    fn test_is_idle() {
+        assert!(!is_idle(Duration::from_secs(3600), 0));
+        assert!(!is_idle(Duration::from_secs(299), 5));
+        assert!(is_idle(Duration::from_secs(300), 5));
+    }
+
+    #[test]
+    fn test_settings_parse() {
+        let settings: SecuritySettings = serde_json::from_str(
+            r#"{"kdf":{"memoryKib":65536,"iterations":3,"parallelism":4},"autoLockMinutes":5,"lockOnSuspend":false}"#,
+        )
+        .unwrap();
+        assert_eq!(settings.kdf.memory_kib, 65536);
+        assert!(settings.kdf.validate().is_ok());
+        assert!(SecuritySettings::default().lock_on_suspend);
+    }
+}
@@ -0,0 +1,669 @@
+use crate::models::SshResult;
+use crate::services::config_service::ConfigService;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::registry_service::{HostMetadata, RegistryService};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Timeout for a VM tool call (vagrant in particular is slow to start)
+const TOOL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How often imported VMs are checked for expiry
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Registry tag given to imported VMs
+const VM_TAG: &str = "vm";
+
+/// VirtualBox NAT address, the same in every guest and not reachable from the host
+const VBOX_NAT_ADDRESS: &str = "10.0.2.15";
+
+/// Local VM manager
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum VmProvider {
+    Vagrant,
+    Libvirt,
+    VirtualBox,
+    Utm,
+}
+
+impl VmProvider {
+    fn id(self) -> &'static str {
+        match self {
+            VmProvider::Vagrant => "vagrant",
+            VmProvider::Libvirt => "libvirt",
+            VmProvider::VirtualBox => "virtualbox",
+            VmProvider::Utm => "utm",
+        }
+    }
+}
+
+/// Running VM with a known SSH address
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredVm {
+    pub provider: VmProvider,
+    /// Provider's machine id (vagrant id, libvirt domain, VirtualBox/UTM uuid)
+    pub id: String,
+    pub name: String,
+    pub hostname: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub identity_file: Option<String>,
+}
+
+impl DiscoveredVm {
+    /// Registry marker of the imported host, "<provider>:<id>"
+    pub fn source(&self) -> String {
+        format!("{}:{}", self.provider.id(), self.id)
+    }
+
+    /// Suggested Host alias
+    pub fn alias(&self) -> String {
+        format!("vm-{}", sanitize_alias(&self.name))
+    }
+}
+
+/// Discovery result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmDiscoveryReport {
+    pub vms: Vec<DiscoveredVm>,
+    /// Providers whose tool is installed and answered
+    pub providers: Vec<VmProvider>,
+}
+
+/// VM import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmImportResult {
+    pub created: Vec<String>,
+    /// Sources skipped because the VM is gone or the alias exists
+    pub skipped: Vec<String>,
+}
+
+/// Finds local Vagrant/libvirt/VirtualBox/UTM machines and imports them as
+/// ephemeral hosts that are removed again once the VM is gone
+pub struct VmDiscoveryService;
+
+impl VmDiscoveryService {
+    /// Run a VM tool and return its stdout; None if it's not installed or fails
+    async fn run_tool(program: &'static str, args: Vec<String>) -> Option<String> {
+        let result = tokio::time::timeout(
+            TOOL_TIMEOUT,
+            tokio::task::spawn_blocking(move || {
+                Command::new(program)
+                    .args(&args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .output()
+            }),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(Ok(output))) if output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stdout).to_string())
+            }
+            Ok(Ok(Ok(output))) => {
+                tracing::warn!(
+                    "[vm_discovery] {} failed: {}",
+                    program,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                None
+            }
+            Ok(Ok(Err(_))) => None,
+            Ok(Err(e)) => {
+                tracing::warn!("[vm_discovery] Internal error running {}: {}", program, e);
+                None
+            }
+            Err(_) => {
+                tracing::warn!("[vm_discovery] {} timed out", program);
+                None
+            }
+        }
+    }
+
+    /// Running Vagrant machines, through `vagrant ssh-config`
+    async fn discover_vagrant() -> Option<Vec<DiscoveredVm>> {
+        let status = Self::run_tool(
+            "vagrant",
+            vec![
+                "global-status".into(),
+                "--prune".into(),
+                "--machine-readable".into(),
+            ],
+        )
+        .await?;
+
+        let mut vms = Vec::new();
+        for machine in parse_vagrant_status(&status)
+            .into_iter()
+            .filter(|m| m.state == "running")
+        {
+            let Some(config) =
+                Self::run_tool("vagrant", vec!["ssh-config".into(), machine.id.clone()]).await
+            else {
+                continue;
+            };
+            if let Some(vm) = vagrant_vm(&machine, &config) {
+                vms.push(vm);
+            }
+        }
+        Some(vms)
+    }
+
+    /// Running libvirt domains with a DHCP lease
+    async fn discover_libvirt() -> Option<Vec<DiscoveredVm>> {
+        let domains =
+            Self::run_tool("virsh", vec!["-q".into(), "list".into(), "--name".into()]).await?;
+
+        let mut vms = Vec::new();
+        for domain in domains.lines().map(str::trim).filter(|d| !d.is_empty()) {
+            let Some(addresses) = Self::run_tool(
+                "virsh",
+                vec!["-q".into(), "domifaddr".into(), domain.into()],
+            )
+            .await
+            else {
+                continue;
+            };
+            if let Some(address) = parse_domifaddr(&addresses) {
+                vms.push(DiscoveredVm {
+                    provider: VmProvider::Libvirt,
+                    id: domain.to_string(),
+                    name: domain.to_string(),
+                    hostname: address,
+                    port: 22,
+                    user: None,
+                    identity_file: None,
+                });
+            }
+        }
+        Some(vms)
+    }
+
+    /// Running VirtualBox VMs whose guest additions report an address
+    async fn discover_virtualbox() -> Option<Vec<DiscoveredVm>> {
+        let running =
+            Self::run_tool("VBoxManage", vec!["list".into(), "runningvms".into()]).await?;
+
+        let mut vms = Vec::new();
+        for (name, uuid) in parse_vbox_list(&running) {
+            // Guest additions number the adapters from 0
+            for adapter in 0..4 {
+                let property = format!("/VirtualBox/GuestInfo/Net/{}/V4/IP", adapter);
+                let Some(output) = Self::run_tool(
+                    "VBoxManage",
+                    vec!["guestproperty".into(), "get".into(), uuid.clone(), property],
+                )
+                .await
+                else {
+                    break;
+                };
+                let Some(address) = parse_vbox_property(&output) else {
+                    break;
+                };
+                if address != VBOX_NAT_ADDRESS {
+                    vms.push(DiscoveredVm {
+                        provider: VmProvider::VirtualBox,
+                        id: uuid.clone(),
+                        name: name.clone(),
+                        hostname: address,
+                        port: 22,
+                        user: None,
+                        identity_file: None,
+                    });
+                    break;
+                }
+            }
+        }
+        Some(vms)
+    }
+
+    /// Started UTM VMs (macOS)
+    async fn discover_utm() -> Option<Vec<DiscoveredVm>> {
+        let list = Self::run_tool("utmctl", vec!["list".into()]).await?;
+
+        let mut vms = Vec::new();
+        for (uuid, name) in parse_utm_list(&list) {
+            let Some(output) =
+                Self::run_tool("utmctl", vec!["ip-address".into(), uuid.clone()]).await
+            else {
+                continue;
+            };
+            if let Some(address) = output
+                .lines()
+                .map(str::trim)
+                .find(|line| line.parse::<std::net::Ipv4Addr>().is_ok())
+            {
+                vms.push(DiscoveredVm {
+                    provider: VmProvider::Utm,
+                    id: uuid,
+                    name,
+                    hostname: address.to_string(),
+                    port: 22,
+                    user: None,
+                    identity_file: None,
+                });
+            }
+        }
+        Some(vms)
+    }
+
+    /// Query every installed provider for running VMs
+    pub async fn discover() -> VmDiscoveryReport {
+        let (vagrant, libvirt, virtualbox, utm) = tokio::join!(
+            Self::discover_vagrant(),
+            Self::discover_libvirt(),
+            Self::discover_virtualbox(),
+            Self::discover_utm()
+        );
+
+        let mut report = VmDiscoveryReport {
+            vms: Vec::new(),
+            providers: Vec::new(),
+        };
+        for (provider, vms) in [
+            (VmProvider::Vagrant, vagrant),
+            (VmProvider::Libvirt, libvirt),
+            (VmProvider::VirtualBox, virtualbox),
+            (VmProvider::Utm, utm),
+        ] {
+            if let Some(vms) = vms {
+                report.providers.push(provider);
+                report.vms.extend(vms);
+            }
+        }
+        tracing::info!(
+            "[vm_discovery] Found {} VM(s) from {:?}",
+            report.vms.len(),
+            report.providers
+        );
+        report
+    }
+
+    /// Create Host entries for discovered VMs (by source), marked as ephemeral
+    pub async fn import_vms(sources: &[String]) -> SshResult<VmImportResult> {
+        ReadOnlyMode::ensure_writable("import local VMs")?;
+        let report = Self::discover().await;
+
+        let (created, skipped) = ConfigService::update_with_registry(
+            |editor| {
+                let mut created = Vec::new();
+                let mut skipped = Vec::new();
+                for source in sources {
+                    let Some(vm) = report.vms.iter().find(|vm| vm.source() == *source) else {
+                        skipped.push(source.clone());
+                        continue;
+                    };
+                    let alias = vm.alias();
+                    if editor.has_host(&alias) || ConfigService::validate_alias(&alias).is_err() {
+                        skipped.push(source.clone());
+                        continue;
+                    }
+                    editor.append_host(&alias, &host_options(vm));
+                    created.push((alias, source.clone()));
+                }
+                Ok((created, skipped))
+            },
+            |store, (created, _)| {
+                for (alias, source) in created {
+                    let metadata = store
+                        .hosts
+                        .entry(alias.clone())
+                        .or_insert_with(HostMetadata::new);
+                    if !metadata.tags.iter().any(|t| t == VM_TAG) {
+                        metadata.tags.push(VM_TAG.to_string());
+                    }
+                    metadata.ephemeral_source = Some(source.clone());
+                }
+                Ok(())
+            },
+        )
+        .await?;
+
+        tracing::info!(
+            "[vm_discovery] Imported {} VM(s) ({} skipped)",
+            created.len(),
+            skipped.len()
+        );
+        Ok(VmImportResult {
+            created: created.into_iter().map(|(alias, _)| alias).collect(),
+            skipped,
+        })
+    }
+
+    /// Remove imported VM hosts whose VM no longer runs; returns the removed aliases
+    /// Hosts of providers that couldn't be queried are left alone
+    pub async fn expire_vanished() -> SshResult<Vec<String>> {
+        let store = RegistryService::load().await?;
+        let ephemeral: Vec<(String, String)> = store
+            .hosts
+            .iter()
+            .filter_map(|(alias, m)| Some((alias.clone(), m.ephemeral_source.clone()?)))
+            .collect();
+        if ephemeral.is_empty() {
+            return Ok(Vec::new());
+        }
+        ReadOnlyMode::ensure_writable("expire local VM hosts")?;
+
+        let report = Self::discover().await;
+        let queried: HashSet<&str> = report.providers.iter().map(|p| p.id()).collect();
+        let running: HashSet<String> = report.vms.iter().map(DiscoveredVm::source).collect();
+        let vanished: Vec<String> = ephemeral
+            .into_iter()
+            .filter(|(_, source)| {
+                let provider = source.split(':').next().unwrap_or_default();
+                queried.contains(provider) && !running.contains(source)
+            })
+            .map(|(alias, _)| alias)
+            .collect();
+        if vanished.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        ConfigService::update_with_registry(
+            |editor| {
+                for alias in &vanished {
+                    editor.remove_host(alias);
+                }
+                Ok(())
+            },
+            |store, _| {
+                for alias in &vanished {
+                    store.hosts.remove(alias);
+                }
+                Ok(())
+            },
+        )
+        .await?;
+
+        tracing::info!("[vm_discovery] Expired vanished VM hosts: {:?}", vanished);
+        Ok(vanished)
+    }
+
+    /// Periodically expire vanished VM hosts (spawned once at app setup)
+    pub async fn run_expiry_loop<F>(on_expired: F)
+    where
+        F: Fn(Vec<String>) + Send + Sync + 'static,
+    {
+        loop {
+            tokio::time::sleep(EXPIRY_INTERVAL).await;
+            if ReadOnlyMode::status().enabled {
+                continue;
+            }
+            match Self::expire_vanished().await {
+                Ok(expired) if !expired.is_empty() => on_expired(expired),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("[vm_discovery] VM expiry check failed: {}", e),
+            }
+        }
+    }
+}
+
+/// Vagrant machine from `vagrant global-status --machine-readable`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct VagrantMachine {
+    id: String,
+    provider: String,
+    home: String,
+    state: String,
+}
+
+/// Parse "timestamp,target,type,data" lines; each machine starts with a machine-id line
+fn parse_vagrant_status(output: &str) -> Vec<VagrantMachine> {
+    let mut machines: Vec<VagrantMachine> = Vec::new();
+    for line in output.lines() {
+        let mut fields = line.splitn(4, ',');
+        let (Some(_), Some(_), Some(kind), Some(data)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // Commas in data are escaped as %!(VAGRANT_COMMA)
+        let data = data.trim().replace("%!(VAGRANT_COMMA)", ",");
+        if kind == "machine-id" {
+            machines.push(VagrantMachine {
+                id: data,
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(machine) = machines.last_mut() else {
+            continue;
+        };
+        match kind {
+            "provider-name" => machine.provider = data,
+            "machine-home" => machine.home = data,
+            "state" => machine.state = data,
+            _ => {}
+        }
+    }
+    machines
+}
+
+/// Build a VM from `vagrant ssh-config <id>` output
+fn vagrant_vm(machine: &VagrantMachine, ssh_config: &str) -> Option<DiscoveredVm> {
+    let mut host = None;
+    let mut hostname = None;
+    let mut port = 22;
+    let mut user = None;
+    let mut identity_file = None;
+    for line in ssh_config.lines() {
+        let Some((key, value)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.to_lowercase().as_str() {
+            "host" => host = Some(value),
+            "hostname" => hostname = Some(value),
+            "port" => port = value.parse().unwrap_or(22),
+            "user" => user = Some(value),
+            // The first key is the machine's own one
+            "identityfile" if identity_file.is_none() => identity_file = Some(value),
+            _ => {}
+        }
+    }
+
+    // "<project dir>" or "<project dir>-<machine>" for multi-machine setups
+    let project = machine
+        .home
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .filter(|p| !p.is_empty())
+        .unwrap_or("vagrant");
+    let name = match host.as_deref() {
+        Some("default") | None => project.to_string(),
+        Some(host) => format!("{}-{}", project, host),
+    };
+    Some(DiscoveredVm {
+        provider: VmProvider::Vagrant,
+        id: machine.id.clone(),
+        name,
+        hostname: hostname?,
+        port,
+        user,
+        identity_file,
+    })
+}
+
+/// First IPv4 address from `virsh -q domifaddr` ("vnet0  <mac>  ipv4  192.168.122.45/24")
+fn parse_domifaddr(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [_, _, "ipv4", address, ..] => address.split('/').next().map(str::to_string),
+            _ => None,
+        }
+    })
+}
+
+/// (name, uuid) pairs from `VBoxManage list runningvms` ("\"name\" {uuid}")
+fn parse_vbox_list(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, uuid) = line.trim().rsplit_once(' ')?;
+            let name = name.trim().strip_prefix('"')?.strip_suffix('"')?;
+            let uuid = uuid.strip_prefix('{')?.strip_suffix('}')?;
+            Some((name.to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Value of `VBoxManage guestproperty get` ("Value: x", or "No value set!")
+fn parse_vbox_property(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Value:"))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// (uuid, name) pairs of started VMs from `utmctl list` ("UUID  Status  Name" table)
+fn parse_utm_list(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let uuid = fields.next()?;
+            let status = fields.next()?;
+            let name = fields.collect::<Vec<_>>().join(" ");
+            (status == "started" && !name.is_empty()).then(|| (uuid.to_string(), name))
+        })
+        .collect()
+}
+
+/// Host block options for a VM
+fn host_options(vm: &DiscoveredVm) -> Vec<(String, String)> {
+    let mut options = vec![("HostName".to_string(), vm.hostname.clone())];
+    if vm.port != 22 {
+        options.push(("Port".to_string(), vm.port.to_string()));
+    }
+    if let Some(user) = &vm.user {
+        options.push(("User".to_string(), user.clone()));
+    }
+    if let Some(identity_file) = &vm.identity_file {
+        options.push(("IdentityFile".to_string(), identity_file.clone()));
+        options.push(("IdentitiesOnly".to_string(), "yes".to_string()));
+    }
+    options
+}
+
+/// Lowercase alias-safe form of a machine name
+pub(crate) fn sanitize_alias(name: &str) -> String {
+    let mut alias = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            alias.push(c.to_ascii_lowercase());
+        } else if !alias.ends_with('-') {
+            alias.push('-');
+        }
+    }
+    let alias = alias.trim_matches('-');
+    if alias.is_empty() {
+        "machine".to_string()
+    } else {
+        alias.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vagrant_status() {
+        let output = "1700000000,,metadata,machine-count,2\n\
+            1700000000,,machine-id,a1b2c3d\n\
+            1700000000,,provider-name,virtualbox\n\
+            1700000000,,machine-home,/home/alice/web\n\
+            1700000000,,state,running\n\
+            1700000000,,machine-id,e4f5a6b\n\
+            1700000000,,provider-name,libvirt\n\
+            1700000000,,machine-home,/home/alice/db\n\
+            1700000000,,state,poweroff\n\
+            1700000000,,ui,info,id  name\n";
+        let machines = parse_vagrant_status(output);
+        assert_eq!(machines.len(), 2);
+        assert_eq!(machines[0].id, "a1b2c3d");
+        assert_eq!(machines[0].home, "/home/alice/web");
+        assert_eq!(machines[0].state, "running");
+        assert_eq!(machines[1].provider, "libvirt");
+        assert_eq!(machines[1].state, "poweroff");
+    }
+
+    #[test]
+    fn test_vagrant_vm_from_ssh_config() {
+        let machine = VagrantMachine {
+            id: "a1b2c3d".to_string(),
+            home: "/home/alice/web".to_string(),
+            ..Default::default()
+        };
+        let config = "Host default\n  HostName 127.0.0.1\n  User vagrant\n  Port 2222\n  \
+            UserKnownHostsFile /dev/null\n  \
+            IdentityFile \"/home/alice/web/.vagrant/machines/default/virtualbox/private_key\"\n  \
+            IdentitiesOnly yes\n";
+        let vm = vagrant_vm(&machine, config).unwrap();
+        assert_eq!(vm.name, "web");
+        assert_eq!(vm.hostname, "127.0.0.1");
+        assert_eq!(vm.port, 2222);
+        assert_eq!(vm.user.as_deref(), Some("vagrant"));
+        assert_eq!(
+            vm.identity_file.as_deref(),
+            Some("/home/alice/web/.vagrant/machines/default/virtualbox/private_key")
+        );
+        assert_eq!(vm.source(), "vagrant:a1b2c3d");
+        assert_eq!(vm.alias(), "vm-web");
+
+        let multi = vagrant_vm(&machine, "Host db\n  HostName 127.0.0.1\n").unwrap();
+        assert_eq!(multi.name, "web-db");
+        assert!(vagrant_vm(&machine, "Host default\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_tool_outputs() {
+        assert_eq!(
+            parse_domifaddr(" vnet0      52:54:00:aa:bb:cc    ipv4         192.168.122.45/24\n"),
+            Some("192.168.122.45".to_string())
+        );
+        assert_eq!(parse_domifaddr(""), None);
+
+        assert_eq!(
+            parse_vbox_list("\"Ubuntu 22.04\" {0f6c1a52-9f3e-4c2a-8d1b-2a4b6c8d0e1f}\n"),
+            vec![(
+                "Ubuntu 22.04".to_string(),
+                "0f6c1a52-9f3e-4c2a-8d1b-2a4b6c8d0e1f".to_string()
+            )]
+        );
+        assert_eq!(
+            parse_vbox_property("Value: 192.168.56.10\n"),
+            Some("192.168.56.10".to_string())
+        );
+        assert_eq!(parse_vbox_property("No value set!\n"), None);
+
+        let utm = "UUID                                 Status   Name\n\
+            A1B2C3D4-0000-0000-0000-000000000001 started  Debian Dev\n\
+            A1B2C3D4-0000-0000-0000-000000000002 stopped  Windows\n";
+        assert_eq!(
+            parse_utm_list(utm),
+            vec![(
+                "A1B2C3D4-0000-0000-0000-000000000001".to_string(),
+                "Debian Dev".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_sanitize_alias() {
+        assert_eq!(sanitize_alias("Ubuntu 22.04 (dev)"), "ubuntu-22.04-dev");
+        assert_eq!(sanitize_alias("  "), "machine");
+    }
+}
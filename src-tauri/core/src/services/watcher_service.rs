@@ -0,0 +1,143 @@
+use crate::utils::{ssh_config_path, ssh_dir, workspace_data_path};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+
+/// How often the watched files are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Files whose changes other parts of the app react to
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchedFile {
+    SshConfig,
+    KnownHosts,
+    /// metadata.json (host registry)
+    Registry,
+    /// tunnels.json
+    Tunnels,
+    /// The SSH directory itself (key files added, removed or renamed)
+    SshDir,
+    /// key_access.jsonl (keys deployed to or found on hosts)
+    KeyAccess,
+}
+
+impl WatchedFile {
+    pub const ALL: [WatchedFile; 6] = [
+        WatchedFile::SshConfig,
+        WatchedFile::KnownHosts,
+        WatchedFile::Registry,
+        WatchedFile::Tunnels,
+        WatchedFile::SshDir,
+        WatchedFile::KeyAccess,
+    ];
+
+    fn path(self) -> Option<PathBuf> {
+        match self {
+            WatchedFile::SshConfig => ssh_config_path().ok(),
+            WatchedFile::KnownHosts => ssh_dir().ok().map(|dir| dir.join("known_hosts")),
+            WatchedFile::Registry => workspace_data_path("metadata.json").ok(),
+            WatchedFile::Tunnels => workspace_data_path("tunnels.json").ok(),
+            WatchedFile::SshDir => ssh_dir().ok(),
+            WatchedFile::KeyAccess => workspace_data_path("key_access.jsonl").ok(),
+        }
+    }
+}
+
+/// Modification time and size; None when the file doesn't exist
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Polls the app's files and broadcasts which ones changed, whoever changed them
+pub struct WatcherService {
+    sender: broadcast::Sender<WatchedFile>,
+}
+
+impl WatcherService {
+    /// Global watcher
+    pub fn global() -> &'static WatcherService {
+        static WATCHER: OnceLock<WatcherService> = OnceLock::new();
+        WATCHER.get_or_init(|| WatcherService {
+            sender: broadcast::channel(32).0,
+        })
+    }
+
+    /// Receive a message for every changed file
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchedFile> {
+        self.sender.subscribe()
+    }
+
+    /// Watch the files for the lifetime of the app (spawned once at app setup)
+    pub async fn run(&'static self) {
+        let mut previous = snapshot().await;
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let current = snapshot().await;
+            for file in changed_files(&previous, &current) {
+                tracing::info!("[watcher_service] {:?} changed", file);
+                // No receivers is fine
+                let _ = self.sender.send(file);
+            }
+            previous = current;
+        }
+    }
+}
+
+async fn snapshot() -> HashMap<WatchedFile, Stamp> {
+    let mut stamps = HashMap::new();
+    for file in WatchedFile::ALL {
+        let stamp = match file.path() {
+            Some(path) => tokio::fs::metadata(path)
+                .await
+                .ok()
+                .and_then(|m| Some((m.modified().ok()?, m.len()))),
+            None => None,
+        };
+        stamps.insert(file, stamp);
+    }
+    stamps
+}
+
+fn changed_files(
+    previous: &HashMap<WatchedFile, Stamp>,
+    current: &HashMap<WatchedFile, Stamp>,
+) -> Vec<WatchedFile> {
+    WatchedFile::ALL
+        .into_iter()
+        .filter(|file| previous.get(file) != current.get(file))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_files() {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let t1 = t0 + Duration::from_secs(1);
+        let previous = HashMap::from([
+            (WatchedFile::SshConfig, Some((t0, 10))),
+            (WatchedFile::KnownHosts, Some((t0, 10))),
+            (WatchedFile::Registry, None),
+            (WatchedFile::Tunnels, Some((t0, 5))),
+        ]);
+        let current = HashMap::from([
+            (WatchedFile::SshConfig, Some((t1, 10))),
+            (WatchedFile::KnownHosts, Some((t0, 10))),
+            (WatchedFile::Registry, Some((t1, 2))),
+            (WatchedFile::Tunnels, None),
+        ]);
+        assert_eq!(
+            changed_files(&previous, &current),
+            vec![
+                WatchedFile::SshConfig,
+                WatchedFile::Registry,
+                WatchedFile::Tunnels
+            ]
+        );
+        assert!(changed_files(&current, &current).is_empty());
+    }
+}
@@ -0,0 +1,453 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::settings_service::SettingsService;
+use crate::services::vault_service::{LockReason, VaultService};
+use crate::utils::{
+    app_paths, resolve_ssh_root, set_workspace, ssh_dir, validate_key_name, workspace_config_path,
+    workspace_dir_of, write_atomic, AppPaths,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Workspace using the files the app had before workspaces existed
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+const MAX_ID_LEN: usize = 32;
+
+/// A separate set of hosts, vault, keys and SSH config (e.g. work and personal)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    /// Private key for hosts without IdentityFile; relative to the SSH root
+    #[serde(default)]
+    pub default_identity: Option<String>,
+    /// SSH config file; None for `<SSH root>/config.d/<id>` (`<SSH root>/config`
+    /// for the default workspace)
+    #[serde(default)]
+    pub config_file: Option<String>,
+    /// Keys (file names in the SSH root) of this workspace, hidden in the others;
+    /// keys of no workspace are shown everywhere
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+/// Workspaces section of the settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkspaceSettings {
+    pub active: String,
+    pub workspaces: Vec<Workspace>,
+}
+
+impl Default for WorkspaceSettings {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_WORKSPACE.to_string(),
+            workspaces: vec![Workspace {
+                id: DEFAULT_WORKSPACE.to_string(),
+                name: "Default".to_string(),
+                ..Default::default()
+            }],
+        }
+    }
+}
+
+impl WorkspaceSettings {
+    pub(crate) fn validate(&self) -> SshResult<()> {
+        let invalid = |message: String| Err(SshBuddyError::InvalidOption { message });
+        let mut owners: Vec<&str> = Vec::new();
+        for (i, workspace) in self.workspaces.iter().enumerate() {
+            if !valid_id(&workspace.id) {
+                return invalid(format!("Invalid workspace id: {}", workspace.id));
+            }
+            if self.workspaces[..i].iter().any(|w| w.id == workspace.id) {
+                return invalid(format!("Duplicate workspace: {}", workspace.id));
+            }
+            if workspace.name.trim().is_empty() {
+                return invalid("Workspace name cannot be empty".to_string());
+            }
+            if workspace.id == DEFAULT_WORKSPACE && workspace.config_file.is_some() {
+                return invalid("The default workspace uses the main SSH config".to_string());
+            }
+            let bad_path =
+                |path: &Option<String>| path.as_deref().is_some_and(|p| p.trim().is_empty());
+            if bad_path(&workspace.config_file) || bad_path(&workspace.default_identity) {
+                return invalid(format!("Empty path in workspace {}", workspace.id));
+            }
+            for key in &workspace.keys {
+                validate_key_name(key)?;
+                if owners.contains(&key.as_str()) {
+                    return invalid(format!("Key {} belongs to two workspaces", key));
+                }
+                owners.push(key);
+            }
+        }
+        if !self.workspaces.iter().any(|w| w.id == DEFAULT_WORKSPACE) {
+            return invalid("The default workspace is missing".to_string());
+        }
+        if self.find(&self.active).is_none() {
+            return invalid(format!("Unknown workspace: {}", self.active));
+        }
+        Ok(())
+    }
+
+    pub fn find(&self, id: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.id == id)
+    }
+
+    fn find_mut(&mut self, id: &str) -> SshResult<&mut Workspace> {
+        self.workspaces
+            .iter_mut()
+            .find(|w| w.id == id)
+            .ok_or_else(|| SshBuddyError::InvalidOption {
+                message: format!("Unknown workspace: {}", id),
+            })
+    }
+
+    pub fn active_workspace(&self) -> Option<&Workspace> {
+        self.find(&self.active)
+    }
+
+    /// Point the path helpers at the active workspace
+    pub(crate) fn apply(&self) {
+        match self.active_workspace() {
+            Some(workspace) if workspace.id != DEFAULT_WORKSPACE => {
+                set_workspace(Some(&workspace.id), workspace.config_file.as_deref())
+            }
+            _ => set_workspace(None, None),
+        }
+    }
+
+    /// A key is visible unless another workspace owns it
+    pub fn key_visible(&self, key: &str) -> bool {
+        self.workspaces
+            .iter()
+            .all(|w| w.id == self.active || !w.keys.iter().any(|k| k == key))
+    }
+}
+
+/// Lowercase letters, digits and dashes, starting with a letter or digit
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && !id.starts_with('-')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Workspace id from its name, unique among `taken`
+pub(crate) fn workspace_id(name: &str, taken: &[&str]) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_ID_LEN - 3);
+    let base = match slug.trim_end_matches('-') {
+        "" => "workspace".to_string(),
+        base => base.to_string(),
+    };
+    let mut id = base.clone();
+    let mut n = 2;
+    while taken.contains(&id.as_str()) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+pub struct WorkspaceService;
+
+impl WorkspaceService {
+    pub fn list() -> WorkspaceSettings {
+        SettingsService::get().workspaces
+    }
+
+    /// Whether the active workspace may see a key (file name in the SSH root)
+    pub fn is_key_visible(key: &str) -> bool {
+        SettingsService::get().workspaces.key_visible(key)
+    }
+
+    /// Default identity of the active workspace, if the file exists
+    pub fn default_identity() -> Option<PathBuf> {
+        let settings = SettingsService::get();
+        let identity = settings
+            .workspaces
+            .active_workspace()?
+            .default_identity
+            .clone()?;
+        let ssh_dir = ssh_dir().ok()?;
+        let path = resolve_ssh_root(&identity, Some(&ssh_dir));
+        path.is_file().then_some(path)
+    }
+
+    /// Add a workspace with an empty registry, vault and SSH config
+    pub async fn create(name: &str) -> SshResult<Workspace> {
+        ReadOnlyMode::ensure_writable("create a workspace")?;
+        let mut created = None;
+        SettingsService::update(|settings| {
+            let taken: Vec<&str> = settings
+                .workspaces
+                .workspaces
+                .iter()
+                .map(|w| w.id.as_str())
+                .collect();
+            let workspace = Workspace {
+                id: workspace_id(name, &taken),
+                name: name.trim().to_string(),
+                ..Default::default()
+            };
+            settings.workspaces.workspaces.push(workspace.clone());
+            settings.workspaces.validate()?;
+            created = Some(workspace);
+            Ok(())
+        })
+        .await?;
+        let workspace = created.ok_or_else(|| SshBuddyError::Unknown {
+            message: "Workspace was not created".to_string(),
+        })?;
+        fs::create_dir_all(workspace_dir_of(&workspace.id)?).await?;
+        Self::ensure_config(&workspace).await?;
+        tracing::info!("[workspace_service] Created workspace {}", workspace.id);
+        Ok(workspace)
+    }
+
+    /// Change a workspace's name, default identity, config file or keys
+    pub async fn update(workspace: Workspace) -> SshResult<Workspace> {
+        ReadOnlyMode::ensure_writable("change a workspace")?;
+        let settings = SettingsService::update(|settings| {
+            let current = settings.workspaces.find_mut(&workspace.id)?;
+            *current = Workspace {
+                name: workspace.name.trim().to_string(),
+                ..workspace.clone()
+            };
+            settings.workspaces.validate()
+        })
+        .await?;
+        if workspace.id != DEFAULT_WORKSPACE {
+            Self::ensure_config(&workspace).await?;
+        }
+        tracing::info!("[workspace_service] Updated workspace {}", workspace.id);
+        settings
+            .workspaces
+            .find(&workspace.id)
+            .cloned()
+            .ok_or_else(|| SshBuddyError::Unknown {
+                message: "Workspace disappeared".to_string(),
+            })
+    }
+
+    /// Remove a workspace that is not active; its keys become shared.
+    /// `delete_data` also removes its registry, vault and generated SSH config
+    pub async fn delete(id: &str, delete_data: bool) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("delete a workspace")?;
+        if id == DEFAULT_WORKSPACE {
+            return Err(SshBuddyError::InvalidOption {
+                message: "The default workspace can't be deleted".to_string(),
+            });
+        }
+        let mut removed = None;
+        SettingsService::update(|settings| {
+            if settings.workspaces.active == id {
+                return Err(SshBuddyError::InvalidOption {
+                    message: "Switch to another workspace before deleting this one".to_string(),
+                });
+            }
+            let index = settings
+                .workspaces
+                .workspaces
+                .iter()
+                .position(|w| w.id == id)
+                .ok_or_else(|| SshBuddyError::InvalidOption {
+                    message: format!("Unknown workspace: {}", id),
+                })?;
+            removed = Some(settings.workspaces.workspaces.remove(index));
+            Ok(())
+        })
+        .await?;
+        if let (true, Some(workspace)) = (delete_data, removed) {
+            let dir = workspace_dir_of(&workspace.id)?;
+            if dir.exists() {
+                fs::remove_dir_all(&dir).await?;
+            }
+            if workspace.config_file.is_none() {
+                let config = workspace_config_path(&workspace.id, None)?;
+                if config.exists() {
+                    fs::remove_file(&config).await?;
+                }
+            }
+        }
+        tracing::info!("[workspace_service] Deleted workspace {}", id);
+        Ok(())
+    }
+
+    /// Make a workspace active; the vault of the previous one is locked
+    pub async fn switch(id: &str) -> SshResult<AppPaths> {
+        let settings = SettingsService::get();
+        let workspace =
+            settings
+                .workspaces
+                .find(id)
+                .cloned()
+                .ok_or_else(|| SshBuddyError::InvalidOption {
+                    message: format!("Unknown workspace: {}", id),
+                })?;
+        if settings.workspaces.active != id {
+            VaultService::global().lock(LockReason::Workspace).await;
+            SettingsService::update(|settings| {
+                settings.workspaces.active = id.to_string();
+                Ok(())
+            })
+            .await?;
+        }
+        if workspace.id != DEFAULT_WORKSPACE {
+            fs::create_dir_all(workspace_dir_of(&workspace.id)?).await?;
+            Self::ensure_config(&workspace).await?;
+        }
+        tracing::info!("[workspace_service] Switched to workspace {}", id);
+        app_paths()
+    }
+
+    /// Give a key to a workspace (None shares it with all workspaces)
+    pub async fn assign_key(key: &str, workspace: Option<&str>) -> SshResult<()> {
+        ReadOnlyMode::ensure_writable("assign a key")?;
+        validate_key_name(key)?;
+        SettingsService::update(|settings| {
+            for w in settings.workspaces.workspaces.iter_mut() {
+                w.keys.retain(|k| k != key);
+            }
+            if let Some(id) = workspace {
+                settings.workspaces.find_mut(id)?.keys.push(key.to_string());
+            }
+            settings.workspaces.validate()
+        })
+        .await?;
+        tracing::info!(
+            "[workspace_service] Key {} assigned to {}",
+            key,
+            workspace.unwrap_or("all workspaces")
+        );
+        Ok(())
+    }
+
+    /// A key created in a workspace other than the default one belongs to it
+    pub(crate) async fn claim_key(key: &str) {
+        let active = SettingsService::get().workspaces.active;
+        if active == DEFAULT_WORKSPACE {
+            return;
+        }
+        if let Err(e) = Self::assign_key(key, Some(&active)).await {
+            tracing::warn!("[workspace_service] Failed to claim key {}: {}", key, e);
+        }
+    }
+
+    /// Forget a deleted key
+    pub(crate) async fn release_key(key: &str) {
+        let settings = SettingsService::get().workspaces;
+        if settings
+            .workspaces
+            .iter()
+            .all(|w| !w.keys.iter().any(|k| k == key))
+        {
+            return;
+        }
+        if let Err(e) = Self::assign_key(key, None).await {
+            tracing::warn!("[workspace_service] Failed to release key {}: {}", key, e);
+        }
+    }
+
+    /// Create the workspace's SSH config file when it doesn't exist yet
+    async fn ensure_config(workspace: &Workspace) -> SshResult<()> {
+        let path = workspace_config_path(&workspace.id, workspace.config_file.as_deref())?;
+        Self::create_config(&path, &workspace.name).await
+    }
+
+    async fn create_config(path: &Path, name: &str) -> SshResult<()> {
+        if path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let header = format!("# SSH config of the {} workspace (SSH Buddy)\n", name);
+        write_atomic(path, header.as_bytes()).await?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> WorkspaceSettings {
+        let mut settings = WorkspaceSettings::default();
+        settings.workspaces.push(Workspace {
+            id: "work".to_string(),
+            name: "Work".to_string(),
+            keys: vec!["id_work".to_string()],
+            ..Default::default()
+        });
+        settings
+    }
+
+    #[test]
+    fn test_workspace_id() {
+        assert_eq!(workspace_id("Work", &["default"]), "work");
+        assert_eq!(workspace_id(" Client: ACME ", &[]), "client-acme");
+        assert_eq!(workspace_id("Work", &["default", "work"]), "work-2");
+        assert_eq!(workspace_id("工作", &[]), "workspace");
+        assert!(workspace_id(&"x".repeat(80), &[]).len() <= MAX_ID_LEN);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(WorkspaceSettings::default().validate().is_ok());
+        assert!(settings().validate().is_ok());
+
+        let mut unknown_active = settings();
+        unknown_active.active = "home".to_string();
+        assert!(unknown_active.validate().is_err());
+
+        let mut bad_id = settings();
+        bad_id.workspaces[1].id = "Work".to_string();
+        assert!(bad_id.validate().is_err());
+
+        let mut shared_key = settings();
+        shared_key.workspaces[0].keys.push("id_work".to_string());
+        assert!(shared_key.validate().is_err());
+
+        let mut no_default = settings();
+        no_default.workspaces.remove(0);
+        no_default.active = "work".to_string();
+        assert!(no_default.validate().is_err());
+
+        let mut default_config = settings();
+        default_config.workspaces[0].config_file = Some("~/.ssh/other".to_string());
+        assert!(default_config.validate().is_err());
+    }
+
+    #[test]
+    fn test_key_visible() {
+        let mut settings = settings();
+        assert!(!settings.key_visible("id_work"));
+        assert!(settings.key_visible("id_ed25519"));
+
+        settings.active = "work".to_string();
+        assert!(settings.key_visible("id_work"));
+        settings.workspaces[0].keys.push("id_personal".to_string());
+        assert!(!settings.key_visible("id_personal"));
+        assert!(settings.key_visible("id_ed25519"));
+    }
+}
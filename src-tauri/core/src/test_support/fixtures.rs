@@ -0,0 +1,159 @@
+use super::server::TestSshServer;
+use crate::utils::SSH_DIR_ENV;
+use rand::rngs::OsRng;
+use ssh_key::{Algorithm, LineEnding, PrivateKey};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tempfile::TempDir;
+
+/// Environment the services read; one `SshTestEnv` owns it at a time
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Throwaway local SSH root, app data directory and remote home
+/// The services find their files through environment variables, so creating one
+/// waits for any other `SshTestEnv` in the process to be dropped
+pub struct SshTestEnv {
+    _lock: MutexGuard<'static, ()>,
+    temp_dir: TempDir,
+    ssh_dir: PathBuf,
+    remote_home: PathBuf,
+}
+
+impl SshTestEnv {
+    /// Empty SSH root (set through SSH_BUDDY_SSH_DIR), a HOME for the app data and
+    /// an empty remote home; the SSH agent is hidden so only files are used
+    pub fn new() -> std::io::Result<Self> {
+        let lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let temp_dir = TempDir::new()?;
+        let local_home = temp_dir.path().join("local");
+        let ssh_dir = local_home.join(".ssh");
+        let remote_home = temp_dir.path().join("remote");
+        std::fs::create_dir_all(&ssh_dir)?;
+        std::fs::create_dir_all(remote_home.join(".ssh"))?;
+
+        std::env::set_var(SSH_DIR_ENV, &ssh_dir);
+        std::env::set_var("HOME", &local_home);
+        std::env::set_var("XDG_DATA_HOME", local_home.join(".local").join("share"));
+        std::env::remove_var("SSH_AUTH_SOCK");
+
+        Ok(Self {
+            _lock: lock,
+            temp_dir,
+            ssh_dir,
+            remote_home,
+        })
+    }
+
+    /// Local SSH root (config, known_hosts, keys)
+    pub fn ssh_dir(&self) -> &Path {
+        &self.ssh_dir
+    }
+
+    /// Home directory to hand to `TestServerOptions`
+    pub fn remote_home(&self) -> &Path {
+        &self.remote_home
+    }
+
+    pub fn root(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// Write a file below the SSH root
+    pub fn write_ssh_file(&self, name: &str, content: &str) -> std::io::Result<PathBuf> {
+        let path = self.ssh_dir.join(name);
+        std::fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    /// Append a line to a file below the SSH root
+    pub fn append_ssh_file(&self, name: &str, line: &str) -> std::io::Result<()> {
+        append_line(&self.ssh_dir.join(name), line)
+    }
+
+    pub fn read_ssh_file(&self, name: &str) -> std::io::Result<String> {
+        std::fs::read_to_string(self.ssh_dir.join(name))
+    }
+
+    pub fn read_remote_file(&self, name: &str) -> std::io::Result<String> {
+        std::fs::read_to_string(self.remote_home.join(name))
+    }
+
+    /// Create an unencrypted Ed25519 key in the SSH root; returns the public key line
+    pub fn generate_key(&self, name: &str) -> std::io::Result<String> {
+        let mut key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        key.set_comment(format!("{}@ssh-buddy-test", name));
+        let private = key
+            .to_openssh(LineEnding::LF)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        let public = key
+            .public_key()
+            .to_openssh()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+
+        let path = self.write_ssh_file(name, &private)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        self.write_ssh_file(&format!("{}.pub", name), &format!("{}\n", public))?;
+        Ok(public)
+    }
+
+    /// Let `public_key` log in to the remote home
+    pub fn authorize_key(&self, public_key: &str) -> std::io::Result<()> {
+        append_line(
+            &self.remote_home.join(".ssh").join("authorized_keys"),
+            public_key.trim(),
+        )
+    }
+
+    /// Trust the server's host key in known_hosts
+    pub fn trust(&self, server: &TestSshServer) -> std::io::Result<()> {
+        self.append_ssh_file("known_hosts", &server.known_hosts_line())
+    }
+
+    /// Add a Host block pointing at the server, logging in with `identity`
+    /// (a key in the SSH root) when given
+    pub fn add_host(
+        &self,
+        alias: &str,
+        server: &TestSshServer,
+        identity: Option<&str>,
+    ) -> std::io::Result<()> {
+        let mut block = format!(
+            "\nHost {}\n    HostName 127.0.0.1\n    Port {}\n    User {}\n",
+            alias,
+            server.port(),
+            server.user()
+        );
+        if let Some(identity) = identity {
+            block.push_str(&format!(
+                "    IdentityFile {}\n",
+                self.ssh_dir.join(identity).display()
+            ));
+        }
+        append_line(&self.ssh_dir.join("config"), &block)
+    }
+
+    /// Key `id_ed25519` authorized on the server, its host key trusted and a Host
+    /// block `alias` using both: the usual setup for a working connection
+    pub fn connect_to(&self, alias: &str, server: &TestSshServer) -> std::io::Result<()> {
+        if !self.ssh_dir.join("id_ed25519").exists() {
+            let public_key = self.generate_key("id_ed25519")?;
+            self.authorize_key(&public_key)?;
+        }
+        self.trust(server)?;
+        self.add_host(alias, server, Some("id_ed25519"))
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)
+}
@@ -0,0 +1,9 @@
+//! In-process SSH server and filesystem fixtures for integration tests
+//! Built with the `test-support` feature so the CLI and plugins can reuse them
+
+mod fixtures;
+mod server;
+mod sftp;
+
+pub use fixtures::SshTestEnv;
+pub use server::{TestServerOptions, TestSshServer, TEST_USER};
@@ -0,0 +1,348 @@
+use super::sftp::HomeSftp;
+use async_trait::async_trait;
+use rand::rngs::OsRng;
+use russh::keys::key::{KeyPair, PublicKey};
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, CryptoVec, MethodSet};
+use russh_keys::PublicKeyBase64;
+use ssh_key::{Algorithm, LineEnding, PrivateKey};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// User the server expects unless the options say otherwise
+pub const TEST_USER: &str = "tester";
+
+/// How the test server authenticates and where it keeps its files
+#[derive(Debug, Clone)]
+pub struct TestServerOptions {
+    /// Remote home: commands run here and SFTP paths are rooted here
+    pub home: PathBuf,
+    pub user: String,
+    /// Accepted password; None turns password auth off
+    pub password: Option<String>,
+}
+
+impl TestServerOptions {
+    pub fn new(home: impl Into<PathBuf>) -> Self {
+        Self {
+            home: home.into(),
+            user: TEST_USER.to_string(),
+            password: None,
+        }
+    }
+}
+
+/// In-process SSH server on 127.0.0.1 for integration tests
+/// Public keys are checked against `<home>/.ssh/authorized_keys` on every attempt,
+/// exec requests run through `sh -c` in the home directory (unix only) and the
+/// `sftp` subsystem serves the home directory
+pub struct TestSshServer {
+    addr: SocketAddr,
+    host_key: String,
+    state: Arc<ServerState>,
+    task: JoinHandle<()>,
+}
+
+struct ServerState {
+    options: TestServerOptions,
+    /// Commands received, in order
+    commands: Mutex<Vec<String>>,
+}
+
+impl TestSshServer {
+    /// Bind a free port and accept connections until the server is dropped
+    /// The host key is a fresh Ed25519 key
+    pub async fn start(options: TestServerOptions) -> std::io::Result<Self> {
+        let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).map_err(io_error)?;
+        let host_key = key.public_key().to_openssh().map_err(io_error)?;
+        let key_pair =
+            russh_keys::decode_secret_key(&key.to_openssh(LineEnding::LF).map_err(io_error)?, None)
+                .map_err(io_error)?;
+
+        std::fs::create_dir_all(options.home.join(".ssh"))?;
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            options,
+            commands: Mutex::new(Vec::new()),
+        });
+        let task = tokio::spawn(serve(listener, key_pair, state.clone()));
+        Ok(Self {
+            addr,
+            host_key,
+            state,
+            task,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    pub fn user(&self) -> &str {
+        &self.state.options.user
+    }
+
+    pub fn home(&self) -> &Path {
+        &self.state.options.home
+    }
+
+    /// Host public key as `<type> <base64>`
+    pub fn host_key(&self) -> &str {
+        self.host_key.trim()
+    }
+
+    /// known_hosts line trusting this server
+    pub fn known_hosts_line(&self) -> String {
+        format!("[127.0.0.1]:{} {}", self.port(), self.host_key())
+    }
+
+    /// Commands run so far
+    pub async fn commands(&self) -> Vec<String> {
+        self.state.commands.lock().await.clone()
+    }
+}
+
+impl Drop for TestSshServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn io_error(e: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+}
+
+async fn serve(listener: TcpListener, key_pair: KeyPair, state: Arc<ServerState>) {
+    let mut methods = MethodSet::PUBLICKEY;
+    if state.options.password.is_some() {
+        methods |= MethodSet::PASSWORD;
+    }
+    let config = Arc::new(server::Config {
+        keys: vec![key_pair],
+        methods,
+        auth_rejection_time: Duration::ZERO,
+        auth_rejection_time_initial: Some(Duration::ZERO),
+        inactivity_timeout: Some(Duration::from_secs(60)),
+        ..Default::default()
+    });
+
+    while let Ok((stream, _)) = listener.accept().await {
+        let handler = ConnectionHandler {
+            state: state.clone(),
+            channels: HashMap::new(),
+            stdin: Arc::new(Mutex::new(HashMap::new())),
+        };
+        let config = config.clone();
+        tokio::spawn(async move {
+            match server::run_stream(config, stream, handler).await {
+                Ok(session) => {
+                    if let Err(e) = session.await {
+                        tracing::debug!("[test_server] Session ended: {}", e);
+                    }
+                }
+                Err(e) => tracing::debug!("[test_server] Handshake failed: {}", e),
+            }
+        });
+    }
+}
+
+/// One client connection
+struct ConnectionHandler {
+    state: Arc<ServerState>,
+    /// Session channels not yet used for exec or a subsystem
+    channels: HashMap<ChannelId, Channel<Msg>>,
+    /// Stdin of running commands, closed on EOF
+    stdin: Arc<Mutex<HashMap<ChannelId, ChildStdin>>>,
+}
+
+impl ConnectionHandler {
+    fn authorized(&self, public_key: &PublicKey) -> bool {
+        let path = self.state.options.home.join(".ssh").join("authorized_keys");
+        let blob = public_key.public_key_base64();
+        std::fs::read_to_string(path).is_ok_and(|content| {
+            content
+                .lines()
+                .filter(|l| !l.trim_start().starts_with('#'))
+                .any(|l| l.split_whitespace().any(|field| field == blob))
+        })
+    }
+}
+
+#[async_trait]
+impl server::Handler for ConnectionHandler {
+    type Error = russh::Error;
+
+    async fn auth_publickey(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> Result<Auth, Self::Error> {
+        if user == self.state.options.user && self.authorized(public_key) {
+            Ok(Auth::Accept)
+        } else {
+            Ok(Auth::Reject {
+                proceed_with_methods: None,
+            })
+        }
+    }
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        if user == self.state.options.user
+            && self.state.options.password.as_deref() == Some(password)
+        {
+            Ok(Auth::Accept)
+        } else {
+            Ok(Auth::Reject {
+                proceed_with_methods: None,
+            })
+        }
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        self.channels.insert(channel.id(), channel);
+        Ok(true)
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        // Input reaches the command through `data`, not the channel object
+        self.channels.remove(&channel);
+        let command = String::from_utf8_lossy(data).to_string();
+        self.state.commands.lock().await.push(command.clone());
+
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&self.state.options.home)
+            .env("HOME", &self.state.options.home)
+            .env("USER", &self.state.options.user)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                tracing::warn!("[test_server] Failed to run {}: {}", command, e);
+                session.channel_failure(channel);
+                return Ok(());
+            }
+        };
+        session.channel_success(channel);
+
+        if let Some(stdin) = child.stdin.take() {
+            self.stdin.lock().await.insert(channel, stdin);
+        }
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let handle = session.handle();
+        let stdin = self.stdin.clone();
+        tokio::spawn(async move {
+            let out = forward_output(handle.clone(), channel, stdout, None);
+            let err = forward_output(handle.clone(), channel, stderr, Some(1));
+            tokio::join!(out, err);
+            let status = child
+                .wait()
+                .await
+                .ok()
+                .and_then(|s| s.code())
+                .unwrap_or(255);
+            stdin.lock().await.remove(&channel);
+            let _ = handle.exit_status_request(channel, status as u32).await;
+            let _ = handle.eof(channel).await;
+            let _ = handle.close(channel).await;
+        });
+        Ok(())
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        match self.channels.remove(&channel) {
+            Some(open) if name == "sftp" => {
+                session.channel_success(channel);
+                russh_sftp::server::run(
+                    open.into_stream(),
+                    HomeSftp::new(&self.state.options.home),
+                )
+                .await;
+            }
+            _ => session.channel_failure(channel),
+        }
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(stdin) = self.stdin.lock().await.get_mut(&channel) {
+            let _ = stdin.write_all(data).await;
+        }
+        Ok(())
+    }
+
+    async fn channel_eof(
+        &mut self,
+        channel: ChannelId,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        // Dropping the pipe closes the command's stdin
+        self.stdin.lock().await.remove(&channel);
+        Ok(())
+    }
+}
+
+/// Copy a command's stdout (`ext` None) or stderr (`ext` 1) to the channel
+async fn forward_output(
+    handle: server::Handle,
+    channel: ChannelId,
+    output: Option<impl AsyncRead + Unpin>,
+    ext: Option<u32>,
+) {
+    let Some(mut output) = output else {
+        return;
+    };
+    let mut buf = [0u8; 8192];
+    while let Ok(n) = output.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+        let data = CryptoVec::from_slice(&buf[..n]);
+        let sent = match ext {
+            Some(ext) => handle.extended_data(channel, ext, data).await,
+            None => handle.data(channel, data).await,
+        };
+        if sent.is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use russh_sftp::protocol::{
+    Attrs, Data, File, FileAttributes, Handle, Name, OpenFlags, Status, StatusCode, Version,
+};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// SFTP server over a local directory: absolute paths and paths relative to
+/// the home directory both resolve below it, and `..` never leaves it
+pub(crate) struct HomeSftp {
+    root: PathBuf,
+    next_handle: u64,
+    files: HashMap<String, fs::File>,
+    /// Directory listings; None once they were sent
+    dirs: HashMap<String, Option<Vec<File>>>,
+}
+
+impl HomeSftp {
+    pub(crate) fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            next_handle: 0,
+            files: HashMap::new(),
+            dirs: HashMap::new(),
+        }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        let mut resolved = self.root.clone();
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::ParentDir if resolved != self.root => {
+                    resolved.pop();
+                }
+                _ => {}
+            }
+        }
+        resolved
+    }
+
+    /// Path as the client sees it
+    fn remote_path(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        format!("/{}", relative.to_string_lossy()).replace('\\', "/")
+    }
+
+    fn new_handle(&mut self) -> String {
+        self.next_handle += 1;
+        self.next_handle.to_string()
+    }
+}
+
+fn status(id: u32) -> Status {
+    Status {
+        id,
+        status_code: StatusCode::Ok,
+        error_message: "Ok".to_string(),
+        language_tag: "en-US".to_string(),
+    }
+}
+
+fn io_status(e: std::io::Error) -> StatusCode {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => StatusCode::NoSuchFile,
+        std::io::ErrorKind::PermissionDenied => StatusCode::PermissionDenied,
+        _ => StatusCode::Failure,
+    }
+}
+
+async fn apply_attrs(path: &Path, attrs: &FileAttributes) -> Result<(), StatusCode> {
+    #[cfg(unix)]
+    if let Some(mode) = attrs.permissions {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o7777))
+            .await
+            .map_err(io_status)?;
+    }
+    if let Some(size) = attrs.size {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .await
+            .map_err(io_status)?
+            .set_len(size)
+            .await
+            .map_err(io_status)?;
+    }
+    Ok(())
+}
+
+impl russh_sftp::server::Handler for HomeSftp {
+    type Error = StatusCode;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported
+    }
+
+    async fn init(
+        &mut self,
+        _version: u32,
+        _extensions: HashMap<String, String>,
+    ) -> Result<Version, Self::Error> {
+        Ok(Version::new())
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _attrs: FileAttributes,
+    ) -> Result<Handle, Self::Error> {
+        let path = self.resolve(&filename);
+        let options: std::fs::OpenOptions = pflags.into();
+        let file = fs::OpenOptions::from(options)
+            .open(&path)
+            .await
+            .map_err(io_status)?;
+        let handle = self.new_handle();
+        self.files.insert(handle.clone(), file);
+        Ok(Handle { id, handle })
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+        if let Some(mut file) = self.files.remove(&handle) {
+            file.flush().await.map_err(io_status)?;
+        }
+        self.dirs.remove(&handle);
+        Ok(status(id))
+    }
+
+    async fn read(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, Self::Error> {
+        let file = self.files.get_mut(&handle).ok_or(StatusCode::Failure)?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(io_status)?;
+        let mut data = vec![0u8; len as usize];
+        let n = file.read(&mut data).await.map_err(io_status)?;
+        if n == 0 {
+            return Err(StatusCode::Eof);
+        }
+        data.truncate(n);
+        Ok(Data { id, data })
+    }
+
+    async fn write(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<Status, Self::Error> {
+        let file = self.files.get_mut(&handle).ok_or(StatusCode::Failure)?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(io_status)?;
+        file.write_all(&data).await.map_err(io_status)?;
+        Ok(status(id))
+    }
+
+    async fn lstat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let metadata = fs::symlink_metadata(self.resolve(&path))
+            .await
+            .map_err(io_status)?;
+        Ok(Attrs {
+            id,
+            attrs: (&metadata).into(),
+        })
+    }
+
+    async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let metadata = fs::metadata(self.resolve(&path)).await.map_err(io_status)?;
+        Ok(Attrs {
+            id,
+            attrs: (&metadata).into(),
+        })
+    }
+
+    async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+        let file = self.files.get(&handle).ok_or(StatusCode::Failure)?;
+        let metadata = file.metadata().await.map_err(io_status)?;
+        Ok(Attrs {
+            id,
+            attrs: (&metadata).into(),
+        })
+    }
+
+    async fn setstat(
+        &mut self,
+        id: u32,
+        path: String,
+        attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        apply_attrs(&self.resolve(&path), &attrs).await?;
+        Ok(status(id))
+    }
+
+    async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+        let dir = self.resolve(&path);
+        let mut entries = fs::read_dir(&dir).await.map_err(io_status)?;
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(io_status)? {
+            let metadata = entry.metadata().await.map_err(io_status)?;
+            files.push(File::new(
+                entry.file_name().to_string_lossy(),
+                (&metadata).into(),
+            ));
+        }
+        let handle = self.new_handle();
+        self.dirs.insert(handle.clone(), Some(files));
+        Ok(Handle { id, handle })
+    }
+
+    async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+        match self.dirs.get_mut(&handle) {
+            Some(listing) => match listing.take() {
+                Some(files) if !files.is_empty() => Ok(Name { id, files }),
+                _ => Err(StatusCode::Eof),
+            },
+            None => Err(StatusCode::Failure),
+        }
+    }
+
+    async fn remove(&mut self, id: u32, filename: String) -> Result<Status, Self::Error> {
+        fs::remove_file(self.resolve(&filename))
+            .await
+            .map_err(io_status)?;
+        Ok(status(id))
+    }
+
+    async fn mkdir(
+        &mut self,
+        id: u32,
+        path: String,
+        attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        let dir = self.resolve(&path);
+        fs::create_dir(&dir).await.map_err(io_status)?;
+        apply_attrs(&dir, &attrs).await?;
+        Ok(status(id))
+    }
+
+    async fn rmdir(&mut self, id: u32, path: String) -> Result<Status, Self::Error> {
+        fs::remove_dir(self.resolve(&path))
+            .await
+            .map_err(io_status)?;
+        Ok(status(id))
+    }
+
+    async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+        let resolved = self.resolve(&path);
+        Ok(Name {
+            id,
+            files: vec![File::dummy(self.remote_path(&resolved))],
+        })
+    }
+
+    async fn rename(
+        &mut self,
+        id: u32,
+        oldpath: String,
+        newpath: String,
+    ) -> Result<Status, Self::Error> {
+        fs::rename(self.resolve(&oldpath), self.resolve(&newpath))
+            .await
+            .map_err(io_status)?;
+        Ok(status(id))
+    }
+}
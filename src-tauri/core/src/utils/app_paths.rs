@@ -0,0 +1,260 @@
+use crate::models::{SshBuddyError, SshResult};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// Identifier of the app's data directory below the platform data dir
+pub const APP_ID: &str = "com.sshbuddy";
+
+/// File next to the executable that turns on portable mode
+pub const PORTABLE_MARKER: &str = "ssh-buddy.portable";
+
+/// Directory next to the executable holding the app data in portable mode
+const PORTABLE_DATA_DIR: &str = "data";
+
+/// Overrides the SSH root (for scripted setups and network homes)
+pub const SSH_DIR_ENV: &str = "SSH_BUDDY_SSH_DIR";
+
+/// Directory below the app data dir holding one directory per workspace
+const WORKSPACES_DIR: &str = "workspaces";
+
+/// SSH root chosen in the settings, set by the settings service
+static SSH_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Active workspace other than the default one, set by the workspace service
+static WORKSPACE: RwLock<Option<ActiveWorkspace>> = RwLock::new(None);
+
+#[derive(Debug, Clone)]
+struct ActiveWorkspace {
+    id: String,
+    /// SSH config file of the workspace; None for `<SSH root>/config.d/<id>`
+    config_file: Option<String>,
+}
+
+/// Where the app keeps its data and finds the SSH files
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppPaths {
+    /// App data lives next to the executable
+    pub portable: bool,
+    pub data_dir: String,
+    pub ssh_dir: String,
+    /// The SSH root is not ~/.ssh
+    pub custom_ssh_dir: bool,
+    /// The SSH root is set by SSH_BUDDY_SSH_DIR and can't be changed in the app
+    pub ssh_dir_forced: bool,
+    /// Active workspace; None for the default one
+    pub workspace: Option<String>,
+    /// Data of the active workspace (host registry, vault)
+    pub workspace_dir: String,
+    /// SSH config file of the active workspace
+    pub ssh_config_path: String,
+}
+
+/// Directory of the executable when portable mode is on
+pub fn portable_root() -> Option<&'static Path> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        let exe = std::env::current_exe().ok()?;
+        let dir = exe.parent()?;
+        dir.join(PORTABLE_MARKER)
+            .is_file()
+            .then(|| dir.to_path_buf())
+    })
+    .as_deref()
+}
+
+/// Directory holding all app data: next to the executable in portable mode,
+/// otherwise `<platform data dir>/com.sshbuddy`
+pub fn app_data_dir() -> SshResult<PathBuf> {
+    if let Some(root) = portable_root() {
+        return Ok(root.join(PORTABLE_DATA_DIR));
+    }
+    Ok(dirs::data_dir()
+        .ok_or(SshBuddyError::HomeDirNotFound)?
+        .join(APP_ID))
+}
+
+/// File in the app data directory
+pub fn app_data_path(file: &str) -> SshResult<PathBuf> {
+    Ok(app_data_dir()?.join(file))
+}
+
+/// Data directory of the active workspace (the app data dir for the default one)
+pub fn workspace_data_dir() -> SshResult<PathBuf> {
+    let workspace = WORKSPACE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match workspace {
+        Some(workspace) => Ok(app_data_dir()?.join(WORKSPACES_DIR).join(workspace.id)),
+        None => app_data_dir(),
+    }
+}
+
+/// File in the data directory of the active workspace
+pub fn workspace_data_path(file: &str) -> SshResult<PathBuf> {
+    Ok(workspace_data_dir()?.join(file))
+}
+
+/// Data directory of a workspace other than the default one
+pub fn workspace_dir_of(id: &str) -> SshResult<PathBuf> {
+    Ok(app_data_dir()?.join(WORKSPACES_DIR).join(id))
+}
+
+/// SSH config file of the active workspace (`<SSH root>/config` for the default one)
+pub fn ssh_config_path() -> SshResult<PathBuf> {
+    let workspace = WORKSPACE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match workspace {
+        Some(workspace) => workspace_config_path(&workspace.id, workspace.config_file.as_deref()),
+        None => Ok(ssh_dir()?.join("config")),
+    }
+}
+
+/// SSH config file of a workspace other than the default one
+pub fn workspace_config_path(id: &str, config_file: Option<&str>) -> SshResult<PathBuf> {
+    match config_file.filter(|file| !file.trim().is_empty()) {
+        Some(file) => Ok(resolve_ssh_root(file, portable_root())),
+        None => Ok(ssh_dir()?.join("config.d").join(id)),
+    }
+}
+
+/// Switch the paths to a workspace; None is the default workspace
+pub fn set_workspace(id: Option<&str>, config_file: Option<&str>) {
+    let workspace = id.map(|id| ActiveWorkspace {
+        id: id.to_string(),
+        config_file: config_file.map(str::to_string),
+    });
+    *WORKSPACE.write().unwrap_or_else(|e| e.into_inner()) = workspace;
+}
+
+/// SSH root (`~/.ssh` unless configured): holds config, known_hosts and the keys
+pub fn ssh_dir() -> SshResult<PathBuf> {
+    if let Some(dir) = std::env::var_os(SSH_DIR_ENV).filter(|v| !v.is_empty()) {
+        return Ok(resolve_ssh_root(&dir.to_string_lossy(), portable_root()));
+    }
+    if let Some(dir) = SSH_ROOT.read().unwrap_or_else(|e| e.into_inner()).clone() {
+        return Ok(dir);
+    }
+    default_ssh_dir()
+}
+
+/// `~/.ssh`
+pub fn default_ssh_dir() -> SshResult<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or(SshBuddyError::HomeDirNotFound)?
+        .join(".ssh"))
+}
+
+/// The SSH root differs from `~/.ssh` (external ssh tools then need `-F`)
+pub fn has_custom_ssh_dir() -> bool {
+    match (ssh_dir(), default_ssh_dir()) {
+        (Ok(dir), Ok(default)) => dir != default,
+        _ => false,
+    }
+}
+
+pub fn app_paths() -> SshResult<AppPaths> {
+    Ok(AppPaths {
+        portable: portable_root().is_some(),
+        data_dir: app_data_dir()?.to_string_lossy().to_string(),
+        ssh_dir: ssh_dir()?.to_string_lossy().to_string(),
+        custom_ssh_dir: has_custom_ssh_dir(),
+        ssh_dir_forced: std::env::var_os(SSH_DIR_ENV).is_some_and(|v| !v.is_empty()),
+        workspace: WORKSPACE
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|workspace| workspace.id.clone()),
+        workspace_dir: workspace_data_dir()?.to_string_lossy().to_string(),
+        ssh_config_path: ssh_config_path()?.to_string_lossy().to_string(),
+    })
+}
+
+/// OpenSSH client options pointing at a custom SSH root and the config of the
+/// active workspace (none for `~/.ssh` and the default workspace)
+pub fn ssh_client_options() -> Vec<String> {
+    let (Ok(dir), Ok(config)) = (ssh_dir(), ssh_config_path()) else {
+        return Vec::new();
+    };
+    let mut options = Vec::new();
+    let default_config = default_ssh_dir().map(|d| d.join("config")).ok();
+    if Some(&config) != default_config.as_ref() && config.is_file() {
+        options.extend(["-F".to_string(), config.to_string_lossy().to_string()]);
+    }
+    if has_custom_ssh_dir() {
+        options.extend([
+            "-o".to_string(),
+            format!(
+                "UserKnownHostsFile=\"{}\"",
+                dir.join("known_hosts").display()
+            ),
+        ]);
+    }
+    options
+}
+
+/// Apply the SSH root setting; None goes back to `~/.ssh`
+pub fn set_ssh_root(root: Option<&str>) {
+    let resolved = root
+        .filter(|root| !root.trim().is_empty())
+        .map(|root| resolve_ssh_root(root, portable_root()));
+    *SSH_ROOT.write().unwrap_or_else(|e| e.into_inner()) = resolved;
+}
+
+/// Expand `~` and resolve relative paths against `base` (the portable root, so
+/// the SSH root can move with a USB stick whose drive letter changes)
+pub fn resolve_ssh_root(root: &str, base: Option<&Path>) -> PathBuf {
+    let root = root.trim();
+    let expanded = match root.strip_prefix("~") {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+            None => PathBuf::from(root),
+        },
+        _ => PathBuf::from(root),
+    };
+    match base {
+        Some(base) if expanded.is_relative() => base.join(expanded),
+        _ => expanded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_ssh_root() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(resolve_ssh_root("~/keys", None), home.join("keys"));
+        assert_eq!(resolve_ssh_root("~", None), home);
+        assert_eq!(
+            resolve_ssh_root("ssh", Some(Path::new("/media/usb"))),
+            PathBuf::from("/media/usb/ssh")
+        );
+        assert_eq!(
+            resolve_ssh_root(" /srv/home/ssh ", Some(Path::new("/media/usb"))),
+            PathBuf::from("/srv/home/ssh")
+        );
+        assert_eq!(
+            resolve_ssh_root("~bob/ssh", None),
+            PathBuf::from("~bob/ssh")
+        );
+    }
+
+    #[test]
+    fn test_workspace_paths() {
+        let root = app_data_dir().unwrap();
+        assert_eq!(
+            workspace_dir_of("work").unwrap(),
+            root.join("workspaces").join("work")
+        );
+    }
+
+    #[test]
+    fn test_app_data_dir() {
+        if portable_root().is_none() {
+            assert!(app_data_dir().unwrap().ends_with(APP_ID));
+        }
+        assert!(app_data_path("settings.json")
+            .unwrap()
+            .ends_with("settings.json"));
+    }
+}
@@ -0,0 +1,257 @@
+use crate::models::{SshBuddyError, SshResult};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Mode of files the atomic writers create (SSH wants its files private)
+#[cfg(unix)]
+const NEW_FILE_MODE: u32 = 0o600;
+
+/// Write a file atomically: write to a sibling temp file, then rename over the target
+/// A symlinked target (e.g. a dotfile manager's ~/.ssh/config) is written through so
+/// the link is kept; existing file permissions are preserved and new files are 0600
+pub async fn write_atomic(path: &Path, content: &[u8]) -> SshResult<()> {
+    write_atomic_with(path, content, false).await
+}
+
+/// `write_atomic` for secrets: the file is 0600 from the moment it is created, even
+/// when the file it replaces was readable by others
+pub async fn write_atomic_private(path: &Path, content: &[u8]) -> SshResult<()> {
+    write_atomic_with(path, content, true).await
+}
+
+/// Blocking `write_atomic`, for code running before the async runtime
+pub fn write_atomic_sync(path: &Path, content: &[u8]) -> SshResult<()> {
+    write_atomic_sync_with(path, content, false)
+}
+
+/// Blocking `write_atomic_private`
+pub fn write_atomic_private_sync(path: &Path, content: &[u8]) -> SshResult<()> {
+    write_atomic_sync_with(path, content, true)
+}
+
+async fn write_atomic_with(path: &Path, content: &[u8], private: bool) -> SshResult<()> {
+    let target = resolve_link(path);
+    let temp_path = temp_path(&target)?;
+    let existing = fs::metadata(&target).await.ok();
+
+    // A leftover temp file would keep its own mode
+    let _ = fs::remove_file(&temp_path).await;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(create_mode(existing.as_ref(), private));
+    let written = async {
+        let mut file = options.open(&temp_path).await?;
+        file.write_all(content).await?;
+        file.flush().await
+    }
+    .await;
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(SshBuddyError::IoError {
+            message: format!("Failed to write {}: {}", temp_path.display(), e),
+        });
+    }
+
+    if let Some(metadata) = existing.filter(|_| !private) {
+        fs::set_permissions(&temp_path, metadata.permissions())
+            .await
+            .ok();
+    }
+
+    if let Err(e) = fs::rename(&temp_path, &target).await {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(SshBuddyError::IoError {
+            message: format!("Failed to replace {}: {}", target.display(), e),
+        });
+    }
+
+    Ok(())
+}
+
+fn write_atomic_sync_with(path: &Path, content: &[u8], private: bool) -> SshResult<()> {
+    use std::io::Write;
+
+    let target = resolve_link(path);
+    let temp_path = temp_path(&target)?;
+    let existing = std::fs::metadata(&target).ok();
+
+    let _ = std::fs::remove_file(&temp_path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(create_mode(existing.as_ref(), private));
+    }
+    let written = options
+        .open(&temp_path)
+        .and_then(|mut file| file.write_all(content));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(SshBuddyError::IoError {
+            message: format!("Failed to write {}: {}", temp_path.display(), e),
+        });
+    }
+
+    if let Some(metadata) = existing.filter(|_| !private) {
+        std::fs::set_permissions(&temp_path, metadata.permissions()).ok();
+    }
+
+    if let Err(e) = std::fs::rename(&temp_path, &target) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(SshBuddyError::IoError {
+            message: format!("Failed to replace {}: {}", target.display(), e),
+        });
+    }
+
+    Ok(())
+}
+
+/// The file a write to `path` should replace: symlinks are followed so the rename
+/// doesn't turn the link into a regular file
+fn resolve_link(path: &Path) -> PathBuf {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => std::fs::canonicalize(path)
+            .or_else(|_| {
+                // Dangling link: create the file it points to
+                std::fs::read_link(path).map(|link| match path.parent() {
+                    Some(parent) => parent.join(link),
+                    None => link,
+                })
+            })
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Sibling temp file of `target`
+fn temp_path(target: &Path) -> SshResult<PathBuf> {
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| SshBuddyError::InvalidPath {
+            message: format!("Not a file path: {}", target.display()),
+        })?
+        .to_string_lossy();
+    Ok(target.with_file_name(format!(".{}.tmp", file_name)))
+}
+
+/// Mode to create the temp file with: the target's, or 0600 for a new or private file
+#[cfg(unix)]
+fn create_mode(existing: Option<&std::fs::Metadata>, private: bool) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    existing
+        .filter(|_| !private)
+        .map_or(NEW_FILE_MODE, |metadata| {
+            metadata.permissions().mode() & 0o7777
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_write_atomic_creates_and_replaces() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config");
+
+        write_atomic(&path, b"first").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+
+        write_atomic(&path, b"second").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+
+        // No temp file left behind
+        assert!(!temp.path().join(".config.tmp").exists());
+    }
+
+    #[test]
+    fn test_write_atomic_sync() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("settings.json");
+        write_atomic_sync(&path, b"{}").unwrap();
+        write_atomic_sync(&path, b"{\"version\":1}").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"version\":1}");
+        assert!(!temp.path().join(".settings.json.tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_atomic_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&path, b"new").await.unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_atomic_creates_private_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config");
+        write_atomic(&path, b"new").await.unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+
+        let sync_path = temp.path().join("settings.json");
+        write_atomic_sync(&sync_path, b"{}").unwrap();
+        let mode = std::fs::metadata(&sync_path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_atomic_keeps_symlinks() {
+        let temp = TempDir::new().unwrap();
+        let real = temp.path().join("dotfiles-config");
+        let link = temp.path().join("config");
+        std::fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write_atomic(&link, b"new").await.unwrap();
+        assert!(std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read_to_string(&real).unwrap(), "new");
+
+        write_atomic_sync(&link, b"newer").unwrap();
+        assert!(std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read_to_string(&real).unwrap(), "newer");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_atomic_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("signing_key");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_atomic_private(&path, b"secret").await.unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        write_atomic_private_sync(&path, b"secret").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+    }
+}
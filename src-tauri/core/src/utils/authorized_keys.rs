@@ -0,0 +1,332 @@
+use crate::models::{SshBuddyError, SshResult};
+use serde::{Deserialize, Serialize};
+use ssh_key::PublicKey;
+
+/// Options that restrict what an authorized key may do (see sshd(8), AUTHORIZED_KEYS FILE FORMAT)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizedKeyOptions {
+    /// Disable all forwarding, PTY allocation and ~/.ssh/rc (and anything added in future
+    /// OpenSSH versions); the `permit_*` fields re-enable single features
+    #[serde(default)]
+    pub restrict: bool,
+    /// Client address patterns, e.g. "10.0.0.0/8" or "*.example.com"
+    #[serde(default)]
+    pub from: Vec<String>,
+    /// Forced command, run instead of whatever the client asks for
+    pub command: Option<String>,
+    /// Key stops working after this time: YYYYMMDD[HHMM[SS]], local time or "Z" for UTC
+    pub expiry_time: Option<String>,
+    #[serde(default)]
+    pub no_agent_forwarding: bool,
+    #[serde(default)]
+    pub no_port_forwarding: bool,
+    #[serde(default)]
+    pub no_pty: bool,
+    #[serde(default)]
+    pub no_x11_forwarding: bool,
+    #[serde(default)]
+    pub no_user_rc: bool,
+    /// Re-enable a PTY under `restrict`
+    #[serde(default)]
+    pub permit_pty: bool,
+    /// Local forwarding destinations ("host:port", port may be "*")
+    #[serde(default)]
+    pub permit_open: Vec<String>,
+    /// Remote forwarding listen addresses ("[host:]port")
+    #[serde(default)]
+    pub permit_listen: Vec<String>,
+}
+
+fn invalid(message: String) -> SshBuddyError {
+    SshBuddyError::InvalidOption { message }
+}
+
+/// Quote an option value; sshd allows `\"` inside the quotes
+fn quote_value(option: &str, value: &str) -> SshResult<String> {
+    if value.is_empty() || value.contains(['\n', '\r']) {
+        return Err(invalid(format!(
+            "{} must be a non-empty single line",
+            option
+        )));
+    }
+    Ok(format!("{}=\"{}\"", option, value.replace('"', "\\\"")))
+}
+
+/// Comma-separated list value; each entry must be non-empty and free of separators
+fn list_value(option: &str, entries: &[String]) -> SshResult<String> {
+    let entries: Vec<&str> = entries.iter().map(|e| e.trim()).collect();
+    if let Some(bad) = entries
+        .iter()
+        .find(|e| e.is_empty() || e.contains([',', '"', ' ', '\t', '\n']))
+    {
+        return Err(invalid(format!("Invalid {} entry: \"{}\"", option, bad)));
+    }
+    quote_value(option, &entries.join(","))
+}
+
+/// YYYYMMDD, YYYYMMDDHHMM or YYYYMMDDHHMMSS, optionally followed by "Z"
+fn validate_expiry(value: &str) -> SshResult<()> {
+    let digits = value.strip_suffix(['Z', 'z']).unwrap_or(value);
+    let valid_date = digits
+        .get(4..6)
+        .and_then(|m| m.parse::<u8>().ok())
+        .is_some_and(|m| (1..=12).contains(&m))
+        && digits
+            .get(6..8)
+            .and_then(|d| d.parse::<u8>().ok())
+            .is_some_and(|d| (1..=31).contains(&d));
+    if matches!(digits.len(), 8 | 12 | 14)
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && valid_date
+    {
+        Ok(())
+    } else {
+        Err(invalid(format!(
+            "Invalid expiry time \"{}\" (expected YYYYMMDD[HHMM[SS]][Z])",
+            value
+        )))
+    }
+}
+
+/// "host:port" where port is a number or "*"
+fn validate_permit_open(value: &str) -> SshResult<()> {
+    let valid = value.rsplit_once(':').is_some_and(|(host, port)| {
+        !host.is_empty() && (port == "*" || port.parse::<u16>().is_ok_and(|p| p > 0))
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!(
+            "Invalid permitopen entry \"{}\" (expected host:port)",
+            value
+        )))
+    }
+}
+
+impl AuthorizedKeyOptions {
+    /// Least-privilege options for a key that may only run `command` (e.g. a backup job)
+    pub fn forced_command(command: &str) -> Self {
+        Self {
+            restrict: true,
+            command: Some(command.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Option string for the start of an authorized_keys line (empty when nothing is set)
+    pub fn render(&self) -> SshResult<String> {
+        let mut options: Vec<String> = Vec::new();
+        if self.restrict {
+            options.push("restrict".to_string());
+        }
+        if !self.from.is_empty() {
+            options.push(list_value("from", &self.from)?);
+        }
+        if let Some(command) = self.command.as_deref() {
+            options.push(quote_value("command", command.trim())?);
+        }
+        if let Some(expiry) = self.expiry_time.as_deref().map(str::trim) {
+            validate_expiry(expiry)?;
+            options.push(quote_value("expiry-time", expiry)?);
+        }
+
+        // Implied by restrict
+        if !self.restrict {
+            let flags = [
+                (self.no_agent_forwarding, "no-agent-forwarding"),
+                (self.no_port_forwarding, "no-port-forwarding"),
+                (self.no_pty, "no-pty"),
+                (self.no_x11_forwarding, "no-X11-forwarding"),
+                (self.no_user_rc, "no-user-rc"),
+            ];
+            options.extend(
+                flags
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, name)| name.to_string()),
+            );
+        }
+        if self.permit_pty && (self.restrict || self.no_pty) {
+            options.push("pty".to_string());
+        }
+
+        let forwarding_disabled = self.restrict || self.no_port_forwarding;
+        if (!self.permit_open.is_empty() || !self.permit_listen.is_empty()) && forwarding_disabled {
+            // permitopen/permitlisten only narrow forwarding, they don't enable it
+            options.push("port-forwarding".to_string());
+        }
+        for entry in &self.permit_open {
+            validate_permit_open(entry.trim())?;
+            options.push(quote_value("permitopen", entry.trim())?);
+        }
+        for entry in &self.permit_listen {
+            options.push(quote_value("permitlisten", entry.trim())?);
+        }
+        Ok(options.join(","))
+    }
+}
+
+/// Split a public key line into (type, base64 blob, comment)
+pub fn split_public_key(line: &str) -> SshResult<(&str, &str, &str)> {
+    let line = line.trim();
+    let mut parts = line.splitn(3, char::is_whitespace);
+    match (parts.next(), parts.next()) {
+        (Some(key_type), Some(blob)) if !key_type.is_empty() && !blob.is_empty() => {
+            Ok((key_type, blob, parts.next().unwrap_or_default().trim()))
+        }
+        _ => Err(invalid("Not a public key line".to_string())),
+    }
+}
+
+/// Full authorized_keys line for a public key with options
+pub fn build_authorized_keys_line(
+    public_key: &str,
+    options: &AuthorizedKeyOptions,
+) -> SshResult<String> {
+    let (key_type, blob, comment) = split_public_key(public_key)?;
+    let mut line = options.render()?;
+    if !line.is_empty() {
+        line.push(' ');
+    }
+    line.push_str(key_type);
+    line.push(' ');
+    line.push_str(blob);
+    if !comment.is_empty() {
+        line.push(' ');
+        line.push_str(comment);
+    }
+    Ok(line)
+}
+
+/// Key line of an authorized_keys file
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizedKeyLine {
+    pub key: PublicKey,
+    /// Options in front of the key type (e.g. `restrict,command="..."`), empty if none
+    pub options: String,
+}
+
+/// Keys in authorized_keys content; comments, blank and unparseable lines are skipped
+pub fn parse_authorized_keys(content: &str) -> Vec<AuthorizedKeyLine> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            // The key starts at some word boundary; options may contain quoted spaces
+            let starts = std::iter::once(0).chain(
+                line.char_indices()
+                    .filter(|(i, c)| {
+                        !c.is_whitespace() && line[..*i].ends_with(char::is_whitespace)
+                    })
+                    .map(|(i, _)| i),
+            );
+            starts.find_map(|start| {
+                let key = PublicKey::from_openssh(&line[start..]).ok()?;
+                Some(AuthorizedKeyLine {
+                    key,
+                    options: line[..start].trim().to_string(),
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_backup_key() {
+        let mut options = AuthorizedKeyOptions::forced_command("rrsync -ro /srv/backup");
+        options.from = vec!["10.0.0.0/8".to_string(), "backup.example.com".to_string()];
+        options.expiry_time = Some("20271231".to_string());
+        assert_eq!(
+            options.render().unwrap(),
+            "restrict,from=\"10.0.0.0/8,backup.example.com\",command=\"rrsync -ro /srv/backup\",expiry-time=\"20271231\""
+        );
+    }
+
+    #[test]
+    fn test_render_flags_and_forwarding() {
+        let options = AuthorizedKeyOptions {
+            no_agent_forwarding: true,
+            no_x11_forwarding: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.render().unwrap(),
+            "no-agent-forwarding,no-X11-forwarding"
+        );
+
+        // Flags are implied by restrict; permitopen needs forwarding re-enabled
+        let options = AuthorizedKeyOptions {
+            restrict: true,
+            no_pty: true,
+            permit_pty: true,
+            permit_open: vec!["db.internal:5432".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            options.render().unwrap(),
+            "restrict,pty,port-forwarding,permitopen=\"db.internal:5432\""
+        );
+        assert_eq!(AuthorizedKeyOptions::default().render().unwrap(), "");
+    }
+
+    #[test]
+    fn test_render_rejects_bad_values() {
+        let quoted = AuthorizedKeyOptions::forced_command("echo \"hi\"");
+        assert_eq!(
+            quoted.render().unwrap(),
+            "restrict,command=\"echo \\\"hi\\\"\""
+        );
+        assert!(AuthorizedKeyOptions::forced_command("a\nb")
+            .render()
+            .is_err());
+
+        let mut options = AuthorizedKeyOptions {
+            from: vec!["10.0.0.1, 10.0.0.2".to_string()],
+            ..Default::default()
+        };
+        assert!(options.render().is_err());
+        options.from.clear();
+        options.expiry_time = Some("2027-12-31".to_string());
+        assert!(options.render().is_err());
+        options.expiry_time = Some("202712311200Z".to_string());
+        assert!(options.render().is_ok());
+        options.permit_open = vec!["db.internal".to_string()];
+        assert!(options.render().is_err());
+    }
+
+    #[test]
+    fn test_build_authorized_keys_line() {
+        let options = AuthorizedKeyOptions::forced_command("/usr/local/bin/backup");
+        assert_eq!(
+            build_authorized_keys_line("ssh-ed25519 AAAAC3Nz backup@laptop\n", &options).unwrap(),
+            "restrict,command=\"/usr/local/bin/backup\" ssh-ed25519 AAAAC3Nz backup@laptop"
+        );
+        assert_eq!(
+            build_authorized_keys_line("ssh-ed25519 AAAAC3Nz", &Default::default()).unwrap(),
+            "ssh-ed25519 AAAAC3Nz"
+        );
+        assert!(build_authorized_keys_line("garbage", &Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_authorized_keys() {
+        let key =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+        let content = format!(
+            "# managed\n{} alice@laptop\n\nrestrict,command=\"rrsync -ro /srv\" {} backup\ngarbage line\n",
+            key, key
+        );
+        let lines = parse_authorized_keys(&content);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].options, "");
+        assert_eq!(lines[0].key.comment(), "alice@laptop");
+        assert_eq!(lines[1].options, "restrict,command=\"rrsync -ro /srv\"");
+        assert_eq!(lines[1].key.comment(), "backup");
+    }
+}
@@ -0,0 +1,201 @@
+use crate::models::{SshBuddyError, SshResult};
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+
+/// How far ahead `next_after` looks (covers Feb 29 schedules)
+const MAX_LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+/// Five-field cron expression (minute hour day-of-month month day-of-week) in local time
+/// Supports `*`, lists, ranges, steps and the @hourly/@daily/@weekly/@monthly shortcuts;
+/// like cron, a day matches if either day field does when both are restricted
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> SshResult<Self> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid(expression, "expected 5 fields"));
+        }
+        let mut weekdays = parse_field(fields[4], 0, 7).map_err(|e| invalid(expression, &e))?;
+        // 7 is Sunday as well
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59).map_err(|e| invalid(expression, &e))?,
+            hours: parse_field(fields[1], 0, 23).map_err(|e| invalid(expression, &e))?,
+            days: parse_field(fields[2], 1, 31).map_err(|e| invalid(expression, &e))?,
+            months: parse_field(fields[3], 1, 12).map_err(|e| invalid(expression, &e))?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    /// Whether the schedule fires in the minute of `time`
+    pub fn matches(&self, time: &NaiveDateTime) -> bool {
+        self.matches_day(time) && bit(self.hours, time.hour()) && bit(self.minutes, time.minute())
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        };
+        bit(self.months, time.month()) && day_matches
+    }
+
+    /// First matching minute after `time`
+    pub fn next_after(&self, time: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let limit = *time + Duration::days(MAX_LOOKAHEAD_DAYS);
+        let mut candidate = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        while candidate < limit {
+            if !self.matches_day(&candidate) {
+                candidate = candidate.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, candidate.hour()) {
+                candidate = candidate.with_minute(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, candidate.minute()) {
+                candidate += Duration::minutes(1);
+            } else {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn invalid(expression: &str, reason: &str) -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: format!("Invalid schedule {:?}: {}", expression, reason),
+    }
+}
+
+/// Bit mask of the values a field allows
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("bad step in {}", part))?;
+                if step == 0 {
+                    return Err(format!("bad step in {}", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, part)?, parse_value(end, part)?)
+        } else {
+            let start = parse_value(range, part)?;
+            // `5/15` runs from 5 to the end of the range
+            (start, if step > 1 { max } else { start })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("{} is outside {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, part: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("bad value in {}", part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(CronSchedule::parse("0 9 * * 1-5").is_ok());
+        assert!(CronSchedule::parse("*/15 * * * *").is_ok());
+        assert!(CronSchedule::parse("@daily").is_ok());
+        assert!(CronSchedule::parse("0 9 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 5-2 * * *").is_err());
+        assert!(CronSchedule::parse("0 9 * * mon").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        // 2026-10-16 is a Friday
+        let weekdays = CronSchedule::parse("30 9 * * 1-5").unwrap();
+        assert!(weekdays.matches(&at(2026, 10, 16, 9, 30)));
+        assert!(!weekdays.matches(&at(2026, 10, 17, 9, 30)));
+        assert!(!weekdays.matches(&at(2026, 10, 16, 9, 31)));
+
+        let sunday = CronSchedule::parse("0 0 * * 7").unwrap();
+        assert!(sunday.matches(&at(2026, 10, 18, 0, 0)));
+
+        // Either day field matches when both are restricted
+        let either = CronSchedule::parse("0 12 1 * 5").unwrap();
+        assert!(either.matches(&at(2026, 10, 16, 12, 0)));
+        assert!(either.matches(&at(2026, 11, 1, 12, 0)));
+        assert!(!either.matches(&at(2026, 11, 2, 12, 0)));
+
+        let steps = CronSchedule::parse("5/20 * * * *").unwrap();
+        assert!(steps.matches(&at(2026, 1, 1, 3, 45)));
+        assert!(!steps.matches(&at(2026, 1, 1, 3, 0)));
+    }
+
+    #[test]
+    fn test_next_after() {
+        let daily = CronSchedule::parse("0 9 * * *").unwrap();
+        assert_eq!(
+            daily.next_after(&at(2026, 10, 16, 9, 0)),
+            Some(at(2026, 10, 17, 9, 0))
+        );
+        assert_eq!(
+            daily.next_after(&at(2026, 10, 16, 8, 59)),
+            Some(at(2026, 10, 16, 9, 0))
+        );
+        let weekdays = CronSchedule::parse("15 8 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next_after(&at(2026, 10, 16, 23, 0)),
+            Some(at(2026, 10, 19, 8, 15))
+        );
+        let leap_day = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap_day.next_after(&at(2026, 10, 16, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::models::{SshBuddyError, SshResult};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+pub const KEY_LEN: usize = 32;
+
+/// Argon2id key derivation parameters
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// OWASP recommended minimum for Argon2id
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// AES-256-GCM ciphertext with its nonce, base64 fields
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedValue {
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Password-encrypted data (Argon2id + AES-256-GCM), base64 fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedPayload {
+    pub kdf: KdfParams,
+    pub salt: String,
+    #[serde(flatten)]
+    pub sealed: SealedValue,
+}
+
+impl KdfParams {
+    /// Check the parameters are usable (and not so large they'd hang the app)
+    pub fn validate(&self) -> SshResult<()> {
+        if !(1..=16).contains(&self.parallelism) {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Parallelism must be between 1 and 16".to_string(),
+            });
+        }
+        if self.iterations == 0 || self.iterations > 64 {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Iterations must be between 1 and 64".to_string(),
+            });
+        }
+        if self.memory_kib < 8 * self.parallelism || self.memory_kib > 1024 * 1024 {
+            return Err(SshBuddyError::InvalidOption {
+                message: "Memory must be between 8 KiB per lane and 1 GiB".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Generate a random salt
+pub fn generate_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Derive an AES-256 key from a password
+pub fn derive_key(password: &str, salt: &[u8], params: &KdfParams) -> SshResult<[u8; KEY_LEN]> {
+    let argon2_params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(KEY_LEN),
+    )
+    .map_err(|e| SshBuddyError::Unknown {
+        message: format!("Invalid key derivation parameters: {}", e),
+    })?;
+
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| SshBuddyError::Unknown {
+            message: format!("Key derivation failed: {}", e),
+        })?;
+    Ok(key)
+}
+
+/// Encrypt data with a derived key (fresh nonce every time)
+pub fn encrypt_with_key(key: &[u8; KEY_LEN], plaintext: &[u8]) -> SshResult<SealedValue> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| SshBuddyError::Unknown {
+            message: format!("Encryption failed: {}", e),
+        })?;
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    Ok(SealedValue {
+        nonce: b64.encode(nonce),
+        ciphertext: b64.encode(ciphertext),
+    })
+}
+
+/// Decrypt data sealed with `encrypt_with_key` (fails on a wrong key or tampering)
+pub fn decrypt_with_key(key: &[u8; KEY_LEN], sealed: &SealedValue) -> SshResult<Vec<u8>> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let invalid = |what: &str| SshBuddyError::Unknown {
+        message: format!("Invalid encrypted data: bad {}", what),
+    };
+    let nonce = b64.decode(&sealed.nonce).map_err(|_| invalid("nonce"))?;
+    if nonce.len() != NONCE_LEN {
+        return Err(invalid("nonce"));
+    }
+    let ciphertext = b64
+        .decode(&sealed.ciphertext)
+        .map_err(|_| invalid("ciphertext"))?;
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| SshBuddyError::Unknown {
+            message: "Decryption failed".to_string(),
+        })
+}
+
+/// Encrypt data with a password (fresh salt and nonce every time)
+pub fn encrypt_with_password(
+    plaintext: &[u8],
+    password: &str,
+    params: &KdfParams,
+) -> SshResult<EncryptedPayload> {
+    let salt = generate_salt();
+    let key = derive_key(password, &salt, params)?;
+    Ok(EncryptedPayload {
+        kdf: *params,
+        salt: base64::engine::general_purpose::STANDARD.encode(salt),
+        sealed: encrypt_with_key(&key, plaintext)?,
+    })
+}
+
+/// Decrypt data sealed with `encrypt_with_password`
+pub fn decrypt_with_password(payload: &EncryptedPayload, password: &str) -> SshResult<Vec<u8>> {
+    // The parameters come from the file; refuse ones that would hang the app
+    payload.kdf.validate()?;
+    let salt = base64::engine::general_purpose::STANDARD
+        .decode(&payload.salt)
+        .map_err(|_| SshBuddyError::Unknown {
+            message: "Invalid encrypted data: bad salt".to_string(),
+        })?;
+    let key = derive_key(password, &salt, &payload.kdf)?;
+    decrypt_with_key(&key, &payload.sealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small parameters so the tests stay fast
+    const TEST_PARAMS: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn test_derive_key_is_deterministic() {
+        let a = derive_key("pw", b"0123456789abcdef", &TEST_PARAMS).unwrap();
+        let b = derive_key("pw", b"0123456789abcdef", &TEST_PARAMS).unwrap();
+        let c = derive_key("other", b"0123456789abcdef", &TEST_PARAMS).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_encrypt_uses_fresh_salt_and_nonce() {
+        let a = encrypt_with_password(b"Host a\n", "pw", &TEST_PARAMS).unwrap();
+        let b = encrypt_with_password(b"Host a\n", "pw", &TEST_PARAMS).unwrap();
+        assert_ne!(a.salt, b.salt);
+        assert_ne!(a.sealed.ciphertext, b.sealed.ciphertext);
+        assert_eq!(a.kdf, TEST_PARAMS);
+    }
+
+    #[test]
+    fn test_password_round_trip() {
+        let payload = encrypt_with_password(b"Host a\n", "pw", &TEST_PARAMS).unwrap();
+        assert_eq!(decrypt_with_password(&payload, "pw").unwrap(), b"Host a\n");
+        assert!(decrypt_with_password(&payload, "wrong").is_err());
+    }
+
+    #[test]
+    fn test_key_round_trip() {
+        let key = [7u8; KEY_LEN];
+        let sealed = encrypt_with_key(&key, b"secret").unwrap();
+        assert_eq!(decrypt_with_key(&key, &sealed).unwrap(), b"secret");
+        assert!(decrypt_with_key(&[8u8; KEY_LEN], &sealed).is_err());
+    }
+
+    #[test]
+    fn test_validate_params() {
+        assert!(KdfParams::default().validate().is_ok());
+        assert!(TEST_PARAMS.validate().is_ok());
+        let too_big = KdfParams {
+            memory_kib: 4 * 1024 * 1024,
+            ..KdfParams::default()
+        };
+        assert!(too_big.validate().is_err());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let params = KdfParams {
+            memory_kib: 1,
+            iterations: 0,
+            parallelism: 1,
+        };
+        assert!(derive_key("pw", b"0123456789abcdef", &params).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::models::{SshBuddyError, SshResult};
+use serde::Serialize;
+
+/// Host from an `ssh://` link
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshTarget {
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Host key fingerprint from the `;fingerprint=` parameter, if given
+    pub fingerprint: Option<String>,
+}
+
+/// What a link asks the app to do
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DeepLink {
+    /// `ssh://[user[;fingerprint=..]@]host[:port]` or `sshbuddy://connect?host=..&user=..&port=..`
+    Connect { target: SshTarget },
+    /// `sshbuddy://connect/<alias>`
+    ConnectAlias { alias: String },
+    /// `sshbuddy://host/<alias>`
+    ShowHost { alias: String },
+    /// `sshbuddy://observe?addr=<ip>:<port>&share=<id>&key=<secret>`, a read-only view
+    /// of a terminal session shared from another instance of the app
+    #[serde(rename_all = "camelCase")]
+    ObserveSession {
+        address: String,
+        share_id: String,
+        key: String,
+    },
+}
+
+fn invalid(message: impl Into<String>) -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: format!("Invalid link: {}", message.into()),
+    }
+}
+
+/// Decode %XX escapes (invalid escapes are kept as-is)
+pub fn percent_decode(value: &str) -> SshResult<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid("not valid UTF-8"))
+}
+
+/// Reject values ssh could read as an option or that can't appear in a config
+fn check_safe(kind: &str, value: &str) -> SshResult<()> {
+    if value.is_empty() {
+        return Err(invalid(format!("{} is empty", kind)));
+    }
+    if value.starts_with('-') || value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(invalid(format!("{} \"{}\" is not allowed", kind, value)));
+    }
+    Ok(())
+}
+
+fn check_host(host: &str) -> SshResult<()> {
+    check_safe("host", host)?;
+    let valid = host
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '%'));
+    if !valid {
+        return Err(invalid(format!("host \"{}\" is not allowed", host)));
+    }
+    Ok(())
+}
+
+fn parse_port(port: &str) -> SshResult<u16> {
+    port.parse::<u16>()
+        .ok()
+        .filter(|p| *p > 0)
+        .ok_or_else(|| invalid(format!("port \"{}\" is not valid", port)))
+}
+
+/// `host`, `host:port`, `[v6]` or `[v6]:port`
+fn parse_host_port(value: &str) -> SshResult<(String, Option<u16>)> {
+    let (host, port) = if let Some(rest) = value.strip_prefix('[') {
+        let (host, after) = rest.split_once(']').ok_or_else(|| invalid("unclosed ["))?;
+        match after {
+            "" => (host, None),
+            _ => match after.strip_prefix(':') {
+                Some(port) => (host, Some(parse_port(port)?)),
+                None => return Err(invalid(format!("unexpected \"{}\"", after))),
+            },
+        }
+    } else {
+        match value.rsplit_once(':') {
+            Some((host, port)) => (host, Some(parse_port(port)?)),
+            None => (value, None),
+        }
+    };
+    check_host(host)?;
+    Ok((host.to_string(), port))
+}
+
+fn parse_ssh(rest: &str) -> SshResult<DeepLink> {
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let (userinfo, host_port) = match authority.rsplit_once('@') {
+        Some((userinfo, host_port)) => (Some(userinfo), host_port),
+        None => (None, authority),
+    };
+    let (host, port) = parse_host_port(host_port)?;
+
+    let mut user = None;
+    let mut fingerprint = None;
+    if let Some(userinfo) = userinfo {
+        let mut parts = userinfo.split(';');
+        let name = percent_decode(parts.next().unwrap_or_default())?;
+        check_safe("user", &name)?;
+        user = Some(name);
+        for param in parts {
+            if let Some(value) = param.strip_prefix("fingerprint=") {
+                fingerprint = Some(percent_decode(value)?);
+            }
+        }
+    }
+
+    Ok(DeepLink::Connect {
+        target: SshTarget {
+            host,
+            user,
+            port,
+            fingerprint,
+        },
+    })
+}
+
+fn parse_sshbuddy(rest: &str) -> SshResult<DeepLink> {
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let path = path.trim_end_matches('/');
+    let (action, alias) = path.split_once('/').unwrap_or((path, ""));
+
+    if !alias.is_empty() {
+        let alias = percent_decode(alias)?;
+        check_safe("host alias", &alias)?;
+        return match action {
+            "connect" => Ok(DeepLink::ConnectAlias { alias }),
+            "host" => Ok(DeepLink::ShowHost { alias }),
+            _ => Err(invalid(format!("unknown action \"{}\"", action))),
+        };
+    }
+    if action == "observe" {
+        return parse_observe(query);
+    }
+    if action != "connect" {
+        return Err(invalid(format!("unknown action \"{}\"", action)));
+    }
+
+    let mut host = None;
+    let mut user = None;
+    let mut port = None;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(&value.replace('+', " "))?;
+        match key {
+            "host" => host = Some(value),
+            "user" => user = Some(value),
+            "port" => port = Some(parse_port(&value)?),
+            _ => {}
+        }
+    }
+    let host = host.ok_or_else(|| invalid("host is missing"))?;
+    check_host(&host)?;
+    if let Some(user) = &user {
+        check_safe("user", user)?;
+    }
+    Ok(DeepLink::Connect {
+        target: SshTarget {
+            host,
+            user,
+            port,
+            fingerprint: None,
+        },
+    })
+}
+
+fn parse_observe(query: &str) -> SshResult<DeepLink> {
+    let mut address = None;
+    let mut share_id = None;
+    let mut key = None;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value)?;
+        match name {
+            "addr" => address = Some(value),
+            "share" => share_id = Some(value),
+            "key" => key = Some(value),
+            _ => {}
+        }
+    }
+    let address = address.ok_or_else(|| invalid("addr is missing"))?;
+    let (host, port) = parse_host_port(&address)?;
+    let port = port.ok_or_else(|| invalid("addr has no port"))?;
+    let token = |name: &str, value: Option<String>| {
+        value
+            .filter(|v| {
+                !v.is_empty()
+                    && v.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+            .ok_or_else(|| invalid(format!("{} is missing or malformed", name)))
+    };
+    Ok(DeepLink::ObserveSession {
+        address: if host.contains(':') {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        },
+        share_id: token("share", share_id)?,
+        key: token("key", key)?,
+    })
+}
+
+/// Parse an `ssh://` or `sshbuddy://` link
+pub fn parse_deep_link(url: &str) -> SshResult<DeepLink> {
+    let url = url.trim();
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| invalid("missing scheme"))?;
+    match scheme.to_ascii_lowercase().as_str() {
+        "ssh" => parse_ssh(rest),
+        "sshbuddy" => parse_sshbuddy(rest),
+        other => Err(invalid(format!("unsupported scheme \"{}\"", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(link: DeepLink) -> SshTarget {
+        match link {
+            DeepLink::Connect { target } => target,
+            other => panic!("not a connect link: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ssh_links() {
+        let t = target(parse_deep_link("ssh://deploy@web.example.com:2222").unwrap());
+        assert_eq!(t.host, "web.example.com");
+        assert_eq!(t.user.as_deref(), Some("deploy"));
+        assert_eq!(t.port, Some(2222));
+
+        let t = target(parse_deep_link("SSH://[2001:db8::1]:22/").unwrap());
+        assert_eq!(t.host, "2001:db8::1");
+        assert_eq!(t.user, None);
+
+        let t =
+            target(parse_deep_link("ssh://a%40b;fingerprint=ssh-ed25519-abc@10.0.0.5").unwrap());
+        assert_eq!(t.user.as_deref(), Some("a@b"));
+        assert_eq!(t.fingerprint.as_deref(), Some("ssh-ed25519-abc"));
+        assert_eq!(t.port, None);
+    }
+
+    #[test]
+    fn test_parse_sshbuddy_links() {
+        assert_eq!(
+            parse_deep_link("sshbuddy://connect/prod%20db")
+                .unwrap_err()
+                .to_string(),
+            "Invalid config option: Invalid link: host alias \"prod db\" is not allowed"
+        );
+        assert_eq!(
+            parse_deep_link("sshbuddy://host/web-1/").unwrap(),
+            DeepLink::ShowHost {
+                alias: "web-1".to_string()
+            }
+        );
+        let t = target(
+            parse_deep_link("sshbuddy://connect?host=db.internal&user=pg&port=2200").unwrap(),
+        );
+        assert_eq!(t.host, "db.internal");
+        assert_eq!(t.user.as_deref(), Some("pg"));
+        assert_eq!(t.port, Some(2200));
+        assert!(parse_deep_link("sshbuddy://delete/web").is_err());
+    }
+
+    #[test]
+    fn test_parse_observe_links() {
+        assert_eq!(
+            parse_deep_link("sshbuddy://observe?addr=192.168.1.20:41234&share=9f2c&key=a-b_C1")
+                .unwrap(),
+            DeepLink::ObserveSession {
+                address: "192.168.1.20:41234".to_string(),
+                share_id: "9f2c".to_string(),
+                key: "a-b_C1".to_string(),
+            }
+        );
+        assert!(parse_deep_link("sshbuddy://observe?addr=192.168.1.20&share=9f2c&key=k").is_err());
+        assert!(parse_deep_link("sshbuddy://observe?addr=10.0.0.1:22&share=a%20b&key=k").is_err());
+        assert!(parse_deep_link("sshbuddy://observe?addr=10.0.0.1:22&share=ab").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unsafe_links() {
+        assert!(parse_deep_link("ssh://-oProxyCommand=calc").is_err());
+        assert!(parse_deep_link("ssh://-l@host").is_err());
+        assert!(parse_deep_link("ssh://host:0").is_err());
+        assert!(parse_deep_link("ssh://host name").is_err());
+        assert!(parse_deep_link("sshbuddy://connect?user=root").is_err());
+        assert!(parse_deep_link("http://example.com").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use crate::models::{SshBuddyError, SshResult};
+
+const MINUTE_MS: i64 = 60 * 1000;
+const DAY_MS: i64 = 24 * 60 * MINUTE_MS;
+
+/// Window of `connected recently` and `failed recently`
+const RECENT_MS: i64 = 7 * DAY_MS;
+
+/// What a smart group query sees of a host
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroupHost {
+    pub alias: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    /// Start of the last successful session, Unix milliseconds
+    pub last_connected: Option<i64>,
+    /// Last failed connection attempt, Unix milliseconds
+    pub last_failed: Option<i64>,
+}
+
+/// Smart group query, e.g. `tag=prod AND (failed recently OR NOT connected within 30d)`
+///
+/// Terms: `tag=<tag>`, `tag!=<tag>`, `user=<user>`, `alias~<text>`, `hostname~<text>`,
+/// `favorite`, and `connected` / `failed` followed by `within <n>m|h|d|w`, `recently`
+/// (7 days) or nothing (ever). Terms combine with AND, OR, NOT and parentheses;
+/// AND binds tighter than OR and keywords are case-insensitive
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupQuery {
+    And(Box<GroupQuery>, Box<GroupQuery>),
+    Or(Box<GroupQuery>, Box<GroupQuery>),
+    Not(Box<GroupQuery>),
+    Tag(String),
+    User(String),
+    /// Case-insensitive substring of the alias
+    AliasContains(String),
+    /// Case-insensitive substring of the HostName
+    HostnameContains(String),
+    Favorite,
+    /// Successful session within this many milliseconds (None: ever)
+    Connected(Option<i64>),
+    /// Failed attempt within this many milliseconds (None: ever)
+    Failed(Option<i64>),
+}
+
+impl GroupQuery {
+    pub fn parse(query: &str) -> SshResult<Self> {
+        let tokens = tokenize(query);
+        if tokens.is_empty() {
+            return Err(invalid(query, "it is empty"));
+        }
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+        };
+        let parsed = parser.or().map_err(|reason| invalid(query, &reason))?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(query, &format!("unexpected {:?}", token)));
+        }
+        Ok(parsed)
+    }
+
+    /// Whether a host belongs to the group at time `now` (Unix milliseconds)
+    pub fn matches(&self, host: &GroupHost, now: i64) -> bool {
+        let within = |time: Option<i64>, window: &Option<i64>| {
+            time.is_some_and(|t| window.map_or(true, |w| now - t <= w))
+        };
+        let contains =
+            |value: &str, needle: &str| value.to_lowercase().contains(&needle.to_lowercase());
+        match self {
+            GroupQuery::And(a, b) => a.matches(host, now) && b.matches(host, now),
+            GroupQuery::Or(a, b) => a.matches(host, now) || b.matches(host, now),
+            GroupQuery::Not(inner) => !inner.matches(host, now),
+            GroupQuery::Tag(tag) => host.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            GroupQuery::User(user) => host.user.as_deref() == Some(user.as_str()),
+            GroupQuery::AliasContains(text) => contains(&host.alias, text),
+            GroupQuery::HostnameContains(text) => {
+                host.hostname.as_deref().is_some_and(|h| contains(h, text))
+            }
+            GroupQuery::Favorite => host.favorite,
+            GroupQuery::Connected(window) => within(host.last_connected, window),
+            GroupQuery::Failed(window) => within(host.last_failed, window),
+        }
+    }
+}
+
+fn invalid(query: &str, reason: &str) -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: format!("Invalid group query {:?}: {}", query, reason),
+    }
+}
+
+/// Words split at whitespace, with parentheses as tokens of their own
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in query.chars() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// `30m`, `12h`, `7d`, `2w` in milliseconds
+fn parse_duration(value: &str) -> Option<i64> {
+    let unit = value.chars().last()?;
+    let count: i64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let unit_ms = match unit.to_ascii_lowercase() {
+        'm' => MINUTE_MS,
+        'h' => 60 * MINUTE_MS,
+        'd' => DAY_MS,
+        'w' => 7 * DAY_MS,
+        _ => return None,
+    };
+    count.checked_mul(unit_ms).filter(|ms| *ms > 0)
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        let token = self.tokens.get(self.pos)?;
+        self.pos += 1;
+        Some(token)
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.eq_ignore_ascii_case(keyword));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<GroupQuery, String> {
+        let mut query = self.and()?;
+        while self.keyword("or") {
+            query = GroupQuery::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<GroupQuery, String> {
+        let mut query = self.unary()?;
+        while self.keyword("and") {
+            query = GroupQuery::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<GroupQuery, String> {
+        if self.keyword("not") {
+            return Ok(GroupQuery::Not(Box::new(self.unary()?)));
+        }
+        if self.keyword("(") {
+            let query = self.or()?;
+            if !self.keyword(")") {
+                return Err("missing )".to_string());
+            }
+            return Ok(query);
+        }
+        self.term()
+    }
+
+    fn term(&mut self) -> Result<GroupQuery, String> {
+        let token = self
+            .next()
+            .ok_or_else(|| "a term is missing at the end".to_string())?
+            .to_string();
+        let lower = token.to_lowercase();
+        match lower.as_str() {
+            "favorite" | "pinned" => return Ok(GroupQuery::Favorite),
+            "connected" => return Ok(GroupQuery::Connected(self.window()?)),
+            "failed" => return Ok(GroupQuery::Failed(self.window()?)),
+            _ => {}
+        }
+
+        let comparison = ["!=", "=", "~"].iter().find_map(|op| {
+            token
+                .split_once(op)
+                .map(|(field, value)| (field, *op, value))
+        });
+        let Some((field, op, value)) = comparison else {
+            return Err(format!("unknown term {:?}", token));
+        };
+        if value.is_empty() {
+            return Err(format!("{:?} has no value", token));
+        }
+        let value = value.to_string();
+        match (field.to_lowercase().as_str(), op) {
+            ("tag", "=") => Ok(GroupQuery::Tag(value)),
+            ("tag", "!=") => Ok(GroupQuery::Not(Box::new(GroupQuery::Tag(value)))),
+            ("user", "=") => Ok(GroupQuery::User(value)),
+            ("user", "!=") => Ok(GroupQuery::Not(Box::new(GroupQuery::User(value)))),
+            ("alias", "~") => Ok(GroupQuery::AliasContains(value)),
+            ("hostname", "~") => Ok(GroupQuery::HostnameContains(value)),
+            _ => Err(format!("unknown term {:?}", token)),
+        }
+    }
+
+    /// Optional `within <duration>` or `recently` after `connected` / `failed`
+    fn window(&mut self) -> Result<Option<i64>, String> {
+        if self.keyword("recently") {
+            return Ok(Some(RECENT_MS));
+        }
+        if self.keyword("within") {
+            let value = self
+                .next()
+                .ok_or_else(|| "within needs a duration like 7d".to_string())?;
+            return parse_duration(value)
+                .map(Some)
+                .ok_or_else(|| format!("bad duration {:?}", value));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 100 * DAY_MS;
+
+    fn host(alias: &str, tags: &[&str], connected_days_ago: Option<i64>) -> GroupHost {
+        GroupHost {
+            alias: alias.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            last_connected: connected_days_ago.map(|d| NOW - d * DAY_MS),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let query = GroupQuery::parse("tag=a OR tag=b AND NOT favorite").unwrap();
+        assert_eq!(
+            query,
+            GroupQuery::Or(
+                Box::new(GroupQuery::Tag("a".to_string())),
+                Box::new(GroupQuery::And(
+                    Box::new(GroupQuery::Tag("b".to_string())),
+                    Box::new(GroupQuery::Not(Box::new(GroupQuery::Favorite))),
+                )),
+            )
+        );
+        assert_eq!(
+            GroupQuery::parse("(connected within 2h)").unwrap(),
+            GroupQuery::Connected(Some(2 * 60 * MINUTE_MS))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for query in [
+            "",
+            "tag=",
+            "color=red",
+            "connected within",
+            "connected within 7y",
+            "(tag=a",
+            "tag=a tag=b",
+            "tag=a AND",
+        ] {
+            assert!(GroupQuery::parse(query).is_err(), "{:?}", query);
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let recent = GroupQuery::parse("connected within 7d").unwrap();
+        assert!(recent.matches(&host("web", &[], Some(3)), NOW));
+        assert!(!recent.matches(&host("web", &[], Some(8)), NOW));
+        assert!(!recent.matches(&host("web", &[], None), NOW));
+
+        let mut failing = host("db", &["Prod"], Some(30));
+        failing.last_failed = Some(NOW - DAY_MS);
+        let query = GroupQuery::parse("tag=prod and failed recently").unwrap();
+        assert!(query.matches(&failing, NOW));
+        assert!(!query.matches(&host("db", &["prod"], Some(1)), NOW));
+
+        let never = GroupQuery::parse("NOT connected AND alias~DB").unwrap();
+        assert!(never.matches(&host("old-db", &[], None), NOW));
+        assert!(!never.matches(&failing, NOW));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::models::{SshBuddyError, SshResult};
+use std::net::SocketAddr;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{timeout_at, Duration, Instant};
+
+/// Delay before starting the next connection attempt (RFC 8305 recommends 250ms)
+pub const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// AddressFamily option of an SSH config host
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AddressFamily {
+    #[default]
+    Any,
+    Inet,
+    Inet6,
+}
+
+impl AddressFamily {
+    /// Parse an AddressFamily value ("any", "inet", "inet6")
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "inet" => AddressFamily::Inet,
+            "inet6" => AddressFamily::Inet6,
+            _ => AddressFamily::Any,
+        }
+    }
+
+    fn allows(self, addr: &SocketAddr) -> bool {
+        match self {
+            AddressFamily::Any => true,
+            AddressFamily::Inet => addr.is_ipv4(),
+            AddressFamily::Inet6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// Resolve A and AAAA records, filtered by family and interleaved for racing
+pub async fn resolve_addresses(
+    hostname: &str,
+    port: u16,
+    family: AddressFamily,
+) -> SshResult<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((hostname, port))
+        .await
+        .map_err(|e| SshBuddyError::DnsResolutionFailed {
+            hostname: format!("{}: {}", hostname, e),
+        })?
+        .filter(|addr| family.allows(addr))
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(SshBuddyError::DnsResolutionFailed {
+            hostname: match family {
+                AddressFamily::Any => hostname.to_string(),
+                _ => format!("{} (no {:?} address)", hostname, family),
+            },
+        });
+    }
+
+    Ok(interleave_families(addrs))
+}
+
+/// Alternate IPv6/IPv4 addresses, starting with the family the resolver preferred (RFC 8305 section 4)
+fn interleave_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let prefer_v6 = addrs.first().is_some_and(|addr| addr.is_ipv6());
+    let (v6, v4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addrs.into_iter().partition(|addr| addr.is_ipv6());
+    let (first, second) = if prefer_v6 { (v6, v4) } else { (v4, v6) };
+
+    let mut result = Vec::with_capacity(first.len() + second.len());
+    let mut first = first.into_iter();
+    let mut second = second.into_iter();
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => result.extend(a.into_iter().chain(b)),
+        }
+    }
+    result
+}
+
+/// Race TCP connections to the addresses, starting a new attempt every `attempt_delay`
+/// (or immediately when the previous one fails). Returns the first established stream.
+pub async fn connect_happy_eyeballs(
+    addrs: &[SocketAddr],
+    attempt_delay: Duration,
+) -> SshResult<(TcpStream, SocketAddr)> {
+    let (tx, mut rx) = mpsc::channel(addrs.len().max(1));
+    let mut handles = Vec::with_capacity(addrs.len());
+    let mut pending = 0;
+    let mut last_error: Option<(SocketAddr, std::io::Error)> = None;
+
+    for &addr in addrs {
+        let tx = tx.clone();
+        handles.push(tokio::spawn(async move {
+            let result = TcpStream::connect(addr).await;
+            let _ = tx.send((addr, result)).await;
+        }));
+        pending += 1;
+
+        // Give the attempts in flight until the deadline; a failure starts the next one early
+        let deadline = Instant::now() + attempt_delay;
+        while pending > 0 {
+            match timeout_at(deadline, rx.recv()).await {
+                Ok(Some((addr, Ok(stream)))) => {
+                    handles.iter().for_each(|h| h.abort());
+                    return Ok((stream, addr));
+                }
+                Ok(Some((addr, Err(e)))) => {
+                    tracing::debug!("[happy_eyeballs] {} failed: {}", addr, e);
+                    pending -= 1;
+                    last_error = Some((addr, e));
+                    break;
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+    }
+    drop(tx);
+
+    while pending > 0 {
+        match rx.recv().await {
+            Some((addr, Ok(stream))) => {
+                handles.iter().for_each(|h| h.abort());
+                return Ok((stream, addr));
+            }
+            Some((addr, Err(e))) => {
+                tracing::debug!("[happy_eyeballs] {} failed: {}", addr, e);
+                pending -= 1;
+                last_error = Some((addr, e));
+            }
+            None => break,
+        }
+    }
+
+    Err(match last_error {
+        Some((addr, e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            SshBuddyError::ConnectionRefused {
+                message: format!("{}: {}", addr, e),
+            }
+        }
+        Some((addr, e)) => SshBuddyError::IoError {
+            message: format!("Failed to connect to {}: {}", addr, e),
+        },
+        None => SshBuddyError::IoError {
+            message: "No addresses to connect to".to_string(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_address_family_parse() {
+        assert_eq!(AddressFamily::parse("inet"), AddressFamily::Inet);
+        assert_eq!(AddressFamily::parse("INET6"), AddressFamily::Inet6);
+        assert_eq!(AddressFamily::parse("any"), AddressFamily::Any);
+        assert_eq!(AddressFamily::parse("bogus"), AddressFamily::Any);
+    }
+
+    #[test]
+    fn test_interleave_families() {
+        let addrs = vec![
+            addr("[2001:db8::1]:22"),
+            addr("[2001:db8::2]:22"),
+            addr("192.0.2.1:22"),
+        ];
+        assert_eq!(
+            interleave_families(addrs),
+            vec![
+                addr("[2001:db8::1]:22"),
+                addr("192.0.2.1:22"),
+                addr("[2001:db8::2]:22"),
+            ]
+        );
+
+        // Resolver preferred IPv4
+        let addrs = vec![addr("192.0.2.1:22"), addr("[2001:db8::1]:22")];
+        assert_eq!(interleave_families(addrs.clone()), addrs);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_filters_family() {
+        let addrs = resolve_addresses("127.0.0.1", 22, AddressFamily::Inet)
+            .await
+            .unwrap();
+        assert_eq!(addrs, vec![addr("127.0.0.1:22")]);
+
+        assert!(resolve_addresses("127.0.0.1", 22, AddressFamily::Inet6)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_skips_failed_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let good = listener.local_addr().unwrap();
+
+        // Grab a free port and close it so connecting to it is refused
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bad = closed.local_addr().unwrap();
+        drop(closed);
+
+        let (_stream, connected) = connect_happy_eyeballs(&[bad, good], Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(connected, good);
+    }
+
+    #[tokio::test]
+    async fn test_connect_all_failed() {
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bad = closed.local_addr().unwrap();
+        drop(closed);
+
+        let err = connect_happy_eyeballs(&[bad], CONNECTION_ATTEMPT_DELAY)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SshBuddyError::ConnectionRefused { .. }));
+    }
+}
@@ -0,0 +1,696 @@
+use std::collections::BTreeMap;
+
+/// Locale every key has a template in; other locales fall back to it per key
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locales with a (possibly partial) catalog
+pub const LOCALES: &[&str] = &["en", "zh-TW"];
+
+/// Message templates; `{name}` is replaced by the parameter of that name
+const EN: &[(&str, &str)] = &[
+    // Errors (see SshBuddyError::localized)
+    ("error.keyNotFound", "Key not found: {path}"),
+    ("error.invalidKeyFormat", "Invalid key format: {message}"),
+    ("error.keyAlreadyExists", "Key already exists: {name}"),
+    (
+        "error.keyPermissionsTooOpen",
+        "Key permissions too open: {path}",
+    ),
+    ("error.invalidPath", "Invalid path: {message}"),
+    (
+        "error.pathTraversalDetected",
+        "Path traversal detected: {path}",
+    ),
+    ("error.invalidKeyName", "Invalid key name: {message}"),
+    ("error.hostKeyChanged", "Host key changed: {hostname}"),
+    ("error.hostKeyUnknown", "Host key unknown: {hostname}"),
+    ("error.connectionRefused", "Connection refused: {message}"),
+    ("error.connectionTimeout", "Connection timeout"),
+    (
+        "error.dnsResolutionFailed",
+        "DNS resolution failed: {hostname}",
+    ),
+    ("error.proxyError", "Proxy error: {message}"),
+    (
+        "error.hostNotFound",
+        "Host not found in SSH config: {alias}",
+    ),
+    (
+        "error.hostAlreadyExists",
+        "Host already exists in SSH config: {alias}",
+    ),
+    ("error.invalidHostAlias", "Invalid host alias: {alias}"),
+    ("error.invalidOption", "Invalid config option: {message}"),
+    ("error.invalidTemplate", "Invalid host template: {message}"),
+    (
+        "error.secretsDetected",
+        "Secrets detected in export ({count} found)",
+    ),
+    ("error.vaultLocked", "Vault is locked"),
+    ("error.vaultNotFound", "Vault not found"),
+    ("error.invalidMasterPassword", "Incorrect master password"),
+    ("error.permissionDenied", "Permission denied: {reason}"),
+    (
+        "error.passphraseRequired",
+        "Passphrase required for key: {path}",
+    ),
+    ("error.keyNotInAgent", "Key not in agent: {path}"),
+    (
+        "error.readOnlyMode",
+        "Read-only mode is on, cannot {operation}",
+    ),
+    ("error.ioError", "IO error: {message}"),
+    ("error.agentNotRunning", "Agent not running"),
+    ("error.homeDirNotFound", "Home directory not found"),
+    ("error.unknown", "Unknown error: {message}"),
+    // Permissions
+    ("permission.keyValid", "Key permissions are correct"),
+    (
+        "permission.keyTooOpen",
+        "Key permissions are {mode} but should be {expected}. File is too accessible.",
+    ),
+    (
+        "permission.keyCheckFailed",
+        "Failed to check file permissions",
+    ),
+    (
+        "permission.keyValidAcl",
+        "Key permissions are correct (restricted to current user)",
+    ),
+    (
+        "permission.keySharedAcl",
+        "Key file is accessible by other users. Consider restricting permissions.",
+    ),
+    (
+        "permission.keyUnverified",
+        "Unable to verify key permissions",
+    ),
+    ("permission.keyModeSet", "Permissions set to {mode}"),
+    (
+        "permission.keyRestricted",
+        "Permissions restricted to current user ({user}) only",
+    ),
+    (
+        "permission.keySetFailed",
+        "Failed to set permissions: {error}",
+    ),
+    (
+        "permission.keyAllowedByPolicy",
+        "Key permissions are {mode}, allowed by the {profile} permission policy",
+    ),
+    ("permission.sshDirMissing", "SSH directory does not exist"),
+    (
+        "permission.sshDirValid",
+        "SSH directory permissions are correct",
+    ),
+    (
+        "permission.sshDirTooOpen",
+        "SSH directory permissions are {mode} but should be {expected}",
+    ),
+    (
+        "permission.sshDirAllowedByPolicy",
+        "SSH directory permissions are {mode}, allowed by the {profile} permission policy",
+    ),
+    (
+        "permission.sshDirCheckFailed",
+        "Failed to check directory permissions",
+    ),
+    (
+        "permission.sshDirAcl",
+        "SSH directory exists with Windows ACL permissions",
+    ),
+    (
+        "permission.sshDirModeSet",
+        "SSH directory permissions set to {mode}",
+    ),
+    (
+        "permission.sshDirRestricted",
+        "SSH directory permissions restricted to current user ({user}) only",
+    ),
+    (
+        "permission.sshDirSetFailed",
+        "Failed to set directory permissions: {error}",
+    ),
+    (
+        "permission.checkUnavailable",
+        "Permissions can't be checked on this system ({reason})",
+    ),
+    (
+        "permission.fixUnavailable",
+        "Permissions can't be changed on this system ({reason})",
+    ),
+    (
+        "permission.sshDirSynced",
+        "SSH directory {path} is inside the synced folder {folder}; private keys may be uploaded to the cloud",
+    ),
+    // Agent
+    ("agent.alreadyLoaded", "Key is already loaded in the agent"),
+    (
+        "agent.passphraseRequired",
+        "This key requires a passphrase.",
+    ),
+    (
+        "agent.passphraseManual",
+        "This key requires a passphrase. Please run 'ssh-add' manually in terminal.",
+    ),
+    (
+        "agent.incorrectPassphrase",
+        "Incorrect passphrase. Please try again.",
+    ),
+    ("agent.keyAdded", "Key added to SSH agent successfully"),
+    ("agent.keyRemoved", "Key removed from SSH agent"),
+    ("agent.sshAddOutput", "{output}"),
+    ("agent.sshAddFailed", "Failed to run ssh-add: {error}"),
+    ("agent.internalError", "Internal error: {error}"),
+    ("agent.timedOut", "Operation timed out. Please try again."),
+    // known_hosts
+    ("knownHosts.fileMissing", "known_hosts file does not exist"),
+    ("knownHosts.removed", "Removed {count} entries for {host}"),
+    ("knownHosts.noEntries", "No entries found for {host}"),
+    (
+        "knownHosts.scanFailed",
+        "Could not retrieve host keys from {host}:{port}",
+    ),
+    ("knownHosts.added", "Added {count} key(s) for {host}"),
+    // Onboarding checklist
+    (
+        "onboarding.fixSshDir",
+        "Create ~/.ssh with permissions only you can access",
+    ),
+    ("onboarding.createKey", "Generate your first SSH key"),
+    (
+        "onboarding.startAgent",
+        "Start the SSH agent so keys are unlocked once per session",
+    ),
+    (
+        "onboarding.addKeyToAgent",
+        "Add one of your {count} key(s) to the agent",
+    ),
+    (
+        "onboarding.addHost",
+        "Add your first host to the SSH config",
+    ),
+    (
+        "onboarding.importPutty",
+        "Import {count} saved PuTTY session(s)",
+    ),
+    ("onboarding.importCloudHosts", "Import hosts using {tools}"),
+    (
+        "onboarding.organizeHosts",
+        "Organize your {count} hosts with tags and groups",
+    ),
+    // Config fixes suggested after a failed connection test
+    (
+        "suggestion.userGit",
+        "{platform} only accepts the user git, not {user}",
+    ),
+    (
+        "suggestion.userCloud",
+        "The server runs {os}; its cloud images log in as {suggested}, not {user}",
+    ),
+    (
+        "suggestion.port",
+        "Nothing answers on port {port}, but SSH runs on port {found}",
+    ),
+    (
+        "suggestion.hostKeyAlias",
+        "The server's key is already trusted as {name}; look it up under that name",
+    ),
+    // Doctor findings
+    ("doctor.noSshClient", "No OpenSSH client (ssh) found on PATH"),
+    (
+        "doctor.multipleClients",
+        "{count} different ssh clients are on PATH; shells run {path}",
+    ),
+    (
+        "doctor.agentToolMismatch",
+        "ssh comes from {ssh} but ssh-add from {sshAdd}; keys may be added to a different agent than ssh uses",
+    ),
+    (
+        "doctor.authSockPipe",
+        "SSH_AUTH_SOCK points to a named pipe, which the {client} client can't use",
+    ),
+    (
+        "doctor.authSockSocket",
+        "SSH_AUTH_SOCK points to a Unix socket, which the {client} client can't use (it expects the agent's named pipe)",
+    ),
+    (
+        "doctor.authSockMissing",
+        "SSH_AUTH_SOCK points to {path}, which doesn't exist; the agent it belonged to is gone",
+    ),
+    (
+        "doctor.gitSshMissing",
+        "{variable} is set to {value}, but that program can't be found",
+    ),
+    (
+        "doctor.gitSshMismatch",
+        "{variable} makes git use the {git} client, while shells use {ssh}; they may use different agents and configs",
+    ),
+    (
+        "doctor.keypairMismatch",
+        "{key}.pub holds a different key than the private key {key}; ssh offers the wrong public key",
+    ),
+    (
+        "doctor.orphanedPublicKey",
+        "{key}.pub has no private key in the SSH directory",
+    ),
+    (
+        "doctor.missingPublicKey",
+        "The private key {key} has no .pub file",
+    ),
+    (
+        "exposure.splitByEnvironment",
+        "{key} opens hosts in {environments}; use a separate key per environment so a leak stays contained",
+    ),
+    (
+        "exposure.wide",
+        "{key} opens {count} hosts; a leak would expose all of them, consider a key per group of hosts",
+    ),
+    (
+        "doctor.executableInSshDir",
+        "{name} in the SSH directory is executable; keys and configs never need to be",
+    ),
+    (
+        "doctor.sshRcFile",
+        "{name} in the SSH directory is read by sshd at every login to this machine",
+    ),
+    (
+        "doctor.hiddenFileInSshDir",
+        "Hidden file {name} in the SSH directory",
+    ),
+    (
+        "doctor.externalConfigChange",
+        "{name} was changed by another program since SSH Buddy last saved it",
+    ),
+    (
+        "integrity.modified",
+        "{name} was changed outside SSH Buddy",
+    ),
+    ("integrity.added", "{name} appeared outside SSH Buddy"),
+    ("integrity.removed", "{name} was removed outside SSH Buddy"),
+    (
+        "doctor.certificateExpired",
+        "The certificate {name} has expired",
+    ),
+    (
+        "doctor.certificateExpiring",
+        "The certificate {name} expires in {days} day(s)",
+    ),
+    (
+        "remediation.regeneratePublicKey",
+        "Rebuild {key}.pub from the private key",
+    ),
+    ("remediation.removeFile", "Delete {name}"),
+    ("remediation.quarantineFile", "Move {name} to the quarantine"),
+    (
+        "remediation.fixKeyPermissions",
+        "Restrict the permissions of {key}",
+    ),
+    (
+        "remediation.fixSshDirPermissions",
+        "Restrict the permissions of the SSH directory",
+    ),
+    (
+        "remediation.deployPublicKey",
+        "Install the corrected {key}.pub on {host}",
+    ),
+    (
+        "remediation.reviewConfigChange",
+        "Review the changes to {name} and keep or revert them",
+    ),
+    ("remediation.installOpenSsh", "Install the OpenSSH client"),
+    (
+        "remediation.renewCertificate",
+        "Request a new certificate for {name} from your certificate authority",
+    ),
+];
+
+const ZH_TW: &[(&str, &str)] = &[
+    ("error.keyNotFound", "找不到金鑰：{path}"),
+    ("error.invalidKeyFormat", "金鑰格式無效：{message}"),
+    ("error.keyAlreadyExists", "金鑰已存在：{name}"),
+    ("error.keyPermissionsTooOpen", "金鑰權限過於開放：{path}"),
+    ("error.invalidPath", "路徑無效：{message}"),
+    ("error.pathTraversalDetected", "偵測到路徑穿越：{path}"),
+    ("error.invalidKeyName", "金鑰名稱無效：{message}"),
+    ("error.hostKeyChanged", "主機金鑰已變更：{hostname}"),
+    ("error.hostKeyUnknown", "未知的主機金鑰：{hostname}"),
+    ("error.connectionRefused", "連線被拒絕：{message}"),
+    ("error.connectionTimeout", "連線逾時"),
+    ("error.dnsResolutionFailed", "DNS 解析失敗：{hostname}"),
+    ("error.proxyError", "代理錯誤：{message}"),
+    ("error.hostNotFound", "SSH 設定中找不到主機：{alias}"),
+    ("error.hostAlreadyExists", "SSH 設定中已有此主機：{alias}"),
+    ("error.invalidHostAlias", "主機別名無效：{alias}"),
+    ("error.invalidOption", "設定選項無效：{message}"),
+    ("error.invalidTemplate", "主機範本無效：{message}"),
+    (
+        "error.secretsDetected",
+        "匯出內容中偵測到機密資料（{count} 筆）",
+    ),
+    ("error.vaultLocked", "保險庫已鎖定"),
+    ("error.vaultNotFound", "找不到保險庫"),
+    ("error.invalidMasterPassword", "主密碼錯誤"),
+    ("error.permissionDenied", "權限遭拒：{reason}"),
+    ("error.passphraseRequired", "金鑰需要密語：{path}"),
+    ("error.keyNotInAgent", "金鑰不在代理程式中：{path}"),
+    ("error.readOnlyMode", "唯讀模式已開啟，無法{operation}"),
+    ("error.ioError", "I/O 錯誤：{message}"),
+    ("error.agentNotRunning", "代理程式未執行"),
+    ("error.homeDirNotFound", "找不到家目錄"),
+    ("error.unknown", "未知錯誤：{message}"),
+    ("permission.keyValid", "金鑰權限正確"),
+    (
+        "permission.keyTooOpen",
+        "金鑰權限為 {mode}，應為 {expected}。檔案可被過多使用者存取。",
+    ),
+    ("permission.keyCheckFailed", "無法檢查檔案權限"),
+    ("permission.keyValidAcl", "金鑰權限正確（僅限目前使用者）"),
+    (
+        "permission.keySharedAcl",
+        "其他使用者可存取此金鑰檔案，建議限制權限。",
+    ),
+    ("permission.keyUnverified", "無法驗證金鑰權限"),
+    ("permission.keyModeSet", "權限已設為 {mode}"),
+    (
+        "permission.keyRestricted",
+        "權限已限制為僅目前使用者（{user}）",
+    ),
+    ("permission.keySetFailed", "無法設定權限：{error}"),
+    (
+        "permission.keyAllowedByPolicy",
+        "金鑰權限為 {mode}，符合 {profile} 權限政策",
+    ),
+    ("permission.sshDirMissing", "SSH 目錄不存在"),
+    ("permission.sshDirValid", "SSH 目錄權限正確"),
+    (
+        "permission.sshDirTooOpen",
+        "SSH 目錄權限為 {mode}，應為 {expected}",
+    ),
+    (
+        "permission.sshDirAllowedByPolicy",
+        "SSH 目錄權限為 {mode}，符合 {profile} 權限政策",
+    ),
+    ("permission.sshDirCheckFailed", "無法檢查目錄權限"),
+    (
+        "permission.sshDirAcl",
+        "SSH 目錄存在，並使用 Windows ACL 權限",
+    ),
+    ("permission.sshDirModeSet", "SSH 目錄權限已設為 {mode}"),
+    (
+        "permission.sshDirRestricted",
+        "SSH 目錄權限已限制為僅目前使用者（{user}）",
+    ),
+    ("permission.sshDirSetFailed", "無法設定目錄權限：{error}"),
+    ("permission.checkUnavailable", "此系統無法檢查權限（{reason}）"),
+    ("permission.fixUnavailable", "此系統無法變更權限（{reason}）"),
+    (
+        "permission.sshDirSynced",
+        "SSH 目錄 {path} 位於同步資料夾 {folder} 中，私密金鑰可能會上傳到雲端",
+    ),
+    ("agent.alreadyLoaded", "金鑰已載入代理程式"),
+    ("agent.passphraseRequired", "此金鑰需要密語。"),
+    (
+        "agent.passphraseManual",
+        "此金鑰需要密語，請在終端機中手動執行 'ssh-add'。",
+    ),
+    ("agent.incorrectPassphrase", "密語錯誤，請再試一次。"),
+    ("agent.keyAdded", "金鑰已加入 SSH 代理程式"),
+    ("agent.keyRemoved", "金鑰已從 SSH 代理程式移除"),
+    ("agent.sshAddOutput", "{output}"),
+    ("agent.sshAddFailed", "無法執行 ssh-add：{error}"),
+    ("agent.internalError", "內部錯誤：{error}"),
+    ("agent.timedOut", "操作逾時，請再試一次。"),
+    ("knownHosts.fileMissing", "known_hosts 檔案不存在"),
+    ("knownHosts.removed", "已移除 {host} 的 {count} 筆項目"),
+    ("knownHosts.noEntries", "找不到 {host} 的項目"),
+    ("knownHosts.scanFailed", "無法從 {host}:{port} 取得主機金鑰"),
+    ("knownHosts.added", "已為 {host} 新增 {count} 把金鑰"),
+    ("onboarding.fixSshDir", "建立僅限您存取的 ~/.ssh 目錄"),
+    ("onboarding.createKey", "產生您的第一把 SSH 金鑰"),
+    (
+        "onboarding.startAgent",
+        "啟動 SSH 代理程式，每個工作階段只需解鎖金鑰一次",
+    ),
+    (
+        "onboarding.addKeyToAgent",
+        "將您 {count} 把金鑰中的一把加入代理程式",
+    ),
+    ("onboarding.addHost", "在 SSH 設定中新增第一台主機"),
+    (
+        "onboarding.importPutty",
+        "匯入 {count} 個已儲存的 PuTTY 工作階段",
+    ),
+    ("onboarding.importCloudHosts", "使用 {tools} 匯入主機"),
+    (
+        "onboarding.organizeHosts",
+        "以標籤和群組整理您的 {count} 台主機",
+    ),
+    (
+        "suggestion.userGit",
+        "{platform} 只接受使用者 git，而不是 {user}",
+    ),
+    (
+        "suggestion.userCloud",
+        "伺服器執行 {os}，其雲端映像檔以 {suggested} 登入，而不是 {user}",
+    ),
+    (
+        "suggestion.port",
+        "連接埠 {port} 沒有回應，但 SSH 在連接埠 {found} 上執行",
+    ),
+    (
+        "suggestion.hostKeyAlias",
+        "伺服器的金鑰已以 {name} 的名稱受信任，請改用該名稱查詢",
+    ),
+    ("doctor.noSshClient", "PATH 中找不到 OpenSSH 用戶端（ssh）"),
+    (
+        "doctor.multipleClients",
+        "PATH 中有 {count} 個不同的 ssh 用戶端；shell 會執行 {path}",
+    ),
+    (
+        "doctor.agentToolMismatch",
+        "ssh 來自 {ssh}，但 ssh-add 來自 {sshAdd}；金鑰可能被加入 ssh 未使用的代理程式",
+    ),
+    (
+        "doctor.authSockPipe",
+        "SSH_AUTH_SOCK 指向具名管道，{client} 用戶端無法使用",
+    ),
+    (
+        "doctor.authSockSocket",
+        "SSH_AUTH_SOCK 指向 Unix socket，{client} 用戶端無法使用（它需要代理程式的具名管道）",
+    ),
+    (
+        "doctor.authSockMissing",
+        "SSH_AUTH_SOCK 指向不存在的 {path}；對應的代理程式已不在執行",
+    ),
+    (
+        "doctor.gitSshMissing",
+        "{variable} 設為 {value}，但找不到該程式",
+    ),
+    (
+        "doctor.gitSshMismatch",
+        "{variable} 讓 git 使用 {git} 用戶端，而 shell 使用 {ssh}；兩者可能使用不同的代理程式與設定",
+    ),
+    (
+        "doctor.keypairMismatch",
+        "{key}.pub 與私鑰 {key} 不是同一把金鑰；ssh 會提供錯誤的公鑰",
+    ),
+    (
+        "doctor.orphanedPublicKey",
+        "SSH 目錄中找不到 {key}.pub 對應的私鑰",
+    ),
+    (
+        "doctor.missingPublicKey",
+        "私鑰 {key} 沒有 .pub 檔案",
+    ),
+    (
+        "exposure.splitByEnvironment",
+        "{key} 可登入 {environments} 的主機；請為每個環境使用不同的金鑰，以限制外洩的影響",
+    ),
+    (
+        "exposure.wide",
+        "{key} 可登入 {count} 台主機；一旦外洩將全部受影響，建議依主機群組使用不同金鑰",
+    ),
+    (
+        "doctor.executableInSshDir",
+        "SSH 目錄中的 {name} 可執行；金鑰與設定檔都不需要執行權限",
+    ),
+    (
+        "doctor.sshRcFile",
+        "SSH 目錄中的 {name} 會在每次登入本機時由 sshd 讀取",
+    ),
+    (
+        "doctor.hiddenFileInSshDir",
+        "SSH 目錄中有隱藏檔案 {name}",
+    ),
+    (
+        "doctor.externalConfigChange",
+        "{name} 在 SSH Buddy 上次儲存後被其他程式修改",
+    ),
+    ("integrity.modified", "{name} 在 SSH Buddy 之外被修改"),
+    ("integrity.added", "{name} 在 SSH Buddy 之外被新增"),
+    ("integrity.removed", "{name} 在 SSH Buddy 之外被刪除"),
+    ("doctor.certificateExpired", "憑證 {name} 已過期"),
+    ("doctor.certificateExpiring", "憑證 {name} 將在 {days} 天後過期"),
+    ("remediation.regeneratePublicKey", "從私鑰重建 {key}.pub"),
+    ("remediation.removeFile", "刪除 {name}"),
+    ("remediation.quarantineFile", "將 {name} 移至隔離區"),
+    ("remediation.fixKeyPermissions", "限制 {key} 的權限"),
+    ("remediation.fixSshDirPermissions", "限制 SSH 目錄的權限"),
+    ("remediation.deployPublicKey", "在 {host} 上安裝修正後的 {key}.pub"),
+    ("remediation.reviewConfigChange", "檢查 {name} 的變更並決定保留或還原"),
+    ("remediation.installOpenSsh", "安裝 OpenSSH 用戶端"),
+    ("remediation.renewCertificate", "向憑證授權中心為 {name} 申請新憑證"),
+];
+
+fn templates(locale: &str) -> &'static [(&'static str, &'static str)] {
+    match locale {
+        "zh-TW" | "zh-Hant" => ZH_TW,
+        _ => EN,
+    }
+}
+
+fn lookup(templates: &[(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    templates
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, template)| *template)
+}
+
+/// Every key with its template in `locale`, falling back to English per key
+pub fn message_catalog(locale: &str) -> BTreeMap<&'static str, &'static str> {
+    let mut catalog: BTreeMap<_, _> = EN.iter().copied().collect();
+    catalog.extend(templates(locale).iter().copied());
+    catalog
+}
+
+/// Render a message in `locale`; unknown keys render as the key itself and
+/// placeholders without a parameter are left as they are
+pub fn render_message(locale: &str, key: &str, params: &BTreeMap<String, String>) -> String {
+    let Some(template) = lookup(templates(locale), key).or_else(|| lookup(EN, key)) else {
+        return key.to_string();
+    };
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| params.get(&after[..end]).map(|value| (end, value)))
+        {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn placeholders(template: &str) -> BTreeSet<&str> {
+        template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_message() {
+        let mut params = BTreeMap::new();
+        params.insert("count".to_string(), "2".to_string());
+        params.insert("host".to_string(), "example.com".to_string());
+        assert_eq!(
+            render_message("en", "knownHosts.removed", &params),
+            "Removed 2 entries for example.com"
+        );
+        assert_eq!(
+            render_message("zh-TW", "knownHosts.removed", &params),
+            "已移除 example.com 的 2 筆項目"
+        );
+        // Unknown locale falls back to English, unknown key to the key
+        assert_eq!(
+            render_message("fr", "knownHosts.noEntries", &params),
+            "No entries found for example.com"
+        );
+        assert_eq!(render_message("en", "missing.key", &params), "missing.key");
+        // A parameter value containing braces is not expanded again
+        params.insert("output".to_string(), "{host}".to_string());
+        assert_eq!(
+            render_message("en", "agent.sshAddOutput", &params),
+            "{host}"
+        );
+        assert_eq!(
+            render_message("en", "permission.keyModeSet", &params),
+            "Permissions set to {mode}"
+        );
+    }
+
+    #[test]
+    fn test_catalogs_consistent() {
+        let english: BTreeMap<_, _> = EN.iter().copied().collect();
+        assert_eq!(english.len(), EN.len(), "duplicate English key");
+        for locale in LOCALES {
+            let entries = templates(locale);
+            let translated: BTreeMap<_, _> = entries.iter().copied().collect();
+            assert_eq!(translated.len(), entries.len(), "duplicate {} key", locale);
+            for (key, template) in translated {
+                let english = english
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{} key {} not in English", locale, key));
+                assert_eq!(
+                    placeholders(template),
+                    placeholders(english),
+                    "{} placeholders of {}",
+                    locale,
+                    key
+                );
+            }
+        }
+        assert_eq!(message_catalog("zh-TW").len(), EN.len());
+    }
+
+    #[test]
+    fn test_errors_render_like_display() {
+        use crate::models::SshBuddyError;
+        let errors = [
+            SshBuddyError::KeyNotFound {
+                path: "~/.ssh/id_ed25519".to_string(),
+            },
+            SshBuddyError::InvalidOption {
+                message: "bad value".to_string(),
+            },
+            SshBuddyError::HostKeyChanged {
+                hostname: "example.com".to_string(),
+            },
+            SshBuddyError::HostNotFound {
+                alias: "web".to_string(),
+            },
+            SshBuddyError::SecretsDetected { count: 3 },
+            SshBuddyError::ReadOnlyMode {
+                operation: "save config".to_string(),
+            },
+            SshBuddyError::ConnectionTimeout,
+            SshBuddyError::HomeDirNotFound,
+        ];
+        for error in errors {
+            let localized = error.localized();
+            assert!(lookup(EN, &localized.key).is_some(), "{}", localized.key);
+            assert_eq!(localized.text, error.to_string());
+        }
+        let json = serde_json::to_value(SshBuddyError::SecretsDetected { count: 3 }).unwrap();
+        assert_eq!(json["messageKey"], "error.secretsDetected");
+        assert_eq!(json["messageParams"]["count"], "3");
+    }
+}
@@ -0,0 +1,62 @@
+use crate::models::{SshBuddyError, SshResult};
+use crate::utils::write_atomic;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+fn to_line<T: Serialize>(item: &T) -> SshResult<String> {
+    let mut line = serde_json::to_string(item).map_err(|e| SshBuddyError::Unknown {
+        message: e.to_string(),
+    })?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Append one JSON line, creating the file and its directory if needed
+pub async fn append_json_line<T: Serialize>(path: &Path, item: &T) -> SshResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(to_line(item)?.as_bytes()).await?;
+    Ok(())
+}
+
+/// All entries, oldest first; a missing file is empty and unreadable lines are skipped
+pub async fn read_json_lines<T: DeserializeOwned>(path: &Path) -> SshResult<Vec<T>> {
+    match fs::read_to_string(path).await {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(SshBuddyError::IoError {
+            message: format!("Failed to read {}: {}", path.display(), e),
+        }),
+    }
+}
+
+/// Rewrite the file with only the entries `keep` accepts; returns the number removed
+pub async fn retain_json_lines<T, F>(path: &Path, keep: F) -> SshResult<usize>
+where
+    T: Serialize + DeserializeOwned,
+    F: Fn(&T) -> bool,
+{
+    let entries: Vec<T> = read_json_lines(path).await?;
+    let total = entries.len();
+    let mut content = String::new();
+    for entry in entries.iter().filter(|e| keep(e)) {
+        content.push_str(&to_line(entry)?);
+    }
+    let removed = total - content.lines().count();
+    if removed > 0 {
+        write_atomic(path, content.as_bytes()).await?;
+    }
+    Ok(removed)
+}
@@ -0,0 +1,259 @@
+use crate::models::{SshBuddyError, SshResult};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// "SSHKRL\n\0"
+const KRL_MAGIC: u64 = 0x5353_484b_524c_0a00;
+const KRL_FORMAT_VERSION: u32 = 1;
+
+const SECTION_CERTIFICATES: u8 = 1;
+const SECTION_EXPLICIT_KEY: u8 = 2;
+const SECTION_FINGERPRINT_SHA1: u8 = 3;
+const SECTION_SIGNATURE: u8 = 4;
+const SECTION_FINGERPRINT_SHA256: u8 = 5;
+
+/// OpenSSH Key Revocation List (see PROTOCOL.krl)
+///
+/// Keys are compared as wire-format public key blobs. Certificate sections are kept
+/// only as a count; revoking certificates by serial or key id is not evaluated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Krl {
+    pub version: u64,
+    /// Unix timestamp in seconds
+    pub generated_at: u64,
+    pub comment: String,
+    pub keys: Vec<Vec<u8>>,
+    pub sha1: Vec<Vec<u8>>,
+    pub sha256: Vec<Vec<u8>>,
+    pub certificate_sections: usize,
+    pub signed: bool,
+}
+
+fn invalid(message: &str) -> SshBuddyError {
+    SshBuddyError::InvalidOption {
+        message: format!("Invalid KRL: {}", message),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> SshResult<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(invalid("truncated"));
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> SshResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> SshResult<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> SshResult<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> SshResult<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn strings(mut self) -> SshResult<Vec<Vec<u8>>> {
+        let mut items = Vec::new();
+        while !self.data.is_empty() {
+            items.push(self.string()?.to_vec());
+        }
+        Ok(items)
+    }
+}
+
+fn put_string(out: &mut Vec<u8>, value: &[u8]) {
+    out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    out.extend_from_slice(value);
+}
+
+fn put_section(out: &mut Vec<u8>, section: u8, items: &[Vec<u8>]) {
+    if items.is_empty() {
+        return;
+    }
+    let mut data = Vec::new();
+    for item in items {
+        put_string(&mut data, item);
+    }
+    out.push(section);
+    put_string(out, &data);
+}
+
+impl Krl {
+    /// Parse a binary KRL, or a plain list of public keys (also accepted by RevokedKeys)
+    pub fn parse(data: &[u8]) -> SshResult<Self> {
+        if data.len() >= 8 && u64::from_be_bytes(data[..8].try_into().unwrap()) == KRL_MAGIC {
+            Self::parse_binary(data)
+        } else {
+            Self::parse_text(data)
+        }
+    }
+
+    fn parse_binary(data: &[u8]) -> SshResult<Self> {
+        let mut reader = Reader { data: &data[8..] };
+        if reader.u32()? != KRL_FORMAT_VERSION {
+            return Err(invalid("unsupported format version"));
+        }
+        let mut krl = Krl {
+            version: reader.u64()?,
+            generated_at: reader.u64()?,
+            ..Krl::default()
+        };
+        let _flags = reader.u64()?;
+        let _reserved = reader.string()?;
+        krl.comment = String::from_utf8_lossy(reader.string()?).into_owned();
+
+        while !reader.data.is_empty() {
+            let section = reader.u8()?;
+            let body = Reader {
+                data: reader.string()?,
+            };
+            match section {
+                SECTION_CERTIFICATES => krl.certificate_sections += 1,
+                SECTION_EXPLICIT_KEY => krl.keys.extend(body.strings()?),
+                SECTION_FINGERPRINT_SHA1 => krl.sha1.extend(body.strings()?),
+                SECTION_FINGERPRINT_SHA256 => krl.sha256.extend(body.strings()?),
+                // The signature covers everything before it and ends the KRL
+                SECTION_SIGNATURE => {
+                    krl.signed = true;
+                    break;
+                }
+                _ => return Err(invalid("unknown section")),
+            }
+        }
+        Ok(krl)
+    }
+
+    fn parse_text(data: &[u8]) -> SshResult<Self> {
+        let text = std::str::from_utf8(data).map_err(|_| invalid("not a KRL or key list"))?;
+        let mut krl = Krl::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            krl.keys
+                .push(public_key_blob(line).ok_or_else(|| invalid("bad key line"))?);
+        }
+        Ok(krl)
+    }
+
+    /// Binary KRL revoking `keys` explicitly
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&KRL_MAGIC.to_be_bytes());
+        out.extend_from_slice(&KRL_FORMAT_VERSION.to_be_bytes());
+        out.extend_from_slice(&self.version.to_be_bytes());
+        out.extend_from_slice(&self.generated_at.to_be_bytes());
+        out.extend_from_slice(&0u64.to_be_bytes());
+        put_string(&mut out, b"");
+        put_string(&mut out, self.comment.as_bytes());
+        put_section(&mut out, SECTION_EXPLICIT_KEY, &self.keys);
+        put_section(&mut out, SECTION_FINGERPRINT_SHA1, &self.sha1);
+        put_section(&mut out, SECTION_FINGERPRINT_SHA256, &self.sha256);
+        out
+    }
+
+    /// Is the key (wire-format blob) revoked explicitly or by fingerprint?
+    pub fn revokes(&self, blob: &[u8]) -> bool {
+        self.keys.iter().any(|k| k == blob)
+            || (!self.sha1.is_empty() && {
+                let digest = Sha1::digest(blob);
+                self.sha1.iter().any(|h| h.as_slice() == digest.as_slice())
+            })
+            || (!self.sha256.is_empty() && {
+                let digest = Sha256::digest(blob);
+                self.sha256
+                    .iter()
+                    .any(|h| h.as_slice() == digest.as_slice())
+            })
+    }
+}
+
+/// Wire-format blob of an OpenSSH public key line ("type base64 [comment]"),
+/// skipping any authorized_keys options in front of the key type
+pub fn public_key_blob(line: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    fields.windows(2).find_map(|pair| {
+        let blob = base64::engine::general_purpose::STANDARD
+            .decode(pair[1])
+            .ok()?;
+        // The blob starts with the key type it claims to be
+        let mut reader = Reader { data: &blob };
+        (reader.string().ok()? == pair[0].as_bytes()).then_some(blob)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl a@b";
+    const OTHER: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBG5Qh1pHZPhCFH5bz6hXNXwMLC4uI2PyzeRHQjVSlpc c@d";
+
+    #[test]
+    fn test_public_key_blob() {
+        let blob = public_key_blob(KEY).unwrap();
+        assert_eq!(blob.len(), 51);
+        let with_options = format!("restrict,command=\"uptime\" {}", KEY);
+        assert_eq!(public_key_blob(&with_options), Some(blob));
+        assert_eq!(public_key_blob("ssh-rsa AAAAC3NzaC1lZDI1NTE5"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = public_key_blob(KEY).unwrap();
+        let krl = Krl {
+            version: 3,
+            generated_at: 1_700_000_000,
+            comment: "offboarding".to_string(),
+            keys: vec![key.clone()],
+            ..Krl::default()
+        };
+        let parsed = Krl::parse(&krl.encode()).unwrap();
+        assert_eq!(parsed, krl);
+        assert!(parsed.revokes(&key));
+        assert!(!parsed.revokes(&public_key_blob(OTHER).unwrap()));
+    }
+
+    #[test]
+    fn test_fingerprint_sections() {
+        let key = public_key_blob(KEY).unwrap();
+        let by_sha256 = Krl {
+            sha256: vec![Sha256::digest(&key).to_vec()],
+            ..Krl::default()
+        };
+        assert!(Krl::parse(&by_sha256.encode()).unwrap().revokes(&key));
+        let by_sha1 = Krl {
+            sha1: vec![Sha1::digest(&key).to_vec()],
+            ..Krl::default()
+        };
+        assert!(by_sha1.revokes(&key));
+    }
+
+    #[test]
+    fn test_text_list_and_errors() {
+        let krl = Krl::parse(format!("# revoked\n{}\n", KEY).as_bytes()).unwrap();
+        assert!(krl.revokes(&public_key_blob(KEY).unwrap()));
+        assert!(Krl::parse(b"not a key").is_err());
+
+        let mut truncated = Krl::default().encode();
+        truncated.truncate(20);
+        assert!(Krl::parse(&truncated).is_err());
+    }
+}
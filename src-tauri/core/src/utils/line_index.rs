@@ -0,0 +1,273 @@
+use crate::models::{SshBuddyError, SshResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader};
+
+/// Start of a record (a known_hosts entry, a Host block) in a line-based file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordOffset {
+    /// Byte offset of the record's first line
+    pub offset: u64,
+    /// 1-based line number of the record's first line
+    pub line: u32,
+}
+
+/// Offsets of the records of a file, so a page of them can be read without loading
+/// the whole file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineIndex {
+    len: u64,
+    modified: Option<SystemTime>,
+    records: Vec<RecordOffset>,
+}
+
+impl LineIndex {
+    /// Stream the file once and note where each record starts
+    /// `starts_record` gets each line without its line ending
+    pub async fn build(path: &Path, starts_record: fn(&str) -> bool) -> SshResult<Self> {
+        let file = File::open(path).await?;
+        let metadata = file.metadata().await?;
+        let mut records = Vec::new();
+        for_each_line(BufReader::new(file), |line, offset, text| {
+            if starts_record(text) {
+                records.push(RecordOffset { offset, line });
+            }
+            ControlFlow::Continue(())
+        })
+        .await?;
+        Ok(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            records,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Whether the file changed since the index was built
+    fn is_stale(&self, metadata: &std::fs::Metadata) -> bool {
+        metadata.len() != self.len || metadata.modified().ok() != self.modified
+    }
+
+    /// Read records `start..start + count` as (record offset, text of all its lines)
+    pub async fn read_records(
+        &self,
+        path: &Path,
+        start: usize,
+        count: usize,
+    ) -> SshResult<Vec<(RecordOffset, String)>> {
+        let end = start.saturating_add(count).min(self.records.len());
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        let mut file = File::open(path).await?;
+        let mut records = Vec::with_capacity(end - start);
+        for i in start..end {
+            let record = self.records[i];
+            let record_end = self.records.get(i + 1).map_or(self.len, |next| next.offset);
+            file.seek(SeekFrom::Start(record.offset)).await?;
+            let mut bytes = vec![0; (record_end - record.offset) as usize];
+            file.read_exact(&mut bytes).await?;
+            records.push((record, String::from_utf8_lossy(&bytes).to_string()));
+        }
+        Ok(records)
+    }
+}
+
+/// Call `visit` with (line number, byte offset, line without its ending) for every line,
+/// holding one line in memory at a time; `visit` can stop early
+pub async fn for_each_line<R, F>(mut reader: R, mut visit: F) -> SshResult<()>
+where
+    R: AsyncBufReadExt + Unpin,
+    F: FnMut(u32, u64, &str) -> ControlFlow<()>,
+{
+    let mut buffer = Vec::new();
+    let mut offset = 0u64;
+    let mut line = 0u32;
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        line += 1;
+        let text = String::from_utf8_lossy(&buffer);
+        let text = text.trim_end_matches(['\n', '\r']);
+        if visit(line, offset, text).is_break() {
+            return Ok(());
+        }
+        offset += read as u64;
+    }
+}
+
+/// Stream the lines of a file (see `for_each_line`)
+pub async fn for_each_file_line<F>(path: &Path, visit: F) -> SshResult<()>
+where
+    F: FnMut(u32, u64, &str) -> ControlFlow<()>,
+{
+    let file = File::open(path).await.map_err(|e| SshBuddyError::IoError {
+        message: format!("Failed to read {}: {}", path.display(), e),
+    })?;
+    for_each_line(BufReader::new(file), visit).await
+}
+
+/// Indexes by path, rebuilt when the file's size or modification time changes
+#[derive(Default)]
+pub struct IndexCache {
+    indexes: Mutex<HashMap<PathBuf, Arc<LineIndex>>>,
+}
+
+impl IndexCache {
+    pub async fn get(
+        &self,
+        path: &Path,
+        starts_record: fn(&str) -> bool,
+    ) -> SshResult<Arc<LineIndex>> {
+        let metadata = tokio::fs::metadata(path).await?;
+        let cached = self
+            .indexes
+            .lock()
+            .ok()
+            .and_then(|indexes| indexes.get(path).cloned());
+        if let Some(index) = cached.filter(|index| !index.is_stale(&metadata)) {
+            return Ok(index);
+        }
+
+        let index = Arc::new(LineIndex::build(path, starts_record).await?);
+        tracing::info!(
+            "[line_index] Indexed {} records of {}",
+            index.len(),
+            path.display()
+        );
+        if let Ok(mut indexes) = self.indexes.lock() {
+            indexes.insert(path.to_path_buf(), index.clone());
+        }
+        Ok(index)
+    }
+}
+
+/// Page parameters of a paginated query
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageQuery {
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_page_size")]
+    pub limit: usize,
+    /// Case-insensitive substring filter; the whole file is streamed to apply it
+    #[serde(default)]
+    pub search: Option<String>,
+}
+
+/// Largest page a query returns
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+fn default_page_size() -> usize {
+    200
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// Lowercased search text, None when empty
+    pub fn needle(&self) -> Option<String> {
+        self.search
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_lowercase)
+    }
+}
+
+/// One page of records
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub offset: usize,
+    /// Records in the file
+    pub total: usize,
+    /// Records matching the search (`total` without one)
+    pub matched: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn is_entry(line: &str) -> bool {
+        let line = line.trim();
+        !line.is_empty() && !line.starts_with('#')
+    }
+
+    #[tokio::test]
+    async fn test_index_and_read_records() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("known_hosts");
+        tokio::fs::write(&path, "# comment\na key1\r\n\nb key2\nc key3")
+            .await
+            .unwrap();
+
+        let index = LineIndex::build(&path, is_entry).await.unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.records[1],
+            RecordOffset {
+                offset: 19,
+                line: 4
+            }
+        );
+
+        let page = index.read_records(&path, 1, 5).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].1, "b key2\n");
+        assert_eq!(page[1].1, "c key3");
+        assert!(index.read_records(&path, 3, 5).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cache_rebuilds_changed_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("known_hosts");
+        tokio::fs::write(&path, "a key1\n").await.unwrap();
+
+        let cache = IndexCache::default();
+        assert_eq!(cache.get(&path, is_entry).await.unwrap().len(), 1);
+        tokio::fs::write(&path, "a key1\nb key2\n").await.unwrap();
+        assert_eq!(cache.get(&path, is_entry).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_for_each_line_stops_early() {
+        let mut seen = Vec::new();
+        for_each_line(&b"one\ntwo\nthree\n"[..], |line, offset, text| {
+            seen.push((line, offset, text.to_string()));
+            if line == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            seen,
+            vec![(1, 0, "one".to_string()), (2, 4, "two".to_string())]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4};
+
+/// mDNS IPv4 multicast group and port (RFC 6762)
+pub const MDNS_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(224, 0, 0, 251), 5353);
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+
+/// Max compression pointers followed in one name (guards against pointer loops)
+const MAX_NAME_JUMPS: usize = 16;
+
+/// Resource record data this app cares about
+#[derive(Debug, Clone, PartialEq)]
+pub enum MdnsRecordData {
+    Ptr(String),
+    Srv {
+        target: String,
+        port: u16,
+    },
+    /// Character strings, usually "key=value"
+    Txt(Vec<String>),
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Other,
+}
+
+/// Resource record from an mDNS response
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdnsRecord {
+    /// Lowercase owner name without the trailing dot
+    pub name: String,
+    pub ttl: u32,
+    pub data: MdnsRecordData,
+}
+
+/// Build a PTR query for a service type (e.g. "_ssh._tcp.local")
+/// Sent from an ephemeral port this is a "legacy unicast" query, answered directly to us
+pub fn build_ptr_query(service: &str) -> Vec<u8> {
+    let mut packet = vec![0u8; 12];
+    // ID 0, no flags, one question
+    packet[5] = 1;
+    for label in service.trim_end_matches('.').split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    packet
+}
+
+fn read_u16(packet: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *packet.get(offset)?,
+        *packet.get(offset + 1)?,
+    ]))
+}
+
+fn read_u32(packet: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        packet.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Read a (possibly compressed) name; returns it and the offset after it
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+    loop {
+        let len = *packet.get(offset)? as usize;
+        match len {
+            0 => {
+                let end = end.unwrap_or(offset + 1);
+                return Some((labels.join(".").to_lowercase(), end));
+            }
+            // Compression pointer
+            l if l & 0xc0 == 0xc0 => {
+                jumps += 1;
+                if jumps > MAX_NAME_JUMPS {
+                    return None;
+                }
+                end.get_or_insert(offset + 2);
+                offset = (read_u16(packet, offset)? & 0x3fff) as usize;
+            }
+            l => {
+                let label = packet.get(offset + 1..offset + 1 + l)?;
+                labels.push(String::from_utf8_lossy(label).to_string());
+                offset += 1 + l;
+            }
+        }
+    }
+}
+
+fn parse_data(packet: &[u8], rtype: u16, start: usize, len: usize) -> Option<MdnsRecordData> {
+    let rdata = packet.get(start..start + len)?;
+    Some(match rtype {
+        TYPE_PTR => MdnsRecordData::Ptr(read_name(packet, start)?.0),
+        TYPE_SRV => MdnsRecordData::Srv {
+            port: read_u16(rdata, 4)?,
+            target: read_name(packet, start + 6)?.0,
+        },
+        TYPE_TXT => {
+            let mut strings = Vec::new();
+            let mut i = 0;
+            while i < rdata.len() {
+                let l = rdata[i] as usize;
+                let text = rdata.get(i + 1..i + 1 + l)?;
+                if !text.is_empty() {
+                    strings.push(String::from_utf8_lossy(text).to_string());
+                }
+                i += 1 + l;
+            }
+            MdnsRecordData::Txt(strings)
+        }
+        TYPE_A => MdnsRecordData::A(Ipv4Addr::from(<[u8; 4]>::try_from(rdata).ok()?)),
+        TYPE_AAAA => MdnsRecordData::Aaaa(Ipv6Addr::from(<[u8; 16]>::try_from(rdata).ok()?)),
+        _ => MdnsRecordData::Other,
+    })
+}
+
+/// Parse the answer, authority and additional records of a response
+/// Returns None for queries and malformed packets
+pub fn parse_response(packet: &[u8]) -> Option<Vec<MdnsRecord>> {
+    let flags = read_u16(packet, 2)?;
+    if flags & 0x8000 == 0 {
+        return None;
+    }
+    let questions = read_u16(packet, 4)?;
+    let records = read_u16(packet, 6)? as usize
+        + read_u16(packet, 8)? as usize
+        + read_u16(packet, 10)? as usize;
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(packet, offset)?.1 + 4;
+    }
+
+    let mut parsed = Vec::with_capacity(records);
+    for _ in 0..records {
+        let (name, next) = read_name(packet, offset)?;
+        let rtype = read_u16(packet, next)?;
+        let ttl = read_u32(packet, next + 4)?;
+        let len = read_u16(packet, next + 8)? as usize;
+        let data = parse_data(packet, rtype, next + 10, len)?;
+        parsed.push(MdnsRecord { name, ttl, data });
+        offset = next + 10 + len;
+    }
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_name(packet: &mut Vec<u8>, name: &str) {
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+    }
+
+    fn push_record(packet: &mut Vec<u8>, name: &[u8], rtype: u16, rdata: &[u8]) {
+        packet.extend_from_slice(name);
+        packet.extend_from_slice(&rtype.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet.extend_from_slice(&120u32.to_be_bytes());
+        packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        packet.extend_from_slice(rdata);
+    }
+
+    #[test]
+    fn test_build_ptr_query() {
+        let query = build_ptr_query("_ssh._tcp.local");
+        assert_eq!(&query[..12], &[0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            read_name(&query, 12),
+            Some(("_ssh._tcp.local".to_string(), 29))
+        );
+        assert_eq!(read_u16(&query, 29), Some(TYPE_PTR));
+    }
+
+    #[test]
+    fn test_parse_response_with_compression() {
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 3];
+        // PTR _ssh._tcp.local -> pi._ssh._tcp.local (compressed against the owner name)
+        let service_offset = packet.len() as u16;
+        let mut owner = Vec::new();
+        push_name(&mut owner, "_ssh._tcp.local");
+        let mut ptr = vec![2, b'p', b'i'];
+        ptr.extend_from_slice(&(0xc000 | service_offset).to_be_bytes());
+        push_record(&mut packet, &owner, TYPE_PTR, &ptr);
+
+        let instance = [
+            vec![2, b'p', b'i'],
+            (0xc000 | service_offset).to_be_bytes().to_vec(),
+        ]
+        .concat();
+        let mut srv = vec![0, 0, 0, 0, 0, 22];
+        push_name(&mut srv, "raspberrypi.local");
+        push_record(&mut packet, &instance, TYPE_SRV, &srv);
+        push_record(&mut packet, &instance, TYPE_TXT, b"\x0bmodel=Pi 4B\x00");
+
+        let mut host = Vec::new();
+        push_name(&mut host, "RaspberryPi.local");
+        push_record(&mut packet, &host, TYPE_A, &[192, 168, 1, 42]);
+
+        let records = parse_response(&packet).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].name, "_ssh._tcp.local");
+        assert_eq!(
+            records[0].data,
+            MdnsRecordData::Ptr("pi._ssh._tcp.local".to_string())
+        );
+        assert_eq!(
+            records[1].data,
+            MdnsRecordData::Srv {
+                target: "raspberrypi.local".to_string(),
+                port: 22
+            }
+        );
+        assert_eq!(
+            records[2].data,
+            MdnsRecordData::Txt(vec!["model=Pi 4B".to_string()])
+        );
+        assert_eq!(records[3].name, "raspberrypi.local");
+        assert_eq!(
+            records[3].data,
+            MdnsRecordData::A(Ipv4Addr::new(192, 168, 1, 42))
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_queries_and_loops() {
+        assert!(parse_response(&build_ptr_query("_ssh._tcp.local")).is_none());
+        // Answer whose name points at itself
+        let packet = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0xc0, 12];
+        assert!(parse_response(&packet).is_none());
+        assert!(parse_response(&[0, 0]).is_none());
+    }
+}
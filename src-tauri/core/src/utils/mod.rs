@@ -0,0 +1,45 @@
+pub mod app_paths;
+pub mod atomic_write;
+pub mod authorized_keys;
+pub mod cron;
+pub mod crypto;
+pub mod deep_link;
+pub mod group_query;
+pub mod happy_eyeballs;
+pub mod i18n;
+pub mod json_lines;
+pub mod krl;
+pub mod line_index;
+pub mod mdns;
+pub mod path_validator;
+pub mod randomart;
+pub mod secret_scanner;
+pub mod ssh_config;
+pub mod ssh_config_editor;
+pub mod ssh_handshake;
+pub mod text_diff;
+pub mod token_bucket;
+#[cfg(windows)]
+pub mod windows_acl;
+
+pub use app_paths::*;
+pub use atomic_write::*;
+pub use authorized_keys::*;
+pub use cron::*;
+pub use crypto::*;
+pub use deep_link::*;
+pub use group_query::*;
+pub use happy_eyeballs::*;
+pub use i18n::*;
+pub use json_lines::*;
+pub use krl::*;
+pub use line_index::*;
+pub use mdns::*;
+pub use path_validator::*;
+pub use randomart::*;
+pub use secret_scanner::*;
+pub use ssh_config::*;
+pub use ssh_config_editor::*;
+pub use ssh_handshake::*;
+pub use text_diff::*;
+pub use token_bucket::*;
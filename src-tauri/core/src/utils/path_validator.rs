@@ -113,6 +113,9 @@ mod tests {
         assert!(validate_key_name("id_ed25519").is_ok());
         assert!(validate_key_name("my-key").is_ok());
         assert!(validate_key_name("key_2024").is_ok());
+        assert!(validate_key_name("my key").is_ok());
+        assert!(validate_key_name("工作金鑰").is_ok());
+        assert!(validate_key_name("🔑_github").is_ok());
     }
 
     #[test]
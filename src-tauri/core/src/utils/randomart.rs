@@ -0,0 +1,160 @@
+use ssh_key::public::KeyData;
+use ssh_key::{EcdsaCurve, HashAlg, PublicKey};
+
+const FIELD_WIDTH: usize = 17;
+const FIELD_HEIGHT: usize = 9;
+
+/// Symbols by visit count; the last two mark the start and end of the walk
+const SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^SE";
+
+/// OpenSSH-style randomart of a key's SHA256 fingerprint, as `ssh-keygen -lv` draws it
+pub fn key_randomart(key: &PublicKey) -> String {
+    let fingerprint = key.fingerprint(HashAlg::Sha256);
+    randomart(&key_title(key), "SHA256", fingerprint.as_bytes())
+}
+
+/// Header label: key type and size, e.g. `ED25519 256`
+fn key_title(key: &PublicKey) -> String {
+    let (name, bits) = match key.key_data() {
+        KeyData::Ed25519(_) => ("ED25519", Some(256)),
+        KeyData::SkEd25519(_) => ("ED25519-SK", Some(256)),
+        KeyData::SkEcdsaSha2NistP256(_) => ("ECDSA-SK", Some(256)),
+        KeyData::Ecdsa(ecdsa) => (
+            "ECDSA",
+            Some(match ecdsa.curve() {
+                EcdsaCurve::NistP256 => 256,
+                EcdsaCurve::NistP384 => 384,
+                EcdsaCurve::NistP521 => 521,
+            }),
+        ),
+        KeyData::Rsa(rsa) => ("RSA", rsa.n.as_positive_bytes().map(bit_length)),
+        KeyData::Dsa(dsa) => ("DSA", dsa.p.as_positive_bytes().map(bit_length)),
+        _ => ("UNKNOWN", None),
+    };
+    match bits {
+        Some(bits) => format!("{} {}", name, bits),
+        None => name.to_string(),
+    }
+}
+
+/// Bits in a big-endian unsigned integer without leading zero bytes
+fn bit_length(bytes: &[u8]) -> u32 {
+    match bytes.first() {
+        Some(first) => bytes.len() as u32 * 8 - first.leading_zeros(),
+        None => 0,
+    }
+}
+
+/// "Drunken bishop" walk over `digest`: each byte moves the bishop four times
+/// diagonally, two bits per move, starting from the centre of a 17x9 field
+pub fn randomart(title: &str, hash_name: &str, digest: &[u8]) -> String {
+    let end = SYMBOLS.len() - 1;
+    let mut field = [[0usize; FIELD_HEIGHT]; FIELD_WIDTH];
+    let (start_x, start_y) = (FIELD_WIDTH / 2, FIELD_HEIGHT / 2);
+    let (mut x, mut y) = (start_x, start_y);
+
+    for &byte in digest {
+        let mut input = byte;
+        for _ in 0..4 {
+            x = if input & 0x1 != 0 {
+                (x + 1).min(FIELD_WIDTH - 1)
+            } else {
+                x.saturating_sub(1)
+            };
+            y = if input & 0x2 != 0 {
+                (y + 1).min(FIELD_HEIGHT - 1)
+            } else {
+                y.saturating_sub(1)
+            };
+            if field[x][y] < end - 2 {
+                field[x][y] += 1;
+            }
+            input >>= 2;
+        }
+    }
+    field[start_x][start_y] = end - 1;
+    field[x][y] = end;
+
+    // Fall back to the bare key type when the size doesn't fit the border
+    let mut title = format!("[{}]", title);
+    if title.len() > FIELD_WIDTH - 2 {
+        if let Some((name, _)) = title.split_once(' ') {
+            title = format!("{}]", name);
+        }
+    }
+
+    let mut art = border(&title);
+    art.push('\n');
+    for row in 0..FIELD_HEIGHT {
+        art.push('|');
+        for column in field.iter() {
+            art.push(SYMBOLS[column[row].min(end)] as char);
+        }
+        art.push_str("|\n");
+    }
+    art.push_str(&border(&format!("[{}]", hash_name)));
+    art
+}
+
+/// `+---[label]---+`, with the label centred like OpenSSH does (extra dash on the right)
+fn border(label: &str) -> String {
+    let label: String = label.chars().take(FIELD_WIDTH).collect();
+    let left = (FIELD_WIDTH - label.len()) / 2;
+    let right = FIELD_WIDTH - label.len() - left;
+    format!("+{}{}{}+", "-".repeat(left), label, "-".repeat(right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_randomart_matches_ssh_keygen() {
+        // ssh-keygen -lv for SHA256:CnCQx/aaeCXdlmwFiPERQP058MdhX8vFDLv40tb24ww
+        let digest = hex("0a7090c7f69a7825dd966c0588f11140fd39f0c7615fcbc50cbbf8d2d6f6e30c");
+        let expected = "\
++--[ED25519 256]--+
+|  .++=oo..    .+ |
+|  ..=.+.  +   ..+|
+|  .o.o.* * o o.o |
+|   o. + @ o ..o. |
+|   ..= oSo  . .  |
+|  . +. .     o . |
+|   .  .     .E+ o|
+|             ooo.|
+|              .oo|
++----[SHA256]-----+";
+        assert_eq!(randomart("ED25519 256", "SHA256", &digest), expected);
+    }
+
+    #[test]
+    fn test_randomart_shape() {
+        let art = randomart("RSA 4096", "SHA256", &[]);
+        let lines: Vec<&str> = art.lines().collect();
+        assert_eq!(lines.len(), FIELD_HEIGHT + 2);
+        assert!(lines.iter().all(|l| l.chars().count() == FIELD_WIDTH + 2));
+        assert_eq!(lines[0], "+---[RSA 4096]----+");
+        // No moves: start and end share the centre
+        assert_eq!(lines[5], "|        E        |");
+    }
+
+    #[test]
+    fn test_randomart_long_title_drops_size() {
+        let art = randomart("ECDSA-SK-LONG 256", "SHA256", &[0]);
+        assert!(art.starts_with("+-[ECDSA-SK-LONG]-+"));
+    }
+
+    #[test]
+    fn test_bit_length() {
+        assert_eq!(bit_length(&[0x80, 0]), 16);
+        assert_eq!(bit_length(&[0x01, 0]), 9);
+        assert_eq!(bit_length(&[]), 0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::utils::{split_directive, unquote_config_arg};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -35,6 +36,45 @@ impl HostConfig {
     }
 }
 
+/// Match an ssh_config style pattern: `*` matches any run of characters, `?` one character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it is currently matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Path of an IdentityFile value: quotes removed, `~` expanded
+fn expand_identity_path(value: &str) -> PathBuf {
+    let value = unquote_config_arg(value);
+    let home_relative = value
+        .strip_prefix("~/")
+        .or_else(|| value.strip_prefix("~\\"));
+    match (home_relative, dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(value),
+    }
+}
+
 /// SSH Config parser
 pub struct SshConfigParser;
 
@@ -45,21 +85,15 @@ impl SshConfigParser {
         let mut current_host: Option<HostConfig> = None;
 
         for line in content.lines() {
-            let line = line.trim();
-
-            // Skip empty lines and comments
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-
-            // Parse key = value or key value format
-            let (key, value) = if let Some((k, v)) = line.split_once('=') {
-                (k.trim().to_lowercase(), v.trim().to_string())
-            } else if let Some((k, v)) = line.split_once(char::is_whitespace) {
-                (k.trim().to_lowercase(), v.trim().to_string())
-            } else {
+            // Parse key = value or key value format (empty lines and comments give None);
+            // only the first '=' separates, a path may contain more
+            let Some((key, value)) = split_directive(line) else {
                 continue;
             };
+            if value.is_empty() {
+                continue;
+            }
+            let key = key.to_lowercase();
 
             match key.as_str() {
                 "host" => {
@@ -73,6 +107,12 @@ impl SshConfigParser {
                         ..Default::default()
                     });
                 }
+                // Options of a Match block don't belong to the Host block before it
+                "match" => {
+                    if let Some(host) = current_host.take() {
+                        hosts.push(host);
+                    }
+                }
                 "hostname" => {
                     if let Some(ref mut host) = current_host {
                         host.hostname = Some(value);
@@ -90,17 +130,7 @@ impl SshConfigParser {
                 }
                 "identityfile" => {
                     if let Some(ref mut host) = current_host {
-                        // Expand ~ to home directory
-                        let path = if let Some(stripped) = value.strip_prefix("~/") {
-                            if let Some(home) = dirs::home_dir() {
-                                home.join(stripped)
-                            } else {
-                                PathBuf::from(&value)
-                            }
-                        } else {
-                            PathBuf::from(&value)
-                        };
-                        host.identity_file = Some(path);
+                        host.identity_file = Some(expand_identity_path(&value));
                     }
                 }
                 _ => {
@@ -128,23 +158,9 @@ impl SshConfigParser {
             .find(|h| Self::match_pattern(&h.host_pattern, alias))
     }
 
-    /// Match host pattern (supports * wildcard)
+    /// Match host pattern (supports * and ? wildcards)
     fn match_pattern(pattern: &str, alias: &str) -> bool {
-        if pattern == "*" {
-            return true;
-        }
-
-        if pattern.contains('*') {
-            // Simple wildcard matching
-            let parts: Vec<&str> = pattern.split('*').collect();
-            if parts.len() == 2 {
-                let prefix = parts[0];
-                let suffix = parts[1];
-                return alias.starts_with(prefix) && alias.ends_with(suffix);
-            }
-        }
-
-        pattern == alias
+        glob_match(pattern, alias)
     }
 
     /// Merge multiple host configurations (for handling Host * and other global configs)
@@ -206,6 +222,44 @@ Host bitbucket
         assert_eq!(github.user.as_deref(), Some("git"));
     }
 
+    #[test]
+    fn test_parse_identity_file_paths() {
+        let config = "Host 工作 🚀
+    IdentityFile \"C:\\Users\\Jo Doe\\.ssh\\id_ed25519\"
+Host cn
+    IdentityFile=/home/王小明/.ssh/鍵=1
+Host home
+    IdentityFile '~/My Keys/🔑'
+";
+        let hosts = SshConfigParser::parse(config);
+        assert_eq!(hosts[0].host_pattern, "工作 🚀");
+        assert_eq!(
+            hosts[0].identity_file,
+            Some(PathBuf::from(r"C:\Users\Jo Doe\.ssh\id_ed25519"))
+        );
+        assert_eq!(
+            hosts[1].identity_file,
+            Some(PathBuf::from("/home/王小明/.ssh/鍵=1"))
+        );
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(hosts[2].identity_file, Some(home.join("My Keys/🔑")));
+        }
+    }
+
+    #[test]
+    fn test_match_block_ends_host() {
+        let config = "Host web
+    User deploy
+Match host web exec \"test -f ~/.vpn\"
+    User admin
+    ProxyJump bastion
+";
+        let hosts = SshConfigParser::parse(config);
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].user.as_deref(), Some("deploy"));
+        assert!(hosts[0].options.is_empty());
+    }
+
     #[test]
     fn test_find_host() {
         let config = r#"
@@ -230,4 +284,15 @@ Host github
             "test.other.com"
         ));
     }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("web-*", "web-01"));
+        assert!(glob_match("*.example.com", "db.example.com"));
+        assert!(glob_match("db-?", "db-1"));
+        assert!(!glob_match("db-?", "db-10"));
+        assert!(glob_match("*-prod-*", "eu-prod-web"));
+        assert!(!glob_match("web-*", "db-01"));
+    }
 }
@@ -0,0 +1,714 @@
+/// Split a config line into (keyword, value)
+/// Returns None for empty lines and comments
+pub fn split_directive(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    // Keyword ends at the first whitespace or '=' (both "Key value" and "Key=value" are valid)
+    let (key, rest) = match line.find(|c: char| c.is_whitespace() || c == '=') {
+        Some(idx) => line.split_at(idx),
+        None => (line, ""),
+    };
+    let rest = rest.trim_start();
+    let value = rest.strip_prefix('=').unwrap_or(rest).trim();
+
+    Some((key.to_string(), value.to_string()))
+}
+
+/// Keywords taking a single path; ssh splits an unquoted value on whitespace, so
+/// `C:\Users\Jo Doe\.ssh\id_ed25519` has to be written in double quotes
+const PATH_KEYWORDS: &[&str] = &[
+    "IdentityFile",
+    "CertificateFile",
+    "IdentityAgent",
+    "ControlPath",
+    "RevokedHostKeys",
+    "PKCS11Provider",
+    "SecurityKeyProvider",
+    "XAuthLocation",
+];
+
+/// Check if a keyword's value is a single path
+pub fn is_path_keyword(key: &str) -> bool {
+    PATH_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(key))
+}
+
+/// Check if a value is one double-quoted argument: no unescaped quote inside and
+/// the closing quote not escaped (`"a" "b"` is two arguments)
+fn is_quoted_arg(value: &str) -> bool {
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return false;
+    }
+    let inner: Vec<char> = value[1..value.len() - 1].chars().collect();
+    let mut i = 0;
+    while i < inner.len() {
+        match (inner[i], inner.get(i + 1)) {
+            ('\\', Some('"' | '\'' | '\\')) => i += 2,
+            ('\\', None) | ('"', _) => return false,
+            _ => i += 1,
+        }
+    }
+    true
+}
+
+/// Quote a config argument when it contains whitespace, quotes or `#`, or starts
+/// with `=` (which would be read as the keyword separator)
+/// Backslashes stay as they are (Windows paths) except where ssh would read them as
+/// an escape: before a quote, another backslash, or the closing quote
+pub fn quote_config_arg(value: &str) -> String {
+    if is_quoted_arg(value)
+        || (!value.is_empty()
+            && !value.starts_with('=')
+            && !value
+                .chars()
+                .any(|c| c.is_whitespace() || c == '"' || c == '\'' || c == '#'))
+    {
+        return value.to_string();
+    }
+    let chars: Vec<char> = value.chars().collect();
+    let mut quoted = String::from("\"");
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' if matches!(chars.get(i + 1), None | Some('"' | '\\' | '\'')) => {
+                quoted.push_str("\\\\")
+            }
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Value of a quoted config argument (`"..."` or `'...'`), unchanged when unquoted
+pub fn unquote_config_arg(value: &str) -> String {
+    let value = value.trim();
+    match value.chars().next() {
+        Some(q @ ('"' | '\'')) if value.len() >= 2 && value.ends_with(q) => {}
+        _ => return value.to_string(),
+    }
+    let inner: Vec<char> = value[1..value.len() - 1].chars().collect();
+    let mut unquoted = String::new();
+    let mut i = 0;
+    while i < inner.len() {
+        match (inner[i], inner.get(i + 1)) {
+            ('\\', Some(&next @ ('"' | '\'' | '\\'))) => {
+                unquoted.push(next);
+                i += 2;
+            }
+            (c, _) => {
+                unquoted.push(c);
+                i += 1;
+            }
+        }
+    }
+    unquoted
+}
+
+/// Option value as written to the file
+fn format_value(key: &str, value: &str) -> String {
+    if is_path_keyword(key) {
+        quote_config_arg(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Check if a line starts a new Host or Match block
+fn is_block_start(line: &str) -> bool {
+    matches!(
+        split_directive(line),
+        Some((key, _)) if key.eq_ignore_ascii_case("host") || key.eq_ignore_ascii_case("match")
+    )
+}
+
+/// Get leading whitespace of a line
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Line-preserving SSH config editor
+/// Comments, blank lines, indentation and unknown directives are kept as-is,
+/// only the lines that are actually edited change
+#[derive(Debug, Clone)]
+pub struct SshConfigEditor {
+    lines: Vec<String>,
+    /// "\r\n" when the original file used Windows line endings
+    line_ending: &'static str,
+    /// The last line ends with a line break (false only for files saved without one)
+    trailing_newline: bool,
+}
+
+impl SshConfigEditor {
+    /// Parse config file content
+    pub fn parse(content: &str) -> Self {
+        let line_ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        Self {
+            lines: content.lines().map(|l| l.to_string()).collect(),
+            line_ending,
+            trailing_newline: content.is_empty() || content.ends_with('\n'),
+        }
+    }
+
+    /// Render the config back to text
+    pub fn render(&self) -> String {
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        let mut content = self.lines.join(self.line_ending);
+        if self.trailing_newline {
+            content.push_str(self.line_ending);
+        }
+        content
+    }
+
+    /// Find the line range [start, end) of the Host block declaring `alias`
+    fn find_host_block(&self, alias: &str) -> Option<(usize, usize)> {
+        let start = self.lines.iter().position(|line| {
+            matches!(
+                split_directive(line),
+                Some((key, value)) if key.eq_ignore_ascii_case("host")
+                    && value.split_whitespace().any(|pattern| pattern == alias)
+            )
+        })?;
+        Some((start, self.block_end(start)))
+    }
+
+    /// End (exclusive) of the block starting at line `start`
+    fn block_end(&self, start: usize) -> usize {
+        self.lines[start + 1..]
+            .iter()
+            .position(|line| is_block_start(line))
+            .map(|offset| start + 1 + offset)
+            .unwrap_or(self.lines.len())
+    }
+
+    /// Find the index of an option line inside a block
+    fn find_option_line(&self, start: usize, end: usize, key: &str) -> Option<usize> {
+        (start + 1..end).find(|&i| {
+            matches!(split_directive(&self.lines[i]), Some((k, _)) if k.eq_ignore_ascii_case(key))
+        })
+    }
+
+    /// Indentation used by the options of a block (defaults to 4 spaces)
+    fn block_indent(&self, start: usize, end: usize) -> String {
+        (start + 1..end)
+            .find(|&i| split_directive(&self.lines[i]).is_some())
+            .map(|i| leading_whitespace(&self.lines[i]).to_string())
+            .filter(|indent| !indent.is_empty())
+            .unwrap_or_else(|| "    ".to_string())
+    }
+
+    /// Aliases declared on Host lines (wildcard and negated patterns excluded)
+    pub fn host_aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for line in &self.lines {
+            if let Some((key, value)) = split_directive(line) {
+                if !key.eq_ignore_ascii_case("host") {
+                    continue;
+                }
+                for pattern in value.split_whitespace() {
+                    let is_literal = !pattern.contains(['*', '?', '!']);
+                    if is_literal && !aliases.iter().any(|a| a == pattern) {
+                        aliases.push(pattern.to_string());
+                    }
+                }
+            }
+        }
+        aliases
+    }
+
+    /// Insert an option line into the block [start, end)
+    fn insert_option(&mut self, start: usize, end: usize, key: &str, value: &str) {
+        // Insert after the last directive so trailing blank lines/comments stay put
+        let insert_at = (start..end)
+            .rev()
+            .find(|&i| split_directive(&self.lines[i]).is_some())
+            .map(|i| i + 1)
+            .unwrap_or(start + 1);
+        let indent = self.block_indent(start, end);
+        self.lines.insert(
+            insert_at,
+            format!("{}{} {}", indent, key, format_value(key, value)),
+        );
+    }
+
+    /// Check if a Host block exists for the alias
+    pub fn has_host(&self, alias: &str) -> bool {
+        self.find_host_block(alias).is_some()
+    }
+
+    /// Get an option value from a Host block (keyword is case-insensitive)
+    /// Path values come back without their quotes
+    pub fn get_option(&self, alias: &str, key: &str) -> Option<String> {
+        let (start, end) = self.find_host_block(alias)?;
+        let idx = self.find_option_line(start, end, key)?;
+        split_directive(&self.lines[idx]).map(|(_, value)| {
+            if is_path_keyword(key) {
+                unquote_config_arg(&value)
+            } else {
+                value
+            }
+        })
+    }
+
+    /// Set an option in a Host block, replacing the existing line or adding a new one
+    /// Returns false if the host block does not exist
+    pub fn set_option(&mut self, alias: &str, key: &str, value: &str) -> bool {
+        let (start, end) = match self.find_host_block(alias) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        match self.find_option_line(start, end, key) {
+            Some(idx) => {
+                let indent = leading_whitespace(&self.lines[idx]).to_string();
+                self.lines[idx] = format!("{}{} {}", indent, key, format_value(key, value));
+            }
+            None => self.insert_option(start, end, key, value),
+        }
+
+        true
+    }
+
+    /// Add an option line to a Host block even if the keyword is already set
+    /// (for multi-value keywords like IdentityFile); an identical line is not duplicated
+    /// Returns false if the host block does not exist
+    pub fn add_option(&mut self, alias: &str, key: &str, value: &str) -> bool {
+        let (start, end) = match self.find_host_block(alias) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let value = format_value(key, value);
+        let exists = (start + 1..end).any(|i| {
+            matches!(split_directive(&self.lines[i]), Some((k, v)) if k.eq_ignore_ascii_case(key) && v == value)
+        });
+        if exists {
+            return true;
+        }
+
+        self.insert_option(start, end, key, &value);
+        true
+    }
+
+    /// Append a new Host block at the end of the file
+    pub fn append_host(&mut self, alias: &str, options: &[(String, String)]) {
+        if self
+            .lines
+            .last()
+            .is_some_and(|line| !line.trim().is_empty())
+        {
+            self.lines.push(String::new());
+        }
+        self.lines.push(format!("Host {}", alias));
+        for (key, value) in options {
+            self.lines
+                .push(format!("    {} {}", key, format_value(key, value)));
+        }
+    }
+
+    /// Replace the Host line and options of a block (edits from the host form)
+    /// `host` is the whole value of the Host line, or one of its aliases
+    /// Comments inside the block are kept after the new options
+    /// Returns false if the host block does not exist
+    pub fn replace_host(
+        &mut self,
+        host: &str,
+        new_host: &str,
+        options: &[(String, String)],
+    ) -> bool {
+        let range = self
+            .lines
+            .iter()
+            .position(|line| {
+                matches!(
+                    split_directive(line),
+                    Some((key, value)) if key.eq_ignore_ascii_case("host") && value == host
+                )
+            })
+            .map(|start| (start, self.block_end(start)))
+            .or_else(|| self.find_host_block(host));
+        let (start, end) = match range {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let last_directive = (start..end)
+            .rev()
+            .find(|&i| split_directive(&self.lines[i]).is_some())
+            .unwrap_or(start);
+        let indent = self.block_indent(start, end);
+        let mut block = vec![format!(
+            "{}Host {}",
+            leading_whitespace(&self.lines[start]),
+            new_host
+        )];
+        block.extend(
+            options
+                .iter()
+                .map(|(key, value)| format!("{}{} {}", indent, key, format_value(key, value))),
+        );
+        block.extend(
+            self.lines[start + 1..=last_directive]
+                .iter()
+                .filter(|line| !line.trim().is_empty() && split_directive(line).is_none())
+                .cloned(),
+        );
+        self.lines.splice(start..=last_directive, block);
+        true
+    }
+
+    /// Text of the Host block declaring `alias`, up to its last directive, with the
+    /// Host line narrowed to `alias`
+    pub fn host_block(&self, alias: &str) -> Option<String> {
+        let (start, end) = self.find_host_block(alias)?;
+        let last_directive = (start..end)
+            .rev()
+            .find(|&i| split_directive(&self.lines[i]).is_some())
+            .unwrap_or(start);
+        let indent = leading_whitespace(&self.lines[start]);
+        let mut block = format!("{}Host {}\n", indent, alias);
+        for line in &self.lines[start + 1..=last_directive] {
+            block.push_str(line);
+            block.push('\n');
+        }
+        Some(block)
+    }
+
+    /// Remove a host: drops the whole block if it only declares `alias`,
+    /// otherwise just removes the alias from the Host line
+    /// Comments and blank lines after the block's last directive are kept
+    /// Returns false if the host block does not exist
+    pub fn remove_host(&mut self, alias: &str) -> bool {
+        let (start, end) = match self.find_host_block(alias) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let patterns: Vec<String> = split_directive(&self.lines[start])
+            .map(|(_, value)| value.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+        if patterns.len() > 1 {
+            let remaining: Vec<&str> = patterns
+                .iter()
+                .map(String::as_str)
+                .filter(|p| *p != alias)
+                .collect();
+            let indent = leading_whitespace(&self.lines[start]).to_string();
+            self.lines[start] = format!("{}Host {}", indent, remaining.join(" "));
+            return true;
+        }
+
+        let last_directive = (start..end)
+            .rev()
+            .find(|&i| split_directive(&self.lines[i]).is_some())
+            .unwrap_or(start);
+        let mut remove_from = start;
+        // Take the blank line separating the block from the previous one with it
+        if remove_from > 0 && self.lines[remove_from - 1].trim().is_empty() {
+            remove_from -= 1;
+        }
+        self.lines.drain(remove_from..=last_directive);
+        true
+    }
+
+    /// Remove all occurrences of an option from a Host block
+    /// Returns the number of removed lines
+    pub fn remove_option(&mut self, alias: &str, key: &str) -> usize {
+        let mut removed = 0;
+        while let Some((start, end)) = self.find_host_block(alias) {
+            match self.find_option_line(start, end, key) {
+                Some(idx) => {
+                    self.lines.remove(idx);
+                    removed += 1;
+                }
+                None => break,
+            }
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# Personal hosts
+Host github
+    HostName github.com
+    User git
+
+# Work
+Host work-box bastion
+\tHostName 10.0.0.5
+\tUser admin
+
+Host *
+    AddKeysToAgent yes
+";
+
+    #[test]
+    fn test_split_directive_formats() {
+        assert_eq!(
+            split_directive("  HostName github.com"),
+            Some(("HostName".to_string(), "github.com".to_string()))
+        );
+        assert_eq!(
+            split_directive("Port=2222"),
+            Some(("Port".to_string(), "2222".to_string()))
+        );
+        assert_eq!(
+            split_directive("User = git"),
+            Some(("User".to_string(), "git".to_string()))
+        );
+        assert_eq!(
+            split_directive("ProxyCommand ssh -o Foo=bar -W %h:%p jump"),
+            Some((
+                "ProxyCommand".to_string(),
+                "ssh -o Foo=bar -W %h:%p jump".to_string()
+            ))
+        );
+        assert_eq!(split_directive("# comment"), None);
+        assert_eq!(split_directive("   "), None);
+    }
+
+    #[test]
+    fn test_round_trip_unchanged() {
+        let editor = SshConfigEditor::parse(SAMPLE);
+        assert_eq!(editor.render(), SAMPLE);
+    }
+
+    #[test]
+    fn test_round_trip_crlf() {
+        let content = "Host a\r\n    User x\r\n";
+        assert_eq!(SshConfigEditor::parse(content).render(), content);
+    }
+
+    #[test]
+    fn test_round_trip_without_trailing_newline() {
+        let content = "# no newline at the end\nHost a\n    User x";
+        assert_eq!(SshConfigEditor::parse(content).render(), content);
+
+        let mut editor = SshConfigEditor::parse(content);
+        assert!(editor.set_option("a", "User", "y"));
+        assert_eq!(
+            editor.render(),
+            "# no newline at the end\nHost a\n    User y"
+        );
+    }
+
+    #[test]
+    fn test_get_option() {
+        let editor = SshConfigEditor::parse(SAMPLE);
+        assert_eq!(editor.get_option("github", "user").as_deref(), Some("git"));
+        assert_eq!(
+            editor.get_option("bastion", "HostName").as_deref(),
+            Some("10.0.0.5")
+        );
+        assert_eq!(editor.get_option("github", "Port"), None);
+        assert_eq!(editor.get_option("missing", "User"), None);
+    }
+
+    #[test]
+    fn test_set_option_replaces_existing() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        assert!(editor.set_option("github", "User", "deploy"));
+        assert!(editor.render().contains("    User deploy\n"));
+        assert!(!editor.render().contains("User git"));
+    }
+
+    #[test]
+    fn test_set_option_inserts_with_block_indent() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        assert!(editor.set_option("work-box", "GSSAPIAuthentication", "yes"));
+
+        let rendered = editor.render();
+        assert!(rendered.contains("\tUser admin\n\tGSSAPIAuthentication yes\n\nHost *"));
+    }
+
+    #[test]
+    fn test_set_option_missing_host() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        assert!(!editor.set_option("nope", "User", "x"));
+        assert_eq!(editor.render(), SAMPLE);
+    }
+
+    #[test]
+    fn test_host_aliases() {
+        let editor = SshConfigEditor::parse(SAMPLE);
+        assert_eq!(editor.host_aliases(), vec!["github", "work-box", "bastion"]);
+    }
+
+    #[test]
+    fn test_add_option() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        assert!(editor.add_option("github", "IdentityFile", "~/.ssh/a"));
+        assert!(editor.add_option("github", "IdentityFile", "~/.ssh/b"));
+        assert!(editor.add_option("github", "IdentityFile", "~/.ssh/a"));
+
+        let rendered = editor.render();
+        assert_eq!(rendered.matches("IdentityFile ~/.ssh/a").count(), 1);
+        assert!(rendered.contains(
+            "    User git\n    IdentityFile ~/.ssh/a\n    IdentityFile ~/.ssh/b\n\n# Work"
+        ));
+    }
+
+    #[test]
+    fn test_quote_config_arg() {
+        assert_eq!(quote_config_arg("~/.ssh/id_ed25519"), "~/.ssh/id_ed25519");
+        assert_eq!(quote_config_arg("~/.ssh/鍵🔑"), "~/.ssh/鍵🔑");
+        assert_eq!(
+            quote_config_arg(r"C:\Users\Jo Doe\.ssh\id_ed25519"),
+            r#""C:\Users\Jo Doe\.ssh\id_ed25519""#
+        );
+        assert_eq!(quote_config_arg(r"C:\My Keys\"), r#""C:\My Keys\\""#);
+        assert_eq!(quote_config_arg(r#"a "b""#), r#""a \"b\"""#);
+        assert_eq!(
+            quote_config_arg(r#""already quoted""#),
+            r#""already quoted""#
+        );
+        // Two quoted arguments, or a closing quote that is escaped, get quoted again
+        assert_eq!(quote_config_arg(r#""a" "b""#), r#""\"a\" \"b\"""#);
+        assert_eq!(quote_config_arg(r#""a\""#), r#""\"a\\\"""#);
+        assert_eq!(quote_config_arg("=key"), "\"=key\"");
+
+        for path in [
+            r"C:\Users\Jo Doe\.ssh\id_ed25519",
+            r"C:\My Keys\",
+            r"\\server\share\my key",
+            "/home/王小明/My Keys/🔑 work",
+            r#"a "b""#,
+            r#""a" "b""#,
+            r#""a\""#,
+            "=key",
+        ] {
+            assert_eq!(unquote_config_arg(&quote_config_arg(path)), path);
+        }
+        assert_eq!(unquote_config_arg("'~/my key'"), "~/my key");
+        assert_eq!(unquote_config_arg(r"C:\Users\x"), r"C:\Users\x");
+    }
+
+    #[test]
+    fn test_path_options_quoted() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        let key = r"C:\Users\王小明\OneDrive - 公司\.ssh\id 🔑";
+        assert!(editor.set_option("github", "IdentityFile", key));
+        assert!(editor.add_option("github", "IdentityFile", key));
+        let rendered = editor.render();
+        assert_eq!(
+            rendered
+                .matches(&format!("    IdentityFile \"{}\"\n", key))
+                .count(),
+            1
+        );
+        assert_eq!(
+            editor.get_option("github", "identityfile").as_deref(),
+            Some(key)
+        );
+
+        // Other keywords keep their value as-is
+        assert!(editor.set_option("github", "ProxyCommand", "ssh -W %h:%p jump"));
+        assert!(editor
+            .render()
+            .contains("    ProxyCommand ssh -W %h:%p jump\n"));
+
+        editor.append_host(
+            "名前",
+            &[(
+                "CertificateFile".to_string(),
+                "/home/me/my cert.pub".to_string(),
+            )],
+        );
+        assert!(editor
+            .render()
+            .ends_with("Host 名前\n    CertificateFile \"/home/me/my cert.pub\"\n"));
+    }
+
+    #[test]
+    fn test_append_host() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        editor.append_host(
+            "pi",
+            &[
+                ("HostName".to_string(), "raspberrypi.local".to_string()),
+                ("User".to_string(), "pi".to_string()),
+            ],
+        );
+
+        let rendered = editor.render();
+        assert!(rendered.starts_with(SAMPLE));
+        assert!(rendered.ends_with(
+            "    AddKeysToAgent yes\n\nHost pi\n    HostName raspberrypi.local\n    User pi\n"
+        ));
+        assert_eq!(editor.get_option("pi", "user").as_deref(), Some("pi"));
+
+        let mut empty = SshConfigEditor::parse("");
+        empty.append_host("a", &[]);
+        assert_eq!(empty.render(), "Host a\n");
+    }
+
+    #[test]
+    fn test_remove_option() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        assert_eq!(editor.remove_option("github", "user"), 1);
+        assert_eq!(editor.get_option("github", "User"), None);
+        // Other blocks untouched
+        assert_eq!(
+            editor.get_option("work-box", "User").as_deref(),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn test_host_block() {
+        let editor = SshConfigEditor::parse(SAMPLE);
+        assert_eq!(
+            editor.host_block("github").as_deref(),
+            Some("Host github\n    HostName github.com\n    User git\n")
+        );
+        assert_eq!(
+            editor.host_block("bastion").as_deref(),
+            Some("Host bastion\n\tHostName 10.0.0.5\n\tUser admin\n")
+        );
+        assert_eq!(editor.host_block("missing"), None);
+    }
+
+    #[test]
+    fn test_remove_host() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        assert!(editor.remove_host("github"));
+        assert!(!editor.has_host("github"));
+        // The comment in front of the next block stays
+        assert!(editor.render().starts_with("# Personal hosts\n\n# Work\n"));
+
+        assert!(editor.remove_host("bastion"));
+        assert!(editor.has_host("work-box"));
+        assert!(!editor.has_host("bastion"));
+        assert!(!editor.remove_host("missing"));
+    }
+
+    #[test]
+    fn test_replace_host() {
+        let mut editor = SshConfigEditor::parse(SAMPLE);
+        let options = [("HostName".to_string(), "gh.example.com".to_string())];
+        assert!(editor.replace_host("github", "gh", &options));
+        assert!(editor
+            .render()
+            .starts_with("# Personal hosts\nHost gh\n    HostName gh.example.com\n\n# Work\n"));
+
+        // The whole Host line of a multi-alias block, keeping its indent
+        let options = [("User".to_string(), "root".to_string())];
+        assert!(editor.replace_host("work-box bastion", "work", &options));
+        assert!(editor
+            .render()
+            .contains("# Work\nHost work\n\tUser root\n\nHost *\n"));
+        assert!(!editor.replace_host("missing", "x", &[]));
+    }
+}
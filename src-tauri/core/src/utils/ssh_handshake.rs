@@ -0,0 +1,373 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Bytes kept per direction; the identification string and KEXINIT fit easily
+const MAX_CAPTURE: usize = 32 * 1024;
+
+/// SSH_MSG_KEXINIT message number
+const MSG_KEXINIT: u8 = 20;
+
+/// Algorithm lists from a KEXINIT message
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KexAlgorithms {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub ciphers_client_to_server: Vec<String>,
+    pub ciphers_server_to_client: Vec<String>,
+    pub macs_client_to_server: Vec<String>,
+    pub macs_server_to_client: Vec<String>,
+    pub compression_client_to_server: Vec<String>,
+    pub compression_server_to_client: Vec<String>,
+}
+
+/// Algorithms agreed on for a connection (RFC 4253 7.1: the first client choice
+/// the server also supports)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NegotiatedAlgorithms {
+    pub kex: Option<String>,
+    pub host_key: Option<String>,
+    pub cipher_client_to_server: Option<String>,
+    pub cipher_server_to_client: Option<String>,
+    /// None for AEAD ciphers (chacha20-poly1305, AES-GCM), which carry their own MAC
+    pub mac_client_to_server: Option<String>,
+    pub mac_server_to_client: Option<String>,
+    pub compression: Option<String>,
+}
+
+/// What the server revealed during the handshake
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHandshakeInfo {
+    /// Identification string, e.g. "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13"
+    pub server_banner: String,
+    /// e.g. "OpenSSH_9.6p1"
+    pub server_software: Option<String>,
+    /// Everything the server offered
+    pub server_algorithms: Option<KexAlgorithms>,
+    pub negotiated: Option<NegotiatedAlgorithms>,
+}
+
+#[derive(Default)]
+struct Captured {
+    server: Vec<u8>,
+    client: Vec<u8>,
+}
+
+/// Start of both directions of an SSH connection, recorded by `CapturingStream`
+#[derive(Clone, Default)]
+pub struct HandshakeCapture(Arc<Mutex<Captured>>);
+
+impl HandshakeCapture {
+    fn record(&self, data: &[u8], from_server: bool) {
+        let Ok(mut captured) = self.0.lock() else {
+            return;
+        };
+        let buffer = if from_server {
+            &mut captured.server
+        } else {
+            &mut captured.client
+        };
+        let room = MAX_CAPTURE.saturating_sub(buffer.len());
+        buffer.extend_from_slice(&data[..data.len().min(room)]);
+    }
+
+    /// Parse what was captured; None if the server never sent an identification string
+    pub fn info(&self) -> Option<SshHandshakeInfo> {
+        let captured = self.0.lock().ok()?;
+        handshake_info(&captured.server, &captured.client)
+    }
+}
+
+/// Transport wrapper that records the start of the traffic in both directions
+pub struct CapturingStream<S> {
+    inner: S,
+    capture: HandshakeCapture,
+}
+
+impl<S> CapturingStream<S> {
+    pub fn new(inner: S, capture: HandshakeCapture) -> Self {
+        Self { inner, capture }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CapturingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.capture.record(&buf.filled()[before..], true);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CapturingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, data);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.capture.record(&data[..written], false);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Software version of an identification string
+/// ("SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13" -> "OpenSSH_9.6p1")
+pub fn banner_software(banner: &str) -> Option<String> {
+    let rest = banner.strip_prefix("SSH-")?;
+    let (_, software) = rest.split_once('-')?;
+    software
+        .split_whitespace()
+        .next()
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Identification string and the bytes after it
+/// (servers may send other lines before it, RFC 4253 4.2)
+fn split_banner(data: &[u8]) -> Option<(String, &[u8])> {
+    let mut rest = data;
+    loop {
+        let end = rest.iter().position(|&b| b == b'\n')?;
+        let line = String::from_utf8_lossy(&rest[..end])
+            .trim_end_matches('\r')
+            .to_string();
+        rest = &rest[end + 1..];
+        if line.starts_with("SSH-") {
+            return Some((line, rest));
+        }
+    }
+}
+
+/// Parse the first binary packet as a KEXINIT message
+fn parse_kexinit(data: &[u8]) -> Option<KexAlgorithms> {
+    let packet_length = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let padding = *data.get(4)? as usize;
+    let payload = data.get(5..(4 + packet_length).checked_sub(padding)?)?;
+    if payload.first() != Some(&MSG_KEXINIT) {
+        return None;
+    }
+
+    // Message number and 16 byte cookie come first
+    let mut pos = 17;
+    let mut next_list = || -> Option<Vec<String>> {
+        let len = u32::from_be_bytes(payload.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let list = payload.get(pos + 4..pos + 4 + len)?;
+        pos += 4 + len;
+        Some(
+            String::from_utf8_lossy(list)
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    };
+    Some(KexAlgorithms {
+        kex: next_list()?,
+        host_key: next_list()?,
+        ciphers_client_to_server: next_list()?,
+        ciphers_server_to_client: next_list()?,
+        macs_client_to_server: next_list()?,
+        macs_server_to_client: next_list()?,
+        compression_client_to_server: next_list()?,
+        compression_server_to_client: next_list()?,
+    })
+}
+
+fn first_common(client: &[String], server: &[String]) -> Option<String> {
+    client.iter().find(|name| server.contains(name)).cloned()
+}
+
+/// AEAD ciphers carry their own MAC, so no MAC algorithm is negotiated for them
+pub fn is_aead_cipher(cipher: &str) -> bool {
+    cipher.starts_with("chacha20-poly1305") || cipher.contains("-gcm")
+}
+
+fn negotiate(client: &KexAlgorithms, server: &KexAlgorithms) -> NegotiatedAlgorithms {
+    let cipher_client_to_server = first_common(
+        &client.ciphers_client_to_server,
+        &server.ciphers_client_to_server,
+    );
+    let cipher_server_to_client = first_common(
+        &client.ciphers_server_to_client,
+        &server.ciphers_server_to_client,
+    );
+    let mac = |cipher: &Option<String>, client: &[String], server: &[String]| {
+        if cipher.as_deref().is_some_and(is_aead_cipher) {
+            None
+        } else {
+            first_common(client, server)
+        }
+    };
+    NegotiatedAlgorithms {
+        kex: first_common(&client.kex, &server.kex),
+        host_key: first_common(&client.host_key, &server.host_key),
+        mac_client_to_server: mac(
+            &cipher_client_to_server,
+            &client.macs_client_to_server,
+            &server.macs_client_to_server,
+        ),
+        mac_server_to_client: mac(
+            &cipher_server_to_client,
+            &client.macs_server_to_client,
+            &server.macs_server_to_client,
+        ),
+        cipher_client_to_server,
+        cipher_server_to_client,
+        compression: first_common(
+            &client.compression_client_to_server,
+            &server.compression_client_to_server,
+        ),
+    }
+}
+
+/// Server identification string and its KEXINIT algorithms (when complete)
+pub fn parse_server_hello(data: &[u8]) -> Option<(String, Option<KexAlgorithms>)> {
+    let (banner, rest) = split_banner(data)?;
+    Some((banner, parse_kexinit(rest)))
+}
+
+/// Build handshake info from the captured server and client bytes
+fn handshake_info(server: &[u8], client: &[u8]) -> Option<SshHandshakeInfo> {
+    let (server_banner, server_algorithms) = parse_server_hello(server)?;
+    let client_algorithms = split_banner(client).and_then(|(_, rest)| parse_kexinit(rest));
+    let negotiated = match (&client_algorithms, &server_algorithms) {
+        (Some(client), Some(server)) => Some(negotiate(client, server)),
+        _ => None,
+    };
+    Some(SshHandshakeInfo {
+        server_software: banner_software(&server_banner),
+        server_banner,
+        server_algorithms,
+        negotiated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unencrypted KEXINIT packet with the given name-lists
+    fn kexinit_packet(lists: [&str; 10]) -> Vec<u8> {
+        let mut payload = vec![MSG_KEXINIT];
+        payload.extend_from_slice(&[0u8; 16]);
+        for list in lists {
+            payload.extend_from_slice(&(list.len() as u32).to_be_bytes());
+            payload.extend_from_slice(list.as_bytes());
+        }
+        payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+        let padding = 4;
+        let mut packet = ((payload.len() + padding + 1) as u32)
+            .to_be_bytes()
+            .to_vec();
+        packet.push(padding as u8);
+        packet.extend_from_slice(&payload);
+        packet.resize(packet.len() + padding, 0);
+        packet
+    }
+
+    #[test]
+    fn test_banner_software() {
+        assert_eq!(
+            banner_software("SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13").as_deref(),
+            Some("OpenSSH_9.6p1")
+        );
+        assert_eq!(
+            banner_software("SSH-2.0-dropbear_2022.83").as_deref(),
+            Some("dropbear_2022.83")
+        );
+        assert_eq!(banner_software("HTTP/1.1 200 OK"), None);
+    }
+
+    #[test]
+    fn test_handshake_info_negotiates() {
+        let mut server = b"Welcome\r\nSSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13\r\n".to_vec();
+        server.extend(kexinit_packet([
+            "sntrup761x25519-sha512@openssh.com,curve25519-sha256,kex-strict-s-v00@openssh.com",
+            "ssh-ed25519,rsa-sha2-512",
+            "chacha20-poly1305@openssh.com,aes256-ctr",
+            "aes256-ctr,chacha20-poly1305@openssh.com",
+            "hmac-sha2-256-etm@openssh.com",
+            "hmac-sha2-256-etm@openssh.com",
+            "none,zlib@openssh.com",
+            "none,zlib@openssh.com",
+            "",
+            "",
+        ]));
+        let mut client = b"SSH-2.0-russh_0.46\r\n".to_vec();
+        client.extend(kexinit_packet([
+            "curve25519-sha256,ext-info-c",
+            "rsa-sha2-512,ssh-ed25519",
+            "chacha20-poly1305@openssh.com,aes256-ctr",
+            "aes256-ctr,chacha20-poly1305@openssh.com",
+            "hmac-sha2-256-etm@openssh.com",
+            "hmac-sha2-256-etm@openssh.com",
+            "none",
+            "none",
+            "",
+            "",
+        ]));
+
+        let info = handshake_info(&server, &client).unwrap();
+        assert_eq!(info.server_banner, "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13");
+        assert_eq!(info.server_software.as_deref(), Some("OpenSSH_9.6p1"));
+        assert_eq!(info.server_algorithms.as_ref().unwrap().kex.len(), 3);
+
+        let negotiated = info.negotiated.unwrap();
+        assert_eq!(negotiated.kex.as_deref(), Some("curve25519-sha256"));
+        assert_eq!(negotiated.host_key.as_deref(), Some("rsa-sha2-512"));
+        assert_eq!(
+            negotiated.cipher_client_to_server.as_deref(),
+            Some("chacha20-poly1305@openssh.com")
+        );
+        assert_eq!(negotiated.mac_client_to_server, None);
+        assert_eq!(
+            negotiated.cipher_server_to_client.as_deref(),
+            Some("aes256-ctr")
+        );
+        assert_eq!(
+            negotiated.mac_server_to_client.as_deref(),
+            Some("hmac-sha2-256-etm@openssh.com")
+        );
+        assert_eq!(negotiated.compression.as_deref(), Some("none"));
+    }
+
+    #[test]
+    fn test_handshake_info_partial() {
+        // Banner only (e.g. the connection dropped before KEXINIT)
+        let info = handshake_info(b"SSH-2.0-dropbear_2022.83\r\n", b"").unwrap();
+        assert_eq!(info.server_software.as_deref(), Some("dropbear_2022.83"));
+        assert!(info.server_algorithms.is_none() && info.negotiated.is_none());
+        assert!(handshake_info(b"not ssh\r\n", b"").is_none());
+    }
+
+    #[test]
+    fn test_capture_limit() {
+        let capture = HandshakeCapture::default();
+        capture.record(&vec![b'x'; MAX_CAPTURE + 10], true);
+        capture.record(b"more", true);
+        assert_eq!(capture.0.lock().unwrap().server.len(), MAX_CAPTURE);
+    }
+}
@@ -0,0 +1,192 @@
+/// Single line-level diff operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Compute a line diff (LCS based; common prefix/suffix are trimmed first)
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let a = &old_lines[prefix..old_lines.len() - suffix];
+    let b = &new_lines[prefix..new_lines.len() - suffix];
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result: Vec<DiffLine> = old_lines[..prefix]
+        .iter()
+        .map(|l| DiffLine::Equal(l))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            result.push(DiffLine::Equal(a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push(DiffLine::Delete(a[i]));
+            i += 1;
+        } else {
+            result.push(DiffLine::Insert(b[j]));
+            j += 1;
+        }
+    }
+    result.extend(
+        old_lines[old_lines.len() - suffix..]
+            .iter()
+            .map(|l| DiffLine::Equal(l)),
+    );
+    result
+}
+
+/// Render a unified diff (empty string if the texts have the same lines)
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context: usize,
+) -> String {
+    let ops = diff_lines(old, new);
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffLine::Equal(_)))
+        .map(|(idx, _)| idx)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // Group changes whose context overlaps into hunks of op indexes [start, end)
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &idx in &changed {
+        let start = idx.saturating_sub(context);
+        let end = (idx + 1 + context).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    // Line numbers (1-based) at the start of each op
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_line, mut new_line) = (1, 1);
+    for op in &ops {
+        positions.push((old_line, new_line));
+        match op {
+            DiffLine::Equal(_) => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffLine::Delete(_) => old_line += 1,
+            DiffLine::Insert(_) => new_line += 1,
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        let old_count = ops[start..end]
+            .iter()
+            .filter(|op| !matches!(op, DiffLine::Insert(_)))
+            .count();
+        let new_count = ops[start..end]
+            .iter()
+            .filter(|op| !matches!(op, DiffLine::Delete(_)))
+            .count();
+        let (old_start, new_start) = positions[start];
+        // An empty range starts at the line before it (diff convention)
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 {
+                old_start - 1
+            } else {
+                old_start
+            },
+            old_count,
+            if new_count == 0 {
+                new_start - 1
+            } else {
+                new_start
+            },
+            new_count
+        ));
+        for op in &ops[start..end] {
+            match op {
+                DiffLine::Equal(line) => out.push_str(&format!(" {}\n", line)),
+                DiffLine::Delete(line) => out.push_str(&format!("-{}\n", line)),
+                DiffLine::Insert(line) => out.push_str(&format!("+{}\n", line)),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let ops = diff_lines("a\nb\nc\n", "a\nx\nc\nd\n");
+        assert_eq!(
+            ops,
+            vec![
+                DiffLine::Equal("a"),
+                DiffLine::Delete("b"),
+                DiffLine::Insert("x"),
+                DiffLine::Equal("c"),
+                DiffLine::Insert("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_identical() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new", 3), "");
+    }
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n";
+        let diff = unified_diff(old, new, "config", "config (new)", 1);
+        assert_eq!(
+            diff,
+            "--- config\n+++ config (new)\n\
+             @@ -2,3 +2,3 @@\n 2\n-3\n+three\n 4\n\
+             @@ -10,1 +10,2 @@\n 10\n+11\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_from_empty() {
+        let diff = unified_diff("", "Host a\n", "a", "b", 3);
+        assert_eq!(diff, "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+Host a\n");
+    }
+}
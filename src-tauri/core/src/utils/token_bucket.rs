@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+/// Token bucket rate limiter; tokens are bytes
+/// Reservations may overdraw the bucket, the caller then waits until the debt is paid back
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    /// Bytes per second
+    rate: f64,
+    /// Largest burst (one second worth of bytes)
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Bucket allowing `bytes_per_sec` on average, starting full
+    pub fn new(bytes_per_sec: u64, now: Instant) -> Self {
+        let rate = bytes_per_sec.max(1) as f64;
+        Self {
+            rate,
+            capacity: rate,
+            tokens: rate,
+            last_refill: now,
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate as u64
+    }
+
+    /// Take `amount` bytes and return how long to wait before sending them
+    pub fn reserve(&mut self, amount: usize, now: Instant) -> Duration {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+        self.tokens -= amount as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_allows_burst_then_throttles() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000, start);
+        assert_eq!(bucket.reserve(1000, start), Duration::ZERO);
+        assert_eq!(bucket.reserve(500, start), Duration::from_millis(500));
+        // Half a second later the debt is paid; the next 1000 bytes take a second
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.reserve(1000, later), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_refill_is_capped() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100, start);
+        let idle = start + Duration::from_secs(60);
+        assert_eq!(bucket.reserve(100, idle), Duration::ZERO);
+        assert_eq!(bucket.reserve(50, idle), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,209 @@
+//! Windows ACLs through the Win32 API, for systems where icacls is missing or blocked
+
+use std::ffi::c_void;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{
+    CloseHandle, LocalFree, ERROR_SUCCESS, HANDLE, HLOCAL, WIN32_ERROR,
+};
+use windows::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, ConvertStringSidToSidW, GetNamedSecurityInfoW, SetEntriesInAclW,
+    SetNamedSecurityInfoW, EXPLICIT_ACCESS_W, SET_ACCESS, SE_FILE_OBJECT, TRUSTEE_IS_SID,
+    TRUSTEE_IS_USER, TRUSTEE_W,
+};
+use windows::Win32::Security::{
+    GetAce, GetTokenInformation, IsWellKnownSid, LookupAccountSidW, TokenUser,
+    WinBuiltinAdministratorsSid, WinLocalSystemSid, ACCESS_ALLOWED_ACE, ACE_HEADER, ACL,
+    DACL_SECURITY_INFORMATION, NO_INHERITANCE, PROTECTED_DACL_SECURITY_INFORMATION,
+    PSECURITY_DESCRIPTOR, PSID, SID_NAME_USE, SUB_CONTAINERS_AND_OBJECTS_INHERIT, TOKEN_QUERY,
+    TOKEN_USER,
+};
+use windows::Win32::Storage::FileSystem::FILE_ALL_ACCESS;
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+
+/// Account holding a grant in a DACL
+#[derive(Debug, Clone)]
+pub struct AclGrant {
+    /// `DOMAIN\name`; empty when the SID doesn't resolve (e.g. a deleted account)
+    pub account: String,
+    /// String form, e.g. `S-1-5-21-...`
+    pub sid: Option<String>,
+    /// Administrators or SYSTEM
+    pub privileged: bool,
+}
+
+fn wide(path: &Path) -> Vec<u16> {
+    path.as_os_str().encode_wide().chain(Some(0)).collect()
+}
+
+fn check(result: WIN32_ERROR) -> io::Result<()> {
+    if result == ERROR_SUCCESS {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(result.0 as i32))
+    }
+}
+
+/// Accounts the DACL of `path` grants access to
+pub fn read_grants(path: &Path) -> io::Result<Vec<AclGrant>> {
+    let path = wide(path);
+    let mut dacl: *mut ACL = std::ptr::null_mut();
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+    unsafe {
+        check(GetNamedSecurityInfoW(
+            PCWSTR(path.as_ptr()),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            None,
+            None,
+            Some(&mut dacl),
+            None,
+            &mut descriptor,
+        ))?;
+        let grants = dacl_grants(dacl);
+        let _ = LocalFree(HLOCAL(descriptor.0));
+        grants
+    }
+}
+
+unsafe fn dacl_grants(dacl: *const ACL) -> io::Result<Vec<AclGrant>> {
+    if dacl.is_null() {
+        // A null DACL grants everyone full access
+        return Ok(vec![AclGrant {
+            account: "Everyone".to_string(),
+            sid: Some("S-1-1-0".to_string()),
+            privileged: false,
+        }]);
+    }
+    let mut grants = Vec::new();
+    for index in 0..u32::from((*dacl).AceCount) {
+        let mut ace: *mut c_void = std::ptr::null_mut();
+        GetAce(dacl, index, &mut ace).map_err(io::Error::other)?;
+        let header = &*(ace as *const ACE_HEADER);
+        if header.AceType != ACCESS_ALLOWED_ACE_TYPE {
+            continue;
+        }
+        let allowed = ace as *const ACCESS_ALLOWED_ACE;
+        let sid = PSID(std::ptr::addr_of!((*allowed).SidStart) as *mut c_void);
+        let privileged = IsWellKnownSid(sid, WinBuiltinAdministratorsSid).as_bool()
+            || IsWellKnownSid(sid, WinLocalSystemSid).as_bool();
+        grants.push(AclGrant {
+            account: account_name(sid).unwrap_or_default(),
+            sid: sid_string(sid).ok(),
+            privileged,
+        });
+    }
+    Ok(grants)
+}
+
+/// Account the app runs as, from the process token
+#[derive(Debug, Clone)]
+pub struct TokenAccount {
+    pub sid: String,
+    /// `DOMAIN\name`, e.g. `AzureAD\Jane Doe`; empty when it doesn't resolve
+    pub account: String,
+}
+
+pub fn current_user() -> io::Result<TokenAccount> {
+    // u64 storage keeps TOKEN_USER aligned
+    let mut buffer = [0u64; 64];
+    let mut len = 0u32;
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).map_err(io::Error::other)?;
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            std::mem::size_of_val(&buffer) as u32,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        result.map_err(io::Error::other)?;
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+        Ok(TokenAccount {
+            sid: sid_string(user.User.Sid)?,
+            account: account_name(user.User.Sid).unwrap_or_default(),
+        })
+    }
+}
+
+unsafe fn sid_string(sid: PSID) -> io::Result<String> {
+    let mut string = PWSTR::null();
+    ConvertSidToStringSidW(sid, &mut string).map_err(io::Error::other)?;
+    let text = string.to_string().map_err(io::Error::other);
+    let _ = LocalFree(HLOCAL(string.0 as *mut c_void));
+    text
+}
+
+unsafe fn account_name(sid: PSID) -> Option<String> {
+    let mut name = [0u16; 256];
+    let mut domain = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain_len = domain.len() as u32;
+    let mut kind = SID_NAME_USE::default();
+    LookupAccountSidW(
+        PCWSTR::null(),
+        sid,
+        PWSTR(name.as_mut_ptr()),
+        &mut name_len,
+        PWSTR(domain.as_mut_ptr()),
+        &mut domain_len,
+        &mut kind,
+    )
+    .ok()?;
+    let name = String::from_utf16_lossy(&name[..name_len as usize]);
+    let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+    Some(if domain.is_empty() {
+        name
+    } else {
+        format!("{}\\{}", domain, name)
+    })
+}
+
+/// Replace the DACL of `path` with full control for the account `sid` only, without
+/// inherited entries (what `icacls <path> /inheritance:r /grant:r *<sid>:F` does)
+pub fn restrict_to_sid(path: &Path, sid: &str, is_dir: bool) -> io::Result<()> {
+    let path = wide(path);
+    let sid_text: Vec<u16> = sid.encode_utf16().chain(Some(0)).collect();
+    let mut psid = PSID::default();
+    unsafe {
+        ConvertStringSidToSidW(PCWSTR(sid_text.as_ptr()), &mut psid).map_err(io::Error::other)?;
+        let access = EXPLICIT_ACCESS_W {
+            grfAccessPermissions: FILE_ALL_ACCESS.0,
+            grfAccessMode: SET_ACCESS,
+            grfInheritance: if is_dir {
+                SUB_CONTAINERS_AND_OBJECTS_INHERIT
+            } else {
+                NO_INHERITANCE
+            },
+            Trustee: TRUSTEE_W {
+                TrusteeForm: TRUSTEE_IS_SID,
+                TrusteeType: TRUSTEE_IS_USER,
+                ptstrName: PWSTR(psid.0 as *mut u16),
+                ..Default::default()
+            },
+        };
+        let mut acl: *mut ACL = std::ptr::null_mut();
+        let result = check(SetEntriesInAclW(Some(&[access]), None, &mut acl)).and_then(|_| {
+            check(SetNamedSecurityInfoW(
+                PCWSTR(path.as_ptr()),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+                PSID::default(),
+                PSID::default(),
+                Some(acl),
+                None,
+            ))
+        });
+        if !acl.is_null() {
+            let _ = LocalFree(HLOCAL(acl as *mut c_void));
+        }
+        let _ = LocalFree(HLOCAL(psid.0));
+        result
+    }
+}
@@ -0,0 +1,364 @@
+//! Property tests for the config writer: parse → edit → write → parse never loses
+//! or corrupts anything the edit did not touch
+
+use proptest::prelude::*;
+use ssh_buddy_core::utils::{quote_config_arg, SshConfigEditor, SshConfigParser};
+
+const KEYS: &[&str] = &[
+    "HostName",
+    "User",
+    "Port",
+    "IdentityFile",
+    "ProxyJump",
+    "ServerAliveInterval",
+    "LocalForward",
+];
+
+/// One generated Host or Match block
+#[derive(Debug, Clone)]
+struct Block {
+    /// Empty for a Match block
+    aliases: Vec<String>,
+    header: String,
+    /// (keyword, value as get_option returns it, line as written)
+    options: Vec<(String, String, String)>,
+    /// Comment and blank lines after the options
+    trailer: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Config {
+    preamble: Vec<String>,
+    blocks: Vec<Block>,
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+impl Config {
+    fn render(&self) -> String {
+        let mut lines = self.preamble.clone();
+        for block in &self.blocks {
+            lines.push(block.header.clone());
+            lines.extend(block.options.iter().map(|(_, _, line)| line.clone()));
+            lines.extend(block.trailer.iter().cloned());
+        }
+        if lines.is_empty() {
+            return String::new();
+        }
+        let ending = if self.crlf { "\r\n" } else { "\n" };
+        let mut content = lines.join(ending);
+        if self.trailing_newline {
+            content.push_str(ending);
+        }
+        content
+    }
+
+    fn hosts(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter().filter(|b| !b.aliases.is_empty())
+    }
+
+    fn aliases(&self) -> Vec<String> {
+        self.hosts().flat_map(|b| b.aliases.clone()).collect()
+    }
+
+    fn block_of(&self, alias: &str) -> Option<&Block> {
+        self.hosts().find(|b| b.aliases.iter().any(|a| a == alias))
+    }
+}
+
+fn indent() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::new()),
+        Just("    ".to_string()),
+        Just("  ".to_string()),
+        Just("\t".to_string()),
+        Just("\t  ".to_string()),
+    ]
+}
+
+fn separator() -> impl Strategy<Value = &'static str> {
+    prop_oneof![Just(" "), Just("\t"), Just("="), Just(" = "), Just("  ")]
+}
+
+fn comment_line() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::new()),
+        indent(),
+        "[ \t]{0,2}#[ -~]{0,20}",
+        "#[ -~鍵🔑]{0,12}",
+    ]
+}
+
+/// Value that reads back unchanged for keywords written as-is
+fn plain_value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[A-Za-z0-9._@:%/-]{1,12}",
+        "[a-z0-9]{1,4}( [a-z0-9]{1,4}){1,2}",
+        "[a-z]{1,4}=[a-z0-9]{1,4}",
+        "[a-z]{1,4}#[a-z]{1,4}",
+    ]
+}
+
+/// Path value with the characters that need quoting
+fn path_value() -> impl Strategy<Value = String> {
+    "[ -~é王鍵🔑\t]{1,16}".prop_filter("already quoted", |v| {
+        !(v.len() >= 2 && v.starts_with('"') && v.ends_with('"'))
+    })
+}
+
+fn option_value(key: &'static str) -> BoxedStrategy<String> {
+    if key == "IdentityFile" {
+        path_value().boxed()
+    } else {
+        plain_value().boxed()
+    }
+}
+
+fn option_line() -> impl Strategy<Value = (String, String, String)> {
+    prop::sample::select(KEYS)
+        .prop_flat_map(|key| (Just(key), option_value(key), indent(), separator()))
+        .prop_map(|(key, value, indent, sep)| {
+            let written = if key == "IdentityFile" {
+                quote_config_arg(&value)
+            } else {
+                value.clone()
+            };
+            let line = format!("{}{}{}{}", indent, key, sep, written);
+            (key.to_string(), value, line)
+        })
+}
+
+fn block(index: usize) -> impl Strategy<Value = Block> {
+    let header = prop_oneof![
+        3 => (
+            prop::collection::vec("[a-z][a-z0-9-]{0,6}", 1..3),
+            prop_oneof![Just("Host"), Just("host"), Just("HOST")],
+            separator(),
+        )
+            .prop_map(move |(names, keyword, sep)| {
+                // Index suffix keeps every alias unique across the file
+                let aliases: Vec<String> = names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, n)| format!("{}-{}{}", n, index, i))
+                    .collect();
+                let header = format!("{}{}{}", keyword, sep, aliases.join(" "));
+                (aliases, header)
+            }),
+        1 => prop_oneof![
+            Just("Match all".to_string()),
+            Just("Match host *.internal user admin".to_string()),
+            Just("Match exec \"test -f ~/.vpn-up\"".to_string()),
+            Just("match originalhost=web*".to_string()),
+        ]
+        .prop_map(|header| (Vec::new(), header)),
+    ];
+    (
+        header,
+        prop::collection::vec(option_line(), 0..5),
+        prop::collection::vec(comment_line(), 0..3),
+    )
+        .prop_map(|((aliases, header), mut options, trailer)| {
+            // One line per keyword, so "first value wins" and "last value wins"
+            // parsers agree
+            let mut seen = Vec::new();
+            options.retain(|(key, _, _)| {
+                let new = !seen.contains(key);
+                seen.push(key.clone());
+                new
+            });
+            Block {
+                aliases,
+                header,
+                options,
+                trailer,
+            }
+        })
+}
+
+fn config() -> impl Strategy<Value = Config> {
+    (
+        prop::collection::vec(comment_line(), 0..3),
+        (0usize..6).prop_flat_map(|n| (0..n).map(block).collect::<Vec<_>>()),
+        any::<bool>(),
+        any::<bool>(),
+    )
+        .prop_map(|(preamble, blocks, crlf, trailing_newline)| Config {
+            preamble,
+            blocks,
+            crlf,
+            trailing_newline,
+        })
+}
+
+/// Every alias → keyword → value the editor reads, except for `skip`'s block
+fn snapshot(
+    editor: &SshConfigEditor,
+    config: &Config,
+    skip: Option<&str>,
+) -> Vec<(String, &'static str, Option<String>)> {
+    let skipped = skip.and_then(|alias| config.block_of(alias));
+    config
+        .aliases()
+        .into_iter()
+        .filter(|alias| !skipped.is_some_and(|block| block.aliases.contains(alias)))
+        .flat_map(|alias| {
+            KEYS.iter()
+                .map(|key| (alias.clone(), *key, editor.get_option(&alias, key)))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn comment_count(content: &str) -> usize {
+    content
+        .lines()
+        .filter(|l| l.trim_start().starts_with('#'))
+        .count()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn prop_unedited_round_trip_is_identical(config in config()) {
+        let content = config.render();
+        prop_assert_eq!(SshConfigEditor::parse(&content).render(), content);
+    }
+
+    #[test]
+    fn prop_parse_reads_generated_values(config in config()) {
+        let editor = SshConfigEditor::parse(&config.render());
+        for block in config.hosts() {
+            for alias in &block.aliases {
+                prop_assert!(editor.has_host(alias));
+                for (key, value, _) in &block.options {
+                    prop_assert_eq!(editor.get_option(alias, key), Some(value.clone()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prop_parser_agrees_with_editor(config in config()) {
+        let content = config.render();
+        let editor = SshConfigEditor::parse(&content);
+        let hosts = SshConfigParser::parse(&content);
+        for block in config.hosts().filter(|b| b.aliases.len() == 1) {
+            let alias = &block.aliases[0];
+            let host = hosts.iter().find(|h| &h.host_pattern == alias);
+            prop_assert!(host.is_some());
+            let host = host.unwrap();
+            prop_assert_eq!(host.hostname.clone(), editor.get_option(alias, "HostName"));
+            prop_assert_eq!(host.user.clone(), editor.get_option(alias, "User"));
+            prop_assert_eq!(
+                host.options.get("proxyjump").cloned(),
+                editor.get_option(alias, "ProxyJump")
+            );
+        }
+    }
+
+    #[test]
+    fn prop_set_option_only_touches_its_block(
+        config in config(),
+        pick in any::<prop::sample::Index>(),
+        (key, value) in prop::sample::select(KEYS).prop_flat_map(|k| (Just(k), option_value(k))),
+    ) {
+        let aliases = config.aliases();
+        prop_assume!(!aliases.is_empty());
+        let alias = pick.get(&aliases).clone();
+        let content = config.render();
+        let mut editor = SshConfigEditor::parse(&content);
+        let before = snapshot(&editor, &config, Some(&alias));
+
+        prop_assert!(editor.set_option(&alias, key, &value));
+        let written = editor.render();
+        let reparsed = SshConfigEditor::parse(&written);
+
+        prop_assert_eq!(reparsed.get_option(&alias, key), Some(value.clone()));
+        prop_assert_eq!(snapshot(&reparsed, &config, Some(&alias)), before);
+        prop_assert_eq!(comment_count(&written), comment_count(&content));
+        prop_assert_eq!(reparsed.host_aliases(), editor.host_aliases());
+        prop_assert_eq!(written.contains("\r\n"), content.contains("\r\n"));
+    }
+
+    #[test]
+    fn prop_add_and_remove_option(
+        config in config(),
+        pick in any::<prop::sample::Index>(),
+        value in path_value(),
+    ) {
+        let aliases = config.aliases();
+        prop_assume!(!aliases.is_empty());
+        let alias = pick.get(&aliases).clone();
+        let mut editor = SshConfigEditor::parse(&config.render());
+        let before = snapshot(&editor, &config, Some(&alias));
+
+        prop_assert!(editor.add_option(&alias, "CertificateFile", &value));
+        let mut reparsed = SshConfigEditor::parse(&editor.render());
+        prop_assert_eq!(reparsed.get_option(&alias, "CertificateFile"), Some(value));
+
+        prop_assert_eq!(reparsed.remove_option(&alias, "CertificateFile"), 1);
+        let reparsed = SshConfigEditor::parse(&reparsed.render());
+        prop_assert_eq!(reparsed.get_option(&alias, "CertificateFile"), None);
+        prop_assert_eq!(snapshot(&reparsed, &config, Some(&alias)), before);
+    }
+
+    #[test]
+    fn prop_append_and_remove_host(
+        config in config(),
+        options in prop::collection::vec(
+            prop::sample::select(KEYS).prop_flat_map(|k| (Just(k), option_value(k))),
+            0..4,
+        ),
+    ) {
+        let content = config.render();
+        let mut editor = SshConfigEditor::parse(&content);
+        let before = snapshot(&editor, &config, None);
+        let options: Vec<(String, String)> = options
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+
+        editor.append_host("appended", &options);
+        let mut reparsed = SshConfigEditor::parse(&editor.render());
+        prop_assert!(reparsed.has_host("appended"));
+        for (key, _) in &options {
+            // Repeated keywords: the first line wins
+            let first = options.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+            prop_assert_eq!(reparsed.get_option("appended", key), first);
+        }
+        prop_assert_eq!(snapshot(&reparsed, &config, None), before.clone());
+
+        prop_assert!(reparsed.remove_host("appended"));
+        let reparsed = SshConfigEditor::parse(&reparsed.render());
+        prop_assert!(!reparsed.has_host("appended"));
+        prop_assert_eq!(snapshot(&reparsed, &config, None), before);
+        prop_assert_eq!(comment_count(&reparsed.render()), comment_count(&content));
+    }
+
+    #[test]
+    fn prop_remove_host_keeps_the_others(
+        config in config(),
+        pick in any::<prop::sample::Index>(),
+    ) {
+        let aliases = config.aliases();
+        prop_assume!(!aliases.is_empty());
+        let alias = pick.get(&aliases).clone();
+        let content = config.render();
+        let mut editor = SshConfigEditor::parse(&content);
+        let before = snapshot(&editor, &config, Some(&alias));
+
+        prop_assert!(editor.remove_host(&alias));
+        let reparsed = SshConfigEditor::parse(&editor.render());
+        prop_assert!(!reparsed.has_host(&alias));
+        prop_assert_eq!(snapshot(&reparsed, &config, Some(&alias)), before);
+        prop_assert_eq!(comment_count(&editor.render()), comment_count(&content));
+    }
+
+    #[test]
+    fn prop_quoted_paths_round_trip(value in path_value()) {
+        let quoted = quote_config_arg(&value);
+        prop_assert_eq!(ssh_buddy_core::utils::unquote_config_arg(&quoted), value);
+    }
+}
@@ -0,0 +1,35 @@
+//! SSH config handling through the crate's public API, the way a CLI links it
+
+#[allow(dead_code)]
+mod common;
+
+use ssh_buddy_core::utils::{SshConfigEditor, SshConfigParser};
+
+#[test]
+fn test_parse_fixture_config() {
+    let hosts = SshConfigParser::parse(&common::read_fixture("ssh_config/basic.config"));
+    let aliases: Vec<&str> = hosts.iter().map(|h| h.host_pattern.as_str()).collect();
+    assert_eq!(aliases, vec!["github", "gitlab", "myserver"]);
+
+    let server = &hosts[2];
+    assert_eq!(server.get_hostname(), "192.168.1.100");
+    assert_eq!(server.get_port(), 2222);
+    assert_eq!(server.get_user(), Some("admin"));
+}
+
+#[test]
+fn test_edit_fixture_config() {
+    let mut editor = SshConfigEditor::parse(&common::read_fixture("ssh_config/basic.config"));
+    assert!(editor.set_option("gitlab", "User", "deploy"));
+    assert!(!editor.has_host("bitbucket"));
+
+    let reparsed = SshConfigEditor::parse(&editor.render());
+    assert_eq!(
+        reparsed.get_option("gitlab", "User").as_deref(),
+        Some("deploy")
+    );
+    assert_eq!(
+        reparsed.get_option("github", "HostName").as_deref(),
+        Some("github.com")
+    );
+}
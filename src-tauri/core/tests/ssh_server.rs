@@ -0,0 +1,157 @@
+//! Connection, deploy, SFTP and known_hosts flows against the in-process test server
+#![cfg(unix)]
+
+use ssh_buddy_core::models::SshBuddyError;
+use ssh_buddy_core::services::sftp_stream::RemoteFileRequest;
+use ssh_buddy_core::services::{
+    KeyDeployService, RangeRead, SftpStreamService, SshConnectionService,
+};
+use ssh_buddy_core::test_support::{SshTestEnv, TestServerOptions, TestSshServer};
+use ssh_buddy_core::utils::AuthorizedKeyOptions;
+use std::time::Duration;
+
+async fn start(env: &SshTestEnv) -> TestSshServer {
+    TestSshServer::start(TestServerOptions::new(env.remote_home()))
+        .await
+        .expect("Failed to start the test server")
+}
+
+#[tokio::test]
+async fn test_connection_test_succeeds() {
+    let env = SshTestEnv::new().unwrap();
+    let server = start(&env).await;
+    env.connect_to("mock", &server).unwrap();
+
+    let result = SshConnectionService::test_connection("mock").await.unwrap();
+    assert!(result.success, "{:?}", result.error_details);
+}
+
+#[tokio::test]
+async fn test_exec_output_and_status() {
+    let env = SshTestEnv::new().unwrap();
+    let server = start(&env).await;
+    env.connect_to("mock", &server).unwrap();
+
+    let session = SshConnectionService::open_session("mock", None)
+        .await
+        .unwrap();
+    let output = session
+        .exec(
+            "cat; echo oops >&2; exit 3",
+            Some(b"hello".as_slice()),
+            Duration::from_secs(10),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+    session.close().await;
+
+    assert_eq!(output.stdout, "hello");
+    assert_eq!(output.stderr.trim(), "oops");
+    assert_eq!(output.exit_code, Some(3));
+    assert!(server
+        .commands()
+        .await
+        .contains(&"cat; echo oops >&2; exit 3".to_string()));
+}
+
+#[tokio::test]
+async fn test_unauthorized_key_is_rejected() {
+    let env = SshTestEnv::new().unwrap();
+    let server = start(&env).await;
+    env.generate_key("other").unwrap();
+    env.trust(&server).unwrap();
+    env.add_host("mock", &server, Some("other")).unwrap();
+
+    let result = SshConnectionService::open_session("mock", None).await;
+    assert!(matches!(
+        result,
+        Err(SshBuddyError::PermissionDenied { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_known_hosts_checks() {
+    let env = SshTestEnv::new().unwrap();
+    let server = start(&env).await;
+    let public_key = env.generate_key("id_ed25519").unwrap();
+    env.authorize_key(&public_key).unwrap();
+    env.add_host("mock", &server, Some("id_ed25519")).unwrap();
+
+    let result = SshConnectionService::open_session("mock", None).await;
+    assert!(matches!(result, Err(SshBuddyError::HostKeyUnknown { .. })));
+
+    // A different server's key under the same name
+    let impostor = start(&env).await;
+    env.write_ssh_file(
+        "known_hosts",
+        &format!("[127.0.0.1]:{} {}\n", server.port(), impostor.host_key()),
+    )
+    .unwrap();
+    let result = SshConnectionService::open_session("mock", None).await;
+    assert!(matches!(result, Err(SshBuddyError::HostKeyChanged { .. })));
+
+    env.write_ssh_file("known_hosts", "").unwrap();
+    env.trust(&server).unwrap();
+    let session = SshConnectionService::open_session("mock", None)
+        .await
+        .unwrap();
+    session.close().await;
+}
+
+#[tokio::test]
+async fn test_deploy_key() {
+    let env = SshTestEnv::new().unwrap();
+    let server = start(&env).await;
+    env.connect_to("mock", &server).unwrap();
+    let new_key = env.generate_key("deploy_me").unwrap();
+
+    let options = AuthorizedKeyOptions::default();
+    let result = KeyDeployService::deploy_public_key("mock", &new_key, &options, None)
+        .await
+        .unwrap();
+    assert!(!result.replaced);
+    let authorized = env.read_remote_file(".ssh/authorized_keys").unwrap();
+    assert!(authorized.contains(new_key.split_whitespace().nth(1).unwrap()));
+
+    let result = KeyDeployService::deploy_public_key("mock", &new_key, &options, None)
+        .await
+        .unwrap();
+    assert!(result.replaced);
+
+    assert!(KeyDeployService::revoke_public_key("mock", &new_key, None)
+        .await
+        .unwrap());
+    let authorized = env.read_remote_file(".ssh/authorized_keys").unwrap();
+    assert!(!authorized.contains(new_key.split_whitespace().nth(1).unwrap()));
+}
+
+#[tokio::test]
+async fn test_sftp_upload_and_read() {
+    let env = SshTestEnv::new().unwrap();
+    let server = start(&env).await;
+    env.connect_to("mock", &server).unwrap();
+
+    let session = SshConnectionService::open_session("mock", None)
+        .await
+        .unwrap();
+    session
+        .upload_file("notes.txt", b"0123456789", 0o600)
+        .await
+        .unwrap();
+    session.close().await;
+    assert_eq!(env.read_remote_file("notes.txt").unwrap(), "0123456789");
+
+    let request = RemoteFileRequest {
+        host_alias: "mock".to_string(),
+        path: "/notes.txt".to_string(),
+        range: None,
+    };
+    match SftpStreamService::read(&request).await.unwrap() {
+        RangeRead::Chunk(chunk) => {
+            assert_eq!(chunk.data, b"0123456789");
+            assert_eq!(chunk.total, 10);
+        }
+        other => panic!("unexpected read: {:?}", other),
+    }
+}
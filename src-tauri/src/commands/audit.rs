@@ -0,0 +1,27 @@
+use crate::models::SshBuddyError;
+use crate::services::{AuditService, LogExportRequest, SiemSettings, SiemTarget};
+
+/// Export the session history or audit log as CSV or JSON Lines; returns the entry count
+#[tauri::command]
+pub async fn export_log(request: LogExportRequest) -> Result<usize, SshBuddyError> {
+    AuditService::export(&request).await
+}
+
+/// Get the audit event forwarding settings
+#[tauri::command]
+pub async fn get_siem_settings() -> Result<SiemSettings, SshBuddyError> {
+    AuditService::get_settings().await
+}
+
+/// Save the audit event forwarding settings
+#[tauri::command]
+pub async fn set_siem_settings(settings: SiemSettings) -> Result<(), SshBuddyError> {
+    tracing::info!("[audit] Forwarding enabled: {}", settings.enabled);
+    AuditService::set_settings(&settings).await
+}
+
+/// Send a test event to a syslog server or HTTP collector
+#[tauri::command]
+pub async fn test_siem_forwarder(target: SiemTarget) -> Result<(), SshBuddyError> {
+    AuditService::test_forwarder(&target).await
+}
@@ -0,0 +1,44 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    CatalogRefreshResult, CatalogSubscribeRequest, CatalogSubscription, TeamCatalogService,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event emitted with the results of background catalog refreshes that changed hosts
+const CATALOGS_REFRESHED_EVENT: &str = "catalogs-refreshed";
+
+/// Start the periodic catalog refresh (called once at app setup)
+pub fn start_catalog_refresh(app: AppHandle) {
+    tauri::async_runtime::spawn(TeamCatalogService::run_refresh_loop(move |results| {
+        if let Err(e) = app.emit(CATALOGS_REFRESHED_EVENT, results) {
+            tracing::error!("[catalog] Failed to emit catalog refresh: {}", e);
+        }
+    }));
+}
+
+/// List the team catalogs of the active workspace
+#[tauri::command]
+pub async fn list_catalogs() -> Result<Vec<CatalogSubscription>, SshBuddyError> {
+    TeamCatalogService::list().await
+}
+
+/// Subscribe to a signed team catalog and merge its hosts
+#[tauri::command]
+pub async fn subscribe_catalog(
+    request: CatalogSubscribeRequest,
+) -> Result<CatalogRefreshResult, SshBuddyError> {
+    tracing::info!("[catalog] Subscribing to {}", request.name);
+    TeamCatalogService::subscribe(request).await
+}
+
+/// Unsubscribe from a catalog and remove its hosts
+#[tauri::command]
+pub async fn unsubscribe_catalog(id: String) -> Result<CatalogRefreshResult, SshBuddyError> {
+    TeamCatalogService::unsubscribe(&id).await
+}
+
+/// Fetch a catalog now
+#[tauri::command]
+pub async fn refresh_catalog(id: String) -> Result<CatalogRefreshResult, SshBuddyError> {
+    TeamCatalogService::refresh(&id).await
+}
@@ -0,0 +1,260 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    BulkUpdateResult, ConfigHostSummary, ConfigService, CreatedHost, GitCommitInfo,
+    GitSshCommandService, GitSshCommandSpec, GitSshStatus, GitVersioningService,
+    GitVersioningStatus, GssapiOptions, HostFilter, HostPage, HostQuery, HostQueryService,
+    HostTemplate, KerberosService, KerberosTicketStatus, ManagedFile, OptionChange, ProxyService,
+    ProxySettings, Revision, RevisionDiff, RevisionService,
+};
+use crate::utils::{Page, PageQuery};
+use std::collections::HashMap;
+
+/// A page of the config's Host/Match blocks (optionally filtered by a search), for
+/// configs too large to load whole in the frontend
+#[tauri::command]
+pub async fn list_config_hosts(query: PageQuery) -> Result<Page<ConfigHostSummary>, SshBuddyError> {
+    ConfigService::list_hosts(&query).await
+}
+
+/// A page of hosts sorted and filtered on the backend; pass `nextCursor` back for
+/// the next page, so virtualized lists never need the whole registry
+#[tauri::command]
+pub async fn query_hosts(query: HostQuery) -> Result<HostPage, SshBuddyError> {
+    HostQueryService::query(&query).await
+}
+
+/// Remove a host from the SSH config
+#[tauri::command]
+pub async fn delete_ssh_host(alias: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[config] Deleting host: {}", alias);
+    ConfigService::delete_host(&alias).await
+}
+
+/// Add a host from the host form (options as [keyword, value] pairs, in order)
+#[tauri::command]
+pub async fn add_ssh_host(
+    host: String,
+    options: Vec<(String, String)>,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[config] Adding host: {}", host);
+    ConfigService::add_host(&host, &options).await
+}
+
+/// Replace a host's Host line and options with the host form's
+#[tauri::command]
+pub async fn update_ssh_host(
+    original: String,
+    host: String,
+    options: Vec<(String, String)>,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[config] Updating host: {}", original);
+    ConfigService::update_host(&original, &host, &options).await
+}
+
+/// Get GSSAPI (Kerberos) options of a host
+#[tauri::command]
+pub async fn get_host_gssapi_options(host_alias: String) -> Result<GssapiOptions, SshBuddyError> {
+    tracing::info!("[config] Getting GSSAPI options for: {}", host_alias);
+    ConfigService::get_gssapi_options(&host_alias).await
+}
+
+/// Set GSSAPI (Kerberos) options of a host
+#[tauri::command]
+pub async fn set_host_gssapi_options(
+    host_alias: String,
+    options: GssapiOptions,
+) -> Result<GssapiOptions, SshBuddyError> {
+    tracing::info!(
+        "[config] Setting GSSAPI options for {}: {:?}",
+        host_alias,
+        options
+    );
+    ConfigService::set_gssapi_options(&host_alias, &options).await
+}
+
+/// Check whether a Kerberos ticket is available
+#[tauri::command]
+pub async fn check_kerberos_ticket() -> Result<KerberosTicketStatus, SshBuddyError> {
+    tracing::info!("[config] Checking Kerberos ticket");
+    Ok(KerberosService::check_ticket().await)
+}
+
+/// Get the app-level proxy (None = direct connections)
+#[tauri::command]
+pub async fn get_app_proxy() -> Result<Option<ProxySettings>, SshBuddyError> {
+    ProxyService::load_app_proxy().await
+}
+
+/// Set the app-level proxy (None disables it)
+#[tauri::command]
+pub async fn set_app_proxy(proxy: Option<ProxySettings>) -> Result<(), SshBuddyError> {
+    tracing::info!(
+        "[config] Setting app proxy: {:?}",
+        proxy.as_ref().map(|p| (p.proxy_type, &p.host, p.port))
+    );
+    ProxyService::save_app_proxy(proxy.as_ref()).await
+}
+
+/// Get the proxy of a host
+#[tauri::command]
+pub async fn get_host_proxy(host_alias: String) -> Result<Option<ProxySettings>, SshBuddyError> {
+    tracing::info!("[config] Getting proxy for: {}", host_alias);
+    ConfigService::get_host_proxy(&host_alias).await
+}
+
+/// Set the proxy of a host (written as a ProxyCommand line)
+#[tauri::command]
+pub async fn set_host_proxy(
+    host_alias: String,
+    proxy: Option<ProxySettings>,
+) -> Result<(), SshBuddyError> {
+    tracing::info!(
+        "[config] Setting proxy for {}: {:?}",
+        host_alias,
+        proxy.as_ref().map(|p| (p.proxy_type, &p.host, p.port))
+    );
+    ConfigService::set_host_proxy(&host_alias, proxy.as_ref()).await
+}
+
+/// List built-in and user-defined host templates
+#[tauri::command]
+pub async fn list_host_templates() -> Result<Vec<HostTemplate>, SshBuddyError> {
+    ConfigService::list_templates().await
+}
+
+/// Create or replace a user-defined host template
+#[tauri::command]
+pub async fn save_host_template(template: HostTemplate) -> Result<(), SshBuddyError> {
+    tracing::info!("[config] Saving host template: {}", template.id);
+    ConfigService::save_template(template).await
+}
+
+/// Delete a user-defined host template
+#[tauri::command]
+pub async fn delete_host_template(template_id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[config] Deleting host template: {}", template_id);
+    ConfigService::delete_template(&template_id).await
+}
+
+/// Create a host from a template (Host block + registry metadata)
+#[tauri::command]
+pub async fn create_host_from_template(
+    template_id: String,
+    host_alias: String,
+    params: HashMap<String, String>,
+    tags: Option<Vec<String>>,
+) -> Result<CreatedHost, SshBuddyError> {
+    tracing::info!(
+        "[config] Creating host {} from template {}",
+        host_alias,
+        template_id
+    );
+    ConfigService::create_host_from_template(
+        &template_id,
+        &host_alias,
+        &params,
+        &tags.unwrap_or_default(),
+    )
+    .await
+}
+
+/// Apply option changes to all hosts matching a filter (preview with dry_run)
+#[tauri::command]
+pub async fn bulk_update_hosts(
+    filter: HostFilter,
+    changes: Vec<OptionChange>,
+    dry_run: bool,
+) -> Result<BulkUpdateResult, SshBuddyError> {
+    tracing::info!(
+        "[config] Bulk update ({} changes, dry_run: {}): {:?}",
+        changes.len(),
+        dry_run,
+        filter
+    );
+    ConfigService::bulk_update_hosts(&filter, &changes, dry_run).await
+}
+
+/// List stored revisions of a managed file (newest first)
+#[tauri::command]
+pub async fn list_file_revisions(file: ManagedFile) -> Result<Vec<Revision>, SshBuddyError> {
+    RevisionService::list_revisions(file).await
+}
+
+/// Diff two revisions of a managed file ("current" = file on disk)
+#[tauri::command]
+pub async fn diff_file_revisions(
+    file: ManagedFile,
+    from: String,
+    to: String,
+) -> Result<RevisionDiff, SshBuddyError> {
+    tracing::info!("[config] Diffing {:?} revisions {} -> {}", file, from, to);
+    RevisionService::diff_revisions(file, &from, &to).await
+}
+
+/// git's ssh settings (GIT_SSH_COMMAND, GIT_SSH, global/system core.sshCommand)
+#[tauri::command]
+pub async fn get_git_ssh_command() -> Result<GitSshStatus, SshBuddyError> {
+    GitSshCommandService::status().await
+}
+
+/// Validate a spec and return the sshCommand it would set
+#[tauri::command]
+pub fn preview_git_ssh_command(spec: GitSshCommandSpec) -> Result<String, SshBuddyError> {
+    GitSshCommandService::build(&spec)
+}
+
+/// Set the global core.sshCommand (None removes it)
+#[tauri::command]
+pub async fn set_git_ssh_command(
+    spec: Option<GitSshCommandSpec>,
+) -> Result<GitSshStatus, SshBuddyError> {
+    tracing::info!("[config] Setting git sshCommand: {:?}", spec);
+    GitSshCommandService::set_global(spec).await
+}
+
+/// Get git versioning status of the SSH config files
+#[tauri::command]
+pub async fn get_git_versioning_status() -> Result<GitVersioningStatus, SshBuddyError> {
+    GitVersioningService::status().await
+}
+
+/// Enable git versioning (initial commit of the current files)
+#[tauri::command]
+pub async fn enable_git_versioning() -> Result<GitVersioningStatus, SshBuddyError> {
+    tracing::info!("[config] Enabling git versioning");
+    GitVersioningService::enable().await
+}
+
+/// Disable git versioning, optionally deleting the history
+#[tauri::command]
+pub async fn disable_git_versioning(delete_history: bool) -> Result<(), SshBuddyError> {
+    tracing::info!(
+        "[config] Disabling git versioning (delete: {})",
+        delete_history
+    );
+    GitVersioningService::disable(delete_history).await
+}
+
+/// Get the git versioning log (newest first)
+#[tauri::command]
+pub async fn get_git_versioning_log(
+    limit: Option<usize>,
+) -> Result<Vec<GitCommitInfo>, SshBuddyError> {
+    GitVersioningService::log(limit.unwrap_or(100)).await
+}
+
+/// Get the patch of a git versioning commit
+#[tauri::command]
+pub async fn show_git_versioning_commit(commit_id: String) -> Result<String, SshBuddyError> {
+    GitVersioningService::show(&commit_id).await
+}
+
+/// Restore a managed file to its content at a git versioning commit
+#[tauri::command]
+pub async fn revert_to_git_commit(
+    commit_id: String,
+    file: ManagedFile,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[config] Reverting {:?} to {}", file, commit_id);
+    GitVersioningService::revert_file(&commit_id, file).await
+}
@@ -1,17 +1,285 @@
 use crate::models::SshBuddyError;
-use crate::services::{ConnectionTestResult, SshConnectionService};
+use crate::services::{
+    AlgorithmCheckService, AlgorithmCompatReport, AlgorithmOverride, AuthPromptBroker,
+    AuthPromptRequest, AuthPrompter, ClientPqSupport, ConfigSuggestion, ConfigSuggestionService,
+    ConnectionHookService, ConnectionTestResult, EngineCapabilities, EngineChoice, EngineFeature,
+    EngineSettings, HookKind, HookRun, HostFacts, HostFactsService, HostHooks, HostLoginBanner,
+    IsolationSettings, LoginBannerService, PortScanResult, PortScanService, PqReadinessReport,
+    PqReadinessRequest, PqReadinessService, SessionHelperService, SessionHelperStatus,
+    SshConnectionService, SshEngineService, SubnetSweepRequest, SubnetSweepResult,
+};
+use async_trait::async_trait;
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying an interactive auth prompt to the frontend
+const AUTH_PROMPT_EVENT: &str = "ssh-auth-prompt";
+
+/// Event reporting subnet sweep progress and found servers
+const SWEEP_PROGRESS_EVENT: &str = "subnet-sweep-progress";
+
+/// Forwards auth prompts to the frontend and waits for `respond_auth_prompt`
+pub(crate) struct EventPrompter {
+    pub(crate) app: AppHandle,
+}
+
+#[async_trait]
+impl AuthPrompter for EventPrompter {
+    async fn prompt(&self, request: AuthPromptRequest) -> Option<Vec<String>> {
+        let broker = AuthPromptBroker::global();
+        let request_id = request.request_id.clone();
+        let rx = broker.register(&request_id).await;
+
+        if let Err(e) = self.app.emit(AUTH_PROMPT_EVENT, &request) {
+            tracing::error!("[connection] Failed to emit auth prompt: {}", e);
+            let _ = broker.respond(&request_id, None).await;
+            return None;
+        }
+
+        broker.wait(&request_id, rx).await
+    }
+}
 
 /// Test SSH connection
+/// When interactive is true, password/keyboard-interactive prompts are
+/// forwarded to the frontend via the "ssh-auth-prompt" event
 #[tauri::command]
 pub async fn test_ssh_connection(
+    app: AppHandle,
     host_alias: String,
+    interactive: Option<bool>,
 ) -> Result<ConnectionTestResult, SshBuddyError> {
-    log::info!("[connection] Testing SSH connection to: {}", host_alias);
-    let result = SshConnectionService::test_connection(&host_alias).await?;
-    log::info!(
+    tracing::info!("[connection] Testing SSH connection to: {}", host_alias);
+    let mut result = if interactive.unwrap_or(false) {
+        let prompter = EventPrompter { app };
+        SshConnectionService::test_connection_with_prompter(&host_alias, Some(&prompter)).await?
+    } else {
+        SshEngineService::test_connection(&host_alias).await?
+    };
+    if !result.success {
+        match ConfigSuggestionService::suggest(&host_alias, &result).await {
+            Ok(suggestions) => result.suggestions = suggestions,
+            Err(e) => tracing::warn!("[connection] No config suggestions: {}", e),
+        }
+    }
+    tracing::info!(
         "[connection] Test result: success={}, output={}",
         result.success,
         result.output.chars().take(100).collect::<String>()
     );
     Ok(result)
 }
+
+/// Write a config patch suggested by a failed connection test
+#[tauri::command]
+pub async fn apply_config_suggestion(suggestion: ConfigSuggestion) -> Result<(), SshBuddyError> {
+    tracing::info!(
+        "[connection] Applying config suggestion for: {}",
+        suggestion.alias
+    );
+    ConfigSuggestionService::apply(&suggestion).await
+}
+
+/// Answer a pending auth prompt (answers = None cancels it)
+/// Answers are never logged
+#[tauri::command]
+pub async fn respond_auth_prompt(
+    request_id: String,
+    answers: Option<Vec<String>>,
+) -> Result<(), SshBuddyError> {
+    tracing::info!(
+        "[connection] Auth prompt response: {} (cancelled={})",
+        request_id,
+        answers.is_none()
+    );
+    AuthPromptBroker::global()
+        .respond(&request_id, answers)
+        .await
+}
+
+/// Find the SSH port of a host (22, then common alternate ports plus `ports`)
+#[tauri::command]
+pub async fn scan_ssh_ports(
+    hostname: String,
+    ports: Option<Vec<u16>>,
+) -> Result<PortScanResult, SshBuddyError> {
+    tracing::info!("[connection] Scanning SSH ports on: {}", hostname);
+    PortScanService::scan_ssh_ports(&hostname, &ports.unwrap_or_default()).await
+}
+
+/// Probe a CIDR range for SSH servers (only when the user starts it)
+/// Progress is streamed via the "subnet-sweep-progress" event
+#[tauri::command]
+pub async fn sweep_subnet(
+    app: AppHandle,
+    request: SubnetSweepRequest,
+) -> Result<SubnetSweepResult, SshBuddyError> {
+    tracing::info!("[connection] Sweeping subnet: {}", request.cidr);
+    PortScanService::sweep_subnet(&request, move |progress| {
+        if let Err(e) = app.emit(SWEEP_PROGRESS_EVENT, &progress) {
+            tracing::error!("[connection] Failed to emit sweep progress: {}", e);
+        }
+    })
+    .await
+}
+
+/// Gather OS, kernel, uptime, CPU/memory, disk and sshd facts of a host
+/// The facts are cached in the host registry for the host detail view
+#[tauri::command]
+pub async fn collect_host_facts(host: String) -> Result<HostFacts, SshBuddyError> {
+    tracing::info!("[connection] Collecting host facts: {}", host);
+    HostFactsService::collect(&host).await
+}
+
+/// Banner and MOTD captured by the last connection tests (None until a test ran)
+#[tauri::command]
+pub async fn get_host_login_banner(host: String) -> Result<Option<HostLoginBanner>, SshBuddyError> {
+    LoginBannerService::get_host(&host).await
+}
+
+/// Unified diff between the previous and the latest login messages of a host
+/// (empty if they never changed)
+#[tauri::command]
+pub async fn diff_host_login_banner(host: String) -> Result<String, SshBuddyError> {
+    LoginBannerService::diff_host(&host).await
+}
+
+/// Compare the server's offered algorithms with what the local OpenSSH client enables
+/// for the host, suggesting host-scoped overrides for legacy servers
+#[tauri::command]
+pub async fn check_algorithm_compat(host: String) -> Result<AlgorithmCompatReport, SshBuddyError> {
+    tracing::info!("[connection] Checking algorithm compatibility of: {}", host);
+    AlgorithmCheckService::check(&host).await
+}
+
+/// Add algorithm overrides (e.g. KexAlgorithms +diffie-hellman-group14-sha1) to one host
+#[tauri::command]
+pub async fn apply_algorithm_overrides(
+    host: String,
+    overrides: Vec<AlgorithmOverride>,
+) -> Result<(), SshBuddyError> {
+    tracing::info!(
+        "[connection] Applying {} algorithm override(s) to: {}",
+        overrides.len(),
+        host
+    );
+    AlgorithmCheckService::apply_overrides(&host, &overrides).await
+}
+
+/// Hybrid post-quantum key exchange methods supported by the local OpenSSH client
+#[tauri::command]
+pub async fn get_client_pq_support() -> Result<ClientPqSupport, SshBuddyError> {
+    PqReadinessService::client_support().await
+}
+
+/// Report which hosts negotiate hybrid post-quantum key exchange
+/// (sntrup761x25519 / mlkem768x25519) and why the others don't
+#[tauri::command]
+pub async fn check_pq_readiness(
+    request: PqReadinessRequest,
+) -> Result<PqReadinessReport, SshBuddyError> {
+    tracing::info!("[connection] Checking post-quantum readiness");
+    PqReadinessService::report(&request).await
+}
+
+/// Local pre-connect/post-disconnect hooks of a host
+#[tauri::command]
+pub async fn get_host_hooks(host: String) -> Result<HostHooks, SshBuddyError> {
+    ConnectionHookService::get_hooks(&host).await
+}
+
+/// Set the hooks of a host (an empty command removes a hook)
+#[tauri::command]
+pub async fn set_host_hooks(host: String, hooks: HostHooks) -> Result<(), SshBuddyError> {
+    tracing::info!("[connection] Setting hooks of: {}", host);
+    ConnectionHookService::set_hooks(&host, hooks).await
+}
+
+/// Run one of a host's hooks now (e.g. to try it out); None when it has none
+#[tauri::command]
+pub async fn run_host_hook(host: String, kind: HookKind) -> Result<Option<HookRun>, SshBuddyError> {
+    tracing::info!("[connection] Running {:?} hook of: {}", kind, host);
+    ConnectionHookService::run(&host, kind).await
+}
+
+/// Captured output of the host's recent hook runs, newest first
+#[tauri::command]
+pub async fn get_hook_runs(host: String) -> Result<Vec<HookRun>, SshBuddyError> {
+    Ok(ConnectionHookService::recent(&host))
+}
+
+/// VPN/network a host depends on
+#[tauri::command]
+pub async fn get_network_requirement(
+    host: String,
+) -> Result<Option<NetworkRequirement>, SshBuddyError> {
+    NetworkRequirementService::get(&host).await
+}
+
+/// Set (or clear with null) the VPN/network a host depends on
+#[tauri::command]
+pub async fn set_network_requirement(
+    host: String,
+    requirement: Option<NetworkRequirement>,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[connection] Setting network requirement of: {}", host);
+    NetworkRequirementService::set(&host, requirement).await
+}
+
+/// Check whether the host's required network is connected (None when it has none)
+#[tauri::command]
+pub async fn check_host_network(host: String) -> Result<Option<NetworkStatus>, SshBuddyError> {
+    NetworkRequirementService::check_host(&host).await
+}
+
+/// Run the host's VPN launch command and wait for the network to come up
+#[tauri::command]
+pub async fn launch_host_network(host: String) -> Result<Option<NetworkStatus>, SshBuddyError> {
+    tracing::info!("[connection] Launching required network of: {}", host);
+    match NetworkRequirementService::get(&host).await? {
+        Some(requirement) => Ok(Some(
+            NetworkRequirementService::launch(&host, &requirement).await,
+        )),
+        None => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub async fn get_isolation_settings() -> Result<IsolationSettings, SshBuddyError> {
+    Ok(SessionHelperService::get_settings())
+}
+
+#[tauri::command]
+pub async fn set_isolation_settings(settings: IsolationSettings) -> Result<(), SshBuddyError> {
+    SessionHelperService::set_settings(&settings).await
+}
+
+/// Whether the SSH helper process runs and how often it crashed recently
+#[tauri::command]
+pub async fn get_session_helper_status() -> Result<SessionHelperStatus, SshBuddyError> {
+    Ok(SessionHelperService::status().await)
+}
+
+#[tauri::command]
+pub async fn get_ssh_engine_settings() -> Result<EngineSettings, SshBuddyError> {
+    Ok(SshEngineService::get_settings())
+}
+
+#[tauri::command]
+pub async fn set_ssh_engine_settings(settings: EngineSettings) -> Result<(), SshBuddyError> {
+    SshEngineService::set_settings(&settings).await
+}
+
+/// What the embedded and system engines support; the system client is probed again
+/// when `refresh` is set
+#[tauri::command]
+pub async fn get_ssh_engine_capabilities(
+    refresh: Option<bool>,
+) -> Result<Vec<EngineCapabilities>, SshBuddyError> {
+    Ok(SshEngineService::capabilities(refresh.unwrap_or(false)).await)
+}
+
+/// Engine a connection test to the host would use
+#[tauri::command]
+pub async fn resolve_ssh_engine(host_alias: String) -> Result<EngineChoice, SshBuddyError> {
+    SshEngineService::resolve(&host_alias, EngineFeature::TestConnection).await
+}
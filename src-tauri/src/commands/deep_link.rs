@@ -0,0 +1,68 @@
+use crate::models::SshBuddyError;
+use crate::services::{DeepLinkRequest, DeepLinkService};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+/// Event carrying a resolved link (the frontend confirms unknown hosts before connecting)
+const DEEP_LINK_EVENT: &str = "deep-link";
+
+/// Event carrying the error for a link that couldn't be used
+const DEEP_LINK_ERROR_EVENT: &str = "deep-link-error";
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Link without its query and fragment, which can carry a session sharing key
+fn link_for_log(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+async fn handle_url(app: &AppHandle, url: String) {
+    show_main_window(app);
+    let result = match DeepLinkService::resolve(&url).await {
+        Ok(request) => app.emit(DEEP_LINK_EVENT, request),
+        Err(e) => {
+            tracing::warn!("[deep_link] Ignoring {}: {}", link_for_log(&url), e);
+            app.emit(DEEP_LINK_ERROR_EVENT, e.to_string())
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!("[deep_link] Failed to emit deep link event: {}", e);
+    }
+}
+
+/// Handle links that launched the app and those opened while it runs
+/// (called once at app setup; a second instance forwards its link here)
+pub fn start_deep_links(app: &AppHandle) -> tauri::Result<()> {
+    // Installers register the schemes on macOS; elsewhere register them at runtime
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!("[deep_link] Failed to register URL schemes: {}", e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            let app = handle.clone();
+            tauri::async_runtime::spawn(async move { handle_url(&app, url.to_string()).await });
+        }
+    });
+
+    let initial = app.deep_link().get_current().ok().flatten();
+    for url in initial.unwrap_or_default() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { handle_url(&app, url.to_string()).await });
+    }
+    Ok(())
+}
+
+/// Resolve a pasted link the same way as an opened one
+#[tauri::command]
+pub async fn resolve_deep_link(url: String) -> Result<DeepLinkRequest, SshBuddyError> {
+    DeepLinkService::resolve(&url).await
+}
@@ -0,0 +1,46 @@
+use super::shell::open_with_events;
+use crate::models::SshBuddyError;
+use crate::services::{DockerContainer, DockerContext, DockerService, DockerStatus};
+use tauri::AppHandle;
+
+/// List local docker contexts with ssh:// endpoints
+#[tauri::command]
+pub async fn list_docker_contexts() -> Result<Vec<DockerContext>, SshBuddyError> {
+    DockerService::list_contexts().await
+}
+
+/// Check whether a host runs a docker daemon the user can reach
+#[tauri::command]
+pub async fn probe_docker(host_alias: String) -> Result<DockerStatus, SshBuddyError> {
+    tracing::info!("[docker] Probing docker on: {}", host_alias);
+    DockerService::probe(&host_alias).await
+}
+
+/// List the containers on a host
+#[tauri::command]
+pub async fn list_docker_containers(
+    host_alias: String,
+) -> Result<Vec<DockerContainer>, SshBuddyError> {
+    tracing::info!("[docker] Listing containers on: {}", host_alias);
+    DockerService::list_containers(&host_alias).await
+}
+
+/// Open an exec shell into a container through the SSH channel
+/// Uses the shell session events; returns the session id
+#[tauri::command]
+pub async fn open_container_shell(
+    app: AppHandle,
+    host_alias: String,
+    container: String,
+    shell: Option<String>,
+    cols: u32,
+    rows: u32,
+) -> Result<String, SshBuddyError> {
+    tracing::info!(
+        "[docker] Opening shell in container {} on {}",
+        container,
+        host_alias
+    );
+    let command = DockerService::exec_shell_command(&container, shell.as_deref().unwrap_or("sh"));
+    open_with_events(app, &host_alias, Some(command), cols, rows, None).await
+}
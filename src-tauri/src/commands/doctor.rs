@@ -0,0 +1,32 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    DoctorComparison, DoctorReport, DoctorService, SshEnvironment, SshInstallationsService,
+};
+
+/// Run the doctor checks on the local SSH setup
+#[tauri::command]
+pub async fn run_doctor() -> Result<DoctorReport, SshBuddyError> {
+    Ok(DoctorService::run().await)
+}
+
+/// All ssh/ssh-agent/ssh-add binaries on PATH with versions, SSH_AUTH_SOCK and git's ssh
+#[tauri::command]
+pub async fn inspect_ssh_installations() -> Result<SshEnvironment, SshBuddyError> {
+    Ok(SshInstallationsService::inspect().await)
+}
+
+/// Stored doctor runs, newest first
+#[tauri::command]
+pub async fn list_doctor_runs() -> Result<Vec<DoctorReport>, SshBuddyError> {
+    DoctorService::list_runs().await
+}
+
+/// New, resolved and persisting findings between two runs (by `ranAt`); defaults to
+/// the latest run and the one before it
+#[tauri::command]
+pub async fn compare_doctor_runs(
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<DoctorComparison, SshBuddyError> {
+    DoctorService::compare(from, to).await
+}
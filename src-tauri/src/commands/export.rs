@@ -0,0 +1,67 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    ExportOptions, ExportResult, ExportService, ExportSigningService, OpenedBundle, SignatureCheck,
+    SigningKeyInfo, TrustedSigner,
+};
+use crate::utils::SecretFinding;
+use std::path::PathBuf;
+
+/// Scan the files an export would contain for secrets
+#[tauri::command]
+pub async fn scan_export_secrets(
+    options: ExportOptions,
+) -> Result<Vec<SecretFinding>, SshBuddyError> {
+    ExportService::scan(&options).await
+}
+
+/// Export the SSH config (and optionally known_hosts and metadata) to a bundle file
+#[tauri::command]
+pub async fn export_bundle(
+    destination: String,
+    options: ExportOptions,
+) -> Result<ExportResult, SshBuddyError> {
+    tracing::info!("[export] Exporting bundle to: {}", destination);
+    ExportService::export_bundle(&destination, &options).await
+}
+
+/// Read a bundle back for a restore (signature checked first, decrypted with `password`)
+#[tauri::command]
+pub async fn open_bundle(
+    path: String,
+    password: Option<String>,
+) -> Result<OpenedBundle, SshBuddyError> {
+    tracing::info!("[export] Opening bundle: {}", path);
+    ExportService::open_bundle(&path, password.as_deref()).await
+}
+
+/// Check the `.sig` next to an exported file
+#[tauri::command]
+pub async fn verify_export_signature(path: String) -> Result<SignatureCheck, SshBuddyError> {
+    ExportSigningService::verify(&PathBuf::from(path)).await
+}
+
+/// Public key exports of this installation are signed with
+#[tauri::command]
+pub async fn get_export_signing_key() -> Result<SigningKeyInfo, SshBuddyError> {
+    ExportSigningService::key_info().await
+}
+
+#[tauri::command]
+pub async fn list_trusted_export_signers() -> Result<Vec<TrustedSigner>, SshBuddyError> {
+    ExportSigningService::list_trusted_signers().await
+}
+
+/// Accept exports signed by another installation's key
+#[tauri::command]
+pub async fn trust_export_signer(
+    public_key: String,
+    label: Option<String>,
+) -> Result<TrustedSigner, SshBuddyError> {
+    ExportSigningService::trust_signer(&public_key, label).await
+}
+
+#[tauri::command]
+pub async fn remove_trusted_export_signer(fingerprint: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[export] Removing trusted export signer {}", fingerprint);
+    ExportSigningService::remove_trusted_signer(&fingerprint).await
+}
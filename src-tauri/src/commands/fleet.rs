@@ -0,0 +1,53 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    AuthorizedKeysAudit, AuthorizedKeysAuditRequest, AuthorizedKeysAuditService, FleetExportFormat,
+    FleetRequest, FleetService, FleetSummary,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying per-host progress and output of a fleet run
+const FLEET_EVENT: &str = "fleet-output";
+
+/// Run a command on all hosts of a tag group (and/or the given aliases)
+/// Progress and output are streamed via the "fleet-output" event
+#[tauri::command]
+pub async fn run_fleet_command(
+    app: AppHandle,
+    request: FleetRequest,
+) -> Result<FleetSummary, SshBuddyError> {
+    tracing::info!(
+        "[fleet] Running fleet command (tag: {:?}, {} explicit hosts)",
+        request.tag,
+        request.host_aliases.len()
+    );
+    FleetService::run(request, move |event| {
+        if let Err(e) = app.emit(FLEET_EVENT, &event) {
+            tracing::error!("[fleet] Failed to emit fleet event: {}", e);
+        }
+    })
+    .await
+}
+
+/// Export a fleet run summary as JSON or CSV
+#[tauri::command]
+pub async fn export_fleet_summary(
+    summary: FleetSummary,
+    format: FleetExportFormat,
+    destination: String,
+) -> Result<String, SshBuddyError> {
+    FleetService::export_summary(&summary, format, &destination).await
+}
+
+/// Read authorized_keys on all hosts of a tag group (and/or the given aliases) and
+/// flag keys that are revoked in a team catalog or not known at all
+#[tauri::command]
+pub async fn audit_fleet_authorized_keys(
+    request: AuthorizedKeysAuditRequest,
+) -> Result<AuthorizedKeysAudit, SshBuddyError> {
+    tracing::info!(
+        "[fleet] Auditing authorized_keys (tag: {:?}, {} explicit hosts)",
+        request.tag,
+        request.host_aliases.len()
+    );
+    AuthorizedKeysAuditService::run(request).await
+}
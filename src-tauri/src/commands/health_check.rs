@@ -0,0 +1,52 @@
+use super::notifications::notify;
+use crate::models::SshBuddyError;
+use crate::services::{
+    HealthCheckRun, HealthCheckService, HealthCheckSettings, HealthCheckStatus, Notification,
+    NotificationCategory,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying a scheduled health check run with new findings
+const HEALTH_CHECK_EVENT: &str = "health-check-new-findings";
+
+/// Run the scheduled health checks (called once at app setup)
+pub fn start_health_checks(app: AppHandle) {
+    tauri::async_runtime::spawn(HealthCheckService::run_schedule_loop(move |run| {
+        if let Err(e) = app.emit(HEALTH_CHECK_EVENT, &run) {
+            tracing::error!("[health_check] Failed to emit health check run: {}", e);
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let body = run
+                .new_findings
+                .iter()
+                .map(|f| f.message.text.clone())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let notification = Notification {
+                category: NotificationCategory::HealthCheck,
+                title: format!("Health check: {} new finding(s)", run.new_findings.len()),
+                body,
+                host_alias: None,
+            };
+            notify(&app, notification).await;
+        });
+    }));
+}
+
+/// Schedule settings, the last run and when the next one is due
+#[tauri::command]
+pub async fn get_health_check_status() -> Result<HealthCheckStatus, SshBuddyError> {
+    HealthCheckService::status().await
+}
+
+#[tauri::command]
+pub async fn set_health_check_settings(settings: HealthCheckSettings) -> Result<(), SshBuddyError> {
+    HealthCheckService::set_settings(&settings).await
+}
+
+/// Run the configured checks now; findings it reports as new won't be notified again
+#[tauri::command]
+pub async fn run_health_check() -> Result<HealthCheckRun, SshBuddyError> {
+    HealthCheckService::run(false).await
+}
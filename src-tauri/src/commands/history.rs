@@ -0,0 +1,8 @@
+use crate::models::SshBuddyError;
+use crate::services::{ActivityQuery, ActivityStats, HistoryService};
+
+/// Session statistics (per day, top hosts, failures by cause) for the dashboard
+#[tauri::command]
+pub async fn get_activity_stats(query: ActivityQuery) -> Result<ActivityStats, SshBuddyError> {
+    HistoryService::activity(&query).await
+}
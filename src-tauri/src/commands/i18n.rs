@@ -0,0 +1,32 @@
+use crate::models::SshBuddyError;
+use crate::utils::{message_catalog, DEFAULT_LOCALE, LOCALES};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Message templates of a locale, keyed like `messageKey` in results and errors
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageCatalog {
+    /// The locale served; unsupported locales get the English catalog
+    pub locale: String,
+    pub available: Vec<String>,
+    pub messages: BTreeMap<String, String>,
+}
+
+/// Get the message catalog for rendering backend messages in the user's language
+#[tauri::command]
+pub async fn get_message_catalog(locale: String) -> Result<MessageCatalog, SshBuddyError> {
+    let locale = if LOCALES.contains(&locale.as_str()) {
+        locale
+    } else {
+        DEFAULT_LOCALE.to_string()
+    };
+    Ok(MessageCatalog {
+        messages: message_catalog(&locale)
+            .into_iter()
+            .map(|(key, template)| (key.to_string(), template.to_string()))
+            .collect(),
+        available: LOCALES.iter().map(|l| l.to_string()).collect(),
+        locale,
+    })
+}
@@ -0,0 +1,46 @@
+use super::notifications::notify;
+use crate::models::SshBuddyError;
+use crate::services::{IntegrityReport, IntegrityService, Notification, NotificationCategory};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying SSH file changes SSH Buddy didn't make
+const INTEGRITY_CHANGED_EVENT: &str = "ssh-files-integrity-changed";
+
+/// Re-verify the SSH files on changes and periodically (called once at app setup)
+pub fn start_integrity_watch(app: AppHandle) {
+    tauri::async_runtime::spawn(IntegrityService::run_verify_loop(move |changes| {
+        if let Err(e) = app.emit(INTEGRITY_CHANGED_EVENT, &changes) {
+            tracing::error!("[integrity] Failed to emit integrity changes: {}", e);
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let body = changes
+                .iter()
+                .map(|c| c.message.text.clone())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let notification = Notification {
+                category: NotificationCategory::SecurityAlert,
+                title: "SSH files changed outside SSH Buddy".to_string(),
+                body,
+                host_alias: None,
+            };
+            notify(&app, notification).await;
+        });
+    }));
+}
+
+/// Compare the SSH files with the integrity baseline (recorded on first use)
+#[tauri::command]
+pub async fn verify_ssh_integrity() -> Result<IntegrityReport, SshBuddyError> {
+    IntegrityService::verify().await
+}
+
+/// Trust the current content of changed files (all of them when `paths` is empty)
+#[tauri::command]
+pub async fn accept_integrity_changes(
+    paths: Vec<String>,
+) -> Result<IntegrityReport, SshBuddyError> {
+    tracing::info!("[integrity] Accepting changes: {:?}", paths);
+    IntegrityService::accept(&paths).await
+}
@@ -0,0 +1,70 @@
+use super::notifications::notify;
+use crate::models::SshBuddyError;
+use crate::services::{
+    JobRun, Notification, NotificationCategory, ScheduledJob, ScheduledJobService,
+    ScheduledJobStatus,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying a scheduled job run that failed
+const JOB_FAILED_EVENT: &str = "scheduled-job-failed";
+
+/// Run the scheduled jobs (called once at app setup)
+pub fn start_job_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(ScheduledJobService::run_schedule_loop(move |run| {
+        if let Err(e) = app.emit(JOB_FAILED_EVENT, &run) {
+            tracing::error!("[job_scheduler] Failed to emit job run: {}", e);
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let failed_hosts = run.failed_hosts();
+            let body = match &run.error {
+                Some(error) => error.clone(),
+                None => format!("Failed on {}", failed_hosts.join(", ")),
+            };
+            let notification = Notification {
+                category: NotificationCategory::JobFailed,
+                title: format!("Job failed: {}", run.job_name),
+                body,
+                // A run that failed on one host is filed under that host
+                host_alias: match failed_hosts.as_slice() {
+                    [alias] => Some(alias.to_string()),
+                    _ => None,
+                },
+            };
+            notify(&app, notification).await;
+        });
+    }));
+}
+
+/// Jobs with their next and last run
+#[tauri::command]
+pub async fn list_scheduled_jobs() -> Result<Vec<ScheduledJobStatus>, SshBuddyError> {
+    ScheduledJobService::list().await
+}
+
+#[tauri::command]
+pub async fn save_scheduled_job(job: ScheduledJob) -> Result<ScheduledJob, SshBuddyError> {
+    ScheduledJobService::save(job).await
+}
+
+/// Delete a job and its run history
+#[tauri::command]
+pub async fn delete_scheduled_job(id: String) -> Result<(), SshBuddyError> {
+    ScheduledJobService::delete(&id).await
+}
+
+/// Run a job now, outside its schedule
+#[tauri::command]
+pub async fn run_scheduled_job(id: String) -> Result<JobRun, SshBuddyError> {
+    ScheduledJobService::run_now(&id).await
+}
+
+/// Past runs of a job (all jobs when `job_id` is omitted), newest first
+#[tauri::command]
+pub async fn list_job_runs(
+    job_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<JobRun>, SshBuddyError> {
+    ScheduledJobService::list_runs(job_id.as_deref(), limit).await
+}
@@ -1,20 +1,30 @@
+use super::connection::EventPrompter;
 use crate::models::{KeyDetails, SSHKeyInfo, SshBuddyError};
-use crate::services::{GenerateKeyOptions, KeyManager};
+use crate::services::{
+    ExposureReport, FingerprintIndexEntry, FingerprintIndexService, GenerateKeyOptions,
+    HostKeyScan, IndexRefresh, IntegrityService, KeyDeployRequest, KeyDeployResult,
+    KeyDeployService, KeyExposureService, KeyManager, KeyMetadata, KeyMetadataService,
+    KeypairAuditService, KeypairScan, QrCodeService, QrContent, QrFormat, ScannedPublicKey,
+};
+use crate::utils::AuthorizedKeyOptions;
+use std::collections::HashMap;
+use tauri::ipc::{InvokeBody, Request, Response};
+use tauri::AppHandle;
 
 /// List all SSH keys
 #[tauri::command]
 pub async fn list_ssh_keys() -> Result<Vec<SSHKeyInfo>, SshBuddyError> {
-    log::info!("[keys] Listing SSH keys");
+    tracing::info!("[keys] Listing SSH keys");
     let manager = KeyManager::new()?;
     let keys = manager.list_keys().await?;
-    log::info!("[keys] Found {} keys", keys.len());
+    tracing::info!("[keys] Found {} keys", keys.len());
     Ok(keys)
 }
 
 /// Read public key content
 #[tauri::command]
 pub async fn read_public_key(key_name: String) -> Result<String, SshBuddyError> {
-    log::info!("[keys] Reading public key: {}", key_name);
+    tracing::info!("[keys] Reading public key: {}", key_name);
     let manager = KeyManager::new()?;
     let content = manager.read_public_key(&key_name).await?;
     Ok(content)
@@ -23,7 +33,7 @@ pub async fn read_public_key(key_name: String) -> Result<String, SshBuddyError>
 /// Get key details
 #[tauri::command]
 pub async fn get_key_details(key_path: String) -> Result<KeyDetails, SshBuddyError> {
-    log::info!("[keys] Getting key details: {}", key_path);
+    tracing::info!("[keys] Getting key details: {}", key_path);
     let manager = KeyManager::new()?;
     let details = manager.get_key_details(&key_path).await?;
     Ok(details)
@@ -32,23 +42,165 @@ pub async fn get_key_details(key_path: String) -> Result<KeyDetails, SshBuddyErr
 /// Generate a new SSH key pair
 #[tauri::command]
 pub async fn generate_ssh_key(options: GenerateKeyOptions) -> Result<SSHKeyInfo, SshBuddyError> {
-    log::info!(
+    tracing::info!(
         "[keys] Generating {} key: {}",
         options.key_type,
         options.name
     );
     let manager = KeyManager::new()?;
+    let name = options.name.clone();
     let key_info = manager.generate_key(options).await?;
-    log::info!("[keys] Key generated successfully");
+    IntegrityService::record_key_write(&name).await;
+    tracing::info!("[keys] Key generated successfully");
     Ok(key_info)
 }
 
+/// QR code of a public key or its fingerprint, as raw PNG or SVG bytes
+#[tauri::command]
+pub async fn render_key_qr_code(
+    key_name: String,
+    content: QrContent,
+    format: QrFormat,
+) -> Result<Response, SshBuddyError> {
+    tracing::info!(
+        "[keys] Rendering {:?} QR code of key: {}",
+        content,
+        key_name
+    );
+    let bytes = QrCodeService::render_key(&key_name, content, format).await?;
+    Ok(Response::new(bytes))
+}
+
+/// Read a public key from a QR code image sent as the raw request body
+#[tauri::command]
+pub async fn scan_public_key_qr(request: Request<'_>) -> Result<ScannedPublicKey, SshBuddyError> {
+    let InvokeBody::Raw(image) = request.body() else {
+        return Err(SshBuddyError::InvalidOption {
+            message: "Send the image as raw bytes".to_string(),
+        });
+    };
+    QrCodeService::scan(image.clone()).await
+}
+
 /// Delete an SSH key pair
 #[tauri::command]
 pub async fn delete_ssh_key(key_name: String) -> Result<(), SshBuddyError> {
-    log::info!("[keys] Deleting key: {}", key_name);
+    tracing::info!("[keys] Deleting key: {}", key_name);
     let manager = KeyManager::new()?;
     manager.delete_key(&key_name).await?;
-    log::info!("[keys] Key deleted successfully");
+    IntegrityService::record_key_write(&key_name).await;
+    tracing::info!("[keys] Key deleted successfully");
     Ok(())
 }
+
+/// Rewrite the comment of a key pair (private key and .pub)
+#[tauri::command]
+pub async fn set_key_comment(
+    key_name: String,
+    comment: String,
+    passphrase: Option<String>,
+) -> Result<SSHKeyInfo, SshBuddyError> {
+    tracing::info!("[keys] Setting comment of key: {}", key_name);
+    let manager = KeyManager::new()?;
+    let key_info = manager
+        .set_comment(&key_name, &comment, passphrase.as_deref())
+        .await?;
+    IntegrityService::record_key_write(&key_name).await;
+    Ok(key_info)
+}
+
+/// Pair .pub files with private keys and report orphans, missing halves and mismatches
+#[tauri::command]
+pub async fn scan_keypairs() -> Result<KeypairScan, SshBuddyError> {
+    KeypairAuditService::scan().await
+}
+
+/// Rebuild a key's .pub from its private key (`overwrite` replaces a mismatched one)
+#[tauri::command]
+pub async fn regenerate_public_key(
+    key_name: String,
+    overwrite: bool,
+) -> Result<SSHKeyInfo, SshBuddyError> {
+    tracing::info!("[keys] Regenerating public key: {}", key_name);
+    let manager = KeyManager::new()?;
+    let key_info = manager.regenerate_public_key(&key_name, overwrite).await?;
+    IntegrityService::record_key_write(&key_name).await;
+    Ok(key_info)
+}
+
+/// Metadata (friendly name, purpose, owner, linked hosts) of all keys by fingerprint
+#[tauri::command]
+pub async fn list_key_metadata() -> Result<HashMap<String, KeyMetadata>, SshBuddyError> {
+    KeyMetadataService::list().await
+}
+
+/// Set the metadata of a key; returns None when the metadata was cleared
+#[tauri::command]
+pub async fn set_key_metadata(
+    fingerprint: String,
+    metadata: KeyMetadata,
+) -> Result<Option<KeyMetadata>, SshBuddyError> {
+    KeyMetadataService::set(&fingerprint, metadata).await
+}
+
+/// Build the authorized_keys line (options + public key) for a key without deploying it
+#[tauri::command]
+pub async fn preview_authorized_keys_line(
+    key_name: String,
+    options: AuthorizedKeyOptions,
+) -> Result<String, SshBuddyError> {
+    KeyDeployService::preview(&key_name, &options).await
+}
+
+/// Install a public key with restricting options in a host's authorized_keys
+#[tauri::command]
+pub async fn deploy_public_key(
+    app: AppHandle,
+    request: KeyDeployRequest,
+) -> Result<KeyDeployResult, SshBuddyError> {
+    tracing::info!(
+        "[keys] Deploying {} to {}",
+        request.key_name,
+        request.host_alias
+    );
+    let prompter = EventPrompter { app };
+    KeyDeployService::deploy(&request, Some(&prompter)).await
+}
+
+/// Read a host's authorized_keys to learn which local keys open it
+#[tauri::command]
+pub async fn scan_host_authorized_keys(
+    app: AppHandle,
+    host_alias: String,
+) -> Result<HostKeyScan, SshBuddyError> {
+    let prompter = EventPrompter { app };
+    KeyExposureService::scan_host(&host_alias, Some(&prompter)).await
+}
+
+/// Which local keys open which hosts, flagging keys that open many hosts
+#[tauri::command]
+pub async fn get_key_exposure_report(
+    wide_threshold: Option<usize>,
+) -> Result<ExposureReport, SshBuddyError> {
+    KeyExposureService::report(wide_threshold).await
+}
+
+/// Everything indexed about key fingerprints: local files, agent identities, hosts
+#[tauri::command]
+pub async fn get_fingerprint_index() -> Result<Vec<FingerprintIndexEntry>, SshBuddyError> {
+    FingerprintIndexService::entries().await
+}
+
+/// What is known about one key fingerprint
+#[tauri::command]
+pub async fn lookup_key_fingerprint(
+    fingerprint: String,
+) -> Result<Option<FingerprintIndexEntry>, SshBuddyError> {
+    FingerprintIndexService::lookup(&fingerprint).await
+}
+
+/// Bring the fingerprint index up to date now instead of waiting for the watcher
+#[tauri::command]
+pub async fn refresh_fingerprint_index() -> Result<IndexRefresh, SshBuddyError> {
+    FingerprintIndexService::refresh().await
+}
@@ -1,27 +1,125 @@
+use super::connection::EventPrompter;
 use crate::models::SshBuddyError;
-use crate::services::{KnownHostAddResult, KnownHostRemoveResult, KnownHostsService};
+use crate::services::{
+    CertAuthority, CertAuthorityService, HostKeyRotationRequest, HostKeyRotationResult,
+    HostKeyRotationService, HostTrust, KnownHostAddResult, KnownHostCandidate, KnownHostEntry,
+    KnownHostRemoveResult, KnownHostsImportRequest, KnownHostsImportResult,
+    KnownHostsImportService, KnownHostsService,
+};
+use crate::utils::{Page, PageQuery};
+use tauri::{AppHandle, Emitter};
+
+/// Event reporting the progress of a host key rotation
+const ROTATION_STEP_EVENT: &str = "host-key-rotation-step";
+
+/// A page of known_hosts entries (optionally filtered by a search)
+#[tauri::command]
+pub async fn list_known_hosts(query: PageQuery) -> Result<Page<KnownHostEntry>, SshBuddyError> {
+    KnownHostsService::list_entries(&query).await
+}
 
 /// Remove a host from known_hosts
 #[tauri::command]
 pub async fn remove_known_host(hostname: String) -> Result<KnownHostRemoveResult, SshBuddyError> {
-    log::info!("[known_hosts] Removing host: {}", hostname);
+    tracing::info!("[known_hosts] Removing host: {}", hostname);
     let result = KnownHostsService::remove_host(&hostname).await?;
-    log::info!("[known_hosts] Remove result: {:?}", result);
+    tracing::info!("[known_hosts] Remove result: {:?}", result);
     Ok(result)
 }
 
+/// Remove one known_hosts line, as listed (`expected` guards against a file that changed)
+#[tauri::command]
+pub async fn remove_known_host_line(line: u32, expected: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[known_hosts] Removing line {}", line);
+    KnownHostsService::remove_line(line, &expected).await
+}
+
 /// Add a host to known_hosts
 #[tauri::command]
 pub async fn add_known_host(
     hostname: String,
     port: Option<u16>,
 ) -> Result<KnownHostAddResult, SshBuddyError> {
-    log::info!(
+    tracing::info!(
         "[known_hosts] Adding host: {}:{}",
         hostname,
         port.unwrap_or(22)
     );
     let result = KnownHostsService::add_host(&hostname, port).await?;
-    log::info!("[known_hosts] Add result: {:?}", result);
+    tracing::info!("[known_hosts] Add result: {:?}", result);
     Ok(result)
 }
+
+/// Suggest Host entries for unhashed known_hosts entries missing from the config
+#[tauri::command]
+pub async fn discover_known_hosts() -> Result<Vec<KnownHostCandidate>, SshBuddyError> {
+    KnownHostsImportService::discover().await
+}
+
+/// Import suggested known_hosts entries into the SSH config and host registry
+#[tauri::command]
+pub async fn import_known_hosts(
+    request: KnownHostsImportRequest,
+) -> Result<KnownHostsImportResult, SshBuddyError> {
+    tracing::info!("[known_hosts] Importing {} host(s)", request.hosts.len());
+    KnownHostsImportService::import_hosts(&request).await
+}
+
+/// Rotate the host keys of a server the user administers (needs sudo on the server)
+/// Steps are reported via the "host-key-rotation-step" event; auth and sudo prompts
+/// are forwarded via the "ssh-auth-prompt" event
+#[tauri::command]
+pub async fn rotate_host_keys(
+    app: AppHandle,
+    request: HostKeyRotationRequest,
+) -> Result<HostKeyRotationResult, SshBuddyError> {
+    tracing::info!(
+        "[known_hosts] Rotating host keys of: {}",
+        request.host_alias
+    );
+    let prompter = EventPrompter { app: app.clone() };
+    HostKeyRotationService::rotate(&request, Some(&prompter), |step| {
+        if let Err(e) = app.emit(ROTATION_STEP_EVENT, step) {
+            tracing::error!("[known_hosts] Failed to emit rotation step: {}", e);
+        }
+    })
+    .await
+}
+
+/// List the CAs trusted via `@cert-authority` lines
+#[tauri::command]
+pub async fn list_cert_authorities() -> Result<Vec<CertAuthority>, SshBuddyError> {
+    CertAuthorityService::list().await
+}
+
+/// Trust a CA public key for hosts matching the given known_hosts patterns
+#[tauri::command]
+pub async fn add_cert_authority(
+    public_key: String,
+    patterns: Vec<String>,
+) -> Result<CertAuthority, SshBuddyError> {
+    tracing::info!("[known_hosts] Adding CA for: {}", patterns.join(","));
+    CertAuthorityService::add(&public_key, &patterns).await
+}
+
+/// Change the host patterns a CA is trusted for
+#[tauri::command]
+pub async fn set_cert_authority_patterns(
+    fingerprint: String,
+    patterns: Vec<String>,
+) -> Result<(), SshBuddyError> {
+    CertAuthorityService::set_patterns(&fingerprint, &patterns).await
+}
+
+/// Stop trusting a CA
+#[tauri::command]
+pub async fn remove_cert_authority(fingerprint: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[known_hosts] Removing CA: {}", fingerprint);
+    CertAuthorityService::remove(&fingerprint).await
+}
+
+/// Which configured hosts are covered by a CA and which have pinned keys
+#[tauri::command]
+pub async fn get_host_trust_coverage() -> Result<Vec<HostTrust>, SshBuddyError> {
+    CertAuthorityService::coverage().await
+}
@@ -0,0 +1,49 @@
+use crate::models::SshBuddyError;
+use crate::services::{HostRevocationReport, KeyRevocation, KrlService, KrlSummary};
+
+/// Summarize a KRL (or plain revoked key list)
+#[tauri::command]
+pub async fn inspect_krl(path: String) -> Result<KrlSummary, SshBuddyError> {
+    KrlService::inspect(&path).await
+}
+
+/// Check the local keys against a KRL
+#[tauri::command]
+pub async fn check_local_keys_revoked(
+    krl_path: String,
+) -> Result<Vec<KeyRevocation>, SshBuddyError> {
+    KrlService::check_local_keys(&krl_path).await
+}
+
+/// Check a host's authorized keys against a KRL
+#[tauri::command]
+pub async fn check_host_keys_revoked(
+    host_alias: String,
+    krl_path: String,
+) -> Result<HostRevocationReport, SshBuddyError> {
+    tracing::info!("[krl] Checking authorized keys of: {}", host_alias);
+    KrlService::check_host(&host_alias, &krl_path).await
+}
+
+/// Generate a KRL revoking the given public keys
+#[tauri::command]
+pub async fn generate_krl(
+    public_keys: Vec<String>,
+    comment: String,
+    output_path: String,
+) -> Result<KrlSummary, SshBuddyError> {
+    tracing::info!("[krl] Generating KRL: {}", output_path);
+    KrlService::generate(&public_keys, &comment, &output_path).await
+}
+
+/// RevokedHostKeys set for all hosts in ~/.ssh/config
+#[tauri::command]
+pub async fn get_revoked_host_keys() -> Result<Option<String>, SshBuddyError> {
+    KrlService::get_revoked_host_keys().await
+}
+
+/// Set or clear RevokedHostKeys for all hosts
+#[tauri::command]
+pub async fn set_revoked_host_keys(path: Option<String>) -> Result<(), SshBuddyError> {
+    KrlService::set_revoked_host_keys(path.as_deref()).await
+}
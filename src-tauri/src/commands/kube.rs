@@ -0,0 +1,26 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    KubeContext, KubeImportRequest, KubeImportResult, KubeImportService, KubeNode,
+};
+
+/// List the contexts in the user's kubeconfig
+#[tauri::command]
+pub async fn list_kube_contexts() -> Result<Vec<KubeContext>, SshBuddyError> {
+    KubeImportService::list_contexts().await
+}
+
+/// List the nodes of a cluster with their internal/external IPs
+#[tauri::command]
+pub async fn list_kube_nodes(context: String) -> Result<Vec<KubeNode>, SshBuddyError> {
+    tracing::info!("[kube] Listing nodes of context: {}", context);
+    KubeImportService::list_nodes(&context).await
+}
+
+/// Create Host entries for cluster nodes (optionally behind a bastion ProxyJump)
+#[tauri::command]
+pub async fn import_kube_nodes(
+    request: KubeImportRequest,
+) -> Result<KubeImportResult, SshBuddyError> {
+    tracing::info!("[kube] Importing nodes from context: {}", request.context);
+    KubeImportService::import_nodes(&request).await
+}
@@ -0,0 +1,58 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    legacy_profiles, LegacyExceptionStatus, LegacyHostRequest, LegacyProfile, LegacyProfileService,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event emitted with legacy exceptions that expired or expire soon
+const LEGACY_DUE_EVENT: &str = "legacy-exceptions-due";
+
+/// Start the legacy exception reminder (called once at app setup)
+pub fn start_legacy_reminders(app: AppHandle) {
+    tauri::async_runtime::spawn(LegacyProfileService::run_reminder_loop(move |due| {
+        if let Err(e) = app.emit(LEGACY_DUE_EVENT, due) {
+            tracing::error!("[legacy] Failed to emit legacy exception reminder: {}", e);
+        }
+    }));
+}
+
+/// List the curated legacy device profiles
+#[tauri::command]
+pub async fn list_legacy_profiles() -> Result<Vec<LegacyProfile>, SshBuddyError> {
+    Ok(legacy_profiles())
+}
+
+/// Create a Host block enabling a profile's deprecated algorithms for one device
+#[tauri::command]
+pub async fn create_legacy_host(
+    request: LegacyHostRequest,
+) -> Result<LegacyExceptionStatus, SshBuddyError> {
+    tracing::info!(
+        "[legacy] Creating legacy host {} ({})",
+        request.alias,
+        request.profile_id
+    );
+    LegacyProfileService::create_host(&request).await
+}
+
+/// List hosts with legacy exceptions, soonest expiry first
+#[tauri::command]
+pub async fn list_legacy_exceptions() -> Result<Vec<LegacyExceptionStatus>, SshBuddyError> {
+    LegacyProfileService::list_exceptions().await
+}
+
+/// Extend a reviewed legacy exception
+#[tauri::command]
+pub async fn renew_legacy_exception(
+    host: String,
+    days: Option<u32>,
+) -> Result<LegacyExceptionStatus, SshBuddyError> {
+    LegacyProfileService::renew(&host, days).await
+}
+
+/// Remove the deprecated algorithms from a host
+#[tauri::command]
+pub async fn remove_legacy_exception(host: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[legacy] Removing legacy exception of {}", host);
+    LegacyProfileService::remove_exception(&host).await
+}
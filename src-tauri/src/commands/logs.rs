@@ -0,0 +1,32 @@
+use crate::models::SshBuddyError;
+use crate::services::{LogEntry, LogQuery, LogService, LogSettings};
+
+/// Get the log level settings
+#[tauri::command]
+pub async fn get_log_settings() -> Result<LogSettings, SshBuddyError> {
+    Ok(LogService::get_settings())
+}
+
+/// Save the log level settings; levels apply at once
+#[tauri::command]
+pub async fn set_log_settings(settings: LogSettings) -> Result<(), SshBuddyError> {
+    LogService::set_settings(&settings).await
+}
+
+/// Query recent log entries (secrets redacted)
+#[tauri::command]
+pub async fn query_logs(query: LogQuery) -> Result<Vec<LogEntry>, SshBuddyError> {
+    LogService::query(&query).await
+}
+
+/// Log entries written after `since` (Unix milliseconds), for following the log
+#[tauri::command]
+pub async fn tail_logs(since: Option<i64>) -> Result<Vec<LogEntry>, SshBuddyError> {
+    LogService::tail(since).await
+}
+
+/// Get the directory containing the log files
+#[tauri::command]
+pub async fn get_log_directory() -> Result<String, SshBuddyError> {
+    Ok(LogService::get_log_dir()?.to_string_lossy().to_string())
+}
@@ -0,0 +1,17 @@
+use crate::models::SshBuddyError;
+use crate::services::{MdnsDiscoveryService, MdnsHost, MdnsImportRequest, MdnsImportResult};
+
+/// Scan the local network for SSH servers advertised over mDNS/Bonjour
+#[tauri::command]
+pub async fn scan_mdns_hosts(secs: Option<u64>) -> Result<Vec<MdnsHost>, SshBuddyError> {
+    MdnsDiscoveryService::scan(secs).await
+}
+
+/// Import discovered mDNS hosts into the SSH config and host registry
+#[tauri::command]
+pub async fn import_mdns_hosts(
+    request: MdnsImportRequest,
+) -> Result<MdnsImportResult, SshBuddyError> {
+    tracing::info!("[mdns] Importing {} host(s)", request.hosts.len());
+    MdnsDiscoveryService::import_hosts(&request).await
+}
@@ -1,15 +1,196 @@
 pub mod agent;
+pub mod audit;
+pub mod catalog;
+pub mod config;
 pub mod connection;
+pub mod deep_link;
+pub mod docker;
+pub mod doctor;
+pub mod export;
+pub mod fleet;
+pub mod health_check;
+pub mod history;
+pub mod i18n;
+pub mod integrity;
+pub mod jobs;
 pub mod keys;
 pub mod known_hosts;
+pub mod krl;
+pub mod kube;
+pub mod legacy;
+pub mod logs;
+pub mod mdns;
+pub mod notifications;
+pub mod onboarding;
+pub mod palette;
 pub mod permissions;
+pub mod privacy;
+pub mod reachability;
+pub mod read_only;
+pub mod registry;
+pub mod remediation;
+pub mod restore;
+pub mod script;
+pub mod settings;
+pub mod shell;
+pub mod shell_history;
+pub mod sudo;
+pub mod tamper;
+pub mod terminal;
+pub mod transfer;
+pub mod trash;
+pub mod tray;
+pub mod tunnel;
+pub mod vault;
+pub mod vm;
+pub mod workspace;
 
 pub use agent::{
-    add_key_to_agent, is_agent_running, is_key_in_agent, list_agent_keys, remove_key_from_agent,
+    add_key_to_agent, is_agent_running, is_key_in_agent, list_agent_keys, lock_agent,
+    remove_key_from_agent, unlock_agent,
+};
+pub use audit::{export_log, get_siem_settings, set_siem_settings, test_siem_forwarder};
+pub use catalog::{
+    list_catalogs, refresh_catalog, start_catalog_refresh, subscribe_catalog, unsubscribe_catalog,
+};
+pub use config::{
+    add_ssh_host, bulk_update_hosts, check_kerberos_ticket, create_host_from_template,
+    delete_host_template, delete_ssh_host, diff_file_revisions, disable_git_versioning,
+    enable_git_versioning, get_app_proxy, get_git_ssh_command, get_git_versioning_log,
+    get_git_versioning_status, get_host_gssapi_options, get_host_proxy, list_config_hosts,
+    list_file_revisions, list_host_templates, preview_git_ssh_command, query_hosts,
+    revert_to_git_commit, save_host_template, set_app_proxy, set_git_ssh_command,
+    set_host_gssapi_options, set_host_proxy, show_git_versioning_commit, update_ssh_host,
+};
+pub use connection::{
+    apply_algorithm_overrides, apply_config_suggestion, check_algorithm_compat, check_host_network,
+    check_pq_readiness, collect_host_facts, diff_host_login_banner, get_client_pq_support,
+    get_hook_runs, get_host_hooks, get_host_login_banner, get_isolation_settings,
+    get_network_requirement, get_session_helper_status, get_ssh_engine_capabilities,
+    get_ssh_engine_settings, launch_host_network, resolve_ssh_engine, respond_auth_prompt,
+    run_host_hook, scan_ssh_ports, set_host_hooks, set_isolation_settings, set_network_requirement,
+    set_ssh_engine_settings, sweep_subnet, test_ssh_connection,
+};
+pub use deep_link::{resolve_deep_link, start_deep_links};
+pub use docker::{
+    list_docker_containers, list_docker_contexts, open_container_shell, probe_docker,
+};
+pub use doctor::{compare_doctor_runs, inspect_ssh_installations, list_doctor_runs, run_doctor};
+pub use export::{
+    export_bundle, get_export_signing_key, list_trusted_export_signers, open_bundle,
+    remove_trusted_export_signer, scan_export_secrets, trust_export_signer,
+    verify_export_signature,
+};
+pub use fleet::{audit_fleet_authorized_keys, export_fleet_summary, run_fleet_command};
+pub use health_check::{
+    get_health_check_status, run_health_check, set_health_check_settings, start_health_checks,
+};
+pub use history::get_activity_stats;
+pub use i18n::get_message_catalog;
+pub use integrity::{accept_integrity_changes, start_integrity_watch, verify_ssh_integrity};
+pub use jobs::{
+    delete_scheduled_job, list_job_runs, list_scheduled_jobs, run_scheduled_job,
+    save_scheduled_job, start_job_scheduler,
+};
+pub use keys::{
+    delete_ssh_key, deploy_public_key, generate_ssh_key, get_fingerprint_index, get_key_details,
+    get_key_exposure_report, list_key_metadata, list_ssh_keys, lookup_key_fingerprint,
+    preview_authorized_keys_line, read_public_key, refresh_fingerprint_index,
+    regenerate_public_key, render_key_qr_code, scan_host_authorized_keys, scan_keypairs,
+    scan_public_key_qr, set_key_comment, set_key_metadata,
+};
+pub use known_hosts::{
+    add_cert_authority, add_known_host, discover_known_hosts, get_host_trust_coverage,
+    import_known_hosts, list_cert_authorities, list_known_hosts, remove_cert_authority,
+    remove_known_host, remove_known_host_line, rotate_host_keys, set_cert_authority_patterns,
+};
+pub use krl::{
+    check_host_keys_revoked, check_local_keys_revoked, generate_krl, get_revoked_host_keys,
+    inspect_krl, set_revoked_host_keys,
+};
+pub use kube::{import_kube_nodes, list_kube_contexts, list_kube_nodes};
+pub use legacy::{
+    create_legacy_host, list_legacy_exceptions, list_legacy_profiles, remove_legacy_exception,
+    renew_legacy_exception, start_legacy_reminders,
+};
+pub use logs::{get_log_directory, get_log_settings, query_logs, set_log_settings, tail_logs};
+pub use mdns::{import_mdns_hosts, scan_mdns_hosts};
+pub use notifications::{
+    clear_notification_history, get_notification_history, get_notification_preferences,
+    send_notification, set_notification_preferences,
+};
+pub use onboarding::{get_onboarding, set_onboarding_finished, set_onboarding_step};
+pub use palette::{
+    delete_snippet, get_palette_shortcut, list_snippets, palette_shortcut_plugin,
+    record_snippet_use, save_snippet, search_palette, set_palette_shortcut, start_palette_shortcut,
 };
-pub use connection::test_ssh_connection;
-pub use keys::{delete_ssh_key, generate_ssh_key, get_key_details, list_ssh_keys, read_public_key};
-pub use known_hosts::{add_known_host, remove_known_host};
 pub use permissions::{
     check_key_permissions, check_ssh_dir_permissions, fix_key_permissions, fix_ssh_dir_permissions,
+    get_permission_capabilities, get_permission_policy, set_permission_policy,
+};
+pub use privacy::{delete_all_local_data, get_privacy_settings, set_privacy_settings};
+pub use reachability::{
+    get_reachability_history, get_reachability_settings, set_reachability_settings,
+    start_reachability_monitor,
+};
+pub use read_only::{get_read_only_mode, set_read_only_mode};
+pub use registry::{
+    add_host_attachment, apply_registry_changes, capture_command_output, delete_host_captures,
+    delete_smart_group, get_host_attachment_path, list_host_captures, list_host_short_codes,
+    list_smart_groups, preview_smart_group, remove_host_attachment, resolve_host_short_code,
+    save_smart_group, set_host_short_code,
+};
+pub use remediation::{apply_remediation, get_remediation_playbook};
+pub use restore::{
+    get_restore_settings, get_restore_summary, set_restore_settings, start_session_restore,
+};
+pub use script::run_remote_script;
+pub use settings::{
+    allow_app_paths, export_settings, get_app_paths, get_app_settings, import_settings,
+    set_ssh_root,
+};
+pub use shell::{
+    close_shell_session, get_console_log_path, get_host_console, get_host_multiplexer,
+    get_host_shell_access, get_shell_route, get_shell_scrollback, list_pinned_sessions,
+    list_remote_sessions, list_session_shares, observe_shared_session, open_shell_session,
+    pin_shell_session, resize_shell_session, send_console_break, set_host_console,
+    set_host_multiplexer, start_session_share, stop_observing_session, stop_session_share,
+    write_shell_session,
+};
+pub use shell_history::{import_history_hosts, scan_shell_history};
+pub use sudo::check_sudo_access;
+pub use tamper::{
+    delete_quarantined_file, list_quarantined_files, quarantine_file, restore_quarantined_file,
+    scan_ssh_directory, start_tamper_watch,
+};
+pub use terminal::{
+    get_host_terminal_profile, get_terminal_settings, list_external_terminals,
+    open_in_external_terminal, set_host_terminal_profile, set_terminal_settings,
+};
+pub use transfer::{
+    cancel_transfer, delete_scheduled_transfer, get_transfer_settings, handle_sftp_request,
+    list_scheduled_transfers, list_transfers, schedule_transfer, set_transfer_rate_limit,
+    set_transfer_settings, start_transfer, start_transfer_scheduler, SFTP_SCHEME,
+};
+pub use trash::{
+    forget_deleted_item, get_trash_settings, list_recently_deleted, restore_deleted_item,
+    set_trash_settings,
+};
+pub use tray::{get_tray_menu, setup_tray};
+pub use tunnel::{
+    delete_reverse_tunnel, delete_tunnel, discover_remote_listeners, get_database_handoffs,
+    get_reverse_tunnel_state, get_tunnel_service_status, install_reverse_tunnel,
+    install_tunnel_service, launch_database_client, list_reverse_tunnels, list_tunnels,
+    preview_reverse_tunnel, preview_tunnel_service, save_reverse_tunnel, save_tunnel, start_tunnel,
+    stop_tunnel, uninstall_reverse_tunnel, uninstall_tunnel_service,
+};
+pub use vault::{
+    change_master_password, create_vault, delete_vault_entry, get_security_settings,
+    get_vault_entry, get_vault_status, list_vault_entries, lock_vault, set_security_settings,
+    set_vault_entry, start_vault_auto_lock, unlock_vault,
+};
+pub use vm::{discover_local_vms, expire_local_vms, import_local_vms, start_vm_expiry};
+pub use workspace::{
+    assign_key_to_workspace, create_workspace, delete_workspace, list_workspaces, switch_workspace,
+    update_workspace,
 };
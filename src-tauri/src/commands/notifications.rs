@@ -0,0 +1,68 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    Notification, NotificationDelivery, NotificationHistoryQuery, NotificationPreferences,
+    NotificationRecord, NotificationService,
+};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// Record a notification and show it unless it's muted or in quiet hours
+pub async fn notify(app: &AppHandle, notification: Notification) -> NotificationRecord {
+    let record = NotificationService::submit(notification).await;
+    if record.delivery == NotificationDelivery::Shown {
+        let result = app
+            .notification()
+            .builder()
+            .title(&record.notification.title)
+            .body(&record.notification.body)
+            .show();
+        if let Err(e) = result {
+            tracing::error!("[notifications] Failed to show notification: {}", e);
+        }
+    } else {
+        tracing::info!(
+            "[notifications] Not showing \"{}\" ({:?})",
+            record.notification.title,
+            record.delivery
+        );
+    }
+    record
+}
+
+/// Show a notification raised by the frontend (e.g. a finished transfer)
+#[tauri::command]
+pub async fn send_notification(
+    app: AppHandle,
+    notification: Notification,
+) -> Result<NotificationRecord, SshBuddyError> {
+    Ok(notify(&app, notification).await)
+}
+
+/// Get notification preferences
+#[tauri::command]
+pub async fn get_notification_preferences() -> Result<NotificationPreferences, SshBuddyError> {
+    NotificationService::get_preferences().await
+}
+
+/// Save notification preferences
+#[tauri::command]
+pub async fn set_notification_preferences(
+    preferences: NotificationPreferences,
+) -> Result<(), SshBuddyError> {
+    NotificationService::set_preferences(&preferences).await
+}
+
+/// Query the notification history, newest first
+#[tauri::command]
+pub async fn get_notification_history(
+    query: Option<NotificationHistoryQuery>,
+) -> Result<Vec<NotificationRecord>, SshBuddyError> {
+    Ok(NotificationService::query(&query.unwrap_or_default()))
+}
+
+/// Clear the notification history
+#[tauri::command]
+pub async fn clear_notification_history() -> Result<(), SshBuddyError> {
+    NotificationService::clear_history();
+    Ok(())
+}
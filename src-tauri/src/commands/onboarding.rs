@@ -0,0 +1,23 @@
+use crate::models::SshBuddyError;
+use crate::services::{OnboardingReport, OnboardingService, OnboardingStep, StepStatus};
+
+/// Inspect the machine and get the prioritized setup checklist
+#[tauri::command]
+pub async fn get_onboarding() -> Result<OnboardingReport, SshBuddyError> {
+    OnboardingService::inspect().await
+}
+
+/// Skip a setup step, undo the skip, or mark it done by hand
+#[tauri::command]
+pub async fn set_onboarding_step(
+    step: OnboardingStep,
+    status: StepStatus,
+) -> Result<(), SshBuddyError> {
+    OnboardingService::set_step_status(step, status).await
+}
+
+/// Finish (or dismiss) the onboarding; `false` starts it over
+#[tauri::command]
+pub async fn set_onboarding_finished(finished: bool) -> Result<(), SshBuddyError> {
+    OnboardingService::set_finished(finished).await
+}
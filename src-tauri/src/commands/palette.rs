@@ -0,0 +1,119 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    PaletteItem, PaletteSearchService, PaletteShortcut, Snippet, SnippetService,
+};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+/// Event asking the frontend to open the quick-connect palette
+const PALETTE_EVENT: &str = "quick-connect-palette";
+
+/// Global shortcut plugin; every registered shortcut opens the palette
+pub fn palette_shortcut_plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            if let Err(e) = app.emit(PALETTE_EVENT, ()) {
+                tracing::error!("[palette] Failed to emit palette event: {}", e);
+            }
+        })
+        .build()
+}
+
+/// Replace the registered palette shortcut
+pub(crate) fn register_shortcut(
+    app: &AppHandle,
+    shortcut: &PaletteShortcut,
+) -> Result<(), SshBuddyError> {
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| SshBuddyError::Unknown {
+            message: format!("Failed to unregister shortcuts: {}", e),
+        })?;
+    if !shortcut.enabled {
+        return Ok(());
+    }
+    shortcuts
+        .register(shortcut.shortcut.as_str())
+        .map_err(|e| SshBuddyError::InvalidOption {
+            message: format!("Cannot use shortcut \"{}\": {}", shortcut.shortcut, e),
+        })
+}
+
+/// Register the saved palette shortcut (called once at app setup)
+pub fn start_palette_shortcut(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let shortcut = PaletteSearchService::get_shortcut()
+            .await
+            .unwrap_or_default();
+        if let Err(e) = register_shortcut(&app, &shortcut) {
+            tracing::warn!("[palette] {}", e);
+        }
+    });
+}
+
+/// Get the palette shortcut
+#[tauri::command]
+pub async fn get_palette_shortcut() -> Result<PaletteShortcut, SshBuddyError> {
+    PaletteSearchService::get_shortcut().await
+}
+
+/// Change the palette shortcut; the previous one stays active if the new one can't be used
+#[tauri::command]
+pub async fn set_palette_shortcut(
+    app: AppHandle,
+    shortcut: PaletteShortcut,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[palette] Setting palette shortcut: {}", shortcut.shortcut);
+    if let Err(e) = register_shortcut(&app, &shortcut) {
+        let previous = PaletteSearchService::get_shortcut()
+            .await
+            .unwrap_or_default();
+        let _ = register_shortcut(&app, &previous);
+        return Err(e);
+    }
+    PaletteSearchService::set_shortcut(&shortcut).await
+}
+
+/// Ranked fuzzy search over hosts and snippets
+#[tauri::command]
+pub async fn search_palette(
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<PaletteItem>, SshBuddyError> {
+    PaletteSearchService::search(&query, limit).await
+}
+
+/// List snippets
+#[tauri::command]
+pub async fn list_snippets() -> Result<Vec<Snippet>, SshBuddyError> {
+    SnippetService::list().await
+}
+
+/// Add or update a snippet
+#[tauri::command]
+pub async fn save_snippet(snippet: Snippet) -> Result<Snippet, SshBuddyError> {
+    tracing::info!("[palette] Saving snippet: {}", snippet.name);
+    SnippetService::save(snippet).await
+}
+
+/// Delete a snippet
+#[tauri::command]
+pub async fn delete_snippet(id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[palette] Deleting snippet: {}", id);
+    SnippetService::delete(&id).await
+}
+
+/// Count a snippet use (ranks it higher in palette searches)
+#[tauri::command]
+pub async fn record_snippet_use(id: String) -> Result<(), SshBuddyError> {
+    SnippetService::record_use(&id).await
+}
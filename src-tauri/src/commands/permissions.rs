@@ -1,40 +1,62 @@
 use crate::models::SshBuddyError;
-use crate::services::{PermissionCheckResult, PermissionFixResult, PermissionService};
+use crate::services::{
+    PermissionCapabilities, PermissionCheckResult, PermissionFixResult, PermissionPolicy,
+    PermissionService,
+};
 
 /// Check key file permissions
 #[tauri::command]
 pub async fn check_key_permissions(
     key_path: String,
 ) -> Result<PermissionCheckResult, SshBuddyError> {
-    log::info!("[permissions] Checking permissions for: {}", key_path);
+    tracing::info!("[permissions] Checking permissions for: {}", key_path);
     let result = PermissionService::check_key_permissions(&key_path).await?;
-    log::info!("[permissions] Check result: {:?}", result);
+    tracing::info!("[permissions] Check result: {:?}", result);
     Ok(result)
 }
 
 /// Fix key file permissions
 #[tauri::command]
 pub async fn fix_key_permissions(key_path: String) -> Result<PermissionFixResult, SshBuddyError> {
-    log::info!("[permissions] Fixing permissions for: {}", key_path);
+    tracing::info!("[permissions] Fixing permissions for: {}", key_path);
     let result = PermissionService::fix_key_permissions(&key_path).await?;
-    log::info!("[permissions] Fix result: {:?}", result);
+    tracing::info!("[permissions] Fix result: {:?}", result);
     Ok(result)
 }
 
 /// Check SSH directory permissions
 #[tauri::command]
 pub async fn check_ssh_dir_permissions() -> Result<PermissionCheckResult, SshBuddyError> {
-    log::info!("[permissions] Checking SSH directory permissions");
+    tracing::info!("[permissions] Checking SSH directory permissions");
     let result = PermissionService::check_ssh_dir_permissions().await?;
-    log::info!("[permissions] Check result: {:?}", result);
+    tracing::info!("[permissions] Check result: {:?}", result);
     Ok(result)
 }
 
 /// Fix SSH directory permissions
 #[tauri::command]
 pub async fn fix_ssh_dir_permissions() -> Result<PermissionFixResult, SshBuddyError> {
-    log::info!("[permissions] Fixing SSH directory permissions");
+    tracing::info!("[permissions] Fixing SSH directory permissions");
     let result = PermissionService::fix_ssh_dir_permissions().await?;
-    log::info!("[permissions] Fix result: {:?}", result);
+    tracing::info!("[permissions] Fix result: {:?}", result);
     Ok(result)
 }
+
+/// Get the permission policy (profile and per-path overrides)
+#[tauri::command]
+pub async fn get_permission_policy() -> Result<PermissionPolicy, SshBuddyError> {
+    Ok(PermissionService::get_policy())
+}
+
+/// Save the permission policy; later checks and fixes follow it
+#[tauri::command]
+pub async fn set_permission_policy(policy: PermissionPolicy) -> Result<(), SshBuddyError> {
+    PermissionService::set_policy(&policy).await
+}
+
+/// How permissions are checked on this system (mode bits, icacls, the Win32 API, or
+/// not at all)
+#[tauri::command]
+pub async fn get_permission_capabilities() -> Result<PermissionCapabilities, SshBuddyError> {
+    Ok(PermissionService::capabilities().clone())
+}
@@ -0,0 +1,23 @@
+use crate::models::SshBuddyError;
+use crate::services::{PrivacyService, PrivacySettings, RetentionResult};
+
+/// Get the retention and privacy settings
+#[tauri::command]
+pub async fn get_privacy_settings() -> Result<PrivacySettings, SshBuddyError> {
+    Ok(PrivacyService::get_settings().await)
+}
+
+/// Save the retention and privacy settings; a shorter retention is applied at once
+#[tauri::command]
+pub async fn set_privacy_settings(
+    settings: PrivacySettings,
+) -> Result<RetentionResult, SshBuddyError> {
+    PrivacyService::set_settings(&settings).await
+}
+
+/// Overwrite and delete all data the app stored locally; returns the number of files
+#[tauri::command]
+pub async fn delete_all_local_data() -> Result<usize, SshBuddyError> {
+    tracing::info!("[privacy] Deleting all local data");
+    PrivacyService::delete_all_data().await
+}
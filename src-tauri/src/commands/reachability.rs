@@ -0,0 +1,52 @@
+use super::notifications::notify;
+use crate::models::SshBuddyError;
+use crate::services::{
+    Notification, NotificationCategory, ReachabilityHistory, ReachabilityService,
+    ReachabilitySettings,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying a host that stopped answering
+const HOST_DOWN_EVENT: &str = "host-down";
+
+/// Probe the monitored hosts on their interval (called once at app setup)
+pub fn start_reachability_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(ReachabilityService::run_monitor(move |down| {
+        if let Err(e) = app.emit(HOST_DOWN_EVENT, &down) {
+            tracing::error!("[reachability] Failed to emit host down: {}", e);
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let notification = Notification {
+                category: NotificationCategory::HostDown,
+                title: format!("{} is unreachable", down.host_alias),
+                body: format!("{}:{}: {}", down.hostname, down.port, down.error),
+                host_alias: Some(down.host_alias),
+            };
+            notify(&app, notification).await;
+        });
+    }));
+}
+
+#[tauri::command]
+pub async fn get_reachability_settings() -> Result<ReachabilitySettings, SshBuddyError> {
+    Ok(ReachabilityService::get_settings())
+}
+
+#[tauri::command]
+pub async fn set_reachability_settings(
+    settings: ReachabilitySettings,
+) -> Result<(), SshBuddyError> {
+    ReachabilityService::set_settings(&settings).await
+}
+
+/// Uptime and latency of a host for charts; `from`/`to` are Unix milliseconds
+#[tauri::command]
+pub async fn get_reachability_history(
+    host_alias: String,
+    from: i64,
+    to: i64,
+    step_ms: Option<i64>,
+) -> Result<ReachabilityHistory, SshBuddyError> {
+    ReachabilityService::history(&host_alias, from, to, step_ms).await
+}
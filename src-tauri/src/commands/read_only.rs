@@ -0,0 +1,15 @@
+use crate::models::SshBuddyError;
+use crate::services::{ReadOnlyMode, ReadOnlyStatus};
+
+/// Get read-only mode status
+#[tauri::command]
+pub async fn get_read_only_mode() -> Result<ReadOnlyStatus, SshBuddyError> {
+    Ok(ReadOnlyMode::status())
+}
+
+/// Turn read-only mode on or off
+#[tauri::command]
+pub async fn set_read_only_mode(enabled: bool) -> Result<ReadOnlyStatus, SshBuddyError> {
+    tracing::info!("[read_only] Setting read-only mode: {}", enabled);
+    ReadOnlyMode::set_enabled(enabled).await
+}
@@ -0,0 +1,142 @@
+use super::tray::refresh_tray;
+use crate::models::SshBuddyError;
+use crate::services::{
+    CaptureRecord, CaptureRequest, CaptureService, HostAttachment, HostNotesService, HostShortCode,
+    RegistryChange, RegistryService, ShortCodeService, SmartGroup, SmartGroupMembers,
+    SmartGroupService,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying the aliases changed by a registry batch
+const REGISTRY_CHANGED_EVENT: &str = "registry-changed";
+
+/// Apply tag, favorite, pin order, usage, rename and removal changes of many hosts in one
+/// registry write; a single change event is emitted for the whole batch
+#[tauri::command]
+pub async fn apply_registry_changes(
+    app: AppHandle,
+    changes: Vec<RegistryChange>,
+) -> Result<Vec<String>, SshBuddyError> {
+    let changed = RegistryService::apply(&changes).await?;
+    if !changed.is_empty() {
+        if let Err(e) = app.emit(REGISTRY_CHANGED_EVENT, &changed) {
+            tracing::error!("[registry] Failed to emit registry change: {}", e);
+        }
+        // Favorites and their order are listed in the tray
+        refresh_tray(&app).await;
+    }
+    Ok(changed)
+}
+
+/// Short codes of all hosts
+#[tauri::command]
+pub async fn list_host_short_codes() -> Result<Vec<HostShortCode>, SshBuddyError> {
+    ShortCodeService::list().await
+}
+
+/// Set or clear a host's short code; returns the stored (lowercased) code
+#[tauri::command]
+pub async fn set_host_short_code(
+    app: AppHandle,
+    alias: String,
+    code: Option<String>,
+) -> Result<Option<String>, SshBuddyError> {
+    let code = ShortCodeService::set(&alias, code.as_deref()).await?;
+    if let Err(e) = app.emit(REGISTRY_CHANGED_EVENT, [&alias]) {
+        tracing::error!("[registry] Failed to emit registry change: {}", e);
+    }
+    Ok(code)
+}
+
+/// Host alias for quick-connect input: the alias itself or a short code
+#[tauri::command]
+pub async fn resolve_host_short_code(input: String) -> Result<Option<String>, SshBuddyError> {
+    ShortCodeService::resolve(&input).await
+}
+
+/// Smart groups with their current members
+#[tauri::command]
+pub async fn list_smart_groups() -> Result<Vec<SmartGroupMembers>, SshBuddyError> {
+    SmartGroupService::list().await
+}
+
+/// Hosts a smart group query matches, to try it before saving
+#[tauri::command]
+pub async fn preview_smart_group(query: String) -> Result<Vec<String>, SshBuddyError> {
+    SmartGroupService::preview(&query).await
+}
+
+#[tauri::command]
+pub async fn save_smart_group(group: SmartGroup) -> Result<SmartGroup, SshBuddyError> {
+    SmartGroupService::save(group).await
+}
+
+#[tauri::command]
+pub async fn delete_smart_group(id: String) -> Result<(), SshBuddyError> {
+    SmartGroupService::delete(&id).await
+}
+
+/// Copy a local file (runbook, diagram) into a host's attachments
+#[tauri::command]
+pub async fn add_host_attachment(
+    app: AppHandle,
+    alias: String,
+    path: String,
+) -> Result<HostAttachment, SshBuddyError> {
+    let attachment = HostNotesService::add_attachment(&alias, &path).await?;
+    if let Err(e) = app.emit(REGISTRY_CHANGED_EVENT, [&alias]) {
+        tracing::error!("[registry] Failed to emit registry change: {}", e);
+    }
+    Ok(attachment)
+}
+
+#[tauri::command]
+pub async fn remove_host_attachment(
+    app: AppHandle,
+    alias: String,
+    id: String,
+) -> Result<(), SshBuddyError> {
+    HostNotesService::remove_attachment(&alias, &id).await?;
+    if let Err(e) = app.emit(REGISTRY_CHANGED_EVENT, [&alias]) {
+        tracing::error!("[registry] Failed to emit registry change: {}", e);
+    }
+    Ok(())
+}
+
+/// Local path of an attachment, for opening it
+#[tauri::command]
+pub async fn get_host_attachment_path(alias: String, id: String) -> Result<String, SshBuddyError> {
+    let path = HostNotesService::attachment_path(&alias, &id).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Keep the output of a remote command run in the host's notes or captures log
+#[tauri::command]
+pub async fn capture_command_output(
+    app: AppHandle,
+    request: CaptureRequest,
+) -> Result<CaptureRecord, SshBuddyError> {
+    let record = CaptureService::capture(&request).await?;
+    if let Err(e) = app.emit(REGISTRY_CHANGED_EVENT, [&request.host_alias]) {
+        tracing::error!("[registry] Failed to emit registry change: {}", e);
+    }
+    Ok(record)
+}
+
+/// Captures of a host, newest first
+#[tauri::command]
+pub async fn list_host_captures(
+    alias: String,
+    limit: Option<usize>,
+) -> Result<Vec<CaptureRecord>, SshBuddyError> {
+    CaptureService::list(&alias, limit).await
+}
+
+/// Delete one capture, or all of the host's captures without an id
+#[tauri::command]
+pub async fn delete_host_captures(
+    alias: String,
+    id: Option<String>,
+) -> Result<usize, SshBuddyError> {
+    CaptureService::delete(&alias, id.as_deref()).await
+}
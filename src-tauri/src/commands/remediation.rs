@@ -0,0 +1,39 @@
+use super::connection::EventPrompter;
+use crate::models::SshBuddyError;
+use crate::services::{
+    RemediationPlaybook, RemediationRequest, RemediationResult, RemediationService,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying the progress of each playbook step
+const REMEDIATION_EVENT: &str = "remediation-progress";
+
+/// Steps that fix a doctor finding (None when it can't be fixed from here)
+#[tauri::command]
+pub async fn get_remediation_playbook(
+    finding_id: String,
+    subject: Option<String>,
+) -> Result<Option<RemediationPlaybook>, SshBuddyError> {
+    RemediationService::playbook(&finding_id, subject.as_deref()).await
+}
+
+/// Run a finding's playbook; steps needing confirmation must be listed as confirmed
+/// Progress is streamed via the "remediation-progress" event
+#[tauri::command]
+pub async fn apply_remediation(
+    app: AppHandle,
+    request: RemediationRequest,
+) -> Result<RemediationResult, SshBuddyError> {
+    tracing::info!(
+        "[remediation] Applying the playbook for {} {:?}",
+        request.finding_id,
+        request.subject
+    );
+    let prompter = EventPrompter { app: app.clone() };
+    RemediationService::apply(&request, Some(&prompter), move |progress| {
+        if let Err(e) = app.emit(REMEDIATION_EVENT, &progress) {
+            tracing::error!("[remediation] Failed to emit progress: {}", e);
+        }
+    })
+    .await
+}
@@ -0,0 +1,70 @@
+use super::shell::reopen_pinned;
+use super::tunnel::start_with_events;
+use crate::models::SshBuddyError;
+use crate::services::{RestoreSettings, RestoreSummary, RestoredItem, SessionRestoreService};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying what was restored at launch
+const RESTORE_EVENT: &str = "session-restore";
+
+fn restored_item(id: String, name: String, result: Result<(), SshBuddyError>) -> RestoredItem {
+    RestoredItem {
+        id,
+        name,
+        restored: result.is_ok(),
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Re-establish the tunnels and pinned sessions active at the last shutdown
+/// (called once at app setup)
+pub fn start_session_restore(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let plan = match SessionRestoreService::take_plan().await {
+            Ok(plan) => plan,
+            Err(e) => {
+                tracing::error!("[restore] Failed to read the restore state: {}", e);
+                return;
+            }
+        };
+        if plan.tunnels.is_empty() && plan.sessions.is_empty() {
+            return;
+        }
+
+        let mut summary = RestoreSummary::default();
+        for tunnel in plan.tunnels {
+            let result = start_with_events(app.clone(), &tunnel.id).await;
+            summary
+                .tunnels
+                .push(restored_item(tunnel.id, tunnel.name, result));
+        }
+        for pinned in plan.sessions {
+            let item = match reopen_pinned(app.clone(), &pinned).await {
+                Ok(session_id) => restored_item(session_id, pinned.host_alias, Ok(())),
+                Err(e) => restored_item(pinned.session_id, pinned.host_alias, Err(e)),
+            };
+            summary.sessions.push(item);
+        }
+        let summary = SessionRestoreService::finish(summary);
+        if let Err(e) = app.emit(RESTORE_EVENT, &summary) {
+            tracing::error!("[restore] Failed to emit restore summary: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_restore_settings() -> Result<RestoreSettings, SshBuddyError> {
+    Ok(SessionRestoreService::get_settings())
+}
+
+#[tauri::command]
+pub async fn set_restore_settings(settings: RestoreSettings) -> Result<(), SshBuddyError> {
+    SessionRestoreService::set_settings(&settings).await
+}
+
+/// What was restored at this launch; None while the restore runs or when there was
+/// nothing to restore
+#[tauri::command]
+pub async fn get_restore_summary() -> Result<Option<RestoreSummary>, SshBuddyError> {
+    Ok(SessionRestoreService::last_summary())
+}
@@ -0,0 +1,44 @@
+use super::connection::EventPrompter;
+use crate::models::SshBuddyError;
+use crate::services::{OutputStream, ScriptRunRequest, ScriptRunResult, ScriptService};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying output of a running remote script
+const SCRIPT_OUTPUT_EVENT: &str = "script-output";
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScriptOutputEvent<'a> {
+    host_alias: &'a str,
+    stream: OutputStream,
+    data: &'a str,
+}
+
+/// Upload a local script to a remote temp path, run it and remove it
+/// Output is streamed via the "script-output" event; auth and sudo prompts are
+/// forwarded via the "ssh-auth-prompt" event
+#[tauri::command]
+pub async fn run_remote_script(
+    app: AppHandle,
+    request: ScriptRunRequest,
+) -> Result<ScriptRunResult, SshBuddyError> {
+    tracing::info!(
+        "[script] Running {} on {} (sudo={})",
+        request.script_path,
+        request.host_alias,
+        request.sudo
+    );
+    let prompter = EventPrompter { app: app.clone() };
+    ScriptService::run(&request, Some(&prompter), |stream, data| {
+        let event = ScriptOutputEvent {
+            host_alias: &request.host_alias,
+            stream,
+            data,
+        };
+        if let Err(e) = app.emit(SCRIPT_OUTPUT_EVENT, event) {
+            tracing::error!("[script] Failed to emit script output: {}", e);
+        }
+    })
+    .await
+}
@@ -0,0 +1,70 @@
+use crate::commands::palette::register_shortcut;
+use crate::models::SshBuddyError;
+use crate::services::{AppSettings, LogService, SettingsService, TransferManager};
+use crate::utils::{app_paths, AppPaths};
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri_plugin_fs::FsExt;
+
+/// Get all app settings
+#[tauri::command]
+pub async fn get_app_settings() -> Result<AppSettings, SshBuddyError> {
+    Ok(SettingsService::get())
+}
+
+/// Export the settings (without secrets) to move them to another machine
+/// `sign` adds a `.sig` the importing machine verifies
+#[tauri::command]
+pub async fn export_settings(path: String, sign: Option<bool>) -> Result<(), SshBuddyError> {
+    SettingsService::export(&PathBuf::from(path), sign.unwrap_or(false)).await
+}
+
+/// Import exported settings and apply them right away
+#[tauri::command]
+pub async fn import_settings(app: AppHandle, path: String) -> Result<AppSettings, SshBuddyError> {
+    let settings = SettingsService::import(&PathBuf::from(path)).await?;
+    if let Err(e) = register_shortcut(&app, &settings.palette) {
+        tracing::warn!("[settings] Imported palette shortcut not registered: {}", e);
+    }
+    LogService::apply_levels()?;
+    TransferManager::global().apply_global_limit(settings.transfers.global_rate_limit);
+    Ok(settings)
+}
+
+/// Get the data and SSH directories (and whether portable mode is on)
+#[tauri::command]
+pub async fn get_app_paths() -> Result<AppPaths, SshBuddyError> {
+    app_paths()
+}
+
+/// Use another SSH directory instead of ~/.ssh; null goes back to ~/.ssh
+#[tauri::command]
+pub async fn set_ssh_root(app: AppHandle, root: Option<String>) -> Result<AppPaths, SshBuddyError> {
+    let paths = SettingsService::change_ssh_root(root).await?;
+    allow_app_paths(&app);
+    Ok(paths)
+}
+
+/// Let the frontend's file access reach a custom SSH root, the portable data
+/// directory and the workspace's SSH config (the capability scopes only cover
+/// ~/.ssh and the app data dir); the frontend only reads there, writes go through
+/// commands
+pub fn allow_app_paths(app: &AppHandle) {
+    let Ok(paths) = app_paths() else {
+        return;
+    };
+    let scope = app.fs_scope();
+    for dir in [&paths.ssh_dir, &paths.data_dir] {
+        if let Err(e) = scope.allow_directory(dir, true) {
+            tracing::warn!("[settings] Failed to allow access to {}: {}", dir, e);
+        }
+    }
+    // A workspace's SSH config may live outside the SSH root
+    if let Err(e) = scope.allow_file(&paths.ssh_config_path) {
+        tracing::warn!(
+            "[settings] Failed to allow access to {}: {}",
+            paths.ssh_config_path,
+            e
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use super::connection::EventPrompter;
+use crate::models::SshBuddyError;
+use crate::services::{
+    ConsoleServerService, ConsoleSession, HostConsole, HostMultiplexer, HostShellAccess,
+    MultiplexerService, ObserveEvent, PinnedSession, RemoteSessions, SessionChoice,
+    SessionRestoreService, SessionShare, SessionShareService, ShellAccessService, ShellEvent,
+    ShellRoute, ShellSessionManager,
+};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying shell output, reconnect and close notifications
+const SHELL_EVENT: &str = "shell-event";
+
+/// Event carrying the output of a session shared by a colleague
+const OBSERVE_EVENT: &str = "session-observe-event";
+
+/// Open a shell (or run `command` on a terminal) with events going to the frontend
+/// Dropped connections are re-established under the same session id
+pub(crate) async fn open_with_events(
+    app: AppHandle,
+    host_alias: &str,
+    command: Option<String>,
+    cols: u32,
+    rows: u32,
+    console: Option<ConsoleSession>,
+) -> Result<String, SshBuddyError> {
+    let prompter = Arc::new(EventPrompter { app: app.clone() });
+    ShellSessionManager::global()
+        .open(
+            host_alias,
+            command,
+            cols,
+            rows,
+            Some(prompter),
+            true,
+            console,
+            move |event: ShellEvent| {
+                if let Err(e) = app.emit(SHELL_EVENT, &event) {
+                    tracing::error!("[shell] Failed to emit shell event: {}", e);
+                }
+                // A pinned session closed on purpose isn't restored at the next launch
+                if let ShellEvent::Closed { session_id, .. } = event {
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = SessionRestoreService::unpin_session(&session_id).await {
+                            tracing::error!("[shell] Failed to unpin session: {}", e);
+                        }
+                    });
+                }
+            },
+        )
+        .await
+}
+
+/// Open an interactive shell on a host, optionally attaching to or starting a tmux/screen
+/// session (without a choice the host's "always tmux" preference applies)
+/// On console servers the serial console is opened as-is, without tmux
+/// Restricted and forced-command hosts get a plain shell (or the command's output);
+/// SFTP-only hosts are refused, the UI opens them in the file browser
+/// Output is streamed via the "shell-event" event; returns the session id
+#[tauri::command]
+pub async fn open_shell_session(
+    app: AppHandle,
+    host_alias: String,
+    cols: u32,
+    rows: u32,
+    session: Option<SessionChoice>,
+) -> Result<String, SshBuddyError> {
+    tracing::info!("[shell] Opening shell on: {} ({:?})", host_alias, session);
+    let route = ShellAccessService::route(&host_alias).await?;
+    if route == ShellRoute::Sftp {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!(
+                "{} only allows SFTP; open it in the file browser instead",
+                host_alias
+            ),
+        });
+    }
+    let console = ConsoleServerService::session_for(&host_alias).await?;
+    let command = match (&console, route) {
+        (None, ShellRoute::Shell) => {
+            MultiplexerService::shell_command(&host_alias, session.as_ref()).await?
+        }
+        _ => None,
+    };
+    open_with_events(app, &host_alias, command, cols, rows, console).await
+}
+
+/// Reopen a pinned session from a previous launch (its tmux command re-attaches)
+/// and pin the new session in its place; returns the new session id
+pub(crate) async fn reopen_pinned(
+    app: AppHandle,
+    pinned: &PinnedSession,
+) -> Result<String, SshBuddyError> {
+    if ShellAccessService::route(&pinned.host_alias).await? == ShellRoute::Sftp {
+        return Err(SshBuddyError::InvalidOption {
+            message: format!("{} only allows SFTP", pinned.host_alias),
+        });
+    }
+    let console = ConsoleServerService::session_for(&pinned.host_alias).await?;
+    // The terminal view resizes the session when it attaches
+    let session_id = open_with_events(
+        app,
+        &pinned.host_alias,
+        pinned.command.clone(),
+        80,
+        24,
+        console,
+    )
+    .await?;
+    SessionRestoreService::pin_session(PinnedSession {
+        session_id: session_id.clone(),
+        ..pinned.clone()
+    })
+    .await?;
+    Ok(session_id)
+}
+
+/// Pin or unpin an open shell session; pinned sessions that are still open at shutdown
+/// are reopened at the next launch (`restore_on_launch` overrides the global setting)
+#[tauri::command]
+pub async fn pin_shell_session(
+    session_id: String,
+    pinned: bool,
+    restore_on_launch: Option<bool>,
+) -> Result<(), SshBuddyError> {
+    if !pinned {
+        return SessionRestoreService::unpin_session(&session_id).await;
+    }
+    let (host_alias, command) = ShellSessionManager::global().describe(&session_id).await?;
+    SessionRestoreService::pin_session(PinnedSession::new(
+        &session_id,
+        &host_alias,
+        command,
+        restore_on_launch,
+    ))
+    .await
+}
+
+/// Pinned sessions that are open
+#[tauri::command]
+pub async fn list_pinned_sessions() -> Result<Vec<PinnedSession>, SshBuddyError> {
+    SessionRestoreService::pinned_sessions().await
+}
+
+/// List tmux/screen sessions on a host (offered before opening a shell)
+#[tauri::command]
+pub async fn list_remote_sessions(
+    app: AppHandle,
+    host_alias: String,
+) -> Result<RemoteSessions, SshBuddyError> {
+    let prompter = EventPrompter { app };
+    MultiplexerService::list_sessions(&host_alias, Some(&prompter)).await
+}
+
+/// Shell access found by the last connection test (None until a test ran)
+#[tauri::command]
+pub async fn get_host_shell_access(
+    host_alias: String,
+) -> Result<Option<HostShellAccess>, SshBuddyError> {
+    ShellAccessService::get_host(&host_alias).await
+}
+
+/// Where opening a host should go: a terminal, a plain terminal or the file browser
+#[tauri::command]
+pub async fn get_shell_route(host_alias: String) -> Result<ShellRoute, SshBuddyError> {
+    ShellAccessService::route(&host_alias).await
+}
+
+/// tmux preference of a host
+#[tauri::command]
+pub async fn get_host_multiplexer(host_alias: String) -> Result<HostMultiplexer, SshBuddyError> {
+    MultiplexerService::get_host_settings(&host_alias).await
+}
+
+/// Set whether a host's shells are always wrapped in tmux
+#[tauri::command]
+pub async fn set_host_multiplexer(
+    host_alias: String,
+    settings: HostMultiplexer,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[shell] Setting multiplexer preference of: {}", host_alias);
+    MultiplexerService::set_host_settings(&host_alias, settings).await
+}
+
+/// Console server options of a host
+#[tauri::command]
+pub async fn get_host_console(host_alias: String) -> Result<HostConsole, SshBuddyError> {
+    ConsoleServerService::get_host_settings(&host_alias).await
+}
+
+/// Mark a host as a console server and set its line discipline, break and logging
+#[tauri::command]
+pub async fn set_host_console(
+    host_alias: String,
+    settings: HostConsole,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[shell] Setting console options of: {}", host_alias);
+    ConsoleServerService::set_host_settings(&host_alias, settings).await
+}
+
+/// Send the break sequence on a console server session
+#[tauri::command]
+pub async fn send_console_break(session_id: String) -> Result<(), SshBuddyError> {
+    ShellSessionManager::global().send_break(&session_id).await
+}
+
+/// File a console session's output is logged to (None when not logged)
+#[tauri::command]
+pub async fn get_console_log_path(session_id: String) -> Result<Option<String>, SshBuddyError> {
+    ShellSessionManager::global()
+        .console_log_path(&session_id)
+        .await
+}
+
+/// Send keyboard input to a shell session
+#[tauri::command]
+pub async fn write_shell_session(session_id: String, data: String) -> Result<(), SshBuddyError> {
+    ShellSessionManager::global()
+        .write(&session_id, &data)
+        .await
+}
+
+/// Resize a shell session's terminal
+#[tauri::command]
+pub async fn resize_shell_session(
+    session_id: String,
+    cols: u32,
+    rows: u32,
+) -> Result<(), SshBuddyError> {
+    ShellSessionManager::global()
+        .resize(&session_id, cols, rows)
+        .await
+}
+
+/// Output a shell session has produced so far (kept across reconnects)
+#[tauri::command]
+pub async fn get_shell_scrollback(session_id: String) -> Result<String, SshBuddyError> {
+    ShellSessionManager::global().scrollback(&session_id).await
+}
+
+/// Close a shell session
+#[tauri::command]
+pub async fn close_shell_session(session_id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[shell] Closing shell session: {}", session_id);
+    ShellSessionManager::global().close(&session_id).await
+}
+
+/// Mirror a shell session read-only to other app instances on the LAN for `minutes`
+/// Returns the link to hand to the colleague; it stops working when the share ends
+#[tauri::command]
+pub async fn start_session_share(
+    session_id: String,
+    minutes: u32,
+) -> Result<SessionShare, SshBuddyError> {
+    SessionShareService::global()
+        .start(&session_id, minutes)
+        .await
+}
+
+/// Stop sharing a session and disconnect its observers
+#[tauri::command]
+pub async fn stop_session_share(share_id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[shell] Stopping session share: {}", share_id);
+    SessionShareService::global().stop(&share_id).await
+}
+
+/// Sessions currently shared, with their observer counts
+#[tauri::command]
+pub async fn list_session_shares() -> Result<Vec<SessionShare>, SshBuddyError> {
+    Ok(SessionShareService::global().list().await)
+}
+
+/// Watch a session a colleague shared; output is streamed via the
+/// "session-observe-event" event, returns the viewer id
+#[tauri::command]
+pub async fn observe_shared_session(app: AppHandle, link: String) -> Result<String, SshBuddyError> {
+    SessionShareService::global()
+        .observe(&link, move |event: ObserveEvent| {
+            if let Err(e) = app.emit(OBSERVE_EVENT, &event) {
+                tracing::error!("[shell] Failed to emit observe event: {}", e);
+            }
+        })
+        .await
+}
+
+/// Stop watching a shared session
+#[tauri::command]
+pub async fn stop_observing_session(viewer_id: String) -> Result<(), SshBuddyError> {
+    SessionShareService::global()
+        .stop_observing(&viewer_id)
+        .await
+}
@@ -0,0 +1,22 @@
+use crate::models::SshBuddyError;
+use crate::services::{
+    HistoryHost, HistoryImportRequest, HistoryImportResult, ShellHistoryService,
+};
+
+/// Propose hosts the user connects to from a shell but has not added to the config
+/// (requires the scanShellHistory privacy setting)
+#[tauri::command]
+pub async fn scan_shell_history(
+    min_count: Option<usize>,
+) -> Result<Vec<HistoryHost>, SshBuddyError> {
+    ShellHistoryService::scan(min_count).await
+}
+
+/// Import proposed shell history hosts into the SSH config and host registry
+#[tauri::command]
+pub async fn import_history_hosts(
+    request: HistoryImportRequest,
+) -> Result<HistoryImportResult, SshBuddyError> {
+    tracing::info!("[shell_history] Importing {} host(s)", request.hosts.len());
+    ShellHistoryService::import_hosts(&request).await
+}
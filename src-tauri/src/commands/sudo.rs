@@ -0,0 +1,10 @@
+use crate::models::SshBuddyError;
+use crate::services::{SudoAccess, SudoService};
+
+/// Check whether sudo works on a host without a password (NOPASSWD)
+/// Saved sudo passwords live in the vault as "sudo:<host alias>" entries
+#[tauri::command]
+pub async fn check_sudo_access(host_alias: String) -> Result<SudoAccess, SshBuddyError> {
+    tracing::info!("[sudo] Checking sudo access on: {}", host_alias);
+    SudoService::check_access(&host_alias).await
+}
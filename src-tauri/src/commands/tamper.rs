@@ -0,0 +1,65 @@
+use super::notifications::notify;
+use crate::models::SshBuddyError;
+use crate::services::{
+    Notification, NotificationCategory, QuarantinedFile, SuspiciousFile, TamperDetectionService,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying files that became suspicious while the app was running
+const SUSPICIOUS_FILES_EVENT: &str = "ssh-dir-suspicious";
+
+/// Watch the SSH directory and alert about new suspicious files (called once at app setup)
+pub fn start_tamper_watch(app: AppHandle) {
+    tauri::async_runtime::spawn(TamperDetectionService::run_watch_loop(move |files| {
+        if let Err(e) = app.emit(SUSPICIOUS_FILES_EVENT, &files) {
+            tracing::error!("[tamper] Failed to emit suspicious files: {}", e);
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let body = files
+                .iter()
+                .map(|f| f.message.text.clone())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let notification = Notification {
+                category: NotificationCategory::SecurityAlert,
+                title: "Unexpected change in the SSH directory".to_string(),
+                body,
+                host_alias: None,
+            };
+            notify(&app, notification).await;
+        });
+    }));
+}
+
+/// Find executables, rc files, hidden files and outside config changes in the SSH directory
+#[tauri::command]
+pub async fn scan_ssh_directory() -> Result<Vec<SuspiciousFile>, SshBuddyError> {
+    TamperDetectionService::scan().await
+}
+
+/// Move a suspicious file out of the SSH directory into the review folder
+#[tauri::command]
+pub async fn quarantine_file(name: String) -> Result<QuarantinedFile, SshBuddyError> {
+    tracing::info!("[tamper] Quarantining {}", name);
+    TamperDetectionService::quarantine(&name).await
+}
+
+/// List quarantined files, most recent first
+#[tauri::command]
+pub async fn list_quarantined_files() -> Result<Vec<QuarantinedFile>, SshBuddyError> {
+    TamperDetectionService::list_quarantine().await
+}
+
+/// Put a quarantined file back into the SSH directory
+#[tauri::command]
+pub async fn restore_quarantined_file(id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[tamper] Restoring quarantined file {}", id);
+    TamperDetectionService::restore(&id).await
+}
+
+/// Delete a quarantined file for good
+#[tauri::command]
+pub async fn delete_quarantined_file(id: String) -> Result<(), SshBuddyError> {
+    TamperDetectionService::delete(&id).await
+}
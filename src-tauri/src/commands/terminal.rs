@@ -0,0 +1,46 @@
+use crate::models::SshBuddyError;
+use crate::services::{TerminalInfo, TerminalLauncher, TerminalSettings};
+
+/// Get the external terminal settings
+#[tauri::command]
+pub async fn get_terminal_settings() -> Result<TerminalSettings, SshBuddyError> {
+    TerminalLauncher::get_settings().await
+}
+
+/// Choose the external terminal and its default profile
+#[tauri::command]
+pub async fn set_terminal_settings(settings: TerminalSettings) -> Result<(), SshBuddyError> {
+    tracing::info!(
+        "[terminal] Setting external terminal: {:?}",
+        settings.terminal
+    );
+    TerminalLauncher::set_settings(settings).await
+}
+
+/// Terminals that can be chosen on this platform
+#[tauri::command]
+pub fn list_external_terminals() -> Vec<TerminalInfo> {
+    TerminalLauncher::available_terminals()
+}
+
+/// Terminal profile a host opens with
+#[tauri::command]
+pub async fn get_host_terminal_profile(host: String) -> Result<Option<String>, SshBuddyError> {
+    TerminalLauncher::get_host_profile(&host).await
+}
+
+/// Set the terminal profile of a host (None uses the default profile)
+#[tauri::command]
+pub async fn set_host_terminal_profile(
+    host: String,
+    profile: Option<String>,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[terminal] Setting terminal profile of: {}", host);
+    TerminalLauncher::set_host_profile(&host, profile).await
+}
+
+/// Open a host in the external terminal
+#[tauri::command]
+pub async fn open_in_external_terminal(host: String) -> Result<(), SshBuddyError> {
+    TerminalLauncher::launch(&host).await
+}
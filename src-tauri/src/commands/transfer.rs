@@ -0,0 +1,210 @@
+use super::connection::EventPrompter;
+use super::notifications::notify;
+use crate::models::SshBuddyError;
+use crate::services::{
+    Notification, NotificationCategory, RangeRead, RemoteFileChunk, ScheduledTransfer,
+    SftpStreamService, TransferDirection, TransferManager, TransferRequest, TransferSettings,
+    TransferState, TransferStatus,
+};
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{AppHandle, Emitter, UriSchemeResponder};
+
+/// Event carrying transfer progress and completion
+const TRANSFER_EVENT: &str = "transfer-event";
+
+/// URI scheme serving remote file bytes: `sftp://localhost/file?host=<alias>&path=<path>`
+/// (`http://sftp.localhost/file?...` on Windows), so previews and downloads get raw
+/// response bodies instead of base64 strings in JSON
+pub const SFTP_SCHEME: &str = "sftp";
+
+/// Answer a `sftp://` request (supports single `Range` requests)
+pub fn handle_sftp_request(request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    tauri::async_runtime::spawn(async move {
+        responder.respond(sftp_response(&request).await);
+    });
+}
+
+async fn sftp_response(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    if request.method() != Method::GET {
+        return text_response(StatusCode::METHOD_NOT_ALLOWED, "Only GET is supported");
+    }
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    let file = match SftpStreamService::parse_request(request.uri().query(), range) {
+        Ok(file) => file,
+        Err(e) => return text_response(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+    let chunk = match SftpStreamService::read(&file).await {
+        Ok(RangeRead::Chunk(chunk)) => chunk,
+        Ok(RangeRead::Unsatisfiable { total }) => {
+            return Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", total))
+                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                .body(Vec::new())
+                .unwrap_or_default();
+        }
+        Err(e) => {
+            tracing::warn!(
+                "[transfer] Failed to read {}:{}: {}",
+                file.host_alias,
+                file.path,
+                e
+            );
+            return text_response(StatusCode::BAD_GATEWAY, &e.to_string());
+        }
+    };
+    chunk_response(chunk, SftpStreamService::content_type(&file.path))
+}
+
+fn chunk_response(chunk: RemoteFileChunk, content_type: &str) -> Response<Vec<u8>> {
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, chunk.data.len())
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-store")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .header(header::ACCESS_CONTROL_EXPOSE_HEADERS, "Content-Range");
+    builder = if chunk.is_partial() {
+        let end = (chunk.start + chunk.data.len() as u64).max(1) - 1;
+        builder.status(StatusCode::PARTIAL_CONTENT).header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", chunk.start, end, chunk.total),
+        )
+    } else {
+        builder.status(StatusCode::OK)
+    };
+    builder.body(chunk.data).unwrap_or_default()
+}
+
+fn text_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+/// Forward transfer progress to the frontend and notify when a transfer ends
+fn transfer_events(app: AppHandle) -> impl Fn(TransferStatus) + Clone + Send + Sync + 'static {
+    move |status: TransferStatus| {
+        if let Err(e) = app.emit(TRANSFER_EVENT, &status) {
+            tracing::error!("[transfer] Failed to emit transfer event: {}", e);
+        }
+        if status.state == TransferState::Running {
+            return;
+        }
+        let file = match status.request.direction {
+            TransferDirection::Upload => &status.request.local_path,
+            TransferDirection::Download => &status.request.remote_path,
+        };
+        let title = match status.state {
+            TransferState::Completed => "Transfer finished",
+            TransferState::Cancelled => "Transfer cancelled",
+            _ => "Transfer failed",
+        };
+        let notification = Notification {
+            category: NotificationCategory::TransferFinished,
+            title: title.to_string(),
+            body: match &status.error {
+                Some(error) => format!("{}: {}", file, error),
+                None => file.clone(),
+            },
+            host_alias: Some(status.request.host_alias.clone()),
+        };
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            notify(&app, notification).await;
+        });
+    }
+}
+
+/// Start due scheduled transfers in the background (called once at app setup)
+pub fn start_transfer_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(TransferManager::global().run_scheduler(transfer_events(app)));
+}
+
+/// Start an SFTP transfer; progress is reported via the "transfer-event" event
+#[tauri::command]
+pub async fn start_transfer(
+    app: AppHandle,
+    request: TransferRequest,
+) -> Result<String, SshBuddyError> {
+    tracing::info!(
+        "[transfer] Starting {:?} on {}: {}",
+        request.direction,
+        request.host_alias,
+        request.remote_path
+    );
+    let prompter = EventPrompter { app: app.clone() };
+    TransferManager::global()
+        .start(request, Some(&prompter), transfer_events(app))
+        .await
+}
+
+/// Running transfers
+#[tauri::command]
+pub async fn list_transfers() -> Vec<TransferStatus> {
+    TransferManager::global().list().await
+}
+
+/// Cancel a running transfer
+#[tauri::command]
+pub async fn cancel_transfer(id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[transfer] Cancelling transfer: {}", id);
+    TransferManager::global().cancel(&id).await
+}
+
+/// Change the bandwidth cap of a running transfer (bytes per second, None for no cap)
+#[tauri::command]
+pub async fn set_transfer_rate_limit(
+    id: String,
+    rate_limit: Option<u64>,
+) -> Result<(), SshBuddyError> {
+    TransferManager::global()
+        .set_rate_limit(&id, rate_limit)
+        .await
+}
+
+/// Get the transfer settings
+#[tauri::command]
+pub async fn get_transfer_settings() -> Result<TransferSettings, SshBuddyError> {
+    TransferManager::get_settings().await
+}
+
+/// Save the transfer settings (global bandwidth cap)
+#[tauri::command]
+pub async fn set_transfer_settings(settings: TransferSettings) -> Result<(), SshBuddyError> {
+    TransferManager::global().set_settings(settings).await
+}
+
+/// Scheduled transfers, soonest first
+#[tauri::command]
+pub async fn list_scheduled_transfers() -> Result<Vec<ScheduledTransfer>, SshBuddyError> {
+    TransferManager::list_scheduled().await
+}
+
+/// Schedule a transfer (`start_at` is a Unix timestamp in milliseconds)
+#[tauri::command]
+pub async fn schedule_transfer(
+    request: TransferRequest,
+    start_at: i64,
+) -> Result<ScheduledTransfer, SshBuddyError> {
+    tracing::info!(
+        "[transfer] Scheduling {:?} on {} at {}",
+        request.direction,
+        request.host_alias,
+        start_at
+    );
+    TransferManager::schedule(request, start_at).await
+}
+
+/// Delete a scheduled transfer
+#[tauri::command]
+pub async fn delete_scheduled_transfer(id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[transfer] Deleting scheduled transfer: {}", id);
+    TransferManager::delete_scheduled(&id).await
+}
@@ -0,0 +1,34 @@
+use crate::models::SshBuddyError;
+use crate::services::{DeletedItem, TrashService, TrashSettings};
+
+/// Get the trash settings
+#[tauri::command]
+pub async fn get_trash_settings() -> Result<TrashSettings, SshBuddyError> {
+    Ok(TrashService::get_settings())
+}
+
+/// Turn the OS trash for deleted keys (and the recently deleted list) on or off
+#[tauri::command]
+pub async fn set_trash_settings(settings: TrashSettings) -> Result<(), SshBuddyError> {
+    tracing::info!("[trash] Updating trash settings: {:?}", settings);
+    TrashService::set_settings(&settings).await
+}
+
+/// Keys, hosts and known_hosts entries deleted recently, most recent first
+#[tauri::command]
+pub async fn list_recently_deleted() -> Result<Vec<DeletedItem>, SshBuddyError> {
+    TrashService::list().await
+}
+
+/// Put a recently deleted item back
+#[tauri::command]
+pub async fn restore_deleted_item(id: String) -> Result<DeletedItem, SshBuddyError> {
+    tracing::info!("[trash] Restoring deleted item {}", id);
+    TrashService::restore(&id).await
+}
+
+/// Remove an item from the recently deleted list
+#[tauri::command]
+pub async fn forget_deleted_item(id: String) -> Result<(), SshBuddyError> {
+    TrashService::forget(&id).await
+}
@@ -0,0 +1,158 @@
+use super::tunnel::start_with_events;
+use crate::models::SshBuddyError;
+use crate::services::{
+    TrayAction, TrayAgentState, TrayMenuModel, TrayMenuService, TunnelManager, WatcherService,
+};
+use tauri::menu::{CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager};
+
+const TRAY_ID: &str = "main";
+
+/// Event asking the frontend to open a session to a host picked in the tray
+const TRAY_CONNECT_EVENT: &str = "tray-quick-connect";
+
+/// Event asking the frontend for the agent password (true = lock, false = unlock)
+const TRAY_AGENT_LOCK_EVENT: &str = "tray-agent-lock";
+
+/// Create the tray icon and keep its menu in sync with the registry and config
+/// (called once at app setup)
+pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("SSH Buddy")
+        .menu(&build_menu(app, None)?)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        refresh_tray(&app).await;
+        let mut changes = WatcherService::global().subscribe();
+        // Lagging only means several changes arrived at once
+        while !matches!(
+            changes.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        ) {
+            refresh_tray(&app).await;
+        }
+    });
+    Ok(())
+}
+
+/// Rebuild the tray menu from the current state
+pub(crate) async fn refresh_tray(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let model = match TrayMenuService::build().await {
+        Ok(model) => model,
+        Err(e) => {
+            tracing::warn!("[tray] Failed to build tray menu: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = build_menu(app, Some(&model)).and_then(|menu| tray.set_menu(Some(menu))) {
+        tracing::error!("[tray] Failed to update tray menu: {}", e);
+    }
+}
+
+/// Menu for a model; only the window items while it's loading
+fn build_menu(app: &AppHandle, model: Option<&TrayMenuModel>) -> tauri::Result<Menu<tauri::Wry>> {
+    let mut menu = MenuBuilder::new(app);
+    if let Some(model) = model {
+        for (title, hosts) in [("Pinned", &model.pinned), ("Recent", &model.recent)] {
+            if hosts.is_empty() {
+                continue;
+            }
+            menu = menu.item(&heading(app, title)?);
+            for host in hosts {
+                menu = menu.text(TrayAction::Connect(host.alias.clone()).id(), &host.alias);
+            }
+            menu = menu.separator();
+        }
+
+        if !model.tunnels.is_empty() {
+            menu = menu.item(&heading(app, "Tunnels")?);
+            for tunnel in &model.tunnels {
+                let item = CheckMenuItemBuilder::with_id(
+                    TrayAction::ToggleTunnel(tunnel.id.clone()).id(),
+                    &tunnel.name,
+                )
+                .checked(tunnel.running)
+                .build(app)?;
+                menu = menu.item(&item);
+            }
+            menu = menu.separator();
+        }
+
+        menu = match model.agent {
+            TrayAgentState::NotRunning => menu.item(&heading(app, "SSH agent not running")?),
+            TrayAgentState::Unlocked => menu.text(TrayAction::LockAgent.id(), "Lock SSH Agent…"),
+            TrayAgentState::Locked => menu.text(TrayAction::UnlockAgent.id(), "Unlock SSH Agent…"),
+        };
+        menu = menu.separator();
+    }
+    menu.text(TrayAction::ShowWindow.id(), "Show SSH Buddy")
+        .text(TrayAction::Quit.id(), "Quit")
+        .build()
+}
+
+/// Disabled item used as a section title
+fn heading(app: &AppHandle, title: &str) -> tauri::Result<tauri::menu::MenuItem<tauri::Wry>> {
+    MenuItemBuilder::with_id(format!("heading:{}", title), title)
+        .enabled(false)
+        .build(app)
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    let Some(action) = TrayAction::parse(id) else {
+        return;
+    };
+    tracing::info!("[tray] Menu action: {:?}", action);
+    match action {
+        TrayAction::Connect(alias) => {
+            show_main_window(app);
+            if let Err(e) = app.emit(TRAY_CONNECT_EVENT, alias) {
+                tracing::error!("[tray] Failed to emit quick connect: {}", e);
+            }
+        }
+        TrayAction::ToggleTunnel(tunnel_id) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let manager = TunnelManager::global();
+                if manager.is_running(&tunnel_id).await {
+                    manager.stop(&tunnel_id).await;
+                } else if let Err(e) = start_with_events(app.clone(), &tunnel_id).await {
+                    tracing::error!("[tray] Failed to start tunnel {}: {}", tunnel_id, e);
+                }
+                refresh_tray(&app).await;
+            });
+        }
+        TrayAction::LockAgent | TrayAction::UnlockAgent => {
+            show_main_window(app);
+            let lock = action == TrayAction::LockAgent;
+            if let Err(e) = app.emit(TRAY_AGENT_LOCK_EVENT, lock) {
+                tracing::error!("[tray] Failed to emit agent lock request: {}", e);
+            }
+        }
+        TrayAction::ShowWindow => show_main_window(app),
+        TrayAction::Quit => app.exit(0),
+    }
+}
+
+/// Get the tray menu model (what the tray currently shows)
+#[tauri::command]
+pub async fn get_tray_menu() -> Result<TrayMenuModel, SshBuddyError> {
+    TrayMenuService::build().await
+}
@@ -0,0 +1,213 @@
+use super::connection::EventPrompter;
+use super::notifications::notify;
+use super::tray::refresh_tray;
+use crate::models::SshBuddyError;
+use crate::services::{
+    DatabaseClient, DatabaseHandoff, DatabaseHandoffService, ListenerDiscovery, Notification,
+    NotificationCategory, RemoteListenerService, ReverseTunnelDefinition, ReverseTunnelFiles,
+    ReverseTunnelInstallResult, ReverseTunnelService, ReverseTunnelState, ServiceFormat,
+    SessionRestoreService, TunnelDefinition, TunnelEvent, TunnelExportService, TunnelManager,
+    TunnelServiceExport, TunnelServiceStatus, TunnelStatus,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Event reporting tunnels starting, stopping and dropping
+const TUNNEL_EVENT: &str = "tunnel-event";
+
+/// Start a tunnel with its events going to the frontend, the tray and notifications
+pub(crate) async fn start_with_events(app: AppHandle, id: &str) -> Result<(), SshBuddyError> {
+    let prompter = EventPrompter { app: app.clone() };
+    TunnelManager::global()
+        .start(id, Some(&prompter), move |event: TunnelEvent| {
+            if let Err(e) = app.emit(TUNNEL_EVENT, &event) {
+                tracing::error!("[tunnel] Failed to emit tunnel event: {}", e);
+            }
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let recorded = match &event {
+                    TunnelEvent::Started { id, .. } => {
+                        SessionRestoreService::tunnel_started(id).await
+                    }
+                    TunnelEvent::Stopped { id, .. } | TunnelEvent::Dropped { id, .. } => {
+                        SessionRestoreService::tunnel_ended(id).await
+                    }
+                };
+                if let Err(e) = recorded {
+                    tracing::error!("[tunnel] Failed to record tunnel state: {}", e);
+                }
+                if let TunnelEvent::Dropped {
+                    name,
+                    host_alias,
+                    reason,
+                    ..
+                } = event
+                {
+                    let notification = Notification {
+                        category: NotificationCategory::TunnelDropped,
+                        title: format!("Tunnel {} dropped", name),
+                        body: reason,
+                        host_alias: Some(host_alias),
+                    };
+                    notify(&app, notification).await;
+                }
+                refresh_tray(&app).await;
+            });
+        })
+        .await
+}
+
+/// List tunnels with their runtime state
+#[tauri::command]
+pub async fn list_tunnels() -> Result<Vec<TunnelStatus>, SshBuddyError> {
+    TunnelManager::global().status().await
+}
+
+/// Add or update a tunnel definition
+#[tauri::command]
+pub async fn save_tunnel(definition: TunnelDefinition) -> Result<TunnelDefinition, SshBuddyError> {
+    tracing::info!("[tunnel] Saving tunnel: {}", definition.name);
+    TunnelManager::save_definition(definition).await
+}
+
+/// Stop and delete a tunnel
+#[tauri::command]
+pub async fn delete_tunnel(id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[tunnel] Deleting tunnel: {}", id);
+    TunnelManager::global().delete_definition(&id).await
+}
+
+/// Start a tunnel; lifecycle changes are reported via the "tunnel-event" event
+#[tauri::command]
+pub async fn start_tunnel(app: AppHandle, id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[tunnel] Starting tunnel: {}", id);
+    start_with_events(app, &id).await
+}
+
+/// Stop a tunnel; returns false when it wasn't running
+#[tauri::command]
+pub async fn stop_tunnel(id: String) -> Result<bool, SshBuddyError> {
+    tracing::info!("[tunnel] Stopping tunnel: {}", id);
+    Ok(TunnelManager::global().stop(&id).await)
+}
+
+/// List the services listening on a host and suggest tunnels to the known ones
+/// (databases, admin panels, ...); a suggestion is saved with `save_tunnel`
+#[tauri::command]
+pub async fn discover_remote_listeners(
+    app: AppHandle,
+    host_alias: String,
+) -> Result<ListenerDiscovery, SshBuddyError> {
+    tracing::info!("[tunnel] Discovering listeners on: {}", host_alias);
+    let prompter = EventPrompter { app };
+    RemoteListenerService::discover(&host_alias, Some(&prompter)).await
+}
+
+/// Connection strings for the database clients that can use a tunnel
+/// (psql, mysql, DBeaver, TablePlus); the password comes from the vault entry
+/// "db:<tunnel id>" when it's unlocked
+#[tauri::command]
+pub async fn get_database_handoffs(
+    tunnel_id: String,
+) -> Result<Vec<DatabaseHandoff>, SshBuddyError> {
+    DatabaseHandoffService::handoffs(&tunnel_id).await
+}
+
+/// Open a database client on a running tunnel
+#[tauri::command]
+pub async fn launch_database_client(
+    tunnel_id: String,
+    client: DatabaseClient,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[tunnel] Opening {:?} on tunnel: {}", client, tunnel_id);
+    DatabaseHandoffService::launch(&tunnel_id, client).await
+}
+
+/// Render a tunnel as a systemd user unit, launchd plist or scheduled task
+/// (this OS's format when none is given), with its install/uninstall commands
+#[tauri::command]
+pub async fn preview_tunnel_service(
+    id: String,
+    format: Option<ServiceFormat>,
+) -> Result<TunnelServiceExport, SshBuddyError> {
+    TunnelExportService::preview(&id, format).await
+}
+
+/// Install a tunnel as a service of this OS so it runs without the app
+#[tauri::command]
+pub async fn install_tunnel_service(id: String) -> Result<TunnelServiceStatus, SshBuddyError> {
+    tracing::info!("[tunnel] Installing tunnel service: {}", id);
+    TunnelExportService::install(&id).await
+}
+
+/// Stop and remove a tunnel's service
+#[tauri::command]
+pub async fn uninstall_tunnel_service(id: String) -> Result<TunnelServiceStatus, SshBuddyError> {
+    tracing::info!("[tunnel] Uninstalling tunnel service: {}", id);
+    TunnelExportService::uninstall(&id).await
+}
+
+/// Whether a tunnel's service is installed and running
+#[tauri::command]
+pub async fn get_tunnel_service_status(id: String) -> Result<TunnelServiceStatus, SshBuddyError> {
+    TunnelExportService::status(&id).await
+}
+
+/// List reverse tunnels (remote forwards from devices behind NAT to a relay)
+#[tauri::command]
+pub async fn list_reverse_tunnels() -> Result<Vec<ReverseTunnelDefinition>, SshBuddyError> {
+    ReverseTunnelService::list().await
+}
+
+/// Add or update a reverse tunnel definition
+#[tauri::command]
+pub async fn save_reverse_tunnel(
+    definition: ReverseTunnelDefinition,
+) -> Result<ReverseTunnelDefinition, SshBuddyError> {
+    tracing::info!("[tunnel] Saving reverse tunnel: {}", definition.name);
+    ReverseTunnelService::save(definition).await
+}
+
+/// Delete a reverse tunnel definition (uninstall it first to stop it on the device)
+#[tauri::command]
+pub async fn delete_reverse_tunnel(id: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[tunnel] Deleting reverse tunnel: {}", id);
+    ReverseTunnelService::delete(&id).await
+}
+
+/// Device ssh config and systemd unit a reverse tunnel installs, without connecting
+#[tauri::command]
+pub async fn preview_reverse_tunnel(id: String) -> Result<ReverseTunnelFiles, SshBuddyError> {
+    ReverseTunnelService::preview(&id).await
+}
+
+/// Install a reverse tunnel on its device and authorize the device on the relay
+#[tauri::command]
+pub async fn install_reverse_tunnel(
+    app: AppHandle,
+    id: String,
+) -> Result<ReverseTunnelInstallResult, SshBuddyError> {
+    tracing::info!("[tunnel] Installing reverse tunnel: {}", id);
+    let prompter = EventPrompter { app };
+    ReverseTunnelService::install(&id, Some(&prompter)).await
+}
+
+/// Remove a reverse tunnel from its device and the device key from the relay
+#[tauri::command]
+pub async fn uninstall_reverse_tunnel(
+    app: AppHandle,
+    id: String,
+) -> Result<ReverseTunnelDefinition, SshBuddyError> {
+    tracing::info!("[tunnel] Uninstalling reverse tunnel: {}", id);
+    let prompter = EventPrompter { app };
+    ReverseTunnelService::uninstall(&id, Some(&prompter)).await
+}
+
+/// Whether a reverse tunnel's unit is running on its device
+#[tauri::command]
+pub async fn get_reverse_tunnel_state(
+    app: AppHandle,
+    id: String,
+) -> Result<ReverseTunnelState, SshBuddyError> {
+    let prompter = EventPrompter { app };
+    ReverseTunnelService::state(&id, Some(&prompter)).await
+}
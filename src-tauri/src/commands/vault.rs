@@ -0,0 +1,94 @@
+use crate::models::SshBuddyError;
+use crate::services::{LockReason, SecuritySettings, VaultService, VaultStatus};
+use tauri::{AppHandle, Emitter};
+
+/// Event sent when the vault locks itself (idle timeout or system suspend)
+const VAULT_LOCKED_EVENT: &str = "vault-locked";
+
+/// Start the vault auto-lock watcher (called once at app setup)
+pub fn start_vault_auto_lock(app: AppHandle) {
+    tauri::async_runtime::spawn(VaultService::global().run_auto_lock(move |reason| {
+        if let Err(e) = app.emit(VAULT_LOCKED_EVENT, reason) {
+            tracing::error!("[vault] Failed to emit vault lock: {}", e);
+        }
+    }));
+}
+
+/// Get vault status
+#[tauri::command]
+pub async fn get_vault_status() -> Result<VaultStatus, SshBuddyError> {
+    VaultService::global().status().await
+}
+
+/// Create the vault protected by a master password
+/// Passwords are never logged
+#[tauri::command]
+pub async fn create_vault(master_password: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[vault] Creating vault");
+    VaultService::global().create(&master_password).await
+}
+
+/// Unlock the vault
+#[tauri::command]
+pub async fn unlock_vault(master_password: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[vault] Unlocking vault");
+    VaultService::global().unlock(&master_password).await
+}
+
+/// Lock the vault
+#[tauri::command]
+pub async fn lock_vault() -> Result<(), SshBuddyError> {
+    VaultService::global().lock(LockReason::Manual).await;
+    Ok(())
+}
+
+/// Change the master password (all entries are re-encrypted)
+#[tauri::command]
+pub async fn change_master_password(
+    current_password: String,
+    new_password: String,
+) -> Result<(), SshBuddyError> {
+    tracing::info!("[vault] Changing master password");
+    VaultService::global()
+        .change_master_password(&current_password, &new_password)
+        .await
+}
+
+/// List vault entry names
+#[tauri::command]
+pub async fn list_vault_entries() -> Result<Vec<String>, SshBuddyError> {
+    VaultService::global().list_entries().await
+}
+
+/// Read a vault entry
+#[tauri::command]
+pub async fn get_vault_entry(name: String) -> Result<String, SshBuddyError> {
+    VaultService::global().get_entry(&name).await
+}
+
+/// Create or replace a vault entry
+#[tauri::command]
+pub async fn set_vault_entry(name: String, value: String) -> Result<(), SshBuddyError> {
+    tracing::info!("[vault] Saving entry: {}", name);
+    VaultService::global().set_entry(&name, &value).await
+}
+
+/// Delete a vault entry
+#[tauri::command]
+pub async fn delete_vault_entry(name: String) -> Result<bool, SshBuddyError> {
+    tracing::info!("[vault] Deleting entry: {}", name);
+    VaultService::global().delete_entry(&name).await
+}
+
+/// Get security settings (key derivation and auto-lock)
+#[tauri::command]
+pub async fn get_security_settings() -> Result<SecuritySettings, SshBuddyError> {
+    VaultService::load_settings().await
+}
+
+/// Save security settings
+#[tauri::command]
+pub async fn set_security_settings(settings: SecuritySettings) -> Result<(), SshBuddyError> {
+    tracing::info!("[vault] Saving security settings: {:?}", settings);
+    VaultService::save_settings(&settings).await
+}
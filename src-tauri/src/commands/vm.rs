@@ -0,0 +1,34 @@
+use crate::models::SshBuddyError;
+use crate::services::{VmDiscoveryReport, VmDiscoveryService, VmImportResult};
+use tauri::{AppHandle, Emitter};
+
+/// Event emitted with the aliases of hosts removed because their VM disappeared
+const VMS_EXPIRED_EVENT: &str = "vms-expired";
+
+/// Start the VM host expiry watcher (called once at app setup)
+pub fn start_vm_expiry(app: AppHandle) {
+    tauri::async_runtime::spawn(VmDiscoveryService::run_expiry_loop(move |aliases| {
+        if let Err(e) = app.emit(VMS_EXPIRED_EVENT, aliases) {
+            tracing::error!("[vm] Failed to emit expired VMs: {}", e);
+        }
+    }));
+}
+
+/// Find running Vagrant/libvirt/VirtualBox/UTM machines with a known address
+#[tauri::command]
+pub async fn discover_local_vms() -> Result<VmDiscoveryReport, SshBuddyError> {
+    Ok(VmDiscoveryService::discover().await)
+}
+
+/// Import discovered VMs (by source) as hosts that expire with the VM
+#[tauri::command]
+pub async fn import_local_vms(sources: Vec<String>) -> Result<VmImportResult, SshBuddyError> {
+    tracing::info!("[vm] Importing {} VM(s)", sources.len());
+    VmDiscoveryService::import_vms(&sources).await
+}
+
+/// Remove imported VM hosts whose VM is gone
+#[tauri::command]
+pub async fn expire_local_vms() -> Result<Vec<String>, SshBuddyError> {
+    VmDiscoveryService::expire_vanished().await
+}
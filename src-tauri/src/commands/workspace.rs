@@ -0,0 +1,54 @@
+use super::settings::allow_app_paths;
+use super::tray::refresh_tray;
+use crate::models::SshBuddyError;
+use crate::services::{Workspace, WorkspaceService, WorkspaceSettings};
+use crate::utils::AppPaths;
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying the paths of the newly active workspace
+const WORKSPACE_CHANGED_EVENT: &str = "workspace-changed";
+
+/// List the workspaces and the active one
+#[tauri::command]
+pub async fn list_workspaces() -> Result<WorkspaceSettings, SshBuddyError> {
+    Ok(WorkspaceService::list())
+}
+
+/// Add a workspace with its own registry, vault and SSH config
+#[tauri::command]
+pub async fn create_workspace(name: String) -> Result<Workspace, SshBuddyError> {
+    WorkspaceService::create(&name).await
+}
+
+/// Change a workspace's name, default identity, SSH config file or keys
+#[tauri::command]
+pub async fn update_workspace(workspace: Workspace) -> Result<Workspace, SshBuddyError> {
+    WorkspaceService::update(workspace).await
+}
+
+/// Remove an inactive workspace, optionally with its data
+#[tauri::command]
+pub async fn delete_workspace(id: String, delete_data: bool) -> Result<(), SshBuddyError> {
+    WorkspaceService::delete(&id, delete_data).await
+}
+
+/// Switch workspaces; the frontend reloads its hosts and keys on the event
+#[tauri::command]
+pub async fn switch_workspace(app: AppHandle, id: String) -> Result<AppPaths, SshBuddyError> {
+    let paths = WorkspaceService::switch(&id).await?;
+    allow_app_paths(&app);
+    refresh_tray(&app).await;
+    if let Err(e) = app.emit(WORKSPACE_CHANGED_EVENT, &paths) {
+        tracing::error!("[workspace] Failed to emit workspace change: {}", e);
+    }
+    Ok(paths)
+}
+
+/// Give a key to a workspace; null shares it with all workspaces
+#[tauri::command]
+pub async fn assign_key_to_workspace(
+    key: String,
+    workspace: Option<String>,
+) -> Result<(), SshBuddyError> {
+    WorkspaceService::assign_key(&key, workspace.as_deref()).await
+}
@@ -1,55 +1,504 @@
 mod commands;
-mod models;
-mod services;
-mod utils;
+
+// The service layer lives in ssh-buddy-core; commands reach it as `crate::services` etc.
+use ssh_buddy_core::{models, services, utils};
 
 use commands::{
-    add_key_to_agent, add_known_host, check_key_permissions, check_ssh_dir_permissions,
-    delete_ssh_key, fix_key_permissions, fix_ssh_dir_permissions, generate_ssh_key,
-    get_key_details, is_agent_running, is_key_in_agent, list_agent_keys, list_ssh_keys,
-    read_public_key, remove_key_from_agent, remove_known_host, test_ssh_connection,
+    accept_integrity_changes, add_cert_authority, add_host_attachment, add_key_to_agent,
+    add_known_host, add_ssh_host, allow_app_paths, apply_algorithm_overrides,
+    apply_config_suggestion, apply_registry_changes, apply_remediation, assign_key_to_workspace,
+    audit_fleet_authorized_keys, bulk_update_hosts, cancel_transfer, capture_command_output,
+    change_master_password, check_algorithm_compat, check_host_keys_revoked, check_host_network,
+    check_kerberos_ticket, check_key_permissions, check_local_keys_revoked, check_pq_readiness,
+    check_ssh_dir_permissions, check_sudo_access, clear_notification_history, close_shell_session,
+    collect_host_facts, compare_doctor_runs, create_host_from_template, create_legacy_host,
+    create_vault, create_workspace, delete_all_local_data, delete_host_captures,
+    delete_host_template, delete_quarantined_file, delete_reverse_tunnel, delete_scheduled_job,
+    delete_scheduled_transfer, delete_smart_group, delete_snippet, delete_ssh_host, delete_ssh_key,
+    delete_tunnel, delete_vault_entry, delete_workspace, deploy_public_key, diff_file_revisions,
+    diff_host_login_banner, disable_git_versioning, discover_known_hosts, discover_local_vms,
+    discover_remote_listeners, enable_git_versioning, expire_local_vms, export_bundle,
+    export_fleet_summary, export_log, export_settings, fix_key_permissions,
+    fix_ssh_dir_permissions, forget_deleted_item, generate_krl, generate_ssh_key,
+    get_activity_stats, get_app_paths, get_app_proxy, get_app_settings, get_client_pq_support,
+    get_console_log_path, get_database_handoffs, get_export_signing_key, get_fingerprint_index,
+    get_git_ssh_command, get_git_versioning_log, get_git_versioning_status,
+    get_health_check_status, get_hook_runs, get_host_attachment_path, get_host_console,
+    get_host_gssapi_options, get_host_hooks, get_host_login_banner, get_host_multiplexer,
+    get_host_proxy, get_host_shell_access, get_host_terminal_profile, get_host_trust_coverage,
+    get_isolation_settings, get_key_details, get_key_exposure_report, get_log_directory,
+    get_log_settings, get_message_catalog, get_network_requirement, get_notification_history,
+    get_notification_preferences, get_onboarding, get_palette_shortcut,
+    get_permission_capabilities, get_permission_policy, get_privacy_settings,
+    get_reachability_history, get_reachability_settings, get_read_only_mode,
+    get_remediation_playbook, get_restore_settings, get_restore_summary, get_reverse_tunnel_state,
+    get_revoked_host_keys, get_security_settings, get_session_helper_status, get_shell_route,
+    get_shell_scrollback, get_siem_settings, get_ssh_engine_capabilities, get_ssh_engine_settings,
+    get_terminal_settings, get_transfer_settings, get_trash_settings, get_tray_menu,
+    get_tunnel_service_status, get_vault_entry, get_vault_status, handle_sftp_request,
+    import_history_hosts, import_known_hosts, import_kube_nodes, import_local_vms,
+    import_mdns_hosts, import_settings, inspect_krl, inspect_ssh_installations,
+    install_reverse_tunnel, install_tunnel_service, is_agent_running, is_key_in_agent,
+    launch_database_client, launch_host_network, list_agent_keys, list_catalogs,
+    list_cert_authorities, list_config_hosts, list_docker_containers, list_docker_contexts,
+    list_doctor_runs, list_external_terminals, list_file_revisions, list_host_captures,
+    list_host_short_codes, list_host_templates, list_job_runs, list_key_metadata, list_known_hosts,
+    list_kube_contexts, list_kube_nodes, list_legacy_exceptions, list_legacy_profiles,
+    list_pinned_sessions, list_quarantined_files, list_recently_deleted, list_remote_sessions,
+    list_reverse_tunnels, list_scheduled_jobs, list_scheduled_transfers, list_session_shares,
+    list_smart_groups, list_snippets, list_ssh_keys, list_transfers, list_trusted_export_signers,
+    list_tunnels, list_vault_entries, list_workspaces, lock_agent, lock_vault,
+    lookup_key_fingerprint, observe_shared_session, open_bundle, open_container_shell,
+    open_in_external_terminal, open_shell_session, palette_shortcut_plugin, pin_shell_session,
+    preview_authorized_keys_line, preview_git_ssh_command, preview_reverse_tunnel,
+    preview_smart_group, preview_tunnel_service, probe_docker, quarantine_file, query_hosts,
+    query_logs, read_public_key, record_snippet_use, refresh_catalog, refresh_fingerprint_index,
+    regenerate_public_key, remove_cert_authority, remove_host_attachment, remove_key_from_agent,
+    remove_known_host, remove_known_host_line, remove_legacy_exception,
+    remove_trusted_export_signer, render_key_qr_code, renew_legacy_exception, resize_shell_session,
+    resolve_deep_link, resolve_host_short_code, resolve_ssh_engine, respond_auth_prompt,
+    restore_deleted_item, restore_quarantined_file, revert_to_git_commit, rotate_host_keys,
+    run_doctor, run_fleet_command, run_health_check, run_host_hook, run_remote_script,
+    run_scheduled_job, save_host_template, save_reverse_tunnel, save_scheduled_job,
+    save_smart_group, save_snippet, save_tunnel, scan_export_secrets, scan_host_authorized_keys,
+    scan_keypairs, scan_mdns_hosts, scan_public_key_qr, scan_shell_history, scan_ssh_directory,
+    scan_ssh_ports, schedule_transfer, search_palette, send_console_break, send_notification,
+    set_app_proxy, set_cert_authority_patterns, set_git_ssh_command, set_health_check_settings,
+    set_host_console, set_host_gssapi_options, set_host_hooks, set_host_multiplexer,
+    set_host_proxy, set_host_short_code, set_host_terminal_profile, set_isolation_settings,
+    set_key_comment, set_key_metadata, set_log_settings, set_network_requirement,
+    set_notification_preferences, set_onboarding_finished, set_onboarding_step,
+    set_palette_shortcut, set_permission_policy, set_privacy_settings, set_reachability_settings,
+    set_read_only_mode, set_restore_settings, set_revoked_host_keys, set_security_settings,
+    set_siem_settings, set_ssh_engine_settings, set_ssh_root, set_terminal_settings,
+    set_transfer_rate_limit, set_transfer_settings, set_trash_settings, set_vault_entry,
+    setup_tray, show_git_versioning_commit, start_catalog_refresh, start_deep_links,
+    start_health_checks, start_integrity_watch, start_job_scheduler, start_legacy_reminders,
+    start_palette_shortcut, start_reachability_monitor, start_session_restore, start_session_share,
+    start_tamper_watch, start_transfer, start_transfer_scheduler, start_tunnel,
+    start_vault_auto_lock, start_vm_expiry, stop_observing_session, stop_session_share,
+    stop_tunnel, subscribe_catalog, sweep_subnet, switch_workspace, tail_logs, test_siem_forwarder,
+    test_ssh_connection, trust_export_signer, uninstall_reverse_tunnel, uninstall_tunnel_service,
+    unlock_agent, unlock_vault, unsubscribe_catalog, update_ssh_host, update_workspace,
+    verify_export_signature, verify_ssh_integrity, write_shell_session, SFTP_SCHEME,
 };
+use tauri::Manager;
+
+/// Run as the SSH helper process when started with `--ssh-helper`; returns false
+/// for a normal launch
+pub fn run_ssh_helper() -> bool {
+    if !std::env::args().any(|arg| arg == services::HELPER_ARG) {
+        return false;
+    }
+    services::SessionHelperService::serve();
+    true
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    services::LogService::init();
     tauri::Builder::default()
+        // Must come first so a second launch (e.g. from a link) focuses this instance
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(palette_shortcut_plugin())
+        .register_asynchronous_uri_scheme_protocol(SFTP_SCHEME, |_ctx, request, responder| {
+            handle_sftp_request(request, responder)
+        })
         .invoke_handler(tauri::generate_handler![
             // Key management
             list_ssh_keys,
             read_public_key,
+            render_key_qr_code,
+            scan_public_key_qr,
             get_key_details,
             generate_ssh_key,
             delete_ssh_key,
+            set_key_comment,
+            list_key_metadata,
+            set_key_metadata,
+            scan_keypairs,
+            regenerate_public_key,
+            preview_authorized_keys_line,
+            deploy_public_key,
+            scan_host_authorized_keys,
+            get_key_exposure_report,
+            get_fingerprint_index,
+            lookup_key_fingerprint,
+            refresh_fingerprint_index,
             // SSH Agent
             is_agent_running,
             list_agent_keys,
             is_key_in_agent,
             add_key_to_agent,
             remove_key_from_agent,
+            lock_agent,
+            unlock_agent,
             // SSH connection test
             test_ssh_connection,
+            respond_auth_prompt,
+            apply_config_suggestion,
+            check_algorithm_compat,
+            apply_algorithm_overrides,
+            get_client_pq_support,
+            check_pq_readiness,
+            // SSH engine
+            get_isolation_settings,
+            set_isolation_settings,
+            get_session_helper_status,
+            get_ssh_engine_settings,
+            set_ssh_engine_settings,
+            get_ssh_engine_capabilities,
+            resolve_ssh_engine,
+            // Network scans
+            scan_ssh_ports,
+            sweep_subnet,
+            // Connection hooks
+            get_host_hooks,
+            set_host_hooks,
+            run_host_hook,
+            get_hook_runs,
+            // Network requirements
+            get_network_requirement,
+            set_network_requirement,
+            check_host_network,
+            launch_host_network,
+            // Host facts
+            collect_host_facts,
+            get_host_login_banner,
+            diff_host_login_banner,
+            // SSH config
+            list_config_hosts,
+            query_hosts,
+            add_ssh_host,
+            update_ssh_host,
+            delete_ssh_host,
+            get_host_gssapi_options,
+            set_host_gssapi_options,
+            check_kerberos_ticket,
+            bulk_update_hosts,
+            // Proxy
+            get_app_proxy,
+            set_app_proxy,
+            get_host_proxy,
+            set_host_proxy,
+            // Host templates
+            list_host_templates,
+            save_host_template,
+            delete_host_template,
+            create_host_from_template,
+            // Revision history
+            list_file_revisions,
+            diff_file_revisions,
+            get_git_versioning_status,
+            enable_git_versioning,
+            disable_git_versioning,
+            get_git_versioning_log,
+            show_git_versioning_commit,
+            revert_to_git_commit,
+            // Git SSH command
+            get_git_ssh_command,
+            preview_git_ssh_command,
+            set_git_ssh_command,
             // Known Hosts
             add_known_host,
             remove_known_host,
+            remove_known_host_line,
+            list_known_hosts,
+            discover_known_hosts,
+            import_known_hosts,
+            rotate_host_keys,
+            // Certificate authorities
+            list_cert_authorities,
+            add_cert_authority,
+            set_cert_authority_patterns,
+            remove_cert_authority,
+            get_host_trust_coverage,
+            // Key revocation lists
+            inspect_krl,
+            check_local_keys_revoked,
+            check_host_keys_revoked,
+            generate_krl,
+            get_revoked_host_keys,
+            set_revoked_host_keys,
+            // Activity and audit log
+            get_activity_stats,
+            export_log,
+            get_siem_settings,
+            set_siem_settings,
+            test_siem_forwarder,
+            // Privacy
+            get_privacy_settings,
+            set_privacy_settings,
+            delete_all_local_data,
+            // Messages
+            get_message_catalog,
+            // Logs
+            get_log_settings,
+            set_log_settings,
+            query_logs,
+            tail_logs,
+            get_log_directory,
+            // Settings
+            get_app_settings,
+            export_settings,
+            import_settings,
+            get_app_paths,
+            set_ssh_root,
+            // Workspaces
+            list_workspaces,
+            create_workspace,
+            update_workspace,
+            delete_workspace,
+            switch_workspace,
+            assign_key_to_workspace,
+            // Team catalogs
+            list_catalogs,
+            subscribe_catalog,
+            unsubscribe_catalog,
+            refresh_catalog,
+            // Onboarding
+            get_onboarding,
+            set_onboarding_step,
+            set_onboarding_finished,
             // Permission management
             check_key_permissions,
             fix_key_permissions,
             check_ssh_dir_permissions,
             fix_ssh_dir_permissions,
+            get_permission_policy,
+            set_permission_policy,
+            get_permission_capabilities,
+            // Export
+            scan_export_secrets,
+            export_bundle,
+            open_bundle,
+            verify_export_signature,
+            get_export_signing_key,
+            list_trusted_export_signers,
+            trust_export_signer,
+            remove_trusted_export_signer,
+            // Vault
+            get_vault_status,
+            create_vault,
+            unlock_vault,
+            lock_vault,
+            change_master_password,
+            list_vault_entries,
+            get_vault_entry,
+            set_vault_entry,
+            delete_vault_entry,
+            get_security_settings,
+            set_security_settings,
+            // Read-only mode
+            get_read_only_mode,
+            set_read_only_mode,
+            // Fleet
+            run_fleet_command,
+            export_fleet_summary,
+            audit_fleet_authorized_keys,
+            // Script
+            run_remote_script,
+            // Sudo
+            check_sudo_access,
+            // Shell sessions
+            open_shell_session,
+            write_shell_session,
+            resize_shell_session,
+            get_shell_scrollback,
+            close_shell_session,
+            get_host_shell_access,
+            get_shell_route,
+            // Session sharing
+            start_session_share,
+            stop_session_share,
+            list_session_shares,
+            observe_shared_session,
+            stop_observing_session,
+            // Remote multiplexers
+            list_remote_sessions,
+            get_host_multiplexer,
+            set_host_multiplexer,
+            // Console servers
+            get_host_console,
+            set_host_console,
+            send_console_break,
+            get_console_log_path,
+            // Docker
+            list_docker_contexts,
+            probe_docker,
+            list_docker_containers,
+            open_container_shell,
+            // Kubernetes
+            list_kube_contexts,
+            list_kube_nodes,
+            import_kube_nodes,
+            // Local VMs
+            discover_local_vms,
+            import_local_vms,
+            expire_local_vms,
+            // mDNS discovery
+            scan_mdns_hosts,
+            import_mdns_hosts,
+            // Shell history
+            scan_shell_history,
+            import_history_hosts,
+            // Legacy devices
+            list_legacy_profiles,
+            create_legacy_host,
+            list_legacy_exceptions,
+            renew_legacy_exception,
+            remove_legacy_exception,
+            // Notifications
+            send_notification,
+            get_notification_preferences,
+            set_notification_preferences,
+            get_notification_history,
+            clear_notification_history,
+            // Tunnels
+            list_tunnels,
+            save_tunnel,
+            delete_tunnel,
+            start_tunnel,
+            stop_tunnel,
+            discover_remote_listeners,
+            // Database handoff
+            get_database_handoffs,
+            launch_database_client,
+            // Tunnel services
+            preview_tunnel_service,
+            install_tunnel_service,
+            uninstall_tunnel_service,
+            get_tunnel_service_status,
+            // Reverse tunnels
+            list_reverse_tunnels,
+            save_reverse_tunnel,
+            delete_reverse_tunnel,
+            preview_reverse_tunnel,
+            install_reverse_tunnel,
+            uninstall_reverse_tunnel,
+            get_reverse_tunnel_state,
+            // Tray
+            get_tray_menu,
+            // Quick-connect palette
+            get_palette_shortcut,
+            set_palette_shortcut,
+            search_palette,
+            // Snippets
+            list_snippets,
+            save_snippet,
+            delete_snippet,
+            record_snippet_use,
+            // Scheduled jobs
+            list_scheduled_jobs,
+            save_scheduled_job,
+            delete_scheduled_job,
+            run_scheduled_job,
+            list_job_runs,
+            // Deep links
+            resolve_deep_link,
+            // External terminal
+            get_terminal_settings,
+            set_terminal_settings,
+            list_external_terminals,
+            get_host_terminal_profile,
+            set_host_terminal_profile,
+            open_in_external_terminal,
+            // Transfers
+            start_transfer,
+            list_transfers,
+            cancel_transfer,
+            set_transfer_rate_limit,
+            get_transfer_settings,
+            set_transfer_settings,
+            list_scheduled_transfers,
+            schedule_transfer,
+            delete_scheduled_transfer,
+            // Doctor
+            run_doctor,
+            list_doctor_runs,
+            compare_doctor_runs,
+            get_remediation_playbook,
+            apply_remediation,
+            inspect_ssh_installations,
+            // SSH directory scan
+            scan_ssh_directory,
+            quarantine_file,
+            list_quarantined_files,
+            restore_quarantined_file,
+            delete_quarantined_file,
+            // Integrity baseline
+            verify_ssh_integrity,
+            accept_integrity_changes,
+            // Health checks
+            get_health_check_status,
+            set_health_check_settings,
+            run_health_check,
+            // Reachability
+            get_reachability_settings,
+            set_reachability_settings,
+            get_reachability_history,
+            // Session restore
+            get_restore_settings,
+            set_restore_settings,
+            get_restore_summary,
+            pin_shell_session,
+            list_pinned_sessions,
+            // Host registry
+            apply_registry_changes,
+            // Short codes
+            list_host_short_codes,
+            set_host_short_code,
+            resolve_host_short_code,
+            // Smart groups
+            list_smart_groups,
+            preview_smart_group,
+            save_smart_group,
+            delete_smart_group,
+            // Host notes
+            add_host_attachment,
+            remove_host_attachment,
+            get_host_attachment_path,
+            capture_command_output,
+            list_host_captures,
+            delete_host_captures,
+            // Trash
+            get_trash_settings,
+            set_trash_settings,
+            list_recently_deleted,
+            restore_deleted_item,
+            forget_deleted_item,
         ])
         .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+            start_vault_auto_lock(app.handle().clone());
+            start_vm_expiry(app.handle().clone());
+            start_catalog_refresh(app.handle().clone());
+            start_legacy_reminders(app.handle().clone());
+            start_tamper_watch(app.handle().clone());
+            start_integrity_watch(app.handle().clone());
+            start_health_checks(app.handle().clone());
+            start_job_scheduler(app.handle().clone());
+            start_reachability_monitor(app.handle().clone());
+            tauri::async_runtime::spawn(services::WatcherService::global().run());
+            tauri::async_runtime::spawn(services::PrivacyService::run_retention());
+            tauri::async_runtime::spawn(services::FingerprintIndexService::run());
+            setup_tray(app.handle())?;
+            start_palette_shortcut(app.handle().clone());
+            start_deep_links(app.handle())?;
+            start_transfer_scheduler(app.handle().clone());
+            allow_app_paths(app.handle());
+            start_session_restore(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -2,5 +2,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if ssh_buddy_lib::run_ssh_helper() {
+        return;
+    }
     ssh_buddy_lib::run();
 }
@@ -1,14 +0,0 @@
-pub mod agent_service;
-pub mod key_manager;
-pub mod known_hosts;
-pub mod permission_service;
-pub mod ssh_connection;
-
-pub use agent_service::{AddKeyResult, AgentKeyInfo, AgentService, RemoveKeyResult};
-pub use key_manager::{GenerateKeyOptions, KeyManager};
-pub use known_hosts::{
-    AddHostResult as KnownHostAddResult, KnownHostsService,
-    RemoveHostResult as KnownHostRemoveResult,
-};
-pub use permission_service::{PermissionCheckResult, PermissionFixResult, PermissionService};
-pub use ssh_connection::{ConnectionTestResult, SshConnectionService};
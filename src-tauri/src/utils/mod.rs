@@ -1,5 +0,0 @@
-pub mod path_validator;
-pub mod ssh_config;
-
-pub use path_validator::*;
-pub use ssh_config::*;
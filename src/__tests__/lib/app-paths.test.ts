@@ -0,0 +1,83 @@
+import { describe, it, expect, vi, beforeEach } from 'vitest'
+import { invoke } from '@tauri-apps/api/core'
+import type { AppPaths } from '../../lib/app-paths'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const defaultPaths: AppPaths = {
+  portable: false,
+  dataDir: '/Users/test/Library/Application Support/com.sshbuddy',
+  sshDir: '/Users/test/.ssh',
+  customSshDir: false,
+  sshDirForced: false,
+  workspace: null,
+  workspaceDir: '/Users/test/Library/Application Support/com.sshbuddy',
+  sshConfigPath: '/Users/test/.ssh/config',
+}
+
+describe('app-paths', () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+    vi.resetModules()
+  })
+
+  it('should fetch the paths once', async () => {
+    vi.mocked(invoke).mockResolvedValue(defaultPaths)
+
+    const { getAppPaths } = await import('../../lib/app-paths')
+    expect(await getAppPaths()).toEqual(defaultPaths)
+    expect(await getAppPaths()).toEqual(defaultPaths)
+
+    expect(invoke).toHaveBeenCalledTimes(1)
+    expect(invoke).toHaveBeenCalledWith('get_app_paths')
+  })
+
+  it('should retry after a failed fetch', async () => {
+    vi.mocked(invoke)
+      .mockRejectedValueOnce(new Error('Backend error'))
+      .mockResolvedValueOnce(defaultPaths)
+
+    const { getAppPaths } = await import('../../lib/app-paths')
+    await expect(getAppPaths()).rejects.toThrow('Backend error')
+    expect(await getAppPaths()).toEqual(defaultPaths)
+  })
+
+  it('should use the paths returned when the SSH directory changes', async () => {
+    const custom = {
+      ...defaultPaths,
+      sshDir: '/Volumes/home/ssh',
+      customSshDir: true,
+    }
+    vi.mocked(invoke).mockResolvedValueOnce(custom)
+
+    const { getAppPaths, setSshRoot } = await import('../../lib/app-paths')
+    expect(await setSshRoot('/Volumes/home/ssh')).toEqual(custom)
+    expect(await getAppPaths()).toEqual(custom)
+
+    expect(invoke).toHaveBeenCalledTimes(1)
+    expect(invoke).toHaveBeenCalledWith('set_ssh_root', {
+      root: '/Volumes/home/ssh',
+    })
+  })
+
+  it('should use the paths of the workspace switched to', async () => {
+    const work = {
+      ...defaultPaths,
+      workspace: 'work',
+      workspaceDir: `${defaultPaths.dataDir}/workspaces/work`,
+      sshConfigPath: '/Users/test/.ssh/config.d/work',
+    }
+    vi.mocked(invoke).mockResolvedValueOnce(work)
+
+    const { getAppPaths, switchWorkspace } = await import(
+      '../../lib/app-paths'
+    )
+    expect(await switchWorkspace('work')).toEqual(work)
+    expect(await getAppPaths()).toEqual(work)
+
+    expect(invoke).toHaveBeenCalledTimes(1)
+    expect(invoke).toHaveBeenCalledWith('switch_workspace', { id: 'work' })
+  })
+})
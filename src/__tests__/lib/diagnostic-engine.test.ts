@@ -147,6 +147,19 @@ describe('diagnostic-engine', () => {
       expect(analysis.confidence).toBe('medium')
     })
 
+    it('should analyze vpn_not_connected error', () => {
+      const result: SSHConnectionTestResult = {
+        success: false,
+        output: 'Office VPN is not connected',
+        errorType: 'vpn_not_connected',
+      }
+
+      const analysis = analyzeRootCause(result)
+
+      expect(analysis.likelyCause).toContain('VPN')
+      expect(analysis.confidence).toBe('high')
+    })
+
     it('should analyze dns_failed error', () => {
       const result: SSHConnectionTestResult = {
         success: false,
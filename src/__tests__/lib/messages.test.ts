@@ -0,0 +1,64 @@
+import { describe, it, expect } from 'vitest'
+import {
+  formatTemplate,
+  localizeMessage,
+  type MessageCatalog,
+} from '../../lib/messages'
+
+const catalog: MessageCatalog = {
+  locale: 'zh-TW',
+  available: ['en', 'zh-TW'],
+  messages: {
+    'knownHosts.removed': '已移除 {host} 的 {count} 筆項目',
+  },
+}
+
+describe('messages', () => {
+  describe('formatTemplate', () => {
+    it('should replace placeholders with parameters', () => {
+      expect(
+        formatTemplate('Removed {count} entries for {host}', {
+          count: '2',
+          host: 'example.com',
+        })
+      ).toBe('Removed 2 entries for example.com')
+    })
+
+    it('should keep placeholders without a parameter', () => {
+      expect(formatTemplate('Permissions set to {mode}')).toBe(
+        'Permissions set to {mode}'
+      )
+    })
+
+    it('should not expand placeholders inside parameter values', () => {
+      expect(formatTemplate('{output}', { output: '{host}', host: 'x' })).toBe(
+        '{host}'
+      )
+    })
+  })
+
+  describe('localizeMessage', () => {
+    it('should render a known key from the catalog', () => {
+      expect(
+        localizeMessage(
+          {
+            message: 'Removed 2 entries for example.com',
+            messageKey: 'knownHosts.removed',
+            messageParams: { count: '2', host: 'example.com' },
+          },
+          catalog
+        )
+      ).toBe('已移除 example.com 的 2 筆項目')
+    })
+
+    it('should fall back to the English text', () => {
+      const value = {
+        message: 'Vault is locked',
+        messageKey: 'error.vaultLocked',
+      }
+      expect(localizeMessage(value, catalog)).toBe('Vault is locked')
+      expect(localizeMessage(value, null)).toBe('Vault is locked')
+      expect(localizeMessage({ message: 'plain' }, catalog)).toBe('plain')
+    })
+  })
+})
@@ -0,0 +1,73 @@
+import { describe, it, expect } from 'vitest'
+import {
+  addHost,
+  parseSSHConfig,
+  quoteConfigArg,
+  serializeSSHConfig,
+  unquoteConfigArg,
+} from '../../lib/ssh-config'
+
+describe('ssh-config', () => {
+  // ========================================
+  // Quoting tests
+  // ========================================
+
+  describe('quoteConfigArg', () => {
+    it('should leave plain paths unquoted', () => {
+      expect(quoteConfigArg('~/.ssh/id_ed25519')).toBe('~/.ssh/id_ed25519')
+      expect(quoteConfigArg('~/.ssh/鍵🔑')).toBe('~/.ssh/鍵🔑')
+    })
+
+    it('should quote paths with spaces and keep Windows backslashes', () => {
+      expect(quoteConfigArg('C:\\Users\\Jo Doe\\.ssh\\id_ed25519')).toBe(
+        '"C:\\Users\\Jo Doe\\.ssh\\id_ed25519"'
+      )
+      expect(quoteConfigArg('C:\\My Keys\\')).toBe('"C:\\My Keys\\\\"')
+    })
+
+    it('should round-trip through unquoteConfigArg', () => {
+      for (const path of [
+        'C:\\Users\\Jo Doe\\.ssh\\id_ed25519',
+        'C:\\My Keys\\',
+        '\\\\server\\share\\my key',
+        '/home/王小明/My Keys/🔑 work',
+        'a "b"',
+      ]) {
+        expect(unquoteConfigArg(quoteConfigArg(path))).toBe(path)
+      }
+      expect(unquoteConfigArg("'~/my key'")).toBe('~/my key')
+    })
+  })
+
+  // ========================================
+  // Parse/serialize tests
+  // ========================================
+
+  describe('path options', () => {
+    it('should unquote IdentityFile when parsing', () => {
+      const config = parseSSHConfig(
+        ['Host 工作', '  IdentityFile "C:\\Users\\王 小明\\.ssh\\id 🔑"'].join(
+          '\n'
+        )
+      )
+
+      expect(config.hosts[0].Host).toBe('工作')
+      expect(config.hosts[0].IdentityFile).toBe(
+        'C:\\Users\\王 小明\\.ssh\\id 🔑'
+      )
+    })
+
+    it('should quote IdentityFile when serializing', () => {
+      const config = addHost(parseSSHConfig(''), {
+        Host: 'dev',
+        IdentityFile: '/Users/Jo Doe/.ssh/id_ed25519',
+        ProxyCommand: 'ssh -W %h:%p jump',
+      })
+      const text = serializeSSHConfig(config)
+
+      expect(text).toContain('  IdentityFile "/Users/Jo Doe/.ssh/id_ed25519"')
+      expect(text).toContain('  ProxyCommand ssh -W %h:%p jump')
+      expect(serializeSSHConfig(parseSSHConfig(text))).toBe(text)
+    })
+  })
+})
@@ -7,15 +7,9 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: vi.fn(),
 }))
 
-vi.mock('@tauri-apps/api/path', () => ({
-  homeDir: vi.fn().mockResolvedValue('/Users/test'),
-}))
-
 vi.mock('@tauri-apps/plugin-fs', () => ({
   readTextFile: vi.fn(),
-  writeTextFile: vi.fn(),
   exists: vi.fn(),
-  copyFile: vi.fn(),
 }))
 
 describe('ssh-service', () => {
@@ -0,0 +1,119 @@
+import { describe, it, expect } from 'vitest'
+import { parseSSHConfig } from '../../lib/ssh-config'
+import {
+  checkWildcardShadowing,
+  validateConfig,
+  validateHost,
+} from '../../lib/ssh-validation'
+
+describe('ssh-validation', () => {
+  // ========================================
+  // checkWildcardShadowing tests
+  // ========================================
+
+  describe('checkWildcardShadowing', () => {
+    it('should warn when Host * comes before a specific host', () => {
+      const config = parseSSHConfig(
+        [
+          'Host *',
+          '  User root',
+          '  ServerAliveInterval 60',
+          '',
+          'Host prod',
+          '  HostName prod.example.com',
+          '  User deploy',
+        ].join('\n')
+      )
+
+      const issues = checkWildcardShadowing(config)
+
+      expect(issues).toHaveLength(1)
+      expect(issues[0].severity).toBe('warning')
+      expect(issues[0].message).toBe(
+        'Host * overrides User (root is used instead of deploy) for prod'
+      )
+    })
+
+    it('should not warn when the wildcard block is last', () => {
+      const config = parseSSHConfig(
+        ['Host prod', '  User deploy', '', 'Host *', '  User root'].join('\n')
+      )
+
+      expect(checkWildcardShadowing(config)).toEqual([])
+    })
+
+    it('should only report hosts matched by the pattern', () => {
+      const config = parseSSHConfig(
+        [
+          'Host *.prod.example.com !bastion.prod.example.com',
+          '  Port 2222',
+          '',
+          'Host web.prod.example.com bastion.prod.example.com',
+          '  Port 22',
+          '',
+          'Host dev.example.com',
+          '  Port 22',
+        ].join('\n')
+      )
+
+      const issues = checkWildcardShadowing(config)
+
+      expect(issues).toHaveLength(1)
+      expect(issues[0].message).toMatch(/for web\.prod\.example\.com$/)
+    })
+
+    it('should ignore cumulative options and identical values', () => {
+      const config = parseSSHConfig(
+        [
+          'Host *',
+          '  IdentityFile ~/.ssh/id_ed25519',
+          '  User deploy',
+          '',
+          'Host prod',
+          '  IdentityFile ~/.ssh/prod_key',
+          '  User deploy',
+        ].join('\n')
+      )
+
+      expect(checkWildcardShadowing(config)).toEqual([])
+    })
+
+    it('should be included in validateConfig', () => {
+      const config = parseSSHConfig(
+        ['Host *', '  Port 2222', '', 'Host prod', '  Port 22'].join('\n')
+      )
+
+      const result = validateConfig(config)
+
+      expect(
+        result.issues.some((i) => i.message.startsWith('Host * overrides Port'))
+      ).toBe(true)
+    })
+  })
+
+  // ========================================
+  // IdentityFile path tests
+  // ========================================
+
+  describe('IdentityFile paths', () => {
+    const pathWarnings = (IdentityFile: string) =>
+      validateHost({ Host: 'h', HostName: 'h', IdentityFile }).issues.filter(
+        (i) => i.message === 'Identity file path may be invalid'
+      )
+
+    it('should accept Windows, UNC and non-ASCII paths', () => {
+      for (const path of [
+        'C:\\Users\\Jo Doe\\.ssh\\id_ed25519',
+        'D:/鍵/id 🔑',
+        '\\\\nas\\home\\.ssh\\id_rsa',
+        '~/.ssh/工作 金鑰',
+      ]) {
+        expect(pathWarnings(path)).toEqual([])
+      }
+    })
+
+    it('should warn about relative paths', () => {
+      expect(pathWarnings('keys/id_ed25519')).toHaveLength(1)
+    })
+  })
+})
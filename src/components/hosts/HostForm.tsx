@@ -200,9 +200,14 @@ export function HostForm({
 
   const validateIdentityFile = (value: string): string | undefined => {
     if (!value) return undefined
-    // Basic path validation
-    if (!value.startsWith('/') && !value.startsWith('~')) {
-      return 'Please enter a valid file path (starting with / or ~)'
+    // Basic path validation; Windows drive (C:\) and UNC (\\server) paths are absolute too
+    const isAbsolute =
+      value.startsWith('/') ||
+      value.startsWith('~') ||
+      /^[A-Za-z]:[\\/]/.test(value) ||
+      value.startsWith('\\\\')
+    if (!isAbsolute) {
+      return 'Please enter a valid file path (starting with /, ~ or a drive letter)'
     }
     return undefined
   }
@@ -99,6 +99,9 @@ export function HostList({
           const favA = metaA?.isFavorite ? 1 : 0
           const favB = metaB?.isFavorite ? 1 : 0
           if (favA !== favB) return favB - favA
+          const pinA = metaA?.pinOrder ?? Number.MAX_SAFE_INTEGER
+          const pinB = metaB?.pinOrder ?? Number.MAX_SAFE_INTEGER
+          if (favA && pinA !== pinB) return pinA - pinB
           return a.Host.localeCompare(b.Host)
         }
         case 'name':
@@ -116,7 +116,7 @@ export function SecurityPanel({ keys, onRefresh }: SecurityPanelProps) {
 
     setIsDeleting(true)
     try {
-      await removeKnownHostEntry(deleteTarget.lineNumber)
+      await removeKnownHostEntry(deleteTarget)
       await runScan()
       onRefresh?.()
     } catch (error) {
@@ -17,6 +17,7 @@ import {
   markFirstRun,
   type MetadataStore,
   type HostMetadata,
+  type EditableHostMetadata,
   type AppMetadata,
 } from '@/lib/metadata-service'
 
@@ -28,7 +29,7 @@ interface UseMetadataReturn {
 
   // Host metadata
   getHostMeta: (hostAlias: string) => HostMetadata | null
-  setHostMeta: (hostAlias: string, meta: Partial<HostMetadata>) => Promise<void>
+  setHostMeta: (hostAlias: string, meta: EditableHostMetadata) => Promise<void>
   addTags: (hostAlias: string, tags: string[]) => Promise<void>
   removeTags: (hostAlias: string, tags: string[]) => Promise<void>
   toggleFavorite: (hostAlias: string) => Promise<boolean>
@@ -85,7 +86,7 @@ export function useMetadata(): UseMetadataReturn {
   )
 
   const setHostMeta = useCallback(
-    async (hostAlias: string, meta: Partial<HostMetadata>) => {
+    async (hostAlias: string, meta: EditableHostMetadata) => {
       await setHostMetadata(hostAlias, meta)
       await refresh()
     },
@@ -0,0 +1,58 @@
+/**
+ * App Paths
+ * Where the Rust backend keeps the app data and finds the SSH files
+ * (the SSH directory is configurable, the data moves in portable mode and
+ * each workspace has its own host registry and SSH config)
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface AppPaths {
+  /** App data lives next to the executable */
+  portable: boolean
+  dataDir: string
+  sshDir: string
+  /** The SSH directory is not ~/.ssh */
+  customSshDir: boolean
+  /** Set by SSH_BUDDY_SSH_DIR; can't be changed in the app */
+  sshDirForced: boolean
+  /** Active workspace; null for the default one */
+  workspace: string | null
+  /** Data of the active workspace (host registry, vault) */
+  workspaceDir: string
+  /** SSH config file of the active workspace */
+  sshConfigPath: string
+}
+
+let appPaths: Promise<AppPaths> | null = null
+
+/**
+ * Get the paths (fetched once, until they change)
+ */
+export function getAppPaths(): Promise<AppPaths> {
+  if (!appPaths) {
+    appPaths = invoke<AppPaths>('get_app_paths').catch((error) => {
+      appPaths = null
+      throw error
+    })
+  }
+  return appPaths
+}
+
+/**
+ * Use another SSH directory instead of ~/.ssh (null goes back to ~/.ssh)
+ */
+export async function setSshRoot(root: string | null): Promise<AppPaths> {
+  const paths = await invoke<AppPaths>('set_ssh_root', { root })
+  appPaths = Promise.resolve(paths)
+  return paths
+}
+
+/**
+ * Switch workspaces; the hosts and keys must be reloaded afterwards
+ */
+export async function switchWorkspace(id: string): Promise<AppPaths> {
+  const paths = await invoke<AppPaths>('switch_workspace', { id })
+  appPaths = Promise.resolve(paths)
+  return paths
+}
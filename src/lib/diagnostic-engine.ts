@@ -536,6 +536,18 @@ export function analyzeRootCause(
         ],
       }
 
+    case 'vpn_not_connected':
+      return {
+        likelyCause: 'Required VPN is not connected',
+        confidence: 'high',
+        explanation:
+          'This host is only reachable through a VPN or private network, and that network is not connected. The connection was not attempted.',
+        relatedIssues: [
+          'Connect the VPN and test again',
+          'Set a launch command to start the VPN automatically',
+        ],
+      }
+
     case 'dns_failed':
       return {
         likelyCause: 'Hostname cannot be resolved',
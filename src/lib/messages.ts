@@ -0,0 +1,65 @@
+/**
+ * Backend Messages
+ * Renders messages returned by the Rust backend (results and errors carry a
+ * messageKey and messageParams) with the catalog of the user's language
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+/**
+ * Localizable message fields of backend results and errors
+ */
+export interface LocalizedMessage {
+  /** English text, used when the key is not in the catalog */
+  message: string
+  messageKey?: string
+  messageParams?: Record<string, string>
+}
+
+/**
+ * Message catalog from the Rust backend
+ */
+export interface MessageCatalog {
+  locale: string
+  available: string[]
+  messages: Record<string, string>
+}
+
+/**
+ * Fetch the catalog for a locale (unsupported locales get English)
+ */
+export async function loadMessageCatalog(
+  locale: string = navigator.language
+): Promise<MessageCatalog> {
+  return invoke<MessageCatalog>('get_message_catalog', { locale })
+}
+
+/**
+ * Replace {name} placeholders; placeholders without a parameter are kept
+ */
+export function formatTemplate(
+  template: string,
+  params: Record<string, string> = {}
+): string {
+  return template.replace(/\{(\w+)\}/g, (placeholder, name: string) =>
+    Object.prototype.hasOwnProperty.call(params, name)
+      ? params[name]
+      : placeholder
+  )
+}
+
+/**
+ * Render a backend message in the catalog's language, falling back to the
+ * English text the backend sent along
+ */
+export function localizeMessage(
+  value: LocalizedMessage,
+  catalog?: MessageCatalog | null
+): string {
+  const template = value.messageKey
+    ? catalog?.messages[value.messageKey]
+    : undefined
+  return template === undefined
+    ? value.message
+    : formatTemplate(template, value.messageParams)
+}
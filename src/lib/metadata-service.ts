@@ -2,28 +2,51 @@
  * Metadata Service
  * Handles app-only metadata storage for host tags, favorites, and last-used timestamps.
  * Data is stored locally in the Tauri app data directory, separate from ~/.ssh/config.
+ * It is read here and changed only through the backend, which serializes the writes.
  */
 
-import {
-  readTextFile,
-  writeTextFile,
-  exists,
-  mkdir,
-} from '@tauri-apps/plugin-fs'
-import { appDataDir } from '@tauri-apps/api/path'
+import { readTextFile, exists } from '@tauri-apps/plugin-fs'
+import { invoke } from '@tauri-apps/api/core'
+import { getAppPaths } from './app-paths'
 
 // Current schema version for migrations
 const METADATA_SCHEMA_VERSION = 1
 
+/**
+ * Key metadata stored per key fingerprint (managed by the backend)
+ */
+export interface KeyMetadata {
+  friendlyName?: string
+  purpose?: string
+  owner?: string
+  linkedHosts?: string[]
+  updatedAt: number
+}
+
 /**
  * Host metadata stored per-host by host alias
  */
 export interface HostMetadata {
   tags: string[]
   isFavorite: boolean
+  pinOrder?: number // Position among the favorites, set by reordering
   lastUsed?: number // Unix timestamp
+  useCount?: number // Times connected, weights quick-connect search
   createdAt: number // Unix timestamp
-  notes?: string
+  notes?: string // Markdown
+  attachments?: HostAttachment[]
+  shortCode?: string // Typed in quick-connect and links instead of the alias
+  catalogSource?: CatalogSource // Set for read-only hosts from a team catalog
+}
+
+/**
+ * Provenance of a host merged from a subscribed team catalog
+ */
+export interface CatalogSource {
+  catalog: string // Subscription id
+  label: string // Catalog name
+  revision?: string
+  digest: string
 }
 
 /**
@@ -43,25 +66,15 @@ export interface MetadataStore {
   hosts: Record<string, HostMetadata>
   tags: string[] // All unique tags for quick access
   app: AppMetadata
+  keys?: Record<string, KeyMetadata> // Keyed by SHA256 fingerprint
 }
 
-let metadataPath: string | null = null
-
 /**
  * Get the metadata file path
  */
 async function getMetadataPath(): Promise<string> {
-  if (metadataPath) return metadataPath
-
-  const appData = await appDataDir()
-  // Ensure app data directory exists
-  const dirExists = await exists(appData)
-  if (!dirExists) {
-    await mkdir(appData, { recursive: true })
-  }
-
-  metadataPath = `${appData}/metadata.json`
-  return metadataPath
+  const { workspaceDir } = await getAppPaths()
+  return `${workspaceDir}/metadata.json`
 }
 
 /**
@@ -107,15 +120,6 @@ export async function readMetadata(): Promise<MetadataStore> {
   }
 }
 
-/**
- * Write metadata to disk
- */
-export async function writeMetadata(metadata: MetadataStore): Promise<void> {
-  const path = await getMetadataPath()
-  const content = JSON.stringify(metadata, null, 2)
-  await writeTextFile(path, content)
-}
-
 /**
  * Handle schema migrations
  */
@@ -142,30 +146,35 @@ export async function getHostMetadata(
   return store.hosts[hostAlias] || null
 }
 
+/**
+ * Host metadata fields edited in the app (the rest is kept by the backend)
+ */
+export type EditableHostMetadata = Partial<
+  Pick<HostMetadata, 'tags' | 'isFavorite' | 'notes'>
+>
+
 /**
  * Set metadata for a host
  */
 export async function setHostMetadata(
   hostAlias: string,
-  metadata: Partial<HostMetadata>
+  metadata: EditableHostMetadata
 ): Promise<void> {
-  const store = await readMetadata()
-
-  const existing = store.hosts[hostAlias] || {
-    tags: [],
-    isFavorite: false,
-    createdAt: Date.now(),
+  const changes: RegistryChange[] = []
+  if (metadata.tags !== undefined) {
+    changes.push({ type: 'setTags', alias: hostAlias, tags: metadata.tags })
   }
-
-  store.hosts[hostAlias] = {
-    ...existing,
-    ...metadata,
+  if (metadata.isFavorite !== undefined) {
+    changes.push({
+      type: 'setFavorite',
+      alias: hostAlias,
+      isFavorite: metadata.isFavorite,
+    })
   }
-
-  // Update global tags list
-  updateGlobalTags(store)
-
-  await writeMetadata(store)
+  if (metadata.notes !== undefined) {
+    changes.push({ type: 'setNotes', alias: hostAlias, notes: metadata.notes })
+  }
+  await applyRegistryChanges(changes)
 }
 
 /**
@@ -175,24 +184,7 @@ export async function addHostTags(
   hostAlias: string,
   tags: string[]
 ): Promise<void> {
-  const store = await readMetadata()
-
-  const existing = store.hosts[hostAlias] || {
-    tags: [],
-    isFavorite: false,
-    createdAt: Date.now(),
-  }
-
-  // Merge tags without duplicates
-  const newTags = [...new Set([...existing.tags, ...tags])]
-
-  store.hosts[hostAlias] = {
-    ...existing,
-    tags: newTags,
-  }
-
-  updateGlobalTags(store)
-  await writeMetadata(store)
+  await applyRegistryChanges([{ type: 'addTags', aliases: [hostAlias], tags }])
 }
 
 /**
@@ -202,64 +194,35 @@ export async function removeHostTags(
   hostAlias: string,
   tags: string[]
 ): Promise<void> {
-  const store = await readMetadata()
-
-  const existing = store.hosts[hostAlias]
-  if (!existing) return
-
-  existing.tags = existing.tags.filter((t) => !tags.includes(t))
-
-  updateGlobalTags(store)
-  await writeMetadata(store)
+  await applyRegistryChanges([
+    { type: 'removeTags', aliases: [hostAlias], tags },
+  ])
 }
 
 /**
  * Toggle favorite status
  */
 export async function toggleHostFavorite(hostAlias: string): Promise<boolean> {
-  const store = await readMetadata()
-
-  const existing = store.hosts[hostAlias] || {
-    tags: [],
-    isFavorite: false,
-    createdAt: Date.now(),
-  }
-
-  existing.isFavorite = !existing.isFavorite
-  store.hosts[hostAlias] = existing
-
-  await writeMetadata(store)
-  return existing.isFavorite
+  const existing = await getHostMetadata(hostAlias)
+  const isFavorite = !existing?.isFavorite
+  await applyRegistryChanges([
+    { type: 'setFavorite', alias: hostAlias, isFavorite },
+  ])
+  return isFavorite
 }
 
 /**
- * Update last-used timestamp
+ * Update last-used timestamp and usage count
  */
 export async function updateLastUsed(hostAlias: string): Promise<void> {
-  const store = await readMetadata()
-
-  const existing = store.hosts[hostAlias] || {
-    tags: [],
-    isFavorite: false,
-    createdAt: Date.now(),
-  }
-
-  existing.lastUsed = Date.now()
-  store.hosts[hostAlias] = existing
-
-  await writeMetadata(store)
+  await applyRegistryChanges([{ type: 'recordUse', alias: hostAlias }])
 }
 
 /**
  * Delete host metadata (call when host is removed from SSH config)
  */
 export async function deleteHostMetadata(hostAlias: string): Promise<void> {
-  const store = await readMetadata()
-
-  delete store.hosts[hostAlias]
-  updateGlobalTags(store)
-
-  await writeMetadata(store)
+  await applyRegistryChanges([{ type: 'remove', alias: hostAlias }])
 }
 
 /**
@@ -269,14 +232,7 @@ export async function renameHostMetadata(
   oldAlias: string,
   newAlias: string
 ): Promise<void> {
-  const store = await readMetadata()
-
-  const metadata = store.hosts[oldAlias]
-  if (metadata) {
-    store.hosts[newAlias] = metadata
-    delete store.hosts[oldAlias]
-    await writeMetadata(store)
-  }
+  await applyRegistryChanges([{ type: 'rename', from: oldAlias, to: newAlias }])
 }
 
 /**
@@ -299,87 +255,210 @@ export async function reconcileMetadata(
   return orphaned
 }
 
+/**
+ * Change of a registry batch
+ */
+export type RegistryChange =
+  | { type: 'setTags'; alias: string; tags: string[] }
+  | { type: 'addTags'; aliases: string[]; tags: string[] }
+  | { type: 'removeTags'; aliases: string[]; tags: string[] }
+  | { type: 'setFavorite'; alias: string; isFavorite: boolean }
+  | { type: 'reorderFavorites'; aliases: string[] }
+  | { type: 'setNotes'; alias: string; notes: string | null }
+  | { type: 'recordUse'; alias: string }
+  | { type: 'rename'; from: string; to: string }
+  | { type: 'remove'; alias: string }
+  | { type: 'createTag'; tag: string }
+  | { type: 'deleteTag'; tag: string }
+  | { type: 'renameTag'; from: string; to: string }
+  | { type: 'updateApp'; values: Partial<AppMetadata> }
+  | { type: 'markFirstRun' }
+
+/**
+ * Apply many changes in a single registry write (bulk imports, tag edits, syncs)
+ * The backend emits one "registry-changed" event with the changed aliases
+ */
+export async function applyRegistryChanges(
+  changes: RegistryChange[]
+): Promise<string[]> {
+  if (changes.length === 0) {
+    return []
+  }
+  return invoke<string[]>('apply_registry_changes', { changes })
+}
+
+/**
+ * Pin hosts in the given order (e.g. after a drag in the favorites list)
+ * Favorites not listed keep their order after these
+ */
+export async function reorderFavorites(aliases: string[]): Promise<string[]> {
+  return applyRegistryChanges([{ type: 'reorderFavorites', aliases }])
+}
+
 // ============================================
-// Tag Helpers
+// Attachments
 // ============================================
 
 /**
- * Get all unique tags across all hosts
+ * File attached to a host, kept in the app data directory
  */
-export async function getAllTags(): Promise<string[]> {
-  const store = await readMetadata()
-  return store.tags
+export interface HostAttachment {
+  id: string
+  name: string
+  size: number // Bytes
+  addedAt: number // Unix timestamp
 }
 
 /**
- * Create a new global tag
+ * Copy a local file into the host's attachments (10 MB per file, 50 MB per host)
  */
-export async function createTag(tag: string): Promise<void> {
-  const store = await readMetadata()
+export async function addHostAttachment(
+  alias: string,
+  path: string
+): Promise<HostAttachment> {
+  return invoke<HostAttachment>('add_host_attachment', { alias, path })
+}
 
-  if (!store.tags.includes(tag)) {
-    store.tags.push(tag)
-    store.tags.sort()
-    await writeMetadata(store)
-  }
+export async function removeHostAttachment(
+  alias: string,
+  id: string
+): Promise<void> {
+  return invoke('remove_host_attachment', { alias, id })
 }
 
 /**
- * Delete a tag from all hosts and global list
+ * Local path of an attachment, to open it with the system viewer
  */
-export async function deleteTag(tag: string): Promise<void> {
-  const store = await readMetadata()
+export async function getHostAttachmentPath(
+  alias: string,
+  id: string
+): Promise<string> {
+  return invoke<string>('get_host_attachment_path', { alias, id })
+}
 
-  // Remove from all hosts
-  for (const hostAlias of Object.keys(store.hosts)) {
-    store.hosts[hostAlias].tags = store.hosts[hostAlias].tags.filter(
-      (t) => t !== tag
-    )
-  }
+// ============================================
+// Captures
+// ============================================
 
-  // Remove from global list
-  store.tags = store.tags.filter((t) => t !== tag)
+/**
+ * Output of a remote command run, kept next to the host
+ */
+export interface CaptureRecord {
+  id: string
+  hostAlias: string
+  command: string
+  output: string
+  exitCode: number | null
+  capturedAt: number // Unix timestamp
+  truncated: boolean // Output was cut at 64 KB
+}
 
-  await writeMetadata(store)
+/**
+ * Append a command's output (with its command and time) to the host's notes
+ * or to its captures log
+ */
+export async function captureCommandOutput(request: {
+  hostAlias: string
+  command: string
+  output: string
+  exitCode?: number | null
+  capturedAt?: number
+  target: 'notes' | 'captures'
+}): Promise<CaptureRecord> {
+  return invoke<CaptureRecord>('capture_command_output', { request })
 }
 
 /**
- * Rename a tag across all hosts
+ * Captures of a host, newest first
  */
-export async function renameTag(oldTag: string, newTag: string): Promise<void> {
-  const store = await readMetadata()
+export async function listHostCaptures(
+  alias: string,
+  limit?: number
+): Promise<CaptureRecord[]> {
+  return invoke<CaptureRecord[]>('list_host_captures', { alias, limit })
+}
 
-  // Update in all hosts
-  for (const hostAlias of Object.keys(store.hosts)) {
-    const idx = store.hosts[hostAlias].tags.indexOf(oldTag)
-    if (idx !== -1) {
-      store.hosts[hostAlias].tags[idx] = newTag
-    }
-  }
+/**
+ * Delete one capture, or all captures of the host when no id is given
+ */
+export async function deleteHostCaptures(
+  alias: string,
+  id?: string
+): Promise<number> {
+  return invoke<number>('delete_host_captures', { alias, id })
+}
 
-  // Update global list
-  const idx = store.tags.indexOf(oldTag)
-  if (idx !== -1) {
-    store.tags[idx] = newTag
-    store.tags.sort()
-  }
+// ============================================
+// Smart Groups
+// ============================================
 
-  await writeMetadata(store)
+/**
+ * Host group defined by a query, e.g. "tag=prod AND failed recently"
+ */
+export interface SmartGroup {
+  id: string // Empty to create
+  name: string
+  query: string
+}
+
+export interface SmartGroupMembers extends SmartGroup {
+  aliases: string[]
+  evaluatedAt: number // Unix timestamp
 }
 
 /**
- * Update global tags list from all host tags
+ * Smart groups with their members, evaluated (and cached) by the backend
  */
-function updateGlobalTags(store: MetadataStore): void {
-  const allTags = new Set<string>()
+export async function listSmartGroups(): Promise<SmartGroupMembers[]> {
+  return invoke<SmartGroupMembers[]>('list_smart_groups')
+}
 
-  for (const hostAlias of Object.keys(store.hosts)) {
-    for (const tag of store.hosts[hostAlias].tags) {
-      allTags.add(tag)
-    }
-  }
+/**
+ * Hosts a query matches, without saving it (rejects invalid queries)
+ */
+export async function previewSmartGroup(query: string): Promise<string[]> {
+  return invoke<string[]>('preview_smart_group', { query })
+}
 
-  store.tags = [...allTags].sort()
+export async function saveSmartGroup(group: SmartGroup): Promise<SmartGroup> {
+  return invoke<SmartGroup>('save_smart_group', { group })
+}
+
+export async function deleteSmartGroup(id: string): Promise<void> {
+  return invoke('delete_smart_group', { id })
+}
+
+// ============================================
+// Tag Helpers
+// ============================================
+
+/**
+ * Get all unique tags across all hosts
+ */
+export async function getAllTags(): Promise<string[]> {
+  const store = await readMetadata()
+  return store.tags
+}
+
+/**
+ * Create a new global tag (listed until deleted, even before a host uses it)
+ */
+export async function createTag(tag: string): Promise<void> {
+  await applyRegistryChanges([{ type: 'createTag', tag }])
+}
+
+/**
+ * Delete a tag from all hosts and global list
+ */
+export async function deleteTag(tag: string): Promise<void> {
+  await applyRegistryChanges([{ type: 'deleteTag', tag }])
+}
+
+/**
+ * Rename a tag across all hosts
+ */
+export async function renameTag(oldTag: string, newTag: string): Promise<void> {
+  await applyRegistryChanges([{ type: 'renameTag', from: oldTag, to: newTag }])
 }
 
 // ============================================
@@ -400,14 +479,7 @@ export async function getAppMetadata(): Promise<AppMetadata> {
 export async function updateAppMetadata(
   updates: Partial<AppMetadata>
 ): Promise<void> {
-  const store = await readMetadata()
-
-  store.app = {
-    ...store.app,
-    ...updates,
-  }
-
-  await writeMetadata(store)
+  await applyRegistryChanges([{ type: 'updateApp', values: updates }])
 }
 
 /**
@@ -436,10 +508,5 @@ export async function isFirstRun(): Promise<boolean> {
  * Mark first run timestamp
  */
 export async function markFirstRun(): Promise<void> {
-  const store = await readMetadata()
-
-  if (!store.app.firstRunAt) {
-    store.app.firstRunAt = Date.now()
-    await writeMetadata(store)
-  }
+  await applyRegistryChanges([{ type: 'markFirstRun' }])
 }
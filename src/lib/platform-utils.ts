@@ -6,6 +6,7 @@
 
 import { platform } from '@tauri-apps/plugin-os'
 import { invoke } from '@tauri-apps/api/core'
+import type { LocalizedMessage } from './messages'
 
 export type Platform = 'macos' | 'windows' | 'linux'
 
@@ -24,22 +25,58 @@ export function getPlatform(): Platform {
 /**
  * Permission check result from Rust backend
  */
-interface RustPermissionCheckResult {
+interface RustPermissionCheckResult extends LocalizedMessage {
   isValid: boolean
   currentMode: string | null
-  expectedMode: string
-  message: string
+  expectedMode: string // Mode a fix sets
+  acceptedModes: string[]
+  profile: PermissionProfile
+  /** How the permissions were read; 'unavailable' when this system can't check them */
+  backend: 'mode' | 'icacls' | 'win32Acl' | 'unavailable'
 }
 
 /**
  * Permission fix result from Rust backend
  */
-interface RustPermissionFixResult {
+interface RustPermissionFixResult extends LocalizedMessage {
   success: boolean
-  message: string
   newMode: string | null
 }
 
+/**
+ * Which modes the permission checks accept
+ * strict: keys 600, SSH directory 700
+ * relaxed: also 640 keys and a 750 directory (shared service accounts)
+ * custom: strict, except for paths with an override
+ */
+export type PermissionProfile = 'strict' | 'relaxed' | 'custom'
+
+export interface PermissionOverride {
+  path: string // Absolute, ~/..., or relative to the SSH directory
+  mode: string // Octal, e.g. '640'
+}
+
+export interface PermissionPolicy {
+  profile: PermissionProfile
+  overrides: PermissionOverride[]
+}
+
+/**
+ * Get the permission policy from settings
+ */
+export async function getPermissionPolicy(): Promise<PermissionPolicy> {
+  return await invoke<PermissionPolicy>('get_permission_policy')
+}
+
+/**
+ * Save the permission policy; later checks and fixes follow it
+ */
+export async function setPermissionPolicy(
+  policy: PermissionPolicy
+): Promise<void> {
+  await invoke('set_permission_policy', { policy })
+}
+
 /**
  * Permission check result (frontend format)
  */
@@ -75,7 +112,7 @@ export async function checkKeyPermissions(
       currentMode: result.currentMode ?? undefined,
       requiredMode: result.expectedMode,
       message: result.message,
-      canFix: !result.isValid,
+      canFix: !result.isValid && result.backend !== 'unavailable',
     }
   } catch (error) {
     console.error('[platform-utils] Failed to check key permissions:', error)
@@ -3,7 +3,8 @@
  * Scans SSH keys and known_hosts for potential issues.
  */
 
-import { readTextFile, writeTextFile, exists } from '@tauri-apps/plugin-fs'
+import { readTextFile, exists } from '@tauri-apps/plugin-fs'
+import { invoke } from '@tauri-apps/api/core'
 import { getSSHDir } from './ssh-service'
 import type { SSHKeyInfo } from './ssh-service'
 
@@ -283,48 +284,25 @@ export async function checkKnownHosts(): Promise<KnownHostsResult> {
 
 /**
  * Remove a known_hosts entry
+ * Fails if the line no longer holds the entry (the file changed since the scan)
  */
-export async function removeKnownHostEntry(lineNumber: number): Promise<void> {
-  const sshDir = await getSSHDir()
-  const knownHostsPath = `${sshDir}/known_hosts`
-
-  const content = await readTextFile(knownHostsPath)
-  const lines = content.split('\n')
-
-  // Remove the line (lineNumber is 1-indexed)
-  lines.splice(lineNumber - 1, 1)
-
-  await writeTextFile(knownHostsPath, lines.join('\n'))
+export async function removeKnownHostEntry(
+  entry: Pick<KnownHostEntry, 'lineNumber' | 'raw'>
+): Promise<void> {
+  await invoke('remove_known_host_line', {
+    line: entry.lineNumber,
+    expected: entry.raw,
+  })
 }
 
 /**
  * Remove known_hosts entries by host name
  */
 export async function removeKnownHostByName(hostname: string): Promise<number> {
-  const sshDir = await getSSHDir()
-  const knownHostsPath = `${sshDir}/known_hosts`
-
-  const content = await readTextFile(knownHostsPath)
-  const lines = content.split('\n')
-  let removedCount = 0
-
-  const filteredLines = lines.filter((line) => {
-    const trimmed = line.trim()
-    if (!trimmed || trimmed.startsWith('#')) return true
-
-    const parsed = parseKnownHostLine(trimmed, 0)
-    if (
-      parsed &&
-      parsed.hosts.some((h) => h === hostname || h.includes(hostname))
-    ) {
-      removedCount++
-      return false
-    }
-    return true
+  const result = await invoke<{ removedCount: number }>('remove_known_host', {
+    hostname,
   })
-
-  await writeTextFile(knownHostsPath, filteredLines.join('\n'))
-  return removedCount
+  return result.removedCount
 }
 
 /**
@@ -73,8 +73,9 @@ export function parseSSHConfig(content: string): ParsedSSHConfig {
       continue
     }
 
-    const [, key, value] = match
+    const [, key, rawValue] = match
     const normalizedKey = normalizeKey(key)
+    const value = isPathKey(key) ? unquoteConfigArg(rawValue) : rawValue
 
     // Host directive starts a new block
     if (normalizedKey === 'Host') {
@@ -121,13 +122,13 @@ export function serializeSSHConfig(config: ParsedSSHConfig): string {
         outputLines.push(line.content)
         break
       case 'global':
-        outputLines.push(`${line.key} ${line.value}`)
+        outputLines.push(`${line.key} ${formatValue(line.key, line.value)}`)
         break
       case 'host':
         outputLines.push(`Host ${line.name}`)
         break
       case 'option':
-        outputLines.push(`  ${line.key} ${line.value}`)
+        outputLines.push(`  ${line.key} ${formatValue(line.key, line.value)}`)
         break
     }
   }
@@ -297,8 +298,61 @@ export function createDefaultHost(name: string): SSHHostConfig {
   }
 }
 
+/**
+ * Quote a config argument containing whitespace, quotes or `#`
+ * Backslashes are kept (Windows paths) except where ssh reads them as an escape:
+ * before a quote, another backslash, or the closing quote
+ */
+export function quoteConfigArg(value: string): string {
+  const alreadyQuoted =
+    value.length >= 2 && value.startsWith('"') && value.endsWith('"')
+  if (alreadyQuoted || (value !== '' && !/[\s"'#]/.test(value))) {
+    return value
+  }
+  const escaped = value
+    .replace(/\\(?=["'\\]|$)/g, '\\\\')
+    .replace(/"/g, '\\"')
+  return `"${escaped}"`
+}
+
+/**
+ * Value of a quoted config argument ("..." or '...'), unchanged when unquoted
+ */
+export function unquoteConfigArg(value: string): string {
+  const trimmed = value.trim()
+  const quote = trimmed[0]
+  if (
+    trimmed.length < 2 ||
+    (quote !== '"' && quote !== "'") ||
+    !trimmed.endsWith(quote)
+  ) {
+    return trimmed
+  }
+  return trimmed.slice(1, -1).replace(/\\(["'\\])/g, '$1')
+}
+
 // Helper functions
 
+// Keywords taking a single path; ssh splits an unquoted value on whitespace
+const PATH_KEYS = [
+  'identityfile',
+  'certificatefile',
+  'identityagent',
+  'controlpath',
+  'revokedhostkeys',
+  'pkcs11provider',
+  'securitykeyprovider',
+  'xauthlocation',
+]
+
+function isPathKey(key: string): boolean {
+  return PATH_KEYS.includes(key.toLowerCase())
+}
+
+function formatValue(key: string, value: string): string {
+  return isPathKey(key) ? quoteConfigArg(value) : value
+}
+
 function normalizeKey(key: string): string {
   // SSH config keys are case-insensitive, normalize to standard casing
   const keyMap: Record<string, string> = {
@@ -1,22 +1,15 @@
 /**
  * SSH Service
- * Handles SSH config and keys file operations using Tauri fs plugin
+ * Reads SSH config and keys with the Tauri fs plugin; changes go through
+ * backend commands, which keep a backup and respect read-only mode
  */
 
-import {
-  readTextFile,
-  writeTextFile,
-  exists,
-  copyFile,
-} from '@tauri-apps/plugin-fs'
-import { homeDir } from '@tauri-apps/api/path'
+import { readTextFile, exists } from '@tauri-apps/plugin-fs'
 import { invoke } from '@tauri-apps/api/core'
+import { getAppPaths } from './app-paths'
+import { getHostMetadata, type KeyMetadata } from './metadata-service'
 import {
   parseSSHConfig,
-  serializeSSHConfig,
-  addHost,
-  updateHost,
-  removeHost,
   createEmptyConfig,
   type ParsedSSHConfig,
   type SSHHostConfig,
@@ -32,29 +25,23 @@ export interface SSHKeyInfo {
   fingerprint?: string
   comment?: string
   bitSize?: number // Key bit size (e.g., 4096 for RSA)
+  randomart?: string // OpenSSH-style randomart of the fingerprint
 }
 
-let sshDirPath: string | null = null
-
 /**
- * Get the SSH directory path
+ * Get the SSH directory path (~/.ssh unless configured)
  */
 export async function getSSHDir(): Promise<string> {
-  if (sshDirPath) return sshDirPath
-
-  const home = await homeDir()
-  // homeDir() returns path without trailing slash on some platforms
-  sshDirPath = home.endsWith('/') ? `${home}.ssh` : `${home}/.ssh`
-  console.log('[ssh-service] SSH dir path:', sshDirPath)
-  return sshDirPath
+  const { sshDir } = await getAppPaths()
+  return sshDir.replace(/[\\/]$/, '')
 }
 
 /**
  * Get the SSH config file path
  */
 export async function getSSHConfigPath(): Promise<string> {
-  const sshDir = await getSSHDir()
-  return `${sshDir}/config`
+  const { sshConfigPath } = await getAppPaths()
+  return sshConfigPath
 }
 
 /**
@@ -92,29 +79,35 @@ export async function readSSHConfig(): Promise<ParsedSSHConfig> {
 }
 
 /**
- * Write SSH config (with backup)
+ * Options of a host as [keyword, value] pairs, in the order they were set
  */
-export async function writeSSHConfig(config: ParsedSSHConfig): Promise<void> {
-  const configPath = await getSSHConfigPath()
-
-  // Create backup if file exists
-  const configExists = await exists(configPath)
-  if (configExists) {
-    const backupPath = `${configPath}.bak`
-    await copyFile(configPath, backupPath)
-  }
-
-  const content = serializeSSHConfig(config)
-  await writeTextFile(configPath, content)
+function hostOptions(host: SSHHostConfig): [string, string][] {
+  return Object.entries(host)
+    .filter(([key, value]) => key !== 'Host' && value !== undefined)
+    .map(([key, value]): [string, string] => [key, String(value)])
 }
 
 /**
  * Add a new host to SSH config
+ * The backend writes it (keeping config.bak), unless read-only mode is on
  */
 export async function addSSHHost(host: SSHHostConfig): Promise<void> {
-  const config = await readSSHConfig()
-  const newConfig = addHost(config, host)
-  await writeSSHConfig(newConfig)
+  await invoke('add_ssh_host', {
+    host: host.Host,
+    options: hostOptions(host),
+  })
+}
+
+/**
+ * Refuse changes to a host that comes from a team catalog
+ */
+async function ensureEditable(hostName: string): Promise<void> {
+  const metadata = await getHostMetadata(hostName)
+  if (metadata?.catalogSource) {
+    throw new Error(
+      `${hostName} is managed by the catalog ${metadata.catalogSource.label}`
+    )
+  }
 }
 
 /**
@@ -124,18 +117,21 @@ export async function updateSSHHost(
   oldHostName: string,
   newHost: SSHHostConfig
 ): Promise<void> {
-  const config = await readSSHConfig()
-  const newConfig = updateHost(config, oldHostName, newHost)
-  await writeSSHConfig(newConfig)
+  await ensureEditable(oldHostName)
+  await invoke('update_ssh_host', {
+    original: oldHostName,
+    host: newHost.Host,
+    options: hostOptions(newHost),
+  })
 }
 
 /**
  * Remove a host from SSH config
  */
 export async function removeSSHHost(hostName: string): Promise<void> {
-  const config = await readSSHConfig()
-  const newConfig = removeHost(config, hostName)
-  await writeSSHConfig(newConfig)
+  await ensureEditable(hostName)
+  // The backend keeps the removed block in the recently deleted list
+  await invoke('delete_ssh_host', { alias: hostName })
 }
 
 /**
@@ -168,6 +164,41 @@ export async function readPublicKey(keyName: string): Promise<string> {
   }
 }
 
+// Public key read from a QR code
+export interface ScannedPublicKey {
+  publicKey: string
+  keyType: SSHKeyInfo['type']
+  fingerprint: string
+  comment: string
+}
+
+/**
+ * QR code of a key's public key or fingerprint, as PNG or SVG bytes
+ */
+export async function renderKeyQrCode(
+  keyName: string,
+  content: 'publicKey' | 'fingerprint' = 'publicKey',
+  format: 'png' | 'svg' = 'png'
+): Promise<Blob> {
+  const bytes = await invoke<ArrayBuffer>('render_key_qr_code', {
+    keyName,
+    content,
+    format,
+  })
+  return new Blob([bytes], {
+    type: format === 'png' ? 'image/png' : 'image/svg+xml',
+  })
+}
+
+/**
+ * Read a public key from a QR code image (PNG, JPEG)
+ */
+export async function scanPublicKeyQr(
+  image: ArrayBuffer | Uint8Array
+): Promise<ScannedPublicKey> {
+  return invoke<ScannedPublicKey>('scan_public_key_qr', image)
+}
+
 /**
  * Delete an SSH key pair
  * Uses Rust backend with path traversal protection
@@ -182,6 +213,88 @@ export async function deleteSSHKey(keyName: string): Promise<void> {
   }
 }
 
+/**
+ * Rewrite the comment of a key pair (private key and .pub stay consistent)
+ * The passphrase is needed for encrypted keys
+ */
+export async function setKeyComment(
+  keyName: string,
+  comment: string,
+  passphrase?: string
+): Promise<SSHKeyInfo> {
+  try {
+    return await invoke<SSHKeyInfo>('set_key_comment', {
+      keyName,
+      comment,
+      passphrase: passphrase || null,
+    })
+  } catch (error) {
+    console.error('[ssh-service] Failed to set key comment:', error)
+    throw error
+  }
+}
+
+/**
+ * Key pair problem found by scanKeypairs
+ */
+export interface KeypairIssue {
+  kind: 'orphanedPublicKey' | 'missingPublicKey' | 'mismatch'
+  name: string
+  fingerprint?: string
+  matchingPrivateKey?: string
+  canRegenerate: boolean
+}
+
+/**
+ * Result of a key pair scan
+ */
+export interface KeypairScan {
+  pairs: number
+  unverified: string[] // Legacy PEM keys that can't be compared
+  issues: KeypairIssue[]
+}
+
+/**
+ * Pair .pub files with private keys by key material
+ */
+export async function scanKeypairs(): Promise<KeypairScan> {
+  return await invoke<KeypairScan>('scan_keypairs')
+}
+
+/**
+ * Rebuild a key's .pub from its private key
+ * Set overwrite to replace a .pub that holds a different key
+ */
+export async function regeneratePublicKey(
+  keyName: string,
+  overwrite = false
+): Promise<SSHKeyInfo> {
+  return await invoke<SSHKeyInfo>('regenerate_public_key', {
+    keyName,
+    overwrite,
+  })
+}
+
+/**
+ * Metadata of all keys, keyed by SHA256 fingerprint
+ */
+export async function listKeyMetadata(): Promise<Record<string, KeyMetadata>> {
+  return await invoke<Record<string, KeyMetadata>>('list_key_metadata')
+}
+
+/**
+ * Set the metadata of a key (returns null when all fields were cleared)
+ */
+export async function setKeyMetadata(
+  fingerprint: string,
+  metadata: Omit<KeyMetadata, 'updatedAt'>
+): Promise<KeyMetadata | null> {
+  return await invoke<KeyMetadata | null>('set_key_metadata', {
+    fingerprint,
+    metadata: { ...metadata, updatedAt: 0 },
+  })
+}
+
 /**
  * Generate options for SSH key generation
  */
@@ -254,6 +367,7 @@ export type SSHErrorType =
   | 'connection_refused'
   | 'timeout'
   | 'dns_failed'
+  | 'vpn_not_connected' // host's required VPN/network is down
   // Configuration issues
   | 'identity_file_not_found' // specified key doesn't exist
   | 'public_key_missing' // .pub file missing
@@ -289,6 +403,53 @@ export interface SSHConnectionTestResult {
   hostToAdd?: string // For host_key_unknown - the hostname to add to known_hosts
   identityFile?: string // The key file actually used for authentication
   debugLog?: string // Full verbose output for debugging
+  handshake?: SSHHandshakeInfo // Server banner and algorithms, also set when auth fails
+  suggestions?: ConfigSuggestion[] // Config patches that would likely fix a failed test
+}
+
+/**
+ * Config patch suggested by a failed connection test
+ */
+export interface ConfigSuggestion {
+  alias: string
+  kind: 'user' | 'port' | 'hostKeyAlias'
+  message: string
+  messageKey: string
+  messageParams: Record<string, string>
+  changes: {
+    action: 'set' | 'add' | 'remove'
+    key: string
+    value?: string | null
+  }[]
+  diff: string // Unified diff of the SSH config
+}
+
+export interface SSHKexAlgorithms {
+  kex: string[]
+  hostKey: string[]
+  ciphersClientToServer: string[]
+  ciphersServerToClient: string[]
+  macsClientToServer: string[]
+  macsServerToClient: string[]
+  compressionClientToServer: string[]
+  compressionServerToClient: string[]
+}
+
+export interface SSHNegotiatedAlgorithms {
+  kex: string | null
+  hostKey: string | null
+  cipherClientToServer: string | null
+  cipherServerToClient: string | null
+  macClientToServer: string | null // null for AEAD ciphers
+  macServerToClient: string | null
+  compression: string | null
+}
+
+export interface SSHHandshakeInfo {
+  serverBanner: string // e.g. "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13"
+  serverSoftware: string | null // e.g. "OpenSSH_9.6p1"
+  serverAlgorithms: SSHKexAlgorithms | null
+  negotiated: SSHNegotiatedAlgorithms | null
 }
 
 /**
@@ -347,6 +508,20 @@ export async function testSSHConnection(
   }
 }
 
+/**
+ * Apply a config patch suggested by a failed connection test
+ */
+export async function applyConfigSuggestion(
+  suggestion: ConfigSuggestion
+): Promise<void> {
+  console.log(
+    '[ssh-service] Applying config suggestion:',
+    suggestion.alias,
+    suggestion.kind
+  )
+  await invoke('apply_config_suggestion', { suggestion })
+}
+
 /**
  * Known host operation result
  */
@@ -374,6 +549,114 @@ export async function removeKnownHost(hostname: string): Promise<void> {
   }
 }
 
+/**
+ * Page parameters for known_hosts and config listings
+ * A search streams the whole file on the backend; only the page comes back
+ */
+export interface PageQuery {
+  offset?: number
+  limit?: number
+  search?: string
+}
+
+export interface Page<T> {
+  items: T[]
+  offset: number
+  total: number
+  matched: number
+}
+
+export interface KnownHostEntry {
+  line: number
+  marker: string | null
+  hosts: string[]
+  hashed: boolean
+  keyType: string
+  fingerprint: string | null
+  comment: string | null
+}
+
+export interface ConfigHostSummary {
+  line: number
+  patterns: string[]
+  isMatch: boolean
+  hostname: string | null
+  user: string | null
+  port: number | null
+  identityFile: string | null
+  optionCount: number
+}
+
+/**
+ * List known_hosts entries a page at a time
+ */
+export async function listKnownHosts(
+  query: PageQuery = {}
+): Promise<Page<KnownHostEntry>> {
+  return invoke<Page<KnownHostEntry>>('list_known_hosts', { query })
+}
+
+/**
+ * List the Host/Match blocks of the SSH config a page at a time
+ */
+export async function listConfigHosts(
+  query: PageQuery = {}
+): Promise<Page<ConfigHostSummary>> {
+  return invoke<Page<ConfigHostSummary>>('list_config_hosts', { query })
+}
+
+export type HostSort =
+  | 'alias'
+  | 'hostname'
+  | 'user'
+  | 'lastUsed'
+  | 'useCount'
+  | 'createdAt'
+  | 'pinned'
+
+/**
+ * Host list query; pass `nextCursor` of a page back as `cursor` to get the next one
+ */
+export interface HostQuery {
+  cursor?: string | null
+  limit?: number
+  sort?: HostSort
+  descending?: boolean
+  search?: string // Also matches notes and attachment names
+  tags?: string[]
+  favoritesOnly?: boolean
+}
+
+export interface HostListItem {
+  alias: string
+  line: number
+  hostname: string | null
+  user: string | null
+  port: number | null
+  tags: string[]
+  isFavorite: boolean
+  pinPosition: number | null
+  lastUsed: number | null
+  useCount: number
+  createdAt: number | null
+  readOnly: boolean
+  attachments: string[] // File names
+}
+
+export interface HostPage {
+  items: HostListItem[]
+  nextCursor: string | null
+  total: number
+  matched: number
+}
+
+/**
+ * Query hosts sorted and filtered on the backend, a page at a time
+ */
+export async function queryHosts(query: HostQuery = {}): Promise<HostPage> {
+  return invoke<HostPage>('query_hosts', { query })
+}
+
 /**
  * Add a host to known_hosts file
  * Uses Rust backend with ssh-keyscan
@@ -37,6 +37,16 @@ const DEPRECATED_KEY_EXCHANGE = [
   'diffie-hellman-group14-sha1',
 ]
 
+// Options OpenSSH accumulates across blocks instead of using the first value
+const CUMULATIVE_OPTIONS = [
+  'identityfile',
+  'certificatefile',
+  'localforward',
+  'remoteforward',
+  'dynamicforward',
+  'sendenv',
+]
+
 /**
  * Validate a single host configuration
  */
@@ -139,10 +149,13 @@ export function validateHost(
   // 3. IdentityFile path validation
   if (host.IdentityFile) {
     const path = host.IdentityFile
+    // Windows drive (C:\) and UNC (\\server) paths are absolute too
     if (
       !path.startsWith('/') &&
       !path.startsWith('~') &&
-      !path.startsWith('%')
+      !path.startsWith('%') &&
+      !/^[A-Za-z]:[\\/]/.test(path) &&
+      !path.startsWith('\\\\')
     ) {
       issues.push({
         severity: 'warning',
@@ -215,6 +228,9 @@ export function validateConfig(config: ParsedSSHConfig): ValidationResult {
     }
   }
 
+  // Wildcard blocks that override later, more specific blocks
+  allIssues.push(...checkWildcardShadowing(config))
+
   // Validate each host
   for (const host of config.hosts) {
     const result = validateHost(host, [], false)
@@ -235,6 +251,84 @@ export function validateConfig(config: ParsedSSHConfig): ValidationResult {
   }
 }
 
+/**
+ * Match a host against an SSH pattern list ("*", "?" and "!" negation)
+ */
+function matchesHostPatterns(patterns: string[], host: string): boolean {
+  let matched = false
+  for (const pattern of patterns) {
+    const negated = pattern.startsWith('!')
+    const glob = negated ? pattern.slice(1) : pattern
+    const regex = new RegExp(
+      '^' +
+        glob
+          .replace(/[.+^${}()|[\]\\]/g, '\\$&')
+          .replace(/\*/g, '.*')
+          .replace(/\?/g, '.') +
+        '$',
+      'i'
+    )
+    if (regex.test(host)) {
+      if (negated) return false
+      matched = true
+    }
+  }
+  return matched
+}
+
+/**
+ * Detect wildcard blocks that override options of later, more specific blocks.
+ * OpenSSH uses the first value it finds for each option, so a broad block
+ * (e.g. `Host *`) placed first silently wins over everything after it.
+ */
+export function checkWildcardShadowing(
+  config: ParsedSSHConfig
+): ValidationIssue[] {
+  const issues: ValidationIssue[] = []
+
+  config.hosts.forEach((earlier, index) => {
+    const earlierPatterns = earlier.Host.split(/\s+/)
+    if (!earlierPatterns.some((p) => p.includes('*') || p.includes('?'))) {
+      return
+    }
+
+    for (const later of config.hosts.slice(index + 1)) {
+      if (later.Host === earlier.Host) continue
+
+      const covered = later.Host.split(/\s+/).filter(
+        (p) => !p.startsWith('!') && matchesHostPatterns(earlierPatterns, p)
+      )
+      if (covered.length === 0) continue
+
+      const shadowed: string[] = []
+      for (const [key, value] of Object.entries(earlier)) {
+        if (key === 'Host' || value === undefined) continue
+        if (CUMULATIVE_OPTIONS.includes(key.toLowerCase())) continue
+
+        const laterKey = Object.keys(later).find(
+          (k) => k.toLowerCase() === key.toLowerCase()
+        )
+        if (!laterKey || later[laterKey] === undefined) continue
+        if (String(later[laterKey]) === String(value)) continue
+
+        shadowed.push(
+          `${key} (${String(value)} is used instead of ${String(later[laterKey])})`
+        )
+      }
+
+      if (shadowed.length > 0) {
+        issues.push({
+          severity: 'warning',
+          message: `Host ${earlier.Host} overrides ${shadowed.join(', ')} for ${covered.join(', ')}`,
+          hint: `OpenSSH uses the first value it finds, so these options in "Host ${later.Host}" are ignored. Move "Host ${earlier.Host}" below it (usually to the end of the file).`,
+        })
+      }
+    }
+  })
+
+  return issues
+}
+
 /**
  * Check if a field contains deprecated values
  */